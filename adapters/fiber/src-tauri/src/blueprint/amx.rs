@@ -0,0 +1,1309 @@
+/**
+ * AMX bytecode reader and disassembler
+ *
+ * Reads compiled Pawn scripts (`.amx`, Pawn 3.x file format) with 32- or
+ * 64-bit cells: header, publics/natives/libraries/pubvars/tags tables and the
+ * (optionally compact-encoded) code section. The code can be disassembled
+ * into a listing, split into basic blocks, and scanned for constant
+ * `CreateObject`/`CreateDynamicObject` calls.
+ */
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{PwnImportResult, PwnObjectData};
+
+#[derive(Debug, Error)]
+pub enum AmxError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid AMX file: {0}")]
+    InvalidData(String),
+    #[error("Unsupported AMX file: {0}")]
+    Unsupported(String),
+}
+
+const AMX_MAGIC_32: u16 = 0xF1E0;
+const AMX_MAGIC_64: u16 = 0xF1E1;
+const AMX_MAGIC_16: u16 = 0xF1E2;
+const AMX_HEADER_SIZE: usize = 56;
+const AMX_FLAG_COMPACT: u16 = 0x04;
+/// Legacy tables (file version < 7) store names inline, up to 19 characters
+const LEGACY_NAME_LENGTH: usize = 20;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AmxHeader {
+    pub size: i32,
+    pub magic: u16,
+    pub file_version: u8,
+    pub amx_version: u8,
+    pub flags: u16,
+    pub defsize: u16,
+    pub cod: i32,
+    pub dat: i32,
+    pub hea: i32,
+    pub stp: i32,
+    pub cip: i32,
+    pub publics: i32,
+    pub natives: i32,
+    pub libraries: i32,
+    pub pubvars: i32,
+    pub tags: i32,
+    pub nametable: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AmxSymbol {
+    pub name: String,
+    /// Code address for publics, data address for pubvars, id for tags
+    pub address: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AmxFile {
+    pub header: AmxHeader,
+    /// Cell size in bytes (4 or 8)
+    pub cell_size: usize,
+    pub publics: Vec<AmxSymbol>,
+    pub natives: Vec<AmxSymbol>,
+    pub libraries: Vec<AmxSymbol>,
+    pub pubvars: Vec<AmxSymbol>,
+    pub tags: Vec<AmxSymbol>,
+    /// Expanded code section
+    #[serde(skip)]
+    pub code: Vec<u8>,
+    /// Expanded data section
+    #[serde(skip)]
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OperandKind {
+    Fixed(u8),
+    /// `casetbl`: record count, default address, then value/address pairs
+    CaseTable,
+    /// `file`/`symbol`: first operand is the byte size of the remainder
+    Sized,
+}
+
+use OperandKind::{CaseTable, Fixed, Sized};
+
+/// Pawn 3.2 opcode table, indexed by opcode number. Opcodes added by later
+/// versions (and garbage cells) decode as `unknown opcode` without operands.
+const OPCODES: [(&str, OperandKind); 138] = [
+    ("none", Fixed(0)),
+    ("load.pri", Fixed(1)),
+    ("load.alt", Fixed(1)),
+    ("load.s.pri", Fixed(1)),
+    ("load.s.alt", Fixed(1)),
+    ("lref.pri", Fixed(1)),
+    ("lref.alt", Fixed(1)),
+    ("lref.s.pri", Fixed(1)),
+    ("lref.s.alt", Fixed(1)),
+    ("load.i", Fixed(0)),
+    ("lodb.i", Fixed(1)),
+    ("const.pri", Fixed(1)),
+    ("const.alt", Fixed(1)),
+    ("addr.pri", Fixed(1)),
+    ("addr.alt", Fixed(1)),
+    ("stor.pri", Fixed(1)),
+    ("stor.alt", Fixed(1)),
+    ("stor.s.pri", Fixed(1)),
+    ("stor.s.alt", Fixed(1)),
+    ("sref.pri", Fixed(1)),
+    ("sref.alt", Fixed(1)),
+    ("sref.s.pri", Fixed(1)),
+    ("sref.s.alt", Fixed(1)),
+    ("stor.i", Fixed(0)),
+    ("strb.i", Fixed(1)),
+    ("lidx", Fixed(0)),
+    ("lidx.b", Fixed(1)),
+    ("idxaddr", Fixed(0)),
+    ("idxaddr.b", Fixed(1)),
+    ("align.pri", Fixed(1)),
+    ("align.alt", Fixed(1)),
+    ("lctrl", Fixed(1)),
+    ("sctrl", Fixed(1)),
+    ("move.pri", Fixed(0)),
+    ("move.alt", Fixed(0)),
+    ("xchg", Fixed(0)),
+    ("push.pri", Fixed(0)),
+    ("push.alt", Fixed(0)),
+    ("push.r", Fixed(1)),
+    ("push.c", Fixed(1)),
+    ("push", Fixed(1)),
+    ("push.s", Fixed(1)),
+    ("pop.pri", Fixed(0)),
+    ("pop.alt", Fixed(0)),
+    ("stack", Fixed(1)),
+    ("heap", Fixed(1)),
+    ("proc", Fixed(0)),
+    ("ret", Fixed(0)),
+    ("retn", Fixed(0)),
+    ("call", Fixed(1)),
+    ("call.pri", Fixed(0)),
+    ("jump", Fixed(1)),
+    ("jrel", Fixed(1)),
+    ("jzer", Fixed(1)),
+    ("jnz", Fixed(1)),
+    ("jeq", Fixed(1)),
+    ("jneq", Fixed(1)),
+    ("jless", Fixed(1)),
+    ("jleq", Fixed(1)),
+    ("jgrtr", Fixed(1)),
+    ("jgeq", Fixed(1)),
+    ("jsless", Fixed(1)),
+    ("jsleq", Fixed(1)),
+    ("jsgrtr", Fixed(1)),
+    ("jsgeq", Fixed(1)),
+    ("shl", Fixed(0)),
+    ("shr", Fixed(0)),
+    ("sshr", Fixed(0)),
+    ("shl.c.pri", Fixed(1)),
+    ("shl.c.alt", Fixed(1)),
+    ("shr.c.pri", Fixed(1)),
+    ("shr.c.alt", Fixed(1)),
+    ("smul", Fixed(0)),
+    ("sdiv", Fixed(0)),
+    ("sdiv.alt", Fixed(0)),
+    ("umul", Fixed(0)),
+    ("udiv", Fixed(0)),
+    ("udiv.alt", Fixed(0)),
+    ("add", Fixed(0)),
+    ("sub", Fixed(0)),
+    ("sub.alt", Fixed(0)),
+    ("and", Fixed(0)),
+    ("or", Fixed(0)),
+    ("xor", Fixed(0)),
+    ("not", Fixed(0)),
+    ("neg", Fixed(0)),
+    ("invert", Fixed(0)),
+    ("add.c", Fixed(1)),
+    ("smul.c", Fixed(1)),
+    ("zero.pri", Fixed(0)),
+    ("zero.alt", Fixed(0)),
+    ("zero", Fixed(1)),
+    ("zero.s", Fixed(1)),
+    ("sign.pri", Fixed(0)),
+    ("sign.alt", Fixed(0)),
+    ("eq", Fixed(0)),
+    ("neq", Fixed(0)),
+    ("less", Fixed(0)),
+    ("leq", Fixed(0)),
+    ("grtr", Fixed(0)),
+    ("geq", Fixed(0)),
+    ("sless", Fixed(0)),
+    ("sleq", Fixed(0)),
+    ("sgrtr", Fixed(0)),
+    ("sgeq", Fixed(0)),
+    ("eq.c.pri", Fixed(1)),
+    ("eq.c.alt", Fixed(1)),
+    ("inc.pri", Fixed(0)),
+    ("inc.alt", Fixed(0)),
+    ("inc", Fixed(1)),
+    ("inc.s", Fixed(1)),
+    ("inc.i", Fixed(0)),
+    ("dec.pri", Fixed(0)),
+    ("dec.alt", Fixed(0)),
+    ("dec", Fixed(1)),
+    ("dec.s", Fixed(1)),
+    ("dec.i", Fixed(0)),
+    ("movs", Fixed(1)),
+    ("cmps", Fixed(1)),
+    ("fill", Fixed(1)),
+    ("halt", Fixed(1)),
+    ("bounds", Fixed(1)),
+    ("sysreq.pri", Fixed(0)),
+    ("sysreq.c", Fixed(1)),
+    ("file", Sized),
+    ("line", Fixed(2)),
+    ("symbol", Sized),
+    ("srange", Fixed(2)),
+    ("jump.pri", Fixed(0)),
+    ("switch", Fixed(1)),
+    ("casetbl", CaseTable),
+    ("swap.pri", Fixed(0)),
+    ("swap.alt", Fixed(0)),
+    ("push.adr", Fixed(1)),
+    ("nop", Fixed(0)),
+    ("sysreq.n", Fixed(2)),
+    ("symtag", Fixed(1)),
+    ("break", Fixed(0)),
+];
+
+const UNKNOWN_OPCODE: &str = "unknown opcode";
+
+// Opcode numbers the analysis cares about
+const OP_CONST_PRI: u32 = 11;
+const OP_CONST_ALT: u32 = 12;
+const OP_MOVE_PRI: u32 = 33;
+const OP_MOVE_ALT: u32 = 34;
+const OP_PUSH_PRI: u32 = 36;
+const OP_PUSH_ALT: u32 = 37;
+const OP_PUSH_R: u32 = 38;
+const OP_PUSH_C: u32 = 39;
+const OP_PUSH: u32 = 40;
+const OP_PUSH_S: u32 = 41;
+const OP_POP_PRI: u32 = 42;
+const OP_POP_ALT: u32 = 43;
+const OP_STACK: u32 = 44;
+const OP_PROC: u32 = 46;
+const OP_RET: u32 = 47;
+const OP_RETN: u32 = 48;
+const OP_CALL: u32 = 49;
+const OP_JUMP: u32 = 51;
+const OP_JREL: u32 = 52;
+const OP_JZER: u32 = 53;
+const OP_JSGEQ: u32 = 64;
+const OP_ZERO_PRI: u32 = 89;
+const OP_ZERO_ALT: u32 = 90;
+const OP_HALT: u32 = 120;
+const OP_SYSREQ_C: u32 = 123;
+const OP_JUMP_PRI: u32 = 128;
+const OP_SWITCH: u32 = 129;
+const OP_CASETBL: u32 = 130;
+const OP_PUSH_ADR: u32 = 133;
+const OP_NOP: u32 = 134;
+const OP_SYSREQ_N: u32 = 135;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AmxInstruction {
+    /// Offset from the start of the code section
+    pub address: u64,
+    pub opcode: u32,
+    pub mnemonic: String,
+    pub operands: Vec<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AmxBasicBlock {
+    pub start: u64,
+    /// Address just past the last instruction
+    pub end: u64,
+    /// Index range into the instruction list
+    pub first_instruction: usize,
+    pub instruction_count: usize,
+    pub successors: Vec<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AmxFunctionCall {
+    pub native: String,
+    pub address: u64,
+    /// Arguments in declaration order; `None` when not a compile-time constant
+    pub arguments: Vec<Option<i64>>,
+}
+
+impl AmxFile {
+    pub fn load_from_path<P: AsRef<Path>>(path: P) -> Result<Self, AmxError> {
+        let bytes = fs::read(path)?;
+        Self::from_bytes(&bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, AmxError> {
+        let header = read_header(bytes)?;
+
+        let cell_size = match header.magic {
+            AMX_MAGIC_32 => 4,
+            AMX_MAGIC_64 => 8,
+            AMX_MAGIC_16 => {
+                return Err(AmxError::Unsupported("16-bit cells".to_string()));
+            }
+            magic => {
+                return Err(AmxError::InvalidData(format!("bad magic 0x{:04X}", magic)));
+            }
+        };
+
+        if header.cod < 0 || header.dat < header.cod || header.hea < header.dat {
+            return Err(AmxError::InvalidData(format!(
+                "inconsistent section offsets (cod {}, dat {}, hea {})",
+                header.cod, header.dat, header.hea
+            )));
+        }
+
+        let cod = header.cod as usize;
+        let image_end = (header.size as usize).min(bytes.len());
+        if image_end < cod {
+            return Err(AmxError::InvalidData(
+                "file truncated before code".to_string(),
+            ));
+        }
+
+        // Compact encoding shrinks everything from COD up to the heap start
+        let memory = if header.flags & AMX_FLAG_COMPACT != 0 {
+            expand_compact(
+                &bytes[cod..image_end],
+                cell_size,
+                (header.hea - header.cod) as usize,
+            )?
+        } else {
+            let hea = (header.hea as usize).min(bytes.len());
+            bytes[cod..hea].to_vec()
+        };
+
+        let code_size = (header.dat - header.cod) as usize;
+        if memory.len() < code_size {
+            return Err(AmxError::InvalidData("code section truncated".to_string()));
+        }
+        let code = memory[..code_size].to_vec();
+        let data = memory[code_size..].to_vec();
+
+        let table_end = if header.file_version >= 7 {
+            header.nametable
+        } else {
+            header.cod
+        };
+
+        let publics = read_table(bytes, &header, cell_size, header.publics, header.natives)?;
+        let natives = read_table(bytes, &header, cell_size, header.natives, header.libraries)?;
+        let libraries = read_table(bytes, &header, cell_size, header.libraries, header.pubvars)?;
+        let pubvars = read_table(bytes, &header, cell_size, header.pubvars, header.tags)?;
+        let tags = read_table(bytes, &header, cell_size, header.tags, table_end)?;
+
+        Ok(Self {
+            header,
+            cell_size,
+            publics,
+            natives,
+            libraries,
+            pubvars,
+            tags,
+            code,
+            data,
+        })
+    }
+
+    fn read_cell(&self, offset: usize) -> Option<i64> {
+        let bytes = self.code.get(offset..offset + self.cell_size)?;
+        Some(if self.cell_size == 8 {
+            i64::from_le_bytes(bytes.try_into().ok()?)
+        } else {
+            i32::from_le_bytes(bytes.try_into().ok()?) as i64
+        })
+    }
+
+    /// Decode the whole code section into instructions.
+    pub fn disassemble(&self) -> Result<Vec<AmxInstruction>, AmxError> {
+        let cell = self.cell_size;
+        let mut instructions = Vec::new();
+        let mut offset = 0usize;
+
+        while offset + cell <= self.code.len() {
+            let address = offset as u64;
+            let raw = self.read_cell(offset).unwrap_or(0);
+            let opcode = raw as u32;
+            offset += cell;
+
+            // The operand count of an unknown opcode is not known either, so decoding
+            // resumes at the next cell
+            let Some((mnemonic, kind)) = OPCODES.get(opcode as usize).copied() else {
+                instructions.push(AmxInstruction {
+                    address,
+                    opcode,
+                    mnemonic: UNKNOWN_OPCODE.to_string(),
+                    operands: vec![raw],
+                });
+                continue;
+            };
+
+            // Counts come from the file, so they are checked against what is left of the code
+            let count_cell = || {
+                usize::try_from(self.read_cell(offset).unwrap_or(0).max(0)).unwrap_or(usize::MAX)
+            };
+            let remaining_cells = (self.code.len() - offset) / cell;
+            let operand_count = match kind {
+                Fixed(n) => Some(n as usize),
+                CaseTable => count_cell().checked_mul(2).and_then(|n| n.checked_add(2)),
+                Sized => count_cell().div_ceil(cell).checked_add(1),
+            }
+            .filter(|count| *count <= remaining_cells)
+            .ok_or_else(|| {
+                AmxError::InvalidData(format!(
+                    "truncated operands for {} at 0x{:08X}",
+                    mnemonic, address
+                ))
+            })?;
+
+            let mut operands = Vec::with_capacity(operand_count);
+            for _ in 0..operand_count {
+                let value = self.read_cell(offset).ok_or_else(|| {
+                    AmxError::InvalidData(format!(
+                        "truncated operands for {} at 0x{:08X}",
+                        mnemonic, address
+                    ))
+                })?;
+                operands.push(value);
+                offset += cell;
+            }
+
+            instructions.push(AmxInstruction {
+                address,
+                opcode,
+                mnemonic: mnemonic.to_string(),
+                operands,
+            });
+        }
+
+        Ok(instructions)
+    }
+
+    /// Split instructions into basic blocks with their successor addresses.
+    pub fn basic_blocks(&self, instructions: &[AmxInstruction]) -> Vec<AmxBasicBlock> {
+        let cell = self.cell_size as i64;
+        let index_of: HashMap<u64, usize> = instructions
+            .iter()
+            .enumerate()
+            .map(|(i, ins)| (ins.address, i))
+            .collect();
+
+        let mut leaders: BTreeSet<u64> = BTreeSet::new();
+        if let Some(first) = instructions.first() {
+            leaders.insert(first.address);
+        }
+        for public in &self.publics {
+            leaders.insert(public.address);
+        }
+        if self.header.cip >= 0 {
+            leaders.insert(self.header.cip as u64);
+        }
+
+        for (i, ins) in instructions.iter().enumerate() {
+            if ins.opcode == OP_PROC {
+                leaders.insert(ins.address);
+            }
+            let targets = self.branch_targets(ins, &index_of, instructions);
+            if !targets.is_empty() || ends_block(ins.opcode) {
+                leaders.extend(targets);
+                if let Some(next) = instructions.get(i + 1) {
+                    leaders.insert(next.address);
+                }
+            }
+        }
+
+        let mut blocks: Vec<AmxBasicBlock> = Vec::new();
+        let mut current: Option<AmxBasicBlock> = None;
+
+        for (i, ins) in instructions.iter().enumerate() {
+            // Case tables are data embedded in the code stream
+            if ins.opcode == OP_CASETBL {
+                if let Some(block) = current.take() {
+                    blocks.push(block);
+                }
+                continue;
+            }
+
+            if leaders.contains(&ins.address) {
+                if let Some(mut block) = current.take() {
+                    if !ends_block(instructions[i - 1].opcode) {
+                        block.successors.push(ins.address);
+                    }
+                    blocks.push(block);
+                }
+            }
+
+            let block = current.get_or_insert_with(|| AmxBasicBlock {
+                start: ins.address,
+                end: ins.address,
+                first_instruction: i,
+                instruction_count: 0,
+                successors: Vec::new(),
+            });
+            block.instruction_count += 1;
+            block.end = ins.address + (1 + ins.operands.len() as u64) * cell as u64;
+
+            let targets = self.branch_targets(ins, &index_of, instructions);
+            block.successors.extend(targets);
+            if is_conditional_jump(ins.opcode) {
+                if let Some(next) = instructions.get(i + 1) {
+                    block.successors.push(next.address);
+                }
+            }
+        }
+        if let Some(block) = current.take() {
+            blocks.push(block);
+        }
+
+        for block in &mut blocks {
+            block.successors.sort_unstable();
+            block.successors.dedup();
+        }
+
+        blocks
+    }
+
+    fn branch_targets(
+        &self,
+        ins: &AmxInstruction,
+        index_of: &HashMap<u64, usize>,
+        instructions: &[AmxInstruction],
+    ) -> Vec<u64> {
+        let cell = self.cell_size as i64;
+        match ins.opcode {
+            OP_JUMP | OP_JZER..=OP_JSGEQ => vec![ins.operands[0] as u64],
+            OP_JREL => vec![(ins.address as i64 + 2 * cell + ins.operands[0]) as u64],
+            OP_SWITCH => {
+                let table = ins.operands[0] as u64;
+                match index_of.get(&table).map(|&i| &instructions[i]) {
+                    Some(casetbl) if casetbl.opcode == OP_CASETBL => {
+                        // operands: count, default, then (value, address) pairs
+                        let mut targets = vec![casetbl.operands[1] as u64];
+                        targets.extend(casetbl.operands[2..].chunks(2).map(|pair| pair[1] as u64));
+                        targets
+                    }
+                    _ => Vec::new(),
+                }
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Human readable listing with public/native names and block labels.
+    pub fn listing(&self) -> Result<String, AmxError> {
+        let instructions = self.disassemble()?;
+        let blocks = self.basic_blocks(&instructions);
+        let block_starts: BTreeSet<u64> = blocks.iter().map(|b| b.start).collect();
+        let publics: HashMap<u64, &str> = self
+            .publics
+            .iter()
+            .map(|p| (p.address, p.name.as_str()))
+            .collect();
+
+        let mut output = String::new();
+        let _ = writeln!(
+            output,
+            "; AMX file version {}, {}-bit cells, {} publics, {} natives",
+            self.header.file_version,
+            self.cell_size * 8,
+            self.publics.len(),
+            self.natives.len()
+        );
+
+        for ins in &instructions {
+            if let Some(name) = publics.get(&ins.address) {
+                let _ = writeln!(output, "\n{}:", name);
+            } else if self.header.cip >= 0 && ins.address == self.header.cip as u64 {
+                let _ = writeln!(output, "\nmain:");
+            }
+            if block_starts.contains(&ins.address) {
+                let _ = writeln!(output, "l_{:08X}:", ins.address);
+            }
+
+            let operands: Vec<String> = ins
+                .operands
+                .iter()
+                .map(|operand| format!("{:#x}", operand))
+                .collect();
+            let _ = write!(
+                output,
+                "    {:08X}  {:<12} {}",
+                ins.address,
+                ins.mnemonic,
+                operands.join(", ")
+            );
+            if let Some(native) = self.native_for(ins) {
+                let _ = write!(output, "  ; {}", native);
+            }
+            output.push('\n');
+        }
+
+        Ok(output)
+    }
+
+    fn native_for(&self, ins: &AmxInstruction) -> Option<&str> {
+        match ins.opcode {
+            OP_SYSREQ_C | OP_SYSREQ_N => self
+                .natives
+                .get(ins.operands[0] as usize)
+                .map(|n| n.name.as_str()),
+            _ => None,
+        }
+    }
+
+    /// Collect calls to the given natives, resolving constant arguments by
+    /// tracking `push.c`/`const.pri` sequences inside each basic block.
+    pub fn find_native_calls(
+        &self,
+        instructions: &[AmxInstruction],
+        natives: &[&str],
+    ) -> Vec<AmxFunctionCall> {
+        let cell = self.cell_size as i64;
+        let blocks = self.basic_blocks(instructions);
+        let mut calls = Vec::new();
+
+        for block in &blocks {
+            let mut stack: Vec<Option<i64>> = Vec::new();
+            let mut pri: Option<i64> = None;
+            let mut alt: Option<i64> = None;
+
+            let range = block.first_instruction..block.first_instruction + block.instruction_count;
+            for ins in &instructions[range] {
+                let operand = ins.operands.first().copied();
+                match ins.opcode {
+                    OP_CONST_PRI => pri = operand,
+                    OP_CONST_ALT => alt = operand,
+                    OP_ZERO_PRI => pri = Some(0),
+                    OP_ZERO_ALT => alt = Some(0),
+                    OP_MOVE_PRI => pri = alt,
+                    OP_MOVE_ALT => alt = pri,
+                    OP_PUSH_PRI => stack.push(pri),
+                    OP_PUSH_ALT => stack.push(alt),
+                    OP_PUSH_C => stack.push(operand),
+                    OP_PUSH | OP_PUSH_S | OP_PUSH_ADR => stack.push(None),
+                    OP_PUSH_R => {
+                        for _ in 0..operand.unwrap_or(0).max(0) {
+                            stack.push(None);
+                        }
+                    }
+                    OP_POP_PRI => pri = stack.pop().flatten(),
+                    OP_POP_ALT => alt = stack.pop().flatten(),
+                    OP_STACK => {
+                        let cells = operand.unwrap_or(0) / cell;
+                        if cells > 0 {
+                            let keep = stack.len().saturating_sub(cells as usize);
+                            stack.truncate(keep);
+                        }
+                        alt = None;
+                    }
+                    OP_SYSREQ_C | OP_SYSREQ_N => {
+                        // sysreq.c takes its byte count from the stack, sysreq.n inline
+                        let (bytes, inline) = if ins.opcode == OP_SYSREQ_N {
+                            (ins.operands.get(1).copied(), true)
+                        } else {
+                            (stack.last().copied().flatten(), false)
+                        };
+                        let argc = (bytes.unwrap_or(0) / cell).max(0) as usize;
+                        let top = if inline {
+                            stack.len()
+                        } else {
+                            stack.len().saturating_sub(1)
+                        };
+                        let base = top.saturating_sub(argc);
+
+                        if let Some(name) = self.native_for(ins).filter(|n| natives.contains(n)) {
+                            // Arguments are pushed last-to-first
+                            let mut arguments: Vec<Option<i64>> =
+                                stack[base..top].iter().rev().copied().collect();
+                            arguments.resize(argc, None);
+                            calls.push(AmxFunctionCall {
+                                native: name.to_string(),
+                                address: ins.address,
+                                arguments,
+                            });
+                        }
+
+                        // sysreq.n cleans up itself; sysreq.c is followed by `stack`
+                        if inline {
+                            stack.truncate(base);
+                        }
+                        pri = None;
+                    }
+                    OP_CALL => {
+                        // Callees pop their own arguments plus the byte count
+                        let bytes = stack.pop().flatten().unwrap_or(0);
+                        let argc = (bytes / cell).max(0) as usize;
+                        let keep = stack.len().saturating_sub(argc);
+                        stack.truncate(keep);
+                        pri = None;
+                        alt = None;
+                    }
+                    OP_NOP => {}
+                    _ => {
+                        // Anything else may change the registers
+                        pri = None;
+                        alt = None;
+                    }
+                }
+            }
+        }
+
+        calls
+    }
+
+    fn cell_to_float(&self, value: i64) -> f32 {
+        if self.cell_size == 8 {
+            f64::from_bits(value as u64) as f32
+        } else {
+            f32::from_bits(value as u32)
+        }
+    }
+
+    /// Extract constant object placements in the same shape as `.pwn` imports.
+    pub fn extract_objects(&self) -> Result<PwnImportResult, AmxError> {
+        let instructions = self.disassemble()?;
+        let calls = self.find_native_calls(&instructions, &["CreateObject", "CreateDynamicObject"]);
+
+        let mut objects = Vec::new();
+        let mut errors = Vec::new();
+
+        for call in &calls {
+            let args = &call.arguments;
+            let missing: Vec<usize> = args
+                .iter()
+                .take(7)
+                .enumerate()
+                .filter(|(_, a)| a.is_none())
+                .map(|(i, _)| i)
+                .collect();
+            if args.len() < 7 || !missing.is_empty() {
+                errors.push(format!(
+                    "{} at 0x{:08X}: non-constant argument(s) {:?}",
+                    call.native, call.address, missing
+                ));
+                continue;
+            }
+
+            let int = |i: usize| args.get(i).copied().flatten().map(|v| v as i32);
+            let float = |i: usize| {
+                args.get(i)
+                    .copied()
+                    .flatten()
+                    .map(|v| self.cell_to_float(v))
+            };
+
+            let mut object = PwnObjectData {
+                modelid: int(0).unwrap_or(0) as u32,
+                x: float(1).unwrap_or(0.0),
+                y: float(2).unwrap_or(0.0),
+                z: float(3).unwrap_or(0.0),
+                rx: float(4).unwrap_or(0.0),
+                ry: float(5).unwrap_or(0.0),
+                rz: float(6).unwrap_or(0.0),
+                worldid: None,
+                interiorid: None,
+                playerid: None,
+                streamdistance: None,
+                drawdistance: None,
+                areaid: None,
+                priority: None,
+                invulnerable: None,
+                health: None,
+            };
+
+            if call.native == "CreateObject" {
+                // CreateObject(modelid, x, y, z, rX, rY, rZ, Float:DrawDistance)
+                object.drawdistance = float(7);
+            } else {
+                object.worldid = int(7);
+                object.interiorid = int(8);
+                object.playerid = int(9);
+                object.streamdistance = float(10);
+                object.drawdistance = float(11);
+                object.areaid = int(12);
+                object.priority = int(13).map(|p| p.max(0) as u32);
+            }
+
+            objects.push(object);
+        }
+
+        Ok(PwnImportResult {
+            parsed_count: objects.len(),
+            objects,
+            // No source lines in bytecode; report the instruction count instead
+            line_count: instructions.len(),
+            errors,
+        })
+    }
+}
+
+fn ends_block(opcode: u32) -> bool {
+    matches!(
+        opcode,
+        OP_RET | OP_RETN | OP_JUMP | OP_JREL | OP_HALT | OP_JUMP_PRI | OP_SWITCH
+    ) || is_conditional_jump(opcode)
+}
+
+fn is_conditional_jump(opcode: u32) -> bool {
+    (OP_JZER..=OP_JSGEQ).contains(&opcode)
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> Result<u16, AmxError> {
+    bytes
+        .get(offset..offset + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .ok_or_else(|| AmxError::InvalidData(format!("unexpected end of file at {}", offset)))
+}
+
+fn read_i32(bytes: &[u8], offset: usize) -> Result<i32, AmxError> {
+    bytes
+        .get(offset..offset + 4)
+        .map(|b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| AmxError::InvalidData(format!("unexpected end of file at {}", offset)))
+}
+
+fn read_header(bytes: &[u8]) -> Result<AmxHeader, AmxError> {
+    if bytes.len() < AMX_HEADER_SIZE {
+        return Err(AmxError::InvalidData(format!(
+            "file is {} bytes, smaller than the header",
+            bytes.len()
+        )));
+    }
+
+    Ok(AmxHeader {
+        size: read_i32(bytes, 0)?,
+        magic: read_u16(bytes, 4)?,
+        file_version: bytes[6],
+        amx_version: bytes[7],
+        flags: read_u16(bytes, 8)?,
+        defsize: read_u16(bytes, 10)?,
+        cod: read_i32(bytes, 12)?,
+        dat: read_i32(bytes, 16)?,
+        hea: read_i32(bytes, 20)?,
+        stp: read_i32(bytes, 24)?,
+        cip: read_i32(bytes, 28)?,
+        publics: read_i32(bytes, 32)?,
+        natives: read_i32(bytes, 36)?,
+        libraries: read_i32(bytes, 40)?,
+        pubvars: read_i32(bytes, 44)?,
+        tags: read_i32(bytes, 48)?,
+        nametable: read_i32(bytes, 52)?,
+    })
+}
+
+fn read_c_string(bytes: &[u8], offset: usize, max: usize) -> String {
+    let slice = bytes.get(offset..).unwrap_or(&[]);
+    let slice = &slice[..slice.len().min(max)];
+    let end = slice.iter().position(|&b| b == 0).unwrap_or(slice.len());
+    String::from_utf8_lossy(&slice[..end]).into_owned()
+}
+
+fn read_table(
+    bytes: &[u8],
+    header: &AmxHeader,
+    cell_size: usize,
+    start: i32,
+    end: i32,
+) -> Result<Vec<AmxSymbol>, AmxError> {
+    let defsize = header.defsize as usize;
+    if defsize == 0 || end <= start {
+        return Ok(Vec::new());
+    }
+
+    // Version 7+ records are {ucell address; uint32 nameofs}, older ones
+    // embed the name right after the address
+    let uses_nametable = defsize == cell_size + 4;
+    if defsize < cell_size {
+        return Err(AmxError::InvalidData(format!(
+            "table records of {} bytes cannot hold an address",
+            defsize
+        )));
+    }
+
+    let start = usize::try_from(start)
+        .map_err(|_| AmxError::InvalidData(format!("table starts at negative offset {}", start)))?;
+    let end = usize::try_from(end).unwrap_or(0);
+    if end > bytes.len() {
+        return Err(AmxError::InvalidData(format!(
+            "table ends at {}, past the end of the file",
+            end
+        )));
+    }
+
+    let count = (end - start) / defsize;
+    let mut symbols = Vec::with_capacity(count);
+
+    for i in 0..count {
+        let record = i
+            .checked_mul(defsize)
+            .and_then(|offset| offset.checked_add(start))
+            .ok_or_else(|| AmxError::InvalidData(format!("table record {} out of bounds", i)))?;
+        let record_bytes = bytes.get(record..record + defsize).ok_or_else(|| {
+            AmxError::InvalidData(format!("table record at {} out of bounds", record))
+        })?;
+        let address = if cell_size == 8 {
+            u64::from_le_bytes(record_bytes[..8].try_into().unwrap_or_default())
+        } else {
+            u32::from_le_bytes(record_bytes[..4].try_into().unwrap_or_default()) as u64
+        };
+
+        let name = if uses_nametable {
+            let name_offset = read_i32(bytes, record + cell_size)? as usize;
+            read_c_string(bytes, name_offset, 256)
+        } else {
+            read_c_string(
+                bytes,
+                record + cell_size,
+                LEGACY_NAME_LENGTH.min(defsize - cell_size),
+            )
+        };
+
+        symbols.push(AmxSymbol { name, address });
+    }
+
+    Ok(symbols)
+}
+
+/// Undo the compact encoding: each cell is stored as big-endian groups of 7
+/// bits, with the high bit marking continuation and bit 6 of the first byte
+/// giving the sign.
+fn expand_compact(
+    compact: &[u8],
+    cell_size: usize,
+    memory_size: usize,
+) -> Result<Vec<u8>, AmxError> {
+    // Every stored cell takes at least one byte, so the stream bounds the decoded size
+    let stored_size = compact
+        .len()
+        .checked_mul(cell_size)
+        .ok_or_else(|| AmxError::InvalidData("compact section too large".to_string()))?;
+    let mut memory = Vec::with_capacity(memory_size.min(stored_size));
+    let mut i = 0;
+
+    while i < compact.len() && memory.len() < memory_size {
+        let first = compact[i];
+        let mut value: i64 = if first & 0x40 != 0 { -1 } else { 0 };
+        loop {
+            let byte = *compact.get(i).ok_or_else(|| {
+                AmxError::InvalidData("compact encoding ends mid-cell".to_string())
+            })?;
+            value = (value << 7) | (byte & 0x7F) as i64;
+            i += 1;
+            if byte & 0x80 == 0 {
+                break;
+            }
+        }
+
+        if cell_size == 8 {
+            memory.extend_from_slice(&value.to_le_bytes());
+        } else {
+            memory.extend_from_slice(&(value as i32).to_le_bytes());
+        }
+    }
+
+    // The uninitialised tail of the data section is not stored
+    memory.resize(memory_size, 0);
+    Ok(memory)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Minimal assembler producing a version 8 AMX image for the tests.
+    struct TestImage {
+        cell_size: usize,
+        publics: Vec<(&'static str, u64)>,
+        natives: Vec<&'static str>,
+        code: Vec<i64>,
+        compact: bool,
+    }
+
+    impl TestImage {
+        fn cell_bytes(&self, value: i64) -> Vec<u8> {
+            if self.cell_size == 8 {
+                value.to_le_bytes().to_vec()
+            } else {
+                (value as i32).to_le_bytes().to_vec()
+            }
+        }
+
+        fn build(&self) -> Vec<u8> {
+            let defsize = self.cell_size + 4;
+            let publics_offset = AMX_HEADER_SIZE;
+            let natives_offset = publics_offset + self.publics.len() * defsize;
+            let tables_end = natives_offset + self.natives.len() * defsize;
+            let nametable = tables_end;
+
+            let mut names = vec![31u8, 0]; // uint16 maximum name length
+            let mut name_offsets = Vec::new();
+            for name in self
+                .publics
+                .iter()
+                .map(|p| p.0)
+                .chain(self.natives.iter().copied())
+            {
+                name_offsets.push(nametable + names.len());
+                names.extend_from_slice(name.as_bytes());
+                names.push(0);
+            }
+
+            let cod = nametable + names.len();
+            let mut code: Vec<u8> = Vec::new();
+            if self.compact {
+                for &value in &self.code {
+                    code.extend(encode_compact(value));
+                }
+            } else {
+                for &value in &self.code {
+                    code.extend(self.cell_bytes(value));
+                }
+            }
+            let code_size = self.code.len() * self.cell_size;
+            let dat = cod + code_size;
+            let hea = dat;
+
+            let mut bytes = Vec::new();
+            let size = cod + code.len();
+            bytes.extend((size as i32).to_le_bytes());
+            let magic = if self.cell_size == 8 {
+                AMX_MAGIC_64
+            } else {
+                AMX_MAGIC_32
+            };
+            bytes.extend(magic.to_le_bytes());
+            bytes.push(8);
+            bytes.push(8);
+            let flags: u16 = if self.compact { AMX_FLAG_COMPACT } else { 0 };
+            bytes.extend(flags.to_le_bytes());
+            bytes.extend((defsize as u16).to_le_bytes());
+            for value in [
+                cod as i32,
+                dat as i32,
+                hea as i32,
+                (hea + 1024) as i32,
+                -1,
+                publics_offset as i32,
+                natives_offset as i32,
+                tables_end as i32,
+                tables_end as i32,
+                tables_end as i32,
+                nametable as i32,
+            ] {
+                bytes.extend(value.to_le_bytes());
+            }
+
+            let mut name_iter = name_offsets.into_iter();
+            for (_, address) in &self.publics {
+                bytes.extend(self.cell_bytes(*address as i64));
+                bytes.extend((name_iter.next().unwrap() as i32).to_le_bytes());
+            }
+            for _ in &self.natives {
+                bytes.extend(self.cell_bytes(0));
+                bytes.extend((name_iter.next().unwrap() as i32).to_le_bytes());
+            }
+            bytes.extend(names);
+            bytes.extend(code);
+            bytes
+        }
+    }
+
+    fn encode_compact(value: i64) -> Vec<u8> {
+        let mut groups = Vec::new();
+        let mut v = value;
+        loop {
+            groups.push((v & 0x7F) as u8);
+            v >>= 7;
+            // Stop once the remaining bits are pure sign extension
+            let sign_bit = groups.last().unwrap() & 0x40 != 0;
+            if (v == 0 && !sign_bit) || (v == -1 && sign_bit) {
+                break;
+            }
+        }
+        groups.reverse();
+        let last = groups.len() - 1;
+        for group in &mut groups[..last] {
+            *group |= 0x80;
+        }
+        groups
+    }
+
+    fn float_cell(value: f32, cell_size: usize) -> i64 {
+        if cell_size == 8 {
+            (value as f64).to_bits() as i64
+        } else {
+            value.to_bits() as i32 as i64
+        }
+    }
+
+    /// `public OnGameModeInit() { CreateObject(...); CreateDynamicObject(...); return 1; }`
+    fn object_script(cell_size: usize, compact: bool) -> TestImage {
+        let c = cell_size as i64;
+        let f = |v: f32| float_cell(v, cell_size);
+        let mut code = vec![OP_PROC as i64];
+
+        // CreateObject(1337, 1.5, -2.0, 3.25, 0.0, 0.0, 90.0, 300.0) via sysreq.c
+        for value in [
+            f(300.0),
+            f(90.0),
+            f(0.0),
+            f(0.0),
+            f(3.25),
+            f(-2.0),
+            f(1.5),
+            1337,
+        ] {
+            code.extend([OP_PUSH_C as i64, value]);
+        }
+        code.extend([
+            OP_PUSH_C as i64,
+            8 * c,
+            OP_SYSREQ_C as i64,
+            0,
+            OP_STACK as i64,
+            9 * c,
+        ]);
+
+        // CreateDynamicObject(19458, 10.0, 20.0, 30.0, 0.0, 90.0, 0.0, 5, 2) via sysreq.n,
+        // with the world id routed through const.pri/push.pri
+        code.extend([OP_PUSH_C as i64, 2]);
+        code.extend([OP_CONST_PRI as i64, 5, OP_PUSH_PRI as i64]);
+        for value in [f(0.0), f(90.0), f(0.0), f(30.0), f(20.0), f(10.0), 19458] {
+            code.extend([OP_PUSH_C as i64, value]);
+        }
+        code.extend([OP_SYSREQ_N as i64, 1, 9 * c]);
+
+        // CreateObject with a runtime model id (push.s) is reported as an error
+        for value in [f(0.0), f(0.0), f(0.0), f(0.0), f(0.0), f(0.0)] {
+            code.extend([OP_PUSH_C as i64, value]);
+        }
+        code.extend([OP_PUSH_S as i64, 12]);
+        code.extend([
+            OP_PUSH_C as i64,
+            7 * c,
+            OP_SYSREQ_C as i64,
+            0,
+            OP_STACK as i64,
+            8 * c,
+        ]);
+
+        code.extend([OP_CONST_PRI as i64, 1, OP_RETN as i64]);
+
+        TestImage {
+            cell_size,
+            publics: vec![("OnGameModeInit", 0)],
+            natives: vec!["CreateObject", "CreateDynamicObject"],
+            code,
+            compact,
+        }
+    }
+
+    #[test]
+    fn test_reads_tables() {
+        let amx = AmxFile::from_bytes(&object_script(4, false).build()).unwrap();
+        assert_eq!(amx.cell_size, 4);
+        assert_eq!(amx.publics.len(), 1);
+        assert_eq!(amx.publics[0].name, "OnGameModeInit");
+        let natives: Vec<&str> = amx.natives.iter().map(|n| n.name.as_str()).collect();
+        assert_eq!(natives, vec!["CreateObject", "CreateDynamicObject"]);
+    }
+
+    #[test]
+    fn test_extracts_constant_objects() {
+        for (cell_size, compact) in [(4, false), (4, true), (8, false), (8, true)] {
+            let amx = AmxFile::from_bytes(&object_script(cell_size, compact).build()).unwrap();
+            let result = amx.extract_objects().unwrap();
+
+            assert_eq!(
+                result.parsed_count, 2,
+                "cell {} compact {}",
+                cell_size, compact
+            );
+            assert_eq!(result.errors.len(), 1);
+
+            let object = &result.objects[0];
+            assert_eq!(object.modelid, 1337);
+            assert_eq!((object.x, object.y, object.z), (1.5, -2.0, 3.25));
+            assert_eq!(object.rz, 90.0);
+            assert_eq!(object.drawdistance, Some(300.0));
+
+            let dynamic = &result.objects[1];
+            assert_eq!(dynamic.modelid, 19458);
+            assert_eq!((dynamic.x, dynamic.y, dynamic.z), (10.0, 20.0, 30.0));
+            assert_eq!(dynamic.ry, 90.0);
+            assert_eq!(dynamic.worldid, Some(5));
+            assert_eq!(dynamic.interiorid, Some(2));
+            assert_eq!(dynamic.playerid, None);
+        }
+    }
+
+    #[test]
+    fn test_basic_blocks_follow_jumps() {
+        let c = 4i64;
+        // 0: proc; 4: load.s.pri 12; 12: jzer 28; 20: const.pri 1; 28: retn
+        let code = vec![
+            OP_PROC as i64,
+            3,
+            3 * c,
+            OP_JZER as i64,
+            7 * c,
+            OP_CONST_PRI as i64,
+            1,
+            OP_RETN as i64,
+        ];
+        let image = TestImage {
+            cell_size: 4,
+            publics: vec![("OnPlayerSpawn", 0)],
+            natives: vec![],
+            code,
+            compact: false,
+        };
+        let amx = AmxFile::from_bytes(&image.build()).unwrap();
+        let instructions = amx.disassemble().unwrap();
+        assert_eq!(instructions.len(), 5);
+        assert_eq!(instructions[2].mnemonic, "jzer");
+
+        let blocks = amx.basic_blocks(&instructions);
+        let starts: Vec<u64> = blocks.iter().map(|b| b.start).collect();
+        assert_eq!(starts, vec![0, 20, 28]);
+        assert_eq!(blocks[0].successors, vec![20, 28]);
+        assert_eq!(blocks[1].successors, vec![28]);
+        assert!(blocks[2].successors.is_empty());
+
+        let listing = amx.listing().unwrap();
+        assert!(listing.contains("OnPlayerSpawn:"));
+        assert!(listing.contains("jzer"));
+    }
+
+    #[test]
+    fn test_rejects_oversized_operand_counts() {
+        let casetbl = OPCODES.iter().position(|(m, _)| *m == "casetbl").unwrap() as i64;
+        for count in [i32::MAX as i64, 3] {
+            let image = TestImage {
+                cell_size: 4,
+                publics: vec![],
+                natives: vec![],
+                code: vec![casetbl, count, 0],
+                compact: false,
+            };
+            let amx = AmxFile::from_bytes(&image.build()).unwrap();
+            assert!(matches!(amx.disassemble(), Err(AmxError::InvalidData(_))));
+        }
+    }
+
+    #[test]
+    fn test_unknown_opcodes_do_not_stop_disassembly() {
+        let unknown = OPCODES.len() as i64;
+        let image = TestImage {
+            cell_size: 4,
+            publics: vec![],
+            natives: vec![],
+            code: vec![OP_PROC as i64, unknown, -1, OP_CONST_PRI as i64, 1, OP_RETN as i64],
+            compact: false,
+        };
+        let amx = AmxFile::from_bytes(&image.build()).unwrap();
+        let instructions = amx.disassemble().unwrap();
+
+        let mnemonics: Vec<&str> = instructions.iter().map(|i| i.mnemonic.as_str()).collect();
+        assert_eq!(
+            mnemonics,
+            vec!["proc", UNKNOWN_OPCODE, UNKNOWN_OPCODE, "const.pri", "retn"]
+        );
+        assert_eq!(instructions[1].operands, vec![unknown]);
+        assert_eq!(instructions[2].opcode, u32::MAX);
+        assert_eq!(instructions[3].address, 12);
+    }
+
+    #[test]
+    fn test_rejects_tables_outside_the_file() {
+        // `publics` lives at byte 32 of the header
+        let mut bytes = object_script(4, false).build();
+        bytes[32..36].copy_from_slice(&(-8i32).to_le_bytes());
+        assert!(matches!(
+            AmxFile::from_bytes(&bytes),
+            Err(AmxError::InvalidData(_))
+        ));
+    }
+
+    #[test]
+    fn test_rejects_bad_magic() {
+        let mut bytes = object_script(4, false).build();
+        bytes[4] = 0;
+        assert!(matches!(
+            AmxFile::from_bytes(&bytes),
+            Err(AmxError::InvalidData(_))
+        ));
+    }
+}
@@ -4,14 +4,51 @@
  */
 use serde::{Deserialize, Serialize};
 
-use crate::blueprint::pawn_parser::PawnAST;
+use crate::blueprint::pawn_parser::{DirectiveNode, ParameterNode, PawnAST, StatementNode};
+use crate::blueprint::pawn_preprocessor::IncludeRecord;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UniversalAST {
     pub language: String,
+    /// Ignored when `directives` is set, the `#include` lines are part of it
+    #[serde(default)]
+    pub includes: Vec<UniversalInclude>,
+    /// Top-level directive lines as written, set when the tree keeps macro
+    /// uses unexpanded so both `#if` branches are in it
+    #[serde(default)]
+    pub directives: Vec<UniversalDirective>,
+    #[serde(default)]
+    pub enums: Vec<UniversalEnum>,
     pub functions: Vec<UniversalFunction>,
     pub variables: Vec<UniversalVariable>,
+    #[serde(default)]
     pub metadata: serde_json::Value,
+    /// The parsed file as written, see `PawnAST::written`. The generator
+    /// prints it instead of this tree so macro uses and inactive `#if`
+    /// branches survive a round trip
+    #[serde(default)]
+    pub written: Option<Box<UniversalAST>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UniversalInclude {
+    pub name: String,
+    /// `#include <name>` when set, `#include "name"` otherwise
+    #[serde(default = "default_system_include")]
+    pub system: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UniversalDirective {
+    /// Directive keyword, e.g. `define` or `if`
+    pub kind: String,
+    /// Full text including `#` and any `\` continuation lines
+    pub content: String,
+    pub position: SourcePosition,
+}
+
+fn default_system_include() -> bool {
+    true
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,6 +59,9 @@ pub struct UniversalFunction {
     pub return_type: UniversalType,
     pub body: Vec<UniversalStatement>,
     pub modifiers: Vec<String>,
+    /// Declaration without a body (`forward`, `native`)
+    #[serde(default)]
+    pub is_prototype: bool,
     pub position: SourcePosition,
 }
 
@@ -30,7 +70,13 @@ pub struct UniversalParameter {
     pub id: String,
     pub name: String,
     pub param_type: UniversalType,
-    pub default_value: Option<String>,
+    pub default_value: Option<UniversalExpression>,
+    #[serde(default)]
+    pub is_reference: bool,
+    #[serde(default)]
+    pub is_const: bool,
+    #[serde(default)]
+    pub is_variadic: bool,
     pub position: SourcePosition,
 }
 
@@ -39,32 +85,901 @@ pub struct UniversalVariable {
     pub id: String,
     pub name: String,
     pub var_type: UniversalType,
-    pub initial_value: Option<String>,
+    pub initial_value: Option<UniversalExpression>,
+    /// Storage keywords such as `new`, `static`, `const`
+    #[serde(default)]
+    pub storage: Vec<String>,
     pub position: SourcePosition,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct UniversalType {
     pub name: String,
     pub tag: Option<String>,
     pub is_array: bool,
     pub array_size: Option<usize>,
+    /// Every dimension as written, `None` for an unsized `[]`
+    #[serde(default)]
+    pub dimensions: Vec<Option<String>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct UniversalStatement {
+pub struct UniversalEnum {
     pub id: String,
-    pub kind: String,
-    pub content: serde_json::Value,
+    pub name: Option<String>,
+    pub tag: Option<String>,
+    pub increment: Option<String>,
+    pub items: Vec<UniversalEnumItem>,
     pub position: SourcePosition,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UniversalEnumItem {
+    pub name: String,
+    pub tag: Option<String>,
+    #[serde(default)]
+    pub dimensions: Vec<Option<String>>,
+    pub value: Option<UniversalExpression>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UniversalStatement {
+    pub id: String,
+    #[serde(flatten)]
+    pub node: UniversalStatementNode,
+    pub position: SourcePosition,
+}
+
+impl UniversalStatement {
+    pub fn kind(&self) -> &'static str {
+        self.node.kind()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum UniversalStatementNode {
+    Expression {
+        expression: UniversalExpression,
+    },
+    Variable {
+        storage: Vec<String>,
+        declarations: Vec<UniversalDeclarator>,
+    },
+    If {
+        condition: UniversalExpression,
+        then_branch: Box<UniversalStatement>,
+        else_branch: Option<Box<UniversalStatement>>,
+    },
+    While {
+        condition: UniversalExpression,
+        body: Box<UniversalStatement>,
+    },
+    DoWhile {
+        body: Box<UniversalStatement>,
+        condition: UniversalExpression,
+    },
+    For {
+        init: Option<UniversalForInit>,
+        condition: Option<UniversalExpression>,
+        #[serde(default)]
+        update: Vec<UniversalExpression>,
+        body: Box<UniversalStatement>,
+    },
+    Switch {
+        subject: UniversalExpression,
+        cases: Vec<UniversalCase>,
+    },
+    Return {
+        value: Option<UniversalExpression>,
+    },
+    Break,
+    Continue,
+    Block {
+        statements: Vec<UniversalStatement>,
+    },
+    Empty,
+    /// Directive line inside a function body, written as is
+    Directive {
+        content: String,
+    },
+}
+
+impl UniversalStatementNode {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Expression { .. } => "expression",
+            Self::Variable { .. } => "variable",
+            Self::If { .. } => "if",
+            Self::While { .. } => "while",
+            Self::DoWhile { .. } => "do_while",
+            Self::For { .. } => "for",
+            Self::Switch { .. } => "switch",
+            Self::Return { .. } => "return",
+            Self::Break => "break",
+            Self::Continue => "continue",
+            Self::Block { .. } => "block",
+            Self::Empty => "empty",
+            Self::Directive { .. } => "directive",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum UniversalForInit {
+    Declaration {
+        declarations: Vec<UniversalDeclarator>,
+    },
+    Expressions {
+        expressions: Vec<UniversalExpression>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UniversalDeclarator {
+    pub name: String,
+    pub var_type: UniversalType,
+    pub initializer: Option<UniversalExpression>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UniversalCase {
+    /// Empty for the `default:` clause
+    pub values: Vec<UniversalCaseValue>,
+    pub body: Vec<UniversalStatement>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UniversalCaseValue {
+    pub value: UniversalExpression,
+    /// Upper bound of a `case a .. b` range
+    pub range_end: Option<UniversalExpression>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum UniversalExpression {
+    Literal {
+        literal_type: LiteralType,
+        /// Literal as written, including quotes and prefixes
+        value: String,
+    },
+    Identifier {
+        name: String,
+    },
+    Assignment {
+        operator: AssignmentOperator,
+        target: Box<UniversalExpression>,
+        value: Box<UniversalExpression>,
+    },
+    Conditional {
+        condition: Box<UniversalExpression>,
+        when_true: Box<UniversalExpression>,
+        when_false: Box<UniversalExpression>,
+    },
+    Binary {
+        operator: BinaryOperator,
+        left: Box<UniversalExpression>,
+        right: Box<UniversalExpression>,
+    },
+    Unary {
+        operator: UnaryOperator,
+        operand: Box<UniversalExpression>,
+    },
+    Postfix {
+        operator: UnaryOperator,
+        operand: Box<UniversalExpression>,
+    },
+    TagOverride {
+        tag: String,
+        operand: Box<UniversalExpression>,
+    },
+    Sizeof {
+        operand: String,
+    },
+    Tagof {
+        operand: String,
+    },
+    Call {
+        callee: Box<UniversalExpression>,
+        arguments: Vec<UniversalArgument>,
+    },
+    Index {
+        array: Box<UniversalExpression>,
+        index: Box<UniversalExpression>,
+    },
+    ArrayInitializer {
+        elements: Vec<UniversalExpression>,
+        /// Trailing `...` filling the remaining cells
+        #[serde(default)]
+        ellipsis: bool,
+    },
+}
+
+impl UniversalExpression {
+    /// Binding strength, from assignment (1) up to primary expressions.
+    pub fn precedence(&self) -> u8 {
+        match self {
+            Self::Assignment { .. } => 1,
+            Self::Conditional { .. } => 2,
+            Self::Binary { operator, .. } => operator.precedence(),
+            Self::Unary { .. }
+            | Self::TagOverride { .. }
+            | Self::Sizeof { .. }
+            | Self::Tagof { .. } => 13,
+            Self::Postfix { .. } | Self::Call { .. } | Self::Index { .. } => 14,
+            Self::Literal { .. } | Self::Identifier { .. } | Self::ArrayInitializer { .. } => 15,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UniversalArgument {
+    /// Set for Pawn's named arguments (`.name = value`)
+    pub name: Option<String>,
+    pub value: UniversalExpression,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LiteralType {
+    Integer,
+    Hex,
+    Binary,
+    Float,
+    Character,
+    String,
+    Boolean,
+}
+
+/// Declares an operator enum serialized as its source symbol.
+macro_rules! operator_enum {
+    ($name:ident { $($variant:ident => $symbol:literal),+ $(,)? }) => {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+        pub enum $name {
+            $(#[serde(rename = $symbol)] $variant,)+
+        }
+
+        impl $name {
+            pub fn symbol(self) -> &'static str {
+                match self {
+                    $(Self::$variant => $symbol,)+
+                }
+            }
+
+            pub fn from_symbol(symbol: &str) -> Option<Self> {
+                match symbol {
+                    $($symbol => Some(Self::$variant),)+
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+operator_enum!(AssignmentOperator {
+    Assign => "=",
+    AddAssign => "+=",
+    SubtractAssign => "-=",
+    MultiplyAssign => "*=",
+    DivideAssign => "/=",
+    ModuloAssign => "%=",
+    AndAssign => "&=",
+    OrAssign => "|=",
+    XorAssign => "^=",
+    ShiftLeftAssign => "<<=",
+    ShiftRightAssign => ">>=",
+    LogicalShiftRightAssign => ">>>=",
+});
+
+operator_enum!(BinaryOperator {
+    LogicalOr => "||",
+    LogicalAnd => "&&",
+    BitOr => "|",
+    BitXor => "^",
+    BitAnd => "&",
+    Equal => "==",
+    NotEqual => "!=",
+    Less => "<",
+    LessEqual => "<=",
+    Greater => ">",
+    GreaterEqual => ">=",
+    ShiftLeft => "<<",
+    ShiftRight => ">>",
+    LogicalShiftRight => ">>>",
+    Add => "+",
+    Subtract => "-",
+    Multiply => "*",
+    Divide => "/",
+    Modulo => "%",
+});
+
+impl BinaryOperator {
+    pub fn precedence(self) -> u8 {
+        match self {
+            Self::LogicalOr => 3,
+            Self::LogicalAnd => 4,
+            Self::BitOr => 5,
+            Self::BitXor => 6,
+            Self::BitAnd => 7,
+            Self::Equal | Self::NotEqual => 8,
+            Self::Less | Self::LessEqual | Self::Greater | Self::GreaterEqual => 9,
+            Self::ShiftLeft | Self::ShiftRight | Self::LogicalShiftRight => 10,
+            Self::Add | Self::Subtract => 11,
+            Self::Multiply | Self::Divide | Self::Modulo => 12,
+        }
+    }
+}
+
+operator_enum!(UnaryOperator {
+    Increment => "++",
+    Decrement => "--",
+    Not => "!",
+    Invert => "~",
+    Negate => "-",
+    Plus => "+",
+});
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SourcePosition {
     pub line: usize,
     pub column: usize,
 }
 
+/// Untagged Pawn values are plain cells.
+pub(crate) const UNTAGGED_TYPE: &str = "cell";
+
+/// Build a `UniversalType` from Pawn's tag and `[]` dimensions.
+pub(crate) fn pawn_type(
+    name: &str,
+    tag: &Option<String>,
+    dimensions: &[Option<String>],
+) -> UniversalType {
+    UniversalType {
+        name: name.to_string(),
+        tag: tag.clone(),
+        is_array: !dimensions.is_empty(),
+        array_size: dimensions
+            .first()
+            .and_then(|size| size.as_ref())
+            .and_then(|size| size.parse::<usize>().ok()),
+        dimensions: dimensions.to_vec(),
+    }
+}
+
+fn universal_parameter(p: &ParameterNode) -> UniversalParameter {
+    UniversalParameter {
+        id: format!("param_{}", p.name),
+        name: p.name.clone(),
+        param_type: pawn_type(&p.param_type, &p.tag, &p.dimensions),
+        default_value: p.default_value.clone(),
+        is_reference: p.is_reference,
+        is_const: p.is_const,
+        is_variadic: p.is_variadic,
+        position: SourcePosition {
+            line: p.line,
+            column: p.column,
+        },
+    }
+}
+
+fn universal_include(include: &IncludeRecord) -> UniversalInclude {
+    UniversalInclude {
+        name: include.name.clone(),
+        system: include.system,
+    }
+}
+
+fn universal_directive(directive: &DirectiveNode) -> UniversalDirective {
+    UniversalDirective {
+        kind: directive.kind.clone(),
+        content: directive.content.clone(),
+        position: SourcePosition {
+            line: directive.line,
+            column: directive.column,
+        },
+    }
+}
+
+fn universal_statement(s: &StatementNode) -> UniversalStatement {
+    UniversalStatement {
+        id: format!("stmt_{}_{}", s.line, s.column),
+        node: s.node.clone(),
+        position: SourcePosition {
+            line: s.line,
+            column: s.column,
+        },
+    }
+}
+
+/*
+ * Program tree sent by the blueprint editor (`converters/blueprint-to-ast.ts`).
+ *
+ * Its nodes carry `type`/`kind` pairs, nest statement and expression data under
+ * `statement`/`expression` and keep literal values untyped, so it is read into
+ * these mirror types and converted instead of deserialized as a `UniversalAST`.
+ */
+
+#[derive(Deserialize)]
+struct BlueprintProgram {
+    language: Option<String>,
+    #[serde(default)]
+    functions: Vec<BlueprintFunction>,
+    #[serde(default)]
+    variables: Vec<BlueprintVariable>,
+    #[serde(default)]
+    metadata: serde_json::Value,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BlueprintFunction {
+    #[serde(default)]
+    id: String,
+    name: String,
+    #[serde(default)]
+    parameters: Vec<BlueprintParameter>,
+    return_type: Option<BlueprintType>,
+    #[serde(default)]
+    body: Vec<BlueprintStatement>,
+    // Either flag may be any truthy value, e.g. the callback name
+    #[serde(default)]
+    is_public: serde_json::Value,
+    #[serde(default)]
+    is_static: serde_json::Value,
+    #[serde(default)]
+    modifiers: Vec<String>,
+    position: Option<BlueprintPosition>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BlueprintParameter {
+    #[serde(default)]
+    id: String,
+    name: String,
+    parameter_type: Option<BlueprintType>,
+    default_value: Option<BlueprintExpression>,
+    #[serde(default)]
+    is_reference: bool,
+    #[serde(default)]
+    is_const: bool,
+    position: Option<BlueprintPosition>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BlueprintVariable {
+    #[serde(default)]
+    id: String,
+    name: String,
+    variable_type: Option<BlueprintType>,
+    #[serde(default)]
+    is_constant: serde_json::Value,
+    initial_value: Option<BlueprintExpression>,
+    position: Option<BlueprintPosition>,
+}
+
+#[derive(Deserialize)]
+struct BlueprintType {
+    name: String,
+}
+
+/// Positions are derived from node coordinates and may be negative or fractional.
+#[derive(Deserialize)]
+struct BlueprintPosition {
+    line: f64,
+    column: f64,
+}
+
+#[derive(Deserialize)]
+struct BlueprintStatement {
+    #[serde(default)]
+    id: String,
+    #[serde(flatten)]
+    node: BlueprintStatementNode,
+    position: Option<BlueprintPosition>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum BlueprintStatementNode {
+    If {
+        condition: BlueprintExpression,
+        #[serde(rename = "then")]
+        then_branch: BlueprintBody,
+        #[serde(rename = "else")]
+        else_branch: Option<BlueprintBody>,
+    },
+    While {
+        condition: BlueprintExpression,
+        body: BlueprintBody,
+    },
+    For {
+        statement: BlueprintFor,
+    },
+    Return {
+        statement: BlueprintReturn,
+    },
+    Assignment {
+        statement: BlueprintAssignment,
+    },
+    Expression {
+        statement: BlueprintExpression,
+    },
+}
+
+/// Branches and loop bodies hold one statement or a list of them.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum BlueprintBody {
+    Single(Box<BlueprintStatement>),
+    List(Vec<BlueprintStatement>),
+}
+
+#[derive(Deserialize)]
+struct BlueprintFor {
+    init: Option<BlueprintExpression>,
+    condition: Option<BlueprintExpression>,
+    update: Option<BlueprintExpression>,
+    body: BlueprintBody,
+}
+
+#[derive(Deserialize)]
+struct BlueprintReturn {
+    value: Option<BlueprintExpression>,
+}
+
+#[derive(Deserialize)]
+struct BlueprintAssignment {
+    target: BlueprintExpression,
+    operator: String,
+    value: BlueprintExpression,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "kind", content = "expression", rename_all = "snake_case")]
+enum BlueprintExpression {
+    Literal {
+        value: serde_json::Value,
+        #[serde(rename = "type")]
+        literal_type: String,
+    },
+    Variable {
+        name: String,
+    },
+    Binary {
+        operator: String,
+        left: Box<BlueprintExpression>,
+        right: Box<BlueprintExpression>,
+    },
+    Unary {
+        operator: String,
+        operand: Box<BlueprintExpression>,
+    },
+    Call {
+        callee: BlueprintCallee,
+        #[serde(default)]
+        arguments: Vec<BlueprintExpression>,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum BlueprintCallee {
+    Name(String),
+    Expression(Box<BlueprintExpression>),
+}
+
+fn is_truthy(value: &serde_json::Value) -> bool {
+    match value {
+        serde_json::Value::Null => false,
+        serde_json::Value::Bool(flag) => *flag,
+        serde_json::Value::Number(number) => number.as_f64().is_some_and(|n| n != 0.0),
+        serde_json::Value::String(text) => !text.is_empty(),
+        _ => true,
+    }
+}
+
+fn blueprint_position(position: &Option<BlueprintPosition>) -> SourcePosition {
+    position
+        .as_ref()
+        .map(|position| SourcePosition {
+            line: position.line.max(0.0) as usize,
+            column: position.column.max(0.0) as usize,
+        })
+        .unwrap_or(SourcePosition { line: 0, column: 0 })
+}
+
+/// Map a blueprint data type onto Pawn's tags: only `float` and `bool` are tagged.
+fn blueprint_type(ty: &Option<BlueprintType>) -> UniversalType {
+    match ty.as_ref().map(|ty| ty.name.as_str()).unwrap_or("any") {
+        "float" | "Float" => pawn_type("Float", &Some("Float".to_string()), &[]),
+        "bool" => pawn_type("bool", &Some("bool".to_string()), &[]),
+        "string" | "array" => pawn_type(UNTAGGED_TYPE, &None, &[None]),
+        "void" => pawn_type("void", &None, &[]),
+        _ => pawn_type(UNTAGGED_TYPE, &None, &[]),
+    }
+}
+
+fn blueprint_body(body: &BlueprintBody) -> Result<UniversalStatement, String> {
+    match body {
+        BlueprintBody::Single(statement) => blueprint_statement(statement),
+        BlueprintBody::List(statements) => Ok(UniversalStatement {
+            id: String::new(),
+            node: UniversalStatementNode::Block {
+                statements: statements
+                    .iter()
+                    .map(blueprint_statement)
+                    .collect::<Result<_, _>>()?,
+            },
+            position: SourcePosition { line: 0, column: 0 },
+        }),
+    }
+}
+
+fn blueprint_statement(statement: &BlueprintStatement) -> Result<UniversalStatement, String> {
+    let node = match &statement.node {
+        BlueprintStatementNode::If {
+            condition,
+            then_branch,
+            else_branch,
+        } => UniversalStatementNode::If {
+            condition: blueprint_expression(condition)?,
+            then_branch: Box::new(blueprint_body(then_branch)?),
+            else_branch: else_branch
+                .as_ref()
+                .map(|branch| blueprint_body(branch).map(Box::new))
+                .transpose()?,
+        },
+        BlueprintStatementNode::While { condition, body } => UniversalStatementNode::While {
+            condition: blueprint_expression(condition)?,
+            body: Box::new(blueprint_body(body)?),
+        },
+        BlueprintStatementNode::For { statement } => UniversalStatementNode::For {
+            init: statement
+                .init
+                .as_ref()
+                .map(|init| {
+                    Ok::<_, String>(UniversalForInit::Expressions {
+                        expressions: vec![blueprint_expression(init)?],
+                    })
+                })
+                .transpose()?,
+            condition: statement
+                .condition
+                .as_ref()
+                .map(blueprint_expression)
+                .transpose()?,
+            update: statement
+                .update
+                .iter()
+                .map(blueprint_expression)
+                .collect::<Result<_, _>>()?,
+            body: Box::new(blueprint_body(&statement.body)?),
+        },
+        BlueprintStatementNode::Return { statement } => UniversalStatementNode::Return {
+            value: statement
+                .value
+                .as_ref()
+                .map(blueprint_expression)
+                .transpose()?,
+        },
+        BlueprintStatementNode::Assignment { statement } => UniversalStatementNode::Expression {
+            expression: UniversalExpression::Assignment {
+                operator: AssignmentOperator::from_symbol(&statement.operator).ok_or_else(
+                    || format!("Unknown assignment operator `{}`", statement.operator),
+                )?,
+                target: Box::new(blueprint_expression(&statement.target)?),
+                value: Box::new(blueprint_expression(&statement.value)?),
+            },
+        },
+        BlueprintStatementNode::Expression { statement } => UniversalStatementNode::Expression {
+            expression: blueprint_expression(statement)?,
+        },
+    };
+
+    Ok(UniversalStatement {
+        id: statement.id.clone(),
+        node,
+        position: blueprint_position(&statement.position),
+    })
+}
+
+fn blueprint_expression(expression: &BlueprintExpression) -> Result<UniversalExpression, String> {
+    Ok(match expression {
+        BlueprintExpression::Literal {
+            value,
+            literal_type,
+        } => blueprint_literal(value, literal_type)?,
+        BlueprintExpression::Variable { name } => {
+            UniversalExpression::Identifier { name: name.clone() }
+        }
+        BlueprintExpression::Binary {
+            operator,
+            left,
+            right,
+        } => UniversalExpression::Binary {
+            operator: BinaryOperator::from_symbol(operator)
+                .ok_or_else(|| format!("Unknown binary operator `{}`", operator))?,
+            left: Box::new(blueprint_expression(left)?),
+            right: Box::new(blueprint_expression(right)?),
+        },
+        BlueprintExpression::Unary { operator, operand } => UniversalExpression::Unary {
+            operator: UnaryOperator::from_symbol(operator)
+                .ok_or_else(|| format!("Unknown unary operator `{}`", operator))?,
+            operand: Box::new(blueprint_expression(operand)?),
+        },
+        BlueprintExpression::Call { callee, arguments } => UniversalExpression::Call {
+            callee: Box::new(match callee {
+                BlueprintCallee::Name(name) => {
+                    UniversalExpression::Identifier { name: name.clone() }
+                }
+                BlueprintCallee::Expression(callee) => blueprint_expression(callee)?,
+            }),
+            arguments: arguments
+                .iter()
+                .map(|argument| {
+                    Ok(UniversalArgument {
+                        name: None,
+                        value: blueprint_expression(argument)?,
+                    })
+                })
+                .collect::<Result<_, String>>()?,
+        },
+    })
+}
+
+/// Literal values arrive untyped (`42`, `"42"`, `true`); they are written out
+/// the way Pawn spells them. Strings and characters that are already quoted
+/// are kept as written.
+fn blueprint_literal(
+    value: &serde_json::Value,
+    literal_type: &str,
+) -> Result<UniversalExpression, String> {
+    let text = match value {
+        serde_json::Value::String(text) => text.trim().to_string(),
+        serde_json::Value::Null => String::new(),
+        other => other.to_string(),
+    };
+    let literal = |literal_type, value: String| UniversalExpression::Literal {
+        literal_type,
+        value,
+    };
+
+    match literal_type {
+        "string" if text.starts_with('"') && text.ends_with('"') && text.len() > 1 => {
+            Ok(literal(LiteralType::String, text))
+        }
+        "string" => {
+            let raw = match value {
+                serde_json::Value::String(raw) => raw.clone(),
+                _ => text,
+            };
+            Ok(literal(
+                LiteralType::String,
+                format!("\"{}\"", raw.replace('\\', "\\\\").replace('"', "\\\"")),
+            ))
+        }
+        "bool" => match text.as_str() {
+            "true" | "false" => Ok(literal(LiteralType::Boolean, text)),
+            _ => Err(format!("Invalid bool literal `{}`", text)),
+        },
+        "float" => {
+            let number: f64 = text
+                .parse()
+                .map_err(|_| format!("Invalid float literal `{}`", text))?;
+            // Pawn floats need a decimal point and have no exponent-only form
+            let mut value = number.abs().to_string();
+            if !value.contains('.') {
+                value.push_str(".0");
+            }
+            let float = literal(LiteralType::Float, value);
+            Ok(if number.is_sign_negative() {
+                UniversalExpression::Unary {
+                    operator: UnaryOperator::Negate,
+                    operand: Box::new(float),
+                }
+            } else {
+                float
+            })
+        }
+        "int" | "any" => {
+            if text.starts_with('\'') && text.ends_with('\'') && text.len() > 1 {
+                Ok(literal(LiteralType::Character, text))
+            } else if text.starts_with("0x") {
+                Ok(literal(LiteralType::Hex, text))
+            } else if text.starts_with("0b") {
+                Ok(literal(LiteralType::Binary, text))
+            } else if let Ok(number) = text.parse::<i64>() {
+                let integer = literal(LiteralType::Integer, number.unsigned_abs().to_string());
+                Ok(if number < 0 {
+                    UniversalExpression::Unary {
+                        operator: UnaryOperator::Negate,
+                        operand: Box::new(integer),
+                    }
+                } else {
+                    integer
+                })
+            } else if text.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+                // Named constants such as `MAX_PLAYERS` are typed into literal nodes
+                Ok(UniversalExpression::Identifier { name: text })
+            } else {
+                Err(format!("Invalid int literal `{}`", text))
+            }
+        }
+        other => Err(format!("Unsupported literal type `{}`", other)),
+    }
+}
+
+fn blueprint_function(function: &BlueprintFunction) -> Result<UniversalFunction, String> {
+    let mut modifiers = function.modifiers.clone();
+    for (flag, modifier) in [
+        (&function.is_public, "public"),
+        (&function.is_static, "static"),
+    ] {
+        if is_truthy(flag) && !modifiers.iter().any(|m| m == modifier) {
+            modifiers.push(modifier.to_string());
+        }
+    }
+
+    let parameters = function
+        .parameters
+        .iter()
+        .map(|parameter| {
+            Ok(UniversalParameter {
+                id: parameter.id.clone(),
+                name: parameter.name.clone(),
+                param_type: blueprint_type(&parameter.parameter_type),
+                default_value: parameter
+                    .default_value
+                    .as_ref()
+                    .map(blueprint_expression)
+                    .transpose()?,
+                is_reference: parameter.is_reference,
+                is_const: parameter.is_const,
+                is_variadic: false,
+                position: blueprint_position(&parameter.position),
+            })
+        })
+        .collect::<Result<_, String>>()?;
+
+    Ok(UniversalFunction {
+        id: function.id.clone(),
+        name: function.name.clone(),
+        parameters,
+        return_type: blueprint_type(&function.return_type),
+        body: function
+            .body
+            .iter()
+            .map(blueprint_statement)
+            .collect::<Result<_, _>>()?,
+        modifiers,
+        is_prototype: false,
+        position: blueprint_position(&function.position),
+    })
+}
+
+fn blueprint_variable(variable: &BlueprintVariable) -> Result<UniversalVariable, String> {
+    Ok(UniversalVariable {
+        id: variable.id.clone(),
+        name: variable.name.clone(),
+        var_type: blueprint_type(&variable.variable_type),
+        initial_value: variable
+            .initial_value
+            .as_ref()
+            .map(blueprint_expression)
+            .transpose()?,
+        storage: if is_truthy(&variable.is_constant) {
+            vec!["new".to_string(), "const".to_string()]
+        } else {
+            Vec::new()
+        },
+        position: blueprint_position(&variable.position),
+    })
+}
+
 pub struct ASTBridge;
 
 impl ASTBridge {
@@ -78,45 +993,11 @@ impl ASTBridge {
             functions.push(UniversalFunction {
                 id: format!("func_{}", func.name),
                 name: func.name.clone(),
-                parameters: func
-                    .parameters
-                    .iter()
-                    .map(|p| UniversalParameter {
-                        id: format!("param_{}", p.name),
-                        name: p.name.clone(),
-                        param_type: UniversalType {
-                            name: p.param_type.clone(),
-                            tag: None,
-                            is_array: false,
-                            array_size: None,
-                        },
-                        default_value: p.default_value.clone(),
-                        position: SourcePosition {
-                            line: p.line,
-                            column: p.column,
-                        },
-                    })
-                    .collect(),
-                return_type: UniversalType {
-                    name: func.return_type.clone(),
-                    tag: None,
-                    is_array: false,
-                    array_size: None,
-                },
-                body: func
-                    .body
-                    .iter()
-                    .map(|s| UniversalStatement {
-                        id: format!("stmt_{}_{}", s.line, s.column),
-                        kind: s.kind.clone(),
-                        content: s.content.clone(),
-                        position: SourcePosition {
-                            line: s.line,
-                            column: s.column,
-                        },
-                    })
-                    .collect(),
+                parameters: func.parameters.iter().map(universal_parameter).collect(),
+                return_type: pawn_type(&func.return_type, &func.return_tag, &[]),
+                body: func.body.iter().map(universal_statement).collect(),
                 modifiers: func.modifiers.clone(),
+                is_prototype: !func.has_body,
                 position: SourcePosition {
                     line: func.line,
                     column: func.column,
@@ -129,45 +1010,11 @@ impl ASTBridge {
             functions.push(UniversalFunction {
                 id: format!("callback_{}", cb.name),
                 name: cb.name.clone(),
-                parameters: cb
-                    .parameters
-                    .iter()
-                    .map(|p| UniversalParameter {
-                        id: format!("param_{}", p.name),
-                        name: p.name.clone(),
-                        param_type: UniversalType {
-                            name: p.param_type.clone(),
-                            tag: None,
-                            is_array: false,
-                            array_size: None,
-                        },
-                        default_value: p.default_value.clone(),
-                        position: SourcePosition {
-                            line: p.line,
-                            column: p.column,
-                        },
-                    })
-                    .collect(),
-                return_type: UniversalType {
-                    name: "void".to_string(),
-                    tag: None,
-                    is_array: false,
-                    array_size: None,
-                },
-                body: cb
-                    .body
-                    .iter()
-                    .map(|s| UniversalStatement {
-                        id: format!("stmt_{}_{}", s.line, s.column),
-                        kind: s.kind.clone(),
-                        content: s.content.clone(),
-                        position: SourcePosition {
-                            line: s.line,
-                            column: s.column,
-                        },
-                    })
-                    .collect(),
+                parameters: cb.parameters.iter().map(universal_parameter).collect(),
+                return_type: pawn_type("void", &None, &[]),
+                body: cb.body.iter().map(universal_statement).collect(),
                 modifiers: vec!["public".to_string()],
+                is_prototype: false,
                 position: SourcePosition {
                     line: cb.line,
                     column: cb.column,
@@ -180,13 +1027,9 @@ impl ASTBridge {
             variables.push(UniversalVariable {
                 id: format!("var_{}", var.name),
                 name: var.name.clone(),
-                var_type: UniversalType {
-                    name: var.var_type.clone(),
-                    tag: None,
-                    is_array: false,
-                    array_size: None,
-                },
+                var_type: pawn_type(&var.var_type, &var.tag, &var.dimensions),
                 initial_value: var.initial_value.clone(),
+                storage: var.storage.clone(),
                 position: SourcePosition {
                     line: var.line,
                     column: var.column,
@@ -194,17 +1037,88 @@ impl ASTBridge {
             });
         }
 
+        let enums = pawn_ast
+            .enums
+            .iter()
+            .map(|e| UniversalEnum {
+                id: format!("enum_{}_{}", e.line, e.column),
+                name: e.name.clone(),
+                tag: e.tag.clone(),
+                increment: e.increment.clone(),
+                items: e
+                    .items
+                    .iter()
+                    .map(|item| UniversalEnumItem {
+                        name: item.name.clone(),
+                        tag: item.tag.clone(),
+                        dimensions: item.dimensions.clone(),
+                        value: item.value.clone(),
+                    })
+                    .collect(),
+                position: SourcePosition {
+                    line: e.line,
+                    column: e.column,
+                },
+            })
+            .collect();
+
         UniversalAST {
             language: "pawn".to_string(),
+            includes: pawn_ast.includes.iter().map(universal_include).collect(),
+            // Directives of an expanded tree belong to the active branches only
+            directives: if pawn_ast.macros_expanded {
+                Vec::new()
+            } else {
+                pawn_ast
+                    .directives
+                    .iter()
+                    .map(universal_directive)
+                    .collect()
+            },
+            enums,
             functions,
             variables,
             metadata: serde_json::json!({
-                "includes": pawn_ast.includes,
                 "directives": pawn_ast.directives,
             }),
+            written: pawn_ast
+                .written
+                .as_deref()
+                .map(|written| Box::new(Self::pawn_to_universal(written))),
         }
     }
 
+    /// Read an AST sent by the frontend: either a `UniversalAST` or the
+    /// program tree built by the blueprint editor (`type: "program"`).
+    pub fn universal_from_json(value: serde_json::Value) -> Result<UniversalAST, String> {
+        if value.get("type").and_then(|t| t.as_str()) != Some("program") {
+            return serde_json::from_value(value)
+                .map_err(|e| format!("Invalid blueprint AST: {}", e));
+        }
+
+        let program: BlueprintProgram =
+            serde_json::from_value(value).map_err(|e| format!("Invalid blueprint AST: {}", e))?;
+
+        Ok(UniversalAST {
+            language: program.language.unwrap_or_else(|| "pawn".to_string()),
+            includes: Vec::new(),
+            directives: Vec::new(),
+            enums: Vec::new(),
+            functions: program
+                .functions
+                .iter()
+                .map(blueprint_function)
+                .collect::<Result<_, _>>()?,
+            variables: program
+                .variables
+                .iter()
+                .map(blueprint_variable)
+                .collect::<Result<_, _>>()?,
+            metadata: program.metadata,
+            written: None,
+        })
+    }
+
     /// Convert Universal AST to JSON for TypeScript
     pub fn universal_to_json(universal: &UniversalAST) -> serde_json::Value {
         serde_json::to_value(universal).unwrap_or(serde_json::json!({}))
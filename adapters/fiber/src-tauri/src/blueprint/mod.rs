@@ -1,12 +1,17 @@
+pub mod amx;
 pub mod ast_bridge;
 /**
  * Blueprint module for parsing and generating code
  */
 pub mod parser;
+pub mod pawn_analyzer;
 pub mod pawn_generator;
 pub mod pawn_parser;
+pub mod pawn_preprocessor;
 
 pub use ast_bridge::ASTBridge;
 pub use parser::{ParseResult, Parser};
+pub use pawn_analyzer::{Diagnostic, DiagnosticSeverity, NativeDatabase, PawnAnalyzer};
 pub use pawn_generator::{GenerationOptions, PawnGenerator};
 pub use pawn_parser::PawnParser;
+pub use pawn_preprocessor::{PawnPreprocessor, PreprocessorOptions};
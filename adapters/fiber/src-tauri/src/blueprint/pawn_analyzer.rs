@@ -0,0 +1,1395 @@
+/**
+ * Semantic analysis over the Pawn AST
+ *
+ * Resolves identifiers against scoped symbol tables and a database of native
+ * signatures (bundled a_samp/streamer includes plus any resolved `#include`s),
+ * then checks call arity, tag usage, unreachable code and missing returns.
+ * Diagnostics carry a `SourcePosition` so the frontend can underline them.
+ */
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::blueprint::ast_bridge::{
+    AssignmentOperator, BinaryOperator, LiteralType, SourcePosition, UnaryOperator,
+    UniversalArgument, UniversalDeclarator, UniversalExpression, UniversalForInit,
+    UniversalStatement, UniversalStatementNode,
+};
+use crate::blueprint::pawn_parser::{ParameterNode, PawnAST, PawnParser, StatementNode};
+use crate::blueprint::pawn_preprocessor::{IncludeRecord, PawnPreprocessor, PreprocessorOptions};
+
+/// Include files shipped with the analyzer, keyed by the name scripts include them as.
+const BUNDLED_INCLUDES: [(&str, &str); 4] = [
+    ("core", include_str!("natives/core.inc")),
+    ("float", include_str!("natives/float.inc")),
+    ("a_samp", include_str!("natives/a_samp.inc")),
+    ("streamer", include_str!("natives/streamer.inc")),
+];
+
+/// Standard include names whose declarations are covered by `BUNDLED_INCLUDES`.
+const COVERED_INCLUDES: [&str; 10] = [
+    "a_samp",
+    "a_players",
+    "a_objects",
+    "a_vehicles",
+    "a_actor",
+    "core",
+    "float",
+    "string",
+    "console",
+    "streamer",
+];
+
+lazy_static::lazy_static! {
+    static ref BUNDLED_DATABASE: NativeDatabase = {
+        let mut database = NativeDatabase::default();
+        for (_, source) in BUNDLED_INCLUDES {
+            // Bundled sources are covered by tests; a parse failure only loses signatures
+            let _ = database.load_source(source, None, &PreprocessorOptions::default());
+        }
+        database
+    };
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiagnosticSeverity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub severity: DiagnosticSeverity,
+    /// Stable identifier such as `undefined-symbol` or `tag-mismatch`
+    pub code: String,
+    pub message: String,
+    pub position: SourcePosition,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParameterSignature {
+    pub name: String,
+    /// Accepted tags; empty for an untagged parameter, `_` inside `{Float, _}:`
+    pub tags: Vec<String>,
+    pub is_array: bool,
+    pub is_reference: bool,
+    pub has_default: bool,
+    pub is_variadic: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FunctionSignature {
+    pub name: String,
+    pub return_tag: Option<String>,
+    pub parameters: Vec<ParameterSignature>,
+    pub is_native: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GlobalSymbol {
+    pub name: String,
+    /// Value tag; `None` for `#define`s, whose tag is not known
+    pub tag: Option<String>,
+    /// Tag of the array cell an enum item indexes (`Float:pHealth`)
+    pub field_tag: Option<String>,
+}
+
+/// Function signatures and global constants visible to analyzed scripts.
+#[derive(Debug, Clone, Default)]
+pub struct NativeDatabase {
+    functions: HashMap<String, FunctionSignature>,
+    globals: HashMap<String, GlobalSymbol>,
+    loaded_includes: HashSet<String>,
+}
+
+impl NativeDatabase {
+    /// Signatures from the bundled core, float, a_samp and streamer includes.
+    pub fn bundled() -> Self {
+        BUNDLED_DATABASE.clone()
+    }
+
+    pub fn function(&self, name: &str) -> Option<&FunctionSignature> {
+        self.functions.get(name)
+    }
+
+    pub fn global(&self, name: &str) -> Option<&GlobalSymbol> {
+        self.globals.get(name)
+    }
+
+    /// Whether declarations from `#include <name>` are known to the database.
+    pub fn covers_include(&self, name: &str) -> bool {
+        let stem = include_stem(name);
+        COVERED_INCLUDES.contains(&stem.as_str()) || self.loaded_includes.contains(&stem)
+    }
+
+    /// Register every declaration of a parsed script or include.
+    pub fn add_ast(&mut self, ast: &PawnAST) {
+        for func in &ast.functions {
+            // A definition replaces its `forward`, never the other way round
+            if !func.has_body && self.functions.contains_key(&func.name) {
+                continue;
+            }
+            self.functions.insert(
+                func.name.clone(),
+                FunctionSignature {
+                    name: func.name.clone(),
+                    return_tag: func.return_tag.clone(),
+                    parameters: func.parameters.iter().map(parameter_signature).collect(),
+                    is_native: func.modifiers.iter().any(|m| m == "native"),
+                },
+            );
+        }
+        for cb in &ast.callbacks {
+            self.functions.insert(
+                cb.name.clone(),
+                FunctionSignature {
+                    name: cb.name.clone(),
+                    return_tag: None,
+                    parameters: cb.parameters.iter().map(parameter_signature).collect(),
+                    is_native: false,
+                },
+            );
+        }
+        for var in &ast.variables {
+            self.add_global(&var.name, Some(tag_or_untagged(&var.tag)), None);
+        }
+        for node in &ast.enums {
+            if let Some(name) = &node.name {
+                self.add_global(name, Some(tag_or_untagged(&node.tag)), None);
+            }
+            let item_tag = node.tag.clone().or_else(|| node.name.clone());
+            for item in &node.items {
+                self.add_global(
+                    &item.name,
+                    Some(tag_or_untagged(&item_tag)),
+                    item.tag.clone(),
+                );
+            }
+        }
+    }
+
+    /// Preprocess and parse an include, registering its macros and declarations.
+    /// Returns the includes it pulls in itself.
+    pub fn load_source(
+        &mut self,
+        source: &str,
+        path: Option<&Path>,
+        options: &PreprocessorOptions,
+    ) -> Result<Vec<IncludeRecord>, Vec<String>> {
+        let preprocessed = PawnPreprocessor::new(options.clone()).process(source, path);
+        for definition in &preprocessed.macros {
+            self.add_global(&definition.name, None, None);
+        }
+        let ast = PawnParser::new().parse_program(&preprocessed.source)?;
+        self.add_ast(&ast);
+        Ok(preprocessed.includes)
+    }
+
+    /// Load the resolved files of `includes` and everything they include in turn.
+    pub fn load_includes(&mut self, includes: &[IncludeRecord], options: &PreprocessorOptions) {
+        let mut visited: HashSet<PathBuf> = HashSet::new();
+        let mut pending: Vec<IncludeRecord> = includes.to_vec();
+
+        while let Some(include) = pending.pop() {
+            let Some(resolved) = include.resolved_path.as_ref().map(PathBuf::from) else {
+                continue;
+            };
+            if !visited.insert(resolved.clone()) {
+                continue;
+            }
+            let Ok(source) = std::fs::read_to_string(&resolved) else {
+                continue;
+            };
+            self.loaded_includes.insert(include_stem(&include.name));
+            // Includes the grammar cannot parse still contribute their macros
+            if let Ok(nested) = self.load_source(&source, Some(&resolved), options) {
+                pending.extend(nested);
+            }
+        }
+    }
+
+    fn add_global(&mut self, name: &str, tag: Option<String>, field_tag: Option<String>) {
+        self.globals.insert(
+            name.to_string(),
+            GlobalSymbol {
+                name: name.to_string(),
+                tag,
+                field_tag,
+            },
+        );
+    }
+}
+
+fn include_stem(name: &str) -> String {
+    Path::new(name.trim())
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or(name)
+        .to_string()
+}
+
+fn tag_or_untagged(tag: &Option<String>) -> String {
+    tag.clone().unwrap_or_else(|| "_".to_string())
+}
+
+/// Split `Float` or `{Float, _}` into the tags a parameter accepts.
+fn parameter_tags(tag: &Option<String>) -> Vec<String> {
+    match tag {
+        None => Vec::new(),
+        Some(tag) => tag
+            .trim_start_matches('{')
+            .trim_end_matches('}')
+            .split(',')
+            .map(|t| t.trim().to_string())
+            .filter(|t| !t.is_empty())
+            .collect(),
+    }
+}
+
+fn parameter_signature(p: &ParameterNode) -> ParameterSignature {
+    ParameterSignature {
+        name: p.name.clone(),
+        tags: parameter_tags(&p.tag),
+        is_array: !p.dimensions.is_empty(),
+        is_reference: p.is_reference,
+        has_default: p.default_value.is_some(),
+        is_variadic: p.is_variadic,
+    }
+}
+
+/// Tag of an expression as far as the analyzer can tell.
+#[derive(Debug, Clone, PartialEq)]
+enum ValueTag {
+    Untagged,
+    Tagged(String),
+    Unknown,
+}
+
+impl ValueTag {
+    fn from_tag(tag: Option<&str>) -> Self {
+        match tag {
+            None | Some("_") => Self::Untagged,
+            Some(tag) => Self::Tagged(tag.to_string()),
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Self::Untagged => "untagged".to_string(),
+            Self::Tagged(tag) => format!("`{}:`", tag),
+            Self::Unknown => "unknown".to_string(),
+        }
+    }
+}
+
+/// Lowercase tags are weak: they convert to and from untagged values silently.
+fn is_strong_tag(tag: &str) -> bool {
+    tag.chars().next().is_some_and(|c| c.is_ascii_uppercase())
+}
+
+fn is_float(tag: &ValueTag) -> bool {
+    matches!(tag, ValueTag::Tagged(t) if t == "Float")
+}
+
+/// Whether a value tagged `actual` can be stored where `expected` tags are accepted.
+fn tag_accepts(expected: &[String], actual: &ValueTag) -> bool {
+    let accepts_untagged = expected.is_empty() || expected.iter().any(|t| t == "_");
+    match actual {
+        ValueTag::Unknown => true,
+        ValueTag::Untagged => accepts_untagged || !expected.iter().all(|t| is_strong_tag(t)),
+        ValueTag::Tagged(tag) => {
+            expected.contains(tag) || (accepts_untagged && !is_strong_tag(tag))
+        }
+    }
+}
+
+fn describe_tags(tags: &[String]) -> String {
+    match tags {
+        [] => "untagged".to_string(),
+        [tag] => ValueTag::from_tag(Some(tag)).describe(),
+        tags => format!("one of {{{}}}", tags.join(", ")),
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Symbol {
+    tag: ValueTag,
+    field_tag: Option<String>,
+    is_array: bool,
+}
+
+impl Symbol {
+    fn new(tag: ValueTag, is_array: bool) -> Self {
+        Self {
+            tag,
+            field_tag: None,
+            is_array,
+        }
+    }
+}
+
+fn is_constant_true(condition: Option<&UniversalExpression>) -> bool {
+    match condition {
+        None => true,
+        Some(UniversalExpression::Literal {
+            literal_type: LiteralType::Boolean,
+            value,
+        }) => value == "true",
+        Some(UniversalExpression::Literal {
+            literal_type: LiteralType::Integer | LiteralType::Hex | LiteralType::Binary,
+            value,
+        }) => value.chars().any(|c| c.is_ascii_digit() && c != '0'),
+        _ => false,
+    }
+}
+
+/// Whether `statement` contains a `break` leaving the loop it sits in.
+/// Pawn's `break` inside a `switch` exits the enclosing loop.
+fn breaks_loop(statement: &UniversalStatement) -> bool {
+    match &statement.node {
+        UniversalStatementNode::Break => true,
+        UniversalStatementNode::Block { statements } => statements.iter().any(breaks_loop),
+        UniversalStatementNode::If {
+            then_branch,
+            else_branch,
+            ..
+        } => breaks_loop(then_branch) || else_branch.as_deref().is_some_and(breaks_loop),
+        UniversalStatementNode::Switch { cases, .. } => {
+            cases.iter().any(|case| case.body.iter().any(breaks_loop))
+        }
+        _ => false,
+    }
+}
+
+fn is_infinite_loop(statement: &UniversalStatement) -> bool {
+    match &statement.node {
+        UniversalStatementNode::While { condition, body }
+        | UniversalStatementNode::DoWhile { body, condition } => {
+            is_constant_true(Some(condition)) && !breaks_loop(body)
+        }
+        UniversalStatementNode::For {
+            condition, body, ..
+        } => is_constant_true(condition.as_ref()) && !breaks_loop(body),
+        _ => false,
+    }
+}
+
+/// Whether control can never reach the statement following `statement`.
+fn terminates(statement: &UniversalStatement) -> bool {
+    match &statement.node {
+        UniversalStatementNode::Return { .. }
+        | UniversalStatementNode::Break
+        | UniversalStatementNode::Continue => true,
+        UniversalStatementNode::Block { statements } => statements.iter().any(terminates),
+        UniversalStatementNode::If {
+            then_branch,
+            else_branch: Some(else_branch),
+            ..
+        } => terminates(then_branch) && terminates(else_branch),
+        UniversalStatementNode::Switch { cases, .. } => {
+            cases.iter().any(|case| case.values.is_empty())
+                && cases.iter().all(|case| case.body.iter().any(terminates))
+        }
+        _ => is_infinite_loop(statement),
+    }
+}
+
+/// Whether every path through `statements` ends in a `return`.
+fn always_returns(statements: &[UniversalStatement]) -> bool {
+    statements.iter().any(returns)
+}
+
+fn returns(statement: &UniversalStatement) -> bool {
+    match &statement.node {
+        UniversalStatementNode::Return { .. } => true,
+        UniversalStatementNode::Block { statements } => always_returns(statements),
+        UniversalStatementNode::If {
+            then_branch,
+            else_branch: Some(else_branch),
+            ..
+        } => returns(then_branch) && returns(else_branch),
+        UniversalStatementNode::Switch { cases, .. } => {
+            cases.iter().any(|case| case.values.is_empty())
+                && cases.iter().all(|case| always_returns(&case.body))
+        }
+        UniversalStatementNode::DoWhile { body, .. } => {
+            returns(body) || is_infinite_loop(statement)
+        }
+        _ => is_infinite_loop(statement),
+    }
+}
+
+fn contains_value_return(statements: &[UniversalStatement]) -> bool {
+    fn visit(statement: &UniversalStatement) -> bool {
+        match &statement.node {
+            UniversalStatementNode::Return { value } => value.is_some(),
+            UniversalStatementNode::Block { statements } => statements.iter().any(visit),
+            UniversalStatementNode::If {
+                then_branch,
+                else_branch,
+                ..
+            } => visit(then_branch) || else_branch.as_deref().is_some_and(visit),
+            UniversalStatementNode::While { body, .. }
+            | UniversalStatementNode::DoWhile { body, .. }
+            | UniversalStatementNode::For { body, .. } => visit(body),
+            UniversalStatementNode::Switch { cases, .. } => {
+                cases.iter().any(|case| case.body.iter().any(visit))
+            }
+            _ => false,
+        }
+    }
+    statements.iter().any(visit)
+}
+
+fn body_statements(body: &[StatementNode]) -> Vec<UniversalStatement> {
+    body.iter()
+        .map(|s| UniversalStatement {
+            id: format!("stmt_{}_{}", s.line, s.column),
+            node: s.node.clone(),
+            position: SourcePosition {
+                line: s.line,
+                column: s.column,
+            },
+        })
+        .collect()
+}
+
+pub struct PawnAnalyzer {
+    database: NativeDatabase,
+}
+
+impl Default for PawnAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PawnAnalyzer {
+    pub fn new() -> Self {
+        Self::with_database(NativeDatabase::bundled())
+    }
+
+    pub fn with_database(database: NativeDatabase) -> Self {
+        Self { database }
+    }
+
+    pub fn database(&self) -> &NativeDatabase {
+        &self.database
+    }
+
+    /// Analyze a parsed script. Includes the database does not cover may
+    /// declare anything, so unresolved names are then only warnings.
+    pub fn analyze(&self, ast: &PawnAST) -> Vec<Diagnostic> {
+        let incomplete = ast
+            .includes
+            .iter()
+            .any(|include| !self.database.covers_include(&include.name));
+        Checker::new(&self.database, ast, incomplete).run(ast)
+    }
+
+    /// Preprocess, parse and analyze a script, loading the signatures of
+    /// every include that resolves. Syntax errors yield no diagnostics; the
+    /// parser already reports them.
+    pub fn analyze_source(
+        &self,
+        source: &str,
+        path: Option<&Path>,
+        options: &PreprocessorOptions,
+    ) -> Vec<Diagnostic> {
+        let preprocessed = PawnPreprocessor::new(options.clone()).process(source, path);
+        let Ok(mut ast) = PawnParser::new().parse_program(&preprocessed.source) else {
+            return Vec::new();
+        };
+        ast.includes = preprocessed.includes.clone();
+
+        let mut database = self.database.clone();
+        database.load_includes(&preprocessed.includes, options);
+        for definition in &preprocessed.macros {
+            database.add_global(&definition.name, None, None);
+        }
+
+        Self::with_database(database).analyze(&ast)
+    }
+}
+
+struct Checker<'a> {
+    database: &'a NativeDatabase,
+    functions: HashMap<String, FunctionSignature>,
+    globals: HashMap<String, Symbol>,
+    scopes: Vec<HashMap<String, Symbol>>,
+    return_tag: ValueTag,
+    undefined_severity: DiagnosticSeverity,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> Checker<'a> {
+    fn new(database: &'a NativeDatabase, ast: &PawnAST, incomplete: bool) -> Self {
+        let mut script = NativeDatabase::default();
+        script.add_ast(ast);
+
+        Self {
+            database,
+            functions: script.functions,
+            globals: HashMap::new(),
+            scopes: Vec::new(),
+            return_tag: ValueTag::Untagged,
+            undefined_severity: if incomplete {
+                DiagnosticSeverity::Warning
+            } else {
+                DiagnosticSeverity::Error
+            },
+            diagnostics: Vec::new(),
+        }
+    }
+
+    fn run(mut self, ast: &PawnAST) -> Vec<Diagnostic> {
+        self.declare_globals(ast);
+
+        let mut defined: HashSet<&str> = HashSet::new();
+        for func in ast.functions.iter().filter(|f| f.has_body) {
+            let position = SourcePosition {
+                line: func.line,
+                column: func.column,
+            };
+            if !defined.insert(&func.name) {
+                self.report(
+                    DiagnosticSeverity::Error,
+                    "redefinition",
+                    format!("Function `{}` is already defined", func.name),
+                    &position,
+                );
+            }
+            let body = body_statements(&func.body);
+            let return_tag = ValueTag::from_tag(func.return_tag.as_deref());
+            self.function_body(&func.parameters, &body, return_tag);
+
+            let returns_value = contains_value_return(&body)
+                || func.return_tag.as_deref().is_some_and(|t| t != "_");
+            if returns_value && !always_returns(&body) {
+                self.report(
+                    DiagnosticSeverity::Warning,
+                    "missing-return",
+                    format!(
+                        "Function `{}` should return a value on every path",
+                        func.name
+                    ),
+                    &position,
+                );
+            }
+        }
+
+        for cb in &ast.callbacks {
+            let position = SourcePosition {
+                line: cb.line,
+                column: cb.column,
+            };
+            if !defined.insert(&cb.name) {
+                self.report(
+                    DiagnosticSeverity::Error,
+                    "redefinition",
+                    format!("Callback `{}` is already defined", cb.name),
+                    &position,
+                );
+            }
+            let body = body_statements(&cb.body);
+            self.function_body(&cb.parameters, &body, ValueTag::Untagged);
+            if !always_returns(&body) {
+                self.report(
+                    DiagnosticSeverity::Warning,
+                    "missing-return",
+                    format!(
+                        "Callback `{}` does not return a value on every path",
+                        cb.name
+                    ),
+                    &position,
+                );
+            }
+        }
+
+        self.diagnostics
+            .sort_by_key(|d| (d.position.line, d.position.column));
+        self.diagnostics
+    }
+
+    fn declare_globals(&mut self, ast: &PawnAST) {
+        for node in &ast.enums {
+            let position = SourcePosition {
+                line: node.line,
+                column: node.column,
+            };
+            let item_tag = node.tag.as_deref().or(node.name.as_deref());
+            if let Some(name) = &node.name {
+                let symbol = Symbol::new(ValueTag::from_tag(node.tag.as_deref()), false);
+                self.declare_global(name, symbol, &position);
+            }
+            for item in &node.items {
+                let symbol = Symbol {
+                    tag: ValueTag::from_tag(item_tag),
+                    field_tag: item.tag.clone(),
+                    is_array: false,
+                };
+                let position = SourcePosition {
+                    line: item.line,
+                    column: item.column,
+                };
+                self.declare_global(&item.name, symbol, &position);
+            }
+        }
+        for var in &ast.variables {
+            let position = SourcePosition {
+                line: var.line,
+                column: var.column,
+            };
+            let symbol = Symbol::new(
+                ValueTag::from_tag(var.tag.as_deref()),
+                !var.dimensions.is_empty(),
+            );
+            self.declare_global(&var.name, symbol, &position);
+        }
+    }
+
+    fn declare_global(&mut self, name: &str, symbol: Symbol, position: &SourcePosition) {
+        if self.globals.insert(name.to_string(), symbol).is_some() {
+            self.report(
+                DiagnosticSeverity::Error,
+                "redefinition",
+                format!("Symbol `{}` is already defined", name),
+                position,
+            );
+        }
+    }
+
+    fn function_body(
+        &mut self,
+        parameters: &[ParameterNode],
+        body: &[UniversalStatement],
+        return_tag: ValueTag,
+    ) {
+        self.return_tag = return_tag;
+        self.scopes.push(HashMap::new());
+        for p in parameters.iter().filter(|p| !p.is_variadic) {
+            let tag = match parameter_tags(&p.tag).as_slice() {
+                [] => ValueTag::Untagged,
+                [tag] => ValueTag::from_tag(Some(tag)),
+                _ => ValueTag::Unknown,
+            };
+            let position = SourcePosition {
+                line: p.line,
+                column: p.column,
+            };
+            self.declare_local(
+                &p.name,
+                Symbol::new(tag, !p.dimensions.is_empty()),
+                &position,
+            );
+        }
+        self.statements(body);
+        self.scopes.pop();
+    }
+
+    fn declare_local(&mut self, name: &str, symbol: Symbol, position: &SourcePosition) {
+        let shadows = self.scopes[..self.scopes.len() - 1]
+            .iter()
+            .any(|scope| scope.contains_key(name));
+        let scope = self.scopes.last_mut().expect("local scope");
+        if scope.insert(name.to_string(), symbol).is_some() {
+            self.report(
+                DiagnosticSeverity::Error,
+                "redefinition",
+                format!("Symbol `{}` is already defined in this scope", name),
+                position,
+            );
+        } else if shadows {
+            self.report(
+                DiagnosticSeverity::Warning,
+                "shadowed-symbol",
+                format!(
+                    "Local variable `{}` shadows a variable of an outer scope",
+                    name
+                ),
+                position,
+            );
+        }
+    }
+
+    fn report(
+        &mut self,
+        severity: DiagnosticSeverity,
+        code: &str,
+        message: String,
+        position: &SourcePosition,
+    ) {
+        self.diagnostics.push(Diagnostic {
+            severity,
+            code: code.to_string(),
+            message,
+            position: position.clone(),
+        });
+    }
+
+    fn check_tag(
+        &mut self,
+        expected: &[String],
+        actual: &ValueTag,
+        context: &str,
+        position: &SourcePosition,
+    ) {
+        if !tag_accepts(expected, actual) {
+            self.report(
+                DiagnosticSeverity::Warning,
+                "tag-mismatch",
+                format!(
+                    "Tag mismatch in {}: expected {}, found {}",
+                    context,
+                    describe_tags(expected),
+                    actual.describe()
+                ),
+                position,
+            );
+        }
+    }
+
+    fn statements(&mut self, statements: &[UniversalStatement]) {
+        let mut terminated = false;
+        let mut reported = false;
+        for statement in statements {
+            if terminated && !reported {
+                self.report(
+                    DiagnosticSeverity::Warning,
+                    "unreachable-code",
+                    "Unreachable code".to_string(),
+                    &statement.position,
+                );
+                reported = true;
+            }
+            self.statement(statement);
+            terminated = terminated || terminates(statement);
+        }
+    }
+
+    fn scoped_statement(&mut self, statement: &UniversalStatement) {
+        self.scopes.push(HashMap::new());
+        self.statement(statement);
+        self.scopes.pop();
+    }
+
+    fn statement(&mut self, statement: &UniversalStatement) {
+        let position = &statement.position;
+        match &statement.node {
+            UniversalStatementNode::Expression { expression } => {
+                self.expression(expression, position);
+            }
+            UniversalStatementNode::Variable { declarations, .. } => {
+                self.declarations(declarations, position);
+            }
+            UniversalStatementNode::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.expression(condition, position);
+                self.scoped_statement(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.scoped_statement(else_branch);
+                }
+            }
+            UniversalStatementNode::While { condition, body }
+            | UniversalStatementNode::DoWhile { body, condition } => {
+                self.expression(condition, position);
+                self.scoped_statement(body);
+            }
+            UniversalStatementNode::For {
+                init,
+                condition,
+                update,
+                body,
+            } => {
+                self.scopes.push(HashMap::new());
+                match init {
+                    Some(UniversalForInit::Declaration { declarations }) => {
+                        self.declarations(declarations, position);
+                    }
+                    Some(UniversalForInit::Expressions { expressions }) => {
+                        for expression in expressions {
+                            self.expression(expression, position);
+                        }
+                    }
+                    None => {}
+                }
+                if let Some(condition) = condition {
+                    self.expression(condition, position);
+                }
+                for expression in update {
+                    self.expression(expression, position);
+                }
+                self.scoped_statement(body);
+                self.scopes.pop();
+            }
+            UniversalStatementNode::Switch { subject, cases } => {
+                self.expression(subject, position);
+                for case in cases {
+                    for value in &case.values {
+                        self.expression(&value.value, position);
+                        if let Some(range_end) = &value.range_end {
+                            self.expression(range_end, position);
+                        }
+                    }
+                    self.scopes.push(HashMap::new());
+                    self.statements(&case.body);
+                    self.scopes.pop();
+                }
+            }
+            UniversalStatementNode::Return { value } => {
+                if let Some(value) = value {
+                    let tag = self.expression(value, position);
+                    let expected = match &self.return_tag {
+                        ValueTag::Tagged(tag) => vec![tag.clone()],
+                        _ => Vec::new(),
+                    };
+                    self.check_tag(&expected, &tag, "return value", position);
+                }
+            }
+            UniversalStatementNode::Block { statements } => {
+                self.scopes.push(HashMap::new());
+                self.statements(statements);
+                self.scopes.pop();
+            }
+            UniversalStatementNode::Break
+            | UniversalStatementNode::Continue
+            | UniversalStatementNode::Empty
+            | UniversalStatementNode::Directive { .. } => {}
+        }
+    }
+
+    fn declarations(&mut self, declarations: &[UniversalDeclarator], position: &SourcePosition) {
+        for declarator in declarations {
+            if let Some(initializer) = &declarator.initializer {
+                let tag = self.expression(initializer, position);
+                if !declarator.var_type.is_array {
+                    let expected: Vec<String> = declarator.var_type.tag.iter().cloned().collect();
+                    let context = format!("initializer of `{}`", declarator.name);
+                    self.check_tag(&expected, &tag, &context, position);
+                }
+            }
+            let tag = ValueTag::from_tag(declarator.var_type.tag.as_deref());
+            let symbol = Symbol::new(tag, declarator.var_type.is_array);
+            self.declare_local(&declarator.name, symbol, position);
+        }
+    }
+
+    fn lookup(&self, name: &str) -> Option<Symbol> {
+        if let Some(symbol) = self.scopes.iter().rev().find_map(|scope| scope.get(name)) {
+            return Some(symbol.clone());
+        }
+        if let Some(symbol) = self.globals.get(name) {
+            return Some(symbol.clone());
+        }
+        if self.functions.contains_key(name) || self.database.function(name).is_some() {
+            return Some(Symbol::new(ValueTag::Unknown, false));
+        }
+        match name {
+            "true" | "false" => {
+                return Some(Symbol::new(ValueTag::Tagged("bool".to_string()), false))
+            }
+            _ => {}
+        }
+        self.database.global(name).map(|global| Symbol {
+            tag: match &global.tag {
+                Some(tag) => ValueTag::from_tag(Some(tag)),
+                None => ValueTag::Unknown,
+            },
+            field_tag: global.field_tag.clone(),
+            is_array: false,
+        })
+    }
+
+    fn identifier(&mut self, name: &str, position: &SourcePosition) -> Option<Symbol> {
+        let symbol = self.lookup(name);
+        if symbol.is_none() {
+            let severity = self.undefined_severity;
+            self.report(
+                severity,
+                "undefined-symbol",
+                format!("Undefined symbol `{}`", name),
+                position,
+            );
+        }
+        symbol
+    }
+
+    fn expression(
+        &mut self,
+        expression: &UniversalExpression,
+        position: &SourcePosition,
+    ) -> ValueTag {
+        match expression {
+            UniversalExpression::Literal { literal_type, .. } => match literal_type {
+                LiteralType::Float => ValueTag::Tagged("Float".to_string()),
+                LiteralType::Boolean => ValueTag::Tagged("bool".to_string()),
+                _ => ValueTag::Untagged,
+            },
+            UniversalExpression::Identifier { name } => self
+                .identifier(name, position)
+                .map(|symbol| symbol.tag)
+                .unwrap_or(ValueTag::Unknown),
+            UniversalExpression::Assignment {
+                operator,
+                target,
+                value,
+            } => {
+                let target_tag = self.expression(target, position);
+                let value_tag = self.expression(value, position);
+                if *operator == AssignmentOperator::Assign && !self.is_array_target(target) {
+                    let expected = match &target_tag {
+                        ValueTag::Tagged(tag) => Some(vec![tag.clone()]),
+                        ValueTag::Untagged => Some(Vec::new()),
+                        ValueTag::Unknown => None,
+                    };
+                    if let Some(expected) = expected {
+                        self.check_tag(&expected, &value_tag, "assignment", position);
+                    }
+                }
+                target_tag
+            }
+            UniversalExpression::Conditional {
+                condition,
+                when_true,
+                when_false,
+            } => {
+                self.expression(condition, position);
+                let left = self.expression(when_true, position);
+                let right = self.expression(when_false, position);
+                match (left, right) {
+                    (left, right) if left == right => left,
+                    (ValueTag::Unknown, other) | (other, ValueTag::Unknown) => other,
+                    _ => ValueTag::Unknown,
+                }
+            }
+            UniversalExpression::Binary {
+                operator,
+                left,
+                right,
+            } => {
+                let left = self.expression(left, position);
+                let right = self.expression(right, position);
+                binary_tag(*operator, left, right)
+            }
+            UniversalExpression::Unary { operator, operand } => {
+                let tag = self.expression(operand, position);
+                match operator {
+                    UnaryOperator::Not => ValueTag::Tagged("bool".to_string()),
+                    _ => tag,
+                }
+            }
+            UniversalExpression::Postfix { operand, .. } => self.expression(operand, position),
+            UniversalExpression::TagOverride { tag, operand } => {
+                self.expression(operand, position);
+                ValueTag::from_tag(Some(tag))
+            }
+            UniversalExpression::Sizeof { operand } => {
+                let name: String = operand
+                    .trim()
+                    .chars()
+                    .take_while(|c| c.is_ascii_alphanumeric() || *c == '_' || *c == '@')
+                    .collect();
+                if !name.is_empty() {
+                    self.identifier(&name, position);
+                }
+                ValueTag::Untagged
+            }
+            UniversalExpression::Tagof { .. } => ValueTag::Untagged,
+            UniversalExpression::Call { callee, arguments } => {
+                self.call(callee, arguments, position)
+            }
+            UniversalExpression::Index { array, index } => {
+                let array_tag = self.expression(array, position);
+                self.expression(index, position);
+                // `gPlayerData[i][pHealth]` takes the tag of the enum field
+                let field_tag = match index.as_ref() {
+                    UniversalExpression::Identifier { name } => {
+                        self.lookup(name).and_then(|symbol| symbol.field_tag)
+                    }
+                    _ => None,
+                };
+                match field_tag {
+                    Some(tag) => ValueTag::from_tag(Some(&tag)),
+                    None => array_tag,
+                }
+            }
+            UniversalExpression::ArrayInitializer { elements, .. } => {
+                for element in elements {
+                    self.expression(element, position);
+                }
+                ValueTag::Unknown
+            }
+        }
+    }
+
+    /// Whole-array assignments (`string = "text"`) are not tag checked.
+    fn is_array_target(&self, target: &UniversalExpression) -> bool {
+        match target {
+            UniversalExpression::Identifier { name } => {
+                self.lookup(name).is_some_and(|symbol| symbol.is_array)
+            }
+            _ => false,
+        }
+    }
+
+    fn call(
+        &mut self,
+        callee: &UniversalExpression,
+        arguments: &[UniversalArgument],
+        position: &SourcePosition,
+    ) -> ValueTag {
+        let name = match callee {
+            UniversalExpression::Identifier { name } => name,
+            other => {
+                self.expression(other, position);
+                for argument in arguments {
+                    self.expression(&argument.value, position);
+                }
+                return ValueTag::Unknown;
+            }
+        };
+
+        let signature = self
+            .functions
+            .get(name)
+            .or_else(|| self.database.function(name))
+            .cloned();
+        let Some(signature) = signature else {
+            // Function-like macros from includes that were not expanded
+            if self.database.global(name).is_none() {
+                let severity = self.undefined_severity;
+                self.report(
+                    severity,
+                    "undefined-function",
+                    format!("Undefined function `{}`", name),
+                    position,
+                );
+            }
+            for argument in arguments {
+                self.expression(&argument.value, position);
+            }
+            return ValueTag::Unknown;
+        };
+
+        self.check_arguments(&signature, arguments, position);
+        ValueTag::from_tag(signature.return_tag.as_deref())
+    }
+
+    fn check_arguments(
+        &mut self,
+        signature: &FunctionSignature,
+        arguments: &[UniversalArgument],
+        position: &SourcePosition,
+    ) {
+        let parameters = &signature.parameters;
+        let fixed = parameters
+            .iter()
+            .position(|p| p.is_variadic)
+            .unwrap_or(parameters.len());
+        let variadic = parameters.get(fixed);
+        let mut supplied = vec![false; fixed];
+        let mut positional = 0;
+        let mut reported_count = false;
+
+        for argument in arguments {
+            let parameter = match &argument.name {
+                Some(name) => match parameters[..fixed].iter().position(|p| &p.name == name) {
+                    Some(index) => {
+                        supplied[index] = true;
+                        Some(&parameters[index])
+                    }
+                    None => {
+                        self.report(
+                            DiagnosticSeverity::Error,
+                            "unknown-argument",
+                            format!("`{}` has no parameter named `{}`", signature.name, name),
+                            position,
+                        );
+                        None
+                    }
+                },
+                None => {
+                    let index = positional;
+                    positional += 1;
+                    if index < fixed {
+                        supplied[index] = true;
+                        Some(&parameters[index])
+                    } else if variadic.is_some() {
+                        variadic
+                    } else {
+                        if !reported_count {
+                            self.report(
+                                DiagnosticSeverity::Error,
+                                "argument-count",
+                                format!(
+                                    "`{}` takes at most {} argument(s), but {} were given",
+                                    signature.name,
+                                    fixed,
+                                    arguments.len()
+                                ),
+                                position,
+                            );
+                            reported_count = true;
+                        }
+                        None
+                    }
+                }
+            };
+
+            // `_` passes the parameter's default value
+            if matches!(&argument.value, UniversalExpression::Identifier { name } if name == "_") {
+                continue;
+            }
+            let tag = self.expression(&argument.value, position);
+            if let Some(parameter) = parameter {
+                let untagged_variadic = parameter.is_variadic && parameter.tags.is_empty();
+                if !parameter.is_array && !untagged_variadic {
+                    let context = if parameter.is_variadic {
+                        format!("variadic argument of `{}`", signature.name)
+                    } else {
+                        format!("argument `{}` of `{}`", parameter.name, signature.name)
+                    };
+                    self.check_tag(&parameter.tags, &tag, &context, position);
+                }
+            }
+        }
+
+        let missing: Vec<String> = parameters[..fixed]
+            .iter()
+            .zip(&supplied)
+            .filter(|(p, supplied)| !**supplied && !p.has_default)
+            .map(|(p, _)| format!("`{}`", p.name))
+            .collect();
+        if !missing.is_empty() {
+            self.report(
+                DiagnosticSeverity::Error,
+                "argument-count",
+                format!(
+                    "Call to `{}` is missing argument(s) {}",
+                    signature.name,
+                    missing.join(", ")
+                ),
+                position,
+            );
+        }
+    }
+}
+
+/// Result tag of a binary operator. float.inc overloads arithmetic and
+/// comparisons between `Float:` and untagged operands.
+fn binary_tag(operator: BinaryOperator, left: ValueTag, right: ValueTag) -> ValueTag {
+    match operator {
+        BinaryOperator::LogicalOr
+        | BinaryOperator::LogicalAnd
+        | BinaryOperator::Equal
+        | BinaryOperator::NotEqual
+        | BinaryOperator::Less
+        | BinaryOperator::LessEqual
+        | BinaryOperator::Greater
+        | BinaryOperator::GreaterEqual => ValueTag::Tagged("bool".to_string()),
+        BinaryOperator::ShiftLeft
+        | BinaryOperator::ShiftRight
+        | BinaryOperator::LogicalShiftRight => left,
+        _ if left == right => left,
+        BinaryOperator::Add
+        | BinaryOperator::Subtract
+        | BinaryOperator::Multiply
+        | BinaryOperator::Divide
+        | BinaryOperator::Modulo
+            if (is_float(&left) && right == ValueTag::Untagged)
+                || (left == ValueTag::Untagged && is_float(&right)) =>
+        {
+            ValueTag::Tagged("Float".to_string())
+        }
+        _ => ValueTag::Unknown,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn corpus_dir() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("src/blueprint/corpus")
+    }
+
+    fn analyze(source: &str) -> Vec<Diagnostic> {
+        PawnAnalyzer::new().analyze_source(
+            &format!("#include <a_samp>\n{}", source),
+            None,
+            &PreprocessorOptions::default(),
+        )
+    }
+
+    fn codes(diagnostics: &[Diagnostic]) -> Vec<&str> {
+        diagnostics.iter().map(|d| d.code.as_str()).collect()
+    }
+
+    #[test]
+    fn test_bundled_includes_parse() {
+        for (name, source) in BUNDLED_INCLUDES {
+            let mut database = NativeDatabase::default();
+            assert!(
+                database
+                    .load_source(source, None, &PreprocessorOptions::default())
+                    .is_ok(),
+                "{} failed to parse",
+                name
+            );
+        }
+
+        let database = NativeDatabase::bundled();
+        let create = database.function("CreateDynamicObject").unwrap();
+        assert_eq!(create.parameters.len(), 14);
+        assert_eq!(create.parameters[1].tags, vec!["Float"]);
+        assert!(create.parameters[7].has_default);
+        assert_eq!(
+            database
+                .function("TextDrawCreate")
+                .unwrap()
+                .return_tag
+                .as_deref(),
+            Some("Text")
+        );
+        assert!(database.global("MAX_PLAYERS").is_some());
+        assert!(database.covers_include("a_samp"));
+        assert!(!database.covers_include("sscanf2"));
+    }
+
+    #[test]
+    fn test_corpus_gamemode_is_clean() {
+        let path = corpus_dir().join("gamemode.pwn");
+        let source = std::fs::read_to_string(&path).unwrap();
+        let diagnostics = PawnAnalyzer::new().analyze_source(
+            &source,
+            Some(&path),
+            &PreprocessorOptions {
+                include_paths: vec![corpus_dir().join("include")],
+                predefined: Vec::new(),
+            },
+        );
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    }
+
+    #[test]
+    fn test_corpus_expressions_reports_undefined_and_named_arguments() {
+        let path = corpus_dir().join("expressions.pwn");
+        let source = std::fs::read_to_string(&path).unwrap();
+        let diagnostics = PawnAnalyzer::new().analyze_source(
+            &source,
+            Some(&path),
+            &PreprocessorOptions::default(),
+        );
+        assert_eq!(
+            codes(&diagnostics),
+            vec!["undefined-function", "unknown-argument"]
+        );
+        assert_eq!(diagnostics[0].severity, DiagnosticSeverity::Error);
+        assert_eq!(diagnostics[0].position.line, 20);
+        assert!(diagnostics[1].message.contains("playerid"));
+    }
+
+    #[test]
+    fn test_undefined_variables_and_scopes() {
+        let diagnostics =
+            analyze("main()\n{\n\t{\n\t\tnew inner = 1;\n\t}\n\tprint(inner);\n\tnew a, a;\n}\n");
+        assert_eq!(
+            codes(&diagnostics),
+            vec!["undefined-symbol", "redefinition"]
+        );
+        assert_eq!(diagnostics[0].position.line, 7);
+        assert!(diagnostics[0].message.contains("`inner`"));
+
+        let diagnostics = analyze("main()\n{\n\tnew x;\n\tif (x)\n\t{\n\t\tnew x = 2;\n\t}\n}\n");
+        assert_eq!(codes(&diagnostics), vec!["shadowed-symbol"]);
+    }
+
+    #[test]
+    fn test_native_argument_counts() {
+        let diagnostics = analyze(
+            "main()\n{\n\tSendClientMessage(0, -1);\n\tKick(0, 1);\n\tCreateDynamicObject(1337, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0);\n}\n",
+        );
+        assert_eq!(
+            codes(&diagnostics),
+            vec!["argument-count", "argument-count"]
+        );
+        assert!(diagnostics[0].message.contains("`message`"));
+        assert_eq!(diagnostics[0].position.line, 4);
+        assert_eq!(diagnostics[1].position.line, 5);
+    }
+
+    #[test]
+    fn test_float_tag_mismatches() {
+        let diagnostics = analyze(
+            "main()\n{\n\tnew Float:x = 1;\n\tnew y = 2.5;\n\tSetPlayerPos(0, 1, 2.0, 3.0);\n\tnew Float:z = x * 2 + 1.0;\n\tprintf(\"%f\", z);\n}\n",
+        );
+        assert_eq!(
+            codes(&diagnostics),
+            vec!["tag-mismatch", "tag-mismatch", "tag-mismatch"]
+        );
+        assert!(diagnostics[0]
+            .message
+            .contains("expected `Float:`, found untagged"));
+        assert!(diagnostics[1]
+            .message
+            .contains("expected untagged, found `Float:`"));
+        assert!(diagnostics[2]
+            .message
+            .contains("argument `x` of `SetPlayerPos`"));
+    }
+
+    #[test]
+    fn test_unreachable_code() {
+        let diagnostics = analyze(
+            "stock Test(a)\n{\n\tif (a)\n\t{\n\t\treturn 1;\n\t\tprint(\"a\");\n\t\tprint(\"b\");\n\t}\n\telse return 2;\n\treturn 3;\n}\n",
+        );
+        assert_eq!(
+            codes(&diagnostics),
+            vec!["unreachable-code", "unreachable-code"]
+        );
+        assert_eq!(diagnostics[0].position.line, 7);
+        assert_eq!(diagnostics[1].position.line, 11);
+    }
+
+    #[test]
+    fn test_missing_callback_returns() {
+        let diagnostics = analyze(
+            "public OnPlayerSpawn(playerid)\n{\n\tif (playerid) return 1;\n}\n\npublic OnPlayerDeath(playerid, killerid, reason)\n{\n\tswitch (reason)\n\t{\n\t\tcase 0: return 1;\n\t\tdefault: return 0;\n\t}\n}\n\nstock Float:Half(Float:value)\n{\n\tif (value > 0.0) return value / 2;\n}\n",
+        );
+        assert_eq!(
+            codes(&diagnostics),
+            vec!["missing-return", "missing-return"]
+        );
+        assert!(diagnostics[0].message.contains("OnPlayerSpawn"));
+        assert!(diagnostics[1].message.contains("Half"));
+    }
+
+    #[test]
+    fn test_unresolved_includes_downgrade_undefined_symbols() {
+        let diagnostics = PawnAnalyzer::new().analyze_source(
+            "#include <sscanf2>\nmain()\n{\n\tsscanf(\"1\", \"i\", 0);\n}\n",
+            None,
+            &PreprocessorOptions::default(),
+        );
+        assert_eq!(codes(&diagnostics), vec!["undefined-function"]);
+        assert_eq!(diagnostics[0].severity, DiagnosticSeverity::Warning);
+    }
+
+    #[test]
+    fn test_diagnostics_serialize_for_frontend() {
+        let diagnostic = Diagnostic {
+            severity: DiagnosticSeverity::Warning,
+            code: "unreachable-code".to_string(),
+            message: "Unreachable code".to_string(),
+            position: SourcePosition { line: 3, column: 2 },
+        };
+        let json = serde_json::to_value(&diagnostic).unwrap();
+        assert_eq!(json["severity"], "warning");
+        assert_eq!(json["position"]["line"], 3);
+    }
+}
@@ -1,13 +1,27 @@
 /**
- * Pawn code generator from AST
+ * Pawn code generator from the universal AST
+ *
+ * Trees that keep their directives are written in source order, with the
+ * directive lines and macro uses as they were parsed. Other trees, e.g. ones
+ * parsed from preprocessed source or built in the blueprint editor, are
+ * written as includes, enums, variables and then functions.
  */
 use serde::{Deserialize, Serialize};
 
+use crate::blueprint::ast_bridge::{
+    ASTBridge, BinaryOperator, LiteralType, SourcePosition, UnaryOperator, UniversalAST,
+    UniversalCase, UniversalCaseValue, UniversalDeclarator, UniversalDirective, UniversalEnum,
+    UniversalExpression, UniversalForInit, UniversalFunction, UniversalParameter,
+    UniversalStatement, UniversalStatementNode, UniversalType, UniversalVariable,
+};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct GenerationOptions {
+    #[serde(alias = "indentSize")]
     pub indent_size: usize,
+    #[serde(alias = "useTabs")]
     pub use_tabs: bool,
-    pub preserve_comments: bool,
 }
 
 impl Default for GenerationOptions {
@@ -15,11 +29,45 @@ impl Default for GenerationOptions {
         Self {
             indent_size: 4,
             use_tabs: false,
-            preserve_comments: true,
         }
     }
 }
 
+/// Top-level entry of a tree written in source order.
+enum TopLevelItem<'a> {
+    Directive(&'a UniversalDirective),
+    Enum(&'a UniversalEnum),
+    Variable(&'a UniversalVariable),
+    Function(&'a UniversalFunction),
+}
+
+impl TopLevelItem<'_> {
+    /// Whether `next` follows this item without a blank line in between.
+    fn keeps_together(&self, next: &Self) -> bool {
+        match (self, next) {
+            // Conditional directives hug the code they wrap
+            (Self::Directive(d), _) if matches!(d.kind.as_str(), "if" | "elseif" | "else") => true,
+            (_, Self::Directive(d)) if matches!(d.kind.as_str(), "elseif" | "else" | "endif") => {
+                true
+            }
+            // Includes are set apart from the directives that follow them
+            (Self::Directive(a), Self::Directive(b)) => {
+                a.kind.ends_with("include") == b.kind.ends_with("include")
+            }
+            (Self::Variable(_), Self::Variable(_)) => true,
+            (Self::Function(a), Self::Function(b)) => a.is_prototype && b.is_prototype,
+            _ => false,
+        }
+    }
+}
+
+/// Precedence of the operand slot that accepts any expression.
+const ANY_EXPRESSION: u8 = 1;
+/// Precedence of the operand slot of unary operators and tag overrides.
+const UNARY_OPERAND: u8 = 13;
+/// Precedence of callees, indexed arrays and postfix operands.
+const POSTFIX_OPERAND: u8 = 14;
+
 pub struct PawnGenerator {
     options: GenerationOptions,
 }
@@ -35,114 +83,198 @@ impl PawnGenerator {
         Self { options }
     }
 
-    pub fn generate(&self, ast: &serde_json::Value) -> Result<String, String> {
+    pub fn generate(&self, ast: &UniversalAST) -> Result<String, String> {
+        if let Some(written) = &ast.written {
+            return self.generate(written);
+        }
+        if !ast.directives.is_empty() {
+            return self.generate_in_source_order(ast);
+        }
+
         let mut output = String::new();
 
         // Generate includes
-        if let Some(includes) = ast.get("includes").and_then(|v| v.as_array()) {
-            for include in includes {
-                if let Some(include_str) = include.as_str() {
-                    output.push_str(&format!("#include <{}>\n", include_str));
-                }
-            }
-            if !includes.is_empty() {
-                output.push('\n');
+        for include in &ast.includes {
+            if include.system {
+                output.push_str(&format!("#include <{}>\n", include.name));
+            } else {
+                output.push_str(&format!("#include \"{}\"\n", include.name));
             }
         }
+        if !ast.includes.is_empty() {
+            output.push('\n');
+        }
 
-        // Generate functions
-        if let Some(functions) = ast.get("functions").and_then(|v| v.as_array()) {
-            for func in functions {
-                output.push_str(&self.generate_function(func)?);
-                output.push_str("\n\n");
-            }
+        // Generate enums
+        for enumeration in &ast.enums {
+            output.push_str(&self.generate_enum(enumeration)?);
+            output.push('\n');
         }
 
-        // Generate callbacks
-        if let Some(callbacks) = ast.get("callbacks").and_then(|v| v.as_array()) {
-            for callback in callbacks {
-                output.push_str(&self.generate_callback(callback)?);
-                output.push_str("\n\n");
-            }
+        // Generate global variables
+        for variable in &ast.variables {
+            output.push_str(&self.generate_global_variable(variable)?);
+        }
+        if !ast.variables.is_empty() {
+            output.push('\n');
+        }
+
+        // Generate functions and callbacks
+        for func in &ast.functions {
+            output.push_str(&self.generate_function(func)?);
+            output.push_str("\n\n");
         }
 
         Ok(output)
     }
 
-    fn generate_function(&self, func: &serde_json::Value) -> Result<String, String> {
-        let mut output = String::new();
+    /// Interleave declarations with the directive lines by position, so each
+    /// `#if` block wraps the same declarations as in the parsed file.
+    fn generate_in_source_order(&self, ast: &UniversalAST) -> Result<String, String> {
+        let mut items: Vec<(&SourcePosition, TopLevelItem)> = ast
+            .directives
+            .iter()
+            .map(|d| (&d.position, TopLevelItem::Directive(d)))
+            .chain(
+                ast.enums
+                    .iter()
+                    .map(|e| (&e.position, TopLevelItem::Enum(e))),
+            )
+            .chain(
+                ast.variables
+                    .iter()
+                    .map(|v| (&v.position, TopLevelItem::Variable(v))),
+            )
+            .chain(
+                ast.functions
+                    .iter()
+                    .map(|f| (&f.position, TopLevelItem::Function(f))),
+            )
+            .collect();
+        items.sort_by_key(|(position, _)| (position.line, position.column));
 
-        // Modifiers
-        if let Some(modifiers) = func.get("modifiers").and_then(|v| v.as_array()) {
-            for modifier in modifiers {
-                if let Some(mod_str) = modifier.as_str() {
-                    output.push_str(mod_str);
-                    output.push(' ');
+        let mut output = String::new();
+        let mut previous: Option<&TopLevelItem> = None;
+        for (_, item) in &items {
+            if previous.is_some_and(|previous| !previous.keeps_together(item)) {
+                output.push('\n');
+            }
+            match item {
+                TopLevelItem::Directive(directive) => {
+                    output.push_str(&directive.content);
+                    output.push('\n');
+                }
+                TopLevelItem::Enum(enumeration) => {
+                    output.push_str(&self.generate_enum(enumeration)?)
+                }
+                TopLevelItem::Variable(variable) => {
+                    output.push_str(&self.generate_global_variable(variable)?)
+                }
+                TopLevelItem::Function(func) => {
+                    output.push_str(&self.generate_function(func)?);
+                    output.push('\n');
                 }
             }
+            previous = Some(item);
         }
 
-        // Return type
-        if let Some(return_type) = func.get("return_type").and_then(|v| v.as_str()) {
-            output.push_str(return_type);
+        Ok(output)
+    }
+
+    fn generate_enum(&self, enumeration: &UniversalEnum) -> Result<String, String> {
+        let mut output = String::from("enum");
+
+        if let Some(name) = &enumeration.name {
             output.push(' ');
+            output.push_str(&tag_prefix(&enumeration.tag));
+            output.push_str(&identifier(name)?);
         }
-
-        // Function name
-        if let Some(name) = func.get("name").and_then(|v| v.as_str()) {
-            output.push_str(name);
+        if let Some(increment) = &enumeration.increment {
+            output.push_str(&format!(" ({})", increment));
         }
-
-        // Parameters
-        output.push('(');
-        if let Some(parameters) = func.get("parameters").and_then(|v| v.as_array()) {
-            let param_strs: Vec<String> = parameters
-                .iter()
-                .filter_map(|p| self.generate_parameter(p).ok())
-                .collect();
-            output.push_str(&param_strs.join(", "));
+        output.push_str(" {\n");
+
+        let indent = self.get_indent(1);
+        let items = enumeration
+            .items
+            .iter()
+            .map(|item| {
+                let mut line = format!(
+                    "{}{}{}{}",
+                    indent,
+                    tag_prefix(&item.tag),
+                    identifier(&item.name)?,
+                    dimension_suffix(&item.dimensions)
+                );
+                if let Some(value) = &item.value {
+                    line.push_str(&format!(" = {}", self.generate_expression(value)?));
+                }
+                Ok(line)
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        output.push_str(&items.join(",\n"));
+        if !items.is_empty() {
+            output.push('\n');
         }
-        output.push(')');
+        output.push_str("}\n");
 
-        // Body
-        if let Some(body) = func.get("body").and_then(|v| v.as_array()) {
-            output.push_str(" {\n");
-            for stmt in body {
-                output.push_str(&self.generate_statement(stmt, 1)?);
-            }
-            output.push('}');
+        Ok(output)
+    }
+
+    fn generate_global_variable(&self, variable: &UniversalVariable) -> Result<String, String> {
+        let storage = if variable.storage.is_empty() {
+            "new".to_string()
         } else {
-            output.push(';');
+            variable.storage.join(" ")
+        };
+
+        let mut output = format!(
+            "{} {}{}{}",
+            storage,
+            tag_prefix(&variable.var_type.tag),
+            identifier(&variable.name)?,
+            type_dimensions(&variable.var_type)
+        );
+        if let Some(initial_value) = &variable.initial_value {
+            output.push_str(&format!(" = {}", self.generate_expression(initial_value)?));
         }
+        output.push_str(";\n");
 
         Ok(output)
     }
 
-    fn generate_callback(&self, callback: &serde_json::Value) -> Result<String, String> {
+    fn generate_function(&self, func: &UniversalFunction) -> Result<String, String> {
         let mut output = String::new();
 
-        output.push_str("public ");
-
-        // Callback name
-        if let Some(name) = callback.get("name").and_then(|v| v.as_str()) {
-            output.push_str(name);
+        // Modifiers; prototypes need at least one to parse as declarations
+        if func.is_prototype && func.modifiers.is_empty() {
+            output.push_str("forward ");
+        }
+        for modifier in &func.modifiers {
+            output.push_str(modifier);
+            output.push(' ');
         }
 
+        // Return tag and name
+        output.push_str(&tag_prefix(&func.return_type.tag));
+        output.push_str(&identifier(&func.name)?);
+
         // Parameters
+        let parameters = func
+            .parameters
+            .iter()
+            .map(|p| self.generate_parameter(p))
+            .collect::<Result<Vec<_>, String>>()?;
         output.push('(');
-        if let Some(parameters) = callback.get("parameters").and_then(|v| v.as_array()) {
-            let param_strs: Vec<String> = parameters
-                .iter()
-                .filter_map(|p| self.generate_parameter(p).ok())
-                .collect();
-            output.push_str(&param_strs.join(", "));
-        }
+        output.push_str(&parameters.join(", "));
         output.push(')');
 
         // Body
-        if let Some(body) = callback.get("body").and_then(|v| v.as_array()) {
+        if func.is_prototype {
+            output.push(';');
+        } else {
             output.push_str(" {\n");
-            for stmt in body {
+            for stmt in &func.body {
                 output.push_str(&self.generate_statement(stmt, 1)?);
             }
             output.push('}');
@@ -151,24 +283,28 @@ impl PawnGenerator {
         Ok(output)
     }
 
-    fn generate_parameter(&self, param: &serde_json::Value) -> Result<String, String> {
+    fn generate_parameter(&self, param: &UniversalParameter) -> Result<String, String> {
         let mut output = String::new();
 
-        // Type
-        if let Some(param_type) = param.get("param_type").and_then(|v| v.as_str()) {
-            output.push_str(param_type);
-            output.push(' ');
+        if param.is_const {
+            output.push_str("const ");
         }
+        if param.is_reference {
+            output.push('&');
+        }
+        output.push_str(&tag_prefix(&param.param_type.tag));
 
-        // Name
-        if let Some(name) = param.get("name").and_then(|v| v.as_str()) {
-            output.push_str(name);
+        if param.is_variadic {
+            output.push_str("...");
+            return Ok(output);
         }
 
-        // Default value
-        if let Some(default) = param.get("default_value").and_then(|v| v.as_str()) {
+        output.push_str(&identifier(&param.name)?);
+        output.push_str(&type_dimensions(&param.param_type));
+
+        if let Some(default) = &param.default_value {
             output.push_str(" = ");
-            output.push_str(default);
+            output.push_str(&self.generate_expression(default)?);
         }
 
         Ok(output)
@@ -182,396 +318,511 @@ impl PawnGenerator {
         }
     }
 
-    fn generate_expression(&self, expr: &serde_json::Value) -> Result<String, String> {
-        // Check if it's a structured expression or raw string
-        if let Some(kind) = expr.get("kind").and_then(|v| v.as_str()) {
-            match kind {
-                "literal" => {
-                    if let Some(value) = expr.get("value") {
-                        if let Some(str_val) = value.as_str() {
-                            return Ok(format!("\"{}\"", str_val));
-                        }
-                        if let Some(num_val) = value.as_f64() {
-                            return Ok(num_val.to_string());
-                        }
-                        if let Some(bool_val) = value.as_bool() {
-                            return Ok(if bool_val { "true" } else { "false" }.to_string());
-                        }
-                        if value.is_null() {
-                            return Ok("NULL".to_string());
-                        }
-                    }
-                    Ok(expr
-                        .get("content")
-                        .and_then(|c| c.get("raw"))
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("")
-                        .to_string())
-                }
-                "variable" => Ok(expr
-                    .get("name")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("")
-                    .to_string()),
-                "binary" => self.generate_binary_expression(expr),
-                "unary" => self.generate_unary_expression(expr),
-                "call" => self.generate_function_call(expr),
-                "member" => {
-                    let object =
-                        self.generate_expression(expr.get("object").ok_or("Missing object")?)?;
-                    let property = expr.get("property").and_then(|v| v.as_str()).unwrap_or("");
-                    Ok(format!("{}.{}", object, property))
+    pub fn generate_expression(&self, expr: &UniversalExpression) -> Result<String, String> {
+        match expr {
+            UniversalExpression::Literal {
+                literal_type,
+                value,
+            } => {
+                if value.trim().is_empty() {
+                    return Err(format!("Empty {:?} literal", literal_type));
                 }
-                "index" => {
-                    let object =
-                        self.generate_expression(expr.get("object").ok_or("Missing object")?)?;
-                    let index =
-                        self.generate_expression(expr.get("index").ok_or("Missing index")?)?;
-                    Ok(format!("{}[{}]", object, index))
+                Ok(value.clone())
+            }
+            UniversalExpression::Identifier { name } => identifier(name),
+            UniversalExpression::Assignment {
+                operator,
+                target,
+                value,
+            } => Ok(format!(
+                "{} {} {}",
+                self.operand(target, 2)?,
+                operator.symbol(),
+                self.operand(value, ANY_EXPRESSION)?
+            )),
+            UniversalExpression::Conditional {
+                condition,
+                when_true,
+                when_false,
+            } => Ok(format!(
+                "{} ? {} : {}",
+                self.operand(condition, 3)?,
+                self.operand(when_true, ANY_EXPRESSION)?,
+                self.operand(when_false, 2)?
+            )),
+            UniversalExpression::Binary {
+                operator,
+                left,
+                right,
+            } => {
+                // Operators are left-associative, so a right operand at the same level needs parentheses
+                let precedence = operator.precedence();
+                Ok(format!(
+                    "{} {} {}",
+                    self.operand(left, precedence)?,
+                    operator.symbol(),
+                    self.operand(right, precedence + 1)?
+                ))
+            }
+            UniversalExpression::Unary { operator, operand } => {
+                let symbol = operator.symbol();
+                let mut operand = self.operand(operand, UNARY_OPERAND)?;
+                // `- -x` must not turn into `--x`
+                if symbol.ends_with(['+', '-']) && operand.starts_with(['+', '-']) {
+                    operand = format!("({})", operand);
                 }
-                _ => {
-                    // Fallback to raw content if available
-                    Ok(expr
-                        .get("content")
-                        .and_then(|c| c.get("raw"))
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("")
-                        .to_string())
+                Ok(format!("{}{}", symbol, operand))
+            }
+            UniversalExpression::Postfix { operator, operand } => match operator {
+                UnaryOperator::Increment | UnaryOperator::Decrement => Ok(format!(
+                    "{}{}",
+                    self.operand(operand, POSTFIX_OPERAND)?,
+                    operator.symbol()
+                )),
+                other => Err(format!("`{}` is not a postfix operator", other.symbol())),
+            },
+            UniversalExpression::TagOverride { tag, operand } => Ok(format!(
+                "{}:{}",
+                identifier(tag)?,
+                self.operand(operand, UNARY_OPERAND)?
+            )),
+            UniversalExpression::Sizeof { operand } => Ok(format!("sizeof({})", operand)),
+            UniversalExpression::Tagof { operand } => Ok(format!("tagof({})", operand)),
+            UniversalExpression::Call { callee, arguments } => {
+                let arguments = arguments
+                    .iter()
+                    .map(|argument| {
+                        let value = self.operand(&argument.value, ANY_EXPRESSION)?;
+                        Ok(match &argument.name {
+                            Some(name) => format!(".{} = {}", identifier(name)?, value),
+                            None => value,
+                        })
+                    })
+                    .collect::<Result<Vec<_>, String>>()?;
+                Ok(format!(
+                    "{}({})",
+                    self.operand(callee, POSTFIX_OPERAND)?,
+                    arguments.join(", ")
+                ))
+            }
+            UniversalExpression::Index { array, index } => Ok(format!(
+                "{}[{}]",
+                self.operand(array, POSTFIX_OPERAND)?,
+                self.operand(index, ANY_EXPRESSION)?
+            )),
+            UniversalExpression::ArrayInitializer { elements, ellipsis } => {
+                let mut parts = elements
+                    .iter()
+                    .map(|element| self.operand(element, ANY_EXPRESSION))
+                    .collect::<Result<Vec<_>, String>>()?;
+                if *ellipsis {
+                    if parts.is_empty() {
+                        return Err("`...` needs at least one array element".to_string());
+                    }
+                    parts.push("...".to_string());
                 }
+                Ok(format!("{{{}}}", parts.join(", ")))
             }
-        } else if let Some(raw) = expr.get("raw").and_then(|v| v.as_str()) {
-            Ok(raw.to_string())
-        } else if let Some(str_val) = expr.as_str() {
-            Ok(str_val.to_string())
-        } else {
-            Ok("".to_string())
         }
     }
 
-    fn generate_binary_expression(&self, expr: &serde_json::Value) -> Result<String, String> {
-        let operator = expr.get("operator").and_then(|v| v.as_str()).unwrap_or("");
-        let left_expr = expr.get("left").ok_or("Missing left operand")?;
-        let right_expr = expr.get("right").ok_or("Missing right operand")?;
-
-        let left = self.generate_expression(left_expr)?;
-        let right = self.generate_expression(right_expr)?;
-
-        // Add parentheses for clarity (can be optimized later based on precedence)
-        Ok(format!("({} {} {})", left, operator, right))
+    /// Generate an operand, parenthesized if it binds looser than its slot.
+    fn operand(&self, expr: &UniversalExpression, min_precedence: u8) -> Result<String, String> {
+        let code = self.generate_expression(expr)?;
+        if expr.precedence() < min_precedence {
+            Ok(format!("({})", code))
+        } else {
+            Ok(code)
+        }
     }
 
-    fn generate_unary_expression(&self, expr: &serde_json::Value) -> Result<String, String> {
-        let operator = expr.get("operator").and_then(|v| v.as_str()).unwrap_or("");
-        let operand_expr = expr.get("operand").ok_or("Missing operand")?;
-
-        let operand = self.generate_expression(operand_expr)?;
+    /// Case labels only accept numbers, identifiers, parentheses and a few operators.
+    fn case_operand(
+        &self,
+        expr: &UniversalExpression,
+        min_precedence: u8,
+    ) -> Result<String, String> {
+        match expr {
+            UniversalExpression::Binary {
+                operator,
+                left,
+                right,
+            } if is_case_operator(*operator) => {
+                let precedence = operator.precedence();
+                let code = format!(
+                    "{} {} {}",
+                    self.case_operand(left, precedence)?,
+                    operator.symbol(),
+                    self.case_operand(right, precedence + 1)?
+                );
+                if precedence < min_precedence {
+                    Ok(format!("({})", code))
+                } else {
+                    Ok(code)
+                }
+            }
+            UniversalExpression::Unary {
+                operator: UnaryOperator::Negate,
+                operand,
+            } => Ok(format!("-{}", self.case_atom(operand)?)),
+            _ => self.case_atom(expr),
+        }
+    }
 
-        // Handle prefix vs postfix operators
-        if operator == "++" || operator == "--" {
-            // Check if it's prefix or postfix (default to prefix)
-            let is_postfix = expr
-                .get("postfix")
-                .and_then(|v| v.as_bool())
-                .unwrap_or(false);
-            if is_postfix {
-                Ok(format!("{}{}", operand, operator))
-            } else {
-                Ok(format!("{}{}", operator, operand))
+    fn case_atom(&self, expr: &UniversalExpression) -> Result<String, String> {
+        match expr {
+            UniversalExpression::Literal {
+                literal_type:
+                    LiteralType::Integer
+                    | LiteralType::Hex
+                    | LiteralType::Binary
+                    | LiteralType::Character,
+                ..
             }
-        } else {
-            Ok(format!("{}{}", operator, operand))
+            | UniversalExpression::Identifier { .. } => self.generate_expression(expr),
+            _ => Ok(format!("({})", self.generate_expression(expr)?)),
         }
     }
 
-    fn generate_function_call(&self, expr: &serde_json::Value) -> Result<String, String> {
-        let callee = if let Some(callee_expr) = expr.get("callee") {
-            self.generate_expression(callee_expr)?
-        } else if let Some(callee_str) = expr.get("callee").and_then(|v| v.as_str()) {
-            callee_str.to_string()
-        } else {
-            expr.get("name")
-                .and_then(|v| v.as_str())
-                .unwrap_or("")
-                .to_string()
-        };
+    fn generate_case_value(&self, value: &UniversalCaseValue) -> Result<String, String> {
+        let mut output = self.case_operand(&value.value, 0)?;
+        if let Some(end) = &value.range_end {
+            output.push_str(" .. ");
+            output.push_str(&self.case_operand(end, 0)?);
+        }
+        Ok(output)
+    }
 
-        let mut args = Vec::new();
-        if let Some(arguments) = expr.get("arguments").and_then(|v| v.as_array()) {
-            for arg in arguments {
-                args.push(self.generate_expression(arg)?);
-            }
+    fn generate_declarator(&self, declarator: &UniversalDeclarator) -> Result<String, String> {
+        let mut output = format!(
+            "{}{}{}",
+            tag_prefix(&declarator.var_type.tag),
+            identifier(&declarator.name)?,
+            type_dimensions(&declarator.var_type)
+        );
+        if let Some(initializer) = &declarator.initializer {
+            output.push_str(" = ");
+            output.push_str(&self.operand(initializer, ANY_EXPRESSION)?);
         }
+        Ok(output)
+    }
 
-        Ok(format!("{}({})", callee, args.join(", ")))
+    fn generate_declarators(&self, declarators: &[UniversalDeclarator]) -> Result<String, String> {
+        if declarators.is_empty() {
+            return Err("Variable declaration without variables".to_string());
+        }
+        let parts = declarators
+            .iter()
+            .map(|d| self.generate_declarator(d))
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(parts.join(", "))
     }
 
     fn generate_statement(
         &self,
-        stmt: &serde_json::Value,
+        stmt: &UniversalStatement,
         indent_level: usize,
     ) -> Result<String, String> {
         let indent = self.get_indent(indent_level);
 
-        let kind = stmt
-            .get("kind")
-            .and_then(|v| v.as_str())
-            .unwrap_or("unknown");
-
-        match kind {
-            "variable" => self.generate_variable_declaration(stmt, &indent),
-            "assignment" => self.generate_assignment_statement(stmt, &indent),
-            "expression" => {
-                let expr = stmt.get("content").ok_or("Missing expression content")?;
-                let expr_str = self.generate_expression(expr)?;
-                Ok(format!("{}{};\n", indent, expr_str))
+        match &stmt.node {
+            UniversalStatementNode::Expression { expression } => Ok(format!(
+                "{}{};\n",
+                indent,
+                self.generate_expression(expression)?
+            )),
+            UniversalStatementNode::Variable {
+                storage,
+                declarations,
+            } => {
+                let storage = if storage.is_empty() {
+                    "new".to_string()
+                } else {
+                    storage.join(" ")
+                };
+                Ok(format!(
+                    "{}{} {};\n",
+                    indent,
+                    storage,
+                    self.generate_declarators(declarations)?
+                ))
             }
-            "return" => self.generate_return_statement(stmt, &indent),
-            "if" => self.generate_if_statement(stmt, indent_level),
-            "while" => self.generate_while_statement(stmt, indent_level),
-            "for" => self.generate_for_statement(stmt, indent_level),
-            "break" => Ok(format!("{}break;\n", indent)),
-            "continue" => Ok(format!("{}continue;\n", indent)),
-            "block" => self.generate_block_statement(stmt, indent_level),
-            _ => {
-                // Fallback to raw content
-                let content = stmt
-                    .get("content")
-                    .and_then(|c| c.get("raw"))
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("");
-                Ok(format!("{}{}\n", indent, content))
+            UniversalStatementNode::Return { value } => match value {
+                Some(value) => Ok(format!(
+                    "{}return {};\n",
+                    indent,
+                    self.generate_expression(value)?
+                )),
+                None => Ok(format!("{}return;\n", indent)),
+            },
+            UniversalStatementNode::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => self.generate_if_statement(
+                condition,
+                then_branch,
+                else_branch.as_deref(),
+                indent_level,
+            ),
+            UniversalStatementNode::While { condition, body } => {
+                let header = format!("{}while ({})", indent, self.generate_expression(condition)?);
+                self.generate_loop_body(header, body, indent_level)
+            }
+            UniversalStatementNode::DoWhile { body, condition } => {
+                let body_code =
+                    self.generate_branch(format!("{}do", indent), body, indent_level, false)?;
+                let condition = self.generate_expression(condition)?;
+                if is_block(body) {
+                    Ok(format!("{} while ({});\n", body_code, condition))
+                } else {
+                    Ok(format!("{}{}while ({});\n", body_code, indent, condition))
+                }
+            }
+            UniversalStatementNode::For {
+                init,
+                condition,
+                update,
+                body,
+            } => self.generate_for_statement(init, condition, update, body, indent_level),
+            UniversalStatementNode::Switch { subject, cases } => {
+                self.generate_switch_statement(subject, cases, indent_level)
+            }
+            UniversalStatementNode::Break => Ok(format!("{}break;\n", indent)),
+            UniversalStatementNode::Continue => Ok(format!("{}continue;\n", indent)),
+            UniversalStatementNode::Block { statements } => {
+                self.generate_block_statement(statements, indent_level)
             }
+            UniversalStatementNode::Empty => Ok(format!("{};\n", indent)),
+            // Directives start at the first column regardless of nesting
+            UniversalStatementNode::Directive { content } => Ok(format!("{}\n", content)),
         }
     }
 
-    fn generate_variable_declaration(
+    /// `header {...}` for blocks, otherwise the statement indented on its own line.
+    /// Block results end at `}` so callers can append ` else` or ` while`.
+    fn generate_branch(
         &self,
-        stmt: &serde_json::Value,
-        indent: &str,
+        header: String,
+        body: &UniversalStatement,
+        indent_level: usize,
+        force_braces: bool,
     ) -> Result<String, String> {
-        let var_type = stmt
-            .get("var_type")
-            .and_then(|v| v.as_str())
-            .unwrap_or("int");
-        let name = stmt.get("name").and_then(|v| v.as_str()).unwrap_or("");
-
-        let mut output = format!("{}new {} {}", indent, var_type, name);
-
-        if let Some(initial_value) = stmt.get("initial_value") {
-            let value_str = self.generate_expression(initial_value)?;
-            output.push_str(&format!(" = {}", value_str));
-        } else if let Some(content) = stmt.get("content") {
-            if let Some(raw) = content.get("raw").and_then(|v| v.as_str()) {
-                // Try to extract initial value from raw content
-                if raw.contains('=') {
-                    output.push_str(&format!(
-                        " = {}",
-                        raw.split('=').nth(1).unwrap_or("").trim()
-                    ));
-                }
+        match &body.node {
+            UniversalStatementNode::Block { statements } => {
+                let block = self.generate_block_statement(statements, indent_level)?;
+                Ok(format!(
+                    "{} {}",
+                    header,
+                    block.trim_start().trim_end_matches('\n')
+                ))
             }
+            _ if force_braces => {
+                let inner = self.generate_statement(body, indent_level + 1)?;
+                Ok(format!(
+                    "{} {{\n{}{}}}",
+                    header,
+                    inner,
+                    self.get_indent(indent_level)
+                ))
+            }
+            _ => Ok(format!(
+                "{}\n{}",
+                header,
+                self.generate_statement(body, indent_level + 1)?
+            )),
         }
-
-        Ok(format!("{};\n", output))
     }
 
-    fn generate_assignment_statement(
+    fn generate_if_statement(
         &self,
-        stmt: &serde_json::Value,
-        indent: &str,
+        condition: &UniversalExpression,
+        then_branch: &UniversalStatement,
+        else_branch: Option<&UniversalStatement>,
+        indent_level: usize,
     ) -> Result<String, String> {
-        let target = if let Some(target_expr) = stmt.get("target") {
-            self.generate_expression(target_expr)?
-        } else {
-            stmt.get("content")
-                .and_then(|c| c.get("raw"))
-                .and_then(|v| v.as_str())
-                .unwrap_or("")
-                .to_string()
-        };
+        let indent = self.get_indent(indent_level);
+        let header = format!("{}if ({})", indent, self.generate_expression(condition)?);
 
-        let operator = stmt.get("operator").and_then(|v| v.as_str()).unwrap_or("=");
-        let value = if let Some(value_expr) = stmt.get("value") {
-            self.generate_expression(value_expr)?
-        } else {
-            "".to_string()
+        // An unbraced inner `if` would capture our `else`
+        let force_braces = else_branch.is_some() && ends_with_open_if(then_branch);
+        let then_braced = is_block(then_branch) || force_braces;
+        let mut output = self.generate_branch(header, then_branch, indent_level, force_braces)?;
+
+        let Some(else_branch) = else_branch else {
+            if then_braced {
+                output.push('\n');
+            }
+            return Ok(output);
         };
 
-        Ok(format!("{}{} {} {};\n", indent, target, operator, value))
-    }
+        if then_braced {
+            output.push_str(" else");
+        } else {
+            output.push_str(&indent);
+            output.push_str("else");
+        }
 
-    fn generate_return_statement(
-        &self,
-        stmt: &serde_json::Value,
-        indent: &str,
-    ) -> Result<String, String> {
-        if let Some(value) = stmt.get("value") {
-            let value_str = self.generate_expression(value)?;
-            Ok(format!("{}return {};\n", indent, value_str))
-        } else if let Some(content) = stmt.get("content") {
-            if let Some(raw) = content.get("raw").and_then(|v| v.as_str()) {
-                if raw.trim().is_empty() {
-                    Ok(format!("{}return;\n", indent))
-                } else {
-                    Ok(format!("{}return {};\n", indent, raw))
+        match &else_branch.node {
+            UniversalStatementNode::If { .. } => {
+                // `else if` chains stay on one level
+                let nested = self.generate_statement(else_branch, indent_level)?;
+                output.push(' ');
+                output.push_str(&nested[indent.len()..]);
+            }
+            _ => {
+                let branch =
+                    self.generate_branch(String::new(), else_branch, indent_level, false)?;
+                output.push_str(&branch);
+                if is_block(else_branch) {
+                    output.push('\n');
                 }
-            } else {
-                Ok(format!("{}return;\n", indent))
             }
-        } else {
-            Ok(format!("{}return;\n", indent))
         }
+
+        Ok(output)
     }
 
-    fn generate_if_statement(
+    fn generate_for_statement(
         &self,
-        stmt: &serde_json::Value,
+        init: &Option<UniversalForInit>,
+        condition: &Option<UniversalExpression>,
+        update: &[UniversalExpression],
+        body: &UniversalStatement,
         indent_level: usize,
     ) -> Result<String, String> {
         let indent = self.get_indent(indent_level);
 
-        let condition = if let Some(cond_expr) = stmt.get("condition") {
-            self.generate_expression(cond_expr)?
-        } else {
-            stmt.get("content")
-                .and_then(|c| c.get("raw"))
-                .and_then(|v| v.as_str())
-                .unwrap_or("")
-                .to_string()
+        let init = match init {
+            Some(UniversalForInit::Declaration { declarations }) => {
+                format!("new {}", self.generate_declarators(declarations)?)
+            }
+            Some(UniversalForInit::Expressions { expressions }) => {
+                self.generate_expression_list(expressions)?
+            }
+            None => String::new(),
         };
 
-        let then_body = if let Some(then_part) = stmt.get("then") {
-            self.generate_statement_block(then_part, indent_level + 1)?
-        } else {
-            String::new()
+        let condition = match condition {
+            Some(condition) => format!(" {}", self.generate_expression(condition)?),
+            None => String::new(),
         };
 
-        let else_body = if let Some(else_part) = stmt.get("else") {
-            format!(
-                "\n{}else {}",
-                indent,
-                self.generate_statement_block(else_part, indent_level + 1)?
-            )
-        } else {
+        let update = if update.is_empty() {
             String::new()
+        } else {
+            format!(" {}", self.generate_expression_list(update)?)
         };
 
-        Ok(format!(
-            "{}if ({}) {{\n{}{}}}{}\n",
-            indent, condition, then_body, indent, else_body
-        ))
+        let header = format!("{}for ({};{};{})", indent, init, condition, update);
+        self.generate_loop_body(header, body, indent_level)
     }
 
-    fn generate_while_statement(
+    fn generate_loop_body(
         &self,
-        stmt: &serde_json::Value,
+        header: String,
+        body: &UniversalStatement,
         indent_level: usize,
     ) -> Result<String, String> {
-        let indent = self.get_indent(indent_level);
-
-        let condition = if let Some(cond_expr) = stmt.get("condition") {
-            self.generate_expression(cond_expr)?
-        } else {
-            stmt.get("content")
-                .and_then(|c| c.get("raw"))
-                .and_then(|v| v.as_str())
-                .unwrap_or("")
-                .to_string()
-        };
-
-        let body = if let Some(body_part) = stmt.get("body") {
-            self.generate_statement_block(body_part, indent_level + 1)?
-        } else {
-            String::new()
-        };
+        let mut output = self.generate_branch(header, body, indent_level, false)?;
+        if is_block(body) {
+            output.push('\n');
+        }
+        Ok(output)
+    }
 
-        Ok(format!(
-            "{}while ({}) {{\n{}{}}}\n",
-            indent, condition, body, indent
-        ))
+    fn generate_expression_list(
+        &self,
+        expressions: &[UniversalExpression],
+    ) -> Result<String, String> {
+        let parts = expressions
+            .iter()
+            .map(|e| self.generate_expression(e))
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(parts.join(", "))
     }
 
-    fn generate_for_statement(
+    fn generate_switch_statement(
         &self,
-        stmt: &serde_json::Value,
+        subject: &UniversalExpression,
+        cases: &[UniversalCase],
         indent_level: usize,
     ) -> Result<String, String> {
         let indent = self.get_indent(indent_level);
+        let case_indent = self.get_indent(indent_level + 1);
 
-        let init = if let Some(init_part) = stmt.get("init") {
-            if let Some(init_stmt) = init_part.as_object() {
-                if init_stmt.contains_key("kind") {
-                    self.generate_statement(init_part, 0)?.trim().to_string()
-                } else {
-                    self.generate_expression(init_part)?.trim().to_string()
-                }
+        let mut output = format!(
+            "{}switch ({}) {{\n",
+            indent,
+            self.generate_expression(subject)?
+        );
+
+        for case in cases {
+            if case.values.is_empty() {
+                output.push_str(&format!("{}default:", case_indent));
             } else {
-                String::new()
+                let values = case
+                    .values
+                    .iter()
+                    .map(|v| self.generate_case_value(v))
+                    .collect::<Result<Vec<_>, String>>()?;
+                output.push_str(&format!("{}case {}:", case_indent, values.join(", ")));
             }
-        } else {
-            String::new()
-        };
 
-        let condition = if let Some(cond_expr) = stmt.get("condition") {
-            self.generate_expression(cond_expr)?
-        } else {
-            String::new()
-        };
-
-        let update = if let Some(update_expr) = stmt.get("update") {
-            self.generate_expression(update_expr)?
-        } else {
-            String::new()
-        };
-
-        let body = if let Some(body_part) = stmt.get("body") {
-            self.generate_statement_block(body_part, indent_level + 1)?
-        } else {
-            String::new()
-        };
+            match case.body.as_slice() {
+                [single] if is_block(single) => {
+                    output.push(' ');
+                    output.push_str(
+                        self.generate_statement(single, indent_level + 1)?
+                            .trim_start(),
+                    );
+                }
+                body => {
+                    output.push('\n');
+                    for stmt in body {
+                        output.push_str(&self.generate_statement(stmt, indent_level + 2)?);
+                    }
+                }
+            }
+        }
 
-        Ok(format!(
-            "{}for ({}; {}; {}) {{\n{}{}}}\n",
-            indent, init, condition, update, body, indent
-        ))
+        output.push_str(&format!("{}}}\n", indent));
+        Ok(output)
     }
 
     fn generate_block_statement(
         &self,
-        stmt: &serde_json::Value,
+        statements: &[UniversalStatement],
         indent_level: usize,
     ) -> Result<String, String> {
         let indent = self.get_indent(indent_level);
 
-        if let Some(statements) = stmt.get("statements").and_then(|v| v.as_array()) {
-            let mut body = String::new();
-            for stmt_node in statements {
-                body.push_str(&self.generate_statement(stmt_node, indent_level + 1)?);
-            }
-            Ok(format!("{}{{\n{}{}}}\n", indent, body, indent))
-        } else {
-            let _indent = self.get_indent(indent_level);
-            Ok(format!("{}{{\n{}}}\n", indent, indent))
+        let mut body = String::new();
+        for stmt in statements {
+            body.push_str(&self.generate_statement(stmt, indent_level + 1)?);
         }
+        Ok(format!("{}{{\n{}{}}}\n", indent, body, indent))
     }
+}
 
-    fn generate_statement_block(
-        &self,
-        block: &serde_json::Value,
-        indent_level: usize,
-    ) -> Result<String, String> {
-        if let Some(statements) = block.as_array() {
-            let mut body = String::new();
-            for stmt in statements {
-                body.push_str(&self.generate_statement(stmt, indent_level)?);
-            }
-            Ok(body)
-        } else if block.get("kind").and_then(|v| v.as_str()) == Some("block") {
-            self.generate_block_statement(block, indent_level)
-        } else {
-            // Single statement
-            self.generate_statement(block, indent_level)
+/// Backs the `generate_blueprint_code` command: `ast` is either a `UniversalAST`
+/// or the blueprint editor's program tree, see `ASTBridge::universal_from_json`.
+pub fn generate_blueprint_code(
+    ast: serde_json::Value,
+    language: &str,
+    options: Option<serde_json::Value>,
+) -> Result<String, String> {
+    match language {
+        "pawn" => {
+            let options = match options {
+                Some(options) => serde_json::from_value(options)
+                    .map_err(|e| format!("Invalid generation options: {}", e))?,
+                None => GenerationOptions::default(),
+            };
+            let ast = ASTBridge::universal_from_json(ast)?;
+            PawnGenerator::with_options(options).generate(&ast)
         }
+        _ => Err(format!("Unsupported language: {}", language)),
     }
 }
 
@@ -580,3 +831,487 @@ impl Default for PawnGenerator {
         Self::new()
     }
 }
+
+fn identifier(name: &str) -> Result<String, String> {
+    let valid = name
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '@')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '@');
+    if valid {
+        Ok(name.to_string())
+    } else {
+        Err(format!("Invalid identifier `{}`", name))
+    }
+}
+
+fn tag_prefix(tag: &Option<String>) -> String {
+    match tag {
+        Some(tag) if !tag.is_empty() => format!("{}:", tag),
+        _ => String::new(),
+    }
+}
+
+fn dimension_suffix(dimensions: &[Option<String>]) -> String {
+    dimensions
+        .iter()
+        .map(|size| format!("[{}]", size.as_deref().unwrap_or("")))
+        .collect()
+}
+
+/// Dimensions of a type, falling back to `array_size` for hand-built types.
+fn type_dimensions(ty: &UniversalType) -> String {
+    if !ty.dimensions.is_empty() {
+        dimension_suffix(&ty.dimensions)
+    } else if ty.is_array {
+        format!(
+            "[{}]",
+            ty.array_size
+                .map(|size| size.to_string())
+                .unwrap_or_default()
+        )
+    } else {
+        String::new()
+    }
+}
+
+fn is_block(stmt: &UniversalStatement) -> bool {
+    matches!(stmt.node, UniversalStatementNode::Block { .. })
+}
+
+fn is_case_operator(operator: BinaryOperator) -> bool {
+    matches!(
+        operator,
+        BinaryOperator::Add
+            | BinaryOperator::Subtract
+            | BinaryOperator::Multiply
+            | BinaryOperator::Divide
+            | BinaryOperator::BitOr
+            | BinaryOperator::ShiftLeft
+            | BinaryOperator::ShiftRight
+    )
+}
+
+/// Whether a trailing `else` written after this statement would bind to an inner `if`.
+fn ends_with_open_if(stmt: &UniversalStatement) -> bool {
+    match &stmt.node {
+        UniversalStatementNode::If {
+            else_branch: None, ..
+        } => true,
+        UniversalStatementNode::If {
+            else_branch: Some(else_branch),
+            ..
+        } => ends_with_open_if(else_branch),
+        UniversalStatementNode::While { body, .. } | UniversalStatementNode::For { body, .. } => {
+            ends_with_open_if(body)
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blueprint::ast_bridge::AssignmentOperator;
+    use crate::blueprint::parser::Parser as ParserTrait;
+    use crate::blueprint::pawn_parser::{PawnAST, PawnParser};
+    use crate::blueprint::pawn_preprocessor::PreprocessorOptions;
+    use std::path::PathBuf;
+
+    fn corpus_dir() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("src/blueprint/corpus")
+    }
+
+    fn corpus_parser() -> PawnParser {
+        // The corpus root is searched too so `#include <include/colors>` resolves
+        PawnParser::with_options(PreprocessorOptions {
+            include_paths: vec![corpus_dir().join("include"), corpus_dir()],
+            predefined: Vec::new(),
+        })
+    }
+
+    fn to_universal(result: crate::blueprint::ParseResult, context: &str) -> UniversalAST {
+        assert!(result.errors.is_empty(), "{}: {:?}", context, result.errors);
+        let pawn_ast: PawnAST = serde_json::from_value(result.ast).unwrap();
+        ASTBridge::pawn_to_universal(&pawn_ast)
+    }
+
+    /// Serialized AST without ids, positions and metadata, which may differ
+    /// between the original source and generated code.
+    fn semantic_json(ast: &UniversalAST) -> serde_json::Value {
+        fn strip(value: &mut serde_json::Value) {
+            match value {
+                serde_json::Value::Object(map) => {
+                    map.remove("id");
+                    map.remove("position");
+                    map.remove("metadata");
+                    map.values_mut().for_each(strip);
+                }
+                serde_json::Value::Array(items) => items.iter_mut().for_each(strip),
+                _ => {}
+            }
+        }
+        let mut value = serde_json::to_value(ast).unwrap();
+        strip(&mut value);
+        value
+    }
+
+    /// Directive lines with their `\\` continuations, ignoring indentation.
+    fn directive_lines(source: &str) -> Vec<String> {
+        let mut directives: Vec<String> = Vec::new();
+        let mut continued = false;
+        for line in source.lines().map(str::trim) {
+            if continued {
+                directives.last_mut().unwrap().push_str(line);
+            } else if line.starts_with('#') {
+                directives.push(line.to_string());
+            } else {
+                continue;
+            }
+            continued = line.ends_with('\\');
+        }
+        directives
+    }
+
+    fn corpus_files() -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = std::fs::read_dir(corpus_dir())
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().and_then(|e| e.to_str()) == Some("pwn"))
+            .collect();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn test_corpus_round_trip_is_semantically_equal() {
+        let option_sets = [
+            GenerationOptions::default(),
+            GenerationOptions {
+                indent_size: 2,
+                ..GenerationOptions::default()
+            },
+            GenerationOptions {
+                use_tabs: true,
+                ..GenerationOptions::default()
+            },
+        ];
+
+        let parser = corpus_parser();
+        for path in corpus_files() {
+            let name = path.display().to_string();
+            let source = std::fs::read_to_string(&path).unwrap();
+            let original = to_universal(parser.parse_file(&path).unwrap(), &name);
+
+            for options in &option_sets {
+                let generator = PawnGenerator::with_options(options.clone());
+                let generated = generator.generate(&original).unwrap();
+                let reparsed = to_universal(parser.parse(&generated).unwrap(), &generated);
+
+                assert_eq!(
+                    semantic_json(&original),
+                    semantic_json(&reparsed),
+                    "{} changed meaning when regenerated:\n{}",
+                    name,
+                    generated
+                );
+
+                assert_eq!(
+                    directive_lines(&generated),
+                    directive_lines(&source),
+                    "{} lost directives when regenerated:\n{}",
+                    name,
+                    generated
+                );
+
+                // Generating again from the reparsed tree is stable
+                assert_eq!(generator.generate(&reparsed).unwrap(), generated);
+            }
+        }
+    }
+
+    #[test]
+    fn test_macro_uses_are_not_expanded() {
+        let path = corpus_dir().join("gamemode.pwn");
+        let ast = to_universal(corpus_parser().parse_file(&path).unwrap(), "gamemode.pwn");
+        let generated = PawnGenerator::new().generate(&ast).unwrap();
+
+        assert!(generated.contains("new gPlayerData[MAX_PLAYERS][E_PLAYER];"));
+        assert!(generated.contains("new const gWarningColor = COLOR_RED;"));
+        assert!(generated.contains("print(\"Loaded \" GAMEMODE_NAME);"));
+        assert!(generated.contains("if (!IsValidHouse(listitem))"));
+        assert!(!generated.contains("0xFF0000FF"), "{}", generated);
+
+        // Each `#if` branch still wraps its own callback
+        let filterscript = generated.find("#if defined FILTERSCRIPT").unwrap();
+        let else_branch = generated.find("#else").unwrap();
+        let end = generated[else_branch..].find("#endif").unwrap() + else_branch;
+        let init = |name: &str| generated.find(name).unwrap();
+        assert!((filterscript..else_branch).contains(&init("OnFilterScriptInit()")));
+        assert!((else_branch..end).contains(&init("OnGameModeInit()")));
+    }
+
+    #[test]
+    fn test_directives_inside_functions_are_kept() {
+        let source =
+            "main()\n{\n\t#if defined DEBUG\n\tprint(\"debug\");\n\t#endif\n\treturn 1;\n}\n";
+        let ast = to_universal(PawnParser::new().parse(source).unwrap(), source);
+        let generated = PawnGenerator::new().generate(&ast).unwrap();
+
+        assert_eq!(
+            generated,
+            "main() {\n#if defined DEBUG\n    print(\"debug\");\n#endif\n    return 1;\n}\n\n"
+        );
+    }
+
+    /// Program tree in the shape `converters/blueprint-to-ast.ts` builds it.
+    fn frontend_blueprint_json() -> serde_json::Value {
+        let position = serde_json::json!({ "line": -1, "column": 12 });
+        let literal = |value: serde_json::Value, ty: &str| {
+            serde_json::json!({
+                "id": "literal_1", "type": "expression", "kind": "literal",
+                "expression": { "value": value, "type": ty },
+                "position": position, "metadata": { "blueprintNodeId": "n1" }
+            })
+        };
+        let variable = |name: &str| {
+            serde_json::json!({
+                "id": "var_1", "type": "expression", "kind": "variable",
+                "expression": { "name": name }, "position": position
+            })
+        };
+        let binary = |operator: &str, left: serde_json::Value, right: serde_json::Value| {
+            serde_json::json!({
+                "id": "binary_1", "type": "expression", "kind": "binary",
+                "expression": { "operator": operator, "left": left, "right": right },
+                "position": position
+            })
+        };
+        let statement = |kind: &str, statement: serde_json::Value| {
+            serde_json::json!({
+                "id": "stmt_1", "type": "statement", "kind": kind,
+                "statement": statement, "position": position
+            })
+        };
+
+        let welcome = statement(
+            "expression",
+            serde_json::json!({
+                "id": "call_1", "type": "expression", "kind": "call",
+                "expression": {
+                    "callee": "SendClientMessage",
+                    "arguments": [variable("playerid"), literal(serde_json::json!(-1), "int"), literal(serde_json::json!("Welcome!"), "string")]
+                },
+                "position": position, "metadata": { "isNative": true }
+            }),
+        );
+        let heal = statement(
+            "assignment",
+            serde_json::json!({ "target": variable("gHealth"), "operator": "=", "value": literal(serde_json::json!(100), "float") }),
+        );
+        let count = statement(
+            "assignment",
+            serde_json::json!({ "target": variable("gCount"), "operator": "+=", "value": literal(serde_json::json!("1"), "int") }),
+        );
+
+        serde_json::json!({
+            "id": "program_1",
+            "type": "program",
+            "language": "pawn",
+            "position": { "line": 0, "column": 0 },
+            "metadata": { "blueprintId": "bp_1", "blueprintName": "Welcome" },
+            "variables": [
+                {
+                    "id": "var_2", "type": "variable", "name": "gCount",
+                    "variableType": { "id": "type_1", "type": "type", "name": "int", "position": position },
+                    "initialValue": literal(serde_json::json!(0), "int"),
+                    "position": position
+                },
+                {
+                    "id": "var_3", "type": "variable", "name": "gHealth",
+                    "variableType": { "id": "type_2", "type": "type", "name": "float", "position": position },
+                    "isConstant": false,
+                    "position": position
+                }
+            ],
+            "functions": [{
+                "id": "func_1",
+                "type": "function",
+                "name": "OnPlayerConnect",
+                "parameters": [{
+                    "id": "param_1", "type": "parameter", "name": "playerid",
+                    "parameterType": { "id": "type_3", "type": "type", "name": "int", "position": position },
+                    "position": position
+                }],
+                "returnType": { "id": "type_4", "type": "type", "name": "int", "position": position },
+                "body": [
+                    {
+                        "id": "if_1", "type": "statement", "kind": "if",
+                        "condition": binary(">", variable("playerid"), literal(serde_json::json!(5), "int")),
+                        "then": [welcome, heal],
+                        "else": statement("return", serde_json::json!({ "value": literal(serde_json::json!(0), "int") })),
+                        "position": position
+                    },
+                    {
+                        "id": "while_1", "type": "statement", "kind": "while",
+                        "condition": binary("<", variable("gCount"), literal(serde_json::json!(10), "int")),
+                        "body": count,
+                        "position": position
+                    },
+                    statement("return", serde_json::json!({ "value": literal(serde_json::json!(true), "bool") }))
+                ],
+                "isPublic": "OnPlayerConnect",
+                "modifiers": [],
+                "position": position,
+                "metadata": { "blueprintNodeId": "n0", "isCallback": "OnPlayerConnect" }
+            }]
+        })
+    }
+
+    #[test]
+    fn test_generates_code_from_the_blueprint_editor_ast() {
+        // The options are sent the way `sync/blueprint-to-code.ts` sends them
+        let options =
+            serde_json::json!({ "indentSize": 2, "useTabs": false, "preserveFormatting": true });
+        let code =
+            generate_blueprint_code(frontend_blueprint_json(), "pawn", Some(options)).unwrap();
+
+        assert_eq!(
+            code,
+            "new gCount = 0;\n\
+             new Float:gHealth;\n\
+             \n\
+             public OnPlayerConnect(playerid) {\n\
+             \x20 if (playerid > 5) {\n\
+             \x20   SendClientMessage(playerid, -1, \"Welcome!\");\n\
+             \x20   gHealth = 100.0;\n\
+             \x20 } else\n\
+             \x20   return 0;\n\
+             \x20 while (gCount < 10)\n\
+             \x20   gCount += 1;\n\
+             \x20 return true;\n\
+             }\n\n"
+        );
+
+        let reparsed = PawnParser::new().parse(&code).unwrap();
+        assert!(reparsed.errors.is_empty(), "{:?}", reparsed.errors);
+
+        let mut unknown_operator = frontend_blueprint_json();
+        unknown_operator["functions"][0]["body"][1]["condition"]["expression"]["operator"] =
+            serde_json::json!("<>");
+        assert!(generate_blueprint_code(unknown_operator, "pawn", None).is_err());
+    }
+
+    fn ident(name: &str) -> Box<UniversalExpression> {
+        Box::new(UniversalExpression::Identifier {
+            name: name.to_string(),
+        })
+    }
+
+    #[test]
+    fn test_parenthesizes_by_precedence() {
+        let generator = PawnGenerator::new();
+        let sum = UniversalExpression::Binary {
+            operator: BinaryOperator::Add,
+            left: ident("a"),
+            right: ident("b"),
+        };
+        let product = UniversalExpression::Binary {
+            operator: BinaryOperator::Multiply,
+            left: Box::new(sum.clone()),
+            right: ident("c"),
+        };
+        assert_eq!(
+            generator.generate_expression(&product).unwrap(),
+            "(a + b) * c"
+        );
+
+        let difference = UniversalExpression::Binary {
+            operator: BinaryOperator::Subtract,
+            left: ident("a"),
+            right: Box::new(sum),
+        };
+        assert_eq!(
+            generator.generate_expression(&difference).unwrap(),
+            "a - (a + b)"
+        );
+
+        let negate_twice = UniversalExpression::Unary {
+            operator: UnaryOperator::Negate,
+            operand: Box::new(UniversalExpression::Unary {
+                operator: UnaryOperator::Negate,
+                operand: ident("x"),
+            }),
+        };
+        assert_eq!(
+            generator.generate_expression(&negate_twice).unwrap(),
+            "-(-x)"
+        );
+    }
+
+    #[test]
+    fn test_dangling_else_gets_braces() {
+        let position = SourcePosition { line: 0, column: 0 };
+        let statement = |node| UniversalStatement {
+            id: String::new(),
+            node,
+            position: position.clone(),
+        };
+        let assign = |name: &str| {
+            statement(UniversalStatementNode::Expression {
+                expression: UniversalExpression::Assignment {
+                    operator: AssignmentOperator::Assign,
+                    target: ident(name),
+                    value: ident("b"),
+                },
+            })
+        };
+        let inner = statement(UniversalStatementNode::If {
+            condition: *ident("y"),
+            then_branch: Box::new(assign("a")),
+            else_branch: None,
+        });
+        let outer = statement(UniversalStatementNode::If {
+            condition: *ident("x"),
+            then_branch: Box::new(inner),
+            else_branch: Some(Box::new(assign("c"))),
+        });
+
+        let code = PawnGenerator::new().generate_statement(&outer, 0).unwrap();
+        let block = PawnParser::new()
+            .parse(&format!("main() {{ {} }}", code))
+            .unwrap();
+        let ast = to_universal(block, &code);
+        match &ast.functions[0].body[0].node {
+            UniversalStatementNode::If { else_branch, .. } => assert!(else_branch.is_some()),
+            other => panic!("expected if, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_malformed_blueprints_are_rejected() {
+        // Unknown operators fail when the blueprint is read, not at code generation
+        let json = serde_json::json!({
+            "kind": "binary",
+            "operator": "<>",
+            "left": { "kind": "identifier", "name": "a" },
+            "right": { "kind": "identifier", "name": "b" }
+        });
+        assert!(serde_json::from_value::<UniversalExpression>(json).is_err());
+
+        let generator = PawnGenerator::new();
+        assert!(generator
+            .generate_expression(&UniversalExpression::Identifier {
+                name: "not valid".to_string()
+            })
+            .is_err());
+        assert!(generator
+            .generate_expression(&UniversalExpression::Postfix {
+                operator: UnaryOperator::Not,
+                operand: ident("x"),
+            })
+            .is_err());
+    }
+}
@@ -1,10 +1,21 @@
 /**
  * Pawn language parser implementation
  */
+use std::path::Path;
+
+use pest::iterators::{Pair, Pairs};
 use pest::Parser;
 use serde::{Deserialize, Serialize};
 
+use crate::blueprint::ast_bridge::{
+    pawn_type, AssignmentOperator, BinaryOperator, LiteralType, SourcePosition, UnaryOperator,
+    UniversalArgument, UniversalCase, UniversalCaseValue, UniversalDeclarator, UniversalExpression,
+    UniversalForInit, UniversalStatement, UniversalStatementNode, UNTAGGED_TYPE,
+};
 use crate::blueprint::parser::{ParseResult, Parser as ParserTrait};
+use crate::blueprint::pawn_preprocessor::{
+    split_directive, strip_comments, IncludeRecord, PawnPreprocessor, PreprocessorOptions,
+};
 
 #[derive(pest_derive::Parser)]
 #[grammar = "blueprint/grammar/pawn.pest"]
@@ -15,8 +26,19 @@ pub struct PawnAST {
     pub functions: Vec<FunctionNode>,
     pub callbacks: Vec<CallbackNode>,
     pub variables: Vec<VariableNode>,
-    pub includes: Vec<String>,
+    pub enums: Vec<EnumNode>,
+    pub includes: Vec<IncludeRecord>,
     pub directives: Vec<DirectiveNode>,
+    /// `true` when the tree was parsed from preprocessed source, `false` when
+    /// macro uses are kept as written and `directives` holds every directive
+    /// line of the file in source order
+    #[serde(default)]
+    pub macros_expanded: bool,
+    /// The file parsed as written, with both `#if` branches and unexpanded
+    /// macro uses. Only used to regenerate the source; `None` when the file
+    /// does not parse without expanding its macros
+    #[serde(default)]
+    pub written: Option<Box<PawnAST>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -24,8 +46,11 @@ pub struct FunctionNode {
     pub name: String,
     pub modifiers: Vec<String>,
     pub return_type: String,
+    pub return_tag: Option<String>,
     pub parameters: Vec<ParameterNode>,
     pub body: Vec<StatementNode>,
+    /// `false` for `forward`/`native` prototypes
+    pub has_body: bool,
     pub line: usize,
     pub column: usize,
 }
@@ -43,7 +68,13 @@ pub struct CallbackNode {
 pub struct ParameterNode {
     pub name: String,
     pub param_type: String,
-    pub default_value: Option<String>,
+    pub tag: Option<String>,
+    /// One entry per `[]`, holding the declared size expression if any
+    pub dimensions: Vec<Option<String>>,
+    pub is_reference: bool,
+    pub is_const: bool,
+    pub is_variadic: bool,
+    pub default_value: Option<UniversalExpression>,
     pub line: usize,
     pub column: usize,
 }
@@ -52,7 +83,32 @@ pub struct ParameterNode {
 pub struct VariableNode {
     pub name: String,
     pub var_type: String,
-    pub initial_value: Option<String>,
+    pub tag: Option<String>,
+    /// Storage keywords as written, e.g. `["static", "const"]`
+    pub storage: Vec<String>,
+    pub dimensions: Vec<Option<String>>,
+    pub initial_value: Option<UniversalExpression>,
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnumNode {
+    pub name: Option<String>,
+    pub tag: Option<String>,
+    /// Increment clause such as `<<= 1`, `None` for the default `+= 1`
+    pub increment: Option<String>,
+    pub items: Vec<EnumItemNode>,
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnumItemNode {
+    pub name: String,
+    pub tag: Option<String>,
+    pub dimensions: Vec<Option<String>>,
+    pub value: Option<UniversalExpression>,
     pub line: usize,
     pub column: usize,
 }
@@ -61,6 +117,8 @@ pub struct VariableNode {
 pub struct StatementNode {
     pub kind: String,
     pub content: serde_json::Value,
+    /// Typed statement tree, shared with the universal AST
+    pub node: UniversalStatementNode,
     pub line: usize,
     pub column: usize,
 }
@@ -73,49 +131,183 @@ pub struct DirectiveNode {
     pub column: usize,
 }
 
-pub struct PawnParser;
+fn line_col(pair: &pest::iterators::Pair<Rule>) -> (usize, usize) {
+    pair.as_span().start_pos().line_col()
+}
+
+fn parse_directive(pair: &Pair<Rule>) -> DirectiveNode {
+    let (line, column) = line_col(pair);
+    let content = pair.as_str().trim_end();
+    DirectiveNode {
+        kind: split_directive(content[1..].trim_start()).0.to_string(),
+        content: content.to_string(),
+        line,
+        column,
+    }
+}
+
+fn strip_tag(tag: &str) -> String {
+    tag.trim_end_matches(':').to_string()
+}
+
+fn next_pair<'i>(pairs: &mut Pairs<'i, Rule>, line: usize) -> Result<Pair<'i, Rule>, String> {
+    pairs
+        .next()
+        .ok_or_else(|| format!("Line {}: incomplete syntax tree", line))
+}
+
+/// Combine `a op b op c` by operator precedence (used for the flat case label grammar).
+fn fold_binary(
+    mut terms: Vec<UniversalExpression>,
+    operators: Vec<BinaryOperator>,
+) -> UniversalExpression {
+    fn reduce(output: &mut Vec<UniversalExpression>, operator: BinaryOperator) {
+        let right = output.pop().expect("operand for right side");
+        let left = output.pop().expect("operand for left side");
+        output.push(UniversalExpression::Binary {
+            operator,
+            left: Box::new(left),
+            right: Box::new(right),
+        });
+    }
+
+    let rest = terms.split_off(1);
+    let mut output = terms;
+    let mut pending: Vec<BinaryOperator> = Vec::new();
+
+    for (operator, term) in operators.into_iter().zip(rest) {
+        while let Some(&top) = pending.last() {
+            if top.precedence() < operator.precedence() {
+                break;
+            }
+            pending.pop();
+            reduce(&mut output, top);
+        }
+        pending.push(operator);
+        output.push(term);
+    }
+    while let Some(operator) = pending.pop() {
+        reduce(&mut output, operator);
+    }
+
+    output.pop().expect("at least one term")
+}
+
+pub struct PawnParser {
+    options: PreprocessorOptions,
+}
 
 impl PawnParser {
     pub fn new() -> Self {
-        Self
+        Self {
+            options: PreprocessorOptions::default(),
+        }
+    }
+
+    pub fn with_options(options: PreprocessorOptions) -> Self {
+        Self { options }
+    }
+
+    /// Parse a script from disk so `#include "..."` resolves relative to it.
+    pub fn parse_file<P: AsRef<Path>>(&self, path: P) -> Result<ParseResult, String> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        Ok(self.parse_with_path(&source, Some(path)))
+    }
+
+    fn parse_with_path(&self, source: &str, path: Option<&Path>) -> ParseResult {
+        let preprocessed = PawnPreprocessor::new(self.options.clone()).process(source, path);
+        let mut errors = preprocessed.errors;
+        let mut warnings = preprocessed.warnings;
+
+        // The tree describes the active `#if` branches with macros expanded;
+        // the file as written is kept next to it to regenerate the source
+        let parsed = self.parse_program(&preprocessed.source).map(|mut ast| {
+            ast.directives = preprocessed.directives;
+            ast.macros_expanded = true;
+            match self.parse_program(&strip_comments(source)) {
+                Ok(written) => ast.written = Some(Box::new(written)),
+                Err(raw_errors) => warnings.push(format!(
+                    "Macros are expanded in generated code, the source does not parse as written: {}",
+                    raw_errors.join("; ")
+                )),
+            }
+            ast
+        });
+
+        match parsed {
+            Ok(mut ast) => {
+                ast.includes = preprocessed.includes;
+
+                let ast_json = serde_json::to_value(&ast)
+                    .unwrap_or_else(|e| serde_json::json!({ "error": e.to_string() }));
+
+                ParseResult {
+                    ast: ast_json,
+                    errors,
+                    warnings,
+                }
+            }
+            Err(parse_errors) => {
+                errors.extend(parse_errors);
+                ParseResult {
+                    ast: serde_json::json!({}),
+                    errors,
+                    warnings,
+                }
+            }
+        }
     }
 
-    fn parse_program(&self, source: &str) -> Result<PawnAST, Vec<String>> {
-        let pairs = PawnParserImpl::parse(Rule::program, source)
-            .map_err(|e| vec![format!("Parse error: {}", e)])?;
+    /// Parse source into a `PawnAST`. Directive lines left in the source are
+    /// collected into `directives`, macro uses are parsed as written.
+    pub fn parse_program(&self, source: &str) -> Result<PawnAST, Vec<String>> {
+        let program = PawnParserImpl::parse(Rule::program, source)
+            .map_err(|e| vec![format!("Parse error: {}", e)])?
+            .next()
+            .ok_or_else(|| vec!["Parse error: empty program".to_string()])?;
 
         let mut ast = PawnAST {
             functions: Vec::new(),
             callbacks: Vec::new(),
             variables: Vec::new(),
+            enums: Vec::new(),
             includes: Vec::new(),
             directives: Vec::new(),
+            macros_expanded: false,
+            written: None,
         };
 
         let mut errors = Vec::new();
 
-        for pair in pairs {
+        for pair in program.into_inner() {
             match pair.as_rule() {
-                Rule::function => match self.parse_function(pair) {
+                Rule::directive => ast.directives.push(parse_directive(&pair)),
+                Rule::function | Rule::function_declaration => match self.parse_function(pair) {
+                    // Public `On...` functions with a body are SA-MP callbacks
+                    Ok(func)
+                        if func.has_body
+                            && func.name.starts_with("On")
+                            && func.modifiers.iter().any(|m| m == "public") =>
+                    {
+                        ast.callbacks.push(CallbackNode {
+                            name: func.name,
+                            parameters: func.parameters,
+                            body: func.body,
+                            line: func.line,
+                            column: func.column,
+                        });
+                    }
                     Ok(func) => ast.functions.push(func),
                     Err(e) => errors.push(e),
                 },
-                Rule::callback => match self.parse_callback(pair) {
-                    Ok(cb) => ast.callbacks.push(cb),
-                    Err(e) => errors.push(e),
-                },
-                Rule::directive => match self.parse_directive(pair) {
-                    Ok(dir) => {
-                        if dir.kind == "include" {
-                            ast.includes.push(dir.content);
-                        } else {
-                            ast.directives.push(dir);
-                        }
-                    }
+                Rule::enum_declaration => match self.parse_enum(pair) {
+                    Ok(node) => ast.enums.push(node),
                     Err(e) => errors.push(e),
                 },
-                Rule::variable_declaration => match self.parse_variable(pair) {
-                    Ok(var) => ast.variables.push(var),
+                Rule::variable_declaration => match self.parse_variable_declaration(pair) {
+                    Ok(vars) => ast.variables.extend(vars),
                     Err(e) => errors.push(e),
                 },
                 _ => {}
@@ -130,11 +322,12 @@ impl PawnParser {
     }
 
     fn parse_function(&self, pair: pest::iterators::Pair<Rule>) -> Result<FunctionNode, String> {
-        let span = pair.as_span();
+        let (line, column) = line_col(&pair);
+        let has_body = pair.as_rule() == Rule::function;
         let inner = pair.into_inner();
 
         let mut modifiers = Vec::new();
-        let mut return_type = "void".to_string();
+        let mut return_tag = None;
         let mut name = String::new();
         let mut parameters = Vec::new();
         let mut body = Vec::new();
@@ -144,13 +337,11 @@ impl PawnParser {
                 Rule::function_modifier => {
                     modifiers.push(pair.as_str().to_string());
                 }
-                Rule::type_name => {
-                    return_type = pair.as_str().to_string();
+                Rule::tag => {
+                    return_tag = Some(strip_tag(pair.as_str()));
                 }
-                Rule::identifier => {
-                    if name.is_empty() {
-                        name = pair.as_str().to_string();
-                    }
+                Rule::identifier if name.is_empty() => {
+                    name = pair.as_str().to_string();
                 }
                 Rule::parameter_list => {
                     parameters = self.parse_parameter_list(pair)?;
@@ -162,226 +353,958 @@ impl PawnParser {
             }
         }
 
+        if name.is_empty() {
+            return Err(format!("Line {}: function without a name", line));
+        }
+
         Ok(FunctionNode {
             name,
             modifiers,
-            return_type,
+            return_type: return_tag.clone().unwrap_or_else(|| "void".to_string()),
+            return_tag,
             parameters,
             body,
-            line: span.start_pos().line_col().0,
-            column: span.start_pos().line_col().1,
+            has_body,
+            line,
+            column,
         })
     }
 
-    fn parse_callback(&self, pair: pest::iterators::Pair<Rule>) -> Result<CallbackNode, String> {
-        let span = pair.as_span();
-        let inner = pair.into_inner();
-
-        let mut name = String::new();
+    fn parse_parameter_list(
+        &self,
+        pair: pest::iterators::Pair<Rule>,
+    ) -> Result<Vec<ParameterNode>, String> {
         let mut parameters = Vec::new();
-        let mut body = Vec::new();
 
-        for pair in inner {
-            match pair.as_rule() {
-                Rule::identifier => {
-                    if name.is_empty() {
-                        name = format!("On{}", pair.as_str());
+        for param_pair in pair.into_inner() {
+            if param_pair.as_rule() != Rule::parameter {
+                continue;
+            }
+            let (line, column) = line_col(&param_pair);
+            let is_const = param_pair.as_str().trim_start().starts_with("const");
+
+            let mut tag = None;
+            let mut name = String::new();
+            let mut dimensions = Vec::new();
+            let mut is_reference = false;
+            let mut is_variadic = false;
+            let mut default_value = None;
+
+            for p in param_pair.into_inner() {
+                match p.as_rule() {
+                    Rule::reference => is_reference = true,
+                    Rule::tag => tag = Some(strip_tag(p.as_str())),
+                    // `{Float, _}:` accepts several tags; keep it as written
+                    Rule::tag_group => tag = Some(strip_tag(p.as_str())),
+                    Rule::variadic => {
+                        is_variadic = true;
+                        name = "...".to_string();
                     }
+                    Rule::identifier if name.is_empty() => {
+                        name = p.as_str().to_string();
+                    }
+                    Rule::dimension => dimensions.push(self.parse_dimension(p)),
+                    Rule::default_value => {
+                        let (line, _) = line_col(&p);
+                        default_value =
+                            Some(self.build_expression(next_pair(&mut p.into_inner(), line)?)?);
+                    }
+                    _ => {}
                 }
-                Rule::parameter_list => {
-                    parameters = self.parse_parameter_list(pair)?;
+            }
+
+            parameters.push(ParameterNode {
+                name,
+                param_type: tag.clone().unwrap_or_else(|| UNTAGGED_TYPE.to_string()),
+                tag,
+                dimensions,
+                is_reference,
+                is_const,
+                is_variadic,
+                default_value,
+                line,
+                column,
+            });
+        }
+
+        Ok(parameters)
+    }
+
+    fn parse_dimension(&self, pair: pest::iterators::Pair<Rule>) -> Option<String> {
+        let size = pair.as_str().trim();
+        let size = size
+            .strip_prefix('[')
+            .and_then(|s| s.strip_suffix(']'))
+            .unwrap_or("")
+            .trim();
+        if size.is_empty() {
+            None
+        } else {
+            Some(size.to_string())
+        }
+    }
+
+    fn parse_variable_declaration(
+        &self,
+        pair: pest::iterators::Pair<Rule>,
+    ) -> Result<Vec<VariableNode>, String> {
+        let mut storage = Vec::new();
+        let mut variables = Vec::new();
+
+        for p in pair.into_inner() {
+            match p.as_rule() {
+                Rule::declaration_keyword => storage.push(p.as_str().to_string()),
+                Rule::declarator => variables.push(self.parse_declarator(p, &storage)?),
+                _ => {}
+            }
+        }
+
+        Ok(variables)
+    }
+
+    fn parse_declarator(
+        &self,
+        pair: pest::iterators::Pair<Rule>,
+        storage: &[String],
+    ) -> Result<VariableNode, String> {
+        let (line, column) = line_col(&pair);
+
+        let mut tag = None;
+        let mut name = String::new();
+        let mut dimensions = Vec::new();
+        let mut initial_value = None;
+
+        for p in pair.into_inner() {
+            match p.as_rule() {
+                Rule::tag => tag = Some(strip_tag(p.as_str())),
+                Rule::identifier if name.is_empty() => {
+                    name = p.as_str().to_string();
                 }
-                Rule::block_statement => {
-                    body = self.parse_block(pair)?;
+                Rule::dimension => dimensions.push(self.parse_dimension(p)),
+                Rule::initializer => initial_value = Some(self.build_expression(p)?),
+                _ => {}
+            }
+        }
+
+        Ok(VariableNode {
+            name,
+            var_type: tag.clone().unwrap_or_else(|| UNTAGGED_TYPE.to_string()),
+            tag,
+            storage: storage.to_vec(),
+            dimensions,
+            initial_value,
+            line,
+            column,
+        })
+    }
+
+    fn parse_enum(&self, pair: pest::iterators::Pair<Rule>) -> Result<EnumNode, String> {
+        let (line, column) = line_col(&pair);
+
+        let mut name = None;
+        let mut tag = None;
+        let mut increment = None;
+        let mut items = Vec::new();
+
+        for p in pair.into_inner() {
+            match p.as_rule() {
+                Rule::tag => tag = Some(strip_tag(p.as_str())),
+                Rule::identifier => name = Some(p.as_str().to_string()),
+                Rule::enum_increment => {
+                    let text = p.as_str().trim();
+                    increment = Some(
+                        text.trim_start_matches('(')
+                            .trim_end_matches(')')
+                            .trim()
+                            .to_string(),
+                    );
                 }
+                Rule::enum_item => items.push(self.parse_enum_item(p)?),
                 _ => {}
             }
         }
 
-        Ok(CallbackNode {
+        Ok(EnumNode {
             name,
-            parameters,
-            body,
-            line: span.start_pos().line_col().0,
-            column: span.start_pos().line_col().1,
+            tag,
+            increment,
+            items,
+            line,
+            column,
         })
     }
 
-    fn parse_parameter_list(
+    fn parse_enum_item(&self, pair: pest::iterators::Pair<Rule>) -> Result<EnumItemNode, String> {
+        let (line, column) = line_col(&pair);
+
+        let mut name = String::new();
+        let mut tag = None;
+        let mut dimensions = Vec::new();
+        let mut value = None;
+
+        for p in pair.into_inner() {
+            match p.as_rule() {
+                Rule::tag => tag = Some(strip_tag(p.as_str())),
+                Rule::identifier => name = p.as_str().to_string(),
+                Rule::dimension => dimensions.push(self.parse_dimension(p)),
+                Rule::expression => value = Some(self.build_expression(p)?),
+                _ => {}
+            }
+        }
+
+        Ok(EnumItemNode {
+            name,
+            tag,
+            dimensions,
+            value,
+            line,
+            column,
+        })
+    }
+
+    fn parse_block(&self, pair: pest::iterators::Pair<Rule>) -> Result<Vec<StatementNode>, String> {
+        let mut statements = Vec::new();
+
+        for stmt_pair in pair.into_inner() {
+            if let Some(stmt) = self.parse_statement(stmt_pair)? {
+                statements.push(stmt);
+            }
+        }
+
+        Ok(statements)
+    }
+
+    fn parse_statement(
         &self,
         pair: pest::iterators::Pair<Rule>,
-    ) -> Result<Vec<ParameterNode>, String> {
-        let mut parameters = Vec::new();
+    ) -> Result<Option<StatementNode>, String> {
+        let (line, column) = line_col(&pair);
 
-        for param_pair in pair.into_inner() {
-            if param_pair.as_rule() == Rule::parameter {
-                let span = param_pair.as_span();
-                let inner = param_pair.into_inner();
+        let kind = match pair.as_rule() {
+            Rule::expression_statement => {
+                // `a = b;` keeps its own kind so generators can treat it specially
+                let is_assignment = pair
+                    .clone()
+                    .into_inner()
+                    .next()
+                    .and_then(|expr| expr.into_inner().next())
+                    .map(|assignment| assignment.into_inner().count() > 1)
+                    .unwrap_or(false);
+                if is_assignment {
+                    "assignment"
+                } else {
+                    "expression"
+                }
+            }
+            Rule::variable_declaration => "variable",
+            Rule::if_statement => "if",
+            Rule::while_statement => "while",
+            Rule::do_while_statement => "do_while",
+            Rule::for_statement => "for",
+            Rule::switch_statement => "switch",
+            Rule::return_statement => "return",
+            Rule::break_statement => "break",
+            Rule::continue_statement => "continue",
+            Rule::block_statement => "block",
+            Rule::directive => "directive",
+            Rule::empty_statement => return Ok(None),
+            other => return Err(format!("Line {}: unexpected {:?} in block", line, other)),
+        };
+
+        let content = serde_json::json!({ "raw": pair.as_str() });
+        Ok(Some(StatementNode {
+            kind: kind.to_string(),
+            content,
+            node: self.build_statement_node(pair)?,
+            line,
+            column,
+        }))
+    }
 
-                let mut param_type = String::new();
-                let mut name = String::new();
-                let mut default_value = None;
+    fn build_statement(&self, pair: Pair<Rule>) -> Result<UniversalStatement, String> {
+        let (line, column) = line_col(&pair);
+        Ok(UniversalStatement {
+            id: format!("stmt_{}_{}", line, column),
+            node: self.build_statement_node(pair)?,
+            position: SourcePosition { line, column },
+        })
+    }
 
+    /// Statements of a block or case clause; stray `;` are dropped.
+    fn build_statement_list(&self, pairs: Pairs<Rule>) -> Result<Vec<UniversalStatement>, String> {
+        let mut statements = Vec::new();
+        for pair in pairs {
+            if pair.as_rule() != Rule::empty_statement {
+                statements.push(self.build_statement(pair)?);
+            }
+        }
+        Ok(statements)
+    }
+
+    fn build_statement_node(&self, pair: Pair<Rule>) -> Result<UniversalStatementNode, String> {
+        let (line, _) = line_col(&pair);
+        let rule = pair.as_rule();
+        let text = pair.as_str();
+        let mut inner = pair.into_inner();
+
+        let node = match rule {
+            Rule::expression_statement => UniversalStatementNode::Expression {
+                expression: self.build_expression(next_pair(&mut inner, line)?)?,
+            },
+            Rule::variable_declaration => {
+                let mut storage = Vec::new();
+                let mut declarations = Vec::new();
+                for p in inner {
+                    match p.as_rule() {
+                        Rule::declaration_keyword => storage.push(p.as_str().to_string()),
+                        Rule::declarator => declarations.push(self.build_declarator(p)?),
+                        _ => {}
+                    }
+                }
+                UniversalStatementNode::Variable {
+                    storage,
+                    declarations,
+                }
+            }
+            Rule::if_statement => {
+                let condition = self.build_expression(next_pair(&mut inner, line)?)?;
+                let then_branch = self.build_statement(next_pair(&mut inner, line)?)?;
+                let else_branch = match inner.next() {
+                    Some(p) => Some(Box::new(self.build_statement(p)?)),
+                    None => None,
+                };
+                UniversalStatementNode::If {
+                    condition,
+                    then_branch: Box::new(then_branch),
+                    else_branch,
+                }
+            }
+            Rule::while_statement => {
+                let condition = self.build_expression(next_pair(&mut inner, line)?)?;
+                let body = self.build_statement(next_pair(&mut inner, line)?)?;
+                UniversalStatementNode::While {
+                    condition,
+                    body: Box::new(body),
+                }
+            }
+            Rule::do_while_statement => {
+                let body = self.build_statement(next_pair(&mut inner, line)?)?;
+                let condition = self.build_expression(next_pair(&mut inner, line)?)?;
+                UniversalStatementNode::DoWhile {
+                    body: Box::new(body),
+                    condition,
+                }
+            }
+            Rule::for_statement => {
+                let mut init = None;
+                let mut condition = None;
+                let mut update = Vec::new();
+                let mut body = None;
                 for p in inner {
                     match p.as_rule() {
-                        Rule::type_name => {
-                            param_type = p.as_str().to_string();
+                        Rule::for_init => {
+                            let clause = next_pair(&mut p.into_inner(), line)?;
+                            init = Some(if clause.as_rule() == Rule::for_declaration {
+                                UniversalForInit::Declaration {
+                                    declarations: clause
+                                        .into_inner()
+                                        .map(|d| self.build_declarator(d))
+                                        .collect::<Result<_, _>>()?,
+                                }
+                            } else {
+                                UniversalForInit::Expressions {
+                                    expressions: self.build_expression_list(clause)?,
+                                }
+                            });
                         }
-                        Rule::identifier => {
-                            if name.is_empty() {
-                                name = p.as_str().to_string();
-                            }
+                        Rule::for_condition => {
+                            condition =
+                                Some(self.build_expression(next_pair(&mut p.into_inner(), line)?)?)
                         }
-                        Rule::expression => {
-                            default_value = Some(p.as_str().to_string());
+                        Rule::for_update => {
+                            update =
+                                self.build_expression_list(next_pair(&mut p.into_inner(), line)?)?
                         }
-                        _ => {}
+                        _ => body = Some(self.build_statement(p)?),
                     }
                 }
-
-                parameters.push(ParameterNode {
-                    name,
-                    param_type,
-                    default_value,
-                    line: span.start_pos().line_col().0,
-                    column: span.start_pos().line_col().1,
-                });
+                UniversalStatementNode::For {
+                    init,
+                    condition,
+                    update,
+                    body: Box::new(
+                        body.ok_or_else(|| format!("Line {}: for without a body", line))?,
+                    ),
+                }
             }
-        }
+            Rule::switch_statement => {
+                let subject = self.build_expression(next_pair(&mut inner, line)?)?;
+                let mut cases = Vec::new();
+                for clause in inner {
+                    let mut clause_pairs = clause.into_inner();
+                    let label = next_pair(&mut clause_pairs, line)?;
+                    let values = if label.as_rule() == Rule::case_label {
+                        label
+                            .into_inner()
+                            .map(|v| self.build_case_value(v))
+                            .collect::<Result<_, _>>()?
+                    } else {
+                        Vec::new()
+                    };
+                    cases.push(UniversalCase {
+                        values,
+                        body: self.build_statement_list(clause_pairs)?,
+                    });
+                }
+                UniversalStatementNode::Switch { subject, cases }
+            }
+            Rule::return_statement => UniversalStatementNode::Return {
+                value: match inner.next() {
+                    Some(p) => Some(self.build_expression(p)?),
+                    None => None,
+                },
+            },
+            Rule::break_statement => UniversalStatementNode::Break,
+            Rule::continue_statement => UniversalStatementNode::Continue,
+            Rule::block_statement => UniversalStatementNode::Block {
+                statements: self.build_statement_list(inner)?,
+            },
+            Rule::empty_statement => UniversalStatementNode::Empty,
+            Rule::directive => UniversalStatementNode::Directive {
+                content: text.trim_end().to_string(),
+            },
+            other => return Err(format!("Line {}: unexpected {:?} in block", line, other)),
+        };
 
-        Ok(parameters)
+        Ok(node)
     }
 
-    fn parse_variable(&self, pair: pest::iterators::Pair<Rule>) -> Result<VariableNode, String> {
-        let span = pair.as_span();
-        let inner = pair.into_inner();
-
-        let mut var_type = String::new();
+    fn build_declarator(&self, pair: Pair<Rule>) -> Result<UniversalDeclarator, String> {
+        let mut tag = None;
         let mut name = String::new();
-        let mut initial_value = None;
+        let mut dimensions = Vec::new();
+        let mut initializer = None;
 
-        for p in inner {
+        for p in pair.into_inner() {
             match p.as_rule() {
-                Rule::type_name => {
-                    var_type = p.as_str().to_string();
-                }
-                Rule::identifier => {
-                    if name.is_empty() {
-                        name = p.as_str().to_string();
-                    }
-                }
-                Rule::expression => {
-                    initial_value = Some(p.as_str().to_string());
-                }
+                Rule::tag => tag = Some(strip_tag(p.as_str())),
+                Rule::identifier if name.is_empty() => name = p.as_str().to_string(),
+                Rule::dimension => dimensions.push(self.parse_dimension(p)),
+                Rule::initializer => initializer = Some(self.build_expression(p)?),
                 _ => {}
             }
         }
 
-        Ok(VariableNode {
+        let type_name = tag.clone().unwrap_or_else(|| UNTAGGED_TYPE.to_string());
+        Ok(UniversalDeclarator {
+            var_type: pawn_type(&type_name, &tag, &dimensions),
             name,
-            var_type,
-            initial_value,
-            line: span.start_pos().line_col().0,
-            column: span.start_pos().line_col().1,
+            initializer,
         })
     }
 
-    fn parse_block(&self, pair: pest::iterators::Pair<Rule>) -> Result<Vec<StatementNode>, String> {
-        let mut statements = Vec::new();
+    fn build_expression_list(&self, pair: Pair<Rule>) -> Result<Vec<UniversalExpression>, String> {
+        pair.into_inner()
+            .map(|p| self.build_expression(p))
+            .collect()
+    }
 
-        for stmt_pair in pair.into_inner() {
-            if stmt_pair.as_rule() == Rule::statement {
-                match self.parse_statement(stmt_pair) {
-                    Ok(stmt) => statements.push(stmt),
-                    Err(e) => return Err(e),
+    fn build_case_value(&self, pair: Pair<Rule>) -> Result<UniversalCaseValue, String> {
+        let (line, _) = line_col(&pair);
+        let mut inner = pair.into_inner();
+        let value = self.build_case_expression(next_pair(&mut inner, line)?)?;
+        let range_end = match inner.next() {
+            Some(p) => Some(self.build_case_expression(p)?),
+            None => None,
+        };
+        Ok(UniversalCaseValue { value, range_end })
+    }
+
+    fn build_case_expression(&self, pair: Pair<Rule>) -> Result<UniversalExpression, String> {
+        let (line, _) = line_col(&pair);
+        let mut terms = Vec::new();
+        let mut operators = Vec::new();
+
+        for p in pair.into_inner() {
+            match p.as_rule() {
+                Rule::case_operator => operators.push(self.binary_operator(&p)?),
+                Rule::case_term => {
+                    let negative = p.as_str().trim_start().starts_with('-');
+                    let term = self.build_expression(next_pair(&mut p.into_inner(), line)?)?;
+                    terms.push(if negative {
+                        UniversalExpression::Unary {
+                            operator: UnaryOperator::Negate,
+                            operand: Box::new(term),
+                        }
+                    } else {
+                        term
+                    });
                 }
+                _ => {}
             }
         }
 
-        Ok(statements)
+        if terms.len() != operators.len() + 1 {
+            return Err(format!("Line {}: malformed case label", line));
+        }
+        Ok(fold_binary(terms, operators))
     }
 
-    fn parse_statement(&self, pair: pest::iterators::Pair<Rule>) -> Result<StatementNode, String> {
-        let span = pair.as_span();
-        let inner = pair.into_inner().next();
-
-        if let Some(inner_pair) = inner {
-            let kind = match inner_pair.as_rule() {
-                Rule::expression_statement => "expression",
-                Rule::variable_declaration => "variable",
-                Rule::assignment_statement => "assignment",
-                Rule::if_statement => "if",
-                Rule::while_statement => "while",
-                Rule::for_statement => "for",
-                Rule::return_statement => "return",
-                Rule::break_statement => "break",
-                Rule::continue_statement => "continue",
-                Rule::block_statement => "block",
-                _ => "unknown",
-            };
-
-            Ok(StatementNode {
-                kind: kind.to_string(),
-                content: serde_json::json!({ "raw": inner_pair.as_str() }),
-                line: span.start_pos().line_col().0,
-                column: span.start_pos().line_col().1,
-            })
-        } else {
-            Err("Empty statement".to_string())
-        }
+    fn binary_operator(&self, pair: &Pair<Rule>) -> Result<BinaryOperator, String> {
+        BinaryOperator::from_symbol(pair.as_str().trim()).ok_or_else(|| {
+            format!(
+                "Line {}: unknown operator `{}`",
+                line_col(pair).0,
+                pair.as_str()
+            )
+        })
     }
 
-    fn parse_directive(&self, pair: pest::iterators::Pair<Rule>) -> Result<DirectiveNode, String> {
-        let span = pair.as_span();
-        let content = pair.as_str().to_string();
-        let inner = pair.into_inner();
+    fn build_expression(&self, pair: Pair<Rule>) -> Result<UniversalExpression, String> {
+        let (line, _) = line_col(&pair);
+        let rule = pair.as_rule();
+        let text = pair.as_str().trim().to_string();
+        let mut inner = pair.into_inner();
 
-        let kind = if let Some(first) = inner.peek() {
-            match first.as_rule() {
-                Rule::include => "include",
-                Rule::define => "define",
-                Rule::pragma => "pragma",
-                _ => "unknown",
+        let literal = |literal_type| UniversalExpression::Literal {
+            literal_type,
+            value: text.clone(),
+        };
+
+        let expression = match rule {
+            Rule::expression | Rule::parenthesized_expression | Rule::initializer => {
+                self.build_expression(next_pair(&mut inner, line)?)?
+            }
+            Rule::assignment_expression => {
+                let target = self.build_expression(next_pair(&mut inner, line)?)?;
+                match inner.next() {
+                    Some(op) => {
+                        let operator = AssignmentOperator::from_symbol(op.as_str().trim())
+                            .ok_or_else(|| {
+                                format!("Line {}: unknown operator `{}`", line, op.as_str())
+                            })?;
+                        let value = self.build_expression(next_pair(&mut inner, line)?)?;
+                        UniversalExpression::Assignment {
+                            operator,
+                            target: Box::new(target),
+                            value: Box::new(value),
+                        }
+                    }
+                    None => target,
+                }
+            }
+            Rule::conditional_expression => {
+                let condition = self.build_expression(next_pair(&mut inner, line)?)?;
+                match inner.next() {
+                    Some(when_true) => {
+                        let when_true = self.build_expression(when_true)?;
+                        let when_false = self.build_expression(next_pair(&mut inner, line)?)?;
+                        UniversalExpression::Conditional {
+                            condition: Box::new(condition),
+                            when_true: Box::new(when_true),
+                            when_false: Box::new(when_false),
+                        }
+                    }
+                    None => condition,
+                }
+            }
+            Rule::logical_or_expression
+            | Rule::logical_and_expression
+            | Rule::bitwise_or_expression
+            | Rule::bitwise_xor_expression
+            | Rule::bitwise_and_expression
+            | Rule::equality_expression
+            | Rule::relational_expression
+            | Rule::shift_expression
+            | Rule::additive_expression
+            | Rule::multiplicative_expression => {
+                let mut left = self.build_expression(next_pair(&mut inner, line)?)?;
+                while let Some(op) = inner.next() {
+                    let operator = self.binary_operator(&op)?;
+                    let right = self.build_expression(next_pair(&mut inner, line)?)?;
+                    left = UniversalExpression::Binary {
+                        operator,
+                        left: Box::new(left),
+                        right: Box::new(right),
+                    };
+                }
+                left
+            }
+            Rule::unary_expression => {
+                let first = next_pair(&mut inner, line)?;
+                if first.as_rule() == Rule::unary_operator {
+                    let operator =
+                        UnaryOperator::from_symbol(first.as_str().trim()).ok_or_else(|| {
+                            format!("Line {}: unknown operator `{}`", line, first.as_str())
+                        })?;
+                    let operand = self.build_expression(next_pair(&mut inner, line)?)?;
+                    UniversalExpression::Unary {
+                        operator,
+                        operand: Box::new(operand),
+                    }
+                } else {
+                    self.build_expression(first)?
+                }
+            }
+            Rule::tag_override => {
+                let tag = strip_tag(next_pair(&mut inner, line)?.as_str());
+                let operand = self.build_expression(next_pair(&mut inner, line)?)?;
+                UniversalExpression::TagOverride {
+                    tag,
+                    operand: Box::new(operand),
+                }
+            }
+            Rule::sizeof_expression => UniversalExpression::Sizeof {
+                operand: next_pair(&mut inner, line)?.as_str().trim().to_string(),
+            },
+            Rule::tagof_expression => UniversalExpression::Tagof {
+                operand: next_pair(&mut inner, line)?.as_str().trim().to_string(),
+            },
+            Rule::postfix_expression => {
+                let mut expression = self.build_expression(next_pair(&mut inner, line)?)?;
+                for suffix in inner {
+                    expression = match suffix.as_rule() {
+                        Rule::call_arguments => UniversalExpression::Call {
+                            callee: Box::new(expression),
+                            arguments: suffix
+                                .into_inner()
+                                .map(|argument| self.build_argument(argument))
+                                .collect::<Result<_, _>>()?,
+                        },
+                        Rule::index_suffix => UniversalExpression::Index {
+                            array: Box::new(expression),
+                            index: Box::new(
+                                self.build_expression(next_pair(&mut suffix.into_inner(), line)?)?,
+                            ),
+                        },
+                        _ => UniversalExpression::Postfix {
+                            operator: UnaryOperator::from_symbol(suffix.as_str().trim())
+                                .ok_or_else(|| {
+                                    format!("Line {}: unknown operator `{}`", line, suffix.as_str())
+                                })?,
+                            operand: Box::new(expression),
+                        },
+                    };
+                }
+                expression
+            }
+            Rule::array_initializer => {
+                let mut elements = Vec::new();
+                let mut ellipsis = false;
+                for p in inner {
+                    if p.as_rule() == Rule::ellipsis {
+                        ellipsis = true;
+                    } else {
+                        elements.push(self.build_expression(p)?);
+                    }
+                }
+                UniversalExpression::ArrayInitializer { elements, ellipsis }
+            }
+            Rule::identifier => UniversalExpression::Identifier { name: text.clone() },
+            Rule::hex => literal(LiteralType::Hex),
+            Rule::binary => literal(LiteralType::Binary),
+            Rule::float => literal(LiteralType::Float),
+            Rule::integer => literal(LiteralType::Integer),
+            Rule::character => literal(LiteralType::Character),
+            Rule::string => literal(LiteralType::String),
+            Rule::boolean => literal(LiteralType::Boolean),
+            other => {
+                return Err(format!(
+                    "Line {}: unexpected {:?} in expression",
+                    line, other
+                ))
             }
-        } else {
-            "unknown"
         };
 
-        Ok(DirectiveNode {
-            kind: kind.to_string(),
-            content,
-            line: span.start_pos().line_col().0,
-            column: span.start_pos().line_col().1,
-        })
+        Ok(expression)
+    }
+
+    fn build_argument(&self, pair: Pair<Rule>) -> Result<UniversalArgument, String> {
+        let (line, _) = line_col(&pair);
+        let first = next_pair(&mut pair.into_inner(), line)?;
+        if first.as_rule() == Rule::named_argument {
+            let mut named = first.into_inner();
+            let name = next_pair(&mut named, line)?.as_str().to_string();
+            let value = self.build_expression(next_pair(&mut named, line)?)?;
+            Ok(UniversalArgument {
+                name: Some(name),
+                value,
+            })
+        } else {
+            Ok(UniversalArgument {
+                name: None,
+                value: self.build_expression(first)?,
+            })
+        }
+    }
+}
+
+impl Default for PawnParser {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 impl ParserTrait for PawnParser {
     fn parse(&self, source: &str) -> Result<ParseResult, String> {
-        match self.parse_program(source) {
-            Ok(ast) => {
-                let ast_json = serde_json::to_value(&ast)
-                    .map_err(|e| format!("Failed to serialize AST: {}", e))?;
+        Ok(self.parse_with_path(source, None))
+    }
 
-                Ok(ParseResult {
-                    ast: ast_json,
-                    errors: Vec::new(),
-                    warnings: Vec::new(),
-                })
-            }
-            Err(errors) => {
-                let ast_json = serde_json::json!({});
+    fn language(&self) -> &str {
+        "pawn"
+    }
+}
 
-                Ok(ParseResult {
-                    ast: ast_json,
-                    errors,
-                    warnings: Vec::new(),
-                })
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blueprint::pawn_generator::PawnGenerator;
+    use std::path::PathBuf;
+
+    fn corpus_dir() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("src/blueprint/corpus")
+    }
+
+    fn parse_corpus(name: &str) -> PawnAST {
+        let parser = PawnParser::with_options(PreprocessorOptions {
+            include_paths: vec![corpus_dir().join("include")],
+            predefined: Vec::new(),
+        });
+        let result = parser.parse_file(corpus_dir().join(name)).unwrap();
+        assert!(result.errors.is_empty(), "{}: {:?}", name, result.errors);
+        serde_json::from_value(result.ast).unwrap()
+    }
+
+    #[test]
+    fn test_corpus_parses_cleanly() {
+        for entry in std::fs::read_dir(corpus_dir()).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().and_then(|e| e.to_str()) == Some("pwn") {
+                parse_corpus(path.file_name().unwrap().to_str().unwrap());
             }
         }
     }
 
-    fn language(&self) -> &str {
-        "pawn"
+    #[test]
+    fn test_gamemode_structure() {
+        let ast = parse_corpus("gamemode.pwn");
+
+        let includes: Vec<(&str, bool)> = ast
+            .includes
+            .iter()
+            .map(|include| (include.name.as_str(), include.system))
+            .collect();
+        assert_eq!(includes, vec![("a_samp", true), ("include/colors", false)]);
+
+        let player_enum = ast
+            .enums
+            .iter()
+            .find(|e| e.name.as_deref() == Some("E_PLAYER"))
+            .unwrap();
+        let item_names: Vec<&str> = player_enum.items.iter().map(|i| i.name.as_str()).collect();
+        assert_eq!(item_names, vec!["pName", "pHealth", "pScore"]);
+        assert_eq!(
+            player_enum.items[0].dimensions,
+            vec![Some("24".to_string())]
+        );
+        assert_eq!(player_enum.items[1].tag.as_deref(), Some("Float"));
+
+        let player_data = ast
+            .variables
+            .iter()
+            .find(|v| v.name == "gPlayerData")
+            .unwrap();
+        assert_eq!(
+            player_data.dimensions,
+            vec![
+                Some("500".to_string()),
+                Some("E_PLAYER".to_string())
+            ]
+        );
+
+        let callback_names: Vec<&str> = ast.callbacks.iter().map(|c| c.name.as_str()).collect();
+        assert!(callback_names.contains(&"OnGameModeInit"));
+        assert!(callback_names.contains(&"OnDialogResponse"));
+
+        let distance = ast
+            .functions
+            .iter()
+            .find(|f| f.name == "GetDistanceBetween")
+            .unwrap();
+        assert_eq!(distance.return_tag.as_deref(), Some("Float"));
+        assert_eq!(distance.modifiers, vec!["stock"]);
+        assert!(distance
+            .parameters
+            .iter()
+            .all(|p| p.tag.as_deref() == Some("Float")));
+
+        let dialog = ast
+            .callbacks
+            .iter()
+            .find(|c| c.name == "OnDialogResponse")
+            .unwrap();
+        assert!(dialog.body.iter().any(|s| s.kind == "switch"));
+    }
+
+    #[test]
+    fn test_prototypes_and_natives() {
+        let ast = parse_corpus("gamemode.pwn");
+
+        let forward = ast
+            .functions
+            .iter()
+            .find(|f| f.name == "OnHouseLoaded")
+            .unwrap();
+        assert!(!forward.has_body);
+        assert_eq!(forward.modifiers, vec!["forward"]);
+
+        let native = ast
+            .functions
+            .iter()
+            .find(|f| f.name == "CustomNative")
+            .unwrap();
+        assert!(!native.has_body);
+        let format_param = &native.parameters[0];
+        assert!(format_param.is_const);
+        assert_eq!(format_param.dimensions, vec![None]);
+        assert!(native.parameters[1].is_variadic);
+
+        let swap = ast.functions.iter().find(|f| f.name == "Swap").unwrap();
+        assert!(swap.parameters.iter().all(|p| p.is_reference));
+    }
+
+    #[test]
+    fn test_literals_and_expressions() {
+        let ast = parse_corpus("expressions.pwn");
+
+        let generator = PawnGenerator::new();
+        let constants: Vec<String> = ast
+            .variables
+            .iter()
+            .filter_map(|v| v.initial_value.as_ref())
+            .map(|value| generator.generate_expression(value).unwrap())
+            .collect();
+        assert!(constants.iter().any(|c| c == "0xFF0000AA"));
+        assert!(constants.iter().any(|c| c == "'\\n'"));
+        assert!(constants.iter().any(|c| c == "0b1010"));
+        assert!(constants.iter().any(|c| c == "{1, 2, ...}"));
+
+        let fill = ast.variables.iter().find(|v| v.name == "gFill").unwrap();
+        assert!(matches!(
+            fill.initial_value,
+            Some(UniversalExpression::ArrayInitializer { ellipsis: true, .. })
+        ));
+
+        let main = ast.functions.iter().find(|f| f.name == "main").unwrap();
+        assert!(main.body.iter().any(|s| s.kind == "do_while"));
+        assert!(main.body.iter().any(|s| s.kind == "assignment"));
+    }
+
+    #[test]
+    fn test_macros_are_kept_as_written() {
+        let parsed = parse_corpus("gamemode.pwn");
+        assert!(parsed.macros_expanded);
+        let ast = *parsed.written.unwrap();
+        assert!(!ast.macros_expanded);
+
+        // MAX_HOUSES comes from a #define, COLOR_RED from an included file
+        let houses = ast
+            .variables
+            .iter()
+            .find(|v| v.name == "gHouseIds")
+            .unwrap();
+        assert_eq!(houses.dimensions, vec![Some("MAX_HOUSES".to_string())]);
+        let color = ast
+            .variables
+            .iter()
+            .find(|v| v.name == "gWarningColor")
+            .unwrap();
+        assert!(matches!(
+            &color.initial_value,
+            Some(UniversalExpression::Identifier { name }) if name == "COLOR_RED"
+        ));
+        // Both branches of `#if defined FILTERSCRIPT` are kept
+        assert!(ast.callbacks.iter().any(|c| c.name == "OnFilterScriptInit"));
+        assert!(ast.callbacks.iter().any(|c| c.name == "OnGameModeInit"));
+
+        let kinds: Vec<&str> = ast.directives.iter().map(|d| d.kind.as_str()).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                "include", "include", "define", "define", "define", "define", "define", "if",
+                "define", "endif", "if", "else", "endif"
+            ]
+        );
+        let is_valid_house = &ast.directives[5];
+        assert_eq!(
+            is_valid_house.content,
+            "#define IsValidHouse(%0) \\\n\t((%0) >= 0 && (%0) < MAX_HOUSES)"
+        );
+        assert_eq!((is_valid_house.line, is_valid_house.column), (10, 1));
+    }
+
+    #[test]
+    fn test_inactive_branches_are_not_in_the_tree() {
+        let parsed = parse_corpus("gamemode.pwn");
+        let houses = parsed
+            .variables
+            .iter()
+            .find(|v| v.name == "gHouseIds")
+            .unwrap();
+        assert_eq!(houses.dimensions, vec![Some("32".to_string())]);
+        assert!(parsed.callbacks.iter().any(|c| c.name == "OnGameModeInit"));
+        assert!(!parsed.callbacks.iter().any(|c| c.name == "OnFilterScriptInit"));
+        assert!(parsed.functions.iter().any(|f| f.name == "cmd_help" && f.has_body));
+
+        let source = "#if defined DEBUG\npublic OnGameModeInit()\n{\n\treturn 0;\n}\n\
+                      #else\npublic OnGameModeInit()\n{\n\treturn 1;\n}\n#endif\n";
+        let result = PawnParser::new().parse(source).unwrap();
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        let ast: PawnAST = serde_json::from_value(result.ast).unwrap();
+        assert_eq!(ast.callbacks.len(), 1);
+        assert_eq!(ast.callbacks[0].line, 7);
+        assert_eq!(ast.written.unwrap().callbacks.len(), 2);
+    }
+
+    #[test]
+    fn test_macros_are_expanded_when_needed_to_parse() {
+        let source = "#define MAX_ITEMS 8\n\
+                      #define loop(%0) for (new %0 = 0; %0 < MAX_ITEMS; %0++)\n\
+                      main()\n{\n\tloop(i) print(\"item\");\n}\n";
+        let result = PawnParser::new().parse(source).unwrap();
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        assert_eq!(result.warnings.len(), 1);
+
+        let ast: PawnAST = serde_json::from_value(result.ast).unwrap();
+        assert!(ast.macros_expanded);
+        assert!(ast.written.is_none());
+        assert_eq!(ast.directives.len(), 2);
+        let UniversalStatementNode::For {
+            condition: Some(condition),
+            ..
+        } = &ast.functions[0].body[0].node
+        else {
+            panic!("expected the expanded for loop");
+        };
+        assert_eq!(
+            PawnGenerator::new().generate_expression(condition).unwrap(),
+            "i < 8"
+        );
+    }
+
+    #[test]
+    fn test_plain_object_list_still_parses() {
+        let parser = PawnParser::new();
+        let result = parser
+            .parse("CreateDynamicObject(19458, 1392.60083, -1302.87585, 1400.26978, 0.0, 90.0, 0.0, -1, -1, -1, 100.00, 100.00);")
+            .unwrap();
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+    }
+
+    #[test]
+    fn test_syntax_error_is_reported() {
+        let parser = PawnParser::new();
+        let result = parser.parse("main() { new x = ; }").unwrap();
+        assert_eq!(result.errors.len(), 1);
     }
 }
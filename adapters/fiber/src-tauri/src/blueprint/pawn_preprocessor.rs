@@ -0,0 +1,1181 @@
+/**
+ * Pawn preprocessor
+ *
+ * Resolves `#include`/`#tryinclude`, expands `#define` macros (object-like,
+ * `%0`..`%9` parameterised and pattern ones such as `CMD:%0(%1)`) and
+ * evaluates `#if`/`#elseif`/`#else` blocks before the source reaches the pest
+ * grammar.
+ *
+ * Included files contribute macros and conditional state; their code is not
+ * spliced into the output, so the resulting AST only describes the file being
+ * edited. Every directive line is replaced by an empty line, which keeps line
+ * numbers of the preprocessed source identical to the original.
+ */
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::blueprint::pawn_parser::DirectiveNode;
+
+const MAX_INCLUDE_DEPTH: usize = 32;
+const MAX_EXPANSION_DEPTH: usize = 64;
+const INCLUDE_EXTENSIONS: [&str; 3] = ["", ".inc", ".pwn"];
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PreprocessorOptions {
+    /// Directories searched for `#include <name>` and, after the including
+    /// file's own directory, for `#include "name"`.
+    pub include_paths: Vec<PathBuf>,
+    /// Macros defined before the first line is read (`-D` on the compiler).
+    pub predefined: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MacroDefinition {
+    pub name: String,
+    /// Number of `%n` parameters, `None` for object-like macros.
+    pub arity: Option<usize>,
+    /// Text that must follow the name for a pattern macro, e.g. `:%0(%1)`
+    /// for `#define CMD:%0(%1) ...`.
+    #[serde(default)]
+    pub pattern: Option<String>,
+    pub body: String,
+    pub file: Option<String>,
+    pub line: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IncludeRecord {
+    pub name: String,
+    pub resolved_path: Option<String>,
+    pub system: bool,
+    pub line: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreprocessedSource {
+    pub source: String,
+    pub includes: Vec<IncludeRecord>,
+    pub directives: Vec<DirectiveNode>,
+    pub macros: Vec<MacroDefinition>,
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ConditionFrame {
+    parent_active: bool,
+    active: bool,
+    taken: bool,
+    seen_else: bool,
+}
+
+pub struct PawnPreprocessor {
+    options: PreprocessorOptions,
+    macros: HashMap<String, MacroDefinition>,
+    included: HashSet<PathBuf>,
+    includes: Vec<IncludeRecord>,
+    directives: Vec<DirectiveNode>,
+    errors: Vec<String>,
+    warnings: Vec<String>,
+}
+
+impl PawnPreprocessor {
+    pub fn new(options: PreprocessorOptions) -> Self {
+        let mut macros = HashMap::new();
+        for (name, body) in &options.predefined {
+            macros.insert(
+                name.clone(),
+                MacroDefinition {
+                    name: name.clone(),
+                    arity: None,
+                    pattern: None,
+                    body: body.clone(),
+                    file: None,
+                    line: 0,
+                },
+            );
+        }
+
+        Self {
+            options,
+            macros,
+            included: HashSet::new(),
+            includes: Vec::new(),
+            directives: Vec::new(),
+            errors: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Preprocess in-memory source. `path` is used to resolve `#include "..."`
+    /// relative to the file and to skip the file if it includes itself.
+    pub fn process(mut self, source: &str, path: Option<&Path>) -> PreprocessedSource {
+        if let Some(path) = path {
+            if let Ok(canonical) = path.canonicalize() {
+                self.included.insert(canonical);
+            }
+        }
+
+        let output = self.process_file(source, path, 0, true);
+
+        let mut macros: Vec<MacroDefinition> = self.macros.into_values().collect();
+        macros.sort_by(|a, b| a.name.cmp(&b.name));
+
+        PreprocessedSource {
+            source: output,
+            includes: self.includes,
+            directives: self.directives,
+            macros,
+            errors: self.errors,
+            warnings: self.warnings,
+        }
+    }
+
+    fn process_file(
+        &mut self,
+        source: &str,
+        path: Option<&Path>,
+        depth: usize,
+        is_root: bool,
+    ) -> String {
+        let file_label = path.map(|p| p.display().to_string());
+        let stripped = strip_comments(source);
+        let lines: Vec<&str> = stripped.split('\n').collect();
+
+        let mut output = String::with_capacity(source.len());
+        let mut stack: Vec<ConditionFrame> = Vec::new();
+        let mut index = 0;
+
+        while index < lines.len() {
+            let line_number = index + 1;
+
+            // Join `\` continuation lines; the joined lines are emitted empty
+            let mut logical = lines[index].trim_end_matches('\r').to_string();
+            let mut consumed = 1;
+            while logical.ends_with('\\') && index + consumed < lines.len() {
+                logical.pop();
+                logical.push(' ');
+                logical.push_str(lines[index + consumed].trim_end_matches('\r'));
+                consumed += 1;
+            }
+
+            let active = stack.last().map(|f| f.active).unwrap_or(true);
+            let trimmed = logical.trim_start();
+
+            if let Some(directive) = trimmed.strip_prefix('#') {
+                let column = logical.len() - trimmed.len() + 1;
+                let stop = self.handle_directive(
+                    directive.trim(),
+                    &mut stack,
+                    active,
+                    path,
+                    depth,
+                    is_root,
+                    line_number,
+                    column,
+                    file_label.as_deref(),
+                );
+                for _ in 0..consumed {
+                    output.push('\n');
+                }
+                if stop {
+                    // `#endinput` inside an include guard leaves its `#if` open
+                    stack.clear();
+                    break;
+                }
+            } else {
+                if active && is_root {
+                    output.push_str(&self.expand(&logical, line_number));
+                }
+                for _ in 0..consumed {
+                    output.push('\n');
+                }
+            }
+
+            index += consumed;
+        }
+
+        if !stack.is_empty() {
+            self.errors.push(format!(
+                "{}: unterminated #if block ({} still open at end of file)",
+                file_label.as_deref().unwrap_or("<source>"),
+                stack.len()
+            ));
+        }
+
+        // Keep the exact line count of the input
+        if output.ends_with('\n') {
+            output.pop();
+        }
+        output
+    }
+
+    /// Returns `true` when the rest of the file must be skipped (`#endinput`).
+    #[allow(clippy::too_many_arguments)]
+    fn handle_directive(
+        &mut self,
+        directive: &str,
+        stack: &mut Vec<ConditionFrame>,
+        active: bool,
+        path: Option<&Path>,
+        depth: usize,
+        is_root: bool,
+        line: usize,
+        column: usize,
+        file_label: Option<&str>,
+    ) -> bool {
+        let (keyword, rest) = split_directive(directive);
+        let location = format!("{}:{}", file_label.unwrap_or("<source>"), line);
+
+        match keyword {
+            "if" => {
+                let value = active && self.evaluate_condition(rest, &location);
+                stack.push(ConditionFrame {
+                    parent_active: active,
+                    active: value,
+                    taken: value,
+                    seen_else: false,
+                });
+            }
+            "elseif" => match stack.last_mut() {
+                Some(frame) if !frame.seen_else => {
+                    let value = frame.parent_active
+                        && !frame.taken
+                        && self.evaluate_condition(rest, &location);
+                    frame.active = value;
+                    frame.taken |= value;
+                }
+                _ => self
+                    .errors
+                    .push(format!("{}: #elseif without matching #if", location)),
+            },
+            "else" => match stack.last_mut() {
+                Some(frame) if !frame.seen_else => {
+                    frame.active = frame.parent_active && !frame.taken;
+                    frame.taken = true;
+                    frame.seen_else = true;
+                }
+                _ => self
+                    .errors
+                    .push(format!("{}: #else without matching #if", location)),
+            },
+            "endif" => {
+                if stack.pop().is_none() {
+                    self.errors
+                        .push(format!("{}: #endif without matching #if", location));
+                }
+            }
+            _ if !active => {}
+            "include" | "tryinclude" => {
+                self.handle_include(rest, keyword == "tryinclude", path, depth, is_root, line);
+            }
+            "define" => match parse_define(rest) {
+                Some((name, arity, pattern, body)) => {
+                    self.macros.insert(
+                        name.clone(),
+                        MacroDefinition {
+                            name,
+                            arity,
+                            pattern,
+                            body,
+                            file: file_label.map(str::to_string),
+                            line,
+                        },
+                    );
+                }
+                None => self
+                    .errors
+                    .push(format!("{}: malformed #define '{}'", location, rest)),
+            },
+            "undef" => {
+                self.macros.remove(rest.trim());
+            }
+            "error" => {
+                self.errors.push(format!("{}: #error {}", location, rest));
+            }
+            "warning" => {
+                self.warnings
+                    .push(format!("{}: #warning {}", location, rest));
+            }
+            "assert" => {
+                if !self.evaluate_condition(rest, &location) {
+                    self.errors
+                        .push(format!("{}: #assert failed: {}", location, rest));
+                }
+            }
+            "endinput" | "endscript" => return true,
+            "pragma" | "emit" | "file" | "line" | "section" => {}
+            _ => self
+                .warnings
+                .push(format!("{}: unknown directive #{}", location, keyword)),
+        }
+
+        if is_root && (active || matches!(keyword, "if" | "elseif" | "else" | "endif")) {
+            self.directives.push(DirectiveNode {
+                kind: keyword.to_string(),
+                content: format!("#{}", directive),
+                line,
+                column,
+            });
+        }
+
+        false
+    }
+
+    fn handle_include(
+        &mut self,
+        argument: &str,
+        optional: bool,
+        path: Option<&Path>,
+        depth: usize,
+        is_root: bool,
+        line: usize,
+    ) {
+        let argument = argument.trim();
+        let (name, system) = if let Some(inner) =
+            argument.strip_prefix('<').and_then(|s| s.strip_suffix('>'))
+        {
+            (inner.trim().to_string(), true)
+        } else if let Some(inner) = argument.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+            (inner.trim().to_string(), false)
+        } else {
+            (argument.to_string(), true)
+        };
+
+        let resolved = self.resolve_include(&name, system, path);
+
+        if is_root {
+            self.includes.push(IncludeRecord {
+                name: name.clone(),
+                resolved_path: resolved.as_ref().map(|p| p.display().to_string()),
+                system,
+                line,
+            });
+        }
+
+        let Some(resolved) = resolved else {
+            if !optional {
+                self.warnings.push(format!(
+                    "Line {}: could not resolve include '{}'",
+                    line, name
+                ));
+            }
+            return;
+        };
+
+        // Pawn includes every file once and defines `_inc_<name>` for it
+        let canonical = resolved.canonicalize().unwrap_or_else(|_| resolved.clone());
+        if !self.included.insert(canonical) {
+            return;
+        }
+        if let Some(stem) = resolved.file_stem().and_then(|s| s.to_str()) {
+            let guard = format!("_inc_{}", stem);
+            self.macros.entry(guard.clone()).or_insert(MacroDefinition {
+                name: guard,
+                arity: None,
+                pattern: None,
+                body: String::new(),
+                file: Some(resolved.display().to_string()),
+                line: 0,
+            });
+        }
+
+        if depth + 1 > MAX_INCLUDE_DEPTH {
+            self.errors.push(format!(
+                "Line {}: include depth limit exceeded while including '{}'",
+                line, name
+            ));
+            return;
+        }
+
+        match fs::read_to_string(&resolved) {
+            Ok(content) => {
+                self.process_file(&content, Some(&resolved), depth + 1, false);
+            }
+            Err(e) => self.errors.push(format!(
+                "Line {}: failed to read include '{}': {}",
+                line,
+                resolved.display(),
+                e
+            )),
+        }
+    }
+
+    fn resolve_include(&self, name: &str, system: bool, path: Option<&Path>) -> Option<PathBuf> {
+        let mut directories: Vec<PathBuf> = Vec::new();
+        if !system {
+            if let Some(parent) = path.and_then(Path::parent) {
+                directories.push(parent.to_path_buf());
+            }
+        }
+        directories.extend(self.options.include_paths.iter().cloned());
+
+        for directory in directories {
+            for extension in INCLUDE_EXTENSIONS {
+                let candidate = directory.join(format!("{}{}", name, extension));
+                if candidate.is_file() {
+                    return Some(candidate);
+                }
+            }
+        }
+
+        None
+    }
+
+    fn evaluate_condition(&mut self, expression: &str, location: &str) -> bool {
+        let resolved = self.replace_defined(expression);
+        let expanded = self.expand(&resolved, 0);
+        match ConditionEvaluator::new(&expanded).evaluate() {
+            Ok(value) => value != 0,
+            Err(e) => {
+                self.errors.push(format!(
+                    "{}: cannot evaluate '{}': {}",
+                    location,
+                    expression.trim(),
+                    e
+                ));
+                false
+            }
+        }
+    }
+
+    /// Replace `defined NAME` / `defined(NAME)` before macro expansion runs.
+    fn replace_defined(&self, expression: &str) -> String {
+        let chars: Vec<char> = expression.chars().collect();
+        let mut output = String::with_capacity(expression.len());
+        let mut i = 0;
+
+        while i < chars.len() {
+            if is_ident_start(chars[i]) {
+                let start = i;
+                while i < chars.len() && is_ident_char(chars[i]) {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+                if word != "defined" {
+                    output.push_str(&word);
+                    continue;
+                }
+
+                let mut j = i;
+                while j < chars.len() && chars[j].is_whitespace() {
+                    j += 1;
+                }
+                let parenthesized = j < chars.len() && chars[j] == '(';
+                if parenthesized {
+                    j += 1;
+                    while j < chars.len() && chars[j].is_whitespace() {
+                        j += 1;
+                    }
+                }
+                let name_start = j;
+                while j < chars.len() && is_ident_char(chars[j]) {
+                    j += 1;
+                }
+                let name: String = chars[name_start..j].iter().collect();
+                if parenthesized {
+                    while j < chars.len() && chars[j] != ')' {
+                        j += 1;
+                    }
+                    j = (j + 1).min(chars.len());
+                }
+
+                output.push_str(if self.macros.contains_key(&name) {
+                    "1"
+                } else {
+                    "0"
+                });
+                i = j;
+            } else {
+                output.push(chars[i]);
+                i += 1;
+            }
+        }
+
+        output
+    }
+
+    /// Expand macros in a single logical line.
+    pub fn expand(&mut self, line: &str, line_number: usize) -> String {
+        let mut disabled = Vec::new();
+        self.expand_with(line, line_number, &mut disabled, 0)
+    }
+
+    fn expand_with(
+        &mut self,
+        text: &str,
+        line_number: usize,
+        disabled: &mut Vec<String>,
+        depth: usize,
+    ) -> String {
+        if depth > MAX_EXPANSION_DEPTH {
+            self.errors.push(format!(
+                "Line {}: macro expansion exceeded {} levels",
+                line_number, MAX_EXPANSION_DEPTH
+            ));
+            return text.to_string();
+        }
+
+        let chars: Vec<char> = text.chars().collect();
+        let mut output = String::with_capacity(text.len());
+        let mut i = 0;
+
+        while i < chars.len() {
+            let c = chars[i];
+
+            if c == '"' || c == '\'' {
+                let end = skip_quoted(&chars, i);
+                output.extend(&chars[i..end]);
+                i = end;
+                continue;
+            }
+
+            if c.is_ascii_digit() {
+                // Numbers like `0x1F` or `1_000` must not be split into identifiers
+                while i < chars.len() && (is_ident_char(chars[i]) || chars[i] == '.') {
+                    output.push(chars[i]);
+                    i += 1;
+                }
+                continue;
+            }
+
+            if !is_ident_start(c) {
+                output.push(c);
+                i += 1;
+                continue;
+            }
+
+            let start = i;
+            while i < chars.len() && is_ident_char(chars[i]) {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+
+            let Some(definition) = self
+                .macros
+                .get(&word)
+                .filter(|_| !disabled.contains(&word))
+                .cloned()
+            else {
+                output.push_str(&word);
+                continue;
+            };
+
+            let replacement = match (definition.arity, &definition.pattern) {
+                (_, Some(pattern)) => {
+                    let Some((arguments, end)) = match_macro_pattern(&chars, i, pattern) else {
+                        // The name alone does not match, e.g. `CMD` without `:name(...)`
+                        output.push_str(&word);
+                        continue;
+                    };
+                    i = end;
+                    substitute_parameters(&definition.body, &arguments)
+                }
+                (None, None) => definition.body.clone(),
+                (Some(arity), None) => {
+                    let mut j = i;
+                    while j < chars.len() && chars[j] == ' ' {
+                        j += 1;
+                    }
+                    if j >= chars.len() || chars[j] != '(' {
+                        // A function-like macro name without arguments is left alone
+                        output.push_str(&word);
+                        continue;
+                    }
+                    let Some((arguments, end)) = split_macro_arguments(&chars, j) else {
+                        self.errors.push(format!(
+                            "Line {}: unterminated argument list for macro '{}'",
+                            line_number, word
+                        ));
+                        output.push_str(&word);
+                        continue;
+                    };
+                    if arguments.len() != arity && !(arity == 0 && arguments.len() == 1) {
+                        self.warnings.push(format!(
+                            "Line {}: macro '{}' expects {} argument(s), got {}",
+                            line_number,
+                            word,
+                            arity,
+                            arguments.len()
+                        ));
+                    }
+                    i = end;
+                    substitute_parameters(&definition.body, &arguments)
+                }
+            };
+
+            disabled.push(word);
+            let expanded = self.expand_with(&replacement, line_number, disabled, depth + 1);
+            disabled.pop();
+            output.push_str(&expanded);
+        }
+
+        output
+    }
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_' || c == '@'
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '@'
+}
+
+/// Returns the index just past the closing quote of the literal at `start`.
+fn skip_quoted(chars: &[char], start: usize) -> usize {
+    let quote = chars[start];
+    let mut i = start + 1;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 2,
+            c if c == quote => return i + 1,
+            '\n' => return i,
+            _ => i += 1,
+        }
+    }
+    chars.len()
+}
+
+/// Blank out comments while keeping newlines and columns intact.
+pub(crate) fn strip_comments(source: &str) -> String {
+    let chars: Vec<char> = source.chars().collect();
+    let mut output = String::with_capacity(source.len());
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+
+        if c == '"' || c == '\'' {
+            let end = skip_quoted(&chars, i).min(chars.len());
+            output.extend(&chars[i..end]);
+            i = end;
+        } else if c == '/' && next == Some('/') {
+            while i < chars.len() && chars[i] != '\n' {
+                output.push(' ');
+                i += 1;
+            }
+        } else if c == '/' && next == Some('*') {
+            output.push_str("  ");
+            i += 2;
+            while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                output.push(if chars[i] == '\n' { '\n' } else { ' ' });
+                i += 1;
+            }
+            if i < chars.len() {
+                output.push_str("  ");
+                i += 2;
+            }
+        } else {
+            output.push(c);
+            i += 1;
+        }
+    }
+
+    output
+}
+
+pub(crate) fn split_directive(directive: &str) -> (&str, &str) {
+    let end = directive
+        .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+        .unwrap_or(directive.len());
+    (&directive[..end], directive[end..].trim())
+}
+
+/// Parse the text following `#define` into name, arity, pattern and body.
+fn parse_define(rest: &str) -> Option<(String, Option<usize>, Option<String>, String)> {
+    let rest = rest.trim_start();
+    let name_end = rest.find(|c: char| !is_ident_char(c)).unwrap_or(rest.len());
+    if name_end == 0 || !rest.starts_with(is_ident_start) {
+        return None;
+    }
+    let name = rest[..name_end].to_string();
+    let after = &rest[name_end..];
+
+    // Parameters must follow the name directly: `#define Foo(%0,%1) ...`
+    if let Some(params) = after.strip_prefix('(') {
+        let close = params.find(')')?;
+        let list = params[..close].trim();
+        let arity = if list.is_empty() {
+            0
+        } else {
+            list.split(',')
+                .filter_map(|p| p.trim().strip_prefix('%'))
+                .filter_map(|n| n.parse::<usize>().ok())
+                .map(|n| n + 1)
+                .max()
+                .unwrap_or(0)
+        };
+        return Some((name, Some(arity), None, params[close + 1..].trim().to_string()));
+    }
+
+    // Anything else glued to the name is a pattern: `#define CMD:%0(%1) ...`
+    if after.starts_with(|c: char| !c.is_whitespace()) {
+        let pattern_end = after.find(char::is_whitespace).unwrap_or(after.len());
+        let pattern = &after[..pattern_end];
+        let arity = pattern
+            .match_indices('%')
+            .filter_map(|(at, _)| pattern[at + 1..].chars().next()?.to_digit(10))
+            .map(|n| n as usize + 1)
+            .max()
+            .unwrap_or(0);
+        return Some((
+            name,
+            Some(arity),
+            Some(pattern.to_string()),
+            after[pattern_end..].trim().to_string(),
+        ));
+    }
+
+    Some((name, None, None, after.trim().to_string()))
+}
+
+/// Match the `pattern` of a pattern macro against the text at `start`, just
+/// past the macro name. Literal characters must appear as written (spaces in
+/// the text are skipped) and each `%n` takes everything up to the next literal
+/// at bracket depth zero, or the following identifier when it ends the
+/// pattern. Returns the arguments by `%n` index and the index past the match.
+fn match_macro_pattern(chars: &[char], start: usize, pattern: &str) -> Option<(Vec<String>, usize)> {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let mut arguments: Vec<String> = Vec::new();
+    let mut i = start;
+    let mut p = 0;
+
+    while p < pattern.len() {
+        let parameter = (pattern[p] == '%')
+            .then(|| pattern.get(p + 1).and_then(|c| c.to_digit(10)))
+            .flatten();
+
+        let Some(index) = parameter else {
+            if p > 0 {
+                while i < chars.len() && chars[i] == ' ' {
+                    i += 1;
+                }
+            }
+            if chars.get(i) != Some(&pattern[p]) {
+                return None;
+            }
+            i += 1;
+            p += 1;
+            continue;
+        };
+        p += 2;
+
+        let argument_start = i;
+        match pattern.get(p) {
+            None => {
+                while i < chars.len() && chars[i] == ' ' {
+                    i += 1;
+                }
+                while i < chars.len() && is_ident_char(chars[i]) {
+                    i += 1;
+                }
+            }
+            Some(&terminator) => {
+                let mut depth = 0usize;
+                loop {
+                    let c = *chars.get(i)?;
+                    if depth == 0 && c == terminator {
+                        break;
+                    }
+                    match c {
+                        '"' | '\'' => {
+                            i = skip_quoted(chars, i);
+                            continue;
+                        }
+                        '(' | '[' | '{' => depth += 1,
+                        ')' | ']' | '}' => depth = depth.checked_sub(1)?,
+                        _ => {}
+                    }
+                    i += 1;
+                }
+            }
+        }
+
+        let index = index as usize;
+        if arguments.len() <= index {
+            arguments.resize(index + 1, String::new());
+        }
+        arguments[index] = chars[argument_start..i].iter().collect::<String>().trim().to_string();
+    }
+
+    Some((arguments, i))
+}
+
+/// Split `( a, (b, c), "d,e" )` starting at the `(` at `open` into arguments.
+/// Returns the arguments and the index just past the closing parenthesis.
+fn split_macro_arguments(chars: &[char], open: usize) -> Option<(Vec<String>, usize)> {
+    let mut arguments = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0usize;
+    let mut i = open + 1;
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '"' | '\'' => {
+                let end = skip_quoted(chars, i);
+                current.extend(&chars[i..end]);
+                i = end;
+                continue;
+            }
+            '(' | '[' | '{' => depth += 1,
+            ')' if depth == 0 => {
+                arguments.push(current.trim().to_string());
+                return Some((arguments, i + 1));
+            }
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                arguments.push(current.trim().to_string());
+                current.clear();
+                i += 1;
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+        i += 1;
+    }
+
+    None
+}
+
+fn substitute_parameters(body: &str, arguments: &[String]) -> String {
+    let chars: Vec<char> = body.chars().collect();
+    let mut output = String::with_capacity(body.len());
+    let mut i = 0;
+
+    while i < chars.len() {
+        if chars[i] == '%' {
+            if let Some(index) = chars.get(i + 1).and_then(|c| c.to_digit(10)) {
+                if let Some(argument) = arguments.get(index as usize) {
+                    output.push_str(argument);
+                }
+                i += 2;
+                continue;
+            }
+        }
+        output.push(chars[i]);
+        i += 1;
+    }
+
+    output
+}
+
+/// Integer expression evaluator for `#if`, `#elseif` and `#assert`.
+/// Unknown identifiers evaluate to 0, as in the Pawn compiler.
+struct ConditionEvaluator {
+    tokens: Vec<String>,
+    position: usize,
+}
+
+impl ConditionEvaluator {
+    fn new(expression: &str) -> Self {
+        Self {
+            tokens: tokenize_condition(expression),
+            position: 0,
+        }
+    }
+
+    fn evaluate(&mut self) -> Result<i64, String> {
+        let value = self.ternary()?;
+        if self.position < self.tokens.len() {
+            return Err(format!("unexpected token '{}'", self.tokens[self.position]));
+        }
+        Ok(value)
+    }
+
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.position).map(String::as_str)
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        if self.peek() == Some(token) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn ternary(&mut self) -> Result<i64, String> {
+        let condition = self.binary(0)?;
+        if self.eat("?") {
+            let then_value = self.ternary()?;
+            if !self.eat(":") {
+                return Err("expected ':' in conditional expression".to_string());
+            }
+            let else_value = self.ternary()?;
+            return Ok(if condition != 0 {
+                then_value
+            } else {
+                else_value
+            });
+        }
+        Ok(condition)
+    }
+
+    fn binary(&mut self, level: usize) -> Result<i64, String> {
+        const LEVELS: [&[&str]; 10] = [
+            &["||"],
+            &["&&"],
+            &["|"],
+            &["^"],
+            &["&"],
+            &["==", "!="],
+            &["<", ">", "<=", ">="],
+            &["<<", ">>", ">>>"],
+            &["+", "-"],
+            &["*", "/", "%"],
+        ];
+
+        if level == LEVELS.len() {
+            return self.unary();
+        }
+
+        let mut left = self.binary(level + 1)?;
+        while let Some(op) = self.peek().filter(|t| LEVELS[level].contains(t)) {
+            let op = op.to_string();
+            self.position += 1;
+            let right = self.binary(level + 1)?;
+            left = match op.as_str() {
+                "||" => ((left != 0) || (right != 0)) as i64,
+                "&&" => ((left != 0) && (right != 0)) as i64,
+                "|" => left | right,
+                "^" => left ^ right,
+                "&" => left & right,
+                "==" => (left == right) as i64,
+                "!=" => (left != right) as i64,
+                "<" => (left < right) as i64,
+                ">" => (left > right) as i64,
+                "<=" => (left <= right) as i64,
+                ">=" => (left >= right) as i64,
+                "<<" => left.wrapping_shl(right as u32),
+                ">>" => left.wrapping_shr(right as u32),
+                ">>>" => ((left as u64).wrapping_shr(right as u32)) as i64,
+                "+" => left.wrapping_add(right),
+                "-" => left.wrapping_sub(right),
+                "*" => left.wrapping_mul(right),
+                "/" | "%" if right == 0 => return Err("division by zero".to_string()),
+                "/" => left.wrapping_div(right),
+                "%" => left.wrapping_rem(right),
+                _ => unreachable!(),
+            };
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<i64, String> {
+        if self.eat("!") {
+            return Ok((self.unary()? == 0) as i64);
+        }
+        if self.eat("-") {
+            return Ok(self.unary()?.wrapping_neg());
+        }
+        if self.eat("~") {
+            return Ok(!self.unary()?);
+        }
+        if self.eat("+") {
+            return self.unary();
+        }
+        if self.eat("(") {
+            let value = self.ternary()?;
+            if !self.eat(")") {
+                return Err("expected ')'".to_string());
+            }
+            return Ok(value);
+        }
+
+        let token = self
+            .peek()
+            .ok_or_else(|| "unexpected end of expression".to_string())?
+            .to_string();
+        self.position += 1;
+
+        // `Float:` style tag overrides do not change the value
+        if self.peek() == Some(":") && token.starts_with(is_ident_start) {
+            self.position += 1;
+            return self.unary();
+        }
+
+        parse_condition_literal(&token)
+    }
+}
+
+fn parse_condition_literal(token: &str) -> Result<i64, String> {
+    let cleaned = token.replace('_', "");
+    if let Some(hex) = cleaned.strip_prefix("0x") {
+        return i64::from_str_radix(hex, 16).map_err(|e| e.to_string());
+    }
+    if let Some(bin) = cleaned.strip_prefix("0b") {
+        return i64::from_str_radix(bin, 2).map_err(|e| e.to_string());
+    }
+    if token.starts_with('\'') {
+        let inner = token.trim_matches('\'');
+        return Ok(match inner {
+            "\\n" => '\n' as i64,
+            "\\t" => '\t' as i64,
+            "\\0" => 0,
+            _ => inner.chars().next().map(|c| c as i64).unwrap_or(0),
+        });
+    }
+    if token.starts_with(|c: char| c.is_ascii_digit()) {
+        if let Ok(value) = cleaned.parse::<i64>() {
+            return Ok(value);
+        }
+        // Floats in conditions are truncated
+        return cleaned
+            .parse::<f64>()
+            .map(|v| v as i64)
+            .map_err(|e| e.to_string());
+    }
+    if token.starts_with(is_ident_start) {
+        return Ok(match token {
+            "true" => 1,
+            _ => 0,
+        });
+    }
+    Err(format!("unexpected token '{}'", token))
+}
+
+fn tokenize_condition(expression: &str) -> Vec<String> {
+    const OPERATORS: [&str; 22] = [
+        ">>>", "<<", ">>", "<=", ">=", "==", "!=", "&&", "||", "+", "-", "*", "/", "%", "<", ">",
+        "!", "~", "&", "|", "^", "?",
+    ];
+
+    let chars: Vec<char> = expression.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    'outer: while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        if c == '\'' {
+            let end = skip_quoted(&chars, i);
+            tokens.push(chars[i..end].iter().collect());
+            i = end;
+            continue;
+        }
+        if is_ident_char(c) {
+            let start = i;
+            while i < chars.len() && (is_ident_char(chars[i]) || chars[i] == '.') {
+                i += 1;
+            }
+            tokens.push(chars[start..i].iter().collect());
+            continue;
+        }
+        if c == '(' || c == ')' || c == ':' {
+            tokens.push(c.to_string());
+            i += 1;
+            continue;
+        }
+        for op in OPERATORS {
+            let op_chars: Vec<char> = op.chars().collect();
+            if chars[i..].starts_with(&op_chars) {
+                tokens.push(op.to_string());
+                i += op_chars.len();
+                continue 'outer;
+            }
+        }
+        tokens.push(c.to_string());
+        i += 1;
+    }
+
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn preprocess(source: &str) -> PreprocessedSource {
+        PawnPreprocessor::new(PreprocessorOptions::default()).process(source, None)
+    }
+
+    #[test]
+    fn test_object_and_function_macros() {
+        let result = preprocess(
+            "#define MAX_HOUSES 50\n#define GetName(%0) pData[%0][pName]\nnew x = MAX_HOUSES;\nprint(GetName(playerid));",
+        );
+        assert!(result.errors.is_empty());
+        let lines: Vec<&str> = result.source.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[2], "new x = 50;");
+        assert_eq!(lines[3], "print(pData[playerid][pName]);");
+    }
+
+    #[test]
+    fn test_macros_inside_strings_are_untouched() {
+        let result = preprocess("#define NAME value\nprint(\"NAME\");");
+        assert_eq!(result.source.lines().nth(1), Some("print(\"NAME\");"));
+    }
+
+    #[test]
+    fn test_conditional_blocks() {
+        let result = preprocess(
+            "#define FILTERSCRIPT\n#if defined FILTERSCRIPT\nnew a;\n#elseif 1\nnew b;\n#else\nnew c;\n#endif\n#if !defined(FILTERSCRIPT) || (2 * 3 == 6)\nnew d;\n#endif",
+        );
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        assert!(result.source.contains("new a;"));
+        assert!(!result.source.contains("new b;"));
+        assert!(!result.source.contains("new c;"));
+        assert!(result.source.contains("new d;"));
+    }
+
+    #[test]
+    fn test_condition_division_overflow_wraps() {
+        let result = preprocess(
+            "#define CELLMIN (-9223372036854775807 - 1)\n#if CELLMIN / -1 < 0\nnew a;\n#endif\n#if CELLMIN % -1 == 0\nnew b;\n#endif\n#if 1 / 0\nnew c;\n#endif",
+        );
+        assert_eq!(result.errors.len(), 1, "{:?}", result.errors);
+        assert!(result.source.contains("new a;"));
+        assert!(result.source.contains("new b;"));
+        assert!(!result.source.contains("new c;"));
+    }
+
+    #[test]
+    fn test_pattern_macros() {
+        let result = preprocess(
+            "#define CMD:%0(%1) forward cmd_%0(%1); public cmd_%0(%1)\n#define Float:%0 _:%0\nCMD:help(playerid, params[])\nnew x = CMD;\nnew y = Float:value;",
+        );
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        let lines: Vec<&str> = result.source.lines().collect();
+        assert_eq!(
+            lines[2],
+            "forward cmd_help(playerid, params[]); public cmd_help(playerid, params[])"
+        );
+        assert_eq!(lines[3], "new x = CMD;");
+        assert_eq!(lines[4], "new y = _:value;");
+        let command = result.macros.iter().find(|m| m.name == "CMD").unwrap();
+        assert_eq!(command.pattern.as_deref(), Some(":%0(%1)"));
+        assert_eq!(command.arity, Some(2));
+    }
+
+    #[test]
+    fn test_unterminated_if_is_reported() {
+        let result = preprocess("#if 1\nnew a;");
+        assert_eq!(result.errors.len(), 1);
+    }
+
+    #[test]
+    fn test_recursive_macro_terminates() {
+        let result = preprocess("#define A B\n#define B A\nnew x = A;");
+        assert_eq!(result.source.lines().nth(2), Some("new x = A;"));
+    }
+
+    #[test]
+    fn test_line_continuation_keeps_line_count() {
+        let result = preprocess("#define LONG 1 + \\\n 2\nnew x = LONG;");
+        let lines: Vec<&str> = result.source.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[2].trim(), "new x = 1 +   2;");
+    }
+}
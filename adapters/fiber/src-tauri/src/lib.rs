@@ -1215,6 +1215,15 @@ pub struct PwnImportResult {
     pub errors: Vec<String>,
 }
 
+#[tauri::command]
+async fn disassemble_amx_file(file_path: String) -> Result<String, String> {
+    let amx = blueprint::amx::AmxFile::load_from_path(&file_path)
+        .map_err(|e| format!("Failed to read AMX file {}: {}", file_path, e))?;
+
+    amx.listing()
+        .map_err(|e| format!("Failed to disassemble AMX file {}: {}", file_path, e))
+}
+
 #[tauri::command]
 async fn import_dff_file(file_path: String) -> Result<rengine_core::renderware::dff::DffModel, String> {
     use rengine_core::renderware::dff::DffModel;
@@ -1260,6 +1269,15 @@ async fn parse_pwn_file(file_path: String) -> Result<PwnImportResult, String> {
     use std::fs::File;
     use std::io::Read;
 
+    // Compiled scripts only carry objects created with constant arguments
+    if file_path.to_lowercase().ends_with(".amx") {
+        let amx = blueprint::amx::AmxFile::load_from_path(&file_path)
+            .map_err(|e| format!("Failed to read AMX file {}: {}", file_path, e))?;
+        return amx
+            .extract_objects()
+            .map_err(|e| format!("Failed to analyse AMX file {}: {}", file_path, e));
+    }
+
     // Read PWN file
     let mut file = File::open(&file_path)
         .map_err(|e| format!("Failed to open PWN file {}: {}", file_path, e))?;
@@ -1898,10 +1916,14 @@ pub fn run() {
             import_col_file,
             import_ipl_file,
             parse_pwn_file,
+            disassemble_amx_file,
             get_samp_model_by_id,
             get_samp_model_by_name,
             search_samp_models_by_name,
             get_all_samp_models_count,
+            query_samp_models,
+            find_similar_samp_models,
+            get_samp_model_facets,
             parse_blueprint_code,
             generate_blueprint_code,
             load_rengine_config,
@@ -1944,22 +1966,55 @@ pub fn run() {
         Ok(SAMP_MODEL_DATABASE.len())
     }
 
+    #[tauri::command]
+    fn query_samp_models(
+        query: crate::models::ModelQuery,
+    ) -> Result<crate::models::ModelQueryResult, String> {
+        SAMP_MODEL_DATABASE.query(&query).map_err(|e| e.to_string())
+    }
+
+    #[tauri::command]
+    fn find_similar_samp_models(
+        id: u32,
+        limit: Option<usize>,
+    ) -> Result<Vec<crate::models::ModelMatch>, String> {
+        SAMP_MODEL_DATABASE
+            .find_similar_footprint(id, limit.unwrap_or(10))
+            .map_err(|e| e.to_string())
+    }
+
+    #[tauri::command]
+    fn get_samp_model_facets() -> Result<crate::models::ModelFacets, String> {
+        Ok(SAMP_MODEL_DATABASE.facets())
+    }
+
     #[tauri::command]
     async fn parse_blueprint_code(
         source: String,
         language: String,
+        include_paths: Option<Vec<String>>,
     ) -> Result<serde_json::Value, String> {
         use crate::blueprint::parser::Parser as ParserTrait;
-        use crate::blueprint::PawnParser;
+        use crate::blueprint::{PawnAnalyzer, PawnParser, PreprocessorOptions};
 
         match language.as_str() {
             "pawn" => {
-                let parser = PawnParser::new();
+                let options = PreprocessorOptions {
+                    include_paths: include_paths
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(std::path::PathBuf::from)
+                        .collect(),
+                    predefined: Vec::new(),
+                };
+                let parser = PawnParser::with_options(options.clone());
                 let result = parser.parse(&source)?;
+                let diagnostics = PawnAnalyzer::new().analyze_source(&source, None, &options);
                 Ok(serde_json::json!({
                     "ast": result.ast,
                     "errors": result.errors,
                     "warnings": result.warnings,
+                    "diagnostics": diagnostics,
                     "language": parser.language()
                 }))
             }
@@ -1973,20 +2028,6 @@ pub fn run() {
         language: String,
         options: Option<serde_json::Value>,
     ) -> Result<String, String> {
-        use crate::blueprint::{GenerationOptions, PawnGenerator};
-
-        match language.as_str() {
-            "pawn" => {
-                let gen_options = if let Some(opts) = options {
-                    serde_json::from_value(opts).unwrap_or_default()
-                } else {
-                    GenerationOptions::default()
-                };
-
-                let generator = PawnGenerator::with_options(gen_options);
-                generator.generate(&ast)
-            }
-            _ => Err(format!("Unsupported language: {}", language)),
-        }
+        crate::blueprint::pawn_generator::generate_blueprint_code(ast, &language, options)
     }
 }
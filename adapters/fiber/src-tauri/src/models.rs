@@ -39,6 +39,204 @@ pub struct SampModel {
     pub tags: String,
 }
 
+impl SampModel {
+    pub fn collides(&self) -> bool {
+        is_yes(&self.has_collision)
+    }
+
+    pub fn breaks(&self) -> bool {
+        is_yes(&self.breaks_on_hit)
+    }
+
+    pub fn animated(&self) -> bool {
+        is_yes(&self.has_animation)
+    }
+
+    /// Timed objects only render during part of the day ("Yes, visible from 20 to 6").
+    pub fn is_timed(&self) -> bool {
+        is_yes(&self.visible_by_time)
+    }
+
+    /// Tag path segments, e.g. `SA-MP|SA-MP 0.3c RC4` yields both parts.
+    pub fn tag_list(&self) -> impl Iterator<Item = &str> {
+        self.tags
+            .split('|')
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+    }
+
+    /// Bounding box as (longer side, shorter side, height) so rotated copies compare equal.
+    pub fn footprint(&self) -> [f32; 3] {
+        let (long, short) = if self.border_box_length >= self.border_box_width {
+            (self.border_box_length, self.border_box_width)
+        } else {
+            (self.border_box_width, self.border_box_length)
+        };
+        [long, short, self.border_box_height]
+    }
+}
+
+fn is_yes(value: &str) -> bool {
+    value.trim_start().to_ascii_lowercase().starts_with("yes")
+}
+
+/// Inclusive bounds; a missing side is unbounded.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct RangeFilter {
+    pub min: Option<f32>,
+    pub max: Option<f32>,
+}
+
+impl RangeFilter {
+    pub fn contains(&self, value: f32) -> bool {
+        self.min.is_none_or(|min| value >= min) && self.max.is_none_or(|max| value <= max)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ModelQuery {
+    /// Fuzzy name match; results are ranked by similarity
+    pub name: Option<String>,
+    /// Rank by bounding box similarity to this model
+    pub similar_to: Option<u32>,
+    /// Every tag must be present (case-insensitive, matched per `|` segment)
+    pub tags_all: Vec<String>,
+    /// At least one tag must be present
+    pub tags_any: Vec<String>,
+    pub tags_none: Vec<String>,
+    pub has_collision: Option<bool>,
+    pub breaks_on_hit: Option<bool>,
+    pub has_animation: Option<bool>,
+    pub timed: Option<bool>,
+    pub length: Option<RangeFilter>,
+    pub width: Option<RangeFilter>,
+    pub height: Option<RangeFilter>,
+    pub radius: Option<RangeFilter>,
+    /// Source IDE files, with or without the `.ide` extension
+    pub ide: Vec<String>,
+    pub txd: Option<String>,
+    pub offset: usize,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelMatch {
+    pub model: SampModel,
+    /// Ranking score in (0, 1]; 1.0 when the query only filters
+    pub score: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelQueryResult {
+    /// Matches before `offset`/`limit` were applied
+    pub total: usize,
+    pub matches: Vec<ModelMatch>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FacetCount {
+    pub name: String,
+    pub count: usize,
+}
+
+/// Distinct tags and IDE files with their model counts, for filter pickers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelFacets {
+    pub tags: Vec<FacetCount>,
+    pub ides: Vec<FacetCount>,
+}
+
+/// Similarity of `name` to `query` in (0, 1], or `None` if unrelated.
+/// Exact > prefix > substring > subsequence > edit distance.
+pub fn fuzzy_name_score(query: &str, name: &str) -> Option<f32> {
+    let query = query.trim().to_lowercase();
+    let name = name.to_lowercase();
+    if query.is_empty() {
+        return Some(1.0);
+    }
+    let coverage = query.chars().count() as f32 / name.chars().count().max(1) as f32;
+
+    if name == query {
+        return Some(1.0);
+    }
+    if name.starts_with(&query) {
+        return Some(0.8 + 0.15 * coverage);
+    }
+    if name.contains(&query) {
+        return Some(0.6 + 0.15 * coverage);
+    }
+
+    // Characters in order, scored by how tightly they cluster
+    let name_chars: Vec<char> = name.chars().collect();
+    let mut position = 0;
+    let mut first = None;
+    let mut matched = true;
+    for c in query.chars() {
+        match name_chars[position..].iter().position(|&n| n == c) {
+            Some(offset) => {
+                first.get_or_insert(position + offset);
+                position += offset + 1;
+            }
+            None => {
+                matched = false;
+                break;
+            }
+        }
+    }
+    if matched {
+        let span = (position - first.unwrap_or(0)) as f32;
+        return Some(0.3 + 0.25 * (query.chars().count() as f32 / span));
+    }
+
+    let distance = levenshtein(&query, &name) as f32;
+    let similarity = 1.0 - distance / query.chars().count().max(name_chars.len()) as f32;
+    (similarity >= 0.5).then_some(similarity * 0.3)
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut previous = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let current = row[j + 1];
+            row[j + 1] = if ca == cb {
+                previous
+            } else {
+                1 + previous.min(row[j]).min(row[j + 1])
+            };
+            previous = current;
+        }
+    }
+    row[b.len()]
+}
+
+/// Scale-independent bounding box similarity in (0, 1].
+pub fn footprint_similarity(a: [f32; 3], b: [f32; 3]) -> f32 {
+    // Flat decals have a zero height; clamp so the log ratio stays finite
+    const EPSILON: f32 = 0.01;
+    let distance: f32 = a
+        .iter()
+        .zip(&b)
+        .map(|(x, y)| (x.max(EPSILON) / y.max(EPSILON)).ln().abs())
+        .sum();
+    1.0 / (1.0 + distance)
+}
+
+fn matches_ide(filter: &str, ide: &str) -> bool {
+    fn stem(name: &str) -> &str {
+        let name = name.trim();
+        if name.to_ascii_lowercase().ends_with(".ide") {
+            &name[..name.len() - 4]
+        } else {
+            name
+        }
+    }
+    stem(filter).eq_ignore_ascii_case(stem(ide))
+}
+
 #[derive(Debug)]
 pub struct SampModelDatabase {
     models: Vec<SampModel>,
@@ -115,6 +313,131 @@ impl SampModelDatabase {
             .collect()
     }
 
+    /// Filter and rank models. Unranked queries keep CSV order.
+    pub fn query(&self, query: &ModelQuery) -> Result<ModelQueryResult, ModelError> {
+        let reference = match query.similar_to {
+            Some(id) => Some(
+                self.get_model_by_id(id)
+                    .ok_or_else(|| ModelError::InvalidData(format!("Unknown model id {}", id)))?,
+            ),
+            None => None,
+        };
+        let name = query
+            .name
+            .as_deref()
+            .map(str::trim)
+            .filter(|n| !n.is_empty());
+
+        let mut matches: Vec<ModelMatch> = Vec::new();
+        for model in &self.models {
+            if !self.passes_filters(model, query) {
+                continue;
+            }
+            let mut score = 1.0;
+            if let Some(name) = name {
+                match fuzzy_name_score(name, &model.name) {
+                    Some(name_score) => score *= name_score,
+                    None => continue,
+                }
+            }
+            if let Some(reference) = reference {
+                if reference.id == model.id {
+                    continue;
+                }
+                score *= footprint_similarity(reference.footprint(), model.footprint());
+            }
+            matches.push(ModelMatch {
+                model: model.clone(),
+                score,
+            });
+        }
+
+        if name.is_some() || reference.is_some() {
+            // Stable sort keeps CSV order among equal scores
+            matches.sort_by(|a, b| b.score.total_cmp(&a.score));
+        }
+
+        let total = matches.len();
+        let matches = matches
+            .into_iter()
+            .skip(query.offset)
+            .take(query.limit.unwrap_or(usize::MAX))
+            .collect();
+
+        Ok(ModelQueryResult { total, matches })
+    }
+
+    /// Models whose bounding box is closest in shape and size to `id`.
+    pub fn find_similar_footprint(
+        &self,
+        id: u32,
+        limit: usize,
+    ) -> Result<Vec<ModelMatch>, ModelError> {
+        let result = self.query(&ModelQuery {
+            similar_to: Some(id),
+            limit: Some(limit),
+            ..Default::default()
+        })?;
+        Ok(result.matches)
+    }
+
+    pub fn facets(&self) -> ModelFacets {
+        let mut tags: HashMap<&str, usize> = HashMap::new();
+        let mut ides: HashMap<&str, usize> = HashMap::new();
+        for model in &self.models {
+            for tag in model.tag_list() {
+                *tags.entry(tag).or_default() += 1;
+            }
+            *ides.entry(model.ide.as_str()).or_default() += 1;
+        }
+
+        fn sorted(counts: HashMap<&str, usize>) -> Vec<FacetCount> {
+            let mut facets: Vec<FacetCount> = counts
+                .into_iter()
+                .map(|(name, count)| FacetCount {
+                    name: name.to_string(),
+                    count,
+                })
+                .collect();
+            facets.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.name.cmp(&b.name)));
+            facets
+        }
+
+        ModelFacets {
+            tags: sorted(tags),
+            ides: sorted(ides),
+        }
+    }
+
+    fn passes_filters(&self, model: &SampModel, query: &ModelQuery) -> bool {
+        let has_tag = |wanted: &String| {
+            model
+                .tag_list()
+                .any(|tag| tag.eq_ignore_ascii_case(wanted.trim()))
+        };
+        let flag = |wanted: Option<bool>, actual: bool| wanted.is_none_or(|w| w == actual);
+        let range = |filter: &Option<RangeFilter>, value: f32| {
+            filter.as_ref().is_none_or(|f| f.contains(value))
+        };
+
+        query.tags_all.iter().all(has_tag)
+            && (query.tags_any.is_empty() || query.tags_any.iter().any(has_tag))
+            && !query.tags_none.iter().any(has_tag)
+            && flag(query.has_collision, model.collides())
+            && flag(query.breaks_on_hit, model.breaks())
+            && flag(query.has_animation, model.animated())
+            && flag(query.timed, model.is_timed())
+            && range(&query.length, model.border_box_length)
+            && range(&query.width, model.border_box_width)
+            && range(&query.height, model.border_box_height)
+            && range(&query.radius, model.radius)
+            && (query.ide.is_empty() || query.ide.iter().any(|ide| matches_ide(ide, &model.ide)))
+            && query
+                .txd
+                .as_deref()
+                .is_none_or(|txd| model.txd.eq_ignore_ascii_case(txd.trim()))
+    }
+
     pub fn get_all_models(&self) -> &[SampModel] {
         &self.models
     }
@@ -168,4 +491,87 @@ mod tests {
             .iter()
             .all(|m| m.name.to_lowercase().starts_with("spl")));
     }
+
+    #[test]
+    fn test_query_filters() {
+        let database = SampModelDatabase::load_from_embedded_csv().unwrap();
+
+        let result = database
+            .query(&ModelQuery {
+                tags_all: vec!["sa-mp".to_string()],
+                has_collision: Some(true),
+                height: Some(RangeFilter {
+                    min: Some(1.0),
+                    max: Some(5.0),
+                }),
+                ide: vec!["SAMP".to_string()],
+                limit: Some(20),
+                ..Default::default()
+            })
+            .unwrap();
+        assert!(result.total >= result.matches.len());
+        assert!(!result.matches.is_empty());
+        for m in &result.matches {
+            assert!(m.model.tag_list().any(|t| t == "SA-MP"));
+            assert!(m.model.collides());
+            assert!((1.0..=5.0).contains(&m.model.border_box_height));
+            assert_eq!(m.model.ide, "SAMP.ide");
+        }
+
+        let timed = database
+            .query(&ModelQuery {
+                timed: Some(true),
+                ..Default::default()
+            })
+            .unwrap();
+        assert!(timed.matches.iter().all(|m| m.model.is_timed()));
+        assert!(timed.total > 0 && timed.total < database.len());
+    }
+
+    #[test]
+    fn test_fuzzy_name_ranking() {
+        let database = SampModelDatabase::load_from_embedded_csv().unwrap();
+        let result = database
+            .query(&ModelQuery {
+                name: Some("spl_b_mar_m".to_string()),
+                limit: Some(5),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(result.matches[0].model.id, 1000);
+        assert_eq!(result.matches[0].score, 1.0);
+        assert!(result
+            .matches
+            .windows(2)
+            .all(|pair| pair[0].score >= pair[1].score));
+
+        assert!(fuzzy_name_score("fence", "fence_a") > fuzzy_name_score("fence", "bigfence"));
+        assert!(fuzzy_name_score("fnc", "fence") > fuzzy_name_score("fnce", "fanc"));
+        assert!(fuzzy_name_score("barrel", "xyz_q").is_none());
+    }
+
+    #[test]
+    fn test_similar_footprint() {
+        let database = SampModelDatabase::load_from_embedded_csv().unwrap();
+        let matches = database.find_similar_footprint(1000, 10).unwrap();
+        assert_eq!(matches.len(), 10);
+        assert!(matches.iter().all(|m| m.model.id != 1000));
+        assert!(matches
+            .windows(2)
+            .all(|pair| pair[0].score >= pair[1].score));
+
+        let reference = database.get_model_by_id(1000).unwrap().footprint();
+        let best = matches[0].model.footprint();
+        assert!((best[0] - reference[0]).abs() < 0.5);
+
+        assert!(database.find_similar_footprint(u32::MAX, 10).is_err());
+    }
+
+    #[test]
+    fn test_facets() {
+        let database = SampModelDatabase::load_from_embedded_csv().unwrap();
+        let facets = database.facets();
+        assert_eq!(facets.ides[0].name, "SAMP.ide");
+        assert!(facets.tags.iter().any(|t| t.name == "Road"));
+    }
 }
@@ -7,7 +7,6 @@ import { invoke } from "@tauri-apps/api/core";
 export interface GenerationOptions {
   indentSize?: number;
   useTabs?: boolean;
-  preserveComments?: boolean;
 }
 
 /**
@@ -22,7 +21,6 @@ export async function generatePawnCode(
       ? {
           indent_size: options.indentSize ?? 4,
           use_tabs: options.useTabs ?? false,
-          preserve_comments: options.preserveComments ?? true,
         }
       : undefined;
 
@@ -58,6 +56,5 @@ export async function generatePawnCodeWithIndent(
   return generatePawnCode(ast, {
     indentSize,
     useTabs,
-    preserveComments: true,
   });
 }
@@ -0,0 +1,137 @@
+use std::fmt;
+use std::path::Path;
+
+use bevy::math::Vec2;
+
+/// Grayscale brush image, 1.0 is full brush strength.
+#[derive(Clone)]
+pub struct BrushAlpha {
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+    values: Vec<f32>,
+}
+
+impl BrushAlpha {
+    pub fn from_values(name: impl Into<String>, width: u32, height: u32, values: Vec<f32>) -> Option<Self> {
+        if width == 0 || height == 0 || values.len() != (width * height) as usize {
+            return None;
+        }
+
+        Some(Self {
+            name: name.into(),
+            width,
+            height,
+            values,
+        })
+    }
+
+    /// Loads any image the `image` crate can read, named after the file stem.
+    pub fn load_from_path(path: &Path) -> Option<Self> {
+        let name = path.file_stem()?.to_str()?.to_string();
+
+        // 16 bit so height stamps keep their precision
+        let image = image::open(path).ok()?.into_luma16();
+
+        let values = image
+            .pixels()
+            .map(|pixel| pixel.0[0] as f32 / u16::MAX as f32)
+            .collect();
+
+        Self::from_values(name, image.width(), image.height(), values)
+    }
+
+    fn value_at(&self, x: u32, y: u32) -> f32 {
+        self.values[(y.min(self.height - 1) * self.width + x.min(self.width - 1)) as usize]
+    }
+
+    /// Bilinear sample at `uv` in 0..1, 0 outside of the image.
+    pub fn sample(&self, uv: Vec2) -> f32 {
+        if !(0.0..=1.0).contains(&uv.x) || !(0.0..=1.0).contains(&uv.y) {
+            return 0.0;
+        }
+
+        let pixel = uv * Vec2::new(self.width as f32 - 1.0, self.height as f32 - 1.0);
+        let base = pixel.floor();
+        let fraction = pixel - base;
+
+        let x = base.x as u32;
+        let y = base.y as u32;
+
+        let top = self.value_at(x, y) + (self.value_at(x + 1, y) - self.value_at(x, y)) * fraction.x;
+        let bottom =
+            self.value_at(x, y + 1) + (self.value_at(x + 1, y + 1) - self.value_at(x, y + 1)) * fraction.x;
+
+        top + (bottom - top) * fraction.y
+    }
+}
+
+// alphas are identified by their file name
+impl PartialEq for BrushAlpha {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && self.width == other.width && self.height == other.height
+    }
+}
+
+impl fmt::Debug for BrushAlpha {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "BrushAlpha({} {}x{})", self.name, self.width, self.height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 0.0 1.0
+    // 0.5 1.0
+    fn gradient() -> BrushAlpha {
+        BrushAlpha::from_values("gradient", 2, 2, vec![0.0, 1.0, 0.5, 1.0]).unwrap()
+    }
+
+    #[test]
+    fn test_from_values_checks_size() {
+        assert!(BrushAlpha::from_values("empty", 0, 2, Vec::new()).is_none());
+        assert!(BrushAlpha::from_values("short", 2, 2, vec![0.0; 3]).is_none());
+        assert!(BrushAlpha::from_values("ok", 2, 2, vec![0.0; 4]).is_some());
+    }
+
+    #[test]
+    fn test_sample_corners() {
+        let alpha = gradient();
+
+        assert_eq!(alpha.sample(Vec2::new(0.0, 0.0)), 0.0);
+        assert_eq!(alpha.sample(Vec2::new(1.0, 0.0)), 1.0);
+        assert_eq!(alpha.sample(Vec2::new(0.0, 1.0)), 0.5);
+        assert_eq!(alpha.sample(Vec2::new(1.0, 1.0)), 1.0);
+    }
+
+    #[test]
+    fn test_sample_bilinear() {
+        let alpha = gradient();
+
+        assert_eq!(alpha.sample(Vec2::new(0.5, 0.0)), 0.5);
+        assert_eq!(alpha.sample(Vec2::new(0.0, 0.5)), 0.25);
+        assert_eq!(alpha.sample(Vec2::new(0.5, 0.5)), 0.625);
+        assert_eq!(alpha.sample(Vec2::new(0.5, 1.0)), 0.75);
+    }
+
+    #[test]
+    fn test_sample_outside_is_zero() {
+        let alpha = BrushAlpha::from_values("full", 2, 2, vec![1.0; 4]).unwrap();
+
+        assert_eq!(alpha.sample(Vec2::new(-0.01, 0.5)), 0.0);
+        assert_eq!(alpha.sample(Vec2::new(1.01, 0.5)), 0.0);
+        assert_eq!(alpha.sample(Vec2::new(0.5, -0.01)), 0.0);
+        assert_eq!(alpha.sample(Vec2::new(0.5, 1.01)), 0.0);
+    }
+
+    #[test]
+    fn test_sample_single_pixel() {
+        let alpha = BrushAlpha::from_values("dot", 1, 1, vec![0.8]).unwrap();
+
+        assert_eq!(alpha.sample(Vec2::new(0.0, 0.0)), 0.8);
+        assert_eq!(alpha.sample(Vec2::new(0.5, 0.5)), 0.8);
+        assert_eq!(alpha.sample(Vec2::new(1.0, 1.0)), 0.8);
+    }
+}
@@ -0,0 +1,232 @@
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+use bevy::math::Vec2;
+
+use crate::brush_alpha::BrushAlpha;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum BrushShapeKind {
+    // the classic round brush, its falloff comes from the brush hardness
+    #[default]
+    Circle,
+    Alpha(Arc<BrushAlpha>),
+
+    // height stamps
+    Mountain,
+    Crater,
+    Plateau,
+}
+
+impl BrushShapeKind {
+    pub const BUILT_IN: [BrushShapeKind; 4] = [
+        BrushShapeKind::Circle,
+        BrushShapeKind::Mountain,
+        BrushShapeKind::Crater,
+        BrushShapeKind::Plateau,
+    ];
+
+    pub fn label(&self) -> String {
+        match self {
+            Self::Circle => "Circle".into(),
+            Self::Alpha(alpha) => alpha.name.clone(),
+            Self::Mountain => "Mountain".into(),
+            Self::Crater => "Crater".into(),
+            Self::Plateau => "Plateau".into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BrushShape {
+    pub kind: BrushShapeKind,
+    // radians, counter clockwise around the brush center
+    pub rotation: f32,
+}
+
+impl BrushShape {
+    pub fn new(kind: BrushShapeKind) -> Self {
+        Self { kind, rotation: 0.0 }
+    }
+
+    pub fn is_circle(&self) -> bool {
+        self.kind == BrushShapeKind::Circle
+    }
+
+    // offset from the brush center mapped into the -1..1 square of the shape
+    fn to_shape_space(&self, offset: Vec2, radius: f32) -> Vec2 {
+        Vec2::from_angle(-self.rotation).rotate(offset) / radius
+    }
+
+    /// Whether the cell at `offset` from the brush center is touched at all.
+    pub fn contains(&self, offset: Vec2, radius: f32) -> bool {
+        match self.kind {
+            BrushShapeKind::Circle => offset.length() < radius,
+            _ => self.weight(offset, radius, 0.0) > 0.0,
+        }
+    }
+
+    /// How strongly the brush applies at `offset` from its center, 0..1.
+    /// Hardness only shapes the falloff of the circle, alphas and stamps bring their own.
+    pub fn weight(&self, offset: Vec2, radius: f32, hardness: f32) -> f32 {
+        if radius <= 0.0 {
+            return 0.0;
+        }
+
+        match self.kind {
+            BrushShapeKind::Circle => {
+                let distance = offset.length();
+                if distance >= radius {
+                    return 0.0;
+                }
+                circle_falloff(distance, radius, hardness)
+            }
+            _ => self.height(offset, radius, hardness).abs().min(1.0),
+        }
+    }
+
+    /// Signed height profile for stamping, -1..1. The circle stamps a dome shaped by its hardness
+    /// and alphas are read as height maps.
+    pub fn height(&self, offset: Vec2, radius: f32, hardness: f32) -> f32 {
+        if radius <= 0.0 {
+            return 0.0;
+        }
+
+        let local = self.to_shape_space(offset, radius);
+        let distance = local.length();
+
+        match &self.kind {
+            BrushShapeKind::Circle => {
+                if distance >= 1.0 {
+                    return 0.0;
+                }
+                circle_falloff(distance, 1.0, hardness)
+            }
+            // clipped to the brush radius so every shape stays inside the circle the tools work with
+            BrushShapeKind::Alpha(alpha) => {
+                if distance >= 1.0 {
+                    return 0.0;
+                }
+                alpha.sample(local * 0.5 + 0.5)
+            }
+            BrushShapeKind::Mountain => {
+                if distance >= 1.0 {
+                    return 0.0;
+                }
+                let bump = 0.5 + 0.5 * (PI * distance).cos();
+                bump * bump
+            }
+            BrushShapeKind::Crater => crater_profile(distance),
+            BrushShapeKind::Plateau => 1.0 - smoothstep(0.6, 1.0, distance),
+        }
+    }
+}
+
+/// Same falloff the paint tools always used for their round brush.
+pub fn circle_falloff(distance: f32, radius: f32, hardness: f32) -> f32 {
+    let distance_percent = f32::min(1.0, distance / radius);
+    let fade_effect = 1.0 - distance_percent;
+
+    f32::max(0.0, fade_effect * (1.0 + hardness) - (distance_percent * hardness))
+}
+
+// a bowl that is dug out in the middle with a raised rim around it
+fn crater_profile(distance: f32) -> f32 {
+    const RIM: f32 = 0.7;
+    const RIM_HEIGHT: f32 = 0.3;
+
+    if distance >= 1.0 {
+        0.0
+    } else if distance < RIM {
+        let t = distance / RIM;
+        -1.0 + t * t * (1.0 + RIM_HEIGHT)
+    } else {
+        RIM_HEIGHT * (1.0 - smoothstep(RIM, 1.0, distance))
+    }
+}
+
+/// Hermite interpolation of `x` between `edge0` and `edge1`, like the wgsl builtin.
+pub fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use super::*;
+
+    // only the right third of the image is painted
+    fn right_side_shape(rotation: f32) -> BrushShape {
+        let alpha = BrushAlpha::from_values("right_side", 3, 1, vec![0.0, 0.0, 1.0]).unwrap();
+
+        BrushShape {
+            kind: BrushShapeKind::Alpha(Arc::new(alpha)),
+            rotation,
+        }
+    }
+
+    #[test]
+    fn test_alpha_height_follows_rotation() {
+        let radius = 10.0;
+
+        let shape = right_side_shape(0.0);
+        assert_eq!(shape.height(Vec2::new(5.0, 0.0), radius, 0.0), 0.5);
+        assert_eq!(shape.height(Vec2::new(-5.0, 0.0), radius, 0.0), 0.0);
+        assert_eq!(shape.height(Vec2::new(0.0, 5.0), radius, 0.0), 0.0);
+
+        // a quarter turn counter clockwise moves the painted side up
+        let shape = right_side_shape(FRAC_PI_2);
+        assert!((shape.height(Vec2::new(0.0, 5.0), radius, 0.0) - 0.5).abs() < 1e-5);
+        assert!(shape.height(Vec2::new(5.0, 0.0), radius, 0.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_alpha_contains_follows_rotation() {
+        let radius = 10.0;
+
+        let shape = right_side_shape(0.0);
+        assert!(shape.contains(Vec2::new(5.0, 0.0), radius));
+        assert!(!shape.contains(Vec2::new(-5.0, 0.0), radius));
+
+        let shape = right_side_shape(PI);
+        assert!(!shape.contains(Vec2::new(5.0, 0.0), radius));
+        assert!(shape.contains(Vec2::new(-5.0, 0.0), radius));
+
+        // clipped to the brush radius even where the image is painted
+        assert!(!shape.contains(Vec2::new(-10.5, 0.0), radius));
+    }
+
+    #[test]
+    fn test_round_shapes_ignore_rotation() {
+        let radius = 8.0;
+        let offset = Vec2::new(3.0, 2.0);
+
+        for kind in BrushShapeKind::BUILT_IN {
+            let unrotated = BrushShape::new(kind.clone());
+            let rotated = BrushShape { kind, rotation: 1.3 };
+
+            assert!((unrotated.height(offset, radius, 0.5) - rotated.height(offset, radius, 0.5)).abs() < 1e-5);
+            assert_eq!(unrotated.contains(offset, radius), rotated.contains(offset, radius));
+        }
+    }
+
+    #[test]
+    fn test_stamp_profiles() {
+        let radius = 10.0;
+
+        let mountain = BrushShape::new(BrushShapeKind::Mountain);
+        assert_eq!(mountain.height(Vec2::ZERO, radius, 0.0), 1.0);
+        assert_eq!(mountain.height(Vec2::new(10.0, 0.0), radius, 0.0), 0.0);
+
+        let crater = BrushShape::new(BrushShapeKind::Crater);
+        assert_eq!(crater.height(Vec2::ZERO, radius, 0.0), -1.0);
+        assert!(crater.height(Vec2::new(7.0, 0.0), radius, 0.0) > 0.0);
+        assert_eq!(crater.weight(Vec2::ZERO, radius, 0.0), 1.0);
+
+        let plateau = BrushShape::new(BrushShapeKind::Plateau);
+        assert_eq!(plateau.height(Vec2::new(5.0, 0.0), radius, 0.0), 1.0);
+        assert!(!plateau.contains(Vec2::new(10.0, 0.0), radius));
+    }
+}
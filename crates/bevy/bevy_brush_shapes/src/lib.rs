@@ -0,0 +1,21 @@
+/*
+
+Brush shapes shared by the terrain height, splat, region and foliage density paint tools.
+
+A brush used to be a circle with a radius and a hardness. A BrushShape can also be an image
+alpha (any grayscale png from a brushes folder) or one of the built in height stamps
+(mountain, crater, plateau). Shapes can be rotated, and a BrushScatter turns one stroke into
+several jittered dabs.
+
+The paint tools only ever ask a shape for a weight at an offset from the brush center, so the
+same shape works for every kind of map.
+
+*/
+
+pub mod brush_alpha;
+pub mod brush_shape;
+pub mod scatter;
+
+pub use brush_alpha::BrushAlpha;
+pub use brush_shape::{BrushShape, BrushShapeKind};
+pub use scatter::{BrushDab, BrushScatter};
@@ -0,0 +1,133 @@
+use std::f32::consts::TAU;
+
+use bevy::math::Vec2;
+use rand::Rng;
+
+use crate::brush_shape::BrushShape;
+
+/// Turns a single brush application into several randomly placed, rotated and scaled dabs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BrushScatter {
+    // dabs per brush application
+    pub count: u32,
+    // how far a dab can land from the cursor, as a fraction of the brush radius
+    pub spread: f32,
+    // radians
+    pub rotation_jitter: f32,
+    // fraction of the brush radius
+    pub scale_jitter: f32,
+}
+
+impl Default for BrushScatter {
+    fn default() -> Self {
+        Self {
+            count: 1,
+            spread: 0.0,
+            rotation_jitter: 0.0,
+            scale_jitter: 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BrushDab {
+    pub coordinates: Vec2,
+    pub radius: f32,
+    pub shape: BrushShape,
+}
+
+impl BrushScatter {
+    /// Without any jitter this is a single dab that matches the inputs.
+    pub fn dabs<R: Rng>(&self, shape: &BrushShape, coordinates: Vec2, radius: f32, rng: &mut R) -> Vec<BrushDab> {
+        (0..self.count.max(1))
+            .map(|_| {
+                let mut dab = BrushDab {
+                    coordinates,
+                    radius,
+                    shape: shape.clone(),
+                };
+
+                if self.spread > 0.0 {
+                    // uniform over the disc
+                    let angle = rng.gen_range(0.0..TAU);
+                    let distance = self.spread * radius * rng.gen_range(0.0f32..=1.0).sqrt();
+                    dab.coordinates += Vec2::from_angle(angle) * distance;
+                }
+
+                if self.rotation_jitter > 0.0 {
+                    dab.shape.rotation += rng.gen_range(-self.rotation_jitter..=self.rotation_jitter);
+                }
+
+                if self.scale_jitter > 0.0 {
+                    let scale = 1.0 + rng.gen_range(-self.scale_jitter..=self.scale_jitter);
+                    dab.radius = radius * scale.max(0.05);
+                }
+
+                dab
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::brush_shape::BrushShapeKind;
+
+    #[test]
+    fn test_no_jitter_is_one_unchanged_dab() {
+        let shape = BrushShape {
+            kind: BrushShapeKind::Crater,
+            rotation: 0.4,
+        };
+        let coordinates = Vec2::new(12.0, -3.0);
+        let mut rng = StdRng::seed_from_u64(7);
+
+        let dabs = BrushScatter::default().dabs(&shape, coordinates, 6.0, &mut rng);
+
+        assert_eq!(
+            dabs,
+            vec![BrushDab {
+                coordinates,
+                radius: 6.0,
+                shape,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_zero_count_still_dabs_once() {
+        let scatter = BrushScatter {
+            count: 0,
+            ..BrushScatter::default()
+        };
+        let mut rng = StdRng::seed_from_u64(7);
+
+        assert_eq!(scatter.dabs(&BrushShape::default(), Vec2::ZERO, 1.0, &mut rng).len(), 1);
+    }
+
+    #[test]
+    fn test_jitter_stays_in_range() {
+        let scatter = BrushScatter {
+            count: 64,
+            spread: 0.5,
+            rotation_jitter: 0.25,
+            scale_jitter: 0.2,
+        };
+        let coordinates = Vec2::new(100.0, 50.0);
+        let radius = 10.0;
+        let mut rng = StdRng::seed_from_u64(7);
+
+        let dabs = scatter.dabs(&BrushShape::default(), coordinates, radius, &mut rng);
+        assert_eq!(dabs.len(), 64);
+
+        for dab in dabs {
+            assert!(dab.coordinates.distance(coordinates) <= 5.0 + 1e-4);
+            assert!(dab.shape.rotation.abs() <= 0.25);
+            assert!((8.0..=12.0).contains(&dab.radius));
+        }
+    }
+}
@@ -335,7 +335,7 @@ fn init_build_clay_tile_block (
 
 
 //should spatially offset the layer at the appropriate height
-#[derive(Component,Clone,Serialize,Deserialize,Reflect,Debug)]
+#[derive(Component,Clone,Serialize,Deserialize,Reflect,Debug,PartialEq)]
 #[reflect(Component)]
 pub struct ClayTileBlock {
 
@@ -8,7 +8,7 @@ use core::f32::consts::PI;
 use bevy::color::palettes::tailwind;
 use bevy::math::VectorSpace;
 use bevy::prelude::*;
-use bevy::ecs::event::{EventReader, EventWriter};
+use bevy::ecs::message::{MessageReader, MessageWriter};
 use geo::{MultiPolygon, BooleanOps, CoordsIter, LineString, OpType, Polygon};
 use bevy::mesh::Indices;
 use bevy::asset::RenderAssetUsages;
@@ -39,7 +39,7 @@ pub(crate) fn tile_edit_plugin(app: &mut App) {
 
 
 // entity, editToolType, coords, magnitude
-#[derive(Event, Debug, Clone)]
+#[derive(Message, Debug, Clone)]
 pub struct BuildGridInteractionEvent {
       
          coordinates: Vec2, 
@@ -48,7 +48,7 @@ pub struct BuildGridInteractionEvent {
 }
 
 
-#[derive(Event, Debug, Clone)]
+#[derive(Message, Debug, Clone)]
 pub enum TileSelectionEvent {
       
     SelectTile(Entity),
@@ -360,7 +360,7 @@ fn listen_for_input_events (
     cursor_ray: Res<RayMap>,
   
 
-   mut build_grid_interaction_evt_writer: EventWriter<BuildGridInteractionEvent>,
+   mut build_grid_interaction_evt_writer: MessageWriter<BuildGridInteractionEvent>,
 
   
 ) {
@@ -525,7 +525,7 @@ fn update_build_grid_horizontal_offset (
 /*
 fn handle_grid_interaction_events(  
 
-    mut evt_reader: EventReader<BuildGridInteractionEvent>
+    mut evt_reader: MessageReader<BuildGridInteractionEvent>
 ){
 
     for evt in evt_reader.read() {
@@ -544,7 +544,7 @@ fn handle_grid_interaction_events(
 
 fn handle_polygon_tile_build_events(
     mut commands: Commands,
-    mut evt_reader: EventReader<BuildGridInteractionEvent>,
+    mut evt_reader: MessageReader<BuildGridInteractionEvent>,
     tile_edit_resource: Res<TileEditingResource>,
     mut builder_query: Query<(Entity, &mut ClayTileBlockBuilder)>,
 
@@ -618,7 +618,7 @@ fn handle_polygon_tile_build_events(
 
 fn handle_rectangle_tile_build_events(
     mut commands: Commands,
-    mut evt_reader: EventReader<BuildGridInteractionEvent>,
+    mut evt_reader: MessageReader<BuildGridInteractionEvent>,
     tile_edit_resource: Res<TileEditingResource>,
     mut builder_query: Query<(Entity,&mut ClayTileBlockBuilder)>,
   // root_query: Query<Entity, With< ClayTilesRoot>>,
@@ -717,7 +717,7 @@ fn handle_rectangle_tile_build_events(
 
 fn handle_linear_tile_build_events(
     mut commands: Commands,
-    mut evt_reader: EventReader<BuildGridInteractionEvent>,
+    mut evt_reader: MessageReader<BuildGridInteractionEvent>,
     tile_edit_resource: Res<TileEditingResource>,
     mut builder_query: Query<(Entity,&mut ClayTileBlockBuilder)>,
   // root_query: Query<Entity, With< ClayTilesRoot>>,
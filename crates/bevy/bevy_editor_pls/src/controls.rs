@@ -251,7 +251,7 @@ pub fn editor_controls_system(
     {
         let was_active = editor.active();
         editor.set_active(!was_active);
-        editor_events.send(EditorEvent::Toggle {
+        editor_events.write(EditorEvent::Toggle {
             now_active: !was_active,
         });
     }
@@ -273,7 +273,7 @@ pub fn editor_controls_system(
         &mouse_input,
         &editor,
     ) {
-        editor_events.send(EditorEvent::FocusSelected);
+        editor_events.write(EditorEvent::FocusSelected);
     }
 
 
@@ -283,7 +283,7 @@ pub fn editor_controls_system(
         &mouse_input,
         &editor,
     ) {
-        editor_events.send(EditorEvent::RotateSelectedDoodadByDegrees( Vec3::new(0.0, 45.0, 0.0)   ));
+        editor_events.write(EditorEvent::RotateSelectedDoodadByDegrees( Vec3::new(0.0, 45.0, 0.0)   ));
     }
 
     if controls.just_pressed(
@@ -292,7 +292,7 @@ pub fn editor_controls_system(
         &mouse_input,
         &editor,
     ) {
-        editor_events.send(EditorEvent::RotateSelectedDoodadByDegrees(  Vec3::new(0.0, -45.0, 0.0)   ));
+        editor_events.write(EditorEvent::RotateSelectedDoodadByDegrees(  Vec3::new(0.0, -45.0, 0.0)   ));
     }
 
 
@@ -302,7 +302,7 @@ pub fn editor_controls_system(
         &mouse_input,
         &editor,
     ) {
-        editor_events.send(EditorEvent::RotateSelectedDoodadByDegrees(   Vec3::new( 0.0 ,0.0,  45.0 )   ));
+        editor_events.write(EditorEvent::RotateSelectedDoodadByDegrees(   Vec3::new( 0.0 ,0.0,  45.0 )   ));
     }
 
 
@@ -313,7 +313,7 @@ pub fn editor_controls_system(
             &mouse_input,
             &editor,
         ) {
-            editor_events.send(EditorEvent::DeleteSelectedEntities);
+            editor_events.write(EditorEvent::DeleteSelectedEntities);
         }
 
 
@@ -324,7 +324,7 @@ pub fn editor_controls_system(
         &mouse_input,
         &editor,
     ) {
-        editor_events.send(EditorEvent::SetSelectedEntities(None));
+        editor_events.write(EditorEvent::SetSelectedEntities(None));
     }
  
 
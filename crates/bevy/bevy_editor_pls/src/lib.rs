@@ -21,6 +21,7 @@ pub use bevy_editor_pls_core::{editor, editor_window, AddEditorWindow};
 use default_windows::{
     materials::MaterialsWindow, 
     placement::PlacementWindow, 
+    selection::SelectionWindow, 
     prefabs::PrefabsWindow, 
     StandardWindowsPlugin};
 pub use egui;
@@ -141,6 +142,9 @@ impl Plugin for EditorPlugin {
             use bevy_editor_pls_default_windows::doodads::DoodadsWindow;
             use bevy_editor_pls_default_windows::prefabs::PrefabsWindow;
             use bevy_editor_pls_default_windows::zones::ZoneWindow;
+            use bevy_editor_pls_default_windows::zone_validation::ZoneValidationWindow;
+            use bevy_editor_pls_default_windows::zone_streaming::ZoneStreamingWindow;
+            use bevy_editor_pls_default_windows::history::HistoryWindow;
 
             app.add_editor_window::<HierarchyWindow>();
             app.add_editor_window::<AssetsWindow>();
@@ -155,8 +159,12 @@ impl Plugin for EditorPlugin {
             app.add_editor_window::<ResourcesWindow>();
             app.add_editor_window::<LightingWindow>();
             app.add_editor_window::<ZoneWindow>();
+            app.add_editor_window::<ZoneValidationWindow>();
+            app.add_editor_window::<ZoneStreamingWindow>();
+            app.add_editor_window::<HistoryWindow>();
             app.add_editor_window::<GizmoWindow>();
             app.add_editor_window::<PlacementWindow>();
+            app.add_editor_window::<SelectionWindow>();
             app.add_editor_window::<MaterialsWindow>();
             app.add_editor_window::<controls::ControlsWindow>();
 
@@ -183,6 +191,7 @@ impl Plugin for EditorPlugin {
                 &[
                     std::any::TypeId::of::<InspectorWindow>(),
                     std::any::TypeId::of::<PlacementWindow>(),
+                    std::any::TypeId::of::<SelectionWindow>(),
                     std::any::TypeId::of::<MaterialsWindow>(),
                 ],
             );
@@ -204,6 +213,9 @@ impl Plugin for EditorPlugin {
                 egui_dock::Split::Below,
                 &[
                     std::any::TypeId::of::<ZoneWindow>(),
+                    std::any::TypeId::of::<ZoneValidationWindow>(),
+                    std::any::TypeId::of::<ZoneStreamingWindow>(),
+                    std::any::TypeId::of::<HistoryWindow>(),
                     std::any::TypeId::of::<ResourcesWindow>(),
                  //   std::any::TypeId::of::<AssetsWindow>(),
                     std::any::TypeId::of::<LightingWindow>(),
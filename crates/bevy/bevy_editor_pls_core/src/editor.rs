@@ -11,7 +11,7 @@ use indexmap::IndexMap;
 use crate::editor_window::{EditorWindow, EditorWindowContext};
 
 #[non_exhaustive]
-#[derive(Event)]
+#[derive(Message)]
 pub enum EditorEvent {
     Toggle { now_active: bool },
     FocusSelected,
@@ -289,7 +289,7 @@ impl Editor {
 
             world.resource_scope(
                 |world, mut editor_internal_state: Mut<EditorInternalState>| {
-                    world.resource_scope(|world, mut editor_events: Mut<Events<EditorEvent>>| {
+                    world.resource_scope(|world, mut editor_events: Mut<Messages<EditorEvent>>| {
                         editor.editor_ui(
                             world,
                             &egui_context,
@@ -307,7 +307,7 @@ impl Editor {
         world: &mut World,
         ctx: &egui::Context,
         internal_state: &mut EditorInternalState,
-        editor_events: &mut Events<EditorEvent>,
+        editor_events: &mut Messages<EditorEvent>,
     ) {
         self.editor_menu_bar(world, ctx, internal_state, editor_events);
 
@@ -365,13 +365,13 @@ impl Editor {
         world: &mut World,
         ctx: &egui::Context,
         internal_state: &mut EditorInternalState,
-        editor_events: &mut Events<EditorEvent>,
+        editor_events: &mut Messages<EditorEvent>,
     ) {
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
             let bar_response = egui::MenuBar::new().show(ui, |ui| {
                 if !self.always_active && play_pause_button(self.active, ui).clicked() {
                     self.active = !self.active;
-                    editor_events.send(EditorEvent::Toggle {
+                    editor_events.write(EditorEvent::Toggle {
                         now_active: self.active,
                     });
                 }
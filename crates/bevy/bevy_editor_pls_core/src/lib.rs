@@ -86,7 +86,7 @@ impl Plugin for EditorPlugin {
 
         app.insert_resource(Editor::new(window_entity, always_active))
             .init_resource::<EditorInternalState>()
-            .add_event::<EditorEvent>()
+            .add_message::<EditorEvent>()
             .configure_sets(PostUpdate, EditorSet::UI)
             .add_systems(
                 Update,
@@ -0,0 +1,304 @@
+use std::path::Path;
+
+use bevy::prelude::*;
+use bevy_inspector_egui::egui::{self, RichText};
+
+use spirit_edit_core::doodads::doodad::DoodadComponent;
+use spirit_edit_core::doodads::prop_schema::{CustomPropKind, CustomPropSchema};
+use spirit_edit_core::history::{EditorCommand, EditorHistoryEvent, SceneEntitySnapshot};
+use spirit_edit_core::zones::zone_file::{CustomProp, CustomPropsComponent, ZoneEntityId};
+
+/*
+
+Inspector widgets for the custom props a doodad declares in its prop schema.  Props that
+are not in the schema are still edited through the reflect inspector below.
+
+*/
+
+// entity refs are picked from the named entities that have a zone id
+struct EntityRefChoices(Vec<(ZoneEntityId, String)>);
+
+impl EntityRefChoices {
+    fn from_world(world: &mut World) -> Self {
+        let mut query = world.query::<(&ZoneEntityId, &Name)>();
+
+        let mut choices: Vec<(ZoneEntityId, String)> = query
+            .iter(world)
+            .map(|(zone_entity_id, name)| (*zone_entity_id, name.to_string()))
+            .collect();
+
+        choices.sort_by(|a, b| a.1.cmp(&b.1).then(a.0.cmp(&b.0)));
+
+        Self(choices)
+    }
+
+    fn label(&self, zone_entity_id: &Option<ZoneEntityId>) -> String {
+        let Some(zone_entity_id) = zone_entity_id else {
+            return "None".into();
+        };
+
+        match self.0.iter().find(|(id, _)| id == zone_entity_id) {
+            Some((_, name)) => name.clone(),
+            None => format!("missing {}", zone_entity_id),
+        }
+    }
+}
+
+pub fn custom_props_schema_ui(world: &mut World, entity: Entity, ui: &mut egui::Ui) {
+    let Some(prop_schema) = world
+        .get::<DoodadComponent>(entity)
+        .and_then(|doodad| doodad.definition.prop_schema.clone())
+    else {
+        return;
+    };
+
+    let mut props = world
+        .get::<CustomPropsComponent>(entity)
+        .map(|comp| comp.props.clone())
+        .unwrap_or_default();
+
+    let entity_ref_choices = EntityRefChoices::from_world(world);
+
+    let mut changed = false;
+
+    egui::CollapsingHeader::new("Custom Props")
+        .default_open(true)
+        .show(ui, |ui| {
+            egui::Grid::new(("custom_props_schema", entity))
+                .num_columns(2)
+                .show(ui, |ui| {
+                    for prop_schema in prop_schema.iter() {
+                        let label = ui.label(&prop_schema.key);
+                        if let Some(description) = &prop_schema.description {
+                            label.on_hover_text(description);
+                        }
+
+                        // unset keys are only written once their widget changes them
+                        let mut prop = props.get(&prop_schema.key).cloned().unwrap_or(CustomProp::EmptyProp);
+
+                        if schema_prop_ui(ui, prop_schema, &mut prop, &entity_ref_choices) {
+                            props.insert(prop_schema.key.clone(), prop);
+                            changed = true;
+                        }
+
+                        ui.end_row();
+                    }
+                });
+        });
+
+    if !changed {
+        return;
+    }
+
+    let before = SceneEntitySnapshot::from_world(world, entity);
+
+    match world.get_mut::<CustomPropsComponent>(entity) {
+        Some(mut custom_props_comp) => custom_props_comp.props = props,
+        None => {
+            world.entity_mut(entity).insert(CustomPropsComponent { props });
+        }
+    }
+
+    // edits in quick succession (typing, dragging a value) are merged into one entry by the history
+    if let (Some(before), Some(after)) = (before, SceneEntitySnapshot::from_world(world, entity)) {
+        world.write_message(EditorHistoryEvent::Record(vec![EditorCommand::Modify { entity, before, after }]));
+    }
+}
+
+fn schema_prop_ui(
+    ui: &mut egui::Ui,
+    prop_schema: &CustomPropSchema,
+    prop: &mut CustomProp,
+    entity_ref_choices: &EntityRefChoices,
+) -> bool {
+    // a missing or mistyped value can only be replaced, the widgets need the right variant
+    if !prop_matches_kind(&prop_schema.kind, prop) {
+        let mut changed = false;
+
+        ui.horizontal(|ui| {
+            ui.label(RichText::new(format!("{:?}", prop)).color(egui::Color32::RED));
+
+            if ui.button("Set default").clicked() {
+                *prop = prop_schema.default_value();
+                changed = true;
+            }
+        });
+
+        return changed;
+    }
+
+    let changed = prop_ui(ui, &prop_schema.kind, prop, entity_ref_choices, &prop_schema.key);
+
+    if !prop_schema.kind.accepts(prop) {
+        ui.label(RichText::new("out of range").color(egui::Color32::YELLOW));
+    }
+
+    changed
+}
+
+fn prop_matches_kind(kind: &CustomPropKind, prop: &CustomProp) -> bool {
+    matches!(
+        (kind, prop),
+        (CustomPropKind::Vec2, CustomProp::Vec2(_))
+            | (CustomPropKind::Vec3, CustomProp::Vec3(_))
+            | (CustomPropKind::String, CustomProp::String(_))
+            | (CustomPropKind::Float { .. }, CustomProp::Float(_))
+            | (CustomPropKind::Integer { .. }, CustomProp::Integer(_))
+            | (CustomPropKind::Boolean, CustomProp::Boolean(_))
+            | (CustomPropKind::Color, CustomProp::Color(_))
+            | (CustomPropKind::Enum { .. }, CustomProp::Enum(_))
+            | (CustomPropKind::EntityRef, CustomProp::EntityRef(_))
+            | (CustomPropKind::List(_), CustomProp::List(_))
+            | (CustomPropKind::AssetPath { .. }, CustomProp::AssetPath(_))
+    )
+}
+
+fn prop_ui(
+    ui: &mut egui::Ui,
+    kind: &CustomPropKind,
+    prop: &mut CustomProp,
+    entity_ref_choices: &EntityRefChoices,
+    id_salt: &str,
+) -> bool {
+    match (kind, prop) {
+        (CustomPropKind::Vec2, CustomProp::Vec2(value)) => {
+            ui.horizontal(|ui| {
+                ui.add(egui::DragValue::new(&mut value.x).speed(0.1)).changed()
+                    | ui.add(egui::DragValue::new(&mut value.y).speed(0.1)).changed()
+            })
+            .inner
+        }
+
+        (CustomPropKind::Vec3, CustomProp::Vec3(value)) => {
+            ui.horizontal(|ui| {
+                ui.add(egui::DragValue::new(&mut value.x).speed(0.1)).changed()
+                    | ui.add(egui::DragValue::new(&mut value.y).speed(0.1)).changed()
+                    | ui.add(egui::DragValue::new(&mut value.z).speed(0.1)).changed()
+            })
+            .inner
+        }
+
+        (CustomPropKind::String, CustomProp::String(value)) => ui.text_edit_singleline(value).changed(),
+
+        (CustomPropKind::Float { min, max }, CustomProp::Float(value)) => ui
+            .add(
+                egui::DragValue::new(value)
+                    .speed(0.1)
+                    .range(min.unwrap_or(f32::MIN)..=max.unwrap_or(f32::MAX)),
+            )
+            .changed(),
+
+        (CustomPropKind::Integer { min, max }, CustomProp::Integer(value)) => ui
+            .add(egui::DragValue::new(value).range(min.unwrap_or(i32::MIN)..=max.unwrap_or(i32::MAX)))
+            .changed(),
+
+        (CustomPropKind::Boolean, CustomProp::Boolean(value)) => ui.checkbox(value, "").changed(),
+
+        (CustomPropKind::Color, CustomProp::Color(value)) => {
+            let mut srgba = value.to_srgba().to_u8_array();
+
+            let changed = ui.color_edit_button_srgba_unmultiplied(&mut srgba).changed();
+
+            if changed {
+                *value = Color::srgba_u8(srgba[0], srgba[1], srgba[2], srgba[3]);
+            }
+
+            changed
+        }
+
+        (CustomPropKind::Enum { choices }, CustomProp::Enum(value)) => {
+            let mut changed = false;
+
+            egui::ComboBox::from_id_salt(("custom_prop_enum", id_salt))
+                .selected_text(value.as_str())
+                .show_ui(ui, |ui| {
+                    for choice in choices.iter() {
+                        changed |= ui.selectable_value(value, choice.clone(), choice).changed();
+                    }
+                });
+
+            changed
+        }
+
+        (CustomPropKind::EntityRef, CustomProp::EntityRef(value)) => {
+            let mut changed = false;
+
+            egui::ComboBox::from_id_salt(("custom_prop_entity_ref", id_salt))
+                .selected_text(entity_ref_choices.label(value))
+                .show_ui(ui, |ui| {
+                    changed |= ui.selectable_value(value, None, "None").changed();
+
+                    for (zone_entity_id, name) in entity_ref_choices.0.iter() {
+                        changed |= ui
+                            .selectable_value(value, Some(*zone_entity_id), name)
+                            .on_hover_text(zone_entity_id.to_string())
+                            .changed();
+                    }
+                });
+
+            changed
+        }
+
+        (CustomPropKind::List(item_kind), CustomProp::List(items)) => {
+            let mut changed = false;
+
+            ui.vertical(|ui| {
+                let mut removed_index = None;
+
+                for (i, item) in items.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        changed |= prop_ui(ui, item_kind, item, entity_ref_choices, &format!("{}.{}", id_salt, i));
+
+                        if ui.small_button("x").clicked() {
+                            removed_index = Some(i);
+                        }
+                    });
+                }
+
+                if let Some(removed_index) = removed_index {
+                    items.remove(removed_index);
+                    changed = true;
+                }
+
+                if ui.small_button("+").clicked() {
+                    items.push(item_kind.zero_value());
+                    changed = true;
+                }
+            });
+
+            changed
+        }
+
+        (CustomPropKind::AssetPath { extensions }, CustomProp::AssetPath(path)) => {
+            ui.horizontal(|ui| {
+                let mut text_edit = egui::TextEdit::singleline(path);
+
+                if let Some(extensions) = extensions {
+                    text_edit = text_edit.hint_text(extensions.join(" "));
+                }
+
+                let changed = ui.add(text_edit).changed();
+
+                // hitting the file system every frame is slow, check again only when the text changes
+                let exists_id = egui::Id::new(("custom_prop_asset_exists", id_salt));
+                let exists = match ui.data(|data| data.get_temp::<(String, bool)>(exists_id)) {
+                    Some((checked_path, exists)) if checked_path == *path => exists,
+                    _ => {
+                        let exists = Path::new("assets").join(path.as_str()).exists();
+                        ui.data_mut(|data| data.insert_temp(exists_id, (path.clone(), exists)));
+                        exists
+                    }
+                };
+
+                if !path.is_empty() && !exists {
+                    ui.label(RichText::new("missing").color(egui::Color32::RED));
+                }
+
+                changed
+            })
+            .inner
+        }
+
+        _ => false,
+    }
+}
@@ -14,9 +14,13 @@ use bevy_inspector_egui::{bevy_inspector::hierarchy::SelectedEntities, egui};
 use transform_gizmo_bevy::{Gizmo,GizmoOrientation,GizmoMode};
 
 
+use spirit_edit_core::history::EditorHistoryEvent;
+use spirit_edit_core::selection::{PivotMode, SelectionToolsState};
+
 use crate::{
     cameras::{ActiveEditorCamera, CameraWindow, EditorCamera, EDITOR_RENDER_LAYER},
     hierarchy::HierarchyWindow,
+    selection::active_selected_entity,
 };
 
 
@@ -41,14 +45,68 @@ impl Plugin for GizmoPlugin {
 
 pub fn update_gizmo_options (
 
-    mut gizmo_options: ResMut<GizmoOptions>
+    mut gizmo_options: ResMut<GizmoOptions>,
+
+    selection_tools_state: Res<SelectionToolsState>,
+    editor: Res<Editor>,
+    global_xform_query: Query<&GlobalTransform>,
 ){
 
      gizmo_options.gizmo_orientation = GizmoOrientation::Local; 
-   // gizmo_options.pivot_point = TransformPivotPoint::
 
+    let active_position = editor
+        .window_state::<HierarchyWindow>()
+        .and_then(|state| active_selected_entity(&state.selected))
+        .and_then(|entity| global_xform_query.get(entity).ok())
+        .map(|xform| xform.translation());
+
+    let (pivot_point, pivot_position) = match selection_tools_state.pivot_mode {
+        PivotMode::MedianPoint => (TransformPivotPoint::MedianPoint, None),
+        PivotMode::IndividualOrigins => (TransformPivotPoint::IndividualOrigins, None),
+        PivotMode::ActiveElement => match active_position {
+            Some(position) => (TransformPivotPoint::Position, Some(position)),
+            None => (TransformPivotPoint::MedianPoint, None),
+        },
+        PivotMode::Cursor3d => (TransformPivotPoint::Position, Some(selection_tools_state.cursor_3d)),
+    };
+
+    // only write when changed so the options are not flagged every frame
+    if gizmo_options.pivot_point != pivot_point {
+        gizmo_options.pivot_point = pivot_point;
+    }
+    if let Some(pivot_position) = pivot_position {
+        if gizmo_options.pivot_position != pivot_position {
+            gizmo_options.pivot_position = pivot_position;
+        }
+    }
+
+}
 
 
+// the gizmo moves its targets in Last, so a drag that starts this frame has not moved anything
+// yet when the history snapshots the targets in PostUpdate
+pub fn record_gizmo_drags(
+    mouse_input: Res<ButtonInput<MouseButton>>,
+
+    gizmo_target_query: Query<(Entity, &GizmoTarget)>,
+
+    mut history_evt_writer: MessageWriter<EditorHistoryEvent>,
+    mut dragging: Local<bool>,
+) {
+    if !*dragging
+        && mouse_input.just_pressed(MouseButton::Left)
+        && gizmo_target_query.iter().any(|(_, target)| target.is_focused())
+    {
+        let targets = gizmo_target_query.iter().map(|(entity, _)| entity).collect();
+
+        history_evt_writer.write(EditorHistoryEvent::BeginEdit(targets));
+        *dragging = true;
+    }
+
+    if *dragging && !mouse_input.pressed(MouseButton::Left) {
+        history_evt_writer.write(EditorHistoryEvent::EndEdit);
+        *dragging = false;
+    }
 }
 
 
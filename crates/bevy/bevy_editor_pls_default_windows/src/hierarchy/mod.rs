@@ -2,6 +2,7 @@
 
  // use crate::debug_settings::DebugSettingsWindow;
 use spirit_edit_core::doodads::rotate::RotateByDegrees;
+use spirit_edit_core::history::EditorHistoryEvent;
 use spirit_edit_core::prefabs::{PrefabComponent,SavePrefabToFileEvent};
 use spirit_edit_core::zones::SaveZoneToFileEvent;
 use spirit_edit_core::zones::ZoneComponent;
@@ -255,8 +256,9 @@ impl<'a> Hierarchy<'a> {
 
         if let Some(entity) = despawn_recursive {
 
-           let _despawned =   self.world.despawn( entity );  //also despawns children! 
-         //    despawn_with_children_recursive(self.world, entity, false);
+            // the history snapshots the entity and its children before despawning them, so it can be undone
+            self.world.write_message(EditorHistoryEvent::DeleteEntities(vec![entity]));
+            self.state.selected.remove(entity);
         }
         if let Some(entity) = despawn {
             self.world.entity_mut(entity).despawn();
@@ -323,10 +325,28 @@ pub fn listen_for_select_entities_events(
 
     mut commands: Commands , 
 
+    mut history_evt_writer: MessageWriter<EditorHistoryEvent>,
 
+    // (entities, degrees) waiting for their BeginEdit snapshot, the history takes it in PostUpdate
+    mut pending_rotations: Local<Vec<(Vec<Entity>, Vec3)>>,
     
     ){
 
+    let ready_rotations = std::mem::take(&mut *pending_rotations);
+
+    for (entities, degrees_vec) in ready_rotations.iter() {
+        for entity in entities.iter() {
+            if let Ok(mut cmd) = commands.get_entity( *entity ){
+                cmd.queue( RotateByDegrees( *degrees_vec )  ) ;  // now an entity command ! 
+            }
+        }
+    }
+
+    // EndEdit before the next BeginEdit, a BeginEdit drops the baselines taken so far
+    if !ready_rotations.is_empty() {
+        history_evt_writer.write(EditorHistoryEvent::EndEdit);
+    }
+
 
     for evt in editor_evt_reader.read(){
 
@@ -351,14 +371,8 @@ pub fn listen_for_select_entities_events(
              EditorEvent::RotateSelectedDoodadByDegrees( degrees_vec ) => {
                  let state = editor.window_state_mut::<HierarchyWindow>().unwrap();
 
-                 
-                    for entity in  state.selected.iter() {
-                        if let Ok(mut cmd) = commands.get_entity( entity ){
-                            cmd.queue( RotateByDegrees( degrees_vec.clone () )  ) ;  // now an entity command ! 
-                           // cmd.insert(RotateByDegrees( degrees_vec.clone () ));
-                        }
-                   
-                    }
+                    // rotated next frame, after the history has snapshotted the selection
+                    pending_rotations.push(( state.selected.iter().collect(), *degrees_vec ));
 
 
             }
@@ -368,13 +382,9 @@ pub fn listen_for_select_entities_events(
                    let state = editor.window_state_mut::<HierarchyWindow>().unwrap();
 
                 //if ui.input(|input| input.key_pressed(egui::Key::Delete)) {
-                    for entity in  state.selected.iter() {
-                        if let Ok(mut cmd) = commands.get_entity( entity ){
-
-                            cmd.despawn_recursive();
-                        }
-                   
-                    }
+                    history_evt_writer.write(EditorHistoryEvent::DeleteEntities(
+                        state.selected.iter().collect(),
+                    ));
                      state.selected.clear();
                 //}
 
@@ -387,6 +397,15 @@ pub fn listen_for_select_entities_events(
 
     }
 
+    if !pending_rotations.is_empty() {
+        let targets = pending_rotations
+            .iter()
+            .flat_map(|(entities, _)| entities.iter().copied())
+            .collect();
+
+        history_evt_writer.write(EditorHistoryEvent::BeginEdit(targets));
+    }
+
 
 }
 
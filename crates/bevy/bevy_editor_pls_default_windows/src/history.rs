@@ -0,0 +1,96 @@
+use spirit_edit_core::history::{EditorHistoryEvent, EditorHistoryResource};
+
+use bevy::prelude::*;
+use bevy_editor_pls_core::{editor_window::{EditorWindow, EditorWindowContext}, Editor};
+use bevy_inspector_egui::egui::{self, RichText};
+
+#[derive(Default)]
+pub struct HistoryWindowState {}
+
+pub struct HistoryWindow;
+
+impl EditorWindow for HistoryWindow {
+    type State = HistoryWindowState;
+    const NAME: &'static str = "History";
+
+    fn ui(world: &mut World, _cx: EditorWindowContext, ui: &mut egui::Ui) {
+        let history = world.resource::<EditorHistoryResource>();
+
+        let cursor = history.cursor();
+        let can_undo = history.can_undo();
+        let can_redo = history.can_redo();
+
+        let descriptions: Vec<String> = history
+            .entries()
+            .iter()
+            .map(|entry| entry.description.clone())
+            .collect();
+
+        let mut request = None;
+
+        ui.horizontal(|ui| {
+            if ui.add_enabled(can_undo, egui::Button::new("Undo")).clicked() {
+                request = Some(EditorHistoryEvent::Undo);
+            }
+            if ui.add_enabled(can_redo, egui::Button::new("Redo")).clicked() {
+                request = Some(EditorHistoryEvent::Redo);
+            }
+            if ui.button("Clear").clicked() {
+                request = Some(EditorHistoryEvent::Clear);
+            }
+        });
+
+        ui.label("Undo: [ key: ctrl+Z ]   Redo: [ keys: ctrl+Y , ctrl+shift+Z ]");
+        ui.separator();
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            // state index 0 is the scene before any recorded entry
+            if ui.selectable_label(cursor == 0, "(initial state)").clicked() {
+                request = Some(EditorHistoryEvent::JumpTo(0));
+            }
+
+            for (index, description) in descriptions.iter().enumerate() {
+                let state_index = index + 1;
+
+                let label = if state_index > cursor {
+                    RichText::new(description).weak()
+                } else {
+                    RichText::new(description)
+                };
+
+                if ui.selectable_label(state_index == cursor, label).clicked() {
+                    request = Some(EditorHistoryEvent::JumpTo(state_index));
+                }
+            }
+        });
+
+        if let Some(request) = request {
+            world.write_message(request);
+        }
+    }
+}
+
+pub fn update_history_shortcuts(
+    key_inputs: Res<ButtonInput<KeyCode>>,
+
+    mut history_evt_writer: MessageWriter<EditorHistoryEvent>,
+
+    editor: Res<Editor>,
+) {
+    if !editor.active() {
+        return;
+    }
+
+    let ctrl_pressed = key_inputs.pressed(KeyCode::ControlLeft) || key_inputs.pressed(KeyCode::ControlRight);
+    let shift_pressed = key_inputs.pressed(KeyCode::ShiftLeft) || key_inputs.pressed(KeyCode::ShiftRight);
+
+    if !ctrl_pressed {
+        return;
+    }
+
+    if key_inputs.just_pressed(KeyCode::KeyY) || (shift_pressed && key_inputs.just_pressed(KeyCode::KeyZ)) {
+        history_evt_writer.write(EditorHistoryEvent::Redo);
+    } else if key_inputs.just_pressed(KeyCode::KeyZ) {
+        history_evt_writer.write(EditorHistoryEvent::Undo);
+    }
+}
@@ -1,6 +1,7 @@
 use std::any::TypeId;
 
 use super::add::{AddWindow, AddWindowState};
+use super::custom_props::custom_props_schema_ui;
 use super::hierarchy::HierarchyWindow;
 use bevy::asset::UntypedAssetId;
 use bevy::prelude::{AppTypeRegistry, Entity, World};
@@ -8,6 +9,7 @@ use bevy::reflect::TypeRegistry;
 use bevy_editor_pls_core::editor_window::{EditorWindow, EditorWindowContext};
 use bevy_inspector_egui::bevy_inspector::hierarchy::SelectedEntities;
 use bevy_inspector_egui::{bevy_inspector, egui};
+use spirit_edit_core::history::{EditorCommand, EditorHistoryEvent, SceneEntitySnapshot};
 
 #[derive(Eq, PartialEq)]
 pub enum InspectorSelection {
@@ -66,11 +68,19 @@ fn inspector(
                 ui.label("No entity selected");
             }
             &[entity] => {
+                custom_props_schema_ui(world, entity, ui);
+
+                let before = snapshot_entities(world, &[entity]);
                 bevy_inspector::ui_for_entity(world, entity, ui);
+                record_inspector_edits(world, before);
+
                 add_ui(ui, &[entity], world, add_window_state);
             }
             entities => {
+                let before = snapshot_entities(world, entities);
                 bevy_inspector::ui_for_entities_shared_components(world, entities, ui);
+                record_inspector_edits(world, before);
+
                 add_ui(ui, entities, world, add_window_state);
             }
         },
@@ -85,6 +95,31 @@ fn inspector(
     });
 }
 
+fn snapshot_entities(world: &World, entities: &[Entity]) -> Vec<(Entity, SceneEntitySnapshot)> {
+    entities
+        .iter()
+        .filter_map(|entity| Some((*entity, SceneEntitySnapshot::from_world(world, *entity)?)))
+        .collect()
+}
+
+// the reflect inspector writes components directly, so compare against the snapshots taken before it ran.
+// edits in quick succession (typing, dragging a value) are merged into one entry by the history, like gizmo drags
+fn record_inspector_edits(world: &mut World, before: Vec<(Entity, SceneEntitySnapshot)>) {
+    let commands: Vec<EditorCommand> = before
+        .into_iter()
+        .filter_map(|(entity, before)| {
+            let after = SceneEntitySnapshot::from_world(world, entity)?;
+
+            (before.transform_differs(&after) || before.custom_props_differ(&after))
+                .then_some(EditorCommand::Modify { entity, before, after })
+        })
+        .collect();
+
+    if !commands.is_empty() {
+        world.write_message(EditorHistoryEvent::Record(commands));
+    }
+}
+
 fn add_ui(
     ui: &mut egui::Ui,
     entities: &[Entity],
@@ -10,10 +10,12 @@ use transform_gizmo_bevy::TransformGizmoPlugin;
 pub mod add;
 pub mod assets;
 pub mod cameras;
+pub mod custom_props;
 pub mod debug_settings;
 pub mod diagnostics;
 pub mod gizmos;
 pub mod hierarchy;
+pub mod history;
 pub mod inspector;
 pub mod renderer;
 pub mod resources;
@@ -25,6 +27,9 @@ pub mod materials;
  pub mod prefabs;
  pub mod placement;
  pub mod zones;
+ pub mod zone_validation;
+ pub mod zone_streaming;
+ pub mod selection;
 
 pub struct StandardWindowsPlugin {}
 impl Plugin for StandardWindowsPlugin {
@@ -39,13 +44,15 @@ impl Plugin for StandardWindowsPlugin {
              .add_event::<materials::MaterialEvent>()  // move to core ? 
 
               .add_systems(Update, (
-               gizmos::update_gizmo_options
+               gizmos::update_gizmo_options,
+               gizmos::record_gizmo_drags,
                 ) .chain()
             )
 
             .add_systems(Update, (
              //   doodads::update_picking_doodads ,
                 placement::update_placement_tool_inputs,
+                history::update_history_shortcuts,
                 placement::handle_placement_tool_events,
                 materials::handle_selected_material_events
                 ) .chain()
@@ -36,7 +36,7 @@ pub struct MaterialNamesManifest {
 }
 
 #[derive(Resource,Default)]
-pub struct MaterialNamesResource ( MaterialNamesManifest );
+pub struct MaterialNamesResource ( pub MaterialNamesManifest );
 
 impl Default for MaterialNamesManifest{
 
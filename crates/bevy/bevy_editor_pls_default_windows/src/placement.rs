@@ -5,6 +5,10 @@ use spirit_edit_core::zones::zone_file::CustomPropsComponent;
 use spirit_edit_core::zones::zone_file::TransformSimpleRads;
 use spirit_edit_core::placement::PlacementEvent;
 use spirit_edit_core::placement::PlacementResource;
+use spirit_edit_core::placement::PlacementToolsState;
+use spirit_edit_core::placement::duplicate::{DuplicateOperation, DuplicatePath, DuplicateToolResource, MirrorAxis};
+use spirit_edit_core::placement::surface::SurfaceAlignMode;
+use spirit_edit_core::splines::TerrainSpline;
 use std::time::Duration;
 
 use bevy::prelude::*;
@@ -20,6 +24,48 @@ pub struct PlacementWindowState {
     pub randomize_yaw: bool,
     pub random_scale_multiplier: f32,
     pub translation_grid_lock_step: Vec3,
+
+    pub duplicate: DuplicateWindowSettings,
+}
+
+pub struct DuplicateWindowSettings {
+    pub linear_count: u32,
+    pub linear_offset: Vec3,
+
+    pub radial_count: u32,
+    pub radial_axis: Vec3,
+    pub radial_angle_degrees: f32,
+    pub radial_custom_center: Option<Vec3>,
+
+    pub mirror_axis: MirrorAxis,
+    pub mirror_plane_offset: Option<f32>,
+
+    pub path_count: u32,
+    pub path_follow_rotation: bool,
+    pub path_spline: Option<Entity>,
+    pub path_polyline: Vec<Vec3>,
+}
+
+impl Default for DuplicateWindowSettings {
+    fn default() -> Self {
+        Self {
+            linear_count: 3,
+            linear_offset: Vec3::new(2.0, 0.0, 0.0),
+
+            radial_count: 5,
+            radial_axis: Vec3::Y,
+            radial_angle_degrees: 360.0,
+            radial_custom_center: None,
+
+            mirror_axis: MirrorAxis::X,
+            mirror_plane_offset: None,
+
+            path_count: 5,
+            path_follow_rotation: true,
+            path_spline: None,
+            path_polyline: Vec::new(),
+        }
+    }
 }
 
 //need to make this update PlacementToolsState !! 
@@ -31,6 +77,11 @@ impl EditorWindow for PlacementWindow {
     const NAME: &'static str = "Placement";
 
     fn ui( world: &mut World, mut cx: EditorWindowContext, ui: &mut egui::Ui) {
+        let selected_entities: Vec<Entity> = cx
+            .state::<HierarchyWindow>()
+            .map(|hierarchy_state| hierarchy_state.selected.iter().collect())
+            .unwrap_or_default();
+
         let state = cx.state_mut::<PlacementWindow>().unwrap();
 
         //  let placement_resource = world.resource::<PlacementResource>();
@@ -121,13 +172,248 @@ impl EditorWindow for PlacementWindow {
           });    
 
              ui.end_row();
+
+            {
+                let mut placement_tools_state = world.resource_mut::<PlacementToolsState>();
+
+                ui.checkbox(&mut placement_tools_state.socket_snapping, "Snap To Sockets");
+                ui.horizontal(|ui| {
+                    ui.label("Socket Snap Distance");
+                    ui.add(
+                        egui::DragValue::new(&mut placement_tools_state.socket_snap_distance)
+                            .range(0.0..=20.0)
+                            .speed(0.05),
+                    );
+                });
+                ui.checkbox(&mut placement_tools_state.show_sockets, "Show Sockets");
+            }
+
+            ui.separator();
+            surface_ui(ui, world, &selected_entities);
+
+            ui.separator();
+            duplicate_ui(ui, world, &mut state.duplicate, &selected_entities);
+            ui.separator();
+
               ui.label("Additional shortcuts");
                ui.label("Clone selected doodad: [ key: ctrl+C ]");
+               ui.label("Drop selection to ground: [ key: End ]");
                ui.label("Rotate selected doodad: [ keys: [J , K] ]");
 
         }); // ---- v
     }
 }
+
+fn vec3_drag_ui(ui: &mut egui::Ui, value: &mut Vec3, speed: f32) {
+    ui.horizontal(|ui| {
+        ui.add(egui::DragValue::new(&mut value.x).speed(speed).prefix("x: "));
+        ui.add(egui::DragValue::new(&mut value.y).speed(speed).prefix("y: "));
+        ui.add(egui::DragValue::new(&mut value.z).speed(speed).prefix("z: "));
+    });
+}
+
+fn mirror_axis_ui(ui: &mut egui::Ui, axis: &mut MirrorAxis) {
+    ui.horizontal(|ui| {
+        ui.selectable_value(axis, MirrorAxis::X, "X");
+        ui.selectable_value(axis, MirrorAxis::Y, "Y");
+        ui.selectable_value(axis, MirrorAxis::Z, "Z");
+    });
+}
+
+// the drop and nudge sweeps run in the editor app against the physics colliders
+fn surface_ui(ui: &mut egui::Ui, world: &mut World, selected_entities: &[Entity]) {
+    ui.label(RichText::new("Surface").strong());
+
+    {
+        let mut placement_tools_state = world.resource_mut::<PlacementToolsState>();
+
+        ui.horizontal(|ui| {
+            for mode in SurfaceAlignMode::ALL {
+                ui.radio_value(&mut placement_tools_state.surface_align_mode, mode, mode.label());
+            }
+        });
+
+        ui.add_enabled_ui(placement_tools_state.surface_align_mode == SurfaceAlignMode::SurfaceNormal, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Max Tilt");
+                ui.add(
+                    egui::DragValue::new(&mut placement_tools_state.max_surface_tilt_degrees)
+                        .range(0.0..=90.0)
+                        .suffix("°"),
+                );
+            });
+        });
+
+        ui.checkbox(&mut placement_tools_state.nudge_out_of_colliders, "Nudge Out Of Colliders After Drop");
+    }
+
+    ui.horizontal(|ui| {
+        let has_selection = !selected_entities.is_empty();
+
+        if ui.add_enabled(has_selection, egui::Button::new("Drop Selection To Ground")).clicked() {
+            world.write_message(PlacementEvent::DropToGround(selected_entities.to_vec()));
+        }
+        if ui.add_enabled(has_selection, egui::Button::new("Nudge Out Of Colliders")).clicked() {
+            world.write_message(PlacementEvent::NudgeOutOfColliders(selected_entities.to_vec()));
+        }
+    });
+}
+
+// copies are spawned by the core duplicate handler, which also remembers the last operation
+fn duplicate_ui(ui: &mut egui::Ui, world: &mut World, settings: &mut DuplicateWindowSettings, selected_entities: &[Entity]) {
+    ui.label(RichText::new("Duplicate Selection").strong());
+    ui.label(format!("{} selected", selected_entities.len()));
+
+    let mut operation = None;
+
+    egui::CollapsingHeader::new("Linear Array").show(ui, |ui| {
+        ui.horizontal(|ui| {
+            ui.label("Count");
+            ui.add(egui::DragValue::new(&mut settings.linear_count).range(1..=100));
+        });
+        ui.label("Offset");
+        vec3_drag_ui(ui, &mut settings.linear_offset, 0.1);
+
+        if ui.button("Linear Array").clicked() {
+            operation = Some(DuplicateOperation::LinearArray {
+                count: settings.linear_count,
+                offset: settings.linear_offset,
+            });
+        }
+    });
+
+    egui::CollapsingHeader::new("Radial Array").show(ui, |ui| {
+        ui.horizontal(|ui| {
+            ui.label("Count");
+            ui.add(egui::DragValue::new(&mut settings.radial_count).range(1..=100));
+        });
+        ui.horizontal(|ui| {
+            ui.label("Angle");
+            ui.add(egui::DragValue::new(&mut settings.radial_angle_degrees).range(-360.0..=360.0).suffix("°"));
+        });
+        ui.label("Axis");
+        vec3_drag_ui(ui, &mut settings.radial_axis, 0.01);
+
+        let mut custom_center = settings.radial_custom_center.is_some();
+        if ui.checkbox(&mut custom_center, "Custom Center").changed() {
+            settings.radial_custom_center = custom_center.then_some(Vec3::ZERO);
+        }
+        if let Some(center) = settings.radial_custom_center.as_mut() {
+            vec3_drag_ui(ui, center, 0.1);
+        }
+
+        if ui.button("Radial Array").clicked() {
+            operation = Some(DuplicateOperation::RadialArray {
+                count: settings.radial_count,
+                axis: settings.radial_axis,
+                center: settings.radial_custom_center,
+                angle_degrees: settings.radial_angle_degrees,
+            });
+        }
+    });
+
+    egui::CollapsingHeader::new("Mirror").show(ui, |ui| {
+        mirror_axis_ui(ui, &mut settings.mirror_axis);
+
+        let mut plane_offset = settings.mirror_plane_offset.is_some();
+        if ui.checkbox(&mut plane_offset, "Plane Offset (else selection center)").changed() {
+            settings.mirror_plane_offset = plane_offset.then_some(0.0);
+        }
+        if let Some(offset) = settings.mirror_plane_offset.as_mut() {
+            ui.add(egui::DragValue::new(offset).speed(0.1));
+        }
+
+        if ui.button("Mirror").clicked() {
+            operation = Some(DuplicateOperation::Mirror {
+                axis: settings.mirror_axis,
+                plane_offset: settings.mirror_plane_offset,
+            });
+        }
+    });
+
+    egui::CollapsingHeader::new("Along Path").show(ui, |ui| {
+        ui.horizontal(|ui| {
+            ui.label("Count");
+            ui.add(egui::DragValue::new(&mut settings.path_count).range(1..=200));
+        });
+        ui.checkbox(&mut settings.path_follow_rotation, "Follow Path Rotation");
+
+        let selected_spline = selected_entities
+            .iter()
+            .copied()
+            .find(|entity| world.get::<TerrainSpline>(*entity).is_some());
+
+        ui.horizontal(|ui| {
+            let spline_name = settings
+                .path_spline
+                .and_then(|entity| world.get::<Name>(entity).map(|name| name.as_str().to_owned()))
+                .unwrap_or_else(|| "None".to_owned());
+            ui.label(format!("Spline: {}", spline_name));
+
+            if ui.add_enabled(selected_spline.is_some(), egui::Button::new("Use Selected Spline")).clicked() {
+                settings.path_spline = selected_spline;
+            }
+            if ui.button("Clear").clicked() {
+                settings.path_spline = None;
+            }
+        });
+
+        if settings.path_spline.is_none() {
+            ui.label("Polyline Points");
+
+            let mut removed_point = None;
+            for (index, point) in settings.path_polyline.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    vec3_drag_ui(ui, point, 0.1);
+                    if ui.button("x").clicked() {
+                        removed_point = Some(index);
+                    }
+                });
+            }
+            if let Some(index) = removed_point {
+                settings.path_polyline.remove(index);
+            }
+
+            if ui.button("Add Point").clicked() {
+                let next_point = settings.path_polyline.last().copied().unwrap_or_default() + Vec3::X;
+                settings.path_polyline.push(next_point);
+            }
+        }
+
+        if ui.button("Along Path").clicked() {
+            let path = match settings.path_spline {
+                Some(spline_entity) => DuplicatePath::Spline(spline_entity),
+                None => DuplicatePath::Polyline(settings.path_polyline.clone()),
+            };
+
+            operation = Some(DuplicateOperation::AlongPath {
+                path,
+                count: settings.path_count,
+                follow_rotation: settings.path_follow_rotation,
+            });
+        }
+    });
+
+    let last_operation_label = world
+        .resource::<DuplicateToolResource>()
+        .last_operation
+        .as_ref()
+        .map(|operation| operation.label());
+
+    ui.horizontal(|ui| {
+        if ui
+            .add_enabled(last_operation_label.is_some(), egui::Button::new("Repeat Last"))
+            .clicked()
+        {
+            world.write_message(PlacementEvent::RepeatLastDuplicate(selected_entities.to_vec()));
+        }
+        ui.label(last_operation_label.unwrap_or("None"));
+    });
+
+    if let Some(operation) = operation {
+        world.write_message(PlacementEvent::DuplicateEntities(selected_entities.to_vec(), operation));
+    }
+}
     
 
 
@@ -169,6 +455,17 @@ pub fn update_placement_tool_inputs(
         placement_evt_writer.send(PlacementEvent::CloneSelectedDoodad);
     }
   }
+
+  if key_inputs.just_pressed(KeyCode::End) {
+      let selected_entities: Vec<Entity> = editor
+          .window_state::<HierarchyWindow>()
+          .map(|hierarchy_state| hierarchy_state.selected.iter().collect())
+          .unwrap_or_default();
+
+      if !selected_entities.is_empty() {
+          placement_evt_writer.write(PlacementEvent::DropToGround(selected_entities));
+      }
+  }
    
 
 
@@ -207,11 +504,13 @@ pub fn handle_placement_tool_events(
         match evt {
             PlacementEvent::CloneSelectedDoodad =>  {
 
-                //clone the selected doodad and select the newly cloned one 
+                //clone each selected doodad 
 
-                let first_selected_entity = selected_entities.iter().next();
+                for selected_entity in selected_entities.iter() {
 
-                if let Some((entity, name_comp, doodad_comp, doodad_parent)) = first_selected_entity.and_then(|ent|  doodad_query.get(ent).ok() ) {
+                let Ok((entity, name_comp, doodad_comp, doodad_parent)) = doodad_query.get(selected_entity) else {
+                    continue;
+                };
 
                   //  let mut translation = doodad_xform.translation ;
 
@@ -236,6 +535,8 @@ pub fn handle_placement_tool_events(
                              custom_props: duplicated_custom_props , 
                              force_parent:  doodad_parent_entity  ,
                               auto_select: false,
+                              zone_entity_id: None,
+                              record_history: true,
                            //  clay_tile_block_data: None , //for now .. 
                       });
 
@@ -250,9 +551,11 @@ pub fn handle_placement_tool_events(
 
                 //set the transform to within a near step 
 
-                  let first_selected_entity = selected_entities.iter().next();
+                for selected_entity in selected_entities.iter() {
 
-                if let Some(  mut doodad_xform ) = first_selected_entity.and_then(|ent|  local_xform_query.get_mut(ent).ok() ) {
+                let Ok(  mut doodad_xform ) = local_xform_query.get_mut(selected_entity) else {
+                    continue;
+                };
 
                     let mut translation = doodad_xform.translation ; 
 
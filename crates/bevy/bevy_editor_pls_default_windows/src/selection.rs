@@ -0,0 +1,512 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use bevy_editor_pls_core::editor_window::{EditorWindow, EditorWindowContext};
+use bevy_editor_pls_core::{Editor, EditorEvent};
+use bevy_inspector_egui::bevy_egui::EguiContexts;
+use bevy_inspector_egui::bevy_inspector::hierarchy::SelectedEntities;
+use bevy_inspector_egui::egui::{self, RichText};
+
+use spirit_edit_core::doodads::doodad::DoodadComponent;
+use spirit_edit_core::doodads::doodad_manifest::DoodadTagMapResource;
+use spirit_edit_core::doodads::picking::PreventEditorSelection;
+use spirit_edit_core::selection::{
+    lasso_contains, PivotMode, SelectionFilter, SelectionGroupsComponent, SelectionToolsState, ViewportSelectShape,
+};
+use spirit_edit_core::zones::zone_file::ZoneEntityId;
+use spirit_edit_core::zones::ZoneComponent;
+
+use crate::hierarchy::HierarchyWindow;
+
+/*
+
+Multi-selection tools: select doodads by name or tag, named selection groups stored in the zones,
+the pivot mode and 3D cursor of the transform gizmo, and box or lasso selection in the viewport
+(Alt + drag replaces the selection, Alt + Ctrl + drag adds to it).
+
+Only entities directly in a zone are picked by the box and lasso, like the entities a zone file holds.
+
+*/
+
+// drags shorter than this are clicks
+const MIN_SELECT_DRAG_PIXELS: f32 = 4.0;
+
+// the entity selected last, used as the active element
+pub fn active_selected_entity(selected: &SelectedEntities) -> Option<Entity> {
+    selected
+        .last_action()
+        .map(|(_, entity)| entity)
+        .filter(|entity| selected.contains(*entity))
+        .or_else(|| selected.iter().last())
+}
+
+#[derive(Default)]
+pub struct SelectionWindowState {
+    pub name_filter: String,
+    pub tag_filter: Option<String>,
+    pub add_to_selection: bool,
+
+    pub new_group_name: String,
+}
+
+pub struct SelectionWindow;
+
+impl EditorWindow for SelectionWindow {
+    type State = SelectionWindowState;
+    const NAME: &'static str = "Selection";
+
+    fn app_setup(app: &mut App) {
+        app.add_systems(Update, (update_viewport_selection, draw_selection_cursor));
+    }
+
+    fn ui(world: &mut World, mut cx: EditorWindowContext, ui: &mut egui::Ui) {
+        let (selected_entities, active_entity) = cx
+            .state::<HierarchyWindow>()
+            .map(|hierarchy_state| {
+                (
+                    hierarchy_state.selected.iter().collect::<Vec<_>>(),
+                    active_selected_entity(&hierarchy_state.selected),
+                )
+            })
+            .unwrap_or_default();
+
+        let state = cx.state_mut::<SelectionWindow>().unwrap();
+
+        ui.label(format!("{} selected", selected_entities.len()));
+
+        ui.separator();
+        pivot_ui(ui, world, &selected_entities, active_entity);
+
+        ui.separator();
+        ui.label(RichText::new("Viewport Selection").strong());
+        {
+            let mut selection_tools_state = world.resource_mut::<SelectionToolsState>();
+
+            ui.horizontal(|ui| {
+                ui.radio_value(&mut selection_tools_state.viewport_select_shape, ViewportSelectShape::Box, "Box");
+                ui.radio_value(&mut selection_tools_state.viewport_select_shape, ViewportSelectShape::Lasso, "Lasso");
+            });
+        }
+        ui.label("Alt + drag selects, Alt + Ctrl + drag adds to the selection");
+
+        ui.separator();
+        select_by_ui(ui, world, state, &selected_entities);
+
+        ui.separator();
+        selection_groups_ui(ui, world, state, &selected_entities);
+    }
+}
+
+fn pivot_ui(ui: &mut egui::Ui, world: &mut World, selected_entities: &[Entity], active_entity: Option<Entity>) {
+    ui.label(RichText::new("Pivot").strong());
+
+    let selected_positions: Vec<Vec3> = selected_entities
+        .iter()
+        .filter_map(|entity| world.get::<GlobalTransform>(*entity))
+        .map(|xform| xform.translation())
+        .collect();
+
+    let active_position = active_entity
+        .and_then(|entity| world.get::<GlobalTransform>(entity))
+        .map(|xform| xform.translation());
+
+    let mut selection_tools_state = world.resource_mut::<SelectionToolsState>();
+
+    for pivot_mode in PivotMode::ALL {
+        ui.radio_value(&mut selection_tools_state.pivot_mode, pivot_mode, pivot_mode.label());
+    }
+
+    ui.label("3D Cursor");
+    ui.horizontal(|ui| {
+        let cursor = &mut selection_tools_state.cursor_3d;
+        ui.add(egui::DragValue::new(&mut cursor.x).speed(0.1).prefix("x: "));
+        ui.add(egui::DragValue::new(&mut cursor.y).speed(0.1).prefix("y: "));
+        ui.add(egui::DragValue::new(&mut cursor.z).speed(0.1).prefix("z: "));
+    });
+
+    ui.horizontal(|ui| {
+        if ui
+            .add_enabled(!selected_positions.is_empty(), egui::Button::new("Cursor To Selection"))
+            .clicked()
+        {
+            selection_tools_state.cursor_3d =
+                selected_positions.iter().sum::<Vec3>() / selected_positions.len() as f32;
+        }
+
+        if ui
+            .add_enabled(active_position.is_some(), egui::Button::new("Cursor To Active"))
+            .clicked()
+        {
+            selection_tools_state.cursor_3d = active_position.unwrap_or_default();
+        }
+    });
+}
+
+fn select_entities(world: &mut World, mut entities: Vec<Entity>, selected_entities: &[Entity], add: bool) {
+    if add {
+        entities.extend(selected_entities.iter().copied());
+    }
+
+    // selecting an entity twice would toggle it off again
+    let mut seen = BTreeSet::new();
+    entities.retain(|entity| seen.insert(*entity));
+
+    world.write_message(EditorEvent::SetSelectedEntities(Some(entities)));
+}
+
+fn select_by_ui(ui: &mut egui::Ui, world: &mut World, state: &mut SelectionWindowState, selected_entities: &[Entity]) {
+    ui.label(RichText::new("Select Doodads").strong());
+    ui.checkbox(&mut state.add_to_selection, "Add To Selection");
+
+    let mut filter = None;
+
+    ui.horizontal(|ui| {
+        ui.text_edit_singleline(&mut state.name_filter);
+        if ui
+            .add_enabled(!state.name_filter.is_empty(), egui::Button::new("Select By Name"))
+            .clicked()
+        {
+            filter = Some(SelectionFilter::Name(state.name_filter.clone()));
+        }
+    });
+
+    let tags: Vec<String> = world
+        .resource::<DoodadTagMapResource>()
+        .doodad_tag_map
+        .keys()
+        .cloned()
+        .collect();
+
+    ui.horizontal(|ui| {
+        egui::ComboBox::from_id_salt("selection_tag_filter")
+            .selected_text(state.tag_filter.as_deref().unwrap_or("None"))
+            .show_ui(ui, |ui| {
+                for tag in tags.iter() {
+                    ui.selectable_value(&mut state.tag_filter, Some(tag.clone()), tag);
+                }
+            });
+
+        if ui
+            .add_enabled(state.tag_filter.is_some(), egui::Button::new("Select By Tag"))
+            .clicked()
+        {
+            filter = state.tag_filter.clone().map(SelectionFilter::Tag);
+        }
+    });
+
+    let Some(filter) = filter else {
+        return;
+    };
+
+    let mut doodad_query = world.query_filtered::<(Entity, &Name), With<DoodadComponent>>();
+
+    let doodad_tag_map = world.resource::<DoodadTagMapResource>();
+    let matching: Vec<Entity> = doodad_query
+        .iter(world)
+        .filter(|(_, name)| filter.matches(name.as_str(), doodad_tag_map))
+        .map(|(entity, _)| entity)
+        .collect();
+
+    select_entities(world, matching, selected_entities, state.add_to_selection);
+}
+
+fn selection_groups_ui(ui: &mut egui::Ui, world: &mut World, state: &mut SelectionWindowState, selected_entities: &[Entity]) {
+    ui.label(RichText::new("Selection Groups").strong());
+
+    let mut zone_query = world.query_filtered::<(Entity, Option<&SelectionGroupsComponent>), With<ZoneComponent>>();
+
+    // group name -> member count over all loaded zones
+    let mut groups: BTreeMap<String, usize> = BTreeMap::new();
+    for (_, selection_groups) in zone_query.iter(world) {
+        for group in selection_groups.iter().flat_map(|selection_groups| selection_groups.groups.iter()) {
+            *groups.entry(group.name.clone()).or_default() += group.entity_ids.len();
+        }
+    }
+
+    let mut group_to_select = None;
+    let mut group_to_delete = None;
+
+    for (group_name, member_count) in groups.iter() {
+        ui.horizontal(|ui| {
+            ui.label(format!("{} ({})", group_name, member_count));
+
+            if ui.button("Select").clicked() {
+                group_to_select = Some(group_name.clone());
+            }
+            if ui.button("Delete").clicked() {
+                group_to_delete = Some(group_name.clone());
+            }
+        });
+    }
+
+    let mut group_to_save = None;
+
+    ui.horizontal(|ui| {
+        ui.text_edit_singleline(&mut state.new_group_name);
+
+        let can_save = !state.new_group_name.is_empty() && !selected_entities.is_empty();
+        if ui
+            .add_enabled(can_save, egui::Button::new("Save Selection As Group"))
+            .clicked()
+        {
+            group_to_save = Some(state.new_group_name.clone());
+        }
+    });
+
+    if let Some(group_name) = group_to_select {
+        let members = selection_group_members(world, &group_name);
+        select_entities(world, members, selected_entities, state.add_to_selection);
+    }
+
+    if let Some(group_name) = group_to_delete {
+        let zones: Vec<Entity> = zone_query.iter(world).map(|(zone, _)| zone).collect();
+
+        for zone in zones {
+            if let Some(mut selection_groups) = world.get_mut::<SelectionGroupsComponent>(zone) {
+                if selection_groups.get(&group_name).is_some() {
+                    selection_groups.remove_group(&group_name);
+                }
+            }
+        }
+    }
+
+    if let Some(group_name) = group_to_save {
+        save_selection_group(world, &group_name, selected_entities);
+    }
+}
+
+// entities of the named group in every loaded zone
+fn selection_group_members(world: &mut World, group_name: &str) -> Vec<Entity> {
+    let mut zone_query = world.query_filtered::<(Entity, &SelectionGroupsComponent), With<ZoneComponent>>();
+
+    let zone_groups: Vec<(Entity, Vec<ZoneEntityId>)> = zone_query
+        .iter(world)
+        .filter_map(|(zone, selection_groups)| {
+            selection_groups
+                .get(group_name)
+                .map(|group| (zone, group.entity_ids.clone()))
+        })
+        .collect();
+
+    let mut members = Vec::new();
+
+    for (zone, entity_ids) in zone_groups {
+        let Some(children) = world.get::<Children>(zone) else {
+            continue;
+        };
+
+        members.extend(children.iter().filter(|child| {
+            world
+                .get::<ZoneEntityId>(*child)
+                .is_some_and(|zone_entity_id| entity_ids.contains(zone_entity_id))
+        }));
+    }
+
+    members
+}
+
+// a group saved again replaces the old one, also in zones the new selection does not touch
+fn save_selection_group(world: &mut World, group_name: &str, selected_entities: &[Entity]) {
+    let mut zone_members: BTreeMap<Entity, Vec<ZoneEntityId>> = BTreeMap::new();
+    let mut skipped = 0;
+
+    for entity in selected_entities.iter().copied() {
+        let zone = world
+            .get::<ChildOf>(entity)
+            .map(|child_of| child_of.parent())
+            .filter(|parent| world.get::<ZoneComponent>(*parent).is_some());
+
+        match (zone, world.get::<ZoneEntityId>(entity)) {
+            (Some(zone), Some(zone_entity_id)) => zone_members.entry(zone).or_default().push(*zone_entity_id),
+            _ => skipped += 1,
+        }
+    }
+
+    if skipped > 0 {
+        warn!("{} selected entities are not directly in a zone and were left out of group {}", skipped, group_name);
+    }
+
+    let mut zone_query = world.query_filtered::<Entity, With<ZoneComponent>>();
+    let zones: Vec<Entity> = zone_query.iter(world).collect();
+
+    for zone in zones {
+        let entity_ids = zone_members.remove(&zone).unwrap_or_default();
+
+        if world.get::<SelectionGroupsComponent>(zone).is_none() {
+            if entity_ids.is_empty() {
+                continue;
+            }
+            world.entity_mut(zone).insert(SelectionGroupsComponent::default());
+        }
+
+        if let Some(mut selection_groups) = world.get_mut::<SelectionGroupsComponent>(zone) {
+            if entity_ids.is_empty() && selection_groups.get(group_name).is_none() {
+                continue;
+            }
+            selection_groups.set_group(group_name, entity_ids);
+        }
+    }
+}
+
+#[derive(Default)]
+struct ViewportSelectDrag {
+    points: Vec<Vec2>, // box corners, or the lasso outline
+    add: bool,
+}
+
+fn update_viewport_selection(
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    key_input: Res<ButtonInput<KeyCode>>,
+
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<Camera3d>>,
+
+    selectable_query: Query<(Entity, &GlobalTransform, &ChildOf), (With<ZoneEntityId>, Without<PreventEditorSelection>)>,
+    zone_query: Query<(), With<ZoneComponent>>,
+
+    selection_tools_state: Res<SelectionToolsState>,
+    editor: Res<Editor>,
+    mut editor_events: MessageWriter<EditorEvent>,
+
+    mut contexts: EguiContexts,
+
+    mut drag: Local<Option<ViewportSelectDrag>>,
+) {
+    let Some(cursor_position) = window_query.single().ok().and_then(|window| window.cursor_position()) else {
+        return;
+    };
+
+    let Ok(egui_ctx) = contexts.ctx_mut() else {
+        return;
+    };
+
+    if mouse_input.just_pressed(MouseButton::Left) && key_input.pressed(KeyCode::AltLeft) {
+        let pointer_over_ui = match editor.active() {
+            true => editor.pointer_used(),
+            false => egui_ctx.is_pointer_over_area(),
+        };
+
+        if !pointer_over_ui {
+            *drag = Some(ViewportSelectDrag {
+                points: vec![cursor_position],
+                add: key_input.pressed(KeyCode::ControlLeft),
+            });
+        }
+    }
+
+    let Some(current_drag) = drag.as_mut() else {
+        return;
+    };
+
+    let shape = selection_tools_state.viewport_select_shape;
+
+    match shape {
+        ViewportSelectShape::Box => {
+            current_drag.points.truncate(1);
+            current_drag.points.push(cursor_position);
+        }
+        ViewportSelectShape::Lasso => {
+            let moved = current_drag
+                .points
+                .last()
+                .is_none_or(|last| last.distance(cursor_position) >= 2.0);
+            if moved {
+                current_drag.points.push(cursor_position);
+            }
+        }
+    }
+
+    if mouse_input.pressed(MouseButton::Left) {
+        draw_viewport_select_shape(egui_ctx, shape, &current_drag.points);
+        return;
+    }
+
+    let Some(finished_drag) = drag.take() else {
+        return;
+    };
+
+    let start = finished_drag.points[0];
+    if start.distance(cursor_position) < MIN_SELECT_DRAG_PIXELS && shape == ViewportSelectShape::Box {
+        return;
+    }
+
+    let Some((camera, camera_xform)) = camera_query
+        .iter()
+        .filter(|(camera, _)| camera.is_active)
+        .max_by_key(|(camera, _)| camera.order)
+    else {
+        return;
+    };
+
+    let viewport_offset = camera.logical_viewport_rect().map(|rect| rect.min).unwrap_or_default();
+
+    let select_rect = Rect::from_corners(start, cursor_position);
+
+    let mut entities: Vec<Entity> = selectable_query
+        .iter()
+        .filter(|(_, _, child_of)| zone_query.contains(child_of.parent()))
+        .filter(|(_, xform, _)| {
+            let Ok(viewport_position) = camera.world_to_viewport(camera_xform, xform.translation()) else {
+                return false;
+            };
+            let screen_position = viewport_position + viewport_offset;
+
+            match shape {
+                ViewportSelectShape::Box => select_rect.contains(screen_position),
+                ViewportSelectShape::Lasso => lasso_contains(&finished_drag.points, screen_position),
+            }
+        })
+        .map(|(entity, _, _)| entity)
+        .collect();
+
+    if finished_drag.add {
+        if let Some(hierarchy_state) = editor.window_state::<HierarchyWindow>() {
+            entities.extend(hierarchy_state.selected.iter());
+        }
+    }
+
+    // selecting an entity twice would toggle it off again
+    let mut seen = BTreeSet::new();
+    entities.retain(|entity| seen.insert(*entity));
+
+    editor_events.write(EditorEvent::SetSelectedEntities(Some(entities)));
+}
+
+fn draw_viewport_select_shape(egui_ctx: &egui::Context, shape: ViewportSelectShape, points: &[Vec2]) {
+    let painter = egui_ctx.layer_painter(egui::LayerId::new(egui::Order::Foreground, egui::Id::new("viewport_select")));
+    let stroke = egui::Stroke::new(1.5, egui::Color32::from_rgb(255, 200, 60));
+
+    let to_pos = |point: &Vec2| egui::pos2(point.x, point.y);
+
+    match shape {
+        ViewportSelectShape::Box => {
+            if let (Some(start), Some(end)) = (points.first(), points.last()) {
+                painter.rect_stroke(
+                    egui::Rect::from_two_pos(to_pos(start), to_pos(end)),
+                    0.0,
+                    stroke,
+                    egui::StrokeKind::Middle,
+                );
+            }
+        }
+        ViewportSelectShape::Lasso => {
+            painter.add(egui::Shape::closed_line(points.iter().map(to_pos).collect(), stroke));
+        }
+    }
+}
+
+fn draw_selection_cursor(mut gizmos: Gizmos, selection_tools_state: Res<SelectionToolsState>) {
+    if selection_tools_state.pivot_mode != PivotMode::Cursor3d {
+        return;
+    }
+
+    let cursor = selection_tools_state.cursor_3d;
+    let color = Color::srgb(1.0, 0.3, 0.3);
+
+    gizmos.sphere(Isometry3d::from_translation(cursor), 0.25, color);
+    for axis in [Vec3::X, Vec3::Y, Vec3::Z] {
+        gizmos.line(cursor - axis * 0.5, cursor + axis * 0.5, color);
+    }
+}
@@ -0,0 +1,131 @@
+use bevy::prelude::*;
+
+use bevy_editor_pls_core::EditorEvent;
+use bevy_editor_pls_core::editor_window::{EditorWindow, EditorWindowContext};
+use bevy_inspector_egui::egui::{self, RichText, ScrollArea};
+
+use spirit_edit_core::zones::zone_streaming::{
+    StreamedZoneState, ZoneStreamingEvent, ZoneStreamingResource, ZoneUnsavedEdits, ZoneViewer,
+};
+
+/*
+
+Shows the zones that are being streamed, their state and how far the nearest viewer is.
+Keep loaded pins a zone so it can be edited from anywhere.
+
+*/
+
+pub struct ZoneStreamingWindow;
+
+impl EditorWindow for ZoneStreamingWindow {
+    type State = ();
+    const NAME: &'static str = "Zone Streaming";
+
+    // zones edited in the editor stay loaded until they are saved
+    fn app_setup(app: &mut App) {
+        app.init_resource::<ZoneStreamingResource>();
+
+        app.world_mut().resource_mut::<ZoneStreamingResource>().track_edits = true;
+    }
+
+    fn ui(world: &mut World, _cx: EditorWindowContext, ui: &mut egui::Ui) {
+        let viewer_locations: Vec<Vec2> = world
+            .query_filtered::<&GlobalTransform, With<ZoneViewer>>()
+            .iter(world)
+            .map(|xform| xform.translation().xz())
+            .collect();
+
+        let mut unsaved_query = world.query_filtered::<(), With<ZoneUnsavedEdits>>();
+        let unsaved_zones: Vec<Entity> = world
+            .resource::<ZoneStreamingResource>()
+            .zones
+            .values()
+            .filter_map(|streamed_zone| streamed_zone.zone_entity)
+            .filter(|zone_entity| unsaved_query.get(world, *zone_entity).is_ok())
+            .collect();
+
+        let mut stop_streaming = false;
+        let mut entity_to_select = None;
+
+        {
+            let mut zone_streaming = world.resource_mut::<ZoneStreamingResource>();
+            let zone_streaming = zone_streaming.as_mut();
+
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut zone_streaming.show_bounds, "Show bounds");
+
+                if ui.button("Stop Streaming").clicked() {
+                    stop_streaming = true;
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Load distance");
+                ui.add(egui::DragValue::new(&mut zone_streaming.config.load_distance).range(0.0..=f32::MAX));
+
+                ui.label("Unload distance");
+                ui.add(
+                    egui::DragValue::new(&mut zone_streaming.config.unload_distance)
+                        .range(zone_streaming.config.load_distance..=f32::MAX),
+                );
+
+                ui.label("Entities per frame");
+                ui.add(egui::DragValue::new(&mut zone_streaming.config.entities_per_frame).range(1..=10000));
+            });
+
+            ui.separator();
+
+            if zone_streaming.zones.is_empty() {
+                ui.label("No zones are streamed, set zone_streaming in the level config.");
+            }
+
+            ScrollArea::vertical().show(ui, |ui| {
+                egui::Grid::new("zone_streaming_zones")
+                    .num_columns(4)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for (zone_name, streamed_zone) in zone_streaming.zones.iter_mut() {
+                            let has_unsaved_edits = streamed_zone
+                                .zone_entity
+                                .is_some_and(|zone_entity| unsaved_zones.contains(&zone_entity));
+
+                            let name_label = ui.add(
+                                egui::Label::new(zone_name.as_str()).sense(egui::Sense::click()),
+                            );
+
+                            if name_label.clicked() {
+                                entity_to_select = streamed_zone.zone_entity;
+                            }
+
+                            let (state_text, color) = match streamed_zone.state {
+                                _ if has_unsaved_edits => ("unsaved", egui::Color32::ORANGE),
+                                StreamedZoneState::Loaded => ("loaded", egui::Color32::GREEN),
+                                StreamedZoneState::Loading => ("loading", egui::Color32::YELLOW),
+                                StreamedZoneState::Unloaded => ("unloaded", egui::Color32::GRAY),
+                                StreamedZoneState::Failed => ("failed", egui::Color32::RED),
+                            };
+
+                            ui.label(RichText::new(state_text).color(color));
+
+                            match streamed_zone.distance_to(&viewer_locations) {
+                                Some(distance) => ui.label(format!("{:.0}m", distance)),
+                                None => ui.label("-"),
+                            };
+
+                            ui.checkbox(&mut streamed_zone.keep_loaded, "Keep loaded");
+
+                            ui.end_row();
+                        }
+                    });
+            });
+        }
+
+        if stop_streaming {
+            world.write_message(ZoneStreamingEvent::StopStreaming);
+        }
+
+        if let Some(entity) = entity_to_select {
+            world.write_message(EditorEvent::SetSelectedEntities(Some(vec![entity])));
+        }
+    }
+}
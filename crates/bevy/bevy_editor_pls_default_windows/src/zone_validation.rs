@@ -0,0 +1,153 @@
+use bevy::platform::collections::hash_map::HashMap;
+use bevy::prelude::*;
+
+use bevy_editor_pls_core::EditorEvent;
+use bevy_editor_pls_core::editor_window::{EditorWindow, EditorWindowContext};
+use bevy_inspector_egui::egui::{self, RichText, ScrollArea};
+
+use spirit_edit_core::zones::zone_file::{ZoneEntityId, ZoneEntityV2};
+use spirit_edit_core::zones::zone_validation::{
+    validate_zone_entities, ZoneValidationContext, ZoneValidationIssue, ZoneValidationSeverity,
+};
+use spirit_edit_core::zones::ZoneComponent;
+
+use crate::materials::MaterialNamesResource;
+
+/*
+
+Runs the zone validator over the zones that are loaded in the editor.  Clicking an issue
+selects the entity it is about.
+
+*/
+
+pub struct ZoneValidationResult {
+    pub zone_name: String,
+    pub issues: Vec<(ZoneValidationIssue, Option<Entity>)>,
+}
+
+#[derive(Default)]
+pub struct ZoneValidationWindowState {
+    pub results: Option<Vec<ZoneValidationResult>>,
+    pub show_warnings: bool,
+}
+
+pub struct ZoneValidationWindow;
+
+impl EditorWindow for ZoneValidationWindow {
+    type State = ZoneValidationWindowState;
+    const NAME: &'static str = "Zone Validation";
+
+    fn ui(world: &mut World, mut cx: EditorWindowContext, ui: &mut egui::Ui) {
+        let state = cx.state_mut::<ZoneValidationWindow>().unwrap();
+
+        ui.horizontal(|ui| {
+            if ui.button("Validate Loaded Zones").clicked() {
+                state.results = Some(validate_loaded_zones(world));
+            }
+
+            ui.checkbox(&mut state.show_warnings, "Show warnings");
+        });
+
+        let Some(results) = &state.results else {
+            return;
+        };
+
+        let mut entity_to_select = None;
+
+        ScrollArea::vertical().show(ui, |ui| {
+            for result in results.iter() {
+                let error_count = result
+                    .issues
+                    .iter()
+                    .filter(|(issue, _)| issue.severity() == ZoneValidationSeverity::Error)
+                    .count();
+                let warning_count = result.issues.len() - error_count;
+
+                ui.label(RichText::new(format!(
+                    "{}: {} errors, {} warnings",
+                    result.zone_name, error_count, warning_count
+                )).strong());
+
+                for (issue, entity) in result.issues.iter() {
+                    let color = match issue.severity() {
+                        ZoneValidationSeverity::Error => egui::Color32::RED,
+                        ZoneValidationSeverity::Warning if state.show_warnings => egui::Color32::YELLOW,
+                        ZoneValidationSeverity::Warning => continue,
+                    };
+
+                    let label = ui.add(
+                        egui::Label::new(RichText::new(issue.to_string()).color(color))
+                            .sense(egui::Sense::click()),
+                    );
+
+                    if label.clicked() {
+                        entity_to_select = *entity;
+                    }
+                }
+
+                ui.separator();
+            }
+
+            if results.is_empty() {
+                ui.label("No zones are loaded.");
+            }
+        });
+
+        if let Some(entity) = entity_to_select {
+            world.write_message(EditorEvent::SetSelectedEntities(Some(vec![entity])));
+        }
+    }
+}
+
+fn validate_loaded_zones(world: &mut World) -> Vec<ZoneValidationResult> {
+    let mut context = ZoneValidationContext::from_world(world);
+
+    context.material_names = world
+        .get_resource::<MaterialNamesResource>()
+        .map(|r| r.0.override_materials.iter().cloned().collect());
+
+    let mut zone_query = world.query_filtered::<
+        (Entity, Option<&Name>, Option<&GlobalTransform>, Option<&Children>),
+        With<ZoneComponent>,
+    >();
+
+    let zones: Vec<(String, Vec3, Vec<Entity>)> = zone_query
+        .iter(world)
+        .map(|(entity, name, zone_transform, children)| {
+            (
+                name.map(|n| n.to_string()).unwrap_or_else(|| format!("{}", entity)),
+                zone_transform.map(|t| t.translation()).unwrap_or_default(),
+                children.map(|c| c.to_vec()).unwrap_or_default(),
+            )
+        })
+        .collect();
+
+    let mut results = Vec::new();
+
+    // zone children are saved with transforms relative to the zone
+    for (zone_name, zone_translation, zone_children) in zones {
+        let mut zone_entities: Vec<ZoneEntityV2> = Vec::new();
+        let mut entities_by_id: HashMap<ZoneEntityId, Entity> = HashMap::new();
+
+        for child in zone_children {
+            let Ok(child_entity_ref) = world.get_entity(child) else { continue };
+
+            if let Some(zone_entity) = ZoneEntityV2::from_entity_ref(&child_entity_ref) {
+                entities_by_id.insert(zone_entity.get_id(), child);
+                zone_entities.push(zone_entity);
+            }
+        }
+
+        let issues = validate_zone_entities(&zone_entities, zone_translation, &context, true)
+            .into_iter()
+            .map(|issue| {
+                let entity = entities_by_id.get(&issue.entity_id).copied();
+                (issue, entity)
+            })
+            .collect();
+
+        results.push(ZoneValidationResult { zone_name, issues });
+    }
+
+    results
+}
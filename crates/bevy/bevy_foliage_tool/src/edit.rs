@@ -12,17 +12,18 @@ use crate::foliage_density::FoliageDensityMapsComponent;
 use bevy::ecs::entity::Entity;
 use bevy::math::Vec2;
 
-use bevy::ecs::event::Event;
 
 // use bevy::prelude::In;
 // use bevy::asset::{AssetServer, Assets};
 // use bevy::render::render_resource::{Extent3d, TextureFormat};
 
 use bevy::prelude::*;
-use bevy::ecs::event::{EventReader, EventWriter};
+use bevy::ecs::message::{MessageReader, MessageWriter};
 
 use core::fmt::{self, Display, Formatter};
 
+use bevy_brush_shapes::BrushShape;
+
 //use crate::foliage::{FoliageDataEvent,    FoliageData    };
 
 
@@ -40,6 +41,10 @@ use core::fmt::{self, Display, Formatter};
 // use core::cmp::{max, min};
 
 pub(crate) fn bevy_foliage_edits_plugin(app: &mut App) {
+    app.add_message::<EditFoliageEvent>();
+    app.add_message::<FoliageCommandEvent>();
+    app.add_message::<FoliageBrushEvent>();
+
     app.add_systems(Update, apply_tool_edits);
     app.add_systems(Update, apply_command_events);
 }
@@ -73,7 +78,7 @@ impl Display for BrushType {
 }
 
 // entity, editToolType, coords, magnitude
-#[derive(Event, Debug, Clone)]
+#[derive(Message, Debug, Clone)]
 pub struct EditFoliageEvent {
     pub entity: Entity, //not used
     pub tool: EditingTool,
@@ -81,15 +86,16 @@ pub struct EditFoliageEvent {
     pub brush_hardness: f32, //1.0 is full
     pub coordinates: Vec2,
     pub brush_type: BrushType,
+    pub brush_shape: BrushShape, // circle or image alpha
 }
 
-#[derive(Event, Debug, Clone)]
+#[derive(Message, Debug, Clone)]
 pub enum FoliageBrushEvent {
     EyeDropFoliageDensity { density: u8 },
     //  EyeDropSplatMap { r: u8, g: u8, b: u8 },
 }
 
-#[derive(Event, Debug, Clone)]
+#[derive(Message, Debug, Clone)]
 pub enum FoliageCommandEvent {
     SaveAll,
 }
@@ -102,7 +108,7 @@ pub fn apply_command_events(
  
     //foliage_config_resource: Res<FoliageConfigResource>,
 
-    mut ev_reader: EventReader<FoliageCommandEvent>,
+    mut ev_reader: MessageReader<FoliageCommandEvent>,
 ) {
     for ev in ev_reader.read() {
         /*let Some((foliage_data, foliage_config)) = foliage_data_query
@@ -116,7 +122,7 @@ pub fn apply_command_events(
 
 
 
-            for (foliage_root, foliage_scene_data, foliage_density_map  )   in foliage_root_query .iter(){
+            for (_foliage_root, foliage_scene_data, foliage_density_map  )   in foliage_root_query .iter(){
                // let foliage_config = &foliage_config_resource.0;
 
 
@@ -162,21 +168,22 @@ pub fn apply_tool_edits(
 
      mut foliage_root_query: Query< (  &FoliageRoot,&FoliageScene,  &mut FoliageDensityMapsComponent ) >,
 
-    mut ev_reader: EventReader<EditFoliageEvent>,
-    mut evt_writer: EventWriter<FoliageBrushEvent>,
+    mut ev_reader: MessageReader<EditFoliageEvent>,
+    mut evt_writer: MessageWriter<FoliageBrushEvent>,
 ) {
     for ev in ev_reader.read() {
         let tool_coords = ev.coordinates;
         let radius = ev.radius;
         let brush_hardness = ev.brush_hardness;
         let brush_type = &ev.brush_type;
+        let brush_shape = &ev.brush_shape;
 
        
         let tool_coords_local = tool_coords;
 
 
 
-        let Ok( (foliage_root, foliage_scene, mut foliage_density_map ) ) = foliage_root_query.single_mut() else {
+        let Ok( (_foliage_root, foliage_scene, mut foliage_density_map ) ) = foliage_root_query.single_mut() else {
             warn!("no single foliage root found ");
             continue ; 
         };
@@ -197,7 +204,7 @@ pub fn apply_tool_edits(
            
 
 
-                    let Some( mut existing_foliage_layer_density_data ) = foliage_density_map.0.get_mut( &(foliage_index as usize) ) else {
+                    let Some( existing_foliage_layer_density_data ) = foliage_density_map.0.get_mut( &(*foliage_index as usize) ) else {
                         warn!( "no foliage layer data to mutate ! " );
                         continue; 
                     };
@@ -211,18 +218,18 @@ pub fn apply_tool_edits(
                                 for x in 0..foliage_dimensions.x as usize {
                                     for y in 0..foliage_dimensions.y as usize {
                                         let local_coords = Vec2::new(x as f32, y as f32);
-                                        let distance = tool_coords_local.distance(local_coords);
+                                        let brush_offset = local_coords - tool_coords_local;
 
-                                        if distance < radius {
-                                            let hardness_multiplier = get_hardness_multiplier(
-                                                distance,
+                                        if brush_shape.contains(brush_offset, radius) {
+                                            let hardness_multiplier = brush_shape.weight(
+                                                brush_offset,
                                                 radius,
                                                 brush_hardness,
                                             );
                                             let original_density = density_data[y][x];
                                             density_data[y][x] = apply_hardness_multiplier(
                                                 original_density as f32,
-                                                new_density as f32,
+                                                *new_density as f32,
                                                 hardness_multiplier,
                                             )
                                                 as u8;
@@ -238,7 +245,7 @@ pub fn apply_tool_edits(
                                     && y < foliage_dimensions.y as usize
                                 {
                                     let local_data = density_data[y][x];
-                                    evt_writer.send(FoliageBrushEvent::EyeDropFoliageDensity {
+                                    evt_writer.write(FoliageBrushEvent::EyeDropFoliageDensity {
                                         density: local_data,
                                     });
                                 }
@@ -257,23 +264,6 @@ pub fn apply_tool_edits(
     }
 }
 
-fn get_hardness_multiplier(pixel_distance: f32, brush_radius: f32, brush_hardness: f32) -> f32 {
-    // Calculate the distance as a percentage of the radius
-    let distance_percent = pixel_distance / brush_radius;
-    let adjusted_distance_percent = f32::min(1.0, distance_percent); // Ensure it does not exceed 1
-
-    // Calculate the fade effect based on brush hardness
-    // When hardness is 0, this will linearly interpolate from 1 at the center to 0 at the edge
-    // When hardness is between 0 and 1, it adjusts the fade effect accordingly
-    let fade_effect = 1.0 - adjusted_distance_percent;
-
-    // Apply the brush hardness to scale the fade effect, ensuring a minimum of 0
-    f32::max(
-        0.0,
-        fade_effect * (1.0 + brush_hardness) - (adjusted_distance_percent * brush_hardness),
-    )
-}
-
 fn apply_hardness_multiplier(
     original_height: f32,
     new_height: f32,
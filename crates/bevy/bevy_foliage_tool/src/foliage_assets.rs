@@ -1,5 +1,4 @@
 use crate::foliage_material::FoliageMaterialExtension;
-use std::default;
 
 use bevy::prelude::*;
 use bevy::platform::collections::hash_map::HashMap;
@@ -4,14 +4,9 @@ use crate::foliage_density::FoliageDensityMapsComponent;
 use crate::foliage_chunk_layer::FoliageChunkLayer;
  
 
-use rand::Rng;
 use bevy::platform::collections::hash_map::HashMap ;
 
-use crate::foliage_proto;
-use crate::foliage_proto::FoliageProto;
-use crate::foliage_proto::FoliageProtoBundle;
 use crate::foliage_viewer::FoliageViewer;
-use crate::noise::NoiseResource;
  
 use bevy::prelude::*;
 
@@ -136,7 +131,7 @@ fn update_chunk_active(
     };
 
 
-    let Ok( (foliage_root, foliage_scene,   foliage_density_map ) ) = foliage_root_query.single () else {
+    let Ok( (_foliage_root, foliage_scene,   _foliage_density_map ) ) = foliage_root_query.single () else {
             warn!("no single foliage root found ");
             return  ; 
         };
@@ -250,7 +245,7 @@ fn handle_chunk_changed(
 
     for (chunk_entity,  _foliage_chunk  ) in chunk_query.iter(){
 
-         if let Some(mut cmd) = commands.get_entity( chunk_entity ).ok() {
+         if let Ok(mut cmd) = commands.get_entity( chunk_entity ) {
             // Only insert the component if the entity exists and is valid
 
             cmd.try_insert(ForceRebuildFoliageChunk);
@@ -305,7 +300,7 @@ fn handle_chunk_rebuilds(
 
 
 
-    let Ok( ( foliage_scene_root_entity, foliage_root, foliage_scene,   foliage_density_map ) ) = foliage_root_query.single () else {
+    let Ok( ( _foliage_scene_root_entity, _foliage_root, _foliage_scene,   foliage_density_map ) ) = foliage_root_query.single () else {
             warn!("no single foliage root found ");
             return  ; 
         };
@@ -315,9 +310,9 @@ fn handle_chunk_rebuilds(
 
 
 
-              for (chunk_entity, foliage_chunk, heightmap, dimensions, chunk_active) in chunk_query.iter() {
+              for (chunk_entity, _foliage_chunk, _heightmap, _dimensions, chunk_active) in chunk_query.iter() {
                     // Check if entity still exists and is valid before proceeding
-                    if let Some(mut cmd) = commands.get_entity(chunk_entity) .ok() {
+                    if let Ok(mut cmd) = commands.get_entity(chunk_entity) {
                         // Verify entity still exists in world
                         if cmd.id() == chunk_entity {
                             info!("Rebuilding foliage chunk {:?}", chunk_entity);
@@ -339,7 +334,7 @@ fn handle_chunk_rebuilds(
                     }
 
 
-                 for (layer_index,foliage_layer_density_map) in   foliage_density_map.0.iter() {
+                 for (layer_index,_foliage_layer_density_map) in   foliage_density_map.0.iter() {
 
 
                         commands.spawn(
@@ -4,19 +4,16 @@ use crate::foliage_scene::FoliageScene;
 use crate::foliage_density::FoliageDensityMapsComponent;
 use crate::foliage_proto::FoliageProtoBundle;
 use crate::foliage_types::FoliageTypesManifest;
-use rand::Rng;
 use crate::foliage_chunk::FoliageHeightMapData;
 use crate::foliage_chunk::FoliageDimensionsData;
 use crate::foliage_density::FoliageDensityMapU8;
  
 use crate::noise::NoiseResource;
  
-use crate::foliage_chunk::{FoliageChunk, FoliageChunkNeedsRebuild};
+use crate::foliage_chunk::FoliageChunk;
  
 use bevy::prelude::*;
 
-use bevy::platform::collections::hash_map::HashMap;
-use serde::{Deserialize, Serialize};
 
 pub(crate) fn foliage_chunk_layer_plugin(app: &mut App) {
     app 
@@ -44,7 +41,7 @@ pub struct FoliageChunkLayer {
 
 
 
-/// only rebuild if Visible?   could be an optimization...   but also need to rebuild when vis changes then 
+// only rebuild if Visible?   could be an optimization...   but also need to rebuild when vis changes then 
 
 fn build_chunk_layers (
 
@@ -76,7 +73,7 @@ fn build_chunk_layers (
 
     for  (chunk_layer_entity, chunk_layer, chunk_layer_parent )  in chunk_layer_query.iter(){
 
-        let Ok( (foliage_root, foliage_scene,   foliage_density_map, foliage_types  ) ) = foliage_root_query.single() else {
+        let Ok( (_foliage_root, foliage_scene,   foliage_density_map, foliage_types  ) ) = foliage_root_query.single() else {
             warn!("no single foliage root found ");
             continue ; 
         };
@@ -90,7 +87,7 @@ fn build_chunk_layers (
         };   
 
 
-        let global_xform = global_xform_query.get( chunk_layer_entity );
+        let _global_xform = global_xform_query.get( chunk_layer_entity );
 
        // println!("build foliage chunk layer ! ");
 
@@ -141,7 +138,7 @@ fn build_chunk_layers (
 
 
             let layer_dimensions = dimensions_data.0; 
-            let chunk_dimensions = IVec2::new( layer_dimensions.x / 1  ,layer_dimensions.y / 1 ) ;
+            let chunk_dimensions = IVec2::new( layer_dimensions.x  ,layer_dimensions.y ) ;
 
 
 
@@ -307,7 +304,7 @@ fn get_foliage_node_spawn_using_noise(
         
         // Sample noise texture for this position
         // This calculation may need adjustment based on your texture coordinates
-        let noise_tex_data_index = (y * 256 + x) as usize % noise_texture_data.len();
+        let noise_tex_data_index = (y * 256 + x) % noise_texture_data.len();
       
 
 
@@ -388,7 +385,7 @@ fn get_normal_from_height_data(
 
     // Get height values from the neighboring cells
     // Using a 3x3 grid centered at (x,y)
-    let h_center = height_map[y][x] as f32;
+    let _h_center = height_map[y][x] as f32;
     let h_left = height_map[y][x - 1] as f32;
     let h_right = height_map[y][x + 1] as f32;
     let h_up = height_map[y - 1][x] as f32;
@@ -7,9 +7,8 @@ use crate::foliage_types::FoliageDefinition;
 
 //use crate::foliage_layer::FoliageLayer;
 use bevy::prelude::*;
-use std::fs::{create_dir_all, File};
+use std::fs::File;
 use std::io::{Read, Write};
-use std::path::Path;
 
 use  bevy::platform::collections::hash_map::HashMap;
 use serde::{Deserialize, Serialize};
@@ -266,18 +265,18 @@ fn handle_foliage_density_resource_changed (
 
    // foliage_density_resource: Res<FoliageDensityResource>,
 
-   foliage_density_maps_query: Query < (&FoliageDensityMapsComponent ) , Changed<FoliageDensityMapsComponent>  >,
+   foliage_density_maps_query: Query < &FoliageDensityMapsComponent  , Changed<FoliageDensityMapsComponent>  >,
 
   
     foliage_chunk_query: Query< (  Entity, & FoliageChunk  ) >
  
 ) {
 
-    for  foliage_density_map in foliage_density_maps_query.iter() {
+    for  _foliage_density_map in foliage_density_maps_query.iter() {
    
         for (chunk_entity, _chunk) in foliage_chunk_query.iter(){ 
 
-            if let Some(mut cmd) = commands.get_entity( chunk_entity ).ok() {
+            if let Ok(mut cmd) = commands.get_entity( chunk_entity ) {
 
                 cmd.insert( ForceRebuildFoliageChunk  );
             }
@@ -1,16 +1,12 @@
 use bevy::{asset::embedded_asset, prelude::*};
-use bevy::asset::load_internal_binary_asset;
-use bevy::asset::VisitAssetDependencies;
 
 use bevy::reflect::TypePath;
 use bevy::render::render_resource::*;
 
-use bevy::render::render_asset::RenderAssets;
 
-use bevy::pbr::StandardMaterialFlags;
-use bevy::pbr::StandardMaterialUniform;
 
 use bevy::pbr::MaterialExtension;
+use bevy::shader::ShaderRef;
 
 use bevy::pbr::ExtendedMaterial;
 
@@ -3,7 +3,7 @@ use crate::foliage_assets::FoliageMaterialHandle;
 use crate::foliage_chunk::FoliageChunkSystemSet;
 use bevy::prelude::*;
 
-use bevy::light::{CascadeShadowConfigBuilder, NotShadowCaster, NotShadowReceiver};
+use bevy::light::{NotShadowCaster, NotShadowReceiver};
 
 use crate::{foliage_assets::FoliageAssetsResource, foliage_types::FoliageDefinition};
 
@@ -1,4 +1,3 @@
-use crate::foliage_material;
 use crate::foliage_material::FoliageMaterial;
 use crate::foliage_scene::FoliageRoot;
 use crate::foliage_scene::FoliageScene;
@@ -47,7 +46,7 @@ fn register_foliage_assets(
 
 
 
-      let Ok( (foliage_root, foliage_scene,  foliage_types ) ) = foliage_root_query.single () else {
+      let Ok( (_foliage_root, _foliage_scene,  foliage_types ) ) = foliage_root_query.single () else {
            
             return  ; 
         };
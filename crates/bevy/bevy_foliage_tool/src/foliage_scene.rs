@@ -1,5 +1,4 @@
 use crate::foliage_density::FoliageDensityMapsComponent;
-use crate::foliage_density::FoliageDensityMapU8;
  
  
 use crate::FoliageTypesManifest;
@@ -7,7 +6,6 @@ use bevy::prelude::*;
 
 use std::fs::File;
 use std::io::Read;
-use std::path::PathBuf;
 
 use serde::{Deserialize, Serialize};
 
@@ -81,7 +79,7 @@ impl Command for LoadFoliageScene {
 
                 let foliage_density_data_path = foliage_scene.foliage_density_data_path.clone(); 
                 let foliage_definitions = foliage_types_manifest.foliage_definitions .clone(); 
-                let foliage_dimensions = foliage_scene.boundary_dimensions .clone(); 
+                let foliage_dimensions = foliage_scene.boundary_dimensions; 
 
  
                 let foliage_density_maps_component = 
@@ -125,7 +123,7 @@ impl Command for DespawnFoliageScene {
 
               for foliage_root_entity in foliage_root_query.iter(world).collect::<Vec<_>>() {
 
-                  if let Some(mut cmd) = world.commands().get_entity( foliage_root_entity ).ok() {
+                  if let Ok(mut cmd) = world.commands().get_entity( foliage_root_entity ) {
                          cmd.despawn();
                   }
 
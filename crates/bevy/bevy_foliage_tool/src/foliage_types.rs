@@ -3,7 +3,6 @@ use bevy::platform::collections::hash_map::HashMap ;
 
 use std::fs::File;
 use std::io::Read;
-use std::path::PathBuf;
 
 use serde::{Deserialize, Serialize};
 
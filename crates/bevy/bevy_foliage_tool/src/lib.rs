@@ -1,8 +1,8 @@
-use crate::foliage_assets::FoliageAssetsResource;
- 
+#![allow(clippy::type_complexity, clippy::too_many_arguments, clippy::needless_range_loop)]
+
 use crate::foliage_types::FoliageTypesManifest;
  
-use bevy::{asset::load_internal_asset, prelude::*};
+use bevy::prelude::*;
 use edit::bevy_foliage_edits_plugin;
  
 
@@ -1,21 +1,16 @@
 use crate::regionmap::RegionMapU8;
 use std::fs::File;
 use std::io::BufWriter;
-use std::ops::{Add, Div, Neg};
 use std::path::{Path, PathBuf};
 
 use bevy::ecs::entity::Entity;
 use bevy::math::Vec2;
 
-use bevy::ecs::event::Event;
-use bevy::prelude::In;
 use bevy::prelude::*;
 
 use bevy::asset::{AssetServer, Assets};
-use bevy::render::render_resource::{Extent3d, TextureFormat};
  
 
-use bevy::prelude::*;
  
 use core::fmt::{self, Display, Formatter};
 
@@ -26,29 +21,24 @@ use crate::regions_material::RegionsMaterialExtension;
 
  
  
-use anyhow::{Context, Result};
 
-use serde::{Deserialize, Serialize};
-use serde_json;
 
-use rand::Rng;
 
-use core::cmp::{max, min};
+use bevy_brush_shapes::BrushShape;
+
 
 
+#[derive(Default)]
 pub struct BevyRegionEditsPlugin {
     
 }
 
-impl Default for BevyRegionEditsPlugin {
-    fn default() -> Self {
-        Self {
-             
-        }
-    }
-}
 impl Plugin for BevyRegionEditsPlugin {
     fn build(&self, app: &mut App) {
+        app.add_message::<EditRegionEvent>();
+        app.add_message::<RegionCommandEvent>();
+        app.add_message::<RegionBrushEvent>();
+
         app.add_systems(Update, apply_tool_edits); //put this in a sub plugin ?
         app.add_systems(Update, apply_command_events);
 
@@ -84,7 +74,7 @@ impl Display for BrushType {
 }
 
 // entity, editToolType, coords, magnitude
-#[derive(Event, Debug, Clone)]
+#[derive(Message, Debug, Clone)]
 pub struct EditRegionEvent {
     pub entity: Entity, //should always be the plane 
     pub tool: EditingTool,
@@ -92,40 +82,40 @@ pub struct EditRegionEvent {
     pub brush_hardness: f32, //1.0 is full
     pub coordinates: Vec2,
     pub brush_type: BrushType,
+    pub brush_shape: BrushShape, // circle or image alpha
 }
 
-#[derive(Event, Debug, Clone)]
+#[derive(Message, Debug, Clone)]
 pub enum RegionBrushEvent {
     EyeDropRegionIndex { region_index: u8 },
   //  EyeDropSplatMap { r: u8, g: u8, b: u8 },
 }
 
-#[derive(Event, Debug, Clone)]
+#[derive(Message, Debug, Clone)]
 pub enum RegionCommandEvent {
     SaveAll ,  
 }
 
 pub fn apply_command_events(
-    In(()): In<()>,
-    asset_server: Res<AssetServer>,
+    _asset_server: Res<AssetServer>,
 
    // mut chunk_query: Query<(&Chunk, &mut ChunkData, &Parent, &Children)>, //chunks parent should have terrain data
 
-    mut images: ResMut<Assets<Image>>,
-    mut region_materials: ResMut<Assets<RegionsMaterialExtension>>,
+    _images: ResMut<Assets<Image>>,
+    _region_materials: ResMut<Assets<RegionsMaterialExtension>>,
 
-    mut region_maps_res: ResMut<RegionsDataMapResource>, //like height map resource 
+    region_maps_res: ResMut<RegionsDataMapResource>, //like height map resource 
 
     region_data_query: Query<(&RegionsData, &RegionsConfig)>,
 
     
-    mut ev_reader: EventReader<RegionCommandEvent>,
+    mut ev_reader: MessageReader<RegionCommandEvent>,
 ) {
     for ev in ev_reader.read() {
        
            
 
-            let Some((region_data, region_config)) = region_data_query
+            let Some((_region_data, region_config)) = region_data_query
                     .single().ok() else {continue};
 
 
@@ -143,7 +133,7 @@ pub fn apply_command_events(
                         {
 
                         save_region_index_map_to_disk(
-                                &region_data,
+                                region_data,
                                 asset_folder_path.join( region_texture_path ),
                         );
                     }
@@ -163,7 +153,6 @@ pub fn apply_command_events(
 }
 
 pub fn apply_tool_edits(
-    In(()): In<()>,
   
     region_data_query: Query<(&mut RegionsData, &RegionsConfig)> , 
 
@@ -175,16 +164,16 @@ pub fn apply_tool_edits(
 
 
 
-    mut ev_reader: EventReader<EditRegionEvent>,
+    mut ev_reader: MessageReader<EditRegionEvent>,
 
-    mut evt_writer: EventWriter<RegionBrushEvent>,
+    mut evt_writer: MessageWriter<RegionBrushEvent>,
 
-    mut region_data_event_writer: EventWriter<RegionDataEvent>
+    mut region_data_event_writer: MessageWriter<RegionDataEvent>
 ) {
     for ev in ev_reader.read() {
         eprintln!("-- {:?} -- region edit event!", &ev.tool);
 
-       let Some((region_data, region_config)) = region_data_query
+       let Some((_region_data, region_config)) = region_data_query
                     .single().ok() else {
                           warn!("no regions entity found" );
                         continue
@@ -195,13 +184,13 @@ pub fn apply_tool_edits(
         let intersected_entity = &ev.entity;
 
        
-       let Some((region_plane_entity,  _ )) = region_plane_mesh_query.get(intersected_entity.clone()).ok() else {
+       let Some((_region_plane_entity,  _ )) = region_plane_mesh_query.get(*intersected_entity).ok() else {
         warn!("region plane not intersected");
         continue
     } ;
             //let mut chunk_entities_within_range: Vec<Entity> = Vec::new();
 
-            let   plane_dimensions = region_config.boundary_dimensions.clone(); //compute me from  config
+            let   _plane_dimensions = region_config.boundary_dimensions; //compute me from  config
           
 
       
@@ -216,6 +205,7 @@ pub fn apply_tool_edits(
             // ------
             let radius = &ev.radius;
             let brush_type = &ev.brush_type;
+            let brush_shape = &ev.brush_shape;
 
               info!("Region Set Exact 1 ");
 
@@ -238,7 +228,7 @@ pub fn apply_tool_edits(
 
                            
 
-                                let tool_coords: &Vec2 = &ev.coordinates;
+                                let _tool_coords: &Vec2 = &ev.coordinates;
 
                                 let tool_coords_local: &Vec2 = &ev.coordinates;
 
@@ -248,7 +238,7 @@ pub fn apply_tool_edits(
 
                              
 
-                                let radius_clone = radius.clone();
+                                let radius_clone = *radius;
 
                                 info!("Region Set Exact 2 ");
 
@@ -257,11 +247,12 @@ pub fn apply_tool_edits(
                                         for x in 0..img_data_length {
                                             for y in 0..img_data_length {
                                                 let local_coords = Vec2::new(x as f32, y as f32);
+                                                let brush_offset = local_coords - *tool_coords_local;
 
                                                  // info!("local_coords {:?} ", local_coords);
 
-                                                let hardness_multiplier = get_hardness_multiplier(
-                                                    tool_coords_local.distance(local_coords),
+                                                let hardness_multiplier = brush_shape.weight(
+                                                    brush_offset,
                                                     radius_clone,
                                                     *brush_hardness,
                                                 );
@@ -271,10 +262,9 @@ pub fn apply_tool_edits(
                                                  //  info!("tool_coords_local {:?} ", tool_coords_local);
 
 
-                                                if tool_coords_local.distance(local_coords)
-                                                    < radius_clone
+                                                if brush_shape.contains(brush_offset, radius_clone)
                                                 {
-                                                    let new_region_index = region_index.clone();
+                                                    let new_region_index = *region_index;
 
 
                                                     region_map_data[y][x] =
@@ -298,13 +288,12 @@ pub fn apply_tool_edits(
                                         for x in 0..img_data_length {
                                             for y in 0..img_data_length {
                                                 let local_coords = Vec2::new(x as f32, y as f32);
-                                                if tool_coords_local.distance(local_coords)
-                                                    < *radius
+                                                let brush_offset = local_coords - *tool_coords_local;
+                                                if brush_shape.contains(brush_offset, *radius)
                                                 {
                                                     let hardness_multiplier =
-                                                        get_hardness_multiplier(
-                                                            tool_coords_local
-                                                                .distance(local_coords),
+                                                        brush_shape.weight(
+                                                            brush_offset,
                                                             radius_clone,
                                                             *brush_hardness,
                                                         );
@@ -342,7 +331,7 @@ pub fn apply_tool_edits(
                                               
 
                                                 let local_index_data = region_map_data[y][x];
-                                                evt_writer.send(
+                                                evt_writer.write(
                                                     RegionBrushEvent::EyeDropRegionIndex   {
                                                         region_index: local_index_data,
                                                     },
@@ -365,7 +354,7 @@ pub fn apply_tool_edits(
 
                              
 
-                                   region_data_event_writer.send(
+                                   region_data_event_writer.write(
 
                                          RegionDataEvent::RegionMapNeedsReloadFromResourceData
                                     );
@@ -374,23 +363,6 @@ pub fn apply_tool_edits(
     }
 }
 
-fn get_hardness_multiplier(pixel_distance: f32, brush_radius: f32, brush_hardness: f32) -> f32 {
-    // Calculate the distance as a percentage of the radius
-    let distance_percent = pixel_distance / brush_radius;
-    let adjusted_distance_percent = f32::min(1.0, distance_percent); // Ensure it does not exceed 1
-
-    // Calculate the fade effect based on brush hardness
-    // When hardness is 0, this will linearly interpolate from 1 at the center to 0 at the edge
-    // When hardness is between 0 and 1, it adjusts the fade effect accordingly
-    let fade_effect = 1.0 - adjusted_distance_percent;
-
-    // Apply the brush hardness to scale the fade effect, ensuring a minimum of 0
-    f32::max(
-        0.0,
-        fade_effect * (1.0 + brush_hardness) - (adjusted_distance_percent * brush_hardness),
-    )
-}
-
 fn apply_hardness_multiplier(
     original_height: f32,
     new_height: f32,
@@ -416,7 +388,7 @@ pub fn save_region_index_map_to_disk<P>(
     let width = region_map_data.first().map_or(0, |row| row.len());
 
     let file = File::create(save_file_path).expect("Failed to create file");
-    let ref mut w = BufWriter::new(file);
+    let w = &mut BufWriter::new(file);
 
     let mut encoder = png::Encoder::new(w, width as u32, height as u32);
     encoder.set_color(png::ColorType::Grayscale);
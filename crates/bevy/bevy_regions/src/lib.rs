@@ -1,17 +1,17 @@
+#![allow(clippy::type_complexity, clippy::too_many_arguments, clippy::needless_range_loop)]
+
 use crate::tool_preview::update_tool_uniforms;
 use crate::regions::{listen_for_region_events, RegionDataEvent, RegionsDataMapResource};
 use crate::edit::BevyRegionEditsPlugin;
 use crate::regions::load_regions_texture_from_image;
 use crate::regions_material::RegionsMaterialExtension;
-use bevy::time::common_conditions::on_timer;
 use bevy::{asset::load_internal_asset, prelude::*};
  
 use regions::{ initialize_regions,  };
 
-use std::time::Duration;
  
 //use crate::chunk::TerrainMaterialExtension;
-use crate::regions_material::{RegionsMaterial,REGION_SHADER_HANDLE};
+use crate::regions_material::REGION_SHADER_HANDLE;
  
  
  
@@ -27,17 +27,8 @@ pub mod regions_config;
 pub mod regions_material;
 pub mod tool_preview;
 
-pub struct BevyRegionsPlugin {
-    task_update_rate: Duration,
-}
-
-impl Default for BevyRegionsPlugin {
-    fn default() -> Self {
-        Self {
-            task_update_rate: Duration::from_millis(250),
-        }
-    }
-}
+#[derive(Default)]
+pub struct BevyRegionsPlugin {}
 impl Plugin for BevyRegionsPlugin {
     fn build(&self, app: &mut App) {
         // load terrain shader into cache
@@ -53,6 +44,7 @@ impl Plugin for BevyRegionsPlugin {
         app.add_plugins( BevyRegionEditsPlugin::default() ) ;
         app.init_resource::<tool_preview::ToolPreviewResource>();
         app.init_resource::<RegionsDataMapResource>();
+        app.add_message::<RegionDataEvent>();
  
         app.add_systems(Update, initialize_regions);
         app.add_systems(Update, listen_for_region_events);
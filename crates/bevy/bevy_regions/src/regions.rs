@@ -1,5 +1,5 @@
  
-use bevy::asset::{AssetPath, LoadState};
+use bevy::asset::AssetPath;
 use bevy::prelude::*;
 use bevy::image::ImageSampler;
 use bevy::pbr::{ExtendedMaterial, OpaqueRendererMethod};
@@ -7,9 +7,8 @@ use bevy::render::render_resource::{
     TextureFormat,
 };
 
-use super::regionmap::{RegionMap,RegionMapU8,SubRegionMapU8};
+use super::regionmap::{RegionMap,RegionMapU8};
 
-use bevy::platform::collections::hash_map::HashMap;
 
  
 use crate::regions_config::RegionsConfig;
@@ -31,7 +30,7 @@ pub struct RegionPlaneMesh {
 
  
 
-#[derive(Event)]
+#[derive(Message)]
 pub enum RegionDataEvent {
     RegionMapNeedsReloadFromResourceData
 } 
@@ -57,10 +56,10 @@ pub struct  RegionsData {
 
 impl RegionsData {
     pub fn new() -> Self {
-        let regions_data = RegionsData::default();
+        
 
          
-        regions_data
+        RegionsData::default()
     }
 }
 
@@ -97,7 +96,7 @@ pub fn initialize_regions(
            }
 
 
-             if regions_data.regions_image_data_load_status == false {continue};
+             if !regions_data.regions_image_data_load_status {continue};
 
 
 
@@ -128,12 +127,10 @@ pub fn initialize_regions(
                         tool_preview_uniforms: ToolPreviewUniforms::default(),
                         regions_texture: regions_texture.clone(),
                         color_map_texture: regions_data.color_map_texture_handle.clone() ,
-                      
-                        ..default()
                     },
                 });
 
-           let dimensions = regions_config.boundary_dimensions.clone();
+           let dimensions = regions_config.boundary_dimensions;
 
              // ground plane
            let regions_plane = commands.spawn_empty()
@@ -186,7 +183,7 @@ pub fn load_regions_texture_from_image(
             regions_data.texture_image_handle = Some(tex_image);
         }
 
-        if regions_data.regions_image_data_load_status ==false {
+        if !regions_data.regions_image_data_load_status {
 
             let texture_image: &mut Image = match &regions_data.texture_image_handle {
                 Some(texture_image_handle) => {
@@ -228,8 +225,8 @@ pub fn load_regions_texture_from_image(
 
 
 pub fn listen_for_region_events(
-    mut commands : Commands, 
-   mut  evt_reader: EventReader<RegionDataEvent>,
+    _commands : Commands, 
+   mut  evt_reader: MessageReader<RegionDataEvent>,
 
    regions_data_res: Res <RegionsDataMapResource>,
   mut region_data_query: Query<(&mut RegionsData, &RegionsConfig)> , 
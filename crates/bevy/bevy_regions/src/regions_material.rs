@@ -1,16 +1,13 @@
 use bevy::pbr::ExtendedMaterial;
-use bevy::asset::{AssetId, AssetIndex, VisitAssetDependencies};
 use bevy::prelude::*;
 use bevy::reflect::TypePath;
 use bevy::render::render_resource::*;
-use bevy::render::render_asset::RenderAssets;
 
-use bevy::pbr::StandardMaterialFlags;
-use bevy::pbr::StandardMaterialUniform;
 
 use bevy::pbr::MaterialExtension;
+use bevy::shader::ShaderRef;
 
-pub const REGION_SHADER_HANDLE: Handle<Shader> = Handle::from(AssetId::<Shader>::invalid());
+pub const REGION_SHADER_HANDLE: Handle<Shader> = bevy::asset::uuid_handle!("5e8b1c2d-4f6a-4d3b-9c7e-1a2b3c4d5e6f");
 
  pub type RegionsMaterialExtension = ExtendedMaterial<StandardMaterial, RegionsMaterial>;
 
@@ -44,11 +41,11 @@ pub struct RegionsMaterial {
 }
 
 impl MaterialExtension for RegionsMaterial {
-    fn fragment_shader() -> Handle<Shader> {
-        REGION_SHADER_HANDLE
+    fn fragment_shader() -> ShaderRef {
+        ShaderRef::Handle(REGION_SHADER_HANDLE)
     }
 
-    fn deferred_fragment_shader() -> Handle<Shader> {
-        REGION_SHADER_HANDLE
+    fn deferred_fragment_shader() -> ShaderRef {
+        ShaderRef::Handle(REGION_SHADER_HANDLE)
     }
 }
@@ -0,0 +1,565 @@
+use std::path::PathBuf;
+
+use bevy::platform::collections::hash_map::HashMap;
+use bevy::prelude::*;
+
+use bevy_brush_shapes::brush_shape::smoothstep;
+use serde::{Deserialize, Serialize};
+
+use crate::chunk::{Chunk, ChunkCoordinates, ChunkCoords, ChunkHeightMapResource};
+use crate::chunk_mask::{load_chunk_mask_file, save_chunk_mask_files};
+use crate::edit::{BrushType, EditTerrainEvent, EditingTool, TerrainCommandEvent, TerrainDataReplacedEvent};
+use crate::erosion::{chunk_dimensions_of, world_texel_to_chunk};
+use crate::heightmap::HeightMapU16;
+use crate::splat::{ChunkSplatDataRaw, SplatMapDataUpdated};
+use crate::terrain_config::TerrainConfig;
+
+/*
+
+Rule based splat painting.
+
+Every rule produces a 0..1 weight per splat pixel from the terrain around it
+(slope, height, curvature, distance to liquid planes), optionally modulated by noise.
+Rules are layered in order on top of the base texture, so a later rule covers the
+ones before it where its weight is high.
+
+At most four layers contribute to a pixel. The splat format stores two texture
+indices and the strength of the second one (R, G, B), so the two strongest layers
+are written.
+
+Pixels that are set in a chunk's lock mask are never touched. With
+`lock_painted_texels` enabled, pixels painted by hand get locked automatically.
+
+*/
+
+const MAX_LAYERS_PER_PIXEL: usize = 4;
+
+pub fn auto_splat_plugin(app: &mut App) {
+    app.add_message::<AutoSplatEvent>()
+        .init_resource::<SplatLockMaskResource>()
+        .add_systems(
+            Update,
+            (
+                lock_hand_painted_splat_texels.after(crate::edit::apply_tool_edits),
+                apply_auto_splat_events,
+                save_splat_lock_masks,
+            ),
+        );
+}
+
+fn default_strength() -> f32 {
+    1.0
+}
+
+/// A value range with a soft edge of `blend` units on both sides.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AutoSplatRange {
+    #[serde(default = "AutoSplatRange::unbounded_min")]
+    pub min: f32,
+    #[serde(default = "AutoSplatRange::unbounded_max")]
+    pub max: f32,
+    #[serde(default)]
+    pub blend: f32,
+}
+
+impl AutoSplatRange {
+    fn unbounded_min() -> f32 {
+        f32::MIN
+    }
+
+    fn unbounded_max() -> f32 {
+        f32::MAX
+    }
+
+    pub fn weight(&self, value: f32) -> f32 {
+        let distance_outside = if value < self.min {
+            self.min - value
+        } else if value > self.max {
+            value - self.max
+        } else {
+            return 1.0;
+        };
+
+        if self.blend <= 0.0 {
+            return 0.0;
+        }
+
+        smoothstep(0.0, 1.0, 1.0 - distance_outside / self.blend)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AutoSplatNoise {
+    pub frequency: f32,
+    pub amplitude: f32, // how far the noise can push the weight up or down
+    #[serde(default)]
+    pub seed: u32,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AutoSplatRule {
+    #[serde(default)]
+    pub name: String,
+    pub texture_index: u8,
+
+    #[serde(default = "default_strength")]
+    pub strength: f32,
+
+    // steepness in degrees
+    #[serde(default)]
+    pub slope: Option<AutoSplatRange>,
+    // world height (height map value * height_scale)
+    #[serde(default)]
+    pub height: Option<AutoSplatRange>,
+    // positive in hollows and valleys, negative on ridges
+    #[serde(default)]
+    pub curvature: Option<AutoSplatRange>,
+    // horizontal distance to the nearest liquid plane
+    #[serde(default)]
+    pub near_liquid: Option<AutoSplatRange>,
+
+    #[serde(default)]
+    pub noise: Option<AutoSplatNoise>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct AutoSplatRuleSet {
+    pub base_texture_index: u8,
+    pub rules: Vec<AutoSplatRule>,
+}
+
+/// The footprint of a liquid plane in world space.
+#[derive(Clone, Debug)]
+pub struct LiquidPlaneArea {
+    pub center: Vec2, // world xz
+    pub half_extents: Vec2,
+    pub height: f32,
+}
+
+impl LiquidPlaneArea {
+    fn distance_to(&self, world_position: Vec2) -> f32 {
+        let outside = ((world_position - self.center).abs() - self.half_extents).max(Vec2::ZERO);
+        outside.length()
+    }
+}
+
+/// Repaints the splat maps of the given chunks from the rule set.
+#[derive(Message, Clone, Debug)]
+pub struct AutoSplatEvent {
+    pub chunk_ids: Vec<u32>,
+    pub rules: AutoSplatRuleSet,
+    pub liquid_planes: Vec<LiquidPlaneArea>,
+}
+
+/// Per chunk lock masks at splat map resolution, non zero means the pixel is protected.
+#[derive(Resource, Default)]
+pub struct SplatLockMaskResource {
+    pub lock_masks: HashMap<u32, Vec<Vec<u8>>>,
+
+    // lock every pixel that gets painted by hand
+    pub lock_painted_texels: bool,
+}
+
+impl SplatLockMaskResource {
+    pub fn is_locked(&self, chunk_id: u32, x: u32, y: u32) -> bool {
+        self.lock_masks
+            .get(&chunk_id)
+            .and_then(|mask| mask.get(y as usize)?.get(x as usize))
+            .is_some_and(|value| *value > 0)
+    }
+
+    pub fn clear(&mut self) {
+        self.lock_masks.values_mut().for_each(|mask| {
+            mask.iter_mut().for_each(|row| row.fill(0));
+        });
+    }
+
+    // locks are only edited together with the splat map, so the chunk is kept until it is saved
+    pub(crate) fn unload_chunk(&mut self, chunk_id: u32) {
+        self.lock_masks.remove(&chunk_id);
+    }
+
+    fn lock_mask_mut(&mut self, chunk_id: u32, dimensions: UVec2) -> &mut Vec<Vec<u8>> {
+        self.lock_masks
+            .entry(chunk_id)
+            .or_insert_with(|| vec![vec![0; dimensions.x as usize]; dimensions.y as usize])
+    }
+}
+
+fn lock_mask_folder(terrain_config: &TerrainConfig) -> PathBuf {
+    PathBuf::from("assets").join(&terrain_config.splat_folder_path).join("lock")
+}
+
+// cheap lattice value noise in 0..1, good enough to break up rule edges
+fn value_noise(position: Vec2, seed: u32) -> f32 {
+    fn hash(x: i32, y: i32, seed: u32) -> f32 {
+        let mut h = (x as u32).wrapping_mul(0x8da6_b343)
+            ^ (y as u32).wrapping_mul(0xd816_3841)
+            ^ seed.wrapping_mul(0xcb1a_b31f);
+        h ^= h >> 13;
+        h = h.wrapping_mul(0x5bd1_e995);
+        h ^= h >> 15;
+        (h & 0xffff) as f32 / 0xffff as f32
+    }
+
+    let cell = position.floor();
+    let (x, y) = (cell.x as i32, cell.y as i32);
+    let t = position - cell;
+    let (sx, sy) = (smoothstep(0.0, 1.0, t.x), smoothstep(0.0, 1.0, t.y));
+
+    let top = hash(x, y, seed) + (hash(x + 1, y, seed) - hash(x, y, seed)) * sx;
+    let bottom = hash(x, y + 1, seed) + (hash(x + 1, y + 1, seed) - hash(x, y + 1, seed)) * sx;
+
+    top + (bottom - top) * sy
+}
+
+/// Samples terrain heights in world units across chunk borders.
+struct TerrainHeightSampler<'a> {
+    chunk_height_maps: &'a HashMap<u32, HeightMapU16>,
+    chunk_rows: u32,
+    chunk_dimensions: UVec2,
+    terrain_size: UVec2,
+    height_scale: f32,
+}
+
+impl TerrainHeightSampler<'_> {
+    fn texel(&self, texel: IVec2) -> f32 {
+        let texel = texel.clamp(IVec2::ZERO, self.terrain_size.as_ivec2() - IVec2::ONE).as_uvec2();
+        let (chunk_id, local) = world_texel_to_chunk(texel, self.chunk_rows, self.chunk_dimensions);
+
+        self.chunk_height_maps
+            .get(&chunk_id)
+            .and_then(|height_map| height_map.get(local.y as usize)?.get(local.x as usize))
+            .map(|height| *height as f32 / u16::MAX as f32 * self.height_scale)
+            .unwrap_or(0.0)
+    }
+
+    fn height(&self, position: Vec2) -> f32 {
+        let cell = position.floor();
+        let base = cell.as_ivec2();
+        let t = position - cell;
+
+        let h00 = self.texel(base);
+        let h10 = self.texel(base + IVec2::X);
+        let h01 = self.texel(base + IVec2::Y);
+        let h11 = self.texel(base + IVec2::ONE);
+
+        let top = h00 + (h10 - h00) * t.x;
+        let bottom = h01 + (h11 - h01) * t.x;
+        top + (bottom - top) * t.y
+    }
+
+    // slope in degrees and laplacian curvature at a terrain position
+    fn slope_and_curvature(&self, position: Vec2) -> (f32, f32) {
+        let center = self.height(position);
+        let left = self.height(position - Vec2::X);
+        let right = self.height(position + Vec2::X);
+        let down = self.height(position - Vec2::Y);
+        let up = self.height(position + Vec2::Y);
+
+        let gradient = Vec2::new(right - left, up - down) * 0.5;
+        let slope = gradient.length().atan().to_degrees();
+        let curvature = left + right + down + up - 4.0 * center;
+
+        (slope, curvature)
+    }
+}
+
+struct AutoSplatSample {
+    world_position: Vec2,
+    height: f32,
+    slope: f32,
+    curvature: f32,
+    liquid_distance: f32,
+}
+
+impl AutoSplatRule {
+    fn weight(&self, sample: &AutoSplatSample) -> f32 {
+        let mut weight = self.strength.clamp(0.0, 1.0);
+
+        if let Some(range) = &self.slope {
+            weight *= range.weight(sample.slope);
+        }
+        if let Some(range) = &self.height {
+            weight *= range.weight(sample.height);
+        }
+        if let Some(range) = &self.curvature {
+            weight *= range.weight(sample.curvature);
+        }
+        if let Some(range) = &self.near_liquid {
+            weight *= range.weight(sample.liquid_distance);
+        }
+
+        // noise only moves weights that are neither fully on nor fully off, so hard rules stay hard
+        if let Some(noise) = &self.noise
+            && weight > 0.0
+        {
+            let n = value_noise(sample.world_position * noise.frequency, noise.seed) * 2.0 - 1.0;
+            weight = (weight + n * noise.amplitude * (1.0 - (weight * 2.0 - 1.0).abs())).clamp(0.0, 1.0);
+        }
+
+        weight
+    }
+}
+
+impl AutoSplatRuleSet {
+    /// Returns the (texture index, weight) of up to four layers, strongest first, weights summing to 1.
+    fn evaluate(&self, sample: &AutoSplatSample) -> Vec<(u8, f32)> {
+        let mut layers: Vec<(u8, f32)> = vec![(self.base_texture_index, 1.0)];
+
+        // each rule covers everything below it by its own weight
+        for rule in self.rules.iter() {
+            let weight = rule.weight(sample);
+            if weight <= 0.0 {
+                continue;
+            }
+
+            layers.iter_mut().for_each(|(_, w)| *w *= 1.0 - weight);
+
+            match layers.iter_mut().find(|(index, _)| *index == rule.texture_index) {
+                Some((_, w)) => *w += weight,
+                None => layers.push((rule.texture_index, weight)),
+            }
+        }
+
+        layers.sort_by(|a, b| b.1.total_cmp(&a.1));
+        layers.truncate(MAX_LAYERS_PER_PIXEL);
+
+        let total: f32 = layers.iter().map(|(_, w)| w).sum();
+        if total > 0.0 {
+            layers.iter_mut().for_each(|(_, w)| *w /= total);
+        }
+
+        layers
+    }
+}
+
+/// Encodes layers into the R, G, B splat channels: primary index, secondary index, secondary strength.
+fn encode_splat_pixel(layers: &[(u8, f32)]) -> [u8; 3] {
+    let (primary, primary_weight) = layers.first().copied().unwrap_or((0, 1.0));
+    let Some((secondary, secondary_weight)) = layers.get(1).copied() else {
+        return [primary, primary, 0];
+    };
+
+    let strength = secondary_weight / (primary_weight + secondary_weight).max(f32::EPSILON);
+
+    [primary, secondary, (strength * 255.0).round() as u8]
+}
+
+/// Repaints one chunk's splat map. Returns true if any pixel changed.
+pub fn auto_splat_chunk(
+    chunk_id: u32,
+    splat_data: &mut ChunkSplatDataRaw,
+    chunk_height_maps: &HashMap<u32, HeightMapU16>,
+    terrain_config: &TerrainConfig,
+    terrain_origin: Vec2,
+    request: &AutoSplatEvent,
+    lock_masks: &SplatLockMaskResource,
+) -> bool {
+    let chunk_dimensions = chunk_dimensions_of(terrain_config);
+
+    let sampler = TerrainHeightSampler {
+        chunk_height_maps,
+        chunk_rows: terrain_config.chunk_rows,
+        chunk_dimensions,
+        terrain_size: chunk_dimensions * terrain_config.chunk_rows,
+        height_scale: terrain_config.height_scale,
+    };
+
+    let splat_dimensions = UVec2::new(splat_data.splat_map_texture.width(), splat_data.splat_map_texture.height());
+    let texels_per_pixel = chunk_dimensions.as_vec2() / splat_dimensions.max(UVec2::ONE).as_vec2();
+
+    let chunk_coords = ChunkCoords::from_chunk_id(chunk_id, terrain_config.chunk_rows);
+    let chunk_origin = UVec2::new(chunk_coords.x(), chunk_coords.y()) * chunk_dimensions;
+
+    let mut changed = false;
+
+    for y in 0..splat_dimensions.y {
+        for x in 0..splat_dimensions.x {
+            if lock_masks.is_locked(chunk_id, x, y) {
+                continue;
+            }
+
+            let terrain_position = chunk_origin.as_vec2() + (Vec2::new(x as f32, y as f32) + 0.5) * texels_per_pixel;
+            let world_position = terrain_origin + terrain_position;
+
+            let (slope, curvature) = sampler.slope_and_curvature(terrain_position);
+            let height = sampler.height(terrain_position);
+
+            // only liquid planes above the ground count, a lake far below a cliff does not make sand
+            let liquid_distance = request
+                .liquid_planes
+                .iter()
+                .filter(|plane| plane.height >= height - 1.0)
+                .map(|plane| plane.distance_to(world_position))
+                .fold(f32::MAX, f32::min);
+
+            let sample = AutoSplatSample {
+                world_position,
+                height,
+                slope,
+                curvature,
+                liquid_distance,
+            };
+
+            let encoded = encode_splat_pixel(&request.rules.evaluate(&sample));
+
+            for (layer, value) in encoded.iter().enumerate() {
+                if splat_data.get_pixel_index_map_data(x, y, layer as u8) != Some(*value) {
+                    splat_data.set_pixel_index_map_data(x, y, layer as u8, *value);
+                    changed = true;
+                }
+            }
+        }
+    }
+
+    changed
+}
+
+pub fn apply_auto_splat_events(
+    mut commands: Commands,
+    mut ev_reader: MessageReader<AutoSplatEvent>,
+    mut replaced_writer: MessageWriter<TerrainDataReplacedEvent>,
+
+    mut chunk_query: Query<(Entity, &Chunk, &GlobalTransform, &mut ChunkSplatDataRaw)>,
+    chunk_height_maps: Res<ChunkHeightMapResource>,
+
+    mut lock_masks: ResMut<SplatLockMaskResource>,
+
+    terrain_query: Query<&TerrainConfig>,
+) {
+    for ev in ev_reader.read() {
+        let Some(terrain_config) = terrain_query.iter().next() else {
+            warn!("auto splat needs a terrain");
+            continue;
+        };
+
+        let chunk_dimensions = chunk_dimensions_of(terrain_config);
+        let lock_folder = lock_mask_folder(terrain_config);
+
+        let mut painted_chunks = 0;
+
+        for (chunk_entity, chunk, chunk_transform, mut splat_data) in chunk_query.iter_mut() {
+            if !ev.chunk_ids.contains(&chunk.chunk_id) {
+                continue;
+            }
+
+            // lock masks of chunks that were never painted this session live on disk
+            if !lock_masks.lock_masks.contains_key(&chunk.chunk_id)
+                && let Some(mask) = load_chunk_mask_file(&lock_folder.join(format!("{}.png", chunk.chunk_id)))
+            {
+                lock_masks.lock_masks.insert(chunk.chunk_id, mask);
+            }
+
+            let chunk_coords = ChunkCoords::from_chunk_id(chunk.chunk_id, terrain_config.chunk_rows);
+            let translation = chunk_transform.translation();
+            let terrain_origin = Vec2::new(translation.x, translation.z)
+                - Vec2::new(chunk_coords.x() as f32, chunk_coords.y() as f32) * chunk_dimensions.as_vec2();
+
+            let changed = auto_splat_chunk(
+                chunk.chunk_id,
+                &mut splat_data,
+                &chunk_height_maps.chunk_height_maps,
+                terrain_config,
+                terrain_origin,
+                ev,
+                &lock_masks,
+            );
+
+            if changed {
+                painted_chunks += 1;
+
+                if let Ok(mut cmds) = commands.get_entity(chunk_entity) {
+                    cmds.try_insert(SplatMapDataUpdated);
+                }
+            }
+        }
+
+        if painted_chunks > 0 {
+            replaced_writer.write(TerrainDataReplacedEvent);
+        }
+
+        info!("auto splat repainted {} chunks", painted_chunks);
+    }
+}
+
+// mirrors the pixel test of the splat brushes in edit::apply_tool_edits
+fn lock_hand_painted_splat_texels(
+    mut ev_reader: MessageReader<EditTerrainEvent>,
+
+    chunk_query: Query<(&Chunk, &GlobalTransform, &ChunkSplatDataRaw)>,
+    terrain_query: Query<&TerrainConfig>,
+
+    mut lock_masks: ResMut<SplatLockMaskResource>,
+) {
+    for ev in ev_reader.read() {
+        if !lock_masks.lock_painted_texels {
+            continue;
+        }
+        if ev.brush_type != BrushType::SetExact {
+            continue;
+        }
+        if !matches!(ev.tool, EditingTool::SetSplatMap { .. } | EditingTool::SetSplatMapUltra { .. }) {
+            continue;
+        }
+
+        let Some(terrain_config) = terrain_query.iter().next() else {
+            continue;
+        };
+        let chunk_dimensions = chunk_dimensions_of(terrain_config).as_vec2();
+
+        for (chunk, chunk_transform, splat_data) in chunk_query.iter() {
+            let translation = chunk_transform.translation();
+            let chunk_origin = Vec2::new(translation.x, translation.z);
+
+            let splat_dimensions =
+                UVec2::new(splat_data.splat_map_texture.width(), splat_data.splat_map_texture.height());
+            let scale_factor = splat_dimensions.as_vec2() / chunk_dimensions;
+
+            let local_min = ((ev.coordinates - chunk_origin - Vec2::splat(ev.radius)) * scale_factor).floor();
+            let local_max = ((ev.coordinates - chunk_origin + Vec2::splat(ev.radius)) * scale_factor).ceil();
+
+            if local_max.x < 0.0
+                || local_max.y < 0.0
+                || local_min.x >= splat_dimensions.x as f32
+                || local_min.y >= splat_dimensions.y as f32
+            {
+                continue;
+            }
+
+            let min = local_min.max(Vec2::ZERO).as_uvec2();
+            let max = local_max.as_uvec2().min(splat_dimensions);
+
+            let mask = lock_masks.lock_mask_mut(chunk.chunk_id, splat_dimensions);
+
+            for y in min.y..max.y {
+                for x in min.x..max.x {
+                    let pixel_pos = Vec2::new(x as f32, y as f32) / scale_factor + chunk_origin;
+
+                    if ev.brush_shape.contains(pixel_pos - ev.coordinates, ev.radius) {
+                        mask[y as usize][x as usize] = 255;
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn save_splat_lock_masks(
+    mut ev_reader: MessageReader<TerrainCommandEvent>,
+
+    lock_masks: Res<SplatLockMaskResource>,
+    terrain_query: Query<&TerrainConfig>,
+) {
+    for ev in ev_reader.read() {
+        let TerrainCommandEvent::SaveAllChunks(_, true, _) = ev else {
+            continue;
+        };
+
+        let Some(terrain_config) = terrain_query.iter().next() else {
+            continue;
+        };
+
+        save_chunk_mask_files(&lock_masks.lock_masks, &lock_mask_folder(terrain_config), "splat lock mask");
+    }
+}
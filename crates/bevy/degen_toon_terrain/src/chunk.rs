@@ -5,29 +5,26 @@ use crate::splat::ChunkSplatDataRaw;
 use std::time::Duration;
 use bevy::image::ImageSampler;
 use bevy::time::common_conditions::on_timer;
-use std::fs::File;
 use std::path::Path;
 
-use bevy::asset::LoadState;
 use bevy::prelude::*;
-use bevy::render::render_asset::RenderAssetUsages;
-use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::render::render_resource::TextureFormat;
 use bevy::tasks::{AsyncComputeTaskPool, Task};
 
 
 use bevy::platform::collections::hash_map::HashMap;
 use futures_lite::future;
-use image::{GrayImage, ImageBuffer, Luma, RgbaImage};
 
 use crate::heightmap::{HeightMap, HeightMapU16   };
 use crate::pre_mesh::PreMesh;
+use crate::holes::ChunkHoleMaskResource;
+use crate::streaming::ChunkStreamingResource;
 use crate::terrain::{TerrainData, TerrainImageDataLoadStatus, TerrainViewer};
 use crate::terrain_config::TerrainConfig;
 use crate::terrain_material::{ChunkMaterialUniforms, TerrainMaterial, ToolPreviewUniforms};
 use crate::tool_preview::ToolPreviewResource;
 
 use bevy::pbr::ExtendedMaterial;
-use bevy::pbr::OpaqueRendererMethod;
 
 
 use std::fs;
@@ -79,6 +76,7 @@ pub fn chunks_plugin(app: &mut App){
             add_render_chunk_at_lod_component,
             rebuild_chunk_from_lod, 
             build_chunk_height_data,
+            finish_height_map_decode_tasks,
             finish_chunk_build_tasks,
              
             build_chunk_meshes,
@@ -287,7 +285,11 @@ impl ChunkData {
  
 
     pub fn get_lod_level(&self) -> u8 {
-        self.lod_level.clone() 
+        self.lod_level 
+    }
+
+    pub fn is_building(&self) -> bool {
+        self.chunk_state == ChunkState::Building
     }
  
 
@@ -308,8 +310,6 @@ pub struct BuiltChunkMeshData {
 
     mesh: Mesh,
     chunk_uv: Vec4,
-
-    lod_level: u8,
 }
 
 #[derive(Component)]
@@ -363,7 +363,7 @@ impl ChunkCoordinates for ChunkCoords {
 
     //chunk index is   chunk_col * 64  + chunk_row   IF chunk_rows is 64
     fn get_chunk_index(&self, chunk_rows: u32) -> u32 {
-        return self.y() * chunk_rows + self.x() as u32;
+        self.y() * chunk_rows + self.x()
     }
 
     fn from_chunk_id(chunk_id: u32, chunk_rows: u32) -> Self {
@@ -437,30 +437,16 @@ impl ChunkCoordinates for ChunkCoords {
 
         let pct_per_row = 1.0 / chunk_rows as f32;
 
-        return [
+        [
             [chunk_x as f32 * pct_per_row, chunk_y as f32 * pct_per_row], //start corner x and y
             [
                 (chunk_x + 1) as f32 * pct_per_row,
                 (chunk_y + 1) as f32 * pct_per_row,
             ], //end corner x and y
-        ];
+        ]
     }
 }
 
-fn calculate_chunk_coords(
-    from_location: Vec3,
-    terrain_origin: Vec3,
-    terrain_dimensions: Vec2,
-    chunk_rows: u32,
-) -> [i32; 2] {
-    let location_delta = from_location - terrain_origin;
-
-    let chunk_x = (location_delta.x / terrain_dimensions.x * chunk_rows as f32) as i32;
-    let chunk_z = (location_delta.z / terrain_dimensions.y * chunk_rows as f32) as i32;
-
-    return [chunk_x, chunk_z];
-}
-
 pub fn initialize_chunk_data(
     mut commands: Commands,
 
@@ -469,15 +455,33 @@ pub fn initialize_chunk_data(
     mut chunk_query: Query<(Entity, &Chunk, &ChildOf), Without<ChunkData>>,
 
     terrain_query: Query<(&TerrainConfig, &TerrainData)>,
+
+    chunk_streaming: Res<ChunkStreamingResource>,
 ) {
+    let mut chunks_initialized: usize = 0;
+
     for (chunk_entity, chunk, terrain_entity) in chunk_query.iter_mut() {
         let terrain_entity_id = terrain_entity.parent();
-        if terrain_query.get(terrain_entity_id).is_ok() == false {
+        if terrain_query.get(terrain_entity_id).is_err() {
             continue;
         }
-        let (terrain_config, terrain_data) = terrain_query.get(terrain_entity_id).unwrap();
+        let (terrain_config, _terrain_data) = terrain_query.get(terrain_entity_id).unwrap();
 
         let chunk_id = chunk.chunk_id;
+
+        //with streaming, only chunks near a terrain viewer get their data loaded (see streaming.rs)
+        if let Some(streaming_config) = &terrain_config.chunk_streaming {
+            if !chunk_streaming.is_streamed_in(chunk_id) {
+                continue;
+            }
+
+            if chunks_initialized >= streaming_config.max_chunk_loads_per_update {
+                continue;
+            }
+        }
+
+        chunks_initialized += 1;
+
         let file_name = format!("{}.png", chunk_id);
 
         //default_terrain/diffuse
@@ -547,7 +551,7 @@ pub fn initialize_chunk_data(
 pub fn add_chunk_splat_data_raw(
 
     mut commands:Commands,
-    mut images: ResMut<Assets<Image>>,
+    images: ResMut<Assets<Image>>,
 
     chunk_query: Query<(Entity, &Chunk, & ChunkData), Without< ChunkSplatDataRaw >>,
 
@@ -563,10 +567,10 @@ pub fn add_chunk_splat_data_raw(
         if chunk_data.chunk_state != ChunkState::FullyBuilt { continue ; }; 
 
             //not rly necessary? 
-        if chunk_data.splat_texture_is_loaded   {
+        if chunk_data.splat_texture_is_loaded
 
 
-            if let Ok(mut cmd) = commands.get_entity(  entity ) {
+            && let Ok(mut cmd) = commands.get_entity(  entity ) {
 
                 let Some(splat_index_texture_handle) = &chunk_data.splat_texture_handle else {continue};
               //  let Some(splat_strength_texture_handle) = &chunk_data.splat_strength_texture_handle else {continue};
@@ -584,8 +588,6 @@ pub fn add_chunk_splat_data_raw(
                  );
             }
 
-        }
-
     }
 }
 
@@ -653,7 +655,7 @@ Have to do this hack since bevy is not correctly detecting the format
 
 
 pub fn update_splat_image_formats(
-    mut ev_asset: EventReader<AssetEvent<Image>>,
+    mut ev_asset: MessageReader<AssetEvent<Image>>,
     mut images: ResMut<Assets<Image>>,
 
     mut chunk_query: Query<(Entity, &Chunk, &mut ChunkData)>,
@@ -682,16 +684,16 @@ pub fn update_splat_image_formats(
                 */
             
 
-                for (entity, chunk, mut chunk_data) in chunk_query.iter_mut() {
+                for (_entity, _chunk, mut chunk_data) in chunk_query.iter_mut() {
 
                        // let mut image_is_splat_index_texture = false;
                        // let mut image_is_splat_strength_texture = false; 
 
-                        if let Some(ref splat_handle) = chunk_data.splat_texture_handle {
-                            if splat_handle.id() == *id {
+                        if let Some(ref splat_handle) = chunk_data.splat_texture_handle
+                            && splat_handle.id() == *id {
                                 //image_is_splat_index_texture = true
 
-                                let img = images.get_mut(id).unwrap();
+                                let img = images.get_mut(*id).unwrap();
                        // println!("splat index map image format is {:?}", img.texture_descriptor.format);
                         img.texture_descriptor.format = TextureFormat::Rgba8Uint;
                         img.sampler = ImageSampler::nearest(); //need for bevy 0.15 
@@ -699,8 +701,8 @@ pub fn update_splat_image_formats(
                         chunk_data.splat_texture_is_loaded = true;
 
 
-                         if let Some( terrain_material_handle ) = &  chunk_data.material_handle {
-                            if let Some(terrain_material) = terrain_materials.get_mut( terrain_material_handle ){
+                         if let Some( terrain_material_handle ) = &  chunk_data.material_handle
+                            && let Some(terrain_material) = terrain_materials.get_mut( terrain_material_handle ){
 
 
                              terrain_material.extension.splat_map_texture =  chunk_data.splat_texture_handle.clone() ;
@@ -708,9 +710,8 @@ pub fn update_splat_image_formats(
                        
                             }
 
-
                                 continue;
-                            } 
+                            }
                   /*  if chunk_data.splat_strength_texture_handle == Some(handle.clone()) {
                         //image_is_splat_strength_texture = true
 
@@ -765,7 +766,7 @@ pub fn update_splat_image_formats(
             }
 
 
-            AssetEvent::Modified { id } => {
+            AssetEvent::Modified { id: _ } => {
 
 
             }
@@ -804,7 +805,7 @@ pub fn add_render_chunk_at_lod_component(
 
         let Some(chunk_parent) = parent_query.get(chunk_entity).ok() else {continue};
 
-         let Some((terrain_data,terrain_config)) = terrain_query.get(chunk_parent.parent()).ok() else {continue};
+         let Some((_terrain_data,terrain_config)) = terrain_query.get(chunk_parent.parent()).ok() else {continue};
 
 
 
@@ -819,8 +820,7 @@ pub fn add_render_chunk_at_lod_component(
             + Vec3::new(chunk_dimensions.x / 2.0, 0.0 , chunk_dimensions.y / 2.0  ) ; // add offset ? 
 
         
-        let Some(viewer_translation) = terrain_viewer_entity.map(|ent|  global_transform_query.get(ent).ok() ) 
-        .flatten().map(|xform| xform.translation() ) else {continue};
+        let Some(viewer_translation) = terrain_viewer_entity.and_then(|ent|  global_transform_query.get(ent).ok()).map(|xform| xform.translation() ) else {continue};
  
 
         let chunk_distance = viewer_translation.distance(chunk_center);
@@ -850,7 +850,7 @@ pub fn rebuild_chunk_from_lod(
 ){
 
 
-    for (chunk, mut chunk_data,  render_at_lod) in chunk_query.iter_mut(){
+    for (_chunk, mut chunk_data,  render_at_lod) in chunk_query.iter_mut(){
 
         let render_lod = render_at_lod.0;
 
@@ -869,17 +869,15 @@ pub fn rebuild_chunk_from_lod(
 }
 
 pub fn reset_chunk_height_data(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    mut images: ResMut<Assets<Image>>,
-
-    chunk_height_maps: Res <ChunkHeightMapResource>,
+    mut chunk_streaming: ResMut<ChunkStreamingResource>,
 
     mut chunk_query: Query<(Entity, &Chunk, &mut ChunkData, &ChildOf, &Children)>,
 ) {
-    for (chunk_entity, chunk, mut chunk_data, terrain_entity, children) in chunk_query.iter_mut() {
+    for (_chunk_entity, chunk, mut chunk_data, _terrain_entity, _children) in chunk_query.iter_mut() {
         if chunk_data.height_map_image_data_load_status == TerrainImageDataLoadStatus::NeedsReload {
             
+            //edited height data has to stay in memory until it is saved
+            chunk_streaming.unsaved_chunks.insert(chunk.chunk_id);
 
             chunk_data.chunk_state = ChunkState::Init; // change me ?
                                                        //chunk_data.height_map_image_data_load_status = TerrainImageDataLoadStatus::NotLoaded;
@@ -894,19 +892,20 @@ pub fn reset_chunk_height_data(
 
 
 
+#[derive(Component)]
+pub struct HeightMapDecodeTask(Task<Option<HeightMapU16>>);
+
 pub fn build_chunk_height_data(
-    //mut commands: Commands,
+    mut commands: Commands,
     asset_server: Res<AssetServer>,
     mut images: ResMut<Assets<Image>>,
 
-    mut chunk_height_maps: ResMut<ChunkHeightMapResource>,
-
-    mut chunk_query: Query<(Entity, &Chunk, &mut ChunkData, &ChildOf)>,
+    mut chunk_query: Query<(Entity, &Chunk, &mut ChunkData, &ChildOf), Without<HeightMapDecodeTask>>,
 ) {
 
 
 
-    for (chunk_entity, chunk, mut chunk_data, terrain_entity) in chunk_query.iter_mut() {
+    for (chunk_entity, chunk, chunk_data, _terrain_entity) in chunk_query.iter_mut() {
 
 
         if chunk_data.height_map_image_data_load_status == TerrainImageDataLoadStatus::NotLoaded {
@@ -931,60 +930,79 @@ pub fn build_chunk_height_data(
 
  
 
-            //this is causing crashes i believe 
-
             let chunk_id = chunk.chunk_id; 
 
-                info!("loading height map {} from disk - io intensive ... ", chunk_id);
+                info!("decoding height map {} ... ", chunk_id);
  
             //make sure height image descriptor is correct here (force) 
             height_map_image.sampler = ImageSampler::nearest();
 
 
- 
-            //maybe we can do this in a thread since it is quite cpu intense ?
-            let loaded_heightmap_data_result = HeightMapU16::load_from_image(height_map_image);
+            //decoding is quite cpu intense so it runs on the async compute pool, see finish_height_map_decode_tasks
+            let height_map_image = height_map_image.clone();
 
-            
-            match loaded_heightmap_data_result {
-                Ok(loaded_heightmap_data) => {
-                    
-                 
-                    chunk_height_maps
-                        .chunk_height_maps
-                        .insert(chunk.chunk_id,  *loaded_heightmap_data );
-                   
-                }
-                Err(e) => {
-                    println!("{}", e);
+            let thread_pool = AsyncComputeTaskPool::get();
+            let task = thread_pool.spawn(async move {
+                match HeightMapU16::load_from_image(&height_map_image) {
+                    Ok(loaded_heightmap_data) => Some(*loaded_heightmap_data),
+                    Err(e) => {
+                        warn!("could not decode height map {}: {}", chunk_id, e);
+                        None
+                    }
                 }
-            }
-
-            //we can let go of the height map image handle now that we loaded our heightmap data from it
-            //terrain_data.height_map_image_handle = None;
-            chunk_data.height_map_image_data_load_status = TerrainImageDataLoadStatus::Loaded;
-           
-             info!("finished loading height map {} from disk  . ", chunk_id);
+            });
 
+            commands.entity(chunk_entity).insert(HeightMapDecodeTask(task));
         }
     }
 } 
 
 
+pub fn finish_height_map_decode_tasks(
+    mut commands: Commands,
+
+    mut chunk_height_maps: ResMut<ChunkHeightMapResource>,
 
-#[derive(Event)] 
-pub struct TerrainImageDataNeedsReload;
+    mut chunk_query: Query<(Entity, &Chunk, &mut ChunkData, &mut HeightMapDecodeTask)>,
+) {
+    for (chunk_entity, chunk, mut chunk_data, mut task) in chunk_query.iter_mut() {
+        let Some(loaded_heightmap_data) = future::block_on(future::poll_once(&mut task.0)) else {
+            continue;
+        };
+
+        if let Some(loaded_heightmap_data) = loaded_heightmap_data {
+            chunk_height_maps
+                .chunk_height_maps
+                .insert(chunk.chunk_id, loaded_heightmap_data);
+        }
+
+        //we can let go of the height map image handle now that we loaded our heightmap data from it
+        //terrain_data.height_map_image_handle = None;
+        chunk_data.height_map_image_data_load_status = TerrainImageDataLoadStatus::Loaded;
+
+        commands.entity(chunk_entity).remove::<HeightMapDecodeTask>();
+
+        info!("finished decoding height map {} . ", chunk.chunk_id);
+    }
+}
+
+
+
+#[derive(EntityEvent)] 
+pub struct TerrainImageDataNeedsReload {
+    pub entity: Entity,
+}
 
 fn handle_trigger_terrain_image_data_needs_reload(
 
-    trigger: Trigger<  TerrainImageDataNeedsReload >,
+    trigger: On<  TerrainImageDataNeedsReload >,
 
     mut chunk_data_query: Query<&mut ChunkData>
 
 ){
 
 
-    let chunk_entity = trigger.target(); 
+    let chunk_entity = trigger.entity; 
 
 
     let Some(mut chunk_data) = chunk_data_query.get_mut(chunk_entity ).ok() else {return };
@@ -1087,7 +1105,9 @@ pub fn build_chunk_meshes(
 
     mut chunk_mesh_build_task_counter_resource: ResMut<ChunkMeshBuildTaskCounterResource>,
 
-         chunk_lod_resource: Res<ChunkLodResource>
+         chunk_lod_resource: Res<ChunkLodResource>,
+
+    hole_masks: Res<ChunkHoleMaskResource>,
 
    // chunk_build_tasks_query: Query<Entity,With<MeshBuilderTask>>
     // mut chunk_data_query: Query<( &mut ChunkData )>,
@@ -1099,7 +1119,7 @@ pub fn build_chunk_meshes(
 
 
 
-    for (chunk_entity, chunk, mut chunk_data, terrain_entity, visibility, render_at_lod) in chunk_query.iter_mut()
+    for (chunk_entity, chunk, mut chunk_data, terrain_entity, visibility, _render_at_lod) in chunk_query.iter_mut()
     {
 
          if chunk_mesh_build_task_counter_resource.active_build_tasks >= MAX_CONCURRENT_CHUNK_BUILD_TASKS {
@@ -1112,10 +1132,15 @@ pub fn build_chunk_meshes(
 
         if chunk_data.chunk_state == ChunkState::Init {
             let terrain_entity_id = terrain_entity.parent();
-            if terrain_query.get(terrain_entity_id).is_ok() == false {
+            if terrain_query.get(terrain_entity_id).is_err() {
+                continue;
+            }
+            let (terrain_config, _terrain_data) = terrain_query.get(terrain_entity_id).unwrap();
+
+            // cut out cells have to be known before the mesh is built
+            if !hole_masks.is_mask_checked(chunk.chunk_id) {
                 continue;
             }
-            let (terrain_config, terrain_data) = terrain_query.get(terrain_entity_id).unwrap();
 
             let height_map_data = chunk_height_maps.chunk_height_maps.get(&chunk.chunk_id); // &chunk_data.height_map_data.clone();
 
@@ -1168,7 +1193,7 @@ pub fn build_chunk_meshes(
                 height_map_subsection_pct[1][1],
             );
 
-            let chunk_id_clone = chunk.chunk_id.clone();
+            let chunk_id_clone = chunk.chunk_id;
 
             //  let chunk_coords = ChunkCoords::from_chunk_id(chunk_id_clone, chunk_rows);
 
@@ -1179,8 +1204,9 @@ pub fn build_chunk_meshes(
                 &chunk_height_maps.chunk_height_maps,
             );
 
+            //a neighbour is still loading, try again next time
             if stitch_data_x_row.is_none() || stitch_data_y_col.is_none() {
-                return;
+                continue;
             }
 
             //for now, add the unstitched data..
@@ -1196,8 +1222,8 @@ pub fn build_chunk_meshes(
             let mut sub_heightmap =  height_map_data_ref.to_vec() ;
  
 
-            stitch_data_x_row.map(|x_row| sub_heightmap.append_x_row(x_row));
-            stitch_data_y_col.map(|y_col| sub_heightmap.append_y_col(y_col));
+            if let Some(x_row) = stitch_data_x_row { sub_heightmap.append_x_row(x_row) }
+            if let Some(y_col) = stitch_data_y_col { sub_heightmap.append_y_col(y_col) }
 
             /*
             commands.entity(chunk_entity).insert(
@@ -1214,6 +1240,8 @@ pub fn build_chunk_meshes(
 
             let adjacent_chunk_lods: HashMap<CardinalDirection, u8> = chunk_lod_resource.get_adjacent_chunk_lods (  chunk.chunk_id  , chunk_rows); 
 
+            let hole_mask = hole_masks.hole_masks.get(&chunk.chunk_id).cloned();
+
             let task = thread_pool.spawn(async move {
                 info!("trying to build premesh");
 
@@ -1233,7 +1261,8 @@ pub fn build_chunk_meshes(
                         height_scale,
                         lod_level,
                         sub_texture_dim,
-                        adjacent_chunk_lods
+                        adjacent_chunk_lods,
+                        hole_mask.as_ref(),
                     ),
 
                     false => PreMesh::from_heightmap_subsection(
@@ -1241,7 +1270,8 @@ pub fn build_chunk_meshes(
                         height_scale,
                         lod_level,
                         sub_texture_dim,
-                        adjacent_chunk_lods
+                        adjacent_chunk_lods,
+                        hole_mask.as_ref(),
                     ),
                 }
                 .build();
@@ -1249,11 +1279,10 @@ pub fn build_chunk_meshes(
                 info!("built premesh   ");
 
                 BuiltChunkMeshData {
-                    chunk_entity_id: chunk_entity.clone(),
+                    chunk_entity_id: chunk_entity,
 
                     mesh,
                     chunk_uv,
-                    lod_level,
                 }
             });
 
@@ -1322,7 +1351,7 @@ pub fn finish_chunk_build_tasks(
 
             let chunk_entity_id = built_chunk_mesh_data.chunk_entity_id;
 
-            if chunk_query.get_mut(chunk_entity_id).is_ok() == false {
+            if chunk_query.get_mut(chunk_entity_id).is_err() {
 
                
 
@@ -1335,7 +1364,7 @@ pub fn finish_chunk_build_tasks(
 
                 continue;
             }
-            let (chunk_entity, chunk, mut chunk_data, terrain_entity) =
+            let (_chunk_entity, _chunk, mut chunk_data, terrain_entity) =
                 chunk_query.get_mut(chunk_entity_id).unwrap();
 
             let terrain_entity_id = terrain_entity.parent();
@@ -1354,7 +1383,7 @@ pub fn finish_chunk_build_tasks(
             }
 
             //careful w this unwrap
-            if terrain_query.get(terrain_entity_id).is_ok() == false {
+            if terrain_query.get(terrain_entity_id).is_err() {
 
                 warn!( "unable to complete mesh builder task: chunk entity terrain parent is broken" );
                 commands.entity(entity).despawn();  //despawn the task  
@@ -1404,12 +1433,12 @@ pub fn finish_chunk_build_tasks(
                             chunk_uv,
                         },
                         tool_preview_uniforms: ToolPreviewUniforms::default(),
-                        diffuse_texture: diffuse_texture,
-                        secondary_diffuse_texture: secondary_diffuse_texture, 
-                        normal_texture: normal_texture,
-                        blend_height_texture: blend_height_texture, 
+                        diffuse_texture,
+                        secondary_diffuse_texture, 
+                        normal_texture,
+                        blend_height_texture, 
 
-                        splat_map_texture: splat_map_texture,
+                        splat_map_texture,
 
                     //    splat_strength_map_texture: splat_strength_map_texture, 
 
@@ -1491,13 +1520,13 @@ pub fn update_chunk_visibility(
         Err(_e) => Vec3::new(0.0, 0.0, 0.0),
     };
 
-    for (chunk, mut chunk_data, parent_entity, chunk_transform, mut chunk_visibility) in
+    for (_chunk, _chunk_data, parent_entity, chunk_transform, mut chunk_visibility) in
         chunk_query.iter_mut()
     {
-        if let Ok((terrain_config, terrain_data)) = terrain_query.get(parent_entity.parent()) {
+        if let Ok((terrain_config, _terrain_data)) = terrain_query.get(parent_entity.parent()) {
             //  let render_distance_chunks:i32  = terrain_config.get_chunk_render_distance() as i32 ; //make based on render dist
-            let lod_level_distance: f32 = terrain_config.get_chunk_lod_distance();
-            let lod_level_offset: u8 = terrain_config.lod_level_offset;
+            let _lod_level_distance: f32 = terrain_config.get_chunk_lod_distance();
+            let _lod_level_offset: u8 = terrain_config.lod_level_offset;
 
             //calc chunk world loc and use to calc the lod
             let chunk_world_location = chunk_transform.translation();
@@ -1540,7 +1569,7 @@ fn despawn_chunks(
 
         if let Ok(mut cmd) = commands.get_entity( ent ){
 
-            cmd.despawn_recursive();
+            cmd.despawn();
         }
 
     }
@@ -1673,10 +1702,11 @@ pub fn compute_stitch_data(
 // chunk_height_data [y][x]
 // this applies  a stitch along the X axis - should pull all values along X axis
     if let Some(chunk_height_data) = chunk_height_maps.get(&stitch_chunk_id_pos_x) {
-        let mut final_vec = Vec::new();
-        for i in 0..chunk_dimensions.x() as usize {
-            final_vec.push(chunk_height_data [i][0]);
-        }
+        let final_vec: Vec<u16> = chunk_height_data
+            .iter()
+            .take(chunk_dimensions.x() as usize)
+            .map(|row| row[0])
+            .collect();
         // final_vec.push(stitch_data_x_y_corner.unwrap_or(0)) ;
         stitch_data_x_row = Some(final_vec);
     } else {
@@ -1686,10 +1716,7 @@ pub fn compute_stitch_data(
             return (None, None);
         }; //prevents loading race cond issue with stitching
 
-        let mut final_vec = Vec::new();
-        for _ in 0..chunk_dimensions.x() as usize {
-            final_vec.push(0);
-        }
+        let final_vec = vec![0; chunk_dimensions.x() as usize];
 
         //final_vec.push(stitch_data_x_y_corner.unwrap_or(0)); // the corner corner --gotta fix me some how ?? - try to read diag chunk
 
@@ -1700,10 +1727,11 @@ pub fn compute_stitch_data(
 
 // this applies  a stitch along the Y axis - should pull all values along y axis
     if let Some(chunk_height_data) = chunk_height_maps.get(&stitch_chunk_id_pos_y) {
-        let mut final_vec = Vec::new();
-        for i in 0..chunk_dimensions.y() as usize {
-            final_vec.push(chunk_height_data [0][i]);
-        }
+        let mut final_vec: Vec<u16> = chunk_height_data[0]
+            .iter()
+            .take(chunk_dimensions.y() as usize)
+            .copied()
+            .collect();
         final_vec.push(stitch_data_x_y_corner.unwrap_or(0)); // the corner corner --gotta fix me some how ?? - try to read diag chunk
         stitch_data_y_col = Some(final_vec);
     } else {
@@ -1712,10 +1740,7 @@ pub fn compute_stitch_data(
             return (None, None);
         }; //prevents loading race cond issue with stitching
 
-        let mut final_vec = Vec::new();
-        for _ in 0..chunk_dimensions.y() as usize {
-            final_vec.push(0);
-        }
+        let mut final_vec = vec![0; chunk_dimensions.y() as usize];
         final_vec.push(stitch_data_x_y_corner.unwrap_or(0)); // the corner corner --gotta fix me some how ?? - try to read diag chunk
 
         stitch_data_y_col = Some(final_vec);
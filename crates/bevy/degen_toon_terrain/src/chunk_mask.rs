@@ -0,0 +1,102 @@
+use std::path::Path;
+
+use bevy::platform::collections::hash_map::HashMap;
+use bevy::prelude::*;
+
+use image::{GrayImage, Luma};
+
+/*
+
+Per chunk u8 masks (hole masks, splat lock masks, erosion maps).
+
+Masks are stored [y][x] like the heightmaps and saved as 8-bit grayscale pngs,
+one {chunk_id}.png per chunk inside the mask's folder.
+
+*/
+
+pub type ChunkMaskU8 = Vec<Vec<u8>>;
+
+pub fn chunk_mask_size(mask: &ChunkMaskU8) -> UVec2 {
+    let height = mask.len() as u32;
+    let width = mask.first().map_or(0, |row| row.len()) as u32;
+    UVec2::new(width, height)
+}
+
+pub fn load_chunk_mask_file(path: &Path) -> Option<ChunkMaskU8> {
+    let image = image::open(path).ok()?.into_luma8();
+
+    Some(
+        image
+            .rows()
+            .map(|row| row.map(|pixel| pixel.0[0]).collect())
+            .collect(),
+    )
+}
+
+pub fn save_chunk_mask_file(mask: &ChunkMaskU8, path: &Path) -> Result<(), image::ImageError> {
+    let size = chunk_mask_size(mask);
+    let image = GrayImage::from_fn(size.x, size.y, |x, y| Luma([mask[y as usize][x as usize]]));
+
+    image.save(path)
+}
+
+/// Saves every mask into `folder`, logging failures. `label` names the masks in the log.
+pub fn save_chunk_mask_files(masks: &HashMap<u32, ChunkMaskU8>, folder: &Path, label: &str) {
+    if let Err(e) = std::fs::create_dir_all(folder) {
+        error!("could not create {} folder {:?}: {}", label, folder, e);
+        return;
+    }
+
+    for (chunk_id, mask) in masks.iter() {
+        let path = folder.join(format!("{}.png", chunk_id));
+        if let Err(e) = save_chunk_mask_file(mask, &path) {
+            error!("could not save {} {:?}: {}", label, path, e);
+        }
+    }
+}
+
+/// Nearest neighbour resize, for mask files saved at a different chunk resolution.
+pub fn resize_chunk_mask(mask: &ChunkMaskU8, size: UVec2) -> ChunkMaskU8 {
+    let source_size = chunk_mask_size(mask);
+
+    if source_size == size {
+        return mask.clone();
+    }
+
+    if source_size.x == 0 || source_size.y == 0 {
+        return vec![vec![0; size.x as usize]; size.y as usize];
+    }
+
+    (0..size.y)
+        .map(|y| {
+            let source_y = (y * source_size.y / size.y) as usize;
+
+            (0..size.x)
+                .map(|x| mask[source_y][(x * source_size.x / size.x) as usize])
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resize_chunk_mask_upscales_nearest() {
+        let mask: ChunkMaskU8 = vec![vec![0, 255], vec![255, 0]];
+
+        let resized = resize_chunk_mask(&mask, UVec2::new(4, 4));
+
+        assert_eq!(chunk_mask_size(&resized), UVec2::new(4, 4));
+        assert_eq!(resized[0], vec![0, 0, 255, 255]);
+        assert_eq!(resized[3], vec![255, 255, 0, 0]);
+    }
+
+    #[test]
+    fn test_resize_chunk_mask_empty_source() {
+        let resized = resize_chunk_mask(&Vec::new(), UVec2::new(3, 2));
+
+        assert_eq!(resized, vec![vec![0; 3]; 2]);
+    }
+}
@@ -111,6 +111,8 @@ pub(crate) fn build_colliders_for_terrain(
            let mesh = meshes.get(mesh_handle).context("No mesh found for terrain chunk")?;
 
            //convex_hull_from_mesh was the old style 
+           //the chunk mesh is built by PreMesh with the hole mask applied, so holes carry over into the collider.
+           //baked colliders (SaveAllChunks) cut the same holes in edit.rs
            let collider = Collider::trimesh_from_mesh(&mesh).context("Failed to create collider from mesh")?; 
            
            commands.entity(entity).insert(collider);
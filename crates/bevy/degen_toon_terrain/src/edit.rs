@@ -1,10 +1,8 @@
 use crate::chunk::ChunkLodResource;
-use crate::chunk::CardinalDirection;
 use crate::splat::SplatMapDataUpdated;
 use crate::splat::save_chunk_splat_map_to_disk; 
 
 
-use bevy::platform::collections::hash_map::HashMap;
  
 use crate::heightmap::HeightMap;
 use crate::splat::ChunkSplatDataRaw;
@@ -14,50 +12,51 @@ use std::path::PathBuf;
 use bevy::ecs::entity::Entity;
 use bevy::math::Vec2;
 
-use bevy::ecs::event::Message;
 use bevy::prelude::MessageReader;
 
 use bevy::asset::{AssetServer, Assets};
-use bevy::render::render_resource::{Extent3d, TextureFormat};
  
 
 
 use bevy::prelude::*;
 
-use crate::pre_mesh::PreMesh;
+use bevy_brush_shapes::BrushShape;
+use crate::holes::ChunkHoleMaskResource;
 use crate::TerrainMaterialExtension;
 use core::fmt::{self, Display, Formatter};
 
 use crate::chunk::{
-    compute_stitch_data, save_chunk_collision_data_to_disk,   
-     Chunk, ChunkCoordinates, ChunkCoords, ChunkData,
+    compute_stitch_data,   
+     Chunk, ChunkCoordinates, ChunkData,
     ChunkHeightMapResource,
 };
 use crate::terrain::{TerrainData, TerrainImageDataLoadStatus};
 use crate::terrain_config::TerrainConfig;
-use crate::terrain_material::TerrainMaterial;
  
  #[cfg(feature = "physics")]
  use avian3d::prelude::Collider;
+#[cfg(feature = "physics")]
+use crate::chunk::{save_chunk_collision_data_to_disk, CardinalDirection};
+#[cfg(feature = "physics")]
+use crate::pre_mesh::PreMesh;
+#[cfg(feature = "physics")]
+use bevy::platform::collections::hash_map::HashMap;
 
 
  
 
 use crate::chunk::TerrainChunkMesh;
-use anyhow::{Context, Result};
 
-use serde::{Deserialize, Serialize};
-use serde_json;
 
 use rand::Rng;
 
-use core::cmp::{max, min};
 
 #[derive(Debug, Clone)]
 pub enum EditingTool {
     SetHeightMap { height: u16 },        // height, radius, save to disk
     SetSplatMap { r: u8, g: u8, b: u8 }, //R, G, B, radius, save to disk
     SetSplatMapUltra { texture_indices: [u8;4], texture_strengths: [u8;4] }, 
+    SetHoleMask, // SetExact cuts, ClearAll fills. handled by holes::apply_hole_brush_edits
 }
 
 #[derive(Debug, Default, Clone, Eq, PartialEq)]
@@ -69,6 +68,8 @@ pub enum BrushType {
     Noise,
     EyeDropper,
     RaiseLower, 
+    Erode, // handled by erosion::apply_erosion_brush_edits
+    Stamp, // adds the height profile of the brush shape, scaled by the tool height
 }
 
 impl Display for BrushType {
@@ -82,6 +83,8 @@ impl Display for BrushType {
             BrushType::EyeDropper => "EyeDropper",
 
              BrushType::RaiseLower => "Raise/Lower",
+             BrushType::Erode => "Erode",
+             BrushType::Stamp => "Stamp",
          
 
 
@@ -100,6 +103,7 @@ pub struct EditTerrainEvent {
     pub brush_hardness: f32, //1.0 is full
     pub coordinates: Vec2,
     pub brush_type: BrushType,
+    pub brush_shape: BrushShape, // circle, image alpha or height stamp
 }
 
 #[derive(Message, Debug, Clone)]
@@ -111,56 +115,68 @@ pub enum TerrainBrushEvent {
 #[derive(Message, Debug, Clone)]
 pub enum TerrainCommandEvent {
     SaveAllChunks(bool, bool, bool), //height data, splat data, collision data
+    ImportHeightmap(PathBuf), // single 16-bit png or raw file covering the whole terrain
+    ExportHeightmap(PathBuf), // stitched heightmap of the whole terrain, png or raw by extension
+    ErodeTerrain, // whole terrain erosion pass using the ErosionSettings resource
 }
 
+// sent by everything that rewrites chunk data outside of a brush stroke (imports, generation,
+// whole terrain passes, splines), undo history recorded against the old data is no longer valid
+#[derive(Message, Debug, Clone)]
+pub struct TerrainDataReplacedEvent;
+
+#[cfg_attr(not(feature = "physics"), allow(unused_variables))]
 pub fn apply_command_events(
-    asset_server: Res<AssetServer>,
+    _asset_server: Res<AssetServer>,
 
       chunk_query: Query<(&Chunk, & ChunkData, &ChunkSplatDataRaw, &ChildOf, &Children)>, //chunks parent should have terrain data
 
-    mut images: ResMut<Assets<Image>>,
-    mut terrain_materials: ResMut<Assets<TerrainMaterialExtension>>,
+    _images: ResMut<Assets<Image>>,
+    _terrain_materials: ResMut<Assets<TerrainMaterialExtension>>,
 
-    mut chunk_height_maps: ResMut<ChunkHeightMapResource>,
+    chunk_height_maps: ResMut<ChunkHeightMapResource>,
 
     terrain_query: Query<(&TerrainData, &TerrainConfig)>,
 
     chunk_mesh_query: Query<(Entity, &Mesh3d , &GlobalTransform), With<TerrainChunkMesh>>,
-    meshes: Res<Assets<Mesh>>,  
+    _meshes: Res<Assets<Mesh>>,  
 
 
         chunk_lod_resource: Res<ChunkLodResource>,
 
+    hole_masks: Res<ChunkHoleMaskResource>,
+
     mut ev_reader: MessageReader<TerrainCommandEvent>,
 ) {
     for ev in ev_reader.read() {
-        for (chunk, chunk_data, chunk_splat_data, parent_terrain_entity, chunk_children) in chunk_query.iter() {
+        for (chunk, _chunk_data, chunk_splat_data, parent_terrain_entity, chunk_children) in chunk_query.iter() {
             let terrain_entity_id = parent_terrain_entity.parent();
 
-            if terrain_query.get(terrain_entity_id).is_ok() == false {
+            if terrain_query.get(terrain_entity_id).is_err() {
                 continue;
             }
 
-            let (terrain_data, terrain_config) = terrain_query.get(terrain_entity_id).unwrap();
+            let (_terrain_data, terrain_config) = terrain_query.get(terrain_entity_id).unwrap();
 
             match ev {
                 TerrainCommandEvent::SaveAllChunks(save_height, save_splat, save_collision) => {
                     let file_name = format!("{}.png", chunk.chunk_id);
                     let asset_folder_path = PathBuf::from("assets");
-                    if *save_height {
-                        if let Some(chunk_height_data) =
+                    if *save_height
+                        && let Some(chunk_height_data) =
                             chunk_height_maps.chunk_height_maps.get(&chunk.chunk_id)
                         {
 
-                            chunk_height_data.save_heightmap_to_image(
-                                asset_folder_path
+                            let height_file_path = asset_folder_path
                                     .join(&terrain_config.height_folder_path)
-                                    .join(&file_name)
-                                    );
+                                    .join(&file_name);
+
+                            if let Err(e) = chunk_height_data.save_heightmap_to_image(&height_file_path) {
+                                error!("could not save chunk heightmap {:?}: {}", height_file_path, e);
+                            }
 
                           
                         }
-                    }
 
                     //need to rewrite this !! 
                      if *save_splat {
@@ -170,7 +186,7 @@ pub fn apply_command_events(
                          
                            
                             save_chunk_splat_map_to_disk(
-                                &chunk_splat_map_image,
+                                chunk_splat_map_image,
                                 asset_folder_path
                                     .join(&terrain_config.splat_folder_path)
                                    // .join("index_maps")
@@ -185,8 +201,8 @@ pub fn apply_command_events(
                     if *save_collision {
                         println!("Generating and saving collision data.. please wait..");
                         for chunk_child in chunk_children {
-                            if let Ok((entity, mesh_handle, mesh_transform)) =
-                                chunk_mesh_query.get(chunk_child.clone())
+                            if let Ok((_entity, _mesh_handle, _mesh_transform)) =
+                                chunk_mesh_query.get(*chunk_child)
                             {
                                 /* let mesh = meshes
                                 .get(mesh_handle)
@@ -200,10 +216,10 @@ pub fn apply_command_events(
                                 let height_map_data =
                                     chunk_height_maps.chunk_height_maps.get(&chunk.chunk_id); // &chunk_data.height_map_data.clone();
                                 let height_map_data_cloned =
-                                    ( height_map_data.as_ref().unwrap()) ;
+                                    height_map_data.as_ref().unwrap()  ;
                                 let mut sub_heightmap : Vec<Vec<u16>> =   height_map_data_cloned.to_vec() ;
 
-                                let chunk_id_clone = chunk.chunk_id.clone();
+                                let chunk_id_clone = chunk.chunk_id;
 
                                 let (stitch_data_x_row, stitch_data_y_col) = compute_stitch_data(
                                     chunk_id_clone,
@@ -216,8 +232,8 @@ pub fn apply_command_events(
                                     return;
                                 }
 
-                                stitch_data_x_row.map(|x_row| sub_heightmap.append_x_row(x_row));
-                                stitch_data_y_col.map(|y_col| sub_heightmap.append_y_col(y_col));
+                                if let Some(x_row) = stitch_data_x_row { sub_heightmap.append_x_row(x_row) }
+                                if let Some(y_col) = stitch_data_y_col { sub_heightmap.append_y_col(y_col) }
 
                                 let height_scale = terrain_config.height_scale;
                                 let sub_texture_dim = [
@@ -249,7 +265,8 @@ pub fn apply_command_events(
                                                 height_scale,
                                                 lod_level,
                                                 sub_texture_dim,
-                                                adjacent_chunk_lods
+                                                adjacent_chunk_lods,
+                                                hole_masks.hole_masks.get(&chunk.chunk_id),
                                             ),
 
                                             false => PreMesh::from_heightmap_subsection(
@@ -257,7 +274,8 @@ pub fn apply_command_events(
                                                 height_scale,
                                                 lod_level,
                                                 sub_texture_dim,
-                                                adjacent_chunk_lods
+                                                adjacent_chunk_lods,
+                                                hole_masks.hole_masks.get(&chunk.chunk_id),
                                             ),
                                         }
                                         .build();
@@ -307,6 +325,12 @@ pub fn apply_command_events(
 
                     println!("save complete");
                 }
+
+                // handled by heightmap_io::apply_heightmap_io_events
+                TerrainCommandEvent::ImportHeightmap(..) | TerrainCommandEvent::ExportHeightmap(..) => {}
+
+                // handled by erosion::apply_erosion_command_events
+                TerrainCommandEvent::ErodeTerrain => {}
             }
         }
     }
@@ -316,13 +340,13 @@ pub fn apply_command_events(
 
 pub fn apply_tool_edits(
     mut commands: Commands, 
-    mut asset_server: Res<AssetServer>,
+    _asset_server: Res<AssetServer>,
 
     mut chunk_query: Query<(Entity, &Chunk, &mut ChunkData, &ChildOf, &GlobalTransform, Option<&mut ChunkSplatDataRaw>)>, //chunks parent should have terrain data
     chunk_mesh_query: Query<(&ChildOf, &GlobalTransform)>,
 
-    mut images: ResMut<Assets<Image>>,
-    mut terrain_materials: ResMut<Assets<TerrainMaterialExtension>>,
+    _images: ResMut<Assets<Image>>,
+    _terrain_materials: ResMut<Assets<TerrainMaterialExtension>>,
 
     mut chunk_height_maps: ResMut<ChunkHeightMapResource>,
 
@@ -330,7 +354,7 @@ pub fn apply_tool_edits(
 
     mut ev_reader: MessageReader<EditTerrainEvent>,
 
-    mut evt_writer: EventWriter<TerrainBrushEvent>,
+    mut evt_writer: MessageWriter<TerrainBrushEvent>,
 ) {
     for ev in ev_reader.read() {
         info!("-- {:?} -- terrain edit event!", &ev.tool);
@@ -338,15 +362,14 @@ pub fn apply_tool_edits(
         let intersected_entity = &ev.entity;
 
         //  if let Some((chunk, mut chunk_data)) = chunk_query.get_mut(intersected_entity.clone()).ok()
-        if let Some((chunk_entity, _)) = chunk_mesh_query.get(intersected_entity.clone()).ok() {
+        if let Ok((chunk_entity, _)) = chunk_mesh_query.get(*intersected_entity) {
             let mut chunk_entities_within_range: Vec<Entity> = Vec::new();
 
             let mut chunk_dimensions = [256, 256]; //compute me from terrain config
             if let Some((_, _, _, terrain_entity, _, _ )) =
-                chunk_query.get_mut(chunk_entity.parent().clone()).ok()
-            {
-                if let Some((terrain_data, terrain_config)) =
-                    terrain_query.get(terrain_entity.parent().clone()).ok()
+                chunk_query.get_mut(chunk_entity.parent()).ok()
+                && let Some((_terrain_data, terrain_config)) =
+                    terrain_query.get(terrain_entity.parent()).ok()
                 {
                     let chunk_rows = terrain_config.chunk_rows;
                     let terrain_dimensions = terrain_config.terrain_dimensions;
@@ -356,7 +379,6 @@ pub fn apply_tool_edits(
                         terrain_dimensions.y as u32 / chunk_rows,
                     ];
                 }
-            }
 
             //populate chunk_entities_within_range
             for (chunk_entity, _, _, _, chunk_transform, _) in chunk_query.iter() {
@@ -393,15 +415,14 @@ pub fn apply_tool_edits(
 
             for chunk_entity_within_range in chunk_entities_within_range.clone() {
                 if let Some((
-                    chunk_entity,
+                    _chunk_entity,
                     chunk,
-                    mut chunk_data,
-                    terrain_entity,
+                    _chunk_data,
+                    _terrain_entity,
                     chunk_transform, 
-                    mut chunk_splat_data_raw
-                )) = chunk_query.get_mut(chunk_entity_within_range.clone()).ok()
-                {
-                    if let Some(height_map_data) =
+                    _chunk_splat_data_raw
+                )) = chunk_query.get_mut(chunk_entity_within_range).ok()
+                    && let Some(height_map_data) =
                         &mut chunk_height_maps.chunk_height_maps.get_mut(&chunk.chunk_id)
                     {
                         let tool_coords: &Vec2 = &ev.coordinates;
@@ -430,24 +451,24 @@ pub fn apply_tool_edits(
                             }
                         }
                     }
-                }
             }
-            let average_height = total_height as f32 / heights_len as f32;
+            let average_height = total_height / heights_len as f32;
             // ------
             let radius = &ev.radius;
             let brush_type = &ev.brush_type;
+            let brush_shape = &ev.brush_shape;
 
             let brush_hardness = &ev.brush_hardness;
             //apply the tool to each chunk in range
             for chunk_entity_within_range in chunk_entities_within_range {
-                if let Some((
+                if let Ok((
                     chunk_entity,
                     chunk,
                     mut chunk_data,
-                    terrain_entity,
+                    _terrain_entity,
                     chunk_transform,
-                    mut chunk_splat_data_raw
-                )) = chunk_query.get_mut(chunk_entity_within_range.clone()).ok()
+                    chunk_splat_data_raw
+                )) = chunk_query.get_mut(chunk_entity_within_range)
                 {
                     //   if let Some(mut terrain_data) = terrain_data_query.get_mut(terrain_entity.get().clone()).ok() { //why cant i find this ?
 
@@ -470,25 +491,25 @@ pub fn apply_tool_edits(
 
                                 let mut height_changed = false;
 
-                                let radius_clone = radius.clone();
+                                let radius_clone = *radius;
 
                                 match brush_type {
                                     BrushType::SetExact => {
                                         for x in 0..img_data_length {
                                             for y in 0..img_data_length {
                                                 let local_coords = Vec2::new(x as f32, y as f32);
+                                                let brush_offset = local_coords - tool_coords_local;
 
-                                                let hardness_multiplier = get_hardness_multiplier(
-                                                    tool_coords_local.distance(local_coords),
+                                                let hardness_multiplier = brush_shape.weight(
+                                                    brush_offset,
                                                     radius_clone,
                                                     *brush_hardness,
                                                 );
                                                 let original_height = height_map_data[y][x];
 
-                                                if tool_coords_local.distance(local_coords)
-                                                    < radius_clone
+                                                if brush_shape.contains(brush_offset, radius_clone)
                                                 {
-                                                    let new_height = height.clone();
+                                                    let new_height = *height;
                                                     height_map_data[y][x] =
                                                         apply_hardness_multiplier(
                                                             original_height as f32,
@@ -513,12 +534,13 @@ pub fn apply_tool_edits(
                                        for x in 0..img_data_length {
                                         for y in 0..img_data_length {
                                             let local_coords = Vec2::new(x as f32, y as f32);
+                                            let brush_offset = local_coords - tool_coords_local;
 
-                                            // Check if the current point is within the brush radius
-                                            if tool_coords_local.distance(local_coords) < radius_clone {
+                                            // Check if the current point is within the brush shape
+                                            if brush_shape.contains(brush_offset, radius_clone) {
                                                 // Compute the hardness multiplier
-                                                let hardness_multiplier = get_hardness_multiplier(
-                                                    tool_coords_local.distance(local_coords),
+                                                let hardness_multiplier = brush_shape.weight(
+                                                    brush_offset,
                                                     radius_clone,
                                                     *brush_hardness,
                                                 );
@@ -551,13 +573,12 @@ pub fn apply_tool_edits(
                                         for x in 0..img_data_length {
                                             for y in 0..img_data_length {
                                                 let local_coords = Vec2::new(x as f32, y as f32);
-                                                if tool_coords_local.distance(local_coords)
-                                                    < *radius
+                                                let brush_offset = local_coords - tool_coords_local;
+                                                if brush_shape.contains(brush_offset, *radius)
                                                 {
                                                     let hardness_multiplier =
-                                                        get_hardness_multiplier(
-                                                            tool_coords_local
-                                                                .distance(local_coords),
+                                                        brush_shape.weight(
+                                                            brush_offset,
                                                             radius_clone,
                                                             *brush_hardness,
                                                         );
@@ -587,14 +608,13 @@ pub fn apply_tool_edits(
                                         for x in 0..img_data_length {
                                             for y in 0..img_data_length {
                                                 let local_coords = Vec2::new(x as f32, y as f32);
-                                                if tool_coords_local.distance(local_coords)
-                                                    < *radius
+                                                let brush_offset = local_coords - tool_coords_local;
+                                                if brush_shape.contains(brush_offset, *radius)
                                                 {
                                                     let original_height = height_map_data[y][x];
                                                     let hardness_multiplier =
-                                                        get_hardness_multiplier(
-                                                            tool_coords_local
-                                                                .distance(local_coords),
+                                                        brush_shape.weight(
+                                                            brush_offset,
                                                             radius_clone,
                                                             *brush_hardness,
                                                         );
@@ -643,6 +663,35 @@ pub fn apply_tool_edits(
                                             }
                                         }
                                     }
+
+                                    // erosion works across chunk borders, see erosion::apply_erosion_brush_edits
+                                    BrushType::Erode => {}
+
+                                    BrushType::Stamp => {
+                                        for x in 0..img_data_length {
+                                            for y in 0..img_data_length {
+                                                let local_coords = Vec2::new(x as f32, y as f32);
+
+                                                // negative for stamps that dig, like the crater
+                                                let profile = brush_shape.height(
+                                                    local_coords - tool_coords_local,
+                                                    radius_clone,
+                                                    *brush_hardness,
+                                                );
+
+                                                if profile == 0.0 {
+                                                    continue;
+                                                }
+
+                                                let original_height = height_map_data[y][x] as f32;
+                                                let new_height = (original_height + profile * *height as f32)
+                                                    .clamp(0.0, u16::MAX as f32);
+
+                                                height_map_data[y][x] = new_height as u16;
+                                                height_changed = true;
+                                            }
+                                        }
+                                    }
                                 }
 
                                 if height_changed {
@@ -674,7 +723,7 @@ pub fn apply_tool_edits(
                                         chunk_dimensions.y() as f32,
                                     );
 
-                                    let tool_coords_local =
+                                    let _tool_coords_local =
                                         tool_coords.add(chunk_transform_vec2.neg());
 
                                     /*let pixel_pos = Vec2::new(
@@ -683,7 +732,7 @@ pub fn apply_tool_edits(
                                         tool_coords_local.y / chunk_dimensions_vec.y
                                             * splat_dimensions.y as f32,
                                     );*/
-                                    let pixel_radius = *radius as f32;
+                                    let pixel_radius = *radius;
 
 
 
@@ -750,7 +799,7 @@ pub fn apply_tool_edits(
                                                         //  img.data[idx] = *r as u8;
 
                                                         // Check if the pixel is within the tool's radius AND within reasonable bounds
-                                                        if tool_coords.distance(pixel_pos ) < pixel_radius
+                                                        if brush_shape.contains(pixel_pos - *tool_coords, pixel_radius)
                                                             && is_pixel_within_reasonable_bounds(
                                                                 pixel_pos,
                                                                 *tool_coords,
@@ -766,12 +815,12 @@ pub fn apply_tool_edits(
 
                                                                 let new_texture_value = match layer_index {
 
-                                                                    0 => *r as u8,
-                                                                    1 => *g as u8, 
+                                                                    0 => *r,
+                                                                    1 => *g, 
 
-                                                                    2 => *b as u8 ,
+                                                                    2 => *b ,
 
-                                                                    _ => 0 as u8 
+                                                                    _ => 0_u8 
 
 
                                                                 };
@@ -780,9 +829,8 @@ pub fn apply_tool_edits(
 
 
                                                                  let   hardness_multiplier =
-                                                                get_hardness_multiplier(
-                                                                    tool_coords 
-                                                                        .distance(pixel_pos),
+                                                                brush_shape.weight(
+                                                                    pixel_pos - *tool_coords,
                                                                     pixel_radius,
                                                                     *brush_hardness,
                                                                 );
@@ -875,7 +923,7 @@ pub fn apply_tool_edits(
                                                         //  img.data[idx] = *r as u8;
 
                                                         // Check if the pixel is within the tool's radius AND within reasonable bounds
-                                                        if tool_coords.distance(pixel_pos ) < pixel_radius
+                                                        if brush_shape.contains(pixel_pos - *tool_coords, pixel_radius)
                                                             && is_pixel_within_reasonable_bounds(
                                                                 pixel_pos,
                                                                 *tool_coords,
@@ -998,11 +1046,11 @@ pub fn apply_tool_edits(
                                         chunk_dimensions.y() as f32,
                                     );
 
-                                    let tool_coords_local =
+                                    let _tool_coords_local =
                                         tool_coords.add(chunk_transform_vec2.neg());
 
                                      
-                                    let pixel_radius = *radius as f32;
+                                    let pixel_radius = *radius;
 
 
 
@@ -1073,7 +1121,7 @@ pub fn apply_tool_edits(
 
 
 
-                                                       let   hardness_multiplier =
+                                                       let   _hardness_multiplier =
                                                         get_hardness_multiplier(
                                                             tool_coords 
                                                                 .distance(pixel_pos),
@@ -1088,7 +1136,7 @@ pub fn apply_tool_edits(
 
 
                                                                 let texture_type_index = texture_indices[texture_layer as usize]; 
-                                                                let texture_strength = texture_strengths[texture_layer as usize];
+                                                                let _texture_strength = texture_strengths[texture_layer as usize];
 
 
 
@@ -1169,7 +1217,7 @@ pub fn apply_tool_edits(
                                                         //  img.data[idx] = *r as u8;
 
                                                         // Check if the pixel is within the tool's radius AND within reasonable bounds
-                                                        if tool_coords.distance(pixel_pos ) < pixel_radius
+                                                        if brush_shape.contains(pixel_pos - *tool_coords, pixel_radius)
                                                             && is_pixel_within_reasonable_bounds(
                                                                 pixel_pos,
                                                                 *tool_coords,
@@ -1268,7 +1316,7 @@ pub fn apply_tool_edits(
                              
                         } 
 
-
+                        EditingTool::SetHoleMask => {} // handled by holes::apply_hole_brush_edits
 
                     } //match
                 }
@@ -1304,7 +1352,7 @@ fn is_pixel_within_reasonable_bounds(
     distance_check && bounds_check
 }
 
-fn get_hardness_multiplier(pixel_distance: f32, brush_radius: f32, brush_hardness: f32) -> f32 {
+pub(crate) fn get_hardness_multiplier(pixel_distance: f32, brush_radius: f32, brush_hardness: f32) -> f32 {
     // Calculate the distance as a percentage of the radius
     let distance_percent = pixel_distance / brush_radius;
     let adjusted_distance_percent = f32::min(1.0, distance_percent); // Ensure it does not exceed 1
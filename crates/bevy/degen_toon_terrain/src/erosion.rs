@@ -0,0 +1,743 @@
+use std::path::PathBuf;
+
+use bevy::platform::collections::hash_map::HashMap;
+use bevy::platform::collections::HashSet;
+use bevy::prelude::*;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::chunk::{Chunk, ChunkCoordinates, ChunkCoords, ChunkData, ChunkHeightMapResource};
+use crate::chunk_mask::save_chunk_mask_files;
+use crate::edit::{BrushType, EditTerrainEvent, EditingTool, TerrainCommandEvent, TerrainDataReplacedEvent};
+use crate::heightmap::HeightMapU16;
+use crate::terrain::TerrainImageDataLoadStatus;
+use crate::terrain_config::TerrainConfig;
+
+/*
+
+Particle based hydraulic erosion and talus based thermal erosion.
+
+Both work on a HeightField, a float copy of any rectangle of the terrain that may span
+several chunks, so droplets and slumping material cross chunk borders freely.
+Heights in the field are in world units (height * height_scale) so slopes and the
+talus angle mean the same thing regardless of the terrain's height scale.
+
+The whole terrain pass can also record where water flowed and where sediment was
+deposited. Those maps are kept per chunk in ChunkErosionMapsResource (and also
+written to disk) so they can drive splat painting.
+
+*/
+
+pub fn erosion_plugin(app: &mut App) {
+    app.init_resource::<ErosionSettings>()
+        .init_resource::<ChunkErosionMapsResource>()
+        .add_systems(
+            Update,
+            (
+                apply_erosion_brush_edits.after(crate::edit::apply_tool_edits),
+                apply_erosion_command_events,
+            ),
+        );
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HydraulicErosionSettings {
+    pub whole_terrain_droplets: u32,
+    pub brush_droplets_per_texel: f32, // per brush event
+
+    pub max_lifetime: u32,
+    pub inertia: f32,
+    pub sediment_capacity_factor: f32,
+    pub min_sediment_capacity: f32,
+    pub erode_speed: f32,
+    pub deposit_speed: f32,
+    pub evaporate_speed: f32,
+    pub gravity: f32,
+    pub erosion_radius: u32,
+
+    pub initial_water: f32,
+    pub initial_speed: f32,
+}
+
+impl Default for HydraulicErosionSettings {
+    fn default() -> Self {
+        Self {
+            whole_terrain_droplets: 200_000,
+            brush_droplets_per_texel: 0.02,
+
+            max_lifetime: 48,
+            inertia: 0.05,
+            sediment_capacity_factor: 4.0,
+            min_sediment_capacity: 0.01,
+            erode_speed: 0.3,
+            deposit_speed: 0.3,
+            evaporate_speed: 0.01,
+            gravity: 4.0,
+            erosion_radius: 3,
+
+            initial_water: 1.0,
+            initial_speed: 1.0,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ThermalErosionSettings {
+    pub whole_terrain_iterations: u32,
+    pub brush_iterations: u32,
+
+    // slopes steeper than this (in degrees) shed material downhill
+    pub talus_angle: f32,
+    pub strength: f32, // 0..1, fraction of the excess moved per iteration
+}
+
+impl Default for ThermalErosionSettings {
+    fn default() -> Self {
+        Self {
+            whole_terrain_iterations: 20,
+            brush_iterations: 1,
+
+            talus_angle: 40.0,
+            strength: 0.5,
+        }
+    }
+}
+
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+#[derive(Default)]
+pub struct ErosionSettings {
+    pub hydraulic: HydraulicErosionSettings,
+    pub thermal: ThermalErosionSettings,
+
+    pub seed: u64,
+
+    // record flow/deposition maps during the whole terrain pass
+    pub output_maps: bool,
+    // written as {folder}/flow/{chunk_id}.png and {folder}/deposition/{chunk_id}.png,
+    // defaults to an `erosion` folder next to the splat maps
+    pub output_maps_folder: Option<PathBuf>,
+}
+
+
+/// Flow and deposition maps from the last whole terrain pass, keyed by chunk id and laid out like the heightmaps.
+#[derive(Resource, Default)]
+pub struct ChunkErosionMapsResource {
+    pub flow_maps: HashMap<u32, Vec<Vec<u8>>>,
+    pub deposition_maps: HashMap<u32, Vec<Vec<u8>>>,
+}
+
+/// Accumulated erosion output over a HeightField, same layout as its heights.
+pub struct ErosionMaps {
+    pub flow: Vec<f32>,
+    pub deposition: Vec<f32>,
+}
+
+impl ErosionMaps {
+    pub fn new(size: UVec2) -> Self {
+        let len = (size.x * size.y) as usize;
+
+        Self {
+            flow: vec![0.0; len],
+            deposition: vec![0.0; len],
+        }
+    }
+}
+
+/// Maps a world texel position to (chunk_id, local texel) the way the chunk heightmaps are laid out.
+pub(crate) fn world_texel_to_chunk(texel: UVec2, chunk_rows: u32, chunk_dimensions: UVec2) -> (u32, UVec2) {
+    let chunk_coords = texel / chunk_dimensions;
+    let chunk_id = ChunkCoords::new(chunk_coords.x, chunk_coords.y).get_chunk_index(chunk_rows);
+
+    (chunk_id, texel - chunk_coords * chunk_dimensions)
+}
+
+pub fn chunk_dimensions_of(terrain_config: &TerrainConfig) -> UVec2 {
+    UVec2::new(
+        terrain_config.terrain_dimensions.x as u32 / terrain_config.chunk_rows,
+        terrain_config.terrain_dimensions.y as u32 / terrain_config.chunk_rows,
+    )
+    .max(UVec2::ONE)
+}
+
+/// A float copy of a rectangle of the terrain, in world texels.
+pub struct HeightField {
+    pub origin: UVec2,
+    pub size: UVec2,
+    pub height_scale: f32,
+
+    pub heights: Vec<f32>,
+
+    // 0..1 per texel, how strongly erosion may change it. 0 for texels without a loaded chunk.
+    pub mask: Vec<f32>,
+
+    // false for texels without a loaded chunk, droplets treat those as outside the field
+    pub loaded: Vec<bool>,
+}
+
+impl HeightField {
+    pub fn from_chunk_height_maps(
+        chunk_height_maps: &HashMap<u32, HeightMapU16>,
+        origin: UVec2,
+        size: UVec2,
+        terrain_config: &TerrainConfig,
+    ) -> Self {
+        let chunk_dimensions = chunk_dimensions_of(terrain_config);
+        let height_scale = terrain_config.height_scale;
+
+        let len = (size.x * size.y) as usize;
+        let mut heights = vec![0.0; len];
+        let mut mask = vec![0.0; len];
+        let mut loaded = vec![false; len];
+
+        for y in 0..size.y {
+            for x in 0..size.x {
+                let (chunk_id, local) =
+                    world_texel_to_chunk(origin + UVec2::new(x, y), terrain_config.chunk_rows, chunk_dimensions);
+
+                let Some(height) = chunk_height_maps
+                    .get(&chunk_id)
+                    .and_then(|height_map| height_map.get(local.y as usize)?.get(local.x as usize))
+                else {
+                    continue;
+                };
+
+                let index = (y * size.x + x) as usize;
+                heights[index] = *height as f32 / u16::MAX as f32 * height_scale;
+                mask[index] = 1.0;
+                loaded[index] = true;
+            }
+        }
+
+        Self {
+            origin,
+            size,
+            height_scale,
+            heights,
+            mask,
+            loaded,
+        }
+    }
+
+    /// Writes the field back into the chunks. Returns the ids of the chunks that changed.
+    pub fn write_to_chunk_height_maps(
+        &self,
+        chunk_height_maps: &mut HashMap<u32, HeightMapU16>,
+        terrain_config: &TerrainConfig,
+    ) -> HashSet<u32> {
+        let chunk_dimensions = chunk_dimensions_of(terrain_config);
+        let mut changed_chunks = HashSet::new();
+
+        for y in 0..self.size.y {
+            for x in 0..self.size.x {
+                let index = (y * self.size.x + x) as usize;
+                if self.mask[index] <= 0.0 {
+                    continue;
+                }
+
+                let (chunk_id, local) =
+                    world_texel_to_chunk(self.origin + UVec2::new(x, y), terrain_config.chunk_rows, chunk_dimensions);
+
+                let Some(texel) = chunk_height_maps
+                    .get_mut(&chunk_id)
+                    .and_then(|height_map| height_map.get_mut(local.y as usize)?.get_mut(local.x as usize))
+                else {
+                    continue;
+                };
+
+                let new_height = (self.heights[index] / self.height_scale.max(f32::EPSILON) * u16::MAX as f32)
+                    .round()
+                    .clamp(0.0, u16::MAX as f32) as u16;
+
+                if *texel != new_height {
+                    *texel = new_height;
+                    changed_chunks.insert(chunk_id);
+                }
+            }
+        }
+
+        changed_chunks
+    }
+
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        if x < 0 || y < 0 || x >= self.size.x as i32 || y >= self.size.y as i32 {
+            return None;
+        }
+
+        Some((y as u32 * self.size.x + x as u32) as usize)
+    }
+
+    // like `index`, but texels of unloaded chunks are outside too. Their height of 0 is not real terrain.
+    fn loaded_index(&self, x: i32, y: i32) -> Option<usize> {
+        self.index(x, y).filter(|index| self.loaded[*index])
+    }
+
+    // bilinear height and gradient at a position inside the loaded part of the field
+    fn height_and_gradient(&self, position: Vec2) -> Option<(f32, Vec2)> {
+        let cell = position.floor();
+        let (x, y) = (cell.x as i32, cell.y as i32);
+
+        let h00 = self.heights[self.loaded_index(x, y)?];
+        let h10 = self.heights[self.loaded_index(x + 1, y)?];
+        let h01 = self.heights[self.loaded_index(x, y + 1)?];
+        let h11 = self.heights[self.loaded_index(x + 1, y + 1)?];
+
+        let offset = position - cell;
+
+        let gradient = Vec2::new(
+            (h10 - h00) * (1.0 - offset.y) + (h11 - h01) * offset.y,
+            (h01 - h00) * (1.0 - offset.x) + (h11 - h10) * offset.x,
+        );
+
+        let height = h00 * (1.0 - offset.x) * (1.0 - offset.y)
+            + h10 * offset.x * (1.0 - offset.y)
+            + h01 * (1.0 - offset.x) * offset.y
+            + h11 * offset.x * offset.y;
+
+        Some((height, gradient))
+    }
+
+    // adds `amount` to the four texels around `position`, scaled by the mask. Returns what was actually added.
+    fn deposit_bilinear(&mut self, position: Vec2, amount: f32) -> f32 {
+        let cell = position.floor();
+        let (x, y) = (cell.x as i32, cell.y as i32);
+        let offset = position - cell;
+
+        let corners = [
+            (x, y, (1.0 - offset.x) * (1.0 - offset.y)),
+            (x + 1, y, offset.x * (1.0 - offset.y)),
+            (x, y + 1, (1.0 - offset.x) * offset.y),
+            (x + 1, y + 1, offset.x * offset.y),
+        ];
+
+        let mut deposited = 0.0;
+        for (cx, cy, weight) in corners {
+            if let Some(index) = self.index(cx, cy) {
+                let added = amount * weight * self.mask[index];
+                self.heights[index] += added;
+                deposited += added;
+            }
+        }
+        deposited
+    }
+}
+
+// precomputed erosion footprint, weights sum to 1
+fn erosion_brush(radius: u32) -> Vec<(IVec2, f32)> {
+    let radius = radius.max(1) as i32;
+
+    let mut offsets = Vec::new();
+    for y in -radius..=radius {
+        for x in -radius..=radius {
+            let distance = ((x * x + y * y) as f32).sqrt();
+            if distance < radius as f32 {
+                offsets.push((IVec2::new(x, y), 1.0 - distance / radius as f32));
+            }
+        }
+    }
+
+    let total: f32 = offsets.iter().map(|(_, weight)| weight).sum();
+    offsets.iter_mut().for_each(|(_, weight)| *weight /= total);
+    offsets
+}
+
+/// Simulates `droplet_count` droplets spawned at random texels where `mask > 0`.
+pub fn hydraulic_erosion<R: Rng>(
+    field: &mut HeightField,
+    settings: &HydraulicErosionSettings,
+    droplet_count: u32,
+    rng: &mut R,
+    mut maps: Option<&mut ErosionMaps>,
+) {
+    if field.size.x < 2 || field.size.y < 2 {
+        return;
+    }
+
+    let brush = erosion_brush(settings.erosion_radius);
+    let spawn_max = (field.size - UVec2::ONE).as_vec2();
+
+    for _ in 0..droplet_count {
+        let mut position = Vec2::new(rng.gen_range(0.0..spawn_max.x), rng.gen_range(0.0..spawn_max.y));
+
+        let Some(spawn_index) = field.index(position.x as i32, position.y as i32) else {
+            continue;
+        };
+        if field.mask[spawn_index] <= 0.0 {
+            continue;
+        }
+
+        let mut direction = Vec2::ZERO;
+        let mut speed = settings.initial_speed;
+        let mut water = settings.initial_water;
+        let mut sediment = 0.0;
+
+        for _ in 0..settings.max_lifetime {
+            let Some((height, gradient)) = field.height_and_gradient(position) else {
+                break;
+            };
+
+            direction = direction * settings.inertia - gradient * (1.0 - settings.inertia);
+            if direction.length_squared() < f32::EPSILON {
+                break;
+            }
+            direction = direction.normalize();
+
+            let old_position = position;
+            position += direction;
+
+            let Some((new_height, _)) = field.height_and_gradient(position) else {
+                // leaving the field, keep the sediment at the last valid position
+                position = old_position;
+                break;
+            };
+
+            let delta_height = new_height - height;
+
+            if let Some(maps) = maps.as_deref_mut()
+                && let Some(index) = field.index(old_position.x as i32, old_position.y as i32)
+            {
+                maps.flow[index] += water;
+            }
+
+            let capacity = (-delta_height * speed * water * settings.sediment_capacity_factor)
+                .max(settings.min_sediment_capacity);
+
+            if sediment > capacity || delta_height > 0.0 {
+                // moving uphill fills the pit behind it, otherwise drop the excess
+                let amount = if delta_height > 0.0 {
+                    delta_height.min(sediment)
+                } else {
+                    (sediment - capacity) * settings.deposit_speed
+                };
+
+                let deposited = field.deposit_bilinear(old_position, amount);
+                sediment -= deposited;
+
+                if let Some(maps) = maps.as_deref_mut()
+                    && let Some(index) = field.index(old_position.x as i32, old_position.y as i32)
+                {
+                    maps.deposition[index] += deposited;
+                }
+            } else {
+                // never dig deeper than the height difference, that would carve spikes
+                let amount = ((capacity - sediment) * settings.erode_speed).min(-delta_height);
+                let center = old_position.floor().as_ivec2();
+
+                for (offset, weight) in brush.iter() {
+                    let texel = center + *offset;
+                    let Some(index) = field.index(texel.x, texel.y) else {
+                        continue;
+                    };
+
+                    let removed = (amount * weight * field.mask[index]).min(field.heights[index]);
+                    field.heights[index] -= removed;
+                    sediment += removed;
+                }
+            }
+
+            speed = (speed * speed - delta_height * settings.gravity).max(0.0).sqrt();
+            water *= 1.0 - settings.evaporate_speed;
+        }
+
+        // whatever the droplet still carries settles where it stopped, so no material is lost
+        if sediment > 0.0 {
+            field.deposit_bilinear(position, sediment);
+        }
+    }
+}
+
+/// Moves material from texels steeper than the talus angle to their lower neighbours.
+pub fn thermal_erosion(field: &mut HeightField, settings: &ThermalErosionSettings, iterations: u32) {
+    const NEIGHBOURS: [(i32, i32, f32); 8] = [
+        (-1, 0, 1.0),
+        (1, 0, 1.0),
+        (0, -1, 1.0),
+        (0, 1, 1.0),
+        (-1, -1, std::f32::consts::SQRT_2),
+        (1, -1, std::f32::consts::SQRT_2),
+        (-1, 1, std::f32::consts::SQRT_2),
+        (1, 1, std::f32::consts::SQRT_2),
+    ];
+
+    let talus = settings.talus_angle.to_radians().tan();
+    let strength = settings.strength.clamp(0.0, 1.0);
+
+    let mut deltas = vec![0.0; field.heights.len()];
+
+    for _ in 0..iterations {
+        deltas.iter_mut().for_each(|delta| *delta = 0.0);
+
+        for y in 0..field.size.y as i32 {
+            for x in 0..field.size.x as i32 {
+                let index = (y as u32 * field.size.x + x as u32) as usize;
+                if field.mask[index] <= 0.0 {
+                    continue;
+                }
+
+                let height = field.heights[index];
+
+                let mut excess_total = 0.0;
+                let mut max_excess: f32 = 0.0;
+                let mut lower = [(0usize, 0.0f32); 8];
+                let mut lower_count = 0;
+
+                for (dx, dy, distance) in NEIGHBOURS {
+                    let Some(neighbour) = field.index(x + dx, y + dy) else {
+                        continue;
+                    };
+                    if field.mask[neighbour] <= 0.0 {
+                        continue;
+                    }
+
+                    let excess = height - field.heights[neighbour] - talus * distance;
+                    if excess > 0.0 {
+                        lower[lower_count] = (neighbour, excess);
+                        lower_count += 1;
+
+                        excess_total += excess;
+                        max_excess = max_excess.max(excess);
+                    }
+                }
+
+                if lower_count == 0 {
+                    continue;
+                }
+
+                // move half of the largest excess so the slope settles instead of oscillating
+                let moved = max_excess * 0.5 * strength * field.mask[index];
+
+                deltas[index] -= moved;
+                for (neighbour, excess) in lower.iter().take(lower_count) {
+                    deltas[*neighbour] += moved * excess / excess_total;
+                }
+            }
+        }
+
+        for (height, delta) in field.heights.iter_mut().zip(deltas.iter()) {
+            *height += delta;
+        }
+    }
+}
+
+/// Splits field-shaped values into per chunk u8 maps, normalized against the largest value.
+/// Only chunks that were loaded into the field get a map.
+fn split_map_into_chunks(
+    values: &[f32],
+    field: &HeightField,
+    terrain_config: &TerrainConfig,
+    curve: fn(f32) -> f32,
+) -> HashMap<u32, Vec<Vec<u8>>> {
+    let chunk_dimensions = chunk_dimensions_of(terrain_config);
+    let max_value = values.iter().copied().fold(0.0f32, f32::max).max(f32::EPSILON);
+
+    let mut chunk_maps: HashMap<u32, Vec<Vec<u8>>> = HashMap::new();
+
+    for y in 0..field.size.y {
+        for x in 0..field.size.x {
+            let index = (y * field.size.x + x) as usize;
+            if !field.loaded[index] {
+                continue;
+            }
+
+            let (chunk_id, local) =
+                world_texel_to_chunk(field.origin + UVec2::new(x, y), terrain_config.chunk_rows, chunk_dimensions);
+
+            let chunk_map = chunk_maps.entry(chunk_id).or_insert_with(|| {
+                vec![vec![0; chunk_dimensions.x as usize]; chunk_dimensions.y as usize]
+            });
+
+            let value = curve((values[index] / max_value).clamp(0.0, 1.0));
+            chunk_map[local.y as usize][local.x as usize] = (value * 255.0).round() as u8;
+        }
+    }
+
+    chunk_maps
+}
+
+/// Erodes every loaded chunk as one field. Returns the ids of the chunks that changed.
+/// Streamed out chunks are left alone, the field only spans the rectangle of loaded chunks.
+pub fn erode_whole_terrain(
+    chunk_height_maps: &mut HashMap<u32, HeightMapU16>,
+    terrain_config: &TerrainConfig,
+    settings: &ErosionSettings,
+    erosion_maps: Option<&mut ChunkErosionMapsResource>,
+) -> HashSet<u32> {
+    let chunk_dimensions = chunk_dimensions_of(terrain_config);
+
+    let Some((min_chunk, max_chunk)) = chunk_height_maps
+        .keys()
+        .map(|chunk_id| {
+            let chunk_coords = ChunkCoords::from_chunk_id(*chunk_id, terrain_config.chunk_rows);
+            UVec2::new(chunk_coords.x(), chunk_coords.y())
+        })
+        .fold(None, |bounds: Option<(UVec2, UVec2)>, coords| {
+            Some(bounds.map_or((coords, coords), |(min, max)| (min.min(coords), max.max(coords))))
+        })
+    else {
+        return HashSet::new();
+    };
+
+    let origin = min_chunk * chunk_dimensions;
+    let size = (max_chunk - min_chunk + UVec2::ONE) * chunk_dimensions;
+
+    let mut field = HeightField::from_chunk_height_maps(chunk_height_maps, origin, size, terrain_config);
+
+    let mut rng = StdRng::seed_from_u64(settings.seed);
+    let mut maps = settings.output_maps.then(|| ErosionMaps::new(field.size));
+
+    hydraulic_erosion(
+        &mut field,
+        &settings.hydraulic,
+        settings.hydraulic.whole_terrain_droplets,
+        &mut rng,
+        maps.as_mut(),
+    );
+    thermal_erosion(&mut field, &settings.thermal, settings.thermal.whole_terrain_iterations);
+
+    if let (Some(maps), Some(erosion_maps)) = (maps, erosion_maps) {
+        // flow spans orders of magnitude, the square root keeps small streams visible
+        let flow_maps = split_map_into_chunks(&maps.flow, &field, terrain_config, f32::sqrt);
+        let deposition_maps = split_map_into_chunks(&maps.deposition, &field, terrain_config, |v| v);
+
+        let folder = settings.output_maps_folder.clone().unwrap_or_else(|| {
+            PathBuf::from("assets").join(&terrain_config.splat_folder_path).join("erosion")
+        });
+
+        // maps of unloaded chunks, in memory and on disk, stay as an earlier pass left them
+        save_chunk_mask_files(&flow_maps, &folder.join("flow"), "erosion map");
+        save_chunk_mask_files(&deposition_maps, &folder.join("deposition"), "erosion map");
+
+        erosion_maps.flow_maps.extend(flow_maps);
+        erosion_maps.deposition_maps.extend(deposition_maps);
+    }
+
+    field.write_to_chunk_height_maps(chunk_height_maps, terrain_config)
+}
+
+fn mark_chunks_for_reload(changed_chunks: &HashSet<u32>, chunk_query: &mut Query<(&Chunk, &mut ChunkData)>) {
+    for (chunk, mut chunk_data) in chunk_query.iter_mut() {
+        if changed_chunks.contains(&chunk.chunk_id) {
+            chunk_data.height_map_image_data_load_status = TerrainImageDataLoadStatus::NeedsReload;
+        }
+    }
+}
+
+pub fn apply_erosion_brush_edits(
+    mut ev_reader: MessageReader<EditTerrainEvent>,
+
+    mut chunk_height_maps: ResMut<ChunkHeightMapResource>,
+    mut chunk_query: Query<(&Chunk, &mut ChunkData)>,
+    chunk_transform_query: Query<(&Chunk, &GlobalTransform)>,
+
+    terrain_query: Query<&TerrainConfig>,
+    settings: Res<ErosionSettings>,
+) {
+    for ev in ev_reader.read() {
+        if ev.brush_type != BrushType::Erode {
+            continue;
+        }
+        if !matches!(ev.tool, EditingTool::SetHeightMap { .. }) {
+            continue;
+        }
+
+        let Some(terrain_config) = terrain_query.iter().next() else {
+            continue;
+        };
+
+        let chunk_dimensions = chunk_dimensions_of(terrain_config);
+
+        // the brush coordinates are in world space, the field is in terrain texels
+        let Some(terrain_origin) = chunk_transform_query.iter().next().map(|(chunk, transform)| {
+            let chunk_coords = ChunkCoords::from_chunk_id(chunk.chunk_id, terrain_config.chunk_rows);
+            let translation = transform.translation();
+
+            Vec2::new(translation.x, translation.z)
+                - Vec2::new(chunk_coords.x() as f32, chunk_coords.y() as f32) * chunk_dimensions.as_vec2()
+        }) else {
+            continue;
+        };
+
+        let brush_center = ev.coordinates - terrain_origin;
+        let terrain_size = (chunk_dimensions * terrain_config.chunk_rows).as_vec2();
+
+        let min = (brush_center - Vec2::splat(ev.radius)).floor().clamp(Vec2::ZERO, terrain_size);
+        let max = (brush_center + Vec2::splat(ev.radius)).ceil().clamp(Vec2::ZERO, terrain_size);
+
+        let origin = min.as_uvec2();
+        let size = (max - min).as_uvec2();
+        if size.x < 2 || size.y < 2 {
+            continue;
+        }
+
+        let mut field = HeightField::from_chunk_height_maps(
+            &chunk_height_maps.chunk_height_maps,
+            origin,
+            size,
+            terrain_config,
+        );
+
+        // the brush falloff decides how strongly each texel may change
+        for y in 0..size.y {
+            for x in 0..size.x {
+                let index = (y * size.x + x) as usize;
+                let texel_center = (origin + UVec2::new(x, y)).as_vec2();
+
+                field.mask[index] *= ev.brush_shape.weight(texel_center - brush_center, ev.radius, ev.brush_hardness);
+            }
+        }
+
+        let brush_area = std::f32::consts::PI * ev.radius * ev.radius;
+        let droplet_count = (brush_area * settings.hydraulic.brush_droplets_per_texel).ceil() as u32;
+
+        hydraulic_erosion(&mut field, &settings.hydraulic, droplet_count, &mut rand::thread_rng(), None);
+        thermal_erosion(&mut field, &settings.thermal, settings.thermal.brush_iterations);
+
+        let changed_chunks =
+            field.write_to_chunk_height_maps(&mut chunk_height_maps.chunk_height_maps, terrain_config);
+
+        mark_chunks_for_reload(&changed_chunks, &mut chunk_query);
+    }
+}
+
+pub fn apply_erosion_command_events(
+    mut ev_reader: MessageReader<TerrainCommandEvent>,
+    mut replaced_writer: MessageWriter<TerrainDataReplacedEvent>,
+
+    mut chunk_height_maps: ResMut<ChunkHeightMapResource>,
+    mut chunk_query: Query<(&Chunk, &mut ChunkData)>,
+
+    mut erosion_maps: ResMut<ChunkErosionMapsResource>,
+
+    terrain_query: Query<&TerrainConfig>,
+    settings: Res<ErosionSettings>,
+) {
+    for ev in ev_reader.read() {
+        let TerrainCommandEvent::ErodeTerrain = ev else {
+            continue;
+        };
+
+        let Some(terrain_config) = terrain_query.iter().next() else {
+            warn!("terrain erosion needs a terrain");
+            continue;
+        };
+
+        info!("eroding terrain ..");
+
+        let changed_chunks = erode_whole_terrain(
+            &mut chunk_height_maps.chunk_height_maps,
+            terrain_config,
+            &settings,
+            Some(&mut erosion_maps),
+        );
+
+        mark_chunks_for_reload(&changed_chunks, &mut chunk_query);
+
+        // brush erosion is recorded as a paint stroke, a whole terrain pass is too large for that
+        if !changed_chunks.is_empty() {
+            replaced_writer.write(TerrainDataReplacedEvent);
+        }
+
+        info!("eroded {} chunks", changed_chunks.len());
+    }
+}
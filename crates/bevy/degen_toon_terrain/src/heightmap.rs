@@ -14,6 +14,12 @@ https://github.com/norman784/gaiku/blob/master/crates/gaiku_baker_heightmap/src/
 pub enum HeightMapError {
     #[error("failed to load the image")]
     LoadingError,
+
+    #[error("failed to write the image: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("failed to encode the image: {0}")]
+    Encoding(#[from] png::EncodingError),
 }
 
 pub type HeightMapU16 = Vec<Vec<u16>>;
@@ -24,7 +30,7 @@ pub type HeightMapU16 = Vec<Vec<u16>>;
 
 pub trait HeightMap {
     fn load_from_image(image: &Image) -> Result<Box<Self>, HeightMapError>;
-    fn save_heightmap_to_image<P>(&self,  save_file_path: P ) where  P:AsRef<Path>;
+    fn save_heightmap_to_image<P>(&self,  save_file_path: P ) -> Result<(), HeightMapError> where  P:AsRef<Path>;
 
       fn append_x_row(&mut self, row: Vec<u16>);
     fn append_y_col(&mut self, col: Vec<u16>);
@@ -86,7 +92,7 @@ impl HeightMap for HeightMapU16 {
     }
 
 
-    fn save_heightmap_to_image<P>(&self,  save_file_path: P ) where  P: AsRef<Path>,
+    fn save_heightmap_to_image<P>(&self,  save_file_path: P ) -> Result<(), HeightMapError> where  P: AsRef<Path>,
     { 
   
        let chunk_height_data = self ;
@@ -96,14 +102,14 @@ impl HeightMap for HeightMapU16 {
         let width = chunk_height_data.first().map_or(0, |row| row.len());
 
         // Prepare the file and writer
-        let file = File::create(save_file_path).expect("Failed to create file");
-        let ref mut w = BufWriter::new(file);
+        let file = File::create(save_file_path)?;
+        let w = &mut BufWriter::new(file);
 
         // Set up the encoder. Since PNG is the format that supports 16-bit grayscale natively, we use it here.
         let mut encoder = png::Encoder::new(w, width as u32, height as u32); // Width and height of image
         encoder.set_color(png::ColorType::Grayscale);
         encoder.set_depth(png::BitDepth::Sixteen);
-        let mut writer = encoder.write_header().expect("Failed to write PNG header");
+        let mut writer = encoder.write_header()?;
 
         // Flatten the Vec<Vec<u16>> to a Vec<u8> for the PNG encoder
         let mut buffer: Vec<u8> = Vec::with_capacity(width * height * 2);
@@ -114,9 +120,9 @@ impl HeightMap for HeightMapU16 {
         }
 
         // Write the image data
-        writer
-            .write_image_data(&buffer)
-            .expect("Failed to write PNG data");
+        writer.write_image_data(&buffer)?;
+
+        Ok(())
 
     }
 
@@ -0,0 +1,499 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use bevy::platform::collections::hash_map::HashMap;
+use bevy::prelude::*;
+
+use thiserror::Error;
+
+use crate::chunk::{ChunkCoordinates, ChunkCoords, ChunkData, ChunkHeightMapResource};
+use crate::edit::{TerrainCommandEvent, TerrainDataReplacedEvent};
+use crate::heightmap::{HeightMap, HeightMapError, HeightMapU16};
+use crate::terrain::TerrainImageDataLoadStatus;
+use crate::terrain_config::TerrainConfig;
+
+/*
+
+Import and export of whole-terrain heightmaps (World Machine, Gaea, ...).
+
+The terrain keeps one heightmap per chunk, each chunk_dimensions in size. A whole-terrain
+heightmap is terrain_dimensions in size, so splitting it into blocks gives chunks whose
+edges match, since the mesh stitching reads the first row / column of the neighbouring chunk.
+
+Supported files:
+  .png        16-bit (or 8-bit) grayscale
+  .raw / .r16 headerless little-endian u16, square
+
+*/
+
+#[derive(Error, Debug)]
+pub enum HeightMapIoError {
+    #[error("failed to read heightmap file: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("failed to decode heightmap image: {0}")]
+    Image(#[from] image::ImageError),
+
+    #[error("failed to write heightmap image: {0}")]
+    HeightMap(#[from] HeightMapError),
+
+    #[error("unsupported heightmap file extension: {0:?}")]
+    UnsupportedFormat(PathBuf),
+
+    #[error("raw heightmap of {0} bytes is not a square 16-bit image")]
+    InvalidRawSize(usize),
+
+    #[error("heightmap is empty")]
+    Empty,
+}
+
+enum HeightMapFileFormat {
+    Png,
+    Raw,
+}
+
+impl HeightMapFileFormat {
+    fn from_path(path: &Path) -> Result<Self, HeightMapIoError> {
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_ascii_lowercase());
+
+        match extension.as_deref() {
+            Some("png") => Ok(Self::Png),
+            Some("raw") | Some("r16") => Ok(Self::Raw),
+            _ => Err(HeightMapIoError::UnsupportedFormat(path.to_path_buf())),
+        }
+    }
+}
+
+pub fn heightmap_io_plugin(app: &mut App) {
+    app.add_systems(Update, apply_heightmap_io_events);
+}
+
+fn heightmap_size(height_map: &HeightMapU16) -> UVec2 {
+    let height = height_map.len() as u32;
+    let width = height_map.first().map_or(0, |row| row.len()) as u32;
+    UVec2::new(width, height)
+}
+
+pub fn load_heightmap_file<P: AsRef<Path>>(path: P) -> Result<HeightMapU16, HeightMapIoError> {
+    let path = path.as_ref();
+
+    match HeightMapFileFormat::from_path(path)? {
+        HeightMapFileFormat::Png => {
+            // 8-bit images are expanded to the full u16 range
+            let image = image::open(path)?.into_luma16();
+            let (width, height) = image.dimensions();
+
+            let height_map: HeightMapU16 = image
+                .as_raw()
+                .chunks_exact(width as usize)
+                .take(height as usize)
+                .map(|row| row.to_vec())
+                .collect();
+
+            if height_map.is_empty() {
+                return Err(HeightMapIoError::Empty);
+            }
+
+            Ok(height_map)
+        }
+
+        HeightMapFileFormat::Raw => {
+            let bytes = fs::read(path)?;
+
+            let texel_count = bytes.len() / 2;
+            let side = (texel_count as f64).sqrt() as usize;
+
+            if side == 0 || side * side * 2 != bytes.len() {
+                return Err(HeightMapIoError::InvalidRawSize(bytes.len()));
+            }
+
+            Ok(load_raw_heightmap(&bytes, side, side))
+        }
+    }
+}
+
+// raw data is little-endian u16, row by row
+pub fn load_raw_heightmap(bytes: &[u8], width: usize, height: usize) -> HeightMapU16 {
+    let mut texels = bytes
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]));
+
+    (0..height)
+        .map(|_| (0..width).map(|_| texels.next().unwrap_or(0)).collect())
+        .collect()
+}
+
+pub fn save_heightmap_file<P: AsRef<Path>>(
+    height_map: &HeightMapU16,
+    path: P,
+) -> Result<(), HeightMapIoError> {
+    let path = path.as_ref();
+    let format = HeightMapFileFormat::from_path(path)?;
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    match format {
+        HeightMapFileFormat::Png => height_map.save_heightmap_to_image(path)?,
+
+        HeightMapFileFormat::Raw => {
+            let bytes: Vec<u8> = height_map
+                .iter()
+                .flatten()
+                .flat_map(|value| value.to_le_bytes())
+                .collect();
+
+            fs::write(path, bytes)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Bilinear resample, with the corner texels of source and target aligned.
+pub fn resample_heightmap(source: &HeightMapU16, target_size: UVec2) -> HeightMapU16 {
+    let source_size = heightmap_size(source);
+
+    if source_size == target_size {
+        return source.clone();
+    }
+
+    if source_size.x == 0 || source_size.y == 0 {
+        return vec![vec![0; target_size.x as usize]; target_size.y as usize];
+    }
+
+    let scale = |source_len: u32, target_len: u32| {
+        if target_len > 1 {
+            (source_len - 1) as f32 / (target_len - 1) as f32
+        } else {
+            0.0
+        }
+    };
+    let scale_x = scale(source_size.x, target_size.x);
+    let scale_y = scale(source_size.y, target_size.y);
+
+    let max_x = source_size.x as usize - 1;
+    let max_y = source_size.y as usize - 1;
+
+    (0..target_size.y)
+        .map(|y| {
+            let source_y = y as f32 * scale_y;
+            let y0 = (source_y.floor() as usize).min(max_y);
+            let y1 = (y0 + 1).min(max_y);
+            let fy = source_y - y0 as f32;
+
+            (0..target_size.x)
+                .map(|x| {
+                    let source_x = x as f32 * scale_x;
+                    let x0 = (source_x.floor() as usize).min(max_x);
+                    let x1 = (x0 + 1).min(max_x);
+                    let fx = source_x - x0 as f32;
+
+                    let top = source[y0][x0] as f32 * (1.0 - fx) + source[y0][x1] as f32 * fx;
+                    let bottom = source[y1][x0] as f32 * (1.0 - fx) + source[y1][x1] as f32 * fx;
+
+                    (top * (1.0 - fy) + bottom * fy).round().clamp(0.0, u16::MAX as f32) as u16
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Splits a whole-terrain heightmap into per-chunk heightmaps keyed by chunk id.
+pub fn split_heightmap_into_chunks(
+    height_map: &HeightMapU16,
+    terrain_config: &TerrainConfig,
+) -> HashMap<u32, HeightMapU16> {
+    let chunk_rows = terrain_config.chunk_rows;
+    let chunk_dimensions = terrain_config.get_chunk_dimensions().as_uvec2();
+
+    let terrain_size = chunk_dimensions * chunk_rows;
+    let height_map = resample_heightmap(height_map, terrain_size);
+
+    let mut chunk_height_maps = HashMap::new();
+
+    for chunk_y in 0..chunk_rows {
+        for chunk_x in 0..chunk_rows {
+            let origin = UVec2::new(chunk_x, chunk_y) * chunk_dimensions;
+
+            let chunk_height_map: HeightMapU16 = (0..chunk_dimensions.y)
+                .map(|y| {
+                    let row = &height_map[(origin.y + y) as usize];
+                    row[origin.x as usize..(origin.x + chunk_dimensions.x) as usize].to_vec()
+                })
+                .collect();
+
+            let chunk_id = ChunkCoords::new(chunk_x, chunk_y).get_chunk_index(chunk_rows);
+            chunk_height_maps.insert(chunk_id, chunk_height_map);
+        }
+    }
+
+    chunk_height_maps
+}
+
+/// Joins per-chunk heightmaps back into one whole-terrain heightmap. Missing chunks are left flat.
+pub fn stitch_chunk_heightmaps(
+    chunk_height_maps: &HashMap<u32, HeightMapU16>,
+    terrain_config: &TerrainConfig,
+) -> HeightMapU16 {
+    let chunk_rows = terrain_config.chunk_rows;
+    let chunk_dimensions = terrain_config.get_chunk_dimensions().as_uvec2();
+    let terrain_size = chunk_dimensions * chunk_rows;
+
+    let mut height_map = vec![vec![0u16; terrain_size.x as usize]; terrain_size.y as usize];
+
+    for (chunk_id, chunk_height_map) in chunk_height_maps.iter() {
+        let chunk_coords = ChunkCoords::from_chunk_id(*chunk_id, chunk_rows);
+        let origin = UVec2::new(chunk_coords.x(), chunk_coords.y()) * chunk_dimensions;
+
+        // tolerate chunk images that were saved at a different resolution
+        let chunk_height_map = resample_heightmap(chunk_height_map, chunk_dimensions);
+
+        for (y, row) in chunk_height_map.iter().enumerate() {
+            let target_row = &mut height_map[origin.y as usize + y];
+            let start = origin.x as usize;
+            target_row[start..start + row.len()].copy_from_slice(row);
+        }
+    }
+
+    height_map
+}
+
+/// Headless import: reads a whole-terrain heightmap and writes one chunk file per chunk into `height_folder`.
+pub fn import_heightmap_to_chunk_files<P: AsRef<Path>, F: AsRef<Path>>(
+    source_path: P,
+    terrain_config: &TerrainConfig,
+    height_folder: F,
+) -> Result<HashMap<u32, HeightMapU16>, HeightMapIoError> {
+    let height_map = load_heightmap_file(source_path)?;
+    let chunk_height_maps = split_heightmap_into_chunks(&height_map, terrain_config);
+
+    let height_folder = height_folder.as_ref();
+    fs::create_dir_all(height_folder)?;
+
+    for (chunk_id, chunk_height_map) in chunk_height_maps.iter() {
+        chunk_height_map.save_heightmap_to_image(height_folder.join(format!("{}.png", chunk_id)))?;
+    }
+
+    Ok(chunk_height_maps)
+}
+
+/// Headless export: stitches the chunk files in `height_folder` into one heightmap file.
+pub fn export_chunk_files_to_heightmap<F: AsRef<Path>, P: AsRef<Path>>(
+    height_folder: F,
+    terrain_config: &TerrainConfig,
+    target_path: P,
+) -> Result<(), HeightMapIoError> {
+    let chunk_height_maps = load_chunk_heightmap_files(height_folder, terrain_config);
+    let height_map = stitch_chunk_heightmaps(&chunk_height_maps, terrain_config);
+
+    save_heightmap_file(&height_map, target_path)
+}
+
+pub fn load_chunk_heightmap_files<F: AsRef<Path>>(
+    height_folder: F,
+    terrain_config: &TerrainConfig,
+) -> HashMap<u32, HeightMapU16> {
+    let chunk_count = terrain_config.chunk_rows * terrain_config.chunk_rows;
+
+    (0..chunk_count)
+        .filter_map(|chunk_id| {
+            let path = height_folder.as_ref().join(format!("{}.png", chunk_id));
+
+            load_heightmap_file(&path)
+                .map_err(|e| warn!("could not load chunk heightmap {:?}: {}", path, e))
+                .ok()
+                .map(|height_map| (chunk_id, height_map))
+        })
+        .collect()
+}
+
+pub fn apply_heightmap_io_events(
+    mut ev_reader: MessageReader<TerrainCommandEvent>,
+    mut replaced_writer: MessageWriter<TerrainDataReplacedEvent>,
+
+    mut chunk_height_maps: ResMut<ChunkHeightMapResource>,
+    mut chunk_data_query: Query<&mut ChunkData>,
+
+    terrain_query: Query<&TerrainConfig>,
+) {
+    for ev in ev_reader.read() {
+        let Some(terrain_config) = terrain_query.iter().next() else {
+            warn!("heightmap import/export needs a terrain");
+            continue;
+        };
+
+        let height_folder = PathBuf::from("assets").join(&terrain_config.height_folder_path);
+
+        match ev {
+            TerrainCommandEvent::ImportHeightmap(source_path) => {
+                match import_heightmap_to_chunk_files(source_path, terrain_config, &height_folder) {
+                    Ok(imported) => {
+                        chunk_height_maps.chunk_height_maps.extend(imported);
+
+                        for mut chunk_data in chunk_data_query.iter_mut() {
+                            chunk_data.height_map_image_data_load_status =
+                                TerrainImageDataLoadStatus::NeedsReload;
+                        }
+
+                        // paint history recorded against the old heights can not be replayed anymore
+                        replaced_writer.write(TerrainDataReplacedEvent);
+
+                        info!("imported heightmap {:?}", source_path);
+                    }
+                    Err(e) => error!("could not import heightmap {:?}: {}", source_path, e),
+                }
+            }
+
+            TerrainCommandEvent::ExportHeightmap(target_path) => {
+                // chunks that are not loaded right now are read from disk
+                let mut all_chunk_height_maps =
+                    load_chunk_heightmap_files(&height_folder, terrain_config);
+                all_chunk_height_maps.extend(
+                    chunk_height_maps
+                        .chunk_height_maps
+                        .iter()
+                        .map(|(chunk_id, height_map)| (*chunk_id, height_map.clone())),
+                );
+
+                let height_map = stitch_chunk_heightmaps(&all_chunk_height_maps, terrain_config);
+
+                match save_heightmap_file(&height_map, target_path) {
+                    Ok(()) => info!("exported heightmap {:?}", target_path),
+                    Err(e) => error!("could not export heightmap {:?}: {}", target_path, e),
+                }
+            }
+
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gradient(width: usize, height: usize) -> HeightMapU16 {
+        (0..height)
+            .map(|y| (0..width).map(|x| (y * 1000 + x) as u16).collect())
+            .collect()
+    }
+
+    fn config(terrain_size: f32, chunk_rows: u32) -> TerrainConfig {
+        TerrainConfig {
+            terrain_dimensions: Vec2::splat(terrain_size),
+            chunk_rows,
+            ..default()
+        }
+    }
+
+    #[test]
+    fn test_resample_keeps_same_size() {
+        let source = gradient(5, 3);
+        assert_eq!(resample_heightmap(&source, UVec2::new(5, 3)), source);
+    }
+
+    #[test]
+    fn test_resample_aligns_corners_on_odd_sizes() {
+        let source = gradient(5, 3);
+        let resampled = resample_heightmap(&source, UVec2::new(7, 4));
+
+        assert_eq!(heightmap_size(&resampled), UVec2::new(7, 4));
+        assert_eq!(resampled[0][0], source[0][0]);
+        assert_eq!(resampled[0][6], source[0][4]);
+        assert_eq!(resampled[3][0], source[2][0]);
+        assert_eq!(resampled[3][6], source[2][4]);
+    }
+
+    #[test]
+    fn test_resample_interpolates_between_texels() {
+        let source = vec![vec![0, 100], vec![200, 300]];
+        let resampled = resample_heightmap(&source, UVec2::new(3, 3));
+
+        assert_eq!(resampled[0], vec![0, 50, 100]);
+        assert_eq!(resampled[1], vec![100, 150, 200]);
+        assert_eq!(resampled[2], vec![200, 250, 300]);
+    }
+
+    #[test]
+    fn test_resample_degenerate_sizes() {
+        let source = gradient(4, 4);
+
+        assert_eq!(resample_heightmap(&source, UVec2::new(1, 1)), vec![vec![source[0][0]]]);
+        assert_eq!(
+            resample_heightmap(&Vec::new(), UVec2::new(2, 3)),
+            vec![vec![0; 2]; 3]
+        );
+    }
+
+    #[test]
+    fn test_split_gives_every_chunk_its_own_rows() {
+        let terrain_config = config(8.0, 2);
+        let source = gradient(8, 8);
+
+        let chunks = split_heightmap_into_chunks(&source, &terrain_config);
+        assert_eq!(chunks.len(), 4);
+
+        for (chunk_id, chunk_height_map) in chunks.iter() {
+            let coords = ChunkCoords::from_chunk_id(*chunk_id, 2);
+            let origin = UVec2::new(coords.x(), coords.y()) * 4;
+
+            assert_eq!(heightmap_size(chunk_height_map), UVec2::new(4, 4));
+            assert_eq!(
+                chunk_height_map[0][0],
+                source[origin.y as usize][origin.x as usize]
+            );
+        }
+
+        // the stitching reads the neighbour's first column, which has to continue the source
+        let chunk_00 = &chunks[&ChunkCoords::new(0, 0).get_chunk_index(2)];
+        let chunk_10 = &chunks[&ChunkCoords::new(1, 0).get_chunk_index(2)];
+        for y in 0..4 {
+            assert_eq!(chunk_00[y][3] + 1, chunk_10[y][0]);
+        }
+    }
+
+    #[test]
+    fn test_split_resamples_odd_source_sizes() {
+        let terrain_config = config(8.0, 2);
+        let source = gradient(13, 9);
+
+        let chunks = split_heightmap_into_chunks(&source, &terrain_config);
+
+        let last_chunk = &chunks[&ChunkCoords::new(1, 1).get_chunk_index(2)];
+        assert_eq!(chunks[&0][0][0], source[0][0]);
+        assert_eq!(last_chunk[3][3], source[8][12]);
+    }
+
+    #[test]
+    fn test_stitch_reverses_split() {
+        let terrain_config = config(12.0, 3);
+        let source = gradient(12, 12);
+
+        let chunks = split_heightmap_into_chunks(&source, &terrain_config);
+        assert_eq!(stitch_chunk_heightmaps(&chunks, &terrain_config), source);
+    }
+
+    #[test]
+    fn test_stitch_leaves_missing_chunks_flat() {
+        let terrain_config = config(8.0, 2);
+        let source = gradient(8, 8);
+
+        let mut chunks = split_heightmap_into_chunks(&source, &terrain_config);
+        chunks.remove(&ChunkCoords::new(1, 1).get_chunk_index(2));
+
+        let stitched = stitch_chunk_heightmaps(&chunks, &terrain_config);
+        assert_eq!(stitched[7][7], 0);
+        assert_eq!(stitched[3][7], source[3][7]);
+    }
+
+    #[test]
+    fn test_load_raw_heightmap_is_little_endian() {
+        let bytes = [0x01, 0x00, 0x00, 0x01, 0xff, 0xff];
+        assert_eq!(load_raw_heightmap(&bytes, 2, 2), vec![vec![1, 256], vec![65535, 0]]);
+    }
+}
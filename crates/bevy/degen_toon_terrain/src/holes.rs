@@ -0,0 +1,270 @@
+use std::path::PathBuf;
+
+use bevy::platform::collections::hash_map::HashMap;
+use bevy::platform::collections::HashSet;
+use bevy::prelude::*;
+use bevy::tasks::{IoTaskPool, Task};
+use futures_lite::future;
+
+use crate::chunk::{Chunk, ChunkData};
+use crate::chunk_mask::{
+    chunk_mask_size, load_chunk_mask_file, resize_chunk_mask, save_chunk_mask_files, ChunkMaskU8,
+};
+use crate::edit::{BrushType, EditTerrainEvent, EditingTool, TerrainCommandEvent};
+use crate::erosion::chunk_dimensions_of;
+use crate::terrain::TerrainImageDataLoadStatus;
+use crate::terrain_config::TerrainConfig;
+
+/*
+
+Terrain holes for caves, tunnels and basements.
+
+Every chunk can have a hole mask at heightmap resolution. A non zero texel cuts out the
+heightmap cell that starts at it, so `PreMesh` leaves a gap there in the render mesh and
+in the collider meshes that are built from it.
+
+Masks are painted with the `SetHoleMask` tool (SetExact cuts, ClearAll fills) and saved
+next to the splat maps in {splat_folder}/holes/{chunk_id}.png.
+
+*/
+
+pub type HoleMaskU8 = ChunkMaskU8;
+
+pub fn holes_plugin(app: &mut App) {
+    app.init_resource::<ChunkHoleMaskResource>().add_systems(
+        Update,
+        (
+            (load_chunk_hole_masks, finish_hole_mask_load_tasks)
+                .chain()
+                .before(crate::chunk::build_chunk_meshes),
+            apply_hole_brush_edits.after(crate::edit::apply_tool_edits),
+            save_chunk_hole_masks,
+        ),
+    );
+}
+
+/// Per chunk hole masks, non zero means the cell is cut out of the terrain.
+#[derive(Resource, Default)]
+pub struct ChunkHoleMaskResource {
+    pub hole_masks: HashMap<u32, HoleMaskU8>,
+
+    // chunks whose mask file was read, or found missing
+    checked_chunks: HashSet<u32>,
+}
+
+/// Reads the hole mask file of a chunk off the main thread.
+#[derive(Component)]
+pub(crate) struct HoleMaskLoadTask(Task<Option<HoleMaskU8>>);
+
+impl ChunkHoleMaskResource {
+    pub fn is_hole(&self, chunk_id: u32, x: u32, y: u32) -> bool {
+        self.hole_masks
+            .get(&chunk_id)
+            .is_some_and(|mask| is_hole(mask, x as usize, y as usize))
+    }
+
+    /// Meshes of a chunk are not built before its mask file was looked for.
+    pub fn is_mask_checked(&self, chunk_id: u32) -> bool {
+        self.checked_chunks.contains(&chunk_id)
+    }
+
+    // the mask file is looked for again when the chunk streams back in
+    pub(crate) fn unload_chunk(&mut self, chunk_id: u32) {
+        self.hole_masks.remove(&chunk_id);
+        self.checked_chunks.remove(&chunk_id);
+    }
+
+    /// The mask of a chunk at `dimensions`, created empty or resized when it does not match.
+    pub fn hole_mask_mut(&mut self, chunk_id: u32, dimensions: UVec2) -> &mut HoleMaskU8 {
+        let mask = self
+            .hole_masks
+            .entry(chunk_id)
+            .or_insert_with(|| vec![vec![0; dimensions.x as usize]; dimensions.y as usize]);
+
+        if chunk_mask_size(mask) != dimensions {
+            *mask = resize_chunk_mask(mask, dimensions);
+        }
+
+        mask
+    }
+}
+
+/// How much of a square of heightmap cells is cut out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HoleCoverage {
+    None,
+    Partial,
+    Full,
+}
+
+fn is_hole(hole_mask: &HoleMaskU8, x: usize, y: usize) -> bool {
+    hole_mask
+        .get(y)
+        .and_then(|row| row.get(x))
+        .is_some_and(|value| *value > 0)
+}
+
+/// Coverage of the `size` x `size` cells starting at (x, z). Cells outside of the mask are solid.
+pub fn tile_hole_coverage(hole_mask: &HoleMaskU8, x: usize, z: usize, size: usize) -> HoleCoverage {
+    let size = size.max(1);
+    let mut holes = 0;
+
+    for cell_z in z..z + size {
+        for cell_x in x..x + size {
+            if is_hole(hole_mask, cell_x, cell_z) {
+                holes += 1;
+            }
+        }
+    }
+
+    match holes {
+        0 => HoleCoverage::None,
+        holes if holes == size * size => HoleCoverage::Full,
+        _ => HoleCoverage::Partial,
+    }
+}
+
+fn hole_mask_folder(terrain_config: &TerrainConfig) -> PathBuf {
+    PathBuf::from("assets").join(&terrain_config.splat_folder_path).join("holes")
+}
+
+// masks have to be there before the first mesh of a chunk is built
+fn load_chunk_hole_masks(
+    mut commands: Commands,
+
+    chunk_query: Query<(Entity, &Chunk), (With<ChunkData>, Without<HoleMaskLoadTask>)>,
+    terrain_query: Query<&TerrainConfig>,
+
+    hole_masks: Res<ChunkHoleMaskResource>,
+) {
+    let Some(terrain_config) = terrain_query.iter().next() else {
+        return;
+    };
+
+    let chunk_dimensions = chunk_dimensions_of(terrain_config);
+
+    for (chunk_entity, chunk) in chunk_query.iter() {
+        if hole_masks.is_mask_checked(chunk.chunk_id) {
+            continue;
+        }
+
+        let path = hole_mask_folder(terrain_config).join(format!("{}.png", chunk.chunk_id));
+
+        // mask files saved at another chunk resolution are resized so brush edits stay in bounds
+        let task = IoTaskPool::get().spawn(async move {
+            load_chunk_mask_file(&path).map(|mask| resize_chunk_mask(&mask, chunk_dimensions))
+        });
+
+        commands.entity(chunk_entity).insert(HoleMaskLoadTask(task));
+    }
+}
+
+fn finish_hole_mask_load_tasks(
+    mut commands: Commands,
+
+    mut chunk_query: Query<(Entity, &Chunk, &mut HoleMaskLoadTask)>,
+
+    mut hole_masks: ResMut<ChunkHoleMaskResource>,
+) {
+    for (chunk_entity, chunk, mut task) in chunk_query.iter_mut() {
+        let Some(mask) = future::block_on(future::poll_once(&mut task.0)) else {
+            continue;
+        };
+
+        commands.entity(chunk_entity).remove::<HoleMaskLoadTask>();
+
+        hole_masks.checked_chunks.insert(chunk.chunk_id);
+
+        if let Some(mask) = mask {
+            hole_masks.hole_masks.insert(chunk.chunk_id, mask);
+        }
+    }
+}
+
+pub fn apply_hole_brush_edits(
+    mut ev_reader: MessageReader<EditTerrainEvent>,
+
+    mut chunk_query: Query<(&Chunk, &mut ChunkData, &GlobalTransform)>,
+    terrain_query: Query<&TerrainConfig>,
+
+    mut hole_masks: ResMut<ChunkHoleMaskResource>,
+) {
+    for ev in ev_reader.read() {
+        if !matches!(ev.tool, EditingTool::SetHoleMask) {
+            continue;
+        }
+
+        let value = match ev.brush_type {
+            BrushType::SetExact => 255,
+            BrushType::ClearAll => 0,
+            _ => continue,
+        };
+
+        let Some(terrain_config) = terrain_query.iter().next() else {
+            continue;
+        };
+
+        let chunk_dimensions = chunk_dimensions_of(terrain_config);
+
+        for (chunk, mut chunk_data, chunk_transform) in chunk_query.iter_mut() {
+            let translation = chunk_transform.translation();
+            let tool_coords_local = ev.coordinates - Vec2::new(translation.x, translation.z);
+
+            let local_min = (tool_coords_local - Vec2::splat(ev.radius)).floor();
+            let local_max = (tool_coords_local + Vec2::splat(ev.radius)).ceil();
+
+            if local_max.x < 0.0
+                || local_max.y < 0.0
+                || local_min.x >= chunk_dimensions.x as f32
+                || local_min.y >= chunk_dimensions.y as f32
+            {
+                continue;
+            }
+
+            let min = local_min.max(Vec2::ZERO).as_uvec2();
+            let max = local_max.as_uvec2().min(chunk_dimensions);
+
+            let mask = hole_masks.hole_mask_mut(chunk.chunk_id, chunk_dimensions);
+            let mut changed = false;
+
+            for y in min.y..max.y {
+                for x in min.x..max.x {
+                    // a cell is cut when its center is inside the brush
+                    let cell_center = Vec2::new(x as f32, y as f32) + 0.5;
+                    if !ev.brush_shape.contains(cell_center - tool_coords_local, ev.radius) {
+                        continue;
+                    }
+
+                    let texel = &mut mask[y as usize][x as usize];
+                    if *texel != value {
+                        *texel = value;
+                        changed = true;
+                    }
+                }
+            }
+
+            if changed {
+                chunk_data.height_map_image_data_load_status = TerrainImageDataLoadStatus::NeedsReload;
+            }
+        }
+    }
+}
+
+fn save_chunk_hole_masks(
+    mut ev_reader: MessageReader<TerrainCommandEvent>,
+
+    hole_masks: Res<ChunkHoleMaskResource>,
+    terrain_query: Query<&TerrainConfig>,
+) {
+    for ev in ev_reader.read() {
+        let TerrainCommandEvent::SaveAllChunks(_, true, _) = ev else {
+            continue;
+        };
+
+        let Some(terrain_config) = terrain_query.iter().next() else {
+            continue;
+        };
+
+        save_chunk_mask_files(&hole_masks.hole_masks, &hole_mask_folder(terrain_config), "terrain hole mask");
+    }
+}
@@ -1,4 +1,4 @@
- 
+#![allow(clippy::type_complexity, clippy::too_many_arguments)]
 
 use crate::terrain_material::STOCHASTIC_SAMPLING_SHADER_HANDLE;
 use bevy::asset::embedded_asset;
@@ -18,17 +18,21 @@ use std::time::Duration;
 use crate::terrain_material::TerrainMaterialExtension;
 use crate::terrain_material::{TERRAIN_SHADER_HANDLE,TOON_LIGHTING_SHADER_HANDLE,CUSTOM_PBR_FUNCTIONS_SHADER_HANDLE};
  
-use terrain_material::{  TerrainMaterial };
 
 use edit::{
     apply_command_events, apply_tool_edits, EditTerrainEvent, TerrainBrushEvent,
-    TerrainCommandEvent,
+    TerrainCommandEvent, TerrainDataReplacedEvent,
 };
 
+pub mod auto_splat;
 pub mod chunk;
+pub mod chunk_mask;
 //pub mod collision;
 pub mod edit;
+pub mod erosion;
 pub mod heightmap;
+pub mod heightmap_io;
+pub mod holes;
 pub mod pre_mesh;
 pub mod terrain;
 pub mod terrain_config;
@@ -37,6 +41,7 @@ pub mod terrain_material;
 pub mod tool_preview;
 
 pub mod splat;
+pub mod streaming;
 
 #[derive(Default, Hash,Eq,PartialEq,States,Debug,Clone )] 
 pub enum TerrainEditMode {
@@ -111,6 +116,11 @@ impl Plugin for TerrainMeshPlugin {
         app.add_plugins(chunk::chunks_plugin);
 
         app.add_plugins(splat::splat_plugin);
+        app.add_plugins(heightmap_io::heightmap_io_plugin);
+        app.add_plugins(erosion::erosion_plugin);
+        app.add_plugins(auto_splat::auto_splat_plugin);
+        app.add_plugins(holes::holes_plugin);
+        app.add_plugins(streaming::streaming_plugin);
 
 
         app.init_state::<terrain_loading_state::TerrainLoadingState>();
@@ -121,6 +131,7 @@ impl Plugin for TerrainMeshPlugin {
         app.add_message::<EditTerrainEvent>();
         app.add_message::<TerrainCommandEvent>();
         app.add_message::<TerrainBrushEvent>();
+        app.add_message::<TerrainDataReplacedEvent>();
         
   
         app.add_systems(
@@ -3,11 +3,11 @@ use crate::chunk::CardinalDirection;
 
 use bevy::platform::collections::hash_map::HashMap;
 
-use bevy::prelude::Vec3;
 use crate::heightmap::HeightMapU16;
-use bevy::prelude::{Mesh, Vec2};
-use bevy::render::mesh::Indices;
-use bevy::render::render_asset::RenderAssetUsages;
+use crate::holes::{tile_hole_coverage, HoleCoverage, HoleMaskU8};
+use bevy::prelude::Mesh;
+use bevy::mesh::Indices;
+use bevy::asset::RenderAssetUsages;
 use bevy::render::render_resource::PrimitiveTopology::TriangleList;
 
  /*
@@ -157,6 +157,22 @@ fn compute_smooth_normals(&self) -> Vec<[f32; 3]> {
         self.uvs.extend(uvs);
     }
 
+    // flat quad from start_fz to end_fz, one step wide
+    fn add_flat_strip(&mut self, fx: f32, start_fz: f32, end_fz: f32, step_size: f32, height: f32, texture_dimensions: [f32; 2]) {
+        let uv_lb = compute_uv(fx, start_fz, texture_dimensions);
+        let uv_rb = compute_uv(fx + step_size, start_fz, texture_dimensions);
+        let uv_rf = compute_uv(fx + step_size, end_fz, texture_dimensions);
+        let uv_lf = compute_uv(fx, end_fz, texture_dimensions);
+
+        let left_back = [fx, height, start_fz];
+        let right_back = [fx + step_size, height, start_fz];
+        let right_front = [fx + step_size, height, end_fz];
+        let left_front = [fx, height, end_fz];
+
+        self.add_triangle([left_front, right_back, left_back], [uv_lf, uv_rb, uv_lb]);
+        self.add_triangle([right_front, right_back, left_front], [uv_rf, uv_rb, uv_lf]);
+    }
+
     pub fn build(self) -> Mesh {
         let mut mesh = Mesh::new(TriangleList, RenderAssetUsages::default());
 
@@ -195,7 +211,9 @@ fn compute_smooth_normals(&self) -> Vec<[f32; 3]> {
 
         texture_dimensions: [f32; 2],
 
-         adjacent_chunk_lods: HashMap<CardinalDirection, u8>
+         adjacent_chunk_lods: HashMap<CardinalDirection, u8>,
+
+        hole_mask: Option<&HoleMaskU8>, // cells set in the mask are left out of the mesh
     ) -> Self {
         let mut premesh = Self::new();
 
@@ -214,8 +232,8 @@ fn compute_smooth_normals(&self) -> Vec<[f32; 3]> {
         //println!("sub_heightmap_width {}", sub_heightmap_width);
         //println!("sub_heightmap_height {}", sub_heightmap_height);
 
-        let tex_dim_x = texture_dimensions.get(0).unwrap().clone();
-        let tex_dim_y = texture_dimensions.get(1).unwrap().clone();
+        let tex_dim_x = *texture_dimensions.first().unwrap();
+        let tex_dim_y = *texture_dimensions.get(1).unwrap();
 
 
 
@@ -241,8 +259,8 @@ fn compute_smooth_normals(&self) -> Vec<[f32; 3]> {
 
 
         //there is a weird bug where there are gaps in betweeen each chunk ...
-        for x in (0..(tex_dim_x as usize - step_size) as usize).step_by(step_size) {
-         for z in (0..(tex_dim_y as usize - step_size) as usize).step_by(step_size) {
+        for x in (0..(tex_dim_x as usize - step_size)).step_by(step_size) {
+         for z in (0..(tex_dim_y as usize - step_size)).step_by(step_size) {
             
 
 
@@ -264,6 +282,7 @@ fn compute_smooth_normals(&self) -> Vec<[f32; 3]> {
                 &mut premesh,
                  sub_heightmap,
                  &adjacent_chunk_lods,
+                hole_mask,
                 texture_dimensions,
                 x,
                 z,
@@ -293,10 +312,12 @@ fn compute_smooth_normals(&self) -> Vec<[f32; 3]> {
 
 // build tile recursively with adaptive tesselation 
 
+#[allow(clippy::only_used_in_recursion)]
 fn refine_tile(
     premesh: &mut Self,
     height_data: &HeightMapU16,
     adjacent_chunk_lods: &HashMap<CardinalDirection, u8>,
+    hole_mask: Option<&HoleMaskU8>,
     texture_dimensions: [f32; 2],
     x: usize,
     z: usize,
@@ -347,7 +368,23 @@ fn refine_tile(
         flat_section = false; // Force refinement at chunk edges to ensure proper stitching
     }
 
-    let should_build_tile = flat_section || recursion_level >= local_max_recursion;
+    let hole_coverage = hole_mask
+        .map(|mask| tile_hole_coverage(mask, x, z, step_size))
+        .unwrap_or(HoleCoverage::None);
+
+    if hole_coverage == HoleCoverage::Full {
+        return;
+    }
+
+    // refine around the edge of a hole so it is cut at full resolution
+    let refine_hole_edge = hole_coverage == HoleCoverage::Partial && recursion_level < max_recursion;
+
+    let should_build_tile = (flat_section || recursion_level >= local_max_recursion) && !refine_hole_edge;
+
+    // a partial hole that can not be refined any further is cut as a whole
+    if should_build_tile && hole_coverage == HoleCoverage::Partial {
+        return;
+    }
 
 
 
@@ -367,10 +404,10 @@ fn refine_tile(
             let step_half = step_size as f32 / 2.0;
 
             let center = (lb + lf + rb + rf) / 4.0;
-            let left_mid = (lb + lf) / 2.0;
-            let right_mid = (rb + rf) / 2.0;
-            let forward_mid = (lf + rf) / 2.0;
-            let back_mid = (lb + rb) / 2.0;
+            let _left_mid = (lb + lf) / 2.0;
+            let _right_mid = (rb + rf) / 2.0;
+            let _forward_mid = (lf + rf) / 2.0;
+            let _back_mid = (lb + rb) / 2.0;
 
             let uv_lb = compute_uv(fx, fz, texture_dimensions);
             let uv_rb = compute_uv(fx + step_size as f32, fz, texture_dimensions);
@@ -426,10 +463,10 @@ fn refine_tile(
         let back_mid = (lb + rb) / 2.0;
 
        
-        Self::refine_tile(premesh, height_data, adjacent_chunk_lods, texture_dimensions, x, z, half_step, lod_level, height_scale, threshold, max_recursion, recursion_level + 1, lb, left_mid, back_mid, center);
-        Self::refine_tile(premesh, height_data, adjacent_chunk_lods, texture_dimensions, x + half_step, z, half_step, lod_level, height_scale, threshold, max_recursion, recursion_level + 1, back_mid, center, rb, right_mid);
-        Self::refine_tile(premesh, height_data, adjacent_chunk_lods, texture_dimensions, x, z + half_step, half_step, lod_level, height_scale, threshold, max_recursion, recursion_level + 1, left_mid, lf, center, forward_mid);
-        Self::refine_tile(premesh, height_data, adjacent_chunk_lods, texture_dimensions, x + half_step, z + half_step, half_step, lod_level, height_scale, threshold, max_recursion, recursion_level + 1, center, forward_mid, right_mid, rf);
+        Self::refine_tile(premesh, height_data, adjacent_chunk_lods, hole_mask, texture_dimensions, x, z, half_step, lod_level, height_scale, threshold, max_recursion, recursion_level + 1, lb, left_mid, back_mid, center);
+        Self::refine_tile(premesh, height_data, adjacent_chunk_lods, hole_mask, texture_dimensions, x + half_step, z, half_step, lod_level, height_scale, threshold, max_recursion, recursion_level + 1, back_mid, center, rb, right_mid);
+        Self::refine_tile(premesh, height_data, adjacent_chunk_lods, hole_mask, texture_dimensions, x, z + half_step, half_step, lod_level, height_scale, threshold, max_recursion, recursion_level + 1, left_mid, lf, center, forward_mid);
+        Self::refine_tile(premesh, height_data, adjacent_chunk_lods, hole_mask, texture_dimensions, x + half_step, z + half_step, half_step, lod_level, height_scale, threshold, max_recursion, recursion_level + 1, center, forward_mid, right_mid, rf);
     }
 }
 
@@ -490,11 +527,13 @@ fn bilinear_interpolate(
         sub_heightmap: & HeightMapU16,
 
         height_scale: f32,
-        lod_level: u8, // 0 is full quality, higher levels decimate the mesh
+        _lod_level: u8, // 0 is full quality, higher levels decimate the mesh
 
         texture_dimensions: [f32; 2],
 
-        chunk_lod_map: HashMap<CardinalDirection, u8>,
+        _chunk_lod_map: HashMap<CardinalDirection, u8>,
+
+        hole_mask: Option<&HoleMaskU8>, // cells set in the mask are left out of the mesh
     ) -> Self {
         let mut premesh = Self::new();
 
@@ -516,8 +555,8 @@ fn bilinear_interpolate(
         //println!("sub_heightmap_width {}", sub_heightmap_width);
         //println!("sub_heightmap_height {}", sub_heightmap_height);
 
-        let tex_dim_x = texture_dimensions.get(0).unwrap().clone();
-        let tex_dim_y = texture_dimensions.get(1).unwrap().clone();
+        let tex_dim_x = *texture_dimensions.first().unwrap();
+        let tex_dim_y = *texture_dimensions.get(1).unwrap();
 
         let width_scale = 1.0;
 
@@ -526,33 +565,44 @@ fn bilinear_interpolate(
 
         let similarity_threshold = scaled_min_threshold * 2.0;
 
-        for x in (0..(tex_dim_x as usize - step_size) as usize).step_by(step_size) {
+        for x in (0..(tex_dim_x as usize - step_size)).step_by(step_size) {
             // let mut greedy_y_start:Option<f32> = None;
             let mut current_greedy_height: Option<f32> = None;
             let mut greedy_points_z_start: Option<f32> = None; //fx
 
             let fx = (x) as f32 * width_scale;
 
-            for y in (0..(tex_dim_y as usize - step_size) as usize).step_by(step_size) {
+            for y in (0..(tex_dim_y as usize - step_size)).step_by(step_size) {
                 let at_end_of_segment = y >= tex_dim_y as usize - (step_size * 2);
 
                 let fz = (y) as f32 * width_scale;
 
-                let mut sample_allowed = true;
+                let sample_allowed = true;
                 //cant sample so we just continue
-                if x + step_size >= sub_heightmap_width as usize {
-                    sample_allowed = false;
+                if x + step_size >= sub_heightmap_width {
                     //warn!("x {}", x + step_size);
                     continue;
                 }
-                if y + step_size >= sub_heightmap_height as usize {
-                    sample_allowed = false;
+                if y + step_size >= sub_heightmap_height {
                     //warn!("y {}", y + step_size);
                     continue; 
                 }
 
+                // a hole ends the current greedy strip and leaves this quad out
+                let is_hole = hole_mask
+                    .is_some_and(|mask| tile_hole_coverage(mask, x, y, step_size) != HoleCoverage::None);
+
+                if is_hole {
+                    if let (Some(greedy_height), Some(start_fz)) =
+                        (current_greedy_height.take(), greedy_points_z_start.take())
+                        && greedy_height >= scaled_min_threshold {
+                            premesh.add_flat_strip(fx, start_fz, fz, step_size as f32, greedy_height, texture_dimensions);
+                        }
+                    continue;
+                }
+
                 // println!( "{} {} {} {} ", x , y , x+step_size, y + step_size   );
-                let (mut lb, mut lf, mut rb, mut rf) = match sample_allowed {
+                let (lb, lf, rb, rf) = match sample_allowed {
                     true => {
                         let lb = height_data[y][x] as f32 * height_scale;
                         let lf = height_data[y+ step_size][x ] as f32 * height_scale;
@@ -699,8 +749,8 @@ fn bilinear_interpolate(
             } // z loop
 
             //if there is still a greedy segment left over ... lets render it !
-            if let Some(greedy_height) = current_greedy_height {
-                if let Some(fz) = greedy_points_z_start {
+            if let Some(greedy_height) = current_greedy_height
+                && let Some(fz) = greedy_points_z_start {
                     let start_fz = greedy_points_z_start.unwrap();
 
                     let uv_lb = compute_uv(fx, start_fz,  texture_dimensions);
@@ -732,7 +782,7 @@ fn bilinear_interpolate(
                     premesh
                         .add_triangle([right_front, right_back, left_front], [uv_rf, uv_rb, uv_lf]);
                 } //if
-            } // x loop
+             // x loop
         } // x loop
 
         //this will be done later ! 
@@ -742,22 +792,6 @@ fn bilinear_interpolate(
     }
 }
 
-fn compute_normal(v0: [f32; 3], v1: [f32; 3], v2: [f32; 3]) -> [f32; 3] {
-    let edge1 = [v1[0] - v0[0], v1[1] - v0[1], v1[2] - v0[2]];
-    let edge2 = [v2[0] - v0[0], v2[1] - v0[1], v2[2] - v0[2]];
-
-    // Cross product
-    let normal = [
-        edge1[1] * edge2[2] - edge1[2] * edge2[1],
-        edge1[2] * edge2[0] - edge1[0] * edge2[2],
-        edge1[0] * edge2[1] - edge1[1] * edge2[0],
-    ];
-
-    // normal
-
-    [normal[0], normal[1], normal[2]] //is this busted ?
-}
-
 fn compute_uv(x: f32, y: f32,   texture_dimensions: [f32; 2]) -> [f32; 2] {
     
 
@@ -765,14 +799,14 @@ fn compute_uv(x: f32, y: f32,   texture_dimensions: [f32; 2]) -> [f32; 2] {
 
    
 
-    let uv = [
-        x / texture_dimensions[0],
-        y / texture_dimensions[1],
-    ];
+    
 
     // println!("uv {:?}", uv);
 
-    uv
+    [
+        x / texture_dimensions[0],
+        y / texture_dimensions[1],
+    ]
 }
 
 /*
@@ -1,22 +1,16 @@
 
 use crate::TerrainMaterialExtension;
 use crate::terrain::TerrainData;
-use crate::terrain::TerrainImageDataLoadStatus;
 use crate::terrain_config::TerrainConfig;
-use std::path::PathBuf;
 use std::path::Path;
 use image::RgbaImage;
 use image::ImageBuffer;
 use crate::chunk::Chunk;
 use crate::chunk::ChunkData;
-use serde::Serialize;
-use serde::Deserialize;
 use bevy::prelude::*;
 
-use bevy::platform::collections::hash_map::HashMap;
 
-use bevy::render::render_resource::{ Extent3d, TextureDimension, TextureFormat};
-use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::TextureFormat;
  
 
  
@@ -77,9 +71,9 @@ impl ChunkSplatDataRaw {
                 // Extract the index and strength data for the current pixel
          let index_offset = pixel_index * layers_count as usize;
 
-         let idx = index_offset + layer as usize; 
+          
 
-         idx
+         index_offset + layer as usize
     }
 
 
@@ -203,7 +197,7 @@ fn rebuild_chunk_splat_textures(
 
      terrain_query: Query<(&TerrainData, &TerrainConfig)>,
 
-     mut terrain_materials: ResMut<Assets<TerrainMaterialExtension>>,
+     _terrain_materials: ResMut<Assets<TerrainMaterialExtension>>,
 
      asset_server: Res<AssetServer>, 
 
@@ -211,7 +205,7 @@ fn rebuild_chunk_splat_textures(
     ){
 
 
-    for (chunk_entity, chunk, mut chunk_data, chunk_splat_data, parent_terrain_entity ) in chunk_query.iter_mut() { 
+    for (chunk_entity, _chunk, mut chunk_data, chunk_splat_data, parent_terrain_entity ) in chunk_query.iter_mut() { 
 
 
          if let Ok(mut cmds) = commands.get_entity( chunk_entity ){
@@ -226,11 +220,11 @@ fn rebuild_chunk_splat_textures(
 
           let terrain_entity_id = parent_terrain_entity.parent();
 
-            if terrain_query.get(terrain_entity_id).is_ok() == false {
+            if terrain_query.get(terrain_entity_id).is_err() {
                 continue;
             }
 
-            let (terrain_data, terrain_config) = terrain_query.get(terrain_entity_id).unwrap();
+            let (_terrain_data, _terrain_config) = terrain_query.get(terrain_entity_id).unwrap();
  
                 info!("replacing splat   texture " );
 
@@ -278,7 +272,7 @@ where
     //   || format == TextureFormat::Rgba16Unorm
     {
         // The data in Bevy's Image type is stored in a Vec<u8>, so we can use it directly
-        let img: RgbaImage = ImageBuffer::from_raw(width, height, image_data.clone().unwrap() .into( )  )
+        let img: RgbaImage = ImageBuffer::from_raw(width, height, image_data.clone().unwrap()  )
             .expect("Failed to create image buffer");
 
         // Save the image to the specified file path
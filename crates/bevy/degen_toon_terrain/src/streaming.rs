@@ -0,0 +1,402 @@
+use std::time::Duration;
+
+use bevy::diagnostic::{Diagnostic, DiagnosticPath, Diagnostics, RegisterDiagnostic};
+use bevy::platform::collections::HashSet;
+use bevy::prelude::*;
+use bevy::time::common_conditions::on_timer;
+
+use crate::chunk::{
+    Chunk, ChunkCoordinates, ChunkCoords, ChunkData, ChunkHeightMapResource, DespawnMarker,
+    HeightMapDecodeTask, TerrainChunkMesh,
+};
+use crate::edit::TerrainCommandEvent;
+use crate::auto_splat::SplatLockMaskResource;
+use crate::holes::{ChunkHoleMaskResource, HoleMaskLoadTask};
+use crate::splat::{ChunkSplatDataRaw, SplatMapDataUpdated};
+use crate::terrain::{TerrainImageDataLoadStatus, TerrainViewer};
+use crate::terrain_config::TerrainConfig;
+
+/*
+
+Distance based streaming of chunk data for very large worlds.
+
+Without `chunk_streaming` in the terrain config every chunk loads its height and splat
+data on startup and keeps it. With it, only chunks within `load_distance` of a
+`TerrainViewer` (plus the neighbours they stitch their meshes to) get their data loaded, and
+chunks further than `unload_distance` from every viewer drop their height map, splat data,
+hole and splat lock masks, meshes and collider again.
+
+Chunks with unsaved edits stay loaded until the next SaveAllChunks, so no work is lost.
+
+Height and splat images are read by the AssetServer, which loads them off the main thread, and
+height maps are decoded on the async compute pool. Hole masks and colliders are read in IoTaskPool tasks.
+
+Counts and memory use are published as diagnostics under terrain/.
+
+*/
+
+pub const RESIDENT_CHUNKS: DiagnosticPath = DiagnosticPath::const_new("terrain/resident_chunks");
+pub const LOADING_CHUNKS: DiagnosticPath = DiagnosticPath::const_new("terrain/loading_chunks");
+pub const UNSAVED_CHUNKS: DiagnosticPath = DiagnosticPath::const_new("terrain/unsaved_chunks");
+pub const HEIGHT_MAP_MEMORY_MB: DiagnosticPath = DiagnosticPath::const_new("terrain/height_map_memory_mb");
+pub const SPLAT_MAP_MEMORY_MB: DiagnosticPath = DiagnosticPath::const_new("terrain/splat_map_memory_mb");
+
+pub fn streaming_plugin(app: &mut App) {
+    let task_update_rate = Duration::from_millis(250);
+
+    app.init_resource::<ChunkStreamingResource>()
+        .register_diagnostic(Diagnostic::new(RESIDENT_CHUNKS))
+        .register_diagnostic(Diagnostic::new(LOADING_CHUNKS))
+        .register_diagnostic(Diagnostic::new(UNSAVED_CHUNKS))
+        .register_diagnostic(Diagnostic::new(HEIGHT_MAP_MEMORY_MB))
+        .register_diagnostic(Diagnostic::new(SPLAT_MAP_MEMORY_MB))
+        .add_systems(Update, (track_unsaved_splat_edits, release_saved_chunks))
+        .add_systems(
+            Update,
+            (update_streamed_chunks, measure_chunk_memory)
+                .chain()
+                .before(crate::chunk::initialize_chunk_data)
+                .run_if(on_timer(task_update_rate)),
+        );
+
+    #[cfg(feature = "physics")]
+    app.add_systems(
+        Update,
+        (load_streamed_chunk_colliders, finish_chunk_collider_load_tasks).chain(),
+    );
+}
+
+#[derive(Resource, Default)]
+pub struct ChunkStreamingResource {
+    // None while streaming is disabled, then every chunk is streamed in
+    streamed_chunks: Option<HashSet<u32>>,
+
+    // edited chunks, they are never unloaded before they are saved
+    pub unsaved_chunks: HashSet<u32>,
+
+    pub metrics: ChunkStreamingMetrics,
+}
+
+impl ChunkStreamingResource {
+    pub fn is_streamed_in(&self, chunk_id: u32) -> bool {
+        self.streamed_chunks
+            .as_ref()
+            .is_none_or(|streamed_chunks| streamed_chunks.contains(&chunk_id))
+    }
+}
+
+#[derive(Default, Clone, Debug)]
+pub struct ChunkStreamingMetrics {
+    pub resident_chunks: usize,
+    pub loading_chunks: usize,
+    pub unsaved_chunks: usize,
+
+    pub height_map_bytes: usize,
+    pub splat_map_bytes: usize,
+}
+
+// the chunk itself plus the chunks compute_stitch_data reads from when building its mesh
+fn chunk_with_stitch_neighbours(chunk_id: u32, chunk_rows: u32) -> [u32; 4] {
+    let chunk_coords = ChunkCoords::from_chunk_id(chunk_id, chunk_rows);
+
+    [
+        chunk_id,
+        ChunkCoords::new(chunk_coords.x() + 1, chunk_coords.y()).get_chunk_index(chunk_rows),
+        ChunkCoords::new(chunk_coords.x(), chunk_coords.y() + 1).get_chunk_index(chunk_rows),
+        ChunkCoords::new(chunk_coords.x() + 1, chunk_coords.y() + 1).get_chunk_index(chunk_rows),
+    ]
+}
+
+fn update_streamed_chunks(
+    mut commands: Commands,
+
+    terrain_query: Query<&TerrainConfig>,
+    chunk_query: Query<(Entity, &Chunk, &ChildOf, &GlobalTransform, Option<&ChunkData>, Option<&Children>)>,
+    chunk_mesh_query: Query<Entity, With<TerrainChunkMesh>>,
+    #[cfg(feature = "physics")] chunk_collider_query: Query<Entity, With<StreamedChunkCollider>>,
+    terrain_viewer_query: Query<&GlobalTransform, With<TerrainViewer>>,
+
+    mut chunk_height_maps: ResMut<ChunkHeightMapResource>,
+    mut hole_masks: ResMut<ChunkHoleMaskResource>,
+    mut lock_masks: ResMut<SplatLockMaskResource>,
+    mut chunk_streaming: ResMut<ChunkStreamingResource>,
+) {
+    let Some(terrain_config) = terrain_query.iter().next() else {
+        return;
+    };
+
+    let Some(streaming_config) = &terrain_config.chunk_streaming else {
+        chunk_streaming.streamed_chunks = None;
+        return;
+    };
+
+    let viewer_locations: Vec<Vec2> = terrain_viewer_query
+        .iter()
+        .map(|xform| xform.translation().xz())
+        .collect();
+
+    //nothing streams in until there is a viewer
+    if viewer_locations.is_empty() {
+        chunk_streaming.streamed_chunks.get_or_insert_default();
+        return;
+    }
+
+    let chunk_rows = terrain_config.chunk_rows;
+    let chunk_dimensions = terrain_config.get_chunk_dimensions();
+
+    let mut chunks_to_load: HashSet<u32> = HashSet::new();
+    let mut chunks_to_keep: HashSet<u32> = HashSet::new();
+
+    for (_, chunk, _, chunk_xform, _, _) in chunk_query.iter() {
+        let chunk_center = chunk_xform.translation().xz() + chunk_dimensions / 2.0;
+
+        let distance_to_viewer = viewer_locations
+            .iter()
+            .map(|viewer_location| viewer_location.distance(chunk_center))
+            .fold(f32::MAX, f32::min);
+
+        if distance_to_viewer <= streaming_config.load_distance {
+            chunks_to_load.extend(chunk_with_stitch_neighbours(chunk.chunk_id, chunk_rows));
+        }
+
+        // between load_distance and unload_distance a chunk keeps whatever state it has
+        if distance_to_viewer <= streaming_config.unload_distance {
+            chunks_to_keep.extend(chunk_with_stitch_neighbours(chunk.chunk_id, chunk_rows));
+        }
+    }
+
+    let chunk_streaming = chunk_streaming.as_mut();
+    let streamed_chunks = chunk_streaming.streamed_chunks.get_or_insert_default();
+
+    streamed_chunks.retain(|chunk_id| {
+        chunks_to_keep.contains(chunk_id) || chunk_streaming.unsaved_chunks.contains(chunk_id)
+    });
+    streamed_chunks.extend(chunks_to_load);
+
+    for (chunk_entity, chunk, _, _, chunk_data, children) in chunk_query.iter() {
+        let Some(chunk_data) = chunk_data else {
+            continue;
+        };
+
+        if streamed_chunks.contains(&chunk.chunk_id) {
+            continue;
+        }
+
+        // a finished mesh task would otherwise attach to a chunk without data
+        if chunk_data.is_building() {
+            continue;
+        }
+
+        info!("streaming out terrain chunk {}", chunk.chunk_id);
+
+        commands.entity(chunk_entity).remove::<(
+            ChunkData,
+            ChunkSplatDataRaw,
+            SplatMapDataUpdated,
+            HeightMapDecodeTask,
+            HoleMaskLoadTask,
+        )>();
+
+        #[cfg(feature = "physics")]
+        commands
+            .entity(chunk_entity)
+            .remove::<(ChunkColliderLoadTask, ChunkColliderLoaded)>();
+
+        for child in children.into_iter().flatten() {
+            if chunk_mesh_query.get(*child).is_ok() {
+                commands.entity(*child).insert(DespawnMarker);
+            }
+
+            #[cfg(feature = "physics")]
+            if chunk_collider_query.get(*child).is_ok() {
+                commands.entity(*child).despawn();
+            }
+        }
+
+        chunk_height_maps.chunk_height_maps.remove(&chunk.chunk_id);
+        hole_masks.unload_chunk(chunk.chunk_id);
+        lock_masks.unload_chunk(chunk.chunk_id);
+    }
+}
+
+// height edits are tracked in reset_chunk_height_data, splat edits change the raw splat data
+fn track_unsaved_splat_edits(
+    chunk_query: Query<(&Chunk, Ref<ChunkSplatDataRaw>)>,
+
+    mut chunk_streaming: ResMut<ChunkStreamingResource>,
+) {
+    for (chunk, splat_data) in chunk_query.iter() {
+        if splat_data.is_changed() && !splat_data.is_added() {
+            chunk_streaming.unsaved_chunks.insert(chunk.chunk_id);
+        }
+    }
+}
+
+fn release_saved_chunks(
+    mut ev_reader: MessageReader<TerrainCommandEvent>,
+
+    mut chunk_streaming: ResMut<ChunkStreamingResource>,
+) {
+    for ev in ev_reader.read() {
+        // hole masks are saved with the splat maps, so both have to be saved
+        if let TerrainCommandEvent::SaveAllChunks(true, true, _) = ev {
+            chunk_streaming.unsaved_chunks.clear();
+        }
+    }
+}
+
+fn measure_chunk_memory(
+    mut diagnostics: Diagnostics,
+
+    chunk_query: Query<(&ChunkData, Option<&ChunkSplatDataRaw>)>,
+    images: Res<Assets<Image>>,
+
+    chunk_height_maps: Res<ChunkHeightMapResource>,
+    mut chunk_streaming: ResMut<ChunkStreamingResource>,
+) {
+    let image_bytes = |handle: &Option<Handle<Image>>| {
+        handle
+            .as_ref()
+            .and_then(|handle| images.get(handle))
+            .and_then(|image| image.data.as_ref())
+            .map_or(0, |data| data.len())
+    };
+
+    let mut metrics = ChunkStreamingMetrics {
+        unsaved_chunks: chunk_streaming.unsaved_chunks.len(),
+        ..default()
+    };
+
+    for (chunk_data, splat_data) in chunk_query.iter() {
+        metrics.resident_chunks += 1;
+
+        if chunk_data.height_map_image_data_load_status == TerrainImageDataLoadStatus::NotLoaded {
+            metrics.loading_chunks += 1;
+        }
+
+        metrics.height_map_bytes += image_bytes(chunk_data.get_height_map_texture_image());
+        metrics.splat_map_bytes += image_bytes(chunk_data.get_splat_texture_image());
+
+        if let Some(splat_data) = splat_data {
+            metrics.splat_map_bytes += splat_data
+                .splat_map_texture
+                .data
+                .as_ref()
+                .map_or(0, |data| data.len());
+        }
+    }
+
+    metrics.height_map_bytes += chunk_height_maps
+        .chunk_height_maps
+        .values()
+        .map(|height_map| height_map.iter().map(|row| row.len() * size_of::<u16>()).sum::<usize>())
+        .sum::<usize>();
+
+    let to_mb = |bytes: usize| bytes as f64 / (1024.0 * 1024.0);
+
+    diagnostics.add_measurement(&RESIDENT_CHUNKS, || metrics.resident_chunks as f64);
+    diagnostics.add_measurement(&LOADING_CHUNKS, || metrics.loading_chunks as f64);
+    diagnostics.add_measurement(&UNSAVED_CHUNKS, || metrics.unsaved_chunks as f64);
+    diagnostics.add_measurement(&HEIGHT_MAP_MEMORY_MB, || to_mb(metrics.height_map_bytes));
+    diagnostics.add_measurement(&SPLAT_MAP_MEMORY_MB, || to_mb(metrics.splat_map_bytes));
+
+    chunk_streaming.metrics = metrics;
+}
+
+#[cfg(feature = "physics")]
+pub use physics::*;
+
+#[cfg(feature = "physics")]
+mod physics {
+    use std::path::PathBuf;
+
+    use avian3d::prelude::{Collider, RigidBody};
+    use bevy::prelude::*;
+    use bevy::tasks::{IoTaskPool, Task};
+    use futures_lite::future;
+
+    use crate::chunk::{Chunk, ChunkData};
+    use crate::terrain_config::TerrainConfig;
+
+    /// Child of a streamed chunk holding its baked collider. Games add their collision layers to it.
+    #[derive(Component)]
+    pub struct StreamedChunkCollider;
+
+    #[derive(Component)]
+    pub(super) struct ChunkColliderLoadTask(Task<Option<Collider>>);
+
+    // also set when the chunk has no collider file, so it is not looked for again
+    #[derive(Component)]
+    pub(super) struct ChunkColliderLoaded;
+
+    pub(super) fn load_streamed_chunk_colliders(
+        mut commands: Commands,
+
+        chunk_query: Query<
+            (Entity, &Chunk, &ChildOf),
+            (With<ChunkData>, Without<ChunkColliderLoadTask>, Without<ChunkColliderLoaded>),
+        >,
+        terrain_query: Query<&TerrainConfig>,
+    ) {
+        for (chunk_entity, chunk, terrain_entity) in chunk_query.iter() {
+            let Ok(terrain_config) = terrain_query.get(terrain_entity.parent()) else {
+                continue;
+            };
+
+            if !terrain_config
+                .chunk_streaming
+                .as_ref()
+                .is_some_and(|streaming_config| streaming_config.stream_colliders)
+            {
+                continue;
+            }
+
+            let file_path = PathBuf::from("assets")
+                .join(&terrain_config.collider_data_folder_path)
+                .join(format!("{}.col", chunk.chunk_id));
+
+            let task = IoTaskPool::get().spawn(async move {
+                let contents = std::fs::read(&file_path).ok()?;
+
+                bincode::deserialize::<Collider>(&contents)
+                    .inspect_err(|e| warn!("could not read terrain collider {:?}: {}", file_path, e))
+                    .ok()
+            });
+
+            commands.entity(chunk_entity).insert(ChunkColliderLoadTask(task));
+        }
+    }
+
+    pub(super) fn finish_chunk_collider_load_tasks(
+        mut commands: Commands,
+
+        mut chunk_query: Query<(Entity, &mut ChunkColliderLoadTask)>,
+    ) {
+        for (chunk_entity, mut task) in chunk_query.iter_mut() {
+            let Some(collider) = future::block_on(future::poll_once(&mut task.0)) else {
+                continue;
+            };
+
+            commands
+                .entity(chunk_entity)
+                .remove::<ChunkColliderLoadTask>()
+                .insert(ChunkColliderLoaded);
+
+            let Some(collider) = collider else {
+                continue;
+            };
+
+            let collider_child = commands
+                .spawn((
+                    Name::new("chunk collider"),
+                    Transform::default(),
+                    collider,
+                    RigidBody::Static,
+                    StreamedChunkCollider,
+                ))
+                .id();
+
+            commands.entity(chunk_entity).add_child(collider_child);
+        }
+    }
+}
@@ -1,15 +1,12 @@
-use bevy::asset::{AssetPath, LoadState};
-use bevy::image::{ImageFormatSetting, ImageLoaderSettings, ImageSampler, ImageSamplerDescriptor};
+use bevy::asset::AssetPath;
+use bevy::image::{ImageLoaderSettings, ImageSampler, ImageSamplerDescriptor};
 use bevy::prelude::*;
 use bevy::render::render_resource::{
-    AddressMode, FilterMode, SamplerDescriptor, TextureDescriptor, TextureFormat,
+    AddressMode, FilterMode,
 };
  
-use bevy::platform::collections::hash_map::HashMap;
 
-use crate::chunk::{Chunk, ChunkCoordinates, ChunkCoords, ChunkData,  };
-use crate::heightmap::{HeightMap, HeightMapU16};
-use crate::terrain_material::{ChunkMaterialUniforms, TerrainMaterial};
+use crate::chunk::{Chunk, ChunkCoordinates, ChunkCoords,  };
 
 use crate::terrain_config::TerrainConfig;
 
@@ -69,11 +66,11 @@ pub struct TerrainData {
 
 impl TerrainData {
     pub fn new() -> Self {
-        let terrain_data = TerrainData::default();
+        
 
         //  terrain_data.texture_image_handle = Some(handle.clone()); //strong clone
 
-        terrain_data
+        TerrainData::default()
     }
 }
 
@@ -228,7 +225,9 @@ pub fn load_terrain_texture_from_image(
             if need_to_reinterpret {
                 //info!("texture info {:?}" , texture_image.texture_descriptor.dimension, texture_image.size().depth_or_array_layers);
 
-                texture_image.reinterpret_stacked_2d_as_array(desired_array_layers);
+                if let Err(err) = texture_image.reinterpret_stacked_2d_as_array(desired_array_layers) {
+                    warn!("could not reinterpret texture as array {:?}", err);
+                }
             }
  
 
@@ -308,7 +307,9 @@ pub fn load_terrain_texture_from_image(
             if need_to_reinterpret {
                 //info!("texture info {:?}" , texture_image.texture_descriptor.dimension, texture_image.size().depth_or_array_layers);
 
-                texture_image.reinterpret_stacked_2d_as_array(desired_array_layers);
+                if let Err(err) = texture_image.reinterpret_stacked_2d_as_array(desired_array_layers) {
+                    warn!("could not reinterpret texture as array {:?}", err);
+                }
             }
  
 
@@ -392,7 +393,9 @@ pub fn load_terrain_normal_from_image(
             if need_to_reinterpret {
                 //info!("texture info {:?}" , texture_image.texture_descriptor.dimension, texture_image.size().depth_or_array_layers);
 
-                texture_image.reinterpret_stacked_2d_as_array(desired_array_layers);
+                if let Err(err) = texture_image.reinterpret_stacked_2d_as_array(desired_array_layers) {
+                    warn!("could not reinterpret texture as array {:?}", err);
+                }
             }
 
            
@@ -457,7 +460,7 @@ pub fn load_terrain_blend_height_from_image(
             };
 
 
-            let format = texture_image.texture_descriptor.format;
+            let _format = texture_image.texture_descriptor.format;
             //force format 
             //texture_image.texture_descriptor.format = TextureFormat::R16Uint ;
 
@@ -489,7 +492,9 @@ pub fn load_terrain_blend_height_from_image(
             if need_to_reinterpret {
                 //info!("texture info {:?}" , texture_image.texture_descriptor.dimension, texture_image.size().depth_or_array_layers);
 
-                texture_image. reinterpret_stacked_2d_as_array(desired_array_layers);
+                if let Err(err) = texture_image.reinterpret_stacked_2d_as_array(desired_array_layers) {
+                    warn!("could not reinterpret texture as array {:?}", err);
+                }
             }
 
            
@@ -45,6 +45,40 @@ pub struct TerrainConfig {
     pub height_folder_path: PathBuf,
     pub splat_folder_path: PathBuf,
     pub collider_data_folder_path: PathBuf,
+
+    // when set, chunk data is only kept in memory around the terrain viewers
+    #[serde(default)]
+    pub chunk_streaming: Option<ChunkStreamingConfig>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct ChunkStreamingConfig {
+    // chunks closer than this to a terrain viewer are loaded
+    pub load_distance: f32,
+    // chunks further than this from every terrain viewer are unloaded, keep it above load_distance
+    pub unload_distance: f32,
+
+    #[serde(default = "default_max_chunk_loads_per_update")]
+    pub max_chunk_loads_per_update: usize,
+
+    // also load the baked .col files of streamed chunks (physics feature)
+    #[serde(default)]
+    pub stream_colliders: bool,
+}
+
+fn default_max_chunk_loads_per_update() -> usize {
+    4
+}
+
+impl Default for ChunkStreamingConfig {
+    fn default() -> Self {
+        Self {
+            load_distance: 1500.0,
+            unload_distance: 2000.0,
+            max_chunk_loads_per_update: default_max_chunk_loads_per_update(),
+            stream_colliders: false,
+        }
+    }
 }
 
 impl Default for TerrainConfig {
@@ -79,6 +113,8 @@ impl Default for TerrainConfig {
             splat_folder_path: "splat/".into(),
             
             collider_data_folder_path: "collider/".into(),
+
+            chunk_streaming: None,
         }
     }
 }
@@ -110,14 +146,14 @@ impl TerrainConfig {
     }
 
     pub fn get_max_render_distance(&self) -> f32 {
-        return self.render_distance;
+        self.render_distance
     }
 
     pub fn get_chunk_render_distance(&self) -> u32 {
-        return self.render_distance as u32 / self.chunk_rows;
+        self.render_distance as u32 / self.chunk_rows
     }
 
     pub fn get_chunk_lod_distance(&self) -> f32 {
-        return self.lod_distance;
+        self.lod_distance
     }
 }
@@ -1,6 +1,5 @@
 use bevy::prelude::*;
 
-use crate::chunk::TerrainChunkMesh;
 
 #[derive(States, Default, Clone, Eq, PartialEq, Debug, Hash)]
 pub enum TerrainLoadingState {
@@ -1,15 +1,11 @@
  
-use bevy::asset::VisitAssetDependencies;
 use bevy::prelude::*;
 use bevy::reflect::TypePath;
 use bevy::render::render_resource::*;
 
 use bevy::pbr::ExtendedMaterial;
 
-use bevy::render::render_asset::RenderAssets;
 
-use bevy::pbr::StandardMaterialFlags;
-use bevy::pbr::StandardMaterialUniform;
 
 use bevy::pbr::MaterialExtension;
 use bevy::shader::ShaderRef;
@@ -17,10 +13,10 @@ use bevy::shader::ShaderRef;
 
 pub type TerrainMaterialExtension = ExtendedMaterial<StandardMaterial, TerrainMaterial>;
 
-pub const STOCHASTIC_SAMPLING_SHADER_HANDLE: Handle<Shader> = Handle::from(AssetId::<Shader>::invalid());
-pub const CUSTOM_PBR_FUNCTIONS_SHADER_HANDLE: Handle<Shader> = Handle::from(AssetId::<Shader>::invalid());
-pub const TOON_LIGHTING_SHADER_HANDLE: Handle<Shader> = Handle::from(AssetId::<Shader>::invalid());
-pub const TERRAIN_SHADER_HANDLE: Handle<Shader> = Handle::from(AssetId::<Shader>::invalid());
+pub const STOCHASTIC_SAMPLING_SHADER_HANDLE: Handle<Shader> = bevy::asset::uuid_handle!("3a6c1f0e-5b2d-4c8e-9f71-2d4b6a8c0e11");
+pub const CUSTOM_PBR_FUNCTIONS_SHADER_HANDLE: Handle<Shader> = bevy::asset::uuid_handle!("7e2b4d90-1c3a-4f5e-8b6d-0a9c2e4f6b13");
+pub const TOON_LIGHTING_SHADER_HANDLE: Handle<Shader> = bevy::asset::uuid_handle!("c51f8a27-9d3e-4b60-a2c4-6e8f0b1d3a15");
+pub const TERRAIN_SHADER_HANDLE: Handle<Shader> = bevy::asset::uuid_handle!("9b0d2f44-6a8c-4e1b-b3d5-7f9a1c3e5d17");
 
  
 
@@ -0,0 +1,112 @@
+//! Three way merge of zone files by entity id and field.
+//!
+//! Usage: zone-merge <base> <ours> <theirs> [output]
+//!
+//! The result is written to `output`, or over `ours` when it is omitted, which is what git
+//! expects from a merge driver. Register it with
+//!
+//! ```text
+//! # .gitattributes
+//! *.zone.ron merge=zone
+//!
+//! # .git/config
+//! [merge "zone"]
+//!     name = zone file merge
+//!     driver = zone-merge %O %A %B
+//! ```
+//!
+//! Conflicting fields keep our value and are listed in comments at the top of the result.
+//! Exits with 1 when there are conflicts and 2 when the files could not be merged at all.
+
+use std::path::Path;
+use std::process::ExitCode;
+
+use spirit_edit_core::zones::zone_file::ZoneFileV2;
+use spirit_edit_core::zones::zone_merge::{merge_zone_files, zone_file_has_entity_ids};
+
+fn load_zone_file(path: &Path) -> Option<ZoneFileV2> {
+    let Ok(file_content) = std::fs::read_to_string(path) else {
+        eprintln!("Could not read file {:?}", path);
+        return None;
+    };
+
+    // git passes an empty base when both sides added the file
+    if file_content.trim().is_empty() {
+        return Some(ZoneFileV2::default());
+    }
+
+    let zone_file = match ron::from_str::<ZoneFileV2>(&file_content) {
+        Ok(zone_file) => zone_file,
+        Err(e) => {
+            eprintln!("Could not parse file {:?} {:?}", path, e);
+            return None;
+        }
+    };
+
+    // without ids every side would get different random ones and nothing would match
+    if !zone_file_has_entity_ids(&file_content) {
+        eprintln!(
+            "{:?} was written before zone entities had ids, save it in the editor before merging",
+            path
+        );
+        return None;
+    }
+
+    Some(zone_file)
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let (base_path, our_path, their_path, output_path) = match args.as_slice() {
+        [base, ours, theirs] => (base, ours, theirs, ours),
+        [base, ours, theirs, output] => (base, ours, theirs, output),
+        _ => {
+            eprintln!("usage: zone-merge <base> <ours> <theirs> [output]");
+            return ExitCode::from(2);
+        }
+    };
+
+    let (Some(base), Some(ours), Some(theirs)) = (
+        load_zone_file(Path::new(base_path)),
+        load_zone_file(Path::new(our_path)),
+        load_zone_file(Path::new(their_path)),
+    ) else {
+        return ExitCode::from(2);
+    };
+
+    let merge_result = match merge_zone_files(&base, &ours, &theirs) {
+        Ok(merge_result) => merge_result,
+        Err(e) => {
+            eprintln!("Could not merge zone files: {}", e);
+            return ExitCode::from(2);
+        }
+    };
+
+    let merged_ron = match merge_result.merged.to_pretty_ron() {
+        Ok(merged_ron) => merged_ron,
+        Err(e) => {
+            eprintln!("Could not write merged zone file: {}", e);
+            return ExitCode::from(2);
+        }
+    };
+
+    let mut output = String::new();
+
+    for conflict in merge_result.conflicts.iter() {
+        eprintln!("zone-merge conflict: {}", conflict);
+        output.push_str(&format!("// zone-merge conflict: {}\n", conflict));
+    }
+
+    output.push_str(&merged_ron);
+
+    if let Err(e) = std::fs::write(output_path, output) {
+        eprintln!("Could not write file {:?}: {}", output_path, e);
+        return ExitCode::from(2);
+    }
+
+    match merge_result.conflicts.is_empty() {
+        true => ExitCode::SUCCESS,
+        false => ExitCode::from(1),
+    }
+}
@@ -0,0 +1,196 @@
+//! Lints zone and prefab files against the doodad manifests, prefabs, material manifest and
+//! clay tile types.
+//!
+//! Usage: zone-validate [options] <zone or prefab files or folders...>
+//!
+//! Run it from the editor folder, the defaults below are relative to it.
+//!
+//! ```text
+//! --doodad-manifests <folder>   default artifacts/game_assets/doodad_manifests
+//! --prefabs <folder>            default assets/prefabs
+//! --materials <file>            default assets/material_manifest.materialmanifest.ron
+//! --tile-types <file>           default assets/clay_tiles/tile_types.ron
+//! --terrain-bounds <min_x,min_z,max_x,max_z>
+//! ```
+//!
+//! Folders are searched for `.zone.ron` and `.prefab.ron` files.  Checks whose data could
+//! not be found are skipped with a note.  Exits with 1 when there are issues and 2 when a
+//! file could not be loaded.
+
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use bevy::math::{Rect, Vec2};
+
+use spirit_edit_core::prefabs::prefab_file::PrefabFile;
+use spirit_edit_core::zones::zone_file::ZoneFileV2;
+use spirit_edit_core::zones::zone_validation::{
+    collect_files_with_suffix, load_doodad_definitions_from_folder, load_material_names,
+    load_prefab_names_from_folder, load_tile_type_count, validate_prefab_file, validate_zone_file,
+    ZoneValidationContext, ZoneValidationSeverity,
+};
+
+const USAGE: &str = "usage: zone-validate [--doodad-manifests <folder>] [--prefabs <folder>] [--materials <file>] [--tile-types <file>] [--terrain-bounds <min_x,min_z,max_x,max_z>] <files or folders...>";
+
+struct Options {
+    doodad_manifests_folder: PathBuf,
+    prefabs_folder: PathBuf,
+    material_manifest_path: PathBuf,
+    tile_types_path: PathBuf,
+    terrain_bounds: Option<Rect>,
+
+    input_paths: Vec<PathBuf>,
+}
+
+fn parse_terrain_bounds(value: &str) -> Option<Rect> {
+    let values: Vec<f32> = value
+        .split(',')
+        .map(|v| v.trim().parse::<f32>())
+        .collect::<Result<_, _>>()
+        .ok()?;
+
+    match values.as_slice() {
+        [min_x, min_z, max_x, max_z] => Some(Rect::from_corners(
+            Vec2::new(*min_x, *min_z),
+            Vec2::new(*max_x, *max_z),
+        )),
+        _ => None,
+    }
+}
+
+fn parse_options(args: Vec<String>) -> Option<Options> {
+    let mut options = Options {
+        doodad_manifests_folder: "artifacts/game_assets/doodad_manifests".into(),
+        prefabs_folder: "assets/prefabs".into(),
+        material_manifest_path: "assets/material_manifest.materialmanifest.ron".into(),
+        tile_types_path: "assets/clay_tiles/tile_types.ron".into(),
+        terrain_bounds: None,
+        input_paths: Vec::new(),
+    };
+
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--doodad-manifests" => options.doodad_manifests_folder = args.next()?.into(),
+            "--prefabs" => options.prefabs_folder = args.next()?.into(),
+            "--materials" => options.material_manifest_path = args.next()?.into(),
+            "--tile-types" => options.tile_types_path = args.next()?.into(),
+            "--terrain-bounds" => options.terrain_bounds = Some(parse_terrain_bounds(&args.next()?)?),
+            _ if arg.starts_with("--") => return None,
+            _ => options.input_paths.push(arg.into()),
+        }
+    }
+
+    if options.input_paths.is_empty() {
+        return None;
+    }
+
+    Some(options)
+}
+
+// a missing file skips its checks, a broken one is worth stopping for
+fn load_optional<T>(
+    description: &str,
+    path: &Path,
+    load: impl FnOnce(&Path) -> Result<T, Box<dyn std::error::Error>>,
+) -> Result<Option<T>, ()> {
+    if !path.exists() {
+        eprintln!("note: {:?} not found, skipping {} checks", path, description);
+        return Ok(None);
+    }
+
+    match load(path) {
+        Ok(loaded) => Ok(Some(loaded)),
+        Err(e) => {
+            eprintln!("Could not load {} from {:?}: {}", description, path, e);
+            Err(())
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let Some(options) = parse_options(std::env::args().skip(1).collect()) else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+
+    let (Ok(doodad_definitions), Ok(prefab_names), Ok(material_names), Ok(tile_type_count)) = (
+        load_optional("doodad", &options.doodad_manifests_folder, load_doodad_definitions_from_folder),
+        load_optional("prefab", &options.prefabs_folder, load_prefab_names_from_folder),
+        load_optional("material", &options.material_manifest_path, load_material_names),
+        load_optional("tile type", &options.tile_types_path, load_tile_type_count),
+    ) else {
+        return ExitCode::from(2);
+    };
+
+    let context = ZoneValidationContext {
+        doodad_definitions,
+        prefab_names,
+        material_names,
+        tile_type_count,
+        terrain_bounds: options.terrain_bounds,
+    };
+
+    let mut file_paths = Vec::new();
+
+    for input_path in options.input_paths.iter() {
+        if !input_path.is_dir() {
+            file_paths.push(input_path.clone());
+            continue;
+        }
+
+        for suffix in [".zone.ron", ".prefab.ron"] {
+            if let Err(e) = collect_files_with_suffix(input_path, suffix, &mut file_paths) {
+                eprintln!("Could not read folder {:?}: {}", input_path, e);
+                return ExitCode::from(2);
+            }
+        }
+    }
+
+    file_paths.sort();
+
+    let mut error_count = 0;
+    let mut warning_count = 0;
+
+    for file_path in file_paths.iter() {
+        let is_prefab = file_path.to_string_lossy().ends_with(".prefab.ron");
+
+        let issues = match is_prefab {
+            true => PrefabFile::load_from_path(file_path).map(|f| validate_prefab_file(&f, &context)),
+            false => ZoneFileV2::load_from_path(file_path).map(|f| validate_zone_file(&f, &context)),
+        };
+
+        // load_from_path already printed why
+        let Some(issues) = issues else {
+            return ExitCode::from(2);
+        };
+
+        for issue in issues.iter() {
+            let label = match issue.severity() {
+                ZoneValidationSeverity::Error => {
+                    error_count += 1;
+                    "error"
+                }
+                ZoneValidationSeverity::Warning => {
+                    warning_count += 1;
+                    "warning"
+                }
+            };
+
+            println!("{}: {}: {}", file_path.display(), label, issue);
+        }
+    }
+
+    println!(
+        "checked {} files: {} errors, {} warnings",
+        file_paths.len(),
+        error_count,
+        warning_count
+    );
+
+    match error_count + warning_count {
+        0 => ExitCode::SUCCESS,
+        _ => ExitCode::from(1),
+    }
+}
@@ -2,6 +2,8 @@
 
 use std::collections::BTreeMap;
 use crate::zones::zone_file::CustomPropsMap;
+use crate::doodads::prop_schema::{schema_default_props, CustomPropSchema};
+use crate::doodads::sockets::DoodadSocket;
 
 use bevy::platform::collections::hash_map::HashMap;
 
@@ -98,7 +100,37 @@ pub struct DoodadDefinition {
   //   pub spawnable_components: Option<Vec< SpawnableComponent  >>,
 
     pub material_override: Option<String>,
-    pub material_replacement_set: Option<  String  >
+    pub material_replacement_set: Option<  String  >,
+
+    pub prop_schema: Option<Vec<CustomPropSchema>>,
+
+    pub sockets: Option<Vec<DoodadSocket>>, // more can come from glTF empties, see sockets.rs
+}
+
+impl DoodadDefinition {
+
+    /// Props a new doodad starts with: the schema defaults, overridden by `initial_custom_props`.
+    pub fn get_initial_custom_props(&self) -> Option<CustomPropsMap> {
+
+        if self.initial_custom_props.is_none() && self.prop_schema.is_none() {
+            return None;
+        }
+
+        let mut props = self.prop_schema.as_ref()
+            .map(|schema| schema_default_props(schema))
+            .unwrap_or_default();
+
+        if let Some(initial_custom_props) = &self.initial_custom_props {
+            props.extend(initial_custom_props.clone());
+        }
+
+        Some(props)
+    }
+
+    pub fn get_prop_schema(&self, key: &str) -> Option<&CustomPropSchema> {
+
+        self.prop_schema.as_ref().and_then(|schema| schema.iter().find(|prop_schema| prop_schema.key == key))
+    }
 }
 /*
 impl DoodadManifest {
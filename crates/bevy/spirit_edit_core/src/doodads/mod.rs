@@ -17,7 +17,7 @@ use bevy::platform::collections::hash_map::HashMap;
 
 use crate::doodads::doodad_manifest::RenderableType;
  
-use crate::zones::zone_file::{CustomPropsComponent,CustomPropsMap};
+use crate::zones::zone_file::{CustomPropsComponent,CustomPropsMap,ZoneEntityId};
  
 
 /*
@@ -35,19 +35,22 @@ use self::doodad_manifest::{DoodadDefinition, DoodadManifest, DoodadDefinitionsR
  
 //pub mod material_overrides; 
 pub mod doodad_manifest;
+pub mod prop_schema;
 pub mod picking;
 pub mod doodad;
 pub mod rotate;
+pub mod sockets;
 //pub mod doodad_placement_preview;
 
 pub fn doodad_plugin(  app: &mut App ){
  
         //put this inside of zone plugin ?
          app
-             .add_event::< picking::SelectDoodadEvent>()
-             .add_event::< PlaceDoodadEvent>()
-              .add_event::< PlaceClayTileEvent>()
-             .add_event::< DoodadToolEvent>()
+             .add_plugins(sockets::doodad_sockets_plugin)
+             .add_message::< picking::SelectDoodadEvent>()
+             .add_message::< PlaceDoodadEvent>()
+              .add_message::< PlaceClayTileEvent>()
+             .add_message::< DoodadToolEvent>()
 
              .init_resource::<DoodadToolState>()
 
@@ -79,7 +82,7 @@ pub struct DoodadToolState {
 
 
 
-#[derive(Event)]
+#[derive(Message)]
 pub enum DoodadToolEvent {
     SetSelectedDoodad(Option<String>)
 }
@@ -93,7 +96,7 @@ pub struct DoodadNeedsModelAttached;
 
 
 
-#[derive(Event)]
+#[derive(Message)]
 pub struct PlaceDoodadEvent {
     pub position: Vec3,
     pub scale: Option<Vec3>,
@@ -101,7 +104,13 @@ pub struct PlaceDoodadEvent {
     pub doodad_name: String,
     pub custom_props: Option<CustomPropsMap>,
     pub force_parent: Option<Entity> ,
-    pub auto_select: bool
+    pub auto_select: bool,
+
+    // set when the doodad comes from a zone file, a new id is assigned otherwise
+    pub zone_entity_id: Option<ZoneEntityId>,
+
+    // placed by a tool, so the spawn becomes an undoable history entry
+    pub record_history: bool,
 
   //  pub clay_tile_block_data: Option<ClayTileBlock >, //dont love this but its K 
     // pub doodad_definition: DoodadDefinition
@@ -109,7 +118,7 @@ pub struct PlaceDoodadEvent {
 
 
 
-#[derive(Event)]
+#[derive(Message)]
 pub struct PlaceClayTileEvent {
     pub position: Vec3,
     pub scale: Option<Vec3>,
@@ -119,6 +128,10 @@ pub struct PlaceClayTileEvent {
     pub zone: Option<Entity> ,
 
     pub clay_tile_block_data: ClayTileBlock , //dont love this but its K 
+
+    pub zone_entity_id: Option<ZoneEntityId>,
+
+    pub record_history: bool,
     // pub doodad_definition: DoodadDefinition
 }
 
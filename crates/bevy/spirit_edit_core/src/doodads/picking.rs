@@ -6,7 +6,7 @@ use bevy::{input::keyboard::KeyboardInput, prelude::*};
 
 use super::{doodad::DoodadComponent, DoodadToolState, PlaceDoodadEvent};
 
-#[derive(Event)]
+#[derive(Message)]
 pub struct SelectDoodadEvent {
     pub entity: Entity,
 }
@@ -0,0 +1,259 @@
+use bevy::prelude::*;
+
+use serde::{Deserialize, Serialize};
+
+use crate::zones::zone_file::{CustomProp, CustomPropsMap};
+
+/*
+
+Typed custom props.
+
+A doodad definition can declare the props its doodads are expected to have, with a kind
+for each one.  The schema fills in defaults when a doodad is spawned, picks the widget the
+inspector uses for the prop and lets the zone validator report props of the wrong type.
+
+  prop_schema: Some([
+      ( key: "unit_spawn_table", kind: AssetPath( extensions: Some(["spawntable.ron"]) ) ),
+      ( key: "special_type", kind: Enum( choices: ["none", "boss", "elite"] ), default: Some(Enum("none")) ),
+      ( key: "aggro_radius", kind: Float( min: Some(0.0), max: Some(100.0) ), default: Some(Float(10.0)) ),
+  ]),
+
+Props without a schema entry still work as before.
+
+*/
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub enum CustomPropKind {
+    Vec2,
+    Vec3,
+    String,
+    Float {
+        #[serde(default)]
+        min: Option<f32>,
+        #[serde(default)]
+        max: Option<f32>,
+    },
+    Integer {
+        #[serde(default)]
+        min: Option<i32>,
+        #[serde(default)]
+        max: Option<i32>,
+    },
+    Boolean,
+    Color,
+    Enum {
+        choices: Vec<String>,
+    },
+    EntityRef,
+    List(Box<CustomPropKind>),
+    AssetPath {
+        // file name endings that are accepted, any file when None
+        #[serde(default)]
+        extensions: Option<Vec<String>>,
+    },
+}
+
+// a manifest can list min and max the wrong way around, `clamp` panics on that
+fn ordered_bounds<T: PartialOrd>(min: T, max: T) -> (T, T) {
+    if min > max { (max, min) } else { (min, max) }
+}
+
+impl CustomPropKind {
+    /// The value a prop of this kind starts with when the schema has no default.
+    pub fn zero_value(&self) -> CustomProp {
+        match self {
+            Self::Vec2 => CustomProp::Vec2(Vec2::ZERO),
+            Self::Vec3 => CustomProp::Vec3(Vec3::ZERO),
+            Self::String => CustomProp::String(String::new()),
+            Self::Float { min, max } => {
+                let (min, max) = ordered_bounds(min.unwrap_or(f32::MIN), max.unwrap_or(f32::MAX));
+                CustomProp::Float(0.0_f32.clamp(min, max))
+            }
+            Self::Integer { min, max } => {
+                let (min, max) = ordered_bounds(min.unwrap_or(i32::MIN), max.unwrap_or(i32::MAX));
+                CustomProp::Integer(0.clamp(min, max))
+            }
+            Self::Boolean => CustomProp::Boolean(false),
+            Self::Color => CustomProp::Color(Color::WHITE),
+            Self::Enum { choices } => CustomProp::Enum(choices.first().cloned().unwrap_or_default()),
+            Self::EntityRef => CustomProp::EntityRef(None),
+            Self::List(_) => CustomProp::List(Vec::new()),
+            Self::AssetPath { .. } => CustomProp::AssetPath(String::new()),
+        }
+    }
+
+    /// Whether the value has the right type and is within the range or choices.
+    pub fn accepts(&self, prop: &CustomProp) -> bool {
+        match (self, prop) {
+            (Self::Vec2, CustomProp::Vec2(_))
+            | (Self::Vec3, CustomProp::Vec3(_))
+            | (Self::String, CustomProp::String(_))
+            | (Self::Boolean, CustomProp::Boolean(_))
+            | (Self::Color, CustomProp::Color(_))
+            | (Self::EntityRef, CustomProp::EntityRef(_)) => true,
+
+            (Self::Float { min, max }, CustomProp::Float(value)) => {
+                let (min, max) = ordered_bounds(min.unwrap_or(f32::MIN), max.unwrap_or(f32::MAX));
+                (min..=max).contains(value)
+            }
+            (Self::Integer { min, max }, CustomProp::Integer(value)) => {
+                let (min, max) = ordered_bounds(min.unwrap_or(i32::MIN), max.unwrap_or(i32::MAX));
+                (min..=max).contains(value)
+            }
+            (Self::Enum { choices }, CustomProp::Enum(choice)) => choices.contains(choice),
+            (Self::List(item_kind), CustomProp::List(items)) => items.iter().all(|item| item_kind.accepts(item)),
+
+            // empty means not set yet
+            (Self::AssetPath { extensions }, CustomProp::AssetPath(path)) => {
+                path.is_empty()
+                    || extensions
+                        .as_ref()
+                        .is_none_or(|extensions| extensions.iter().any(|ext| path.ends_with(ext.as_str())))
+            }
+
+            _ => false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct CustomPropSchema {
+    pub key: String,
+    pub kind: CustomPropKind,
+
+    #[serde(default)]
+    pub default: Option<CustomProp>,
+
+    // shown as a tooltip in the inspector
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+impl CustomPropSchema {
+    pub fn default_value(&self) -> CustomProp {
+        self.default.clone().unwrap_or_else(|| self.kind.zero_value())
+    }
+}
+
+/// Every prop in the schema with its default value.
+pub fn schema_default_props(schema: &[CustomPropSchema]) -> CustomPropsMap {
+    schema
+        .iter()
+        .map(|prop_schema| (prop_schema.key.clone(), prop_schema.default_value()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema(key: &str, kind: CustomPropKind, default: Option<CustomProp>) -> CustomPropSchema {
+        CustomPropSchema {
+            key: key.to_string(),
+            kind,
+            default,
+            description: None,
+        }
+    }
+
+    #[test]
+    fn test_zero_value_is_within_bounds() {
+        let kind = CustomPropKind::Float { min: Some(2.0), max: Some(10.0) };
+        assert_eq!(kind.zero_value(), CustomProp::Float(2.0));
+
+        let kind = CustomPropKind::Integer { min: None, max: Some(-5) };
+        assert_eq!(kind.zero_value(), CustomProp::Integer(-5));
+
+        // reversed bounds are swapped instead of panicking in clamp
+        let kind = CustomPropKind::Float { min: Some(10.0), max: Some(2.0) };
+        assert_eq!(kind.zero_value(), CustomProp::Float(2.0));
+
+        let kind = CustomPropKind::Integer { min: Some(10), max: Some(2) };
+        assert_eq!(kind.zero_value(), CustomProp::Integer(2));
+
+        let kind = CustomPropKind::Enum { choices: vec!["none".into(), "boss".into()] };
+        assert_eq!(kind.zero_value(), CustomProp::Enum("none".into()));
+    }
+
+    #[test]
+    fn test_zero_value_is_accepted() {
+        let kinds = [
+            CustomPropKind::Vec2,
+            CustomPropKind::Vec3,
+            CustomPropKind::String,
+            CustomPropKind::Float { min: Some(10.0), max: Some(2.0) },
+            CustomPropKind::Integer { min: Some(1), max: None },
+            CustomPropKind::Boolean,
+            CustomPropKind::Color,
+            CustomPropKind::Enum { choices: vec!["none".into()] },
+            CustomPropKind::EntityRef,
+            CustomPropKind::List(Box::new(CustomPropKind::Boolean)),
+            CustomPropKind::AssetPath { extensions: Some(vec!["spawntable.ron".into()]) },
+        ];
+
+        for kind in kinds {
+            assert!(kind.accepts(&kind.zero_value()), "{:?} rejects its zero value", kind);
+        }
+    }
+
+    #[test]
+    fn test_accepts_checks_bounds() {
+        let kind = CustomPropKind::Float { min: Some(0.0), max: Some(100.0) };
+        assert!(kind.accepts(&CustomProp::Float(0.0)));
+        assert!(kind.accepts(&CustomProp::Float(100.0)));
+        assert!(!kind.accepts(&CustomProp::Float(-0.5)));
+        assert!(!kind.accepts(&CustomProp::Float(100.5)));
+        assert!(!kind.accepts(&CustomProp::Integer(50)));
+
+        // same range as above, written the wrong way around
+        let kind = CustomPropKind::Float { min: Some(100.0), max: Some(0.0) };
+        assert!(kind.accepts(&CustomProp::Float(50.0)));
+        assert!(!kind.accepts(&CustomProp::Float(150.0)));
+
+        let kind = CustomPropKind::Integer { min: Some(5), max: Some(-5) };
+        assert!(kind.accepts(&CustomProp::Integer(0)));
+        assert!(kind.accepts(&CustomProp::Integer(-5)));
+        assert!(!kind.accepts(&CustomProp::Integer(6)));
+
+        let kind = CustomPropKind::Integer { min: None, max: None };
+        assert!(kind.accepts(&CustomProp::Integer(i32::MIN)));
+        assert!(kind.accepts(&CustomProp::Integer(i32::MAX)));
+    }
+
+    #[test]
+    fn test_accepts_checks_choices_lists_and_paths() {
+        let kind = CustomPropKind::Enum { choices: vec!["none".into(), "boss".into()] };
+        assert!(kind.accepts(&CustomProp::Enum("boss".into())));
+        assert!(!kind.accepts(&CustomProp::Enum("elite".into())));
+        assert!(!kind.accepts(&CustomProp::String("boss".into())));
+
+        let kind = CustomPropKind::List(Box::new(CustomPropKind::Integer { min: Some(0), max: None }));
+        assert!(kind.accepts(&CustomProp::List(vec![CustomProp::Integer(1), CustomProp::Integer(2)])));
+        assert!(!kind.accepts(&CustomProp::List(vec![CustomProp::Integer(1), CustomProp::Integer(-2)])));
+
+        let kind = CustomPropKind::AssetPath { extensions: Some(vec!["spawntable.ron".into()]) };
+        assert!(kind.accepts(&CustomProp::AssetPath(String::new())));
+        assert!(kind.accepts(&CustomProp::AssetPath("tables/orcs.spawntable.ron".into())));
+        assert!(!kind.accepts(&CustomProp::AssetPath("models/orc.glb".into())));
+    }
+
+    #[test]
+    fn test_schema_default_props() {
+        let schema = [
+            schema(
+                "special_type",
+                CustomPropKind::Enum { choices: vec!["none".into(), "boss".into()] },
+                Some(CustomProp::Enum("boss".into())),
+            ),
+            schema("aggro_radius", CustomPropKind::Float { min: Some(1.0), max: Some(100.0) }, None),
+            schema("spawn_table", CustomPropKind::AssetPath { extensions: None }, None),
+        ];
+
+        let props = schema_default_props(&schema);
+
+        assert_eq!(props.len(), 3);
+        assert_eq!(props.get("special_type"), Some(&CustomProp::Enum("boss".into())));
+        assert_eq!(props.get("aggro_radius"), Some(&CustomProp::Float(1.0)));
+        assert_eq!(props.get("spawn_table"), Some(&CustomProp::AssetPath(String::new())));
+    }
+}
@@ -0,0 +1,431 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use bevy::scene::SceneInstanceReady;
+
+use serde::{Deserialize, Serialize};
+
+use crate::doodads::doodad::DoodadComponent;
+use crate::doodads::doodad_manifest::DoodadDefinition;
+
+/*
+
+Sockets are named attachment points on modular doodads (walls, floors, stairs of a kit).
+
+A socket faces out of its piece along its local forward (-Z). Two pieces snap when a socket of
+one can attach to a socket of the other, turning the new piece so the two sockets face each other.
+
+Sockets are declared in the doodad manifest or authored in the model as glTF empties named
+
+    socket_<tag>                                      snaps to other <tag> sockets
+    socket_<tag>__<compatible_tag>__<compatible_tag>  snaps to the listed tags
+
+A trailing blender duplicate suffix (.001) is ignored.
+
+*/
+
+pub const GLTF_SOCKET_PREFIX: &str = "socket_";
+
+const GLTF_COMPATIBLE_TAG_SEPARATOR: &str = "__";
+
+pub fn doodad_sockets_plugin(app: &mut App) {
+    app.add_observer(collect_gltf_sockets_on_scene_ready);
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DoodadSocket {
+    pub name: String,
+    pub position: Vec3,
+
+    #[serde(default)]
+    pub rotation_degrees: Vec3, // euler YXZ
+
+    pub tag: String,
+
+    // empty means the socket snaps to sockets with the same tag
+    #[serde(default)]
+    pub compatible_tags: Vec<String>,
+}
+
+impl DoodadSocket {
+    pub fn from_gltf_node_name(node_name: &str, local_transform: Transform) -> Option<Self> {
+        let socket_name = node_name.strip_prefix(GLTF_SOCKET_PREFIX)?;
+
+        // blender appends .001 to duplicated objects
+        let socket_name = match socket_name.rsplit_once('.') {
+            Some((stem, suffix)) if !suffix.is_empty() && suffix.chars().all(|c| c.is_ascii_digit()) => stem,
+            _ => socket_name,
+        };
+
+        let mut parts = socket_name.split(GLTF_COMPATIBLE_TAG_SEPARATOR);
+
+        let tag = parts.next().filter(|tag| !tag.is_empty())?.to_string();
+        let compatible_tags = parts.filter(|part| !part.is_empty()).map(|part| part.to_string()).collect();
+
+        let (y, x, z) = local_transform.rotation.to_euler(EulerRot::YXZ);
+
+        Some(Self {
+            name: node_name.to_string(),
+            position: local_transform.translation,
+            rotation_degrees: Vec3::new(y.to_degrees(), x.to_degrees(), z.to_degrees()),
+            tag,
+            compatible_tags,
+        })
+    }
+
+    pub fn rotation(&self) -> Quat {
+        Quat::from_euler(
+            EulerRot::YXZ,
+            self.rotation_degrees.x.to_radians(),
+            self.rotation_degrees.y.to_radians(),
+            self.rotation_degrees.z.to_radians(),
+        )
+    }
+
+    pub fn local_transform(&self) -> Transform {
+        Transform::from_translation(self.position).with_rotation(self.rotation())
+    }
+
+    fn accepts_tag(&self, tag: &str) -> bool {
+        match self.compatible_tags.is_empty() {
+            true => self.tag == tag,
+            false => self.compatible_tags.iter().any(|compatible_tag| compatible_tag == tag),
+        }
+    }
+
+    pub fn can_attach_to(&self, other: &DoodadSocket) -> bool {
+        self.accepts_tag(&other.tag) || other.accepts_tag(&self.tag)
+    }
+}
+
+// sockets read from the glTF empties of a spawned model, relative to the doodad root
+#[derive(Component, Clone, Debug, Default)]
+pub struct GltfSocketsComponent {
+    pub sockets: Vec<DoodadSocket>,
+}
+
+// collects model sockets for entities that are not doodads, like the placement preview
+#[derive(Component, Clone, Debug, Default)]
+pub struct CollectGltfSockets;
+
+pub fn doodad_sockets(
+    definition: Option<&DoodadDefinition>,
+    gltf_sockets: Option<&GltfSocketsComponent>,
+) -> Vec<DoodadSocket> {
+    let mut sockets: Vec<DoodadSocket> = definition
+        .and_then(|definition| definition.sockets.clone())
+        .unwrap_or_default();
+
+    if let Some(gltf_sockets) = gltf_sockets {
+        sockets.extend(gltf_sockets.sockets.iter().cloned());
+    }
+
+    sockets
+}
+
+#[derive(Clone, Debug)]
+pub struct SocketSnap {
+    pub transform: Transform, // world transform for the new doodad
+
+    pub socket_name: String,
+    pub target_entity: Entity,
+    pub target_socket_name: String,
+    pub target_socket_position: Vec3,
+}
+
+/*
+
+Finds the transform that puts one of `sockets` onto the nearest compatible target socket.
+`target_sockets` are in world space, `sockets` are relative to the doodad being placed
+and `placement` is where that doodad would go unsnapped.
+
+*/
+pub fn find_socket_snap(
+    placement: &Transform,
+    sockets: &[DoodadSocket],
+    target_sockets: &[(Entity, GlobalTransform, DoodadSocket)],
+    snap_distance: f32,
+) -> Option<SocketSnap> {
+    let mut best: Option<(f32, SocketSnap)> = None;
+
+    for socket in sockets {
+        let socket_local = socket.local_transform();
+        let unsnapped_socket_position = placement.transform_point(socket_local.translation);
+
+        for (target_entity, target_xform, target_socket) in target_sockets {
+            if !socket.can_attach_to(target_socket) {
+                continue;
+            }
+
+            let target_socket_world = target_xform.mul_transform(target_socket.local_transform()).compute_transform();
+
+            let distance = unsnapped_socket_position.distance(target_socket_world.translation);
+            if distance > snap_distance {
+                continue;
+            }
+
+            if best.as_ref().is_some_and(|(best_distance, _)| *best_distance <= distance) {
+                continue;
+            }
+
+            // turn around so the socket faces into the target socket
+            let facing_rotation = target_socket_world.rotation * Quat::from_rotation_y(PI);
+            let rotation = (facing_rotation * socket_local.rotation.inverse()).normalize();
+
+            let scaled_socket_offset = socket_local.translation * placement.scale;
+            let translation = target_socket_world.translation - rotation * scaled_socket_offset;
+
+            best = Some((
+                distance,
+                SocketSnap {
+                    transform: Transform {
+                        translation,
+                        rotation,
+                        scale: placement.scale,
+                    },
+                    socket_name: socket.name.clone(),
+                    target_entity: *target_entity,
+                    target_socket_name: target_socket.name.clone(),
+                    target_socket_position: target_socket_world.translation,
+                },
+            ));
+        }
+    }
+
+    best.map(|(_, snap)| snap)
+}
+
+// a glTF scene is spawned under its doodad, so node transforms are composed up to that doodad
+fn collect_gltf_sockets_on_scene_ready(
+    scene_ready: On<SceneInstanceReady>,
+
+    mut commands: Commands,
+
+    parent_query: Query<&ChildOf>,
+    children_query: Query<&Children>,
+    node_query: Query<(&Name, &Transform)>,
+    transform_query: Query<&Transform>,
+    doodad_query: Query<(), With<DoodadComponent>>,
+    collect_query: Query<(), With<CollectGltfSockets>>,
+) {
+    let scene_root = scene_ready.entity;
+
+    let Ok(doodad_entity) = parent_query.get(scene_root).map(|child_of| child_of.parent()) else {
+        return;
+    };
+
+    if !doodad_query.contains(doodad_entity) && !collect_query.contains(doodad_entity) {
+        return;
+    }
+
+    let mut sockets = Vec::new();
+
+    for node_entity in children_query.iter_descendants(scene_root) {
+        let Ok((node_name, node_transform)) = node_query.get(node_entity) else {
+            continue;
+        };
+
+        if !node_name.as_str().starts_with(GLTF_SOCKET_PREFIX) {
+            continue;
+        }
+
+        let mut relative_transform = *node_transform;
+        let mut current_entity = node_entity;
+
+        while let Ok(child_of) = parent_query.get(current_entity) {
+            let parent_entity = child_of.parent();
+            if parent_entity == doodad_entity {
+                break;
+            }
+
+            if let Ok(parent_transform) = transform_query.get(parent_entity) {
+                relative_transform = parent_transform.mul_transform(relative_transform);
+            }
+
+            current_entity = parent_entity;
+        }
+
+        if let Some(socket) = DoodadSocket::from_gltf_node_name(node_name.as_str(), relative_transform) {
+            sockets.push(socket);
+        }
+    }
+
+    if let Ok(mut cmd) = commands.get_entity(doodad_entity) {
+        cmd.try_insert(GltfSocketsComponent { sockets });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn socket(name: &str, position: Vec3, rotation_degrees: Vec3, tag: &str) -> DoodadSocket {
+        DoodadSocket {
+            name: name.to_string(),
+            position,
+            rotation_degrees,
+            tag: tag.to_string(),
+            compatible_tags: Vec::new(),
+        }
+    }
+
+    // faces out along -X of its piece
+    fn socket_west() -> DoodadSocket {
+        socket("west", Vec3::new(-1.0, 0.5, 0.0), Vec3::new(90.0, 0.0, 0.0), "wall")
+    }
+
+    fn assert_faces_target(
+        snap: &SocketSnap,
+        socket: &DoodadSocket,
+        target_xform: &GlobalTransform,
+        target_socket: &DoodadSocket,
+    ) {
+        let socket_world = snap.transform.mul_transform(socket.local_transform());
+        let target_world = target_xform.mul_transform(target_socket.local_transform()).compute_transform();
+
+        assert!(
+            socket_world.translation.abs_diff_eq(target_world.translation, 1e-4),
+            "{:?} != {:?}",
+            socket_world.translation,
+            target_world.translation
+        );
+
+        // forward is -Z, the two sockets point into each other
+        let facing = socket_world.forward().dot(*target_world.forward());
+        assert!((facing + 1.0).abs() < 1e-4, "sockets are not facing, dot {}", facing);
+    }
+
+    #[test]
+    fn test_from_gltf_node_name_tag() {
+        let socket = DoodadSocket::from_gltf_node_name("socket_wall", Transform::from_xyz(1.0, 2.0, 3.0)).unwrap();
+
+        assert_eq!(socket.name, "socket_wall");
+        assert_eq!(socket.tag, "wall");
+        assert!(socket.compatible_tags.is_empty());
+        assert_eq!(socket.position, Vec3::new(1.0, 2.0, 3.0));
+    }
+
+    #[test]
+    fn test_from_gltf_node_name_compatible_tags() {
+        let socket = DoodadSocket::from_gltf_node_name("socket_door__wall__arch", Transform::IDENTITY).unwrap();
+
+        assert_eq!(socket.tag, "door");
+        assert_eq!(socket.compatible_tags, vec!["wall".to_string(), "arch".to_string()]);
+
+        // empty parts from doubled separators are dropped
+        let socket = DoodadSocket::from_gltf_node_name("socket_door____wall__", Transform::IDENTITY).unwrap();
+        assert_eq!(socket.compatible_tags, vec!["wall".to_string()]);
+    }
+
+    #[test]
+    fn test_from_gltf_node_name_duplicate_suffix() {
+        let socket = DoodadSocket::from_gltf_node_name("socket_floor__stair.001", Transform::IDENTITY).unwrap();
+
+        assert_eq!(socket.name, "socket_floor__stair.001");
+        assert_eq!(socket.tag, "floor");
+        assert_eq!(socket.compatible_tags, vec!["stair".to_string()]);
+
+        // only a numeric suffix is a blender duplicate
+        let socket = DoodadSocket::from_gltf_node_name("socket_floor.big", Transform::IDENTITY).unwrap();
+        assert_eq!(socket.tag, "floor.big");
+    }
+
+    #[test]
+    fn test_from_gltf_node_name_rejects() {
+        assert!(DoodadSocket::from_gltf_node_name("wall_socket", Transform::IDENTITY).is_none());
+        assert!(DoodadSocket::from_gltf_node_name("socket_", Transform::IDENTITY).is_none());
+        assert!(DoodadSocket::from_gltf_node_name("socket___wall", Transform::IDENTITY).is_none());
+        assert!(DoodadSocket::from_gltf_node_name("socket_.001", Transform::IDENTITY).is_none());
+    }
+
+    #[test]
+    fn test_from_gltf_node_name_keeps_rotation() {
+        let rotation = Quat::from_euler(EulerRot::YXZ, 0.8, 0.3, -0.4);
+        let socket = DoodadSocket::from_gltf_node_name("socket_wall", Transform::from_rotation(rotation)).unwrap();
+
+        assert!(socket.rotation().abs_diff_eq(rotation, 1e-5));
+    }
+
+    #[test]
+    fn test_can_attach_to() {
+        let wall = socket("a", Vec3::ZERO, Vec3::ZERO, "wall");
+        let floor = socket("b", Vec3::ZERO, Vec3::ZERO, "floor");
+        let door = DoodadSocket {
+            compatible_tags: vec!["wall".to_string()],
+            ..socket("c", Vec3::ZERO, Vec3::ZERO, "door")
+        };
+
+        assert!(wall.can_attach_to(&wall));
+        assert!(!wall.can_attach_to(&floor));
+        assert!(door.can_attach_to(&wall));
+        assert!(wall.can_attach_to(&door));
+        assert!(!door.can_attach_to(&floor));
+    }
+
+    #[test]
+    fn test_find_socket_snap_faces_target() {
+        let socket = socket("east", Vec3::new(1.0, 0.0, 0.0), Vec3::new(-90.0, 0.0, 0.0), "wall");
+
+        let target_entity = Entity::from_raw_u32(5).unwrap();
+        let target_xform = GlobalTransform::from(
+            Transform::from_xyz(10.0, 2.0, -4.0).with_rotation(Quat::from_rotation_y(0.6)),
+        );
+        let target_socket = socket_west();
+
+        let target_world = target_xform.transform_point(target_socket.position);
+        let placement = Transform::from_translation(target_world + Vec3::new(-0.5, 0.0, 0.3));
+
+        let snap = find_socket_snap(
+            &placement,
+            std::slice::from_ref(&socket),
+            &[(target_entity, target_xform, target_socket.clone())],
+            2.0,
+        )
+        .unwrap();
+
+        assert_eq!(snap.target_entity, target_entity);
+        assert_eq!(snap.socket_name, "east");
+        assert_eq!(snap.target_socket_name, "west");
+        assert!(snap.target_socket_position.abs_diff_eq(target_world, 1e-4));
+        assert_faces_target(&snap, &socket, &target_xform, &target_socket);
+    }
+
+    #[test]
+    fn test_find_socket_snap_keeps_placement_scale() {
+        let socket = socket("east", Vec3::new(1.0, 0.0, 0.0), Vec3::new(-90.0, 0.0, 0.0), "wall");
+
+        let target_xform = GlobalTransform::IDENTITY;
+        let target_socket = socket_west();
+
+        let placement = Transform::from_xyz(-3.0, 0.0, 0.0).with_scale(Vec3::splat(2.0));
+
+        let snap = find_socket_snap(
+            &placement,
+            std::slice::from_ref(&socket),
+            &[(Entity::PLACEHOLDER, target_xform, target_socket.clone())],
+            5.0,
+        )
+        .unwrap();
+
+        assert_eq!(snap.transform.scale, Vec3::splat(2.0));
+        assert_faces_target(&snap, &socket, &target_xform, &target_socket);
+    }
+
+    #[test]
+    fn test_find_socket_snap_picks_nearest_compatible() {
+        let sockets = [socket("east", Vec3::ZERO, Vec3::ZERO, "wall")];
+
+        let target = |x: f32, name: &str, tag: &str| {
+            (Entity::PLACEHOLDER, GlobalTransform::from_xyz(x, 0.0, 0.0), socket(name, Vec3::ZERO, Vec3::ZERO, tag))
+        };
+
+        // the floor socket is closest but does not fit
+        let targets = [target(0.1, "floor", "floor"), target(1.5, "far", "wall"), target(-0.5, "near", "wall")];
+
+        let snap = find_socket_snap(&Transform::IDENTITY, &sockets, &targets, 2.0).unwrap();
+        assert_eq!(snap.target_socket_name, "near");
+
+        assert!(find_socket_snap(&Transform::from_xyz(10.0, 0.0, 0.0), &sockets, &targets, 2.0).is_none());
+        assert!(find_socket_snap(&Transform::IDENTITY, &sockets, &targets[..1], 2.0).is_none());
+    }
+}
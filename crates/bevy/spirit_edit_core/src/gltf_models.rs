@@ -103,7 +103,7 @@ fn add_gltf_model_scenes_on_added(
 
 fn add_gltf_model_scenes_on_load(
 		mut commands: Commands, 
-	 mut asset_ready_event: EventReader<AssetEvent<Gltf>>,
+	 mut asset_ready_event: MessageReader<AssetEvent<Gltf>>,
 	 gltf_assets: Res<Assets<Gltf>>,
 
 	 entity_query: Query<(Entity, &AddGltfModelComponent ) >,
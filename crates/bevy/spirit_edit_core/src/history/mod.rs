@@ -0,0 +1,922 @@
+use crate::doodads::doodad::DoodadComponent;
+use crate::doodads::DoodadProto;
+use crate::prefabs::prefab_overrides::{prefab_instance_overrides_in_world, PrefabOverrides, PrefabOverridesComponent};
+use crate::prefabs::PrefabComponent;
+use crate::zones::zone_file::{CustomPropsComponent, CustomPropsMap, ZoneEntityId};
+use bevy_clay_tiles::clay_tile_block::ClayTileBlock;
+
+use bevy::platform::collections::hash_map::HashMap;
+use bevy::platform::collections::hash_set::HashSet;
+use bevy::prelude::*;
+
+/*
+
+Reversible command stack for scene entity operations (doodads, clay tiles and prefabs).
+
+Every tool records its own commands through EditorHistoryEvent:
+
+ - placing, cloning and spawning prefabs record the spawned entities (RecordSpawned)
+ - deletes go through the history so the entities are snapshotted first (DeleteEntities)
+ - gizmo drags bracket the drag with BeginEdit / EndEdit
+ - brushes that spawn or delete every frame bracket the stroke with BeginStroke / EndStroke,
+   everything recorded in between goes into one entry
+ - the inspector records the custom props it changed (Record)
+
+Entities that are spawned any other way (zone loading, streaming, prefab children) are
+never part of the history.  Edits of prefab children are, but the children are spawned again
+whenever their instance is rebuilt, so those commands find their entity by its ZoneEntityId.
+
+*/
+
+// consecutive edits of the same entities within this window collapse into one entry (typing in the inspector)
+const MERGE_WINDOW_SECS: f64 = 0.75;
+
+const MAX_HISTORY_ENTRIES: usize = 256;
+
+pub fn history_plugin(app: &mut App) {
+    app
+        .init_resource::<EditorHistoryResource>()
+        .add_message::<EditorHistoryEvent>()
+
+        // after Update so the entities spawned by the placement handlers exist, and before
+        // the gizmo moves anything in Last so a drag starts from the state before it
+        .add_systems(PostUpdate, (
+            handle_editor_history_events,
+            apply_editor_history_requests,
+        ).chain());
+}
+
+#[derive(Message, Clone, Debug)]
+pub enum EditorHistoryEvent {
+    Undo,
+    Redo,
+    JumpTo(usize), // number of applied entries, 0 is the state before the first entry
+    Clear,
+
+    // entities a tool just spawned, recorded as one entry
+    RecordSpawned(Vec<Entity>),
+    // despawns the entities with their children as one entry
+    DeleteEntities(Vec<Entity>),
+    // snapshots the entities before an edit, EndEdit records what changed since
+    BeginEdit(Vec<Entity>),
+    EndEdit,
+    // commands the tool built itself
+    Record(Vec<EditorCommand>),
+    // everything recorded until EndStroke extends the same entry
+    BeginStroke,
+    EndStroke,
+}
+
+#[derive(Clone, Debug)]
+pub enum SceneEntityKind {
+    Doodad { name: String },
+    ClayTile { clay_tile_block: ClayTileBlock },
+    // the overrides are worked out from the children, see SceneEntitySnapshot::from_world
+    Prefab { name: String, overrides: Option<PrefabOverrides> },
+}
+
+#[derive(Clone, Debug)]
+pub struct SceneEntitySnapshot {
+    pub kind: SceneEntityKind,
+    pub transform: Transform,
+    pub custom_props: Option<CustomPropsMap>,
+    pub parent: Option<Entity>,
+
+    // kept across a delete and undo, entity refs and prefab overrides point at it
+    pub zone_entity_id: Option<ZoneEntityId>,
+}
+
+impl SceneEntitySnapshot {
+    fn from_entity_ref(entity_ref: &EntityRef) -> Option<Self> {
+        let name = entity_ref.get::<Name>()?;
+        let transform = entity_ref.get::<Transform>()?;
+
+        let kind = if let Some(clay_tile_block) = entity_ref.get::<ClayTileBlock>() {
+            SceneEntityKind::ClayTile {
+                clay_tile_block: clay_tile_block.clone(),
+            }
+        } else if entity_ref.contains::<PrefabComponent>() {
+            SceneEntityKind::Prefab {
+                name: name.as_str().to_string(),
+                overrides: None,
+            }
+        } else if entity_ref.contains::<DoodadComponent>() || entity_ref.contains::<DoodadProto>() {
+            SceneEntityKind::Doodad {
+                name: name.as_str().to_string(),
+            }
+        } else {
+            return None;
+        };
+
+        Some(Self {
+            kind,
+            transform: *transform,
+            custom_props: entity_ref
+                .get::<CustomPropsComponent>()
+                .map(|comp| comp.props.clone()),
+            parent: entity_ref.get::<ChildOf>().map(|child_of| child_of.parent()),
+            zone_entity_id: entity_ref.get::<ZoneEntityId>().copied(),
+        })
+    }
+
+    pub fn from_world(world: &World, entity: Entity) -> Option<Self> {
+        let entity_ref = world.get_entity(entity).ok()?;
+        let mut snapshot = Self::from_entity_ref(&entity_ref)?;
+
+        // a respawned instance rebuilds its children from these, so edited children come back edited
+        if let SceneEntityKind::Prefab { name, overrides } = &mut snapshot.kind {
+            *overrides = prefab_instance_overrides_in_world(world, entity, name)
+                .filter(|instance_overrides| !instance_overrides.is_empty());
+        }
+
+        Some(snapshot)
+    }
+
+    pub fn display_name(&self) -> &str {
+        match &self.kind {
+            SceneEntityKind::Doodad { name } => name,
+            SceneEntityKind::ClayTile { .. } => "ClayTileBlock",
+            SceneEntityKind::Prefab { name, .. } => name,
+        }
+    }
+
+    pub fn transform_differs(&self, other: &Self) -> bool {
+        self.transform != other.transform || self.parent != other.parent
+    }
+
+    pub fn custom_props_differ(&self, other: &Self) -> bool {
+        self.custom_props != other.custom_props
+    }
+
+    // spawns the entity the same way the placement handlers do, so models and prefab children get attached again
+    fn respawn(&self, world: &mut World) -> Entity {
+        let mut entity_mut = world.spawn((self.transform, Visibility::default()));
+
+        match &self.kind {
+            SceneEntityKind::Doodad { name } => {
+                entity_mut.insert((
+                    Name::new(name.clone()),
+                    DoodadProto,
+                    CustomPropsComponent {
+                        props: self.custom_props.clone().unwrap_or_default(),
+                    },
+                ));
+            }
+            SceneEntityKind::ClayTile { clay_tile_block } => {
+                entity_mut.insert((
+                    Name::new("ClayTileBlock"),
+                    DoodadProto,
+                    clay_tile_block.clone(),
+                ));
+            }
+            SceneEntityKind::Prefab { name, overrides } => {
+                entity_mut.insert((Name::new(name.clone()), PrefabComponent));
+
+                if let Some(overrides) = overrides {
+                    entity_mut.insert(PrefabOverridesComponent(overrides.clone()));
+                }
+            }
+        }
+
+        if let Some(zone_entity_id) = self.zone_entity_id {
+            entity_mut.insert(zone_entity_id);
+        }
+
+        entity_mut.id()
+    }
+
+    fn write_to(&self, world: &mut World, entity: Entity) {
+        let Ok(mut entity_mut) = world.get_entity_mut(entity) else {
+            return;
+        };
+
+        entity_mut.insert(self.transform);
+
+        match &self.custom_props {
+            Some(props) => {
+                entity_mut.insert(CustomPropsComponent {
+                    props: props.clone(),
+                });
+            }
+            None => {
+                entity_mut.remove::<CustomPropsComponent>();
+            }
+        }
+
+        // only touched when it changed, inserting ChildOf again would move the entity to the end of its siblings
+        let current_parent = entity_mut.get::<ChildOf>().map(|child_of| child_of.parent());
+
+        if current_parent == self.parent {
+            return;
+        }
+
+        match self.parent {
+            Some(parent) => {
+                if world.get_entity(parent).is_ok() {
+                    world.entity_mut(entity).insert(ChildOf(parent));
+                }
+            }
+            None => {
+                world.entity_mut(entity).remove::<ChildOf>();
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum EditorCommand {
+    Spawn {
+        entity: Entity,
+        snapshot: SceneEntitySnapshot,
+    },
+    Despawn {
+        entity: Entity,
+        snapshot: SceneEntitySnapshot,
+    },
+    Modify {
+        entity: Entity,
+        before: SceneEntitySnapshot,
+        after: SceneEntitySnapshot,
+    },
+}
+
+impl EditorCommand {
+    pub fn entity(&self) -> Entity {
+        match self {
+            Self::Spawn { entity, .. } => *entity,
+            Self::Despawn { entity, .. } => *entity,
+            Self::Modify { entity, .. } => *entity,
+        }
+    }
+
+    pub fn description(&self) -> String {
+        match self {
+            Self::Spawn { snapshot, .. } => format!("Place {}", snapshot.display_name()),
+            Self::Despawn { snapshot, .. } => format!("Delete {}", snapshot.display_name()),
+            Self::Modify { before, after, .. } => {
+                if before.transform_differs(after) {
+                    format!("Transform {}", after.display_name())
+                } else {
+                    format!("Edit custom props of {}", after.display_name())
+                }
+            }
+        }
+    }
+
+    fn verb(&self) -> &'static str {
+        match self {
+            Self::Spawn { .. } => "Place",
+            Self::Despawn { .. } => "Delete",
+            Self::Modify { before, after, .. } => {
+                if before.transform_differs(after) {
+                    "Transform"
+                } else {
+                    "Edit"
+                }
+            }
+        }
+    }
+
+    // the snapshot the entity is respawned from when undoing (or redoing) respawns it
+    fn respawn_snapshot(&self, undo: bool) -> Option<&SceneEntitySnapshot> {
+        match (self, undo) {
+            (Self::Despawn { snapshot, .. }, true) => Some(snapshot),
+            (Self::Spawn { snapshot, .. }, false) => Some(snapshot),
+            _ => None,
+        }
+    }
+
+    fn refers_to(&self, entities: &HashSet<Entity>) -> bool {
+        let parent = match self {
+            Self::Spawn { snapshot, .. } | Self::Despawn { snapshot, .. } => snapshot.parent,
+            Self::Modify { after, .. } => after.parent,
+        };
+
+        entities.contains(&self.entity()) || parent.is_some_and(|parent| entities.contains(&parent))
+    }
+
+    fn undo(&self, world: &mut World, history: &mut EditorHistoryResource) {
+        match self {
+            Self::Spawn { entity, .. } => despawn_entity(world, *entity),
+            Self::Despawn { entity, snapshot } => history.respawn_tracked(world, *entity, snapshot),
+            Self::Modify { entity, before, .. } => {
+                if let Some(entity) = history.resolve_entity(world, *entity, before.zone_entity_id) {
+                    before.write_to(world, entity);
+                }
+            }
+        }
+    }
+
+    fn redo(&self, world: &mut World, history: &mut EditorHistoryResource) {
+        match self {
+            Self::Spawn { entity, snapshot } => history.respawn_tracked(world, *entity, snapshot),
+            Self::Despawn { entity, .. } => despawn_entity(world, *entity),
+            Self::Modify { entity, after, .. } => {
+                if let Some(entity) = history.resolve_entity(world, *entity, after.zone_entity_id) {
+                    after.write_to(world, entity);
+                }
+            }
+        }
+    }
+
+    fn remap_entity(&mut self, from: Entity, to: Entity) {
+        let (entity, snapshots): (&mut Entity, Vec<&mut SceneEntitySnapshot>) = match self {
+            Self::Spawn { entity, snapshot } => (entity, vec![snapshot]),
+            Self::Despawn { entity, snapshot } => (entity, vec![snapshot]),
+            Self::Modify { entity, before, after } => (entity, vec![before, after]),
+        };
+
+        if *entity == from {
+            *entity = to;
+        }
+
+        for snapshot in snapshots {
+            if snapshot.parent == Some(from) {
+                snapshot.parent = Some(to);
+            }
+        }
+    }
+}
+
+fn despawn_entity(world: &mut World, entity: Entity) {
+    if let Ok(entity_mut) = world.get_entity_mut(entity) {
+        entity_mut.despawn();
+    }
+}
+
+// the order respawns have to run in: parents first, so every child has a live parent to be added to
+fn parent_first_order(commands: &[EditorCommand], indices: Vec<usize>, undo: bool) -> Vec<usize> {
+    let parent_of = |index: usize| {
+        commands[index]
+            .respawn_snapshot(undo)
+            .and_then(|snapshot| snapshot.parent)
+    };
+
+    let mut ordered = Vec::with_capacity(indices.len());
+    let mut pending = indices;
+
+    while !pending.is_empty() {
+        let pending_entities: HashSet<Entity> = pending.iter().map(|index| commands[*index].entity()).collect();
+
+        let (ready, waiting): (Vec<usize>, Vec<usize>) = pending
+            .into_iter()
+            .partition(|index| parent_of(*index).is_none_or(|parent| !pending_entities.contains(&parent)));
+
+        // only a parent cycle leaves nothing ready, respawn the rest in recorded order
+        if ready.is_empty() {
+            ordered.extend(waiting);
+            break;
+        }
+
+        ordered.extend(ready);
+        pending = waiting;
+    }
+
+    ordered
+}
+
+#[derive(Clone, Debug)]
+pub struct HistoryEntry {
+    pub description: String,
+    pub commands: Vec<EditorCommand>,
+    recorded_at: f64,
+}
+
+impl HistoryEntry {
+    fn new(commands: Vec<EditorCommand>, recorded_at: f64) -> Self {
+        let mut entry = Self {
+            description: String::new(),
+            commands,
+            recorded_at,
+        };
+        entry.refresh_description();
+        entry
+    }
+
+    fn refresh_description(&mut self) {
+        self.description = match self.commands.as_slice() {
+            [single] => single.description(),
+            commands => {
+                let verb = commands[0].verb();
+                let same_verb = commands.iter().all(|command| command.verb() == verb);
+
+                format!(
+                    "{} {} entities",
+                    if same_verb { verb } else { "Edit" },
+                    commands.len()
+                )
+            }
+        };
+    }
+
+    // only pure modifications of exactly the same entities are merged
+    fn can_merge(&self, commands: &[EditorCommand]) -> bool {
+        let is_modify = |command: &EditorCommand| matches!(command, EditorCommand::Modify { .. });
+
+        if !self.commands.iter().all(is_modify) || !commands.iter().all(is_modify) {
+            return false;
+        }
+
+        let own: HashSet<Entity> = self.commands.iter().map(|c| c.entity()).collect();
+        let other: HashSet<Entity> = commands.iter().map(|c| c.entity()).collect();
+
+        own == other
+    }
+
+    fn merge(&mut self, commands: Vec<EditorCommand>, recorded_at: f64) {
+        for command in commands {
+            let EditorCommand::Modify { entity, after, .. } = command else {
+                continue;
+            };
+
+            for existing in self.commands.iter_mut() {
+                if let EditorCommand::Modify {
+                    entity: existing_entity,
+                    after: existing_after,
+                    ..
+                } = existing
+                {
+                    if *existing_entity == entity {
+                        *existing_after = after.clone();
+                    }
+                }
+            }
+        }
+
+        self.recorded_at = recorded_at;
+        self.refresh_description();
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct EditorHistoryResource {
+    entries: Vec<HistoryEntry>,
+    cursor: usize, // entries[..cursor] are applied
+
+    // snapshots taken by BeginEdit, waiting for the EndEdit
+    edit_baselines: HashMap<Entity, SceneEntitySnapshot>,
+
+    // respawned entities whose parent did not exist yet, keyed by that parent
+    orphans: HashMap<Entity, Vec<Entity>>,
+
+    allow_merge: bool,
+
+    // between BeginStroke and EndStroke, and whether the stroke has recorded its entry yet
+    stroke_active: bool,
+    stroke_recorded: bool,
+
+    requests: Vec<EditorHistoryEvent>,
+}
+
+impl EditorHistoryResource {
+    pub fn entries(&self) -> &[HistoryEntry] {
+        &self.entries
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Entities that leave the scene without being deleted, like a zone streaming out. Their
+    /// commands are dropped, undoing them would bring back parts of an unloaded zone.
+    pub fn forget_entities(&mut self, entities: impl IntoIterator<Item = Entity>) {
+        let forgotten: HashSet<Entity> = entities.into_iter().collect();
+
+        let mut index = 0;
+        while index < self.entries.len() {
+            let entry = &mut self.entries[index];
+            entry.commands.retain(|command| !command.refers_to(&forgotten));
+
+            if entry.commands.is_empty() {
+                self.entries.remove(index);
+                if index < self.cursor {
+                    self.cursor -= 1;
+                }
+                continue;
+            }
+
+            entry.refresh_description();
+            index += 1;
+        }
+
+        self.edit_baselines.retain(|entity, _| !forgotten.contains(entity));
+        self.orphans.retain(|parent, _| !forgotten.contains(parent));
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.cursor > 0
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.cursor < self.entries.len()
+    }
+
+    pub fn record(&mut self, commands: Vec<EditorCommand>, now: f64) {
+        if commands.is_empty() {
+            return;
+        }
+
+        // recording after an undo discards the redo branch
+        self.entries.truncate(self.cursor);
+
+        if self.stroke_active && self.stroke_recorded {
+            if let Some(last) = self.entries.last_mut() {
+                last.commands.extend(commands);
+                last.recorded_at = now;
+                last.refresh_description();
+                return;
+            }
+        }
+
+        if self.allow_merge && !self.stroke_active {
+            if let Some(last) = self.entries.last_mut() {
+                if now - last.recorded_at < MERGE_WINDOW_SECS && last.can_merge(&commands) {
+                    last.merge(commands, now);
+                    return;
+                }
+            }
+        }
+
+        self.entries.push(HistoryEntry::new(commands, now));
+
+        if self.entries.len() > MAX_HISTORY_ENTRIES {
+            let overflow = self.entries.len() - MAX_HISTORY_ENTRIES;
+            self.entries.drain(..overflow);
+        }
+
+        self.cursor = self.entries.len();
+        self.allow_merge = true;
+        self.stroke_recorded = self.stroke_active;
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.cursor = 0;
+        self.edit_baselines.clear();
+        self.orphans.clear();
+        self.allow_merge = false;
+        self.stroke_recorded = false;
+    }
+
+    fn record_spawned(&mut self, world: &World, entities: &[Entity], now: f64) {
+        let commands = entities
+            .iter()
+            .filter_map(|entity| {
+                SceneEntitySnapshot::from_world(world, *entity).map(|snapshot| EditorCommand::Spawn {
+                    entity: *entity,
+                    snapshot,
+                })
+            })
+            .collect();
+
+        self.record(commands, now);
+    }
+
+    fn delete_entities(&mut self, world: &mut World, entities: &[Entity], now: f64) {
+        let mut commands = Vec::new();
+        let mut visited: HashSet<Entity> = HashSet::new();
+
+        // depth first from every root, so parents are recorded before their children
+        let mut stack: Vec<Entity> = entities.iter().rev().copied().collect();
+        while let Some(entity) = stack.pop() {
+            if !visited.insert(entity) {
+                continue;
+            }
+
+            let Ok(entity_ref) = world.get_entity(entity) else {
+                continue;
+            };
+
+            // prefab children are rebuilt from the prefab file when the prefab is respawned
+            if !entity_ref.contains::<PrefabComponent>() {
+                if let Some(children) = entity_ref.get::<Children>() {
+                    stack.extend(children.iter().rev());
+                }
+            }
+
+            if let Some(snapshot) = SceneEntitySnapshot::from_world(world, entity) {
+                commands.push(EditorCommand::Despawn { entity, snapshot });
+            }
+        }
+
+        for entity in entities {
+            despawn_entity(world, *entity);
+        }
+
+        self.record(commands, now);
+    }
+
+    fn begin_edit(&mut self, world: &World, entities: &[Entity]) {
+        self.edit_baselines.clear();
+
+        for entity in entities {
+            if let Some(snapshot) = SceneEntitySnapshot::from_world(world, *entity) {
+                self.edit_baselines.insert(*entity, snapshot);
+            }
+        }
+    }
+
+    fn end_edit(&mut self, world: &World, now: f64) {
+        let mut commands = Vec::new();
+
+        for (entity, before) in self.edit_baselines.drain() {
+            let Some(after) = SceneEntitySnapshot::from_world(world, entity) else {
+                continue;
+            };
+
+            if before.transform_differs(&after) || before.custom_props_differ(&after) {
+                commands.push(EditorCommand::Modify { entity, before, after });
+            }
+        }
+
+        self.record(commands, now);
+    }
+
+    fn respawn_tracked(&mut self, world: &mut World, entity: Entity, snapshot: &SceneEntitySnapshot) {
+        let spawned = snapshot.respawn(world);
+
+        match snapshot.parent {
+            Some(parent) if world.get_entity(parent).is_ok() => {
+                world.entity_mut(parent).add_child(spawned);
+            }
+            Some(parent) => {
+                self.orphans.entry(parent).or_default().push(spawned);
+            }
+            None => {}
+        }
+
+        // the respawned entity gets a new id, so every command referring to the old one has to follow
+        for entry in self.entries.iter_mut() {
+            for command in entry.commands.iter_mut() {
+                command.remap_entity(entity, spawned);
+            }
+        }
+
+        for orphans in self.orphans.values_mut() {
+            for orphan in orphans.iter_mut() {
+                if *orphan == entity {
+                    *orphan = spawned;
+                }
+            }
+        }
+
+        if let Some(baseline) = self.edit_baselines.remove(&entity) {
+            self.edit_baselines.insert(spawned, baseline);
+        }
+
+        // children that came back before this entity are added to it now
+        for orphan in self.orphans.remove(&entity).unwrap_or_default() {
+            if world.get_entity(orphan).is_ok() {
+                world.entity_mut(spawned).add_child(orphan);
+            }
+        }
+    }
+
+    // the entity a command was recorded for, or the one that took its place: prefab children are
+    // spawned again when their instance is rebuilt, with the same ZoneEntityId
+    fn resolve_entity(&mut self, world: &mut World, entity: Entity, zone_entity_id: Option<ZoneEntityId>) -> Option<Entity> {
+        if world.get_entity(entity).is_ok() {
+            return Some(entity);
+        }
+
+        let zone_entity_id = zone_entity_id?;
+
+        let replacement = world
+            .query::<(Entity, &ZoneEntityId)>()
+            .iter(world)
+            .find(|(_, id)| **id == zone_entity_id)
+            .map(|(replacement, _)| replacement)?;
+
+        for entry in self.entries.iter_mut() {
+            for command in entry.commands.iter_mut() {
+                command.remap_entity(entity, replacement);
+            }
+        }
+
+        Some(replacement)
+    }
+
+    fn apply_entry(&mut self, world: &mut World, entry_index: usize, undo: bool) {
+        let command_count = self.entries[entry_index].commands.len();
+
+        let (respawns, others): (Vec<usize>, Vec<usize>) = (0..command_count)
+            .partition(|index| self.entries[entry_index].commands[*index].respawn_snapshot(undo).is_some());
+
+        let others = if undo { others.into_iter().rev().collect() } else { others };
+        let respawns = parent_first_order(&self.entries[entry_index].commands, respawns, undo);
+
+        // commands are read again every step, a respawn remaps the parents of the ones after it
+        for index in others.into_iter().chain(respawns) {
+            let command = self.entries[entry_index].commands[index].clone();
+
+            if undo {
+                command.undo(world, self);
+            } else {
+                command.redo(world, self);
+            }
+        }
+    }
+
+    fn undo(&mut self, world: &mut World) {
+        if !self.can_undo() {
+            return;
+        }
+
+        self.cursor -= 1;
+        self.apply_entry(world, self.cursor, true);
+    }
+
+    fn redo(&mut self, world: &mut World) {
+        if !self.can_redo() {
+            return;
+        }
+
+        self.apply_entry(world, self.cursor, false);
+        self.cursor += 1;
+    }
+}
+
+fn handle_editor_history_events(
+    mut evt_reader: MessageReader<EditorHistoryEvent>,
+    mut history: ResMut<EditorHistoryResource>,
+) {
+    for evt in evt_reader.read() {
+        history.requests.push(evt.clone());
+    }
+}
+
+fn apply_editor_history_requests(world: &mut World) {
+    let now = world.resource::<Time>().elapsed_secs_f64();
+
+    world.resource_scope(|world, mut history: Mut<EditorHistoryResource>| {
+        let requests: Vec<EditorHistoryEvent> = history.requests.drain(..).collect();
+
+        for request in requests {
+            match request {
+                EditorHistoryEvent::Undo => history.undo(world),
+                EditorHistoryEvent::Redo => history.redo(world),
+                EditorHistoryEvent::JumpTo(target) => {
+                    let target = target.min(history.entries.len());
+
+                    while history.cursor > target {
+                        history.undo(world);
+                    }
+                    while history.cursor < target {
+                        history.redo(world);
+                    }
+                }
+                EditorHistoryEvent::Clear => history.clear(),
+
+                EditorHistoryEvent::RecordSpawned(entities) => {
+                    history.record_spawned(world, &entities, now);
+                    continue;
+                }
+                EditorHistoryEvent::DeleteEntities(entities) => {
+                    history.delete_entities(world, &entities, now);
+                    continue;
+                }
+                EditorHistoryEvent::BeginEdit(entities) => {
+                    history.begin_edit(world, &entities);
+                    continue;
+                }
+                EditorHistoryEvent::EndEdit => {
+                    history.end_edit(world, now);
+                    continue;
+                }
+                EditorHistoryEvent::Record(commands) => {
+                    history.record(commands, now);
+                    continue;
+                }
+                EditorHistoryEvent::BeginStroke => {
+                    history.stroke_active = true;
+                    history.stroke_recorded = false;
+                    continue;
+                }
+                EditorHistoryEvent::EndStroke => {
+                    history.stroke_active = false;
+                    history.stroke_recorded = false;
+                }
+            }
+
+            // never fold a fresh edit into an entry that was just undone or redone
+            history.allow_merge = false;
+            history.stroke_recorded = false;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doodad_snapshot(world: &World, entity: Entity) -> SceneEntitySnapshot {
+        SceneEntitySnapshot::from_world(world, entity).expect("doodad snapshot")
+    }
+
+    fn spawn_doodad(world: &mut World) -> Entity {
+        world
+            .spawn((Name::new("crate"), Transform::default(), DoodadProto))
+            .id()
+    }
+
+    #[test]
+    fn test_undo_removes_added_custom_props() {
+        let mut world = World::new();
+        let entity = spawn_doodad(&mut world);
+
+        let before = doodad_snapshot(&world, entity);
+        assert!(before.custom_props.is_none());
+
+        let mut props = CustomPropsMap::default();
+        props.insert("health".to_string(), Default::default());
+        world.entity_mut(entity).insert(CustomPropsComponent { props });
+
+        let after = doodad_snapshot(&world, entity);
+        assert!(before.custom_props_differ(&after));
+
+        before.write_to(&mut world, entity);
+        assert!(!world.entity(entity).contains::<CustomPropsComponent>());
+
+        after.write_to(&mut world, entity);
+        assert!(world.entity(entity).contains::<CustomPropsComponent>());
+    }
+
+    #[test]
+    fn test_undo_restores_parent() {
+        let mut world = World::new();
+        let old_parent = world.spawn(Transform::default()).id();
+        let new_parent = world.spawn(Transform::default()).id();
+
+        let entity = spawn_doodad(&mut world);
+        world.entity_mut(entity).insert(ChildOf(old_parent));
+
+        let before = doodad_snapshot(&world, entity);
+
+        world.entity_mut(entity).insert(ChildOf(new_parent));
+        let after = doodad_snapshot(&world, entity);
+        assert!(before.transform_differs(&after));
+
+        before.write_to(&mut world, entity);
+        assert_eq!(world.entity(entity).get::<ChildOf>().map(|c| c.parent()), Some(old_parent));
+
+        after.write_to(&mut world, entity);
+        assert_eq!(world.entity(entity).get::<ChildOf>().map(|c| c.parent()), Some(new_parent));
+
+        // an entity that was moved out of its parent goes back under it, and out again on redo
+        world.entity_mut(entity).remove::<ChildOf>();
+        let unparented = doodad_snapshot(&world, entity);
+
+        after.write_to(&mut world, entity);
+        assert_eq!(world.entity(entity).get::<ChildOf>().map(|c| c.parent()), Some(new_parent));
+
+        unparented.write_to(&mut world, entity);
+        assert!(world.entity(entity).get::<ChildOf>().is_none());
+    }
+
+    #[test]
+    fn test_stroke_records_one_entry() {
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        world.init_resource::<EditorHistoryResource>();
+
+        let first = spawn_doodad(&mut world);
+        let second = spawn_doodad(&mut world);
+        let erased = spawn_doodad(&mut world);
+        let after_stroke = spawn_doodad(&mut world);
+
+        // one frame of requests per step, like a brush held down over several frames
+        let frames = [
+            vec![EditorHistoryEvent::BeginStroke, EditorHistoryEvent::RecordSpawned(vec![first])],
+            vec![EditorHistoryEvent::RecordSpawned(vec![second])],
+            vec![EditorHistoryEvent::DeleteEntities(vec![erased]), EditorHistoryEvent::EndStroke],
+            vec![EditorHistoryEvent::RecordSpawned(vec![after_stroke])],
+        ];
+
+        for requests in frames {
+            world.resource_mut::<EditorHistoryResource>().requests.extend(requests);
+            apply_editor_history_requests(&mut world);
+        }
+
+        let history = world.resource::<EditorHistoryResource>();
+        assert_eq!(history.entries().len(), 2);
+        assert_eq!(history.entries()[0].commands.len(), 3);
+        assert_eq!(history.entries()[1].commands.len(), 1);
+
+        world
+            .resource_mut::<EditorHistoryResource>()
+            .requests
+            .extend([EditorHistoryEvent::Undo, EditorHistoryEvent::Undo]);
+        apply_editor_history_requests(&mut world);
+
+        // the whole stroke is undone at once: both spawns are gone and the erased doodad is back
+        assert!(world.get_entity(first).is_err());
+        assert!(world.get_entity(second).is_err());
+        assert!(world.get_entity(after_stroke).is_err());
+
+        let doodad_count = world
+            .query_filtered::<(), With<DoodadProto>>()
+            .iter(&world)
+            .count();
+        assert_eq!(doodad_count, 1);
+    }
+}
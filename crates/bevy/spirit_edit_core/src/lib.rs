@@ -11,6 +11,9 @@ pub mod doodads;
 pub mod zones;
 pub mod placement;
 pub mod prefabs;
+pub mod history;
+pub mod splines;
+pub mod selection;
 
 
 mod utils;
@@ -27,18 +30,27 @@ impl Plugin for SpiritEditCorePlugin {
              .add_plugins(doodads::doodad_plugin)
              .add_plugins(prefabs::prefab_plugin)
              .add_plugins(gltf_models::gltf_models_plugin   )
+             .add_plugins(history::history_plugin)
+             .add_plugins(splines::splines_plugin)
+             .add_plugins(zones::zone_streaming::zone_streaming_plugin)
 
-           	 .add_event::<placement::PlacementEvent>()
+           	 .add_message::<placement::PlacementEvent>()
            	
             .init_resource::<placement::PlacementResource>()
 
             .init_resource::<placement::PlacementToolsState>()
 
-             .add_event::<prefabs::PrefabEvent>()
-             .add_event::<prefabs::SpawnPrefabEvent>()
-            .add_event::<ZoneEvent>()
-            .add_event::<SaveZoneToFileEvent>()
-            .add_event::<prefabs::SavePrefabToFileEvent>()
+            .init_resource::<placement::duplicate::DuplicateToolResource>()
+
+            .init_resource::<selection::SelectionToolsState>()
+
+            .init_resource::<zones::zone_validation::ZoneValidationTerrainBounds>()
+
+             .add_message::<prefabs::PrefabEvent>()
+             .add_message::<prefabs::SpawnPrefabEvent>()
+            .add_message::<ZoneEvent>()
+            .add_message::<SaveZoneToFileEvent>()
+            .add_message::<prefabs::SavePrefabToFileEvent>()
            
             .register_type::<CustomPropsComponent>() //reflect
             .register_type::<CustomProp>() //reflect
@@ -49,10 +61,12 @@ impl Plugin for SpiritEditCorePlugin {
             
             .add_systems(Update, (
                 zones::handle_zone_events,
+                zones::assign_zone_entity_ids,
                 zones::handle_save_zone_events,
                 prefabs::handle_prefab_events ,
                 prefabs::handle_save_prefab_events, 
                 placement::handle_placement_events,
+                placement::duplicate::handle_duplicate_events,
             ).chain())
 
 
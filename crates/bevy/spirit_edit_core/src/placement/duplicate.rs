@@ -0,0 +1,521 @@
+use bevy::prelude::*;
+
+use crate::placement::{PlacementEvent, PlacementResource};
+use crate::prefabs::prefab_definitions::PrefabDefinitionsResource;
+use crate::prefabs::prefab_overrides::zone_entity_with_prefab_overrides;
+use crate::splines::TerrainSpline;
+use crate::zones::zone_file::ZoneEntityId;
+use crate::zones::ZoneEntitySpawners;
+
+/*
+
+Array, mirror and path duplicates of a set of zone entities.
+
+Copies are built from the zone file form of each source, so doodads, clay tiles, prefab instances
+(with their overrides) and splines are duplicated the same way a zone loads them, with new ids
+and under the same parent as their source. The last operation is kept so it can be repeated
+on a new selection.
+
+*/
+
+// spline paths are sampled this often before copies are spread along them
+const PATH_SAMPLE_SPACING: f32 = 0.25;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MirrorAxis {
+    #[default]
+    X,
+    Y,
+    Z,
+}
+
+impl MirrorAxis {
+    pub fn normal(&self) -> Vec3 {
+        match self {
+            Self::X => Vec3::X,
+            Self::Y => Vec3::Y,
+            Self::Z => Vec3::Z,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum DuplicatePath {
+    Spline(Entity),
+    Polyline(Vec<Vec3>), // world space
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum DuplicateOperation {
+    LinearArray {
+        count: u32,
+        offset: Vec3, // between neighbouring copies, world space
+    },
+    RadialArray {
+        count: u32,
+        axis: Vec3,
+        center: Option<Vec3>, // the selection center when none
+        angle_degrees: f32,   // a full turn spreads the copies evenly around the circle
+    },
+    Mirror {
+        axis: MirrorAxis,
+        plane_offset: Option<f32>, // where the plane crosses the axis, through the selection center when none
+    },
+    AlongPath {
+        path: DuplicatePath,
+        count: u32,
+        follow_rotation: bool,
+    },
+}
+
+impl DuplicateOperation {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::LinearArray { .. } => "Linear Array",
+            Self::RadialArray { .. } => "Radial Array",
+            Self::Mirror { .. } => "Mirror",
+            Self::AlongPath { .. } => "Along Path",
+        }
+    }
+
+    /// World transforms for every copy, each with one transform per source.
+    /// `path_points` is the resolved path for `AlongPath`.
+    pub fn copy_transforms(&self, sources: &[Transform], path_points: &[Vec3]) -> Vec<Vec<Transform>> {
+        if sources.is_empty() {
+            return Vec::new();
+        }
+
+        let selection_center = sources.iter().map(|source| source.translation).sum::<Vec3>() / sources.len() as f32;
+
+        match self {
+            Self::LinearArray { count, offset } => (1..=*count)
+                .map(|index| {
+                    sources
+                        .iter()
+                        .map(|source| source.with_translation(source.translation + *offset * index as f32))
+                        .collect()
+                })
+                .collect(),
+
+            Self::RadialArray {
+                count,
+                axis,
+                center,
+                angle_degrees,
+            } => {
+                let center = center.unwrap_or(selection_center);
+                let axis = axis.normalize_or(Vec3::Y);
+
+                // a full turn would put the last copy on top of the source
+                let step_degrees = match angle_degrees.abs() >= 360.0 {
+                    true => angle_degrees / (*count + 1) as f32,
+                    false => angle_degrees / (*count).max(1) as f32,
+                };
+
+                (1..=*count)
+                    .map(|index| {
+                        let rotation = Quat::from_axis_angle(axis, (step_degrees * index as f32).to_radians());
+
+                        sources
+                            .iter()
+                            .map(|source| {
+                                let mut copy = *source;
+                                copy.rotate_around(center, rotation);
+                                copy
+                            })
+                            .collect()
+                    })
+                    .collect()
+            }
+
+            Self::Mirror { axis, plane_offset } => {
+                let normal = axis.normal();
+                let plane_distance = plane_offset.unwrap_or(selection_center.dot(normal));
+
+                vec![sources
+                    .iter()
+                    .map(|source| mirror_transform(source, normal, plane_distance))
+                    .collect()]
+            }
+
+            Self::AlongPath {
+                count,
+                follow_rotation,
+                ..
+            } => {
+                let path = PolylinePath::new(path_points);
+                if path.length <= 0.0 || *count == 0 {
+                    return Vec::new();
+                }
+
+                let start_tangent = path.tangent_at(0.0);
+
+                (0..*count)
+                    .map(|index| {
+                        let fraction = match *count > 1 {
+                            true => index as f32 / (*count - 1) as f32,
+                            false => 0.0,
+                        };
+
+                        let distance = fraction * path.length;
+                        let point = path.point_at(distance);
+
+                        let group_rotation = match follow_rotation {
+                            true => Quat::from_rotation_arc(start_tangent, path.tangent_at(distance)),
+                            false => Quat::IDENTITY,
+                        };
+
+                        sources
+                            .iter()
+                            .map(|source| Transform {
+                                translation: point + group_rotation * (source.translation - selection_center),
+                                rotation: group_rotation * source.rotation,
+                                scale: source.scale,
+                            })
+                            .collect()
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+/*
+
+Mirrors the placement of an entity, the model itself is not flipped since
+negative scales break colliders and culling.
+A rotation reflected across a plane keeps the axis components in the plane negated.
+
+*/
+fn mirror_transform(source: &Transform, normal: Vec3, plane_distance: f32) -> Transform {
+    let translation = source.translation - 2.0 * (source.translation.dot(normal) - plane_distance) * normal;
+
+    let rotation_axis = source.rotation.xyz();
+    let mirrored_axis = 2.0 * rotation_axis.dot(normal) * normal - rotation_axis;
+    let rotation = Quat::from_xyzw(mirrored_axis.x, mirrored_axis.y, mirrored_axis.z, source.rotation.w).normalize();
+
+    Transform {
+        translation,
+        rotation,
+        scale: source.scale,
+    }
+}
+
+struct PolylinePath<'a> {
+    points: &'a [Vec3],
+    cumulative_lengths: Vec<f32>,
+    length: f32,
+}
+
+impl<'a> PolylinePath<'a> {
+    fn new(points: &'a [Vec3]) -> Self {
+        let mut cumulative_lengths = Vec::with_capacity(points.len());
+        let mut length = 0.0;
+
+        for (index, point) in points.iter().enumerate() {
+            if index > 0 {
+                length += point.distance(points[index - 1]);
+            }
+            cumulative_lengths.push(length);
+        }
+
+        Self {
+            points,
+            cumulative_lengths,
+            length,
+        }
+    }
+
+    fn segment_at(&self, distance: f32) -> usize {
+        let segment = self
+            .cumulative_lengths
+            .iter()
+            .position(|length| *length >= distance)
+            .unwrap_or(self.points.len() - 1);
+
+        segment.clamp(1, self.points.len() - 1)
+    }
+
+    fn point_at(&self, distance: f32) -> Vec3 {
+        let segment = self.segment_at(distance);
+
+        let start_length = self.cumulative_lengths[segment - 1];
+        let segment_length = self.cumulative_lengths[segment] - start_length;
+
+        let t = match segment_length > 0.0 {
+            true => ((distance - start_length) / segment_length).clamp(0.0, 1.0),
+            false => 0.0,
+        };
+
+        self.points[segment - 1].lerp(self.points[segment], t)
+    }
+
+    fn tangent_at(&self, distance: f32) -> Vec3 {
+        let segment = self.segment_at(distance);
+
+        (self.points[segment] - self.points[segment - 1]).normalize_or(Vec3::NEG_Z)
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct DuplicateToolResource {
+    pub last_operation: Option<DuplicateOperation>,
+}
+
+pub fn handle_duplicate_events(
+    mut evt_reader: MessageReader<PlacementEvent>,
+
+    mut duplicate_tool_resource: ResMut<DuplicateToolResource>,
+    placement_resource: Res<PlacementResource>,
+
+    entity_ref_query: Query<EntityRef>,
+    prefab_definitions: Res<PrefabDefinitionsResource>,
+
+    global_xform_query: Query<&GlobalTransform>,
+    parent_query: Query<&ChildOf>,
+    spline_query: Query<(&TerrainSpline, &GlobalTransform)>,
+
+    mut zone_entity_spawners: ZoneEntitySpawners,
+) {
+    for evt in evt_reader.read() {
+        let (entities, operation) = match evt {
+            PlacementEvent::DuplicateEntities(entities, operation) => {
+                duplicate_tool_resource.last_operation = Some(operation.clone());
+                (entities, operation.clone())
+            }
+
+            PlacementEvent::RepeatLastDuplicate(entities) => {
+                let Some(operation) = duplicate_tool_resource.last_operation.clone() else {
+                    warn!("no duplicate operation to repeat");
+                    continue;
+                };
+                (entities, operation)
+            }
+
+            _ => continue,
+        };
+
+        let path_points: Vec<Vec3> = match &operation {
+            DuplicateOperation::AlongPath {
+                path: DuplicatePath::Spline(spline_entity),
+                ..
+            } => match spline_query.get(*spline_entity) {
+                Ok((spline, spline_xform)) => spline
+                    .sample_world(spline_xform, PATH_SAMPLE_SPACING)
+                    .into_iter()
+                    .map(|sample| sample.position)
+                    .collect(),
+                Err(_) => {
+                    warn!("duplicate path spline {:?} no longer exists", spline_entity);
+                    continue;
+                }
+            },
+
+            DuplicateOperation::AlongPath {
+                path: DuplicatePath::Polyline(points),
+                ..
+            } => points.clone(),
+
+            _ => Vec::new(),
+        };
+
+        let path_spline = match &operation {
+            DuplicateOperation::AlongPath {
+                path: DuplicatePath::Spline(spline_entity),
+                ..
+            } => Some(*spline_entity),
+            _ => None,
+        };
+
+        // children of another selected entity come along with it
+        let is_selected_descendant = |entity: Entity| {
+            parent_query
+                .iter_ancestors(entity)
+                .any(|ancestor| entities.contains(&ancestor))
+        };
+
+        let mut sources = Vec::new();
+
+        for entity in entities.iter().copied() {
+            if Some(entity) == path_spline || is_selected_descendant(entity) {
+                continue;
+            }
+
+            let Some(zone_entity) = zone_entity_with_prefab_overrides(entity, &entity_ref_query, &prefab_definitions) else {
+                continue;
+            };
+
+            let Ok(world_xform) = global_xform_query.get(entity) else {
+                continue;
+            };
+
+            let parent = parent_query
+                .get(entity)
+                .ok()
+                .map(|child_of| child_of.parent())
+                .or(placement_resource.placement_parent);
+
+            let Some(parent) = parent else {
+                warn!("cannot duplicate {:?}, it has no parent zone", entity);
+                continue;
+            };
+
+            let parent_xform = global_xform_query.get(parent).copied().unwrap_or_default();
+
+            sources.push((zone_entity, world_xform.compute_transform(), parent, parent_xform));
+        }
+
+        let source_transforms: Vec<Transform> = sources.iter().map(|(_, world_xform, _, _)| *world_xform).collect();
+
+        let copies = operation.copy_transforms(&source_transforms, &path_points);
+
+        for copy in copies.iter() {
+            for ((zone_entity, _, parent, parent_xform), world_xform) in sources.iter().zip(copy.iter()) {
+                let local_xform = GlobalTransform::from(*world_xform).reparented_to(parent_xform);
+
+                let mut duplicate = zone_entity.clone();
+                *duplicate.get_transform_simple_mut() = local_xform.into();
+                duplicate.set_id(ZoneEntityId::new_random());
+
+                zone_entity_spawners.spawn_recorded(&duplicate, *parent);
+            }
+        }
+
+        info!(
+            "{}: {} copies of {} entities",
+            operation.label(),
+            copies.len(),
+            sources.len()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reflect(point: Vec3, normal: Vec3) -> Vec3 {
+        point - 2.0 * point.dot(normal) * normal
+    }
+
+    #[test]
+    fn test_radial_full_turn_does_not_overlap_source() {
+        let source = Transform::from_xyz(10.0, 0.0, 0.0);
+        let operation = DuplicateOperation::RadialArray {
+            count: 3,
+            axis: Vec3::Y,
+            center: Some(Vec3::ZERO),
+            angle_degrees: 360.0,
+        };
+
+        let copies = operation.copy_transforms(&[source], &[]);
+        assert_eq!(copies.len(), 3);
+
+        // four evenly spaced around the circle, source included
+        let expected = [Vec3::new(0.0, 0.0, -10.0), Vec3::new(-10.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 10.0)];
+        for (copy, expected) in copies.iter().zip(expected) {
+            assert!(copy[0].translation.abs_diff_eq(expected, 1e-4), "{:?} != {:?}", copy[0].translation, expected);
+        }
+    }
+
+    #[test]
+    fn test_radial_partial_turn_ends_on_angle() {
+        let source = Transform::from_xyz(10.0, 0.0, 0.0);
+        let operation = DuplicateOperation::RadialArray {
+            count: 2,
+            axis: Vec3::Y,
+            center: Some(Vec3::ZERO),
+            angle_degrees: 180.0,
+        };
+
+        let copies = operation.copy_transforms(&[source], &[]);
+        assert_eq!(copies.len(), 2);
+        assert!(copies[1][0].translation.abs_diff_eq(Vec3::new(-10.0, 0.0, 0.0), 1e-4));
+    }
+
+    #[test]
+    fn test_mirror_reflects_translation_and_rotation() {
+        let normal = Vec3::X;
+        let source = Transform::from_xyz(3.0, 1.0, 2.0)
+            .with_rotation(Quat::from_euler(EulerRot::YXZ, 0.7, 0.3, -0.2));
+
+        let mirrored = mirror_transform(&source, normal, 1.0);
+        assert!(mirrored.translation.abs_diff_eq(Vec3::new(-1.0, 1.0, 2.0), 1e-5));
+        assert_eq!(mirrored.scale, source.scale);
+
+        // the mirrored rotation points a mirrored direction where the mirror of the original points
+        for direction in [Vec3::X, Vec3::Y, Vec3::Z, Vec3::new(1.0, 2.0, -3.0)] {
+            let expected = reflect(source.rotation * direction, normal);
+            let actual = mirrored.rotation * reflect(direction, normal);
+            assert!(actual.abs_diff_eq(expected, 1e-5), "{:?} != {:?}", actual, expected);
+        }
+    }
+
+    #[test]
+    fn test_mirror_turns_rotation_in_plane_the_other_way() {
+        let source = Transform::from_rotation(Quat::from_rotation_y(0.5));
+
+        let mirrored = mirror_transform(&source, Vec3::X, 0.0);
+        assert!(mirrored.rotation.abs_diff_eq(Quat::from_rotation_y(-0.5), 1e-5));
+
+        // a rotation around the mirror normal itself is unchanged
+        let source = Transform::from_rotation(Quat::from_rotation_x(0.5));
+
+        let mirrored = mirror_transform(&source, Vec3::X, 0.0);
+        assert!(mirrored.rotation.abs_diff_eq(Quat::from_rotation_x(0.5), 1e-5));
+    }
+
+    #[test]
+    fn test_along_path_without_length_makes_no_copies() {
+        let source = Transform::IDENTITY;
+        let operation = DuplicateOperation::AlongPath {
+            path: DuplicatePath::Polyline(Vec::new()),
+            count: 4,
+            follow_rotation: true,
+        };
+
+        assert!(operation.copy_transforms(&[source], &[]).is_empty());
+        assert!(operation.copy_transforms(&[source], &[Vec3::ONE]).is_empty());
+        assert!(operation.copy_transforms(&[source], &[Vec3::ONE, Vec3::ONE]).is_empty());
+    }
+
+    #[test]
+    fn test_polyline_path_sampling() {
+        let points = [Vec3::ZERO, Vec3::new(4.0, 0.0, 0.0), Vec3::new(4.0, 0.0, 4.0)];
+        let path = PolylinePath::new(&points);
+
+        assert_eq!(path.length, 8.0);
+        assert_eq!(path.point_at(0.0), Vec3::ZERO);
+        assert_eq!(path.point_at(2.0), Vec3::new(2.0, 0.0, 0.0));
+        assert_eq!(path.point_at(6.0), Vec3::new(4.0, 0.0, 2.0));
+        assert_eq!(path.point_at(20.0), Vec3::new(4.0, 0.0, 4.0));
+        assert_eq!(path.tangent_at(1.0), Vec3::X);
+        assert_eq!(path.tangent_at(7.0), Vec3::Z);
+
+        // repeated points have no direction to follow
+        let points = [Vec3::ONE, Vec3::ONE];
+        let path = PolylinePath::new(&points);
+
+        assert_eq!(path.length, 0.0);
+        assert_eq!(path.point_at(0.0), Vec3::ONE);
+        assert_eq!(path.tangent_at(0.0), Vec3::NEG_Z);
+    }
+
+    #[test]
+    fn test_along_path_spreads_copies_end_to_end() {
+        let sources = [Transform::from_xyz(1.0, 0.0, 0.0), Transform::from_xyz(-1.0, 0.0, 0.0)];
+        let operation = DuplicateOperation::AlongPath {
+            path: DuplicatePath::Polyline(Vec::new()),
+            count: 3,
+            follow_rotation: false,
+        };
+
+        let points = [Vec3::ZERO, Vec3::new(0.0, 0.0, 10.0)];
+        let copies = operation.copy_transforms(&sources, &points);
+
+        assert_eq!(copies.len(), 3);
+        assert_eq!(copies[0][0].translation, Vec3::new(1.0, 0.0, 0.0));
+        assert_eq!(copies[1][1].translation, Vec3::new(-1.0, 0.0, 5.0));
+        assert_eq!(copies[2][0].translation, Vec3::new(1.0, 0.0, 10.0));
+    }
+}
@@ -1,6 +1,14 @@
 use std::time::Duration;
 
 use bevy::prelude::*;
+
+pub mod duplicate;
+pub mod scatter;
+pub mod surface;
+
+use duplicate::DuplicateOperation;
+use scatter::ScatterRules;
+use surface::{surface_tilt_rotation, SurfaceAlignMode};
 /*use bevy_editor_pls_core::{editor_window::{EditorWindow, EditorWindowContext}, Editor};
 use bevy_inspector_egui::egui::{self, RichText};*/
 
@@ -31,13 +39,20 @@ impl Default for PlacementResource {
     }
 }
 
-#[derive(Event)]
+#[derive(Message)]
 pub enum PlacementEvent {
 
     CloneSelectedDoodad,
     GridLockSelectedDoodad(Vec3),
 
-    SetPlacementParent( Option<Entity> )
+    SetPlacementParent( Option<Entity> ),
+
+    DuplicateEntities( Vec<Entity>, DuplicateOperation ),
+    RepeatLastDuplicate( Vec<Entity> ),
+
+    // handled by the editor app, which owns the colliders
+    DropToGround( Vec<Entity> ),
+    NudgeOutOfColliders( Vec<Entity> ),
 
 } 
 
@@ -46,7 +61,16 @@ pub enum PlacementEvent {
 pub struct PlacementToolsState{
 	pub  randomize_yaw: bool,
 	pub random_scale_multiplier : f32,
-    
+
+    pub scatter: ScatterRules, // used by the doodad scatter brush
+
+    pub socket_snapping: bool,
+    pub socket_snap_distance: f32,
+    pub show_sockets: bool,
+
+    pub surface_align_mode: SurfaceAlignMode,
+    pub max_surface_tilt_degrees: f32,
+    pub nudge_out_of_colliders: bool, // after a drop to ground
     
 }
 
@@ -56,17 +80,38 @@ impl Default for PlacementToolsState {
 	fn default() -> Self {
 	    Self {
 	    	randomize_yaw: false,
-	    	random_scale_multiplier: 0.0 
+	    	random_scale_multiplier: 0.0 ,
+	    	scatter: ScatterRules::default(),
+	    	socket_snapping: true,
+	    	socket_snap_distance: 1.5,
+	    	show_sockets: true,
+	    	surface_align_mode: SurfaceAlignMode::WorldUp,
+	    	max_surface_tilt_degrees: 30.0,
+	    	nudge_out_of_colliders: true,
 	    }
 	}
 
 }
 
 
+impl PlacementToolsState {
+
+    // the tilt for a doodad landing on this surface, none when doodads stay upright
+    pub fn surface_rotation(&self, surface_normal: Option<Vec3>) -> Option<Quat> {
+        match self.surface_align_mode {
+            SurfaceAlignMode::WorldUp => None,
+            SurfaceAlignMode::SurfaceNormal => surface_normal
+                .map(|normal| surface_tilt_rotation(normal, self.max_surface_tilt_degrees)),
+        }
+    }
+
+}
+
+
 
 pub fn handle_placement_events(
     //mut commands: Commands,
-    mut evt_reader: EventReader<PlacementEvent>,
+    mut evt_reader: MessageReader<PlacementEvent>,
 
     mut placement_resource: ResMut<PlacementResource>,
 
@@ -0,0 +1,274 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::doodads::doodad_manifest::{DoodadName, DoodadTagMapResource};
+
+/*
+
+Rules for the doodad scatter brush.
+
+The app raycasts the candidate points onto the terrain and samples the region and splat maps,
+these rules decide where candidates go, which of them survive and what doodad they become.
+Scattered doodads are ordinary doodads so they save, undo and select like hand placed ones.
+
+*/
+
+// density is expressed per this many square meters
+const DENSITY_AREA: f32 = 100.0;
+
+// dart throwing attempts per requested point before the brush area counts as full
+const POISSON_ATTEMPTS_PER_POINT: usize = 30;
+
+#[derive(Clone, Debug)]
+pub struct WeightedDoodad {
+    pub doodad_name: DoodadName,
+    pub weight: f32,
+}
+
+#[derive(Clone, Debug)]
+pub enum ScatterDoodadSource {
+    Weighted(Vec<WeightedDoodad>),
+    Tag(String), // every doodad with this tag, equally weighted
+}
+
+impl Default for ScatterDoodadSource {
+    fn default() -> Self {
+        Self::Weighted(Vec::new())
+    }
+}
+
+impl ScatterDoodadSource {
+    pub fn resolve(&self, tag_map: &DoodadTagMapResource) -> Vec<WeightedDoodad> {
+        match self {
+            Self::Weighted(doodads) => doodads
+                .iter()
+                .filter(|doodad| doodad.weight > 0.0 && !doodad.doodad_name.is_empty())
+                .cloned()
+                .collect(),
+
+            Self::Tag(tag) => tag_map
+                .doodad_tag_map
+                .get(tag)
+                .map(|names| {
+                    names
+                        .iter()
+                        .map(|doodad_name| WeightedDoodad {
+                            doodad_name: doodad_name.clone(),
+                            weight: 1.0,
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+        }
+    }
+}
+
+pub fn pick_weighted_doodad<'a>(doodads: &'a [WeightedDoodad], rng: &mut impl Rng) -> Option<&'a DoodadName> {
+    let total_weight: f32 = doodads.iter().map(|doodad| doodad.weight).sum();
+
+    if total_weight <= 0.0 {
+        return None;
+    }
+
+    let mut roll = rng.gen_range(0.0..total_weight);
+
+    for doodad in doodads {
+        if roll < doodad.weight {
+            return Some(&doodad.doodad_name);
+        }
+        roll -= doodad.weight;
+    }
+
+    doodads.last().map(|doodad| &doodad.doodad_name)
+}
+
+// what the app found under a candidate point
+#[derive(Clone, Debug)]
+pub struct ScatterSurfaceSample {
+    pub point: Vec3,
+    pub normal: Vec3,
+    pub region: Option<u8>,
+    pub splat_texture: Option<u8>, // the dominant splat texture index
+}
+
+#[derive(Clone, Debug)]
+pub struct ScatterRules {
+    pub source: ScatterDoodadSource,
+
+    pub spacing: f32, // minimum distance between doodads, in meters
+    pub density: f32, // doodads per 100 square meters
+
+    pub min_slope_degrees: f32,
+    pub max_slope_degrees: f32,
+
+    pub min_height: Option<f32>,
+    pub max_height: Option<f32>,
+
+    // empty means any
+    pub allowed_regions: Vec<u8>,
+    pub allowed_splat_textures: Vec<u8>,
+
+    pub normal_alignment: f32, // 0 stands upright, 1 follows the surface normal
+    pub random_yaw: bool,
+    pub min_scale: f32,
+    pub max_scale: f32,
+
+    pub erase_tag: String, // empty erases whatever the source would place
+}
+
+impl Default for ScatterRules {
+    fn default() -> Self {
+        Self {
+            source: ScatterDoodadSource::default(),
+            spacing: 4.0,
+            density: 2.0,
+            min_slope_degrees: 0.0,
+            max_slope_degrees: 35.0,
+            min_height: None,
+            max_height: None,
+            allowed_regions: Vec::new(),
+            allowed_splat_textures: Vec::new(),
+            normal_alignment: 0.0,
+            random_yaw: true,
+            min_scale: 1.0,
+            max_scale: 1.0,
+            erase_tag: String::new(),
+        }
+    }
+}
+
+impl ScatterRules {
+    pub fn target_count(&self, radius: f32) -> usize {
+        let area = PI * radius * radius;
+
+        (self.density.max(0.0) * area / DENSITY_AREA).round() as usize
+    }
+
+    pub fn accepts(&self, sample: &ScatterSurfaceSample) -> bool {
+        let slope_degrees = sample.normal.normalize_or(Vec3::Y).angle_between(Vec3::Y).to_degrees();
+
+        if slope_degrees < self.min_slope_degrees || slope_degrees > self.max_slope_degrees {
+            return false;
+        }
+
+        if self.min_height.is_some_and(|min_height| sample.point.y < min_height) {
+            return false;
+        }
+
+        if self.max_height.is_some_and(|max_height| sample.point.y > max_height) {
+            return false;
+        }
+
+        // a constraint on data that is not loaded rejects the point rather than ignoring the rule
+        if !self.allowed_regions.is_empty()
+            && !sample.region.is_some_and(|region| self.allowed_regions.contains(&region))
+        {
+            return false;
+        }
+
+        if !self.allowed_splat_textures.is_empty()
+            && !sample
+                .splat_texture
+                .is_some_and(|texture| self.allowed_splat_textures.contains(&texture))
+        {
+            return false;
+        }
+
+        true
+    }
+
+    // euler angles in the same YXZ order that PlaceDoodadEvent applies them
+    pub fn rotation_euler(&self, normal: Vec3, rng: &mut impl Rng) -> Option<Vec3> {
+        let yaw = match self.random_yaw {
+            true => rng.gen_range(0.0..(2.0 * PI)),
+            false => 0.0,
+        };
+
+        let alignment = self.normal_alignment.clamp(0.0, 1.0);
+
+        if yaw == 0.0 && alignment <= 0.0 {
+            return None;
+        }
+
+        let tilt = Quat::IDENTITY.slerp(Quat::from_rotation_arc(Vec3::Y, normal.normalize_or(Vec3::Y)), alignment);
+        let rotation = tilt * Quat::from_rotation_y(yaw);
+
+        let (y, x, z) = rotation.to_euler(EulerRot::YXZ);
+
+        Some(Vec3::new(y, x, z))
+    }
+
+    pub fn scale(&self, rng: &mut impl Rng) -> Option<Vec3> {
+        let min_scale = self.min_scale.min(self.max_scale);
+        let max_scale = self.min_scale.max(self.max_scale);
+
+        if (max_scale - min_scale).abs() < 0.001 {
+            return match (min_scale - 1.0).abs() < 0.001 {
+                true => None,
+                false => Some(Vec3::splat(min_scale)),
+            };
+        }
+
+        Some(Vec3::splat(rng.gen_range(min_scale..max_scale)))
+    }
+
+    pub fn erase_doodad_names(&self, tag_map: &DoodadTagMapResource) -> Vec<DoodadName> {
+        if self.erase_tag.is_empty() {
+            return self
+                .source
+                .resolve(tag_map)
+                .into_iter()
+                .map(|doodad| doodad.doodad_name)
+                .collect();
+        }
+
+        tag_map.doodad_tag_map.get(&self.erase_tag).cloned().unwrap_or_default()
+    }
+}
+
+/*
+
+Poisson-disk sampling by dart throwing inside the brush circle.
+Existing points count against the spacing, so repeated dabs fill gaps instead of stacking.
+
+*/
+pub fn poisson_disk_points(
+    center: Vec2,
+    radius: f32,
+    spacing: f32,
+    max_points: usize,
+    existing: &[Vec2],
+    rng: &mut impl Rng,
+) -> Vec<Vec2> {
+    let mut points: Vec<Vec2> = Vec::new();
+
+    if radius <= 0.0 || max_points == 0 {
+        return points;
+    }
+
+    let min_distance_squared = spacing.max(0.0).powi(2);
+
+    for _ in 0..(max_points * POISSON_ATTEMPTS_PER_POINT) {
+        if points.len() >= max_points {
+            break;
+        }
+
+        // uniform inside the circle
+        let angle = rng.gen_range(0.0..(2.0 * PI));
+        let distance = radius * rng.gen_range(0.0f32..1.0).sqrt();
+        let candidate = center + Vec2::new(angle.cos(), angle.sin()) * distance;
+
+        let too_close = existing
+            .iter()
+            .chain(points.iter())
+            .any(|other| other.distance_squared(candidate) < min_distance_squared);
+
+        if !too_close {
+            points.push(candidate);
+        }
+    }
+
+    points
+}
@@ -0,0 +1,58 @@
+use bevy::prelude::*;
+
+use serde::{Deserialize, Serialize};
+
+/*
+
+How placed and dropped doodads sit on the surface under them.
+
+The sweeps themselves (drop to ground and the non-penetration nudge) run in the editor app,
+which owns the physics world, this only holds the modes and the rotation math they share.
+
+*/
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SurfaceAlignMode {
+    #[default]
+    WorldUp, // doodads stand upright wherever they land
+    SurfaceNormal, // the doodad up axis follows the surface normal, up to the max tilt
+}
+
+impl SurfaceAlignMode {
+    pub const ALL: [SurfaceAlignMode; 2] = [SurfaceAlignMode::WorldUp, SurfaceAlignMode::SurfaceNormal];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::WorldUp => "World Up",
+            Self::SurfaceNormal => "Surface Normal",
+        }
+    }
+}
+
+/// Rotation taking the up axis toward `normal`, tilting at most `max_tilt_degrees` away from world up.
+pub fn surface_tilt_rotation(normal: Vec3, max_tilt_degrees: f32) -> Quat {
+    let Some(normal) = normal.try_normalize() else {
+        return Quat::IDENTITY;
+    };
+
+    let tilt_angle = Vec3::Y.angle_between(normal);
+    let max_tilt = max_tilt_degrees.clamp(0.0, 180.0).to_radians();
+
+    if tilt_angle <= f32::EPSILON {
+        return Quat::IDENTITY;
+    }
+
+    let full_tilt = Quat::from_rotation_arc(Vec3::Y, normal);
+
+    match tilt_angle <= max_tilt {
+        true => full_tilt,
+        false => Quat::IDENTITY.slerp(full_tilt, max_tilt / tilt_angle),
+    }
+}
+
+/// Keeps only the rotation about the world up axis, so a tilt can be applied again from upright.
+pub fn yaw_rotation(rotation: Quat) -> Quat {
+    let (yaw, _, _) = rotation.to_euler(EulerRot::YXZ);
+
+    Quat::from_rotation_y(yaw)
+}
@@ -1,12 +1,19 @@
-
-use crate::{prefabs::prefab_file::PrefabFile, zones::zone_file::ZoneEntityV2 as ZoneEntity};
+use crate::{prefabs::prefab_file::PrefabFile, zones::zone_file::{ZoneEntityId, ZoneEntityV2 as ZoneEntity}};
 use bevy::prelude::*;
 
+use prefab_definitions::PrefabDefinitionsResource;
+use prefab_overrides::{prefab_instance_children, prefab_instance_overrides, prefab_would_nest_itself, PrefabOverrides, PrefabOverridesComponent};
+
 pub mod prefab_file;
 pub mod prefab_definitions;
+pub mod prefab_overrides;
 
 #[derive(Component)]
 pub struct PrefabComponent ;
+
+// the children of this prefab instance are despawned and spawned again from the prefab definition
+#[derive(Component)]
+pub struct RebuildPrefabChildren ;
  
 
 
@@ -28,14 +35,14 @@ pub struct PrefabToolState {
 
 
 
-#[derive(Event)]
+#[derive(Message)]
 pub enum PrefabToolEvent {
     SetSelectedPrefab(Option<String>)
 }
 
 
 
-#[derive(Event)]
+#[derive(Message)]
 pub enum PrefabEvent {
    
     SaveAllPrefabs,
@@ -49,11 +56,11 @@ pub enum PrefabEvent {
 
 
 
-#[derive(Event)]
+#[derive(Message)]
 pub struct SavePrefabToFileEvent(pub Entity) ;
 
 
-#[derive(Event)]
+#[derive(Message)]
 pub struct SpawnPrefabEvent {
 
 	pub position: Vec3,
@@ -66,14 +73,19 @@ pub struct SpawnPrefabEvent {
 
     pub zone: Option<Entity> ,
 
+    pub zone_entity_id: Option<ZoneEntityId>,
+
+    pub overrides: Option<PrefabOverrides>,
 
+    // placed by a tool, so the spawn becomes an undoable history entry
+    pub record_history: bool,
 
 } 
 
 pub fn handle_prefab_events(  
 
 
-    mut prefab_evt_reader: EventReader<PrefabEvent>,
+    mut prefab_evt_reader: MessageReader<PrefabEvent>,
 
 
     prefab_entity_query: Query<
@@ -100,7 +112,7 @@ pub fn handle_prefab_events(
                 for prefab_entity in prefab_entity_query.iter(){
 
 
-                    commands.send_event( SavePrefabToFileEvent ( prefab_entity.clone() ) );
+                    commands.write_message( SavePrefabToFileEvent ( prefab_entity.clone() ) );
 
                     
                 }
@@ -127,11 +139,14 @@ pub fn handle_prefab_events(
 
 
 pub fn handle_save_prefab_events(
-  //  mut commands: Commands,
-    mut evt_reader: EventReader<SavePrefabToFileEvent>,
+    mut commands: Commands,
+    mut evt_reader: MessageReader<SavePrefabToFileEvent>,
 
     entity_ref_query: Query<EntityRef>  ,
 
+    prefab_instance_query: Query<(Entity, &Name), With<PrefabComponent>>,
+
+    mut prefab_definitions: ResMut<PrefabDefinitionsResource>,
       
 ) {
     for evt in evt_reader.read() {
@@ -163,30 +178,32 @@ pub fn handle_save_prefab_events(
         };
         
 
-       // let mut all_children: Vec<Entity> = Vec::new();
+        if prefab_entity_ref.get::<Children>().is_none() {continue};
 
-        /*for child in DescendantIter::new(&children_query, ent.clone()) {
-            all_children.push(child);
-        }*/
+        //nested prefab instances are saved as references, with their overrides
+        let zone_entities:Vec<ZoneEntity> = prefab_instance_children( ent, &entity_ref_query, &prefab_definitions );
 
-        let mut zone_entities:Vec<ZoneEntity> = Vec::new();
-
-        let Some(zone_children) = prefab_entity_ref.get::<Children>()  else {continue};
-
-        for child_entity in zone_children {
-
-
-            if let Some(child_entity_ref) = entity_ref_query.get( *child_entity ).ok() {
-
-                if let Some(zone_entity) = ZoneEntity::from_entity_ref( &child_entity_ref ) {
-                    zone_entities.push(zone_entity);
-                }
-            }
 
+        let nested_cycle = zone_entities.iter().find_map(|zone_entity| match zone_entity {
+            ZoneEntity::Prefab { name, .. } if prefab_would_nest_itself( &fixed_prefab_name, name, &prefab_definitions ) => Some(name.clone()),
+            _ => None
+        });
 
+        if let Some(nested_name) = nested_cycle {
+            warn!("cannot save prefab {}: the nested prefab {} contains {}", fixed_prefab_name, nested_name, fixed_prefab_name);
+            continue;
         }
 
 
+        // the other instances keep their differences from the old version of the prefab
+        let other_instances: Vec<(Entity, PrefabOverrides)> = prefab_instance_query
+            .iter()
+            .filter(|(instance, name)| *instance != ent && name.as_str() == fixed_prefab_name)
+            .filter_map(|(instance, _)| {
+                prefab_instance_overrides( instance, &fixed_prefab_name, &entity_ref_query, &prefab_definitions )
+                    .map(|overrides| (instance, overrides))
+            })
+            .collect();
 
 
         let prefab_file = PrefabFile {
@@ -202,7 +219,24 @@ pub fn handle_save_prefab_events(
         let file_saved = std::fs::write(zone_file_name, ron);
 
         println!("exported prefab ! {:?}", file_saved);
+
+        if file_saved.is_err() {continue};
+
+
+        prefab_definitions.loaded_prefab_definitions
+            .get_or_insert_with(Default::default)
+            .insert(fixed_prefab_name.clone(), prefab_file);
+
+        // the saved instance is now the prefab itself
+        commands.entity(ent).remove::<PrefabOverridesComponent>();
+
+        for (instance, overrides) in other_instances {
+
+            commands.entity(instance).insert((
+                PrefabOverridesComponent(overrides),
+                RebuildPrefabChildren,
+            ));
+        }
             
     }
 }
-
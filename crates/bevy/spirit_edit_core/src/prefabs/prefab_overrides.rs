@@ -0,0 +1,474 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use bevy::prelude::*;
+
+use serde::{Deserialize, Serialize};
+
+use uuid::Uuid;
+
+use crate::zones::zone_file::{serialize_custom_props_sorted, CustomPropsMap, TransformSimpleRads, ZoneEntityId, ZoneEntityV2};
+
+use super::prefab_definitions::PrefabDefinitionsResource;
+
+/*
+
+Per instance overrides of a prefab.
+
+Every entity in a prefab file has a ZoneEntityId.  The children spawned for an instance get that
+id mixed with the id of the instance (instance_child_id), so they are unique in the scene and the
+history can find them again after the instance is rebuilt, and mixing again gives back the id in
+the prefab file so the children of an instance can be compared to the prefab they came from.
+Only the differences are stored with the instance in the zone file (moved children, changed and
+removed custom props, material overrides, removed and added children) so changes to the prefab
+itself still reach the instance.
+
+A nested prefab is a Prefab entity inside a prefab file.  Its own overrides are stored with the
+child override of the prefab that contains it.
+
+*/
+
+// transforms closer than this count as unchanged, saving and loading is not exact
+const TRANSFORM_EPSILON: f32 = 0.0001;
+
+const MATERIAL_OVERRIDE_PROP: &str = "material_override";
+
+// the version and variant bits of a v4 uuid, left alone so mixed ids are still v4
+const UUID_VERSION_VARIANT_MASK: u128 = 0x0000_0000_0000_f000_c000_0000_0000_0000;
+
+// deeper than this is treated as a cycle, even if the names differ
+pub const MAX_PREFAB_NESTING_DEPTH: usize = 16;
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct PrefabChildOverride {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transform: Option<TransformSimpleRads>,
+
+    // only the props that differ from the prefab
+    #[serde(default, skip_serializing_if = "Option::is_none", serialize_with = "serialize_custom_props_sorted")]
+    pub custom_props: Option<CustomPropsMap>,
+
+    // props of the prefab that the instance does not have, including the material override
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub removed_custom_props: BTreeSet<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub material_override: Option<String>,
+
+    // for nested prefabs, replaces the overrides the nested prefab has in the prefab file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prefab_overrides: Option<PrefabOverrides>,
+}
+
+impl PrefabChildOverride {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct PrefabOverrides {
+    // keyed by the id of the child in the prefab file
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub children: BTreeMap<ZoneEntityId, PrefabChildOverride>,
+
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub removed_children: BTreeSet<ZoneEntityId>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub added_children: Vec<ZoneEntityV2>,
+}
+
+/// The id of a child of a prefab instance, from the id of the instance and the id of the child in
+/// the prefab file.  Mixing an instance child id with the instance id again gives back the id in
+/// the prefab file.
+pub fn instance_child_id(instance_id: ZoneEntityId, child_id: ZoneEntityId) -> ZoneEntityId {
+    let instance_bits = instance_id.0.as_u128() & !UUID_VERSION_VARIANT_MASK;
+
+    ZoneEntityId(Uuid::from_u128(child_id.0.as_u128() ^ instance_bits))
+}
+
+/// The overrides an instance was spawned with, so its children can be spawned from them.
+#[derive(Component, Clone, Debug, Default)]
+pub struct PrefabOverridesComponent(pub PrefabOverrides);
+
+impl PrefabOverrides {
+    pub fn is_empty(&self) -> bool {
+        self.children.is_empty() && self.removed_children.is_empty() && self.added_children.is_empty()
+    }
+
+    /// The differences between the entities of a prefab and the current children of an instance.
+    pub fn from_instance(source_entities: &[ZoneEntityV2], instance_entities: &[ZoneEntityV2]) -> Self {
+        let mut overrides = Self::default();
+
+        let instance_by_id: BTreeMap<ZoneEntityId, &ZoneEntityV2> = instance_entities
+            .iter()
+            .map(|entity| (entity.get_id(), entity))
+            .collect();
+
+        let source_ids: BTreeSet<ZoneEntityId> = source_entities.iter().map(|entity| entity.get_id()).collect();
+
+        for source_entity in source_entities.iter() {
+            let id = source_entity.get_id();
+
+            let Some(instance_entity) = instance_by_id.get(&id) else {
+                overrides.removed_children.insert(id);
+                continue;
+            };
+
+            let child_override = child_override_between(source_entity, instance_entity);
+
+            if !child_override.is_empty() {
+                overrides.children.insert(id, child_override);
+            }
+        }
+
+        overrides.added_children = instance_entities
+            .iter()
+            .filter(|entity| !source_ids.contains(&entity.get_id()))
+            .cloned()
+            .collect();
+
+        overrides
+    }
+
+    /// The entities an instance should have: the prefab entities with these overrides applied.
+    pub fn apply_to_entities(&self, source_entities: &[ZoneEntityV2]) -> Vec<ZoneEntityV2> {
+        let mut entities: Vec<ZoneEntityV2> = source_entities
+            .iter()
+            .filter(|entity| !self.removed_children.contains(&entity.get_id()))
+            .map(|entity| match self.children.get(&entity.get_id()) {
+                Some(child_override) => apply_child_override(entity, child_override),
+                None => entity.clone(),
+            })
+            .collect();
+
+        entities.extend(self.added_children.iter().cloned());
+
+        entities
+    }
+}
+
+fn transforms_match(a: &TransformSimpleRads, b: &TransformSimpleRads) -> bool {
+    a.translation.abs_diff_eq(b.translation, TRANSFORM_EPSILON)
+        && a.rotation.abs_diff_eq(b.rotation, TRANSFORM_EPSILON)
+        && a.scale.abs_diff_eq(b.scale, TRANSFORM_EPSILON)
+}
+
+fn child_override_between(source: &ZoneEntityV2, instance: &ZoneEntityV2) -> PrefabChildOverride {
+    let mut child_override = PrefabChildOverride::default();
+
+    let (source_transform, instance_transform) = (source.get_transform_simple(), instance.get_transform_simple());
+
+    if !transforms_match(source_transform, instance_transform) {
+        child_override.transform = Some(instance_transform.clone());
+    }
+
+    match (source, instance) {
+        (
+            ZoneEntityV2::Doodad { custom_props: source_props, .. },
+            ZoneEntityV2::Doodad { custom_props: instance_props, .. },
+        ) => {
+            let source_props = source_props.clone().unwrap_or_default();
+
+            child_override.removed_custom_props = source_props
+                .keys()
+                .filter(|key| instance_props.as_ref().is_none_or(|props| !props.contains_key(*key)))
+                .cloned()
+                .collect();
+
+            let mut changed_props: CustomPropsMap = instance_props
+                .iter()
+                .flatten()
+                .filter(|(key, value)| source_props.get(*key) != Some(*value))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect();
+
+            if let Some(material_override) = changed_props.remove(MATERIAL_OVERRIDE_PROP) {
+                child_override.material_override = Some(material_override.to_string());
+            }
+
+            if !changed_props.is_empty() {
+                child_override.custom_props = Some(changed_props);
+            }
+        }
+
+        (
+            ZoneEntityV2::Prefab { overrides: source_overrides, .. },
+            ZoneEntityV2::Prefab { overrides: instance_overrides, .. },
+        ) => {
+            let instance_overrides = instance_overrides.clone().unwrap_or_default();
+
+            if instance_overrides != source_overrides.clone().unwrap_or_default() {
+                child_override.prefab_overrides = Some(instance_overrides);
+            }
+        }
+
+        _ => {}
+    }
+
+    child_override
+}
+
+fn apply_child_override(source: &ZoneEntityV2, child_override: &PrefabChildOverride) -> ZoneEntityV2 {
+    let mut entity = source.clone();
+
+    if let Some(transform) = &child_override.transform {
+        *entity.get_transform_simple_mut() = transform.clone();
+    }
+
+    match &mut entity {
+        ZoneEntityV2::Doodad { custom_props, .. } => {
+            if let Some(custom_props) = custom_props.as_mut() {
+                custom_props.retain(|key, _| !child_override.removed_custom_props.contains(key));
+            }
+
+            if child_override.custom_props.is_some() || child_override.material_override.is_some() {
+                let custom_props = custom_props.get_or_insert_with(Default::default);
+
+                if let Some(override_props) = &child_override.custom_props {
+                    custom_props.extend(override_props.clone());
+                }
+
+                if let Some(material_override) = &child_override.material_override {
+                    custom_props.insert(
+                        MATERIAL_OVERRIDE_PROP.to_string(),
+                        crate::zones::zone_file::CustomProp::String(material_override.clone()),
+                    );
+                }
+            }
+        }
+
+        ZoneEntityV2::Prefab { overrides, .. } => {
+            if let Some(prefab_overrides) = &child_override.prefab_overrides {
+                *overrides = Some(prefab_overrides.clone());
+            }
+        }
+
+        _ => {}
+    }
+
+    entity
+}
+
+/// The chain of prefab names that leads back to `prefab_name`, if the prefab contains itself
+/// through its nested prefabs.
+pub fn find_prefab_cycle(prefab_name: &str, prefab_definitions: &PrefabDefinitionsResource) -> Option<Vec<String>> {
+    let mut path = vec![prefab_name.to_string()];
+
+    find_prefab_cycle_from(prefab_name, prefab_name, prefab_definitions, &mut path).then_some(path)
+}
+
+fn find_prefab_cycle_from(
+    root_name: &str,
+    prefab_name: &str,
+    prefab_definitions: &PrefabDefinitionsResource,
+    path: &mut Vec<String>,
+) -> bool {
+    let Some(prefab_definition) = prefab_definitions.get_prefab_definition_by_name(&prefab_name.to_string()) else {
+        return false;
+    };
+
+    for entity in prefab_definition.entities.iter() {
+        let ZoneEntityV2::Prefab { name: nested_name, .. } = entity else {
+            continue;
+        };
+
+        path.push(nested_name.clone());
+
+        if nested_name == root_name || path.len() > MAX_PREFAB_NESTING_DEPTH {
+            return true;
+        }
+
+        // another cycle further down, it is reported for that prefab
+        if path[..path.len() - 1].contains(nested_name) {
+            path.pop();
+            continue;
+        }
+
+        if find_prefab_cycle_from(root_name, nested_name, prefab_definitions, path) {
+            return true;
+        }
+
+        path.pop();
+    }
+
+    false
+}
+
+/// Whether putting `nested_name` into `prefab_name` would make the prefab contain itself.
+pub fn prefab_would_nest_itself(
+    prefab_name: &str,
+    nested_name: &str,
+    prefab_definitions: &PrefabDefinitionsResource,
+) -> bool {
+    if prefab_name == nested_name {
+        return true;
+    }
+
+    let mut path = vec![nested_name.to_string()];
+
+    find_prefab_cycle_from(prefab_name, nested_name, prefab_definitions, &mut path)
+}
+
+/// Like ZoneEntityV2::from_entity_ref, but prefab instances also get their overrides, which
+/// are worked out from their children.
+pub fn zone_entity_with_prefab_overrides(
+    entity: Entity,
+    entity_ref_query: &Query<EntityRef>,
+    prefab_definitions: &PrefabDefinitionsResource,
+) -> Option<ZoneEntityV2> {
+    zone_entity_at_depth(entity, entity_ref_query, prefab_definitions, 0)
+}
+
+/// The children of an entity as zone entities, with the overrides of nested prefab instances.
+pub fn prefab_instance_children(
+    instance: Entity,
+    entity_ref_query: &Query<EntityRef>,
+    prefab_definitions: &PrefabDefinitionsResource,
+) -> Vec<ZoneEntityV2> {
+    instance_children_at_depth(instance, entity_ref_query, prefab_definitions, 0)
+}
+
+/// The overrides of a prefab instance relative to the prefab it was spawned from.
+pub fn prefab_instance_overrides(
+    instance: Entity,
+    prefab_name: &str,
+    entity_ref_query: &Query<EntityRef>,
+    prefab_definitions: &PrefabDefinitionsResource,
+) -> Option<PrefabOverrides> {
+    let prefab_definition = prefab_definitions.get_prefab_definition_by_name(&prefab_name.to_string())?;
+
+    let instance_children = prefab_instance_children(instance, entity_ref_query, prefab_definitions);
+
+    Some(PrefabOverrides::from_instance(&prefab_definition.entities, &instance_children))
+}
+
+fn zone_entity_at_depth(
+    entity: Entity,
+    entity_ref_query: &Query<EntityRef>,
+    prefab_definitions: &PrefabDefinitionsResource,
+    depth: usize,
+) -> Option<ZoneEntityV2> {
+    let entity_ref = entity_ref_query.get(entity).ok()?;
+
+    let mut zone_entity = ZoneEntityV2::from_entity_ref(&entity_ref)?;
+
+    if let ZoneEntityV2::Prefab { name, overrides, .. } = &mut zone_entity {
+        let prefab_definition = prefab_definitions.get_prefab_definition_by_name(name);
+
+        if let (Some(prefab_definition), true) = (prefab_definition, depth < MAX_PREFAB_NESTING_DEPTH) {
+            let instance_children =
+                instance_children_at_depth(entity, entity_ref_query, prefab_definitions, depth + 1);
+
+            let instance_overrides = PrefabOverrides::from_instance(&prefab_definition.entities, &instance_children);
+
+            *overrides = (!instance_overrides.is_empty()).then_some(instance_overrides);
+        }
+    }
+
+    Some(zone_entity)
+}
+
+fn instance_children_at_depth(
+    instance: Entity,
+    entity_ref_query: &Query<EntityRef>,
+    prefab_definitions: &PrefabDefinitionsResource,
+    depth: usize,
+) -> Vec<ZoneEntityV2> {
+    let Ok(instance_ref) = entity_ref_query.get(instance) else {
+        return Vec::new();
+    };
+
+    let Some(children) = instance_ref.get::<Children>() else {
+        return Vec::new();
+    };
+
+    let instance_id = instance_ref.get::<ZoneEntityId>().copied();
+
+    children
+        .into_iter()
+        .filter_map(|child| zone_entity_at_depth(*child, entity_ref_query, prefab_definitions, depth))
+        .map(|mut zone_entity| {
+            // back to the id the child has in the prefab file
+            if let Some(instance_id) = instance_id {
+                zone_entity.set_id(instance_child_id(instance_id, zone_entity.get_id()));
+            }
+            zone_entity
+        })
+        .collect()
+}
+
+/// Like prefab_instance_overrides, for exclusive systems.
+pub fn prefab_instance_overrides_in_world(world: &World, instance: Entity, prefab_name: &str) -> Option<PrefabOverrides> {
+    let prefab_definitions = world.get_resource::<PrefabDefinitionsResource>()?;
+
+    let mut entity_ref_state = world.try_query::<EntityRef>()?;
+    let entity_ref_query = entity_ref_state.query(world);
+
+    prefab_instance_overrides(instance, prefab_name, &entity_ref_query, prefab_definitions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::zones::zone_file::CustomProp;
+
+    fn doodad_with_props(id: ZoneEntityId, props: &[(&str, CustomProp)]) -> ZoneEntityV2 {
+        ZoneEntityV2::Doodad {
+            id,
+            name: "crate".to_string(),
+            transform: TransformSimpleRads::default(),
+            custom_props: Some(props.iter().map(|(key, value)| (key.to_string(), value.clone())).collect()),
+        }
+    }
+
+    #[test]
+    fn test_instance_child_id_round_trips() {
+        let instance_id = ZoneEntityId::new_random();
+        let child_id = ZoneEntityId::new_random();
+
+        let mixed = instance_child_id(instance_id, child_id);
+
+        assert_ne!(mixed, child_id);
+        assert_eq!(mixed.0.get_version_num(), 4);
+        assert_eq!(instance_child_id(instance_id, mixed), child_id);
+    }
+
+    #[test]
+    fn test_instance_child_ids_differ_per_instance() {
+        let child_id = ZoneEntityId::new_random();
+
+        assert_ne!(
+            instance_child_id(ZoneEntityId::new_random(), child_id),
+            instance_child_id(ZoneEntityId::new_random(), child_id)
+        );
+    }
+
+    #[test]
+    fn test_removed_custom_props_are_recorded_and_applied() {
+        let id = ZoneEntityId::new_random();
+
+        let source = doodad_with_props(id, &[
+            ("health", CustomProp::Integer(10)),
+            (MATERIAL_OVERRIDE_PROP, CustomProp::String("stone".to_string())),
+        ]);
+        let instance = doodad_with_props(id, &[("health", CustomProp::Integer(10))]);
+
+        let overrides = PrefabOverrides::from_instance(&[source.clone()], &[instance.clone()]);
+
+        let child_override = overrides.children.get(&id).expect("the removed prop is an override");
+        assert!(child_override.removed_custom_props.contains(MATERIAL_OVERRIDE_PROP));
+        assert_eq!(child_override.material_override, None);
+
+        assert_eq!(overrides.apply_to_entities(&[source]), vec![instance]);
+    }
+
+    #[test]
+    fn test_unchanged_instance_has_no_overrides() {
+        let id = ZoneEntityId::new_random();
+        let source = doodad_with_props(id, &[("health", CustomProp::Integer(10))]);
+
+        assert!(PrefabOverrides::from_instance(&[source.clone()], &[source]).is_empty());
+    }
+}
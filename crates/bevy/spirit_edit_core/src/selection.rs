@@ -0,0 +1,183 @@
+use bevy::prelude::*;
+
+use serde::{Deserialize, Serialize};
+
+use crate::doodads::doodad_manifest::DoodadTagMapResource;
+use crate::zones::zone_file::ZoneEntityId;
+
+/*
+
+Multi-selection support shared by the editor windows.
+
+The selection itself lives in the editor hierarchy. This holds the pivot mode and 3D cursor used by
+the transform gizmo, the shape of the viewport box / lasso selection, and the named selection
+groups of each zone.
+
+A group only stores the ids of entities directly in its zone, so a selection spanning several
+zones is saved as one group per zone under the same name.
+
+*/
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PivotMode {
+    #[default]
+    MedianPoint,
+    IndividualOrigins,
+    ActiveElement, // the entity selected last
+    Cursor3d,
+}
+
+impl PivotMode {
+    pub const ALL: [PivotMode; 4] = [
+        PivotMode::MedianPoint,
+        PivotMode::IndividualOrigins,
+        PivotMode::ActiveElement,
+        PivotMode::Cursor3d,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::MedianPoint => "Median Point",
+            Self::IndividualOrigins => "Individual Origins",
+            Self::ActiveElement => "Active Element",
+            Self::Cursor3d => "3D Cursor",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ViewportSelectShape {
+    #[default]
+    Box,
+    Lasso,
+}
+
+#[derive(Resource, Default)]
+pub struct SelectionToolsState {
+    pub pivot_mode: PivotMode,
+    pub cursor_3d: Vec3,
+
+    pub viewport_select_shape: ViewportSelectShape,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SelectionGroup {
+    pub name: String,
+    pub entity_ids: Vec<ZoneEntityId>,
+}
+
+// the selection groups of a zone, loaded from and saved to its zone file
+#[derive(Component, Clone, Debug, Default)]
+pub struct SelectionGroupsComponent {
+    pub groups: Vec<SelectionGroup>,
+}
+
+impl SelectionGroupsComponent {
+    pub fn get(&self, name: &str) -> Option<&SelectionGroup> {
+        self.groups.iter().find(|group| group.name == name)
+    }
+
+    /// Replaces the group with this name, a group without entities is removed.
+    pub fn set_group(&mut self, name: &str, entity_ids: Vec<ZoneEntityId>) {
+        if entity_ids.is_empty() {
+            self.remove_group(name);
+            return;
+        }
+
+        match self.groups.iter_mut().find(|group| group.name == name) {
+            Some(group) => group.entity_ids = entity_ids,
+            None => self.groups.push(SelectionGroup {
+                name: name.to_string(),
+                entity_ids,
+            }),
+        }
+    }
+
+    pub fn remove_group(&mut self, name: &str) {
+        self.groups.retain(|group| group.name != name);
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SelectionFilter {
+    Name(String), // case insensitive, part of the doodad name
+    Tag(String),
+}
+
+impl SelectionFilter {
+    pub fn matches(&self, doodad_name: &str, doodad_tag_map: &DoodadTagMapResource) -> bool {
+        match self {
+            Self::Name(name) => {
+                !name.is_empty() && doodad_name.to_lowercase().contains(&name.to_lowercase())
+            }
+            Self::Tag(tag) => doodad_tag_map
+                .doodad_tag_map
+                .get(tag)
+                .is_some_and(|doodad_names| doodad_names.iter().any(|tagged_name| tagged_name == doodad_name)),
+        }
+    }
+}
+
+/// Even-odd test of a screen point against a lasso outline.
+pub fn lasso_contains(lasso: &[Vec2], point: Vec2) -> bool {
+    if lasso.len() < 3 {
+        return false;
+    }
+
+    let mut inside = false;
+    let mut previous = lasso[lasso.len() - 1];
+
+    for current in lasso.iter().copied() {
+        let crosses = (current.y > point.y) != (previous.y > point.y)
+            && point.x < (previous.x - current.x) * (point.y - current.y) / (previous.y - current.y) + current.x;
+
+        if crosses {
+            inside = !inside;
+        }
+
+        previous = current;
+    }
+
+    inside
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lasso_contains_square() {
+        let lasso = [Vec2::new(0.0, 0.0), Vec2::new(10.0, 0.0), Vec2::new(10.0, 10.0), Vec2::new(0.0, 10.0)];
+
+        assert!(lasso_contains(&lasso, Vec2::new(5.0, 5.0)));
+        assert!(lasso_contains(&lasso, Vec2::new(0.5, 9.5)));
+        assert!(!lasso_contains(&lasso, Vec2::new(-1.0, 5.0)));
+        assert!(!lasso_contains(&lasso, Vec2::new(5.0, 11.0)));
+    }
+
+    #[test]
+    fn test_lasso_contains_concave() {
+        // a U shape, the notch at the top is outside
+        let lasso = [
+            Vec2::new(0.0, 0.0),
+            Vec2::new(30.0, 0.0),
+            Vec2::new(30.0, 30.0),
+            Vec2::new(20.0, 30.0),
+            Vec2::new(20.0, 10.0),
+            Vec2::new(10.0, 10.0),
+            Vec2::new(10.0, 30.0),
+            Vec2::new(0.0, 30.0),
+        ];
+
+        assert!(lasso_contains(&lasso, Vec2::new(5.0, 20.0)));
+        assert!(lasso_contains(&lasso, Vec2::new(25.0, 20.0)));
+        assert!(lasso_contains(&lasso, Vec2::new(15.0, 5.0)));
+        assert!(!lasso_contains(&lasso, Vec2::new(15.0, 20.0)));
+    }
+
+    #[test]
+    fn test_lasso_contains_needs_three_points() {
+        assert!(!lasso_contains(&[], Vec2::ZERO));
+        assert!(!lasso_contains(&[Vec2::new(-1.0, -1.0), Vec2::new(1.0, 1.0)], Vec2::ZERO));
+    }
+}
@@ -0,0 +1,406 @@
+use crate::placement::PlacementResource;
+use crate::zones::zone_file::ZoneEntityId;
+use bevy::prelude::*;
+
+use serde::{Deserialize, Serialize};
+
+/*
+
+Splines are zone entities that describe a path (road, river ...) through a list of control points.
+
+The control points are edited through child handle entities so they can be grabbed with the
+regular transform gizmo. The spline only stores data, the editor decides what to do with it
+(deforming the terrain, painting splat, spawning liquid).
+
+*/
+
+const CONTROL_POINT_HANDLE_RADIUS: f32 = 0.5;
+
+pub fn splines_plugin(app: &mut App) {
+    app
+        .register_type::<TerrainSpline>()
+        .register_type::<SplineControlPoint>()
+        .register_type::<SplineTerrainMode>()
+        .register_type::<SplineLiquid>()
+
+        .init_resource::<SplineHandleAssets>()
+
+        .add_message::<SplineEvent>()
+
+        .add_systems(Update, (
+            handle_spline_events,
+            sync_spline_from_control_point_handles,
+            sync_control_point_handles_from_spline,
+            draw_spline_gizmos,
+        ).chain());
+}
+
+#[derive(Component, Serialize, Deserialize, Clone, Debug, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct TerrainSpline {
+    // relative to the spline entity
+    pub control_points: Vec<SplineControlPoint>,
+    #[serde(default)]
+    pub closed: bool,
+
+    // full width of the flat part of the path
+    pub width: f32,
+    // distance over which the path blends back into the terrain (the embankment)
+    pub falloff: f32,
+
+    #[serde(default)]
+    pub terrain_mode: SplineTerrainMode,
+
+    #[serde(default)]
+    pub splat_texture_index: Option<u8>,
+
+    #[serde(default)]
+    pub liquid: Option<SplineLiquid>,
+}
+
+impl Default for TerrainSpline {
+    fn default() -> Self {
+        Self {
+            control_points: Vec::new(),
+            closed: false,
+            width: 6.0,
+            falloff: 4.0,
+            terrain_mode: SplineTerrainMode::Flatten,
+            splat_texture_index: None,
+            liquid: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Reflect)]
+pub struct SplineControlPoint {
+    pub position: Vec3,
+    // multiplies the spline width, interpolated between control points
+    #[serde(default = "default_width_scale")]
+    pub width_scale: f32,
+}
+
+fn default_width_scale() -> f32 {
+    1.0
+}
+
+impl SplineControlPoint {
+    pub fn new(position: Vec3) -> Self {
+        Self {
+            position,
+            width_scale: 1.0,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default, Reflect)]
+pub enum SplineTerrainMode {
+    // only paints and spawns liquid
+    None,
+    // levels the terrain to the height of the curve
+    #[default]
+    Flatten,
+    // digs the curve this far below its control points (river beds)
+    Carve { depth: f32 },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Reflect)]
+pub struct SplineLiquid {
+    pub liquid_type: String,
+    // offset of the liquid surface from the curve
+    pub height_offset: f32,
+}
+
+impl Default for SplineLiquid {
+    fn default() -> Self {
+        Self {
+            liquid_type: "water".into(),
+            height_offset: -0.25,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SplineSample {
+    pub position: Vec3,
+    pub width: f32,
+}
+
+impl TerrainSpline {
+    /// Samples the Catmull-Rom curve through the control points roughly every `spacing` units, in spline space.
+    pub fn sample_local(&self, spacing: f32) -> Vec<SplineSample> {
+        let points = &self.control_points;
+        let spacing = spacing.max(0.01);
+
+        match points.len() {
+            0 => return Vec::new(),
+            1 => {
+                return vec![SplineSample {
+                    position: points[0].position,
+                    width: self.width * points[0].width_scale,
+                }]
+            }
+            _ => {}
+        }
+
+        let count = points.len() as isize;
+        let segment_count = if self.closed { count } else { count - 1 };
+
+        let point_at = |index: isize| -> &SplineControlPoint {
+            if self.closed {
+                &points[index.rem_euclid(count) as usize]
+            } else {
+                &points[index.clamp(0, count - 1) as usize]
+            }
+        };
+
+        let mut samples = Vec::new();
+
+        for segment in 0..segment_count {
+            let p0 = point_at(segment - 1).position;
+            let p1 = point_at(segment);
+            let p2 = point_at(segment + 1);
+            let p3 = point_at(segment + 2).position;
+
+            let steps = ((p1.position.distance(p2.position) / spacing).ceil() as usize).max(1);
+
+            for step in 0..steps {
+                let t = step as f32 / steps as f32;
+
+                samples.push(SplineSample {
+                    position: catmull_rom(p0, p1.position, p2.position, p3, t),
+                    width: self.width * p1.width_scale.lerp(p2.width_scale, t),
+                });
+            }
+        }
+
+        let last = point_at(segment_count);
+        samples.push(SplineSample {
+            position: last.position,
+            width: self.width * last.width_scale,
+        });
+
+        samples
+    }
+
+    /// Same as `sample_local` but in world space.
+    pub fn sample_world(&self, global_transform: &GlobalTransform, spacing: f32) -> Vec<SplineSample> {
+        let width_scale = global_transform.scale().x.abs();
+
+        self.sample_local(spacing)
+            .into_iter()
+            .map(|sample| SplineSample {
+                position: global_transform.transform_point(sample.position),
+                width: sample.width * width_scale,
+            })
+            .collect()
+    }
+}
+
+fn catmull_rom(p0: Vec3, p1: Vec3, p2: Vec3, p3: Vec3, t: f32) -> Vec3 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+
+    0.5 * ((2.0 * p1)
+        + (p2 - p0) * t
+        + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2
+        + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t3)
+}
+
+/// Child of a spline entity that mirrors one of its control points.
+#[derive(Component, Clone, Debug)]
+pub struct SplineControlPointHandle {
+    pub index: usize,
+}
+
+#[derive(Resource)]
+pub struct SplineHandleAssets {
+    pub mesh: Handle<Mesh>,
+    pub material: Handle<StandardMaterial>,
+}
+
+impl FromWorld for SplineHandleAssets {
+    fn from_world(world: &mut World) -> Self {
+        let mesh = world
+            .resource_mut::<Assets<Mesh>>()
+            .add(Sphere::new(CONTROL_POINT_HANDLE_RADIUS).mesh());
+
+        let material = world
+            .resource_mut::<Assets<StandardMaterial>>()
+            .add(StandardMaterial {
+                base_color: Color::srgb(1.0, 0.8, 0.1),
+                unlit: true,
+                ..default()
+            });
+
+        Self { mesh, material }
+    }
+}
+
+#[derive(Message)]
+pub enum SplineEvent {
+    SpawnSpline {
+        name: String,
+        transform: Transform,
+        spline: TerrainSpline,
+        // falls back to the placement parent
+        zone: Option<Entity>,
+        zone_entity_id: Option<ZoneEntityId>,
+    },
+    // world space position
+    AddControlPoint { spline_entity: Entity, position: Vec3 },
+    RemoveLastControlPoint { spline_entity: Entity },
+}
+
+pub fn handle_spline_events(
+    mut commands: Commands,
+    mut evt_reader: MessageReader<SplineEvent>,
+
+    placement_resource: Res<PlacementResource>,
+
+    mut spline_query: Query<(&mut TerrainSpline, &GlobalTransform)>,
+) {
+    for evt in evt_reader.read() {
+        match evt {
+            SplineEvent::SpawnSpline { name, transform, spline, zone, zone_entity_id } => {
+                let spline_entity = commands
+                    .spawn((
+                        *transform,
+                        Visibility::default(),
+                        Name::new(name.clone()),
+                        spline.clone(),
+                    ))
+                    .id();
+
+                if let Some(zone_entity_id) = zone_entity_id {
+                    commands.entity(spline_entity).insert(*zone_entity_id);
+                }
+
+                if let Some(parent) = zone.or(placement_resource.placement_parent) {
+                    if let Ok(mut parent_cmd) = commands.get_entity(parent) {
+                        parent_cmd.add_child(spline_entity);
+                    }
+                }
+            }
+
+            SplineEvent::AddControlPoint { spline_entity, position } => {
+                let Ok((mut spline, global_transform)) = spline_query.get_mut(*spline_entity) else {
+                    continue;
+                };
+
+                let local_position = global_transform.affine().inverse().transform_point3(*position);
+
+                spline.control_points.push(SplineControlPoint::new(local_position));
+            }
+
+            SplineEvent::RemoveLastControlPoint { spline_entity } => {
+                let Ok((mut spline, _)) = spline_query.get_mut(*spline_entity) else {
+                    continue;
+                };
+
+                spline.control_points.pop();
+            }
+        }
+    }
+}
+
+// handles moved with the gizmo write their position back into the spline
+pub fn sync_spline_from_control_point_handles(
+    handle_query: Query<(&SplineControlPointHandle, &Transform, &ChildOf), Changed<Transform>>,
+    mut spline_query: Query<&mut TerrainSpline>,
+) {
+    for (handle, transform, child_of) in handle_query.iter() {
+        let Ok(mut spline) = spline_query.get_mut(child_of.parent()) else {
+            continue;
+        };
+
+        let Some(control_point) = spline.control_points.get(handle.index) else {
+            continue;
+        };
+
+        if control_point.position != transform.translation {
+            spline.control_points[handle.index].position = transform.translation;
+        }
+    }
+}
+
+pub fn sync_control_point_handles_from_spline(
+    mut commands: Commands,
+
+    spline_query: Query<(Entity, &TerrainSpline, Option<&Children>), Changed<TerrainSpline>>,
+    mut handle_query: Query<(&SplineControlPointHandle, &mut Transform)>,
+
+    handle_assets: Res<SplineHandleAssets>,
+) {
+    for (spline_entity, spline, children) in spline_query.iter() {
+        let mut existing_handles = 0;
+
+        for child in children.map(|c| c.to_vec()).unwrap_or_default() {
+            let Ok((handle, mut transform)) = handle_query.get_mut(child) else {
+                continue;
+            };
+
+            match spline.control_points.get(handle.index) {
+                Some(control_point) => {
+                    existing_handles = existing_handles.max(handle.index + 1);
+
+                    if transform.translation != control_point.position {
+                        transform.translation = control_point.position;
+                    }
+                }
+                None => {
+                    commands.entity(child).despawn();
+                }
+            }
+        }
+
+        for index in existing_handles..spline.control_points.len() {
+            let handle = commands
+                .spawn((
+                    Name::new(format!("SplineControlPoint {}", index)),
+                    SplineControlPointHandle { index },
+                    Transform::from_translation(spline.control_points[index].position),
+                    Visibility::default(),
+                    Mesh3d(handle_assets.mesh.clone()),
+                    MeshMaterial3d(handle_assets.material.clone()),
+                ))
+                .id();
+
+            commands.entity(spline_entity).add_child(handle);
+        }
+    }
+}
+
+fn draw_spline_gizmos(
+    mut gizmos: Gizmos,
+    spline_query: Query<(&TerrainSpline, &GlobalTransform)>,
+) {
+    for (spline, global_transform) in spline_query.iter() {
+        let samples = spline.sample_world(global_transform, 1.0);
+
+        let color = match spline.liquid {
+            Some(_) => Color::srgb(0.2, 0.5, 1.0),
+            None => Color::srgb(1.0, 0.8, 0.1),
+        };
+
+        gizmos.linestrip(samples.iter().map(|s| s.position), color);
+
+        // outline of the flat part of the path
+        let mut left_edge = Vec::with_capacity(samples.len());
+        let mut right_edge = Vec::with_capacity(samples.len());
+
+        for (index, sample) in samples.iter().enumerate() {
+            let previous = samples[index.saturating_sub(1)].position;
+            let next = samples[(index + 1).min(samples.len() - 1)].position;
+
+            let tangent = Vec3::new(next.x - previous.x, 0.0, next.z - previous.z).normalize_or_zero();
+            let side = Vec3::new(-tangent.z, 0.0, tangent.x) * sample.width * 0.5;
+
+            left_edge.push(sample.position + side);
+            right_edge.push(sample.position - side);
+        }
+
+        gizmos.linestrip(left_edge, color.with_alpha(0.5));
+        gizmos.linestrip(right_edge, color.with_alpha(0.5));
+    }
+}
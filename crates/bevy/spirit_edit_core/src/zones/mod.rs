@@ -1,20 +1,26 @@
 use crate::utils::StringUtilsExt;
 use crate::placement::PlacementEvent;
 use crate::prefabs::SpawnPrefabEvent;
+use crate::prefabs::PrefabComponent;
+use crate::prefabs::prefab_definitions::PrefabDefinitionsResource;
+use crate::prefabs::prefab_overrides::zone_entity_with_prefab_overrides;
+use crate::selection::{SelectionGroup, SelectionGroupsComponent};
+use crate::splines::SplineEvent;
 use crate::{doodads::PlaceClayTileEvent };
 use bevy_clay_tiles::clay_tile_block::ClayTileBlock;
 use std::fs;
 use bevy::prelude::*;
  
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use bevy::ecs::system::SystemParam;
 
 use crate::doodads::PlaceDoodadEvent;
 
 #[derive(Component)]
 pub struct ZoneComponent {}
 
-#[derive(Event)]
+#[derive(Message)]
 pub enum ZoneEvent {
    // SetZoneAsPrimary(Entity),
    // SaveZoneToFile(Entity),
@@ -25,16 +31,20 @@ pub enum ZoneEvent {
 }
 
 
-#[derive(Event)]
+#[derive(Message)]
 pub struct SaveZoneToFileEvent(pub Entity) ;
 
 
 
 pub mod zone_file;
+pub mod zone_merge;
+pub mod zone_streaming;
+pub mod zone_validation;
 
 use zone_file::ZoneFileV2 as ZoneFile;
 
 use self::zone_file::ZoneEntityV2 as ZoneEntity;
+use self::zone_file::ZoneEntityId;
 
 //use self::zone_file::CustomPropsComponent;
 
@@ -76,7 +86,7 @@ impl EditorWindow for ZoneWindow {
             ui.horizontal(|ui| {
                 ui.label(format!("Primary zone: {:?}", primary_zone_name.clone()));
                 if ui.button("Reset").clicked() {
-                    world.send_event::<ZoneEvent>(ZoneEvent::ResetPrimaryZone);
+                    world.write_message(ZoneEvent::ResetPrimaryZone);
                 }
             });
 
@@ -166,7 +176,7 @@ pub fn create_zone(
     world: &mut World,
     name: &str,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    world.send_event::<ZoneEvent>(ZoneEvent::CreateNewZone(name.into()));
+    world.write_message(ZoneEvent::CreateNewZone(name.into()));
 
     Ok(())
 }
@@ -175,7 +185,7 @@ pub fn load_zone(
     world: &mut World,
     name: &str,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    world.send_event::<ZoneEvent>(ZoneEvent::LoadZoneFile(name.into()));
+    world.write_message(ZoneEvent::LoadZoneFile(name.into()));
 
     Ok(())
 }
@@ -188,7 +198,7 @@ pub fn load_all_zones(
     let zone_file_names:Vec<String> = get_all_zone_file_names();
 
     for file_name in zone_file_names {
-          world.send_event::<ZoneEvent>(ZoneEvent::LoadZoneFile(file_name.into()));
+          world.write_message(ZoneEvent::LoadZoneFile(file_name.into()));
     }
 
   
@@ -222,9 +232,88 @@ pub fn get_all_zone_file_names() -> Vec<String> {
     file_names
 }
 
+// zone names may or may not end in .zone or .zone.ron
+pub fn zone_file_path(zone_name: &str) -> PathBuf {
+    let fixed_zone_name = match zone_name.ends_with("zone.ron") || zone_name.ends_with("zone") {
+        true => zone_name.split('.').next().unwrap_or_default(),
+        false => zone_name,
+    };
+
+    PathBuf::from(format!("assets/zonesv2/{}.zone.ron", fixed_zone_name))
+}
+
+/// The events that spawn the entities of a zone file, shared by zone loading and streaming.
+#[derive(SystemParam)]
+pub struct ZoneEntitySpawners<'w> {
+    spawn_doodad_event_writer: MessageWriter<'w, PlaceDoodadEvent>,
+    spawn_clay_tile_event_writer: MessageWriter<'w, PlaceClayTileEvent>,
+    spawn_prefab_event_writer: MessageWriter<'w, SpawnPrefabEvent>,
+    spline_event_writer: MessageWriter<'w, SplineEvent>,
+}
+
+impl ZoneEntitySpawners<'_> {
+    pub fn spawn(&mut self, zone_entity: &ZoneEntity, zone: Entity) {
+        self.spawn_entity(zone_entity, zone, false);
+    }
+
+    /// Spawns an entity the user created, like a duplicate, as an undoable history entry.
+    pub fn spawn_recorded(&mut self, zone_entity: &ZoneEntity, zone: Entity) {
+        self.spawn_entity(zone_entity, zone, true);
+    }
+
+    fn spawn_entity(&mut self, zone_entity: &ZoneEntity, zone: Entity, record_history: bool) {
+        match zone_entity {
+            ZoneEntity::Doodad { name, .. } => {
+                self.spawn_doodad_event_writer.write(PlaceDoodadEvent {
+                    doodad_name: name.clone(),
+                    position: zone_entity.get_position(),
+                    rotation_euler: Some(zone_entity.get_rotation_euler()),
+                    scale: Some(zone_entity.get_scale()),
+                    custom_props: zone_entity.get_custom_props().clone(),
+                    force_parent: Some(zone),
+                    auto_select: false,
+                    zone_entity_id: Some(zone_entity.get_id()),
+                    record_history,
+                });
+            }
+            ZoneEntity::ClayTile { clay_tile_block, .. } => {
+                self.spawn_clay_tile_event_writer.write(PlaceClayTileEvent {
+                    position: zone_entity.get_position(),
+                    rotation_euler: Some(zone_entity.get_rotation_euler()),
+                    scale: Some(zone_entity.get_scale()),
+                    clay_tile_block_data: clay_tile_block.clone(),
+                    zone: Some(zone),
+                    zone_entity_id: Some(zone_entity.get_id()),
+                    record_history,
+                });
+            }
+            ZoneEntity::Prefab { name, overrides, .. } => {
+                self.spawn_prefab_event_writer.write(SpawnPrefabEvent {
+                    position: zone_entity.get_position(),
+                    rotation_euler: Some(zone_entity.get_rotation_euler()),
+                    prefab_name: name.clone(),
+                    zone: Some(zone),
+                    zone_entity_id: Some(zone_entity.get_id()),
+                    overrides: overrides.clone(),
+                    record_history,
+                });
+            }
+            ZoneEntity::Spline { name, transform, spline, .. } => {
+                self.spline_event_writer.write(SplineEvent::SpawnSpline {
+                    name: name.clone(),
+                    transform: transform.to_transform(),
+                    spline: spline.clone(),
+                    zone: Some(zone),
+                    zone_entity_id: Some(zone_entity.get_id()),
+                });
+            }
+        }
+    }
+}
+
 pub fn handle_zone_events(
     mut commands: Commands,
-    mut evt_reader: EventReader<ZoneEvent>,
+    mut evt_reader: MessageReader<ZoneEvent>,
 
      
 
@@ -238,15 +327,11 @@ pub fn handle_zone_events(
       //(&Name, &Transform, Option<&CustomPropsComponent>, Option<&ClayTileBlock>),
      With<ZoneComponent>>, 
 
-    mut save_zone_evt_writer: EventWriter<SaveZoneToFileEvent>,
-
-    mut placement_evt_writer: EventWriter<PlacementEvent>,
+    mut save_zone_evt_writer: MessageWriter<SaveZoneToFileEvent>,
 
-    mut spawn_doodad_event_writer: EventWriter<PlaceDoodadEvent>,
+    mut placement_evt_writer: MessageWriter<PlacementEvent>,
 
-    mut spawn_clay_tile_event_writer: EventWriter<PlaceClayTileEvent>,
-    
-    mut spawn_prefab_event_writer: EventWriter<SpawnPrefabEvent>,
+    mut zone_entity_spawners: ZoneEntitySpawners,
 ) {
     for evt in evt_reader.read() {
         match evt {
@@ -260,7 +345,7 @@ pub fn handle_zone_events(
                     .insert(Name::new( name_fixed .to_string()))
                     .id();
 
-                placement_evt_writer.send(PlacementEvent::SetPlacementParent( Some(created_zone) ));
+                placement_evt_writer.write(PlacementEvent::SetPlacementParent( Some(created_zone) ));
 
                // zone_resource.primary_zone = Some(created_zone);
             }
@@ -270,7 +355,7 @@ pub fn handle_zone_events(
                 
                 for zone_entity in zone_entity_query.iter(){
 
-                    save_zone_evt_writer.send(
+                    save_zone_evt_writer.write(
                         SaveZoneToFileEvent(zone_entity)
                     );
                 }
@@ -323,24 +408,9 @@ pub fn handle_zone_events(
 
             ZoneEvent::LoadZoneFile(zone_name) => {
 
-                let fixed_zone_name = match zone_name.ends_with( "zone.ron" ) || zone_name.ends_with( "zone" ){
-
-                    true => {
-
-                          let   parts: Vec<&str> = zone_name.split('.').collect();
-                         
-                            parts.first().unwrap() .to_string()  
-                           
-
-                      }, 
-                    false => zone_name.to_string()
-
-                };
-
-
-                let file_name = format!("assets/zonesv2/{}.zone.ron", fixed_zone_name);
+                let file_name = zone_file_path(zone_name);
  
-                 let path = Path::new(&file_name);
+                 let path = file_name.as_path();
 
                 // Read the file into a string
                /* let Ok(file_content) = std::fs::read_to_string(path) else {
@@ -370,76 +440,23 @@ pub fn handle_zone_events(
                     .spawn((Transform::default(),Visibility::default()))
                     .insert(ZoneComponent {})
                     .insert(Name::new(zone_name.to_string()))
+                    .insert(SelectionGroupsComponent {
+                        groups: zone_file.selection_groups.clone(),
+                    })
                     
                     .id();
 
 
-                placement_evt_writer.send(PlacementEvent::SetPlacementParent( Some(created_zone) ));
+                placement_evt_writer.write(PlacementEvent::SetPlacementParent( Some(created_zone) ));
 
 
                  
 
                 //trigger spawn doodad events
 
-                for zone_entity in zone_file.entities {
-
-                    match zone_entity {
-
-                        ZoneEntity::Doodad { ref name, ref transform, ref custom_props } => {
-
-                             spawn_doodad_event_writer.send({
-                                PlaceDoodadEvent {
-                                    doodad_name: name.clone(),
-                                    position: zone_entity.get_position(),
-                                    rotation_euler: Some(zone_entity.get_rotation_euler()),
-                                    scale: Some(zone_entity.get_scale()),
-                                    custom_props: zone_entity.get_custom_props().clone(),
-                                  //  clay_tile_block_data: zone_entity.clay_tile_block_data.clone(), 
-                                    force_parent:Some(created_zone),
-                                    auto_select: false 
-                                }
-                            });
-
-
-                        },
-                        ZoneEntity::ClayTile { ref transform,   ref clay_tile_block } => {
-
-                              spawn_clay_tile_event_writer.send({
-                                PlaceClayTileEvent {
-                                    //doodad_name: name.clone(),
-                                    position: zone_entity.get_position(),
-                                    rotation_euler: Some(zone_entity.get_rotation_euler()),
-                                    scale: Some(zone_entity.get_scale()),
-                                   // custom_props: zone_entity.get_custom_props().clone(),
-                                    clay_tile_block_data: clay_tile_block.clone(), 
-                                    zone:Some(created_zone)
-                                }
-                            });
-
-
-                        },
-                        ZoneEntity::Prefab { ref name, ref transform } => {
-
-                            spawn_prefab_event_writer.send(
-
-                                SpawnPrefabEvent {
-                                 position:zone_entity.get_position(),
-                                 rotation_euler: Some(zone_entity.get_rotation_euler()),
-                                  prefab_name: name.clone(), 
-                                  zone: Some(created_zone)
-                               }
-                            );
-
-
-
-                        }
-
-                    }
-
-
-                   
-
+                for zone_entity in zone_file.entities.iter() {
 
+                    zone_entity_spawners.spawn(zone_entity, created_zone);
 
                 }
             }
@@ -451,10 +468,11 @@ pub fn handle_zone_events(
 
 pub fn handle_save_zone_events(
   //  mut commands: Commands,
-    mut evt_reader: EventReader<SaveZoneToFileEvent>,
+    mut evt_reader: MessageReader<SaveZoneToFileEvent>,
 
     entity_ref_query: Query<EntityRef>  ,
 
+    prefab_definitions: Res<PrefabDefinitionsResource>,
       
 ) {
     for evt in evt_reader.read() {
@@ -472,20 +490,11 @@ pub fn handle_save_zone_events(
 
         println!("handle save zone.." );
 
-
-        let fixed_zone_name = match zone_name.ends_with( "zone.ron" ) || zone_name.ends_with( "zone" ){
-
-            true => {
-
-                  let   parts: Vec<&str> = zone_name.split('.').collect();
-                 
-                    parts.first().unwrap() .to_string()  
-                   
-
-              }, 
-            false => zone_name.to_string()
-
-        };
+        // a streamed zone that is still spawning in would be saved without its missing entities
+        if zone_entity_ref.contains::<zone_streaming::StreamingInZone>() {
+            warn!("zone {} is still streaming in, not saving it", zone_name);
+            continue;
+        }
         
 
        // let mut all_children: Vec<Entity> = Vec::new();
@@ -509,11 +518,9 @@ pub fn handle_save_zone_events(
         for child_entity in zone_children {
 
 
-            if let Some(child_entity_ref) = entity_ref_query.get( *child_entity ).ok() {
-
-                if let Some(zone_entity) = ZoneEntity::from_entity_ref( &child_entity_ref ) {
-                    zone_entities.push(zone_entity);
-                }
+            //prefab instances also get their overrides from their children
+            if let Some(zone_entity) = zone_entity_with_prefab_overrides( *child_entity, &entity_ref_query, &prefab_definitions ) {
+                zone_entities.push(zone_entity);
             }
 
 
@@ -522,18 +529,55 @@ pub fn handle_save_zone_events(
 
 
 
+        // groups only keep members that are still in the zone
+        let selection_groups: Vec<SelectionGroup> = zone_entity_ref
+            .get::<SelectionGroupsComponent>()
+            .map(|selection_groups| {
+                selection_groups
+                    .groups
+                    .iter()
+                    .map(|group| SelectionGroup {
+                        name: group.name.clone(),
+                        entity_ids: group
+                            .entity_ids
+                            .iter()
+                            .filter(|id| zone_entities.iter().any(|zone_entity| zone_entity.get_id() == **id))
+                            .copied()
+                            .collect(),
+                    })
+                    .filter(|group| !group.entity_ids.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+
         let zone_file = ZoneFile {
 
             entities: zone_entities,
+            selection_groups,
             ..default()
         };
 
-        let zone_file_name = format!("assets/zonesv2/{}.zone.ron", fixed_zone_name);
-
-        let ron = ron::ser::to_string(&zone_file).unwrap();
-        let file_saved = std::fs::write(zone_file_name, ron);
+        let file_saved = zone_file.save_to_path( &zone_file_path(zone_name) );
 
         println!("exported zone ! {:?}", file_saved);
             
     }
 }
+
+
+// every entity directly in a zone keeps the same id across saves, and so does every child
+// of a prefab instance so it can be matched to the prefab file
+pub fn assign_zone_entity_ids(
+    mut commands: Commands,
+
+    zone_child_query: Query<(Entity, &ChildOf), Without<ZoneEntityId>>,
+    zone_query: Query<(), Or<(With<ZoneComponent>, With<PrefabComponent>)>>,
+) {
+    for (entity, child_of) in zone_child_query.iter() {
+        if zone_query.get(child_of.parent()).is_err() {
+            continue;
+        }
+
+        commands.entity(entity).insert(ZoneEntityId::new_random());
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::f32::consts::PI;
 use std::path::Path;
 
@@ -6,11 +7,18 @@ use bevy::{prelude::* };
 use bevy::platform::collections::hash_map::HashMap;
 
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, Serializer};
+
+use ron::ser::PrettyConfig;
+
+use uuid::Uuid;
 
 use bevy_clay_tiles::{ clay_tile_block:: ClayTileBlock };
 
 use crate::prefabs::PrefabComponent; 
+use crate::prefabs::prefab_overrides::PrefabOverrides;
+use crate::selection::SelectionGroup;
+use crate::splines::TerrainSpline;
 
 
 #[derive(Serialize, Deserialize,Default,Clone,Debug)]
@@ -50,7 +58,15 @@ impl ZoneFile {
 #[derive(Serialize, Deserialize,Default,Clone,Debug)]
 pub struct ZoneFileV2 {
     pub translation_offset: Option<Vec3>, 
+
+    // written on every save, used by zone streaming to decide when to load the zone
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bounds: Option<ZoneBounds>,
+
     pub entities: Vec<ZoneEntityV2>, 
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub selection_groups: Vec<SelectionGroup>,
 }
 
 impl ZoneFileV2 {
@@ -58,7 +74,9 @@ impl ZoneFileV2 {
    pub fn from_zone_file(zone_file: ZoneFile) -> Self {
         ZoneFileV2 {
             translation_offset: zone_file.translation_offset,
+            bounds: None,
             entities: zone_file.entities.into_iter().map(ZoneEntityV2::from_zone_entity).collect(),
+            selection_groups: Vec::new(),
         }
     }
 
@@ -83,6 +101,134 @@ impl ZoneFileV2 {
         Some(zone_file)
 
     }
+
+    /// Pretty RON with the entities sorted by id, one block per entity, so the same zone
+    /// always produces the same text and unrelated edits do not touch the same lines.
+    pub fn to_pretty_ron(&self) -> Result<String, ron::Error> {
+        let mut sorted = self.clone();
+        sorted.entities.sort_by_key(|entity| entity.get_id());
+        sorted.bounds = sorted.compute_bounds();
+
+        for group in sorted.selection_groups.iter_mut() {
+            group.entity_ids.sort();
+        }
+
+        let pretty_config = PrettyConfig::new()
+            .new_line("\n".to_string())
+            .indentor("    ".to_string());
+
+        let mut ron = ron::ser::to_string_pretty(&sorted, pretty_config)?;
+        ron.push('\n');
+
+        Ok(ron)
+    }
+
+    /// Bounds of everything in the zone, relative to the zone.  Prefab instances only count
+    /// with their position, the prefab contents are not loaded here.
+    pub fn compute_bounds(&self) -> Option<ZoneBounds> {
+        let mut points: Vec<Vec3> = Vec::new();
+
+        for zone_entity in self.entities.iter() {
+            let transform = zone_entity.get_transform_simple().to_transform();
+
+            points.push(transform.translation);
+
+            match zone_entity {
+                ZoneEntityV2::ClayTile { clay_tile_block, .. } => points.extend(
+                    clay_tile_block
+                        .polygon_points
+                        .iter()
+                        .map(|point| transform.transform_point(Vec3::new(point.x as f32, 0.0, point.y as f32))),
+                ),
+                ZoneEntityV2::Spline { spline, .. } => points.extend(
+                    spline
+                        .control_points
+                        .iter()
+                        .map(|control_point| transform.transform_point(control_point.position)),
+                ),
+                _ => {}
+            }
+        }
+
+        ZoneBounds::from_points(points.into_iter().filter(|point| point.is_finite()))
+    }
+
+    pub fn save_to_path(&self, path: &Path) -> std::io::Result<()> {
+        let ron = self
+            .to_pretty_ron()
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+
+        std::fs::write(path, ron)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct ZoneBounds {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl ZoneBounds {
+    pub fn from_points(points: impl IntoIterator<Item = Vec3>) -> Option<Self> {
+        points.into_iter().fold(None, |bounds: Option<Self>, point| {
+            Some(match bounds {
+                Some(bounds) => Self {
+                    min: bounds.min.min(point),
+                    max: bounds.max.max(point),
+                },
+                None => Self { min: point, max: point },
+            })
+        })
+    }
+
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) / 2.0
+    }
+
+    pub fn size(&self) -> Vec3 {
+        self.max - self.min
+    }
+
+    /// Horizontal distance from a point to the bounds, zero inside them.
+    pub fn distance_xz(&self, point: Vec2) -> f32 {
+        let closest = point.clamp(self.min.xz(), self.max.xz());
+
+        point.distance(closest)
+    }
+}
+
+/// Identity of an entity across saves, so zone files can be diffed and merged per entity.
+#[derive(Component, Reflect, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(transparent)]
+pub struct ZoneEntityId(pub Uuid);
+
+impl ZoneEntityId {
+    pub fn new_random() -> Self {
+        Self(Uuid::new_v4())
+    }
+}
+
+impl Default for ZoneEntityId {
+    fn default() -> Self {
+        Self::new_random()
+    }
+}
+
+impl std::fmt::Display for ZoneEntityId {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+// hash map order is random, zone files should not change when nothing changed
+pub fn serialize_custom_props_sorted<S>(custom_props: &Option<CustomPropsMap>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    custom_props
+        .as_ref()
+        .map(|props| props.iter().collect::<BTreeMap<_, _>>())
+        .serialize(serializer)
 }
 
 
@@ -119,7 +265,8 @@ impl CustomPropsComponent {
 
         for (key,val) in new_props.iter(){
 
-            if self.props.get( key ).is_none(){
+            // an EmptyProp is a key that was added in the inspector but never given a value
+            if self.props.get( key ).is_none_or(|existing| *existing == CustomProp::EmptyProp){
                   self.props.insert(key.to_string(), val.clone());
             }
           
@@ -163,7 +310,7 @@ impl CustomPropsComponent {
 
 pub type CustomPropsMap = HashMap<String, CustomProp>;
 
-#[derive(Serialize, Deserialize, Clone, Debug, Reflect, Default)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Reflect, Default)]
 #[reflect(Serialize, Deserialize,Default)]  //need to reflect default or else cant use + in editor 
 pub enum CustomProp {
     Vec3(Vec3),
@@ -173,7 +320,14 @@ pub enum CustomProp {
     Integer(i32), 
     Boolean(bool),
     #[default]
-    EmptyProp  
+    EmptyProp,
+
+    Vec2(Vec2),
+    Color(Color),
+    Enum(String), //one of the choices in the doodads prop schema
+    EntityRef(Option<ZoneEntityId>), //another entity in a zone, by its id
+    List(Vec<CustomProp>),
+    AssetPath(String), //relative to assets
 }
 
 
@@ -186,6 +340,18 @@ impl std::fmt::Display for CustomProp {
             CustomProp::Integer(num) => write!(f, "{}", num),
             CustomProp::Boolean(b) => write!(f, "{}", b),
             CustomProp::EmptyProp => write!(f, ""),
+            CustomProp::Vec2(vec) => write!(f, "{}", vec),
+            CustomProp::Color(color) => write!(f, "{}", color.to_srgba().to_hex()),
+            CustomProp::Enum(choice) => write!(f, "{}", choice),
+            CustomProp::EntityRef(entity_id) => match entity_id {
+                Some(entity_id) => write!(f, "{}", entity_id),
+                None => write!(f, ""),
+            },
+            CustomProp::List(items) => {
+                let items: Vec<String> = items.iter().map(|item| item.to_string()).collect();
+                write!(f, "[{}]", items.join(", "))
+            }
+            CustomProp::AssetPath(path) => write!(f, "{}", path),
         }
     }
 }
@@ -195,20 +361,27 @@ impl std::fmt::Display for CustomProp {
 
 //pub struct StringSpecial; 
 
-#[derive(Serialize, Deserialize,Component,Clone,Debug)]
+// files written before entity ids existed get fresh ids when they are loaded
+#[derive(Serialize, Deserialize,Component,Clone,Debug,PartialEq)]
  pub enum ZoneEntityV2 {
     Doodad {
 
+          #[serde(default)]
+          id: ZoneEntityId,
 
           name: String,
 
           transform: TransformSimpleRads,
 
+          #[serde(serialize_with = "serialize_custom_props_sorted")]
           custom_props: Option<CustomPropsMap>,
 
     },
     ClayTile  {
 
+          #[serde(default)]
+          id: ZoneEntityId,
+
           //name: String,
 
           transform: TransformSimpleRads,
@@ -217,11 +390,29 @@ impl std::fmt::Display for CustomProp {
     } ,
     Prefab{
 
+          #[serde(default)]
+          id: ZoneEntityId,
+
+          name: String,
+
+          transform: TransformSimpleRads,
+
+          // differences from the prefab file, see prefab_overrides
+          #[serde(default, skip_serializing_if = "Option::is_none")]
+          overrides: Option<PrefabOverrides>,
+
+    },
+    Spline {
+
+          #[serde(default)]
+          id: ZoneEntityId,
 
           name: String,
 
           transform: TransformSimpleRads,
 
+          spline: TerrainSpline,
+
     }
 
  }
@@ -234,6 +425,7 @@ impl ZoneEntityV2{
         if let Some(clay_tile_block_data) = zone_entity.clay_tile_block_data {
 
             return Self::ClayTile { 
+                id: ZoneEntityId::new_random(),
                 transform : zone_entity.transform.clone(),
                  clay_tile_block: clay_tile_block_data.clone() 
              }
@@ -241,6 +433,7 @@ impl ZoneEntityV2{
 
 
             return Self::Doodad { 
+                id: ZoneEntityId::new_random(),
                 name: zone_entity.name.clone(), 
                 transform:  zone_entity.transform.clone(),
                custom_props: zone_entity.custom_props
@@ -252,11 +445,39 @@ impl ZoneEntityV2{
 
 
 
+    pub fn get_id(&self) -> ZoneEntityId {
+        match self {
+            Self::Doodad  { id, .. } => *id,
+            Self::ClayTile  { id, .. } => *id,
+            Self::Prefab  { id, .. } => *id,
+            Self::Spline  { id, .. } => *id,
+        }
+    }
+
+    pub fn set_id(&mut self, new_id: ZoneEntityId) {
+        match self {
+            Self::Doodad  { id, .. } => *id = new_id,
+            Self::ClayTile  { id, .. } => *id = new_id,
+            Self::Prefab  { id, .. } => *id = new_id,
+            Self::Spline  { id, .. } => *id = new_id,
+        }
+    }
+
     pub fn get_transform_simple(&self) -> &TransformSimpleRads {
         match self {
             Self::Doodad  { transform, .. } => transform,
             Self::ClayTile  { transform, .. } => transform,
             Self::Prefab  { transform, .. } => transform,
+            Self::Spline  { transform, .. } => transform,
+        }
+    }
+
+    pub fn get_transform_simple_mut(&mut self) -> &mut TransformSimpleRads {
+        match self {
+            Self::Doodad  { transform, .. } => transform,
+            Self::ClayTile  { transform, .. } => transform,
+            Self::Prefab  { transform, .. } => transform,
+            Self::Spline  { transform, .. } => transform,
         }
     }
 
@@ -292,10 +513,26 @@ impl ZoneEntityV2{
         let clay_tile_block_data = entity_ref.get::<ClayTileBlock>() ;
         let prefab_component = entity_ref.get::<PrefabComponent>();
 
+        //zone children get their id from assign_zone_entity_ids, anything else gets a new one
+        let id = entity_ref.get::<ZoneEntityId>().copied().unwrap_or_default();
+
+        if let Some( spline ) = entity_ref.get::<TerrainSpline>() {
+
+            return Some(
+                ZoneEntityV2::Spline {
+                 id,
+                 name: name_comp.as_str().to_string(),
+                 transform: xform.clone().into(),
+                 spline: spline.clone()
+             }
+            )
+
+        }
+
         if let Some( clay_tile_block_data ) = clay_tile_block_data {
 
             return Some(  
-                ZoneEntityV2::ClayTile { transform: xform.clone().into(), clay_tile_block: clay_tile_block_data.clone() }
+                ZoneEntityV2::ClayTile { id, transform: xform.clone().into(), clay_tile_block: clay_tile_block_data.clone() }
             )
 
         }
@@ -306,8 +543,10 @@ impl ZoneEntityV2{
 
             return Some(  
                 ZoneEntityV2::Prefab { 
+                 id,
                  name: name_comp.as_str().to_string(), 
-                 transform: xform.clone().into()
+                 transform: xform.clone().into(),
+                 overrides: None, //needs the children, see prefab_overrides::zone_entity_with_prefab_overrides
              }
             )
 
@@ -320,6 +559,7 @@ impl ZoneEntityV2{
 
              return Some(  
                 ZoneEntityV2::Doodad { 
+                 id,
                  name: name_comp.as_str().to_string(),
                 transform: xform.clone().into(),
                 custom_props,
@@ -426,7 +666,7 @@ impl ZoneEntity {
 
 */
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct TransformSimpleRads {
     pub translation: Vec3,
     pub rotation: Vec3, //euler  SHOULD ALWAYS BE IN RADS -- ONLY USED IN EDITOR ZONES FILES 
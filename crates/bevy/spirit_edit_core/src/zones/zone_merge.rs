@@ -0,0 +1,537 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+
+use super::zone_file::{CustomProp, CustomPropsMap, ZoneEntityId, ZoneEntityV2, ZoneFileV2};
+use crate::selection::SelectionGroup;
+
+/*
+
+Three way merge of zone files.
+
+Entities are matched by their ZoneEntityId and merged field by field (name, transform,
+custom props ...), so two people moving different doodads, or changing different fields of
+the same doodad, merge cleanly. When both sides changed the same field differently our
+side is kept and the field is reported as a conflict. Selection groups are matched by name
+and merged the same way, as a whole.
+
+Used by the zone-merge binary, which can be set up as a git merge driver.
+
+*/
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ZoneMergeConflict {
+    // None for fields of the zone file itself
+    pub entity_id: Option<ZoneEntityId>,
+    pub field: String,
+
+    pub ours: String,
+    pub theirs: String,
+}
+
+impl std::fmt::Display for ZoneMergeConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.entity_id {
+            Some(entity_id) => write!(f, "entity {} field {}", entity_id, self.field)?,
+            None => write!(f, "field {}", self.field)?,
+        }
+
+        write!(f, ": ours {} theirs {}", self.ours, self.theirs)
+    }
+}
+
+pub struct ZoneMergeResult {
+    pub merged: ZoneFileV2,
+    pub conflicts: Vec<ZoneMergeConflict>,
+}
+
+const DELETED: &str = "<deleted>";
+
+// an entity as its variant and the RON text of every field except the id
+#[derive(Debug, Clone, PartialEq)]
+struct EntityFields {
+    variant: &'static str,
+    fields: Vec<(&'static str, String)>,
+}
+
+// only the entity ids of a zone file, without a serde default on them so a missing id fails
+#[derive(Deserialize)]
+#[serde(rename = "ZoneFileV2")]
+struct ZoneFileIdProbe {
+    #[allow(dead_code)]
+    entities: Vec<ZoneEntityIdProbe>,
+}
+
+#[allow(dead_code)]
+#[derive(Deserialize)]
+#[serde(rename = "ZoneEntityV2")]
+enum ZoneEntityIdProbe {
+    Doodad { id: ZoneEntityId },
+    ClayTile { id: ZoneEntityId },
+    Prefab { id: ZoneEntityId },
+    Spline { id: ZoneEntityId },
+}
+
+/// Whether every entity in the zone file text has an id. Files written before entities had
+/// ids get random ones when they are loaded, so nothing would match between merge sides.
+pub fn zone_file_has_entity_ids(file_content: &str) -> bool {
+    ron::from_str::<ZoneFileIdProbe>(file_content).is_ok()
+}
+
+fn ron_text<T: Serialize>(value: &T) -> Result<String, ron::Error> {
+    ron::to_string(value)
+}
+
+// same order as serialize_custom_props_sorted so equal maps compare equal
+fn sorted_custom_props(custom_props: &Option<CustomPropsMap>) -> Option<BTreeMap<&String, &CustomProp>> {
+    custom_props.as_ref().map(|props| props.iter().collect())
+}
+
+impl EntityFields {
+    fn from_entity(entity: &ZoneEntityV2) -> Result<Self, ron::Error> {
+        let (variant, fields) = match entity {
+            ZoneEntityV2::Doodad { name, transform, custom_props, .. } => (
+                "Doodad",
+                vec![
+                    ("name", ron_text(name)?),
+                    ("transform", ron_text(transform)?),
+                    ("custom_props", ron_text(&sorted_custom_props(custom_props))?),
+                ],
+            ),
+            ZoneEntityV2::ClayTile { transform, clay_tile_block, .. } => (
+                "ClayTile",
+                vec![
+                    ("transform", ron_text(transform)?),
+                    ("clay_tile_block", ron_text(clay_tile_block)?),
+                ],
+            ),
+            ZoneEntityV2::Prefab { name, transform, overrides, .. } => (
+                "Prefab",
+                vec![
+                    ("name", ron_text(name)?),
+                    ("transform", ron_text(transform)?),
+                    ("overrides", ron_text(overrides)?),
+                ],
+            ),
+            ZoneEntityV2::Spline { name, transform, spline, .. } => (
+                "Spline",
+                vec![
+                    ("name", ron_text(name)?),
+                    ("transform", ron_text(transform)?),
+                    ("spline", ron_text(spline)?),
+                ],
+            ),
+        };
+
+        Ok(Self { variant, fields })
+    }
+
+    fn field(&self, name: &str) -> Option<&String> {
+        self.fields
+            .iter()
+            .find(|(field_name, _)| *field_name == name)
+            .map(|(_, value)| value)
+    }
+
+    fn to_entity(&self, id: ZoneEntityId) -> Result<ZoneEntityV2, ron::Error> {
+        let mut text = format!("{}(id:{}", self.variant, ron_text(&id)?);
+
+        for (name, value) in self.fields.iter() {
+            text.push_str(&format!(",{}:{}", name, value));
+        }
+
+        text.push(')');
+
+        ron::from_str(&text).map_err(|e| e.code)
+    }
+}
+
+// Ok with the merged value (None means absent), Err when both sides changed it differently
+fn three_way<'a, T: PartialEq>(
+    base: Option<&'a T>,
+    ours: Option<&'a T>,
+    theirs: Option<&'a T>,
+) -> Result<Option<&'a T>, ()> {
+    if ours == theirs || theirs == base {
+        Ok(ours)
+    } else if ours == base {
+        Ok(theirs)
+    } else {
+        Err(())
+    }
+}
+
+fn entity_fields_by_id(zone_file: &ZoneFileV2) -> Result<BTreeMap<ZoneEntityId, EntityFields>, ron::Error> {
+    zone_file
+        .entities
+        .iter()
+        .map(|entity| Ok((entity.get_id(), EntityFields::from_entity(entity)?)))
+        .collect()
+}
+
+pub fn merge_zone_files(
+    base: &ZoneFileV2,
+    ours: &ZoneFileV2,
+    theirs: &ZoneFileV2,
+) -> Result<ZoneMergeResult, ron::Error> {
+    let mut conflicts = Vec::new();
+
+    let translation_offset = match three_way(
+        Some(&base.translation_offset),
+        Some(&ours.translation_offset),
+        Some(&theirs.translation_offset),
+    ) {
+        Ok(merged) => merged.copied().flatten(),
+        Err(()) => {
+            conflicts.push(ZoneMergeConflict {
+                entity_id: None,
+                field: "translation_offset".into(),
+                ours: ron_text(&ours.translation_offset)?,
+                theirs: ron_text(&theirs.translation_offset)?,
+            });
+            ours.translation_offset
+        }
+    };
+
+    let base_entities = entity_fields_by_id(base)?;
+    let our_entities = entity_fields_by_id(ours)?;
+    let their_entities = entity_fields_by_id(theirs)?;
+
+    let all_ids: BTreeSet<ZoneEntityId> = base_entities
+        .keys()
+        .chain(our_entities.keys())
+        .chain(their_entities.keys())
+        .copied()
+        .collect();
+
+    let mut entities = Vec::new();
+
+    for id in all_ids {
+        let base_entity = base_entities.get(&id);
+        let our_entity = our_entities.get(&id);
+        let their_entity = their_entities.get(&id);
+
+        if let Ok(merged) = three_way(base_entity, our_entity, their_entity) {
+            if let Some(merged) = merged {
+                entities.push(merged.to_entity(id)?);
+            }
+            continue;
+        }
+
+        let (our_entity, their_entity) = match (our_entity, their_entity) {
+            (Some(our_entity), Some(their_entity)) => (our_entity, their_entity),
+
+            // deleted on one side and changed on the other, keep the changed one
+            (Some(kept), None) | (None, Some(kept)) => {
+                conflicts.push(ZoneMergeConflict {
+                    entity_id: Some(id),
+                    field: "entity".into(),
+                    ours: if our_entity.is_some() { "<changed>".into() } else { DELETED.into() },
+                    theirs: if their_entity.is_some() { "<changed>".into() } else { DELETED.into() },
+                });
+
+                entities.push(kept.to_entity(id)?);
+                continue;
+            }
+
+            (None, None) => continue,
+        };
+
+        if our_entity.variant != their_entity.variant {
+            conflicts.push(ZoneMergeConflict {
+                entity_id: Some(id),
+                field: "type".into(),
+                ours: our_entity.variant.into(),
+                theirs: their_entity.variant.into(),
+            });
+
+            entities.push(our_entity.to_entity(id)?);
+            continue;
+        }
+
+        // a base of another type (or none, when both sides added the id) counts as empty
+        let base_entity = base_entity.filter(|base_entity| base_entity.variant == our_entity.variant);
+
+        let mut merged = our_entity.clone();
+
+        for (name, value) in merged.fields.iter_mut() {
+            let base_value = base_entity.and_then(|base_entity| base_entity.field(name));
+            let their_value = their_entity.field(name);
+            let our_value = value.clone();
+
+            match three_way(base_value, Some(&our_value), their_value) {
+                Ok(merged_value) => {
+                    if let Some(merged_value) = merged_value {
+                        *value = merged_value.clone();
+                    }
+                }
+                Err(()) => conflicts.push(ZoneMergeConflict {
+                    entity_id: Some(id),
+                    field: name.to_string(),
+                    ours: our_value,
+                    theirs: their_value.cloned().unwrap_or_default(),
+                }),
+            }
+        }
+
+        entities.push(merged.to_entity(id)?);
+    }
+
+    let selection_groups = merge_selection_groups(base, ours, theirs, &mut conflicts)?;
+
+    Ok(ZoneMergeResult {
+        merged: ZoneFileV2 {
+            translation_offset,
+            // recomputed when the merged file is written
+            bounds: None,
+            entities,
+            selection_groups,
+        },
+        conflicts,
+    })
+}
+
+fn selection_group_members(zone_file: &ZoneFileV2) -> BTreeMap<&str, BTreeSet<ZoneEntityId>> {
+    zone_file
+        .selection_groups
+        .iter()
+        .map(|group| (group.name.as_str(), group.entity_ids.iter().copied().collect()))
+        .collect()
+}
+
+// groups are matched by name and merged as a whole, our groups keep their order
+fn merge_selection_groups(
+    base: &ZoneFileV2,
+    ours: &ZoneFileV2,
+    theirs: &ZoneFileV2,
+    conflicts: &mut Vec<ZoneMergeConflict>,
+) -> Result<Vec<SelectionGroup>, ron::Error> {
+    let base_groups = selection_group_members(base);
+    let our_groups = selection_group_members(ours);
+    let their_groups = selection_group_members(theirs);
+
+    let mut names: Vec<&str> = ours.selection_groups.iter().map(|group| group.name.as_str()).collect();
+    for group in theirs.selection_groups.iter() {
+        if !names.contains(&group.name.as_str()) {
+            names.push(group.name.as_str());
+        }
+    }
+
+    let mut selection_groups = Vec::new();
+
+    for name in names {
+        let base_members = base_groups.get(name);
+        let our_members = our_groups.get(name);
+        let their_members = their_groups.get(name);
+
+        let members = match three_way(base_members, our_members, their_members) {
+            Ok(members) => members,
+            Err(()) => {
+                conflicts.push(ZoneMergeConflict {
+                    entity_id: None,
+                    field: format!("selection_groups.{}", name),
+                    ours: match our_members {
+                        Some(members) => ron_text(members)?,
+                        None => DELETED.into(),
+                    },
+                    theirs: match their_members {
+                        Some(members) => ron_text(members)?,
+                        None => DELETED.into(),
+                    },
+                });
+
+                // deleted on one side and changed on the other, keep the changed one
+                our_members.or(their_members)
+            }
+        };
+
+        if let Some(members) = members {
+            selection_groups.push(SelectionGroup {
+                name: name.to_string(),
+                entity_ids: members.iter().copied().collect(),
+            });
+        }
+    }
+
+    Ok(selection_groups)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use bevy::prelude::Vec3;
+
+    use crate::zones::zone_file::TransformSimpleRads;
+
+    fn doodad(id: ZoneEntityId, name: &str, x: f32) -> ZoneEntityV2 {
+        ZoneEntityV2::Doodad {
+            id,
+            name: name.to_string(),
+            transform: TransformSimpleRads {
+                translation: Vec3::new(x, 0.0, 0.0),
+                ..Default::default()
+            },
+            custom_props: None,
+        }
+    }
+
+    fn zone_file(entities: Vec<ZoneEntityV2>, selection_groups: Vec<SelectionGroup>) -> ZoneFileV2 {
+        ZoneFileV2 {
+            translation_offset: None,
+            bounds: None,
+            entities,
+            selection_groups,
+        }
+    }
+
+    // members are sorted, like the merge and to_pretty_ron write them
+    fn group(name: &str, entity_ids: &[ZoneEntityId]) -> SelectionGroup {
+        let mut entity_ids = entity_ids.to_vec();
+        entity_ids.sort();
+
+        SelectionGroup {
+            name: name.to_string(),
+            entity_ids,
+        }
+    }
+
+    #[test]
+    fn test_zone_file_has_entity_ids() {
+        let with_ids = zone_file(vec![doodad(ZoneEntityId::new_random(), "tree", 0.0)], vec![]);
+        assert!(zone_file_has_entity_ids(&with_ids.to_pretty_ron().unwrap()));
+
+        let legacy = r#"(
+            translation_offset: None,
+            entities: [
+                Doodad(
+                    name: "tree",
+                    transform: (translation: (0.0, 0.0, 0.0), rotation: (0.0, 0.0, 0.0), scale: (1.0, 1.0, 1.0)),
+                    custom_props: None,
+                ),
+            ],
+        )"#;
+        assert!(!zone_file_has_entity_ids(legacy));
+
+        // the legacy file still loads, with random ids
+        assert!(ron::from_str::<ZoneFileV2>(legacy).is_ok());
+    }
+
+    #[test]
+    fn test_three_way() {
+        assert_eq!(three_way(Some(&1), Some(&1), Some(&1)), Ok(Some(&1)));
+        assert_eq!(three_way(Some(&1), Some(&2), Some(&1)), Ok(Some(&2)));
+        assert_eq!(three_way(Some(&1), Some(&1), Some(&3)), Ok(Some(&3)));
+        assert_eq!(three_way(Some(&1), Some(&2), Some(&2)), Ok(Some(&2)));
+        assert_eq!(three_way(Some(&1), None, Some(&1)), Ok(None));
+        assert_eq!(three_way(Some(&1), Some(&2), Some(&3)), Err(()));
+        assert_eq!(three_way(Some(&1), None, Some(&3)), Err(()));
+    }
+
+    #[test]
+    fn test_disjoint_field_edits_merge() {
+        let id = ZoneEntityId::new_random();
+
+        let base = zone_file(vec![doodad(id, "tree", 0.0)], vec![]);
+        let ours = zone_file(vec![doodad(id, "tree", 5.0)], vec![]);
+        let theirs = zone_file(vec![doodad(id, "bush", 0.0)], vec![]);
+
+        let result = merge_zone_files(&base, &ours, &theirs).unwrap();
+
+        assert!(result.conflicts.is_empty());
+        assert_eq!(result.merged.entities, vec![doodad(id, "bush", 5.0)]);
+    }
+
+    #[test]
+    fn test_same_field_conflict_keeps_ours() {
+        let id = ZoneEntityId::new_random();
+
+        let base = zone_file(vec![doodad(id, "tree", 0.0)], vec![]);
+        let ours = zone_file(vec![doodad(id, "tree", 5.0)], vec![]);
+        let theirs = zone_file(vec![doodad(id, "tree", 7.0)], vec![]);
+
+        let result = merge_zone_files(&base, &ours, &theirs).unwrap();
+
+        assert_eq!(result.conflicts.len(), 1);
+        assert_eq!(result.conflicts[0].entity_id, Some(id));
+        assert_eq!(result.conflicts[0].field, "transform");
+        assert_eq!(result.merged.entities, vec![doodad(id, "tree", 5.0)]);
+    }
+
+    #[test]
+    fn test_delete_vs_modify_keeps_modified() {
+        let id = ZoneEntityId::new_random();
+        let other_id = ZoneEntityId::new_random();
+
+        let base = zone_file(vec![doodad(id, "tree", 0.0), doodad(other_id, "rock", 0.0)], vec![]);
+        let ours = zone_file(vec![doodad(other_id, "rock", 0.0)], vec![]);
+        let theirs = zone_file(vec![doodad(id, "tree", 3.0), doodad(other_id, "rock", 0.0)], vec![]);
+
+        let result = merge_zone_files(&base, &ours, &theirs).unwrap();
+
+        assert_eq!(result.conflicts.len(), 1);
+        assert_eq!(result.conflicts[0].field, "entity");
+        assert_eq!(result.conflicts[0].ours, DELETED);
+        assert!(result.merged.entities.contains(&doodad(id, "tree", 3.0)));
+        assert_eq!(result.merged.entities.len(), 2);
+
+        // deleted on one side and untouched on the other is a clean delete
+        let theirs = zone_file(vec![doodad(id, "tree", 0.0), doodad(other_id, "rock", 0.0)], vec![]);
+
+        let result = merge_zone_files(&base, &ours, &theirs).unwrap();
+
+        assert!(result.conflicts.is_empty());
+        assert_eq!(result.merged.entities, vec![doodad(other_id, "rock", 0.0)]);
+    }
+
+    #[test]
+    fn test_both_add_same_id() {
+        let id = ZoneEntityId::new_random();
+
+        let base = zone_file(vec![], vec![]);
+
+        let identical = zone_file(vec![doodad(id, "tree", 1.0)], vec![]);
+        let result = merge_zone_files(&base, &identical, &identical).unwrap();
+
+        assert!(result.conflicts.is_empty());
+        assert_eq!(result.merged.entities, vec![doodad(id, "tree", 1.0)]);
+
+        // without a base every differing field conflicts, the equal ones merge
+        let ours = zone_file(vec![doodad(id, "tree", 1.0)], vec![]);
+        let theirs = zone_file(vec![doodad(id, "tree", 2.0)], vec![]);
+
+        let result = merge_zone_files(&base, &ours, &theirs).unwrap();
+
+        assert_eq!(result.conflicts.len(), 1);
+        assert_eq!(result.conflicts[0].field, "transform");
+        assert_eq!(result.merged.entities, vec![doodad(id, "tree", 1.0)]);
+    }
+
+    #[test]
+    fn test_selection_group_merges() {
+        let a = ZoneEntityId::new_random();
+        let b = ZoneEntityId::new_random();
+        let c = ZoneEntityId::new_random();
+
+        let base = zone_file(vec![], vec![group("shared", &[a])]);
+        let ours = zone_file(vec![], vec![group("shared", &[a, b]), group("ours", &[a])]);
+        let theirs = zone_file(vec![], vec![group("shared", &[a]), group("theirs", &[c])]);
+
+        let result = merge_zone_files(&base, &ours, &theirs).unwrap();
+
+        assert!(result.conflicts.is_empty());
+        assert_eq!(
+            result.merged.selection_groups,
+            vec![group("shared", &[a, b]), group("ours", &[a]), group("theirs", &[c])]
+        );
+
+        // both sides changed the same group differently
+        let theirs = zone_file(vec![], vec![group("shared", &[a, c])]);
+
+        let result = merge_zone_files(&base, &ours, &theirs).unwrap();
+
+        assert_eq!(result.conflicts.len(), 1);
+        assert_eq!(result.conflicts[0].field, "selection_groups.shared");
+        assert_eq!(result.merged.selection_groups[0], group("shared", &[a, b]));
+    }
+}
@@ -0,0 +1,511 @@
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::tasks::{IoTaskPool, Task};
+use bevy::time::common_conditions::on_timer;
+use futures_lite::future;
+use serde::{Deserialize, Serialize};
+
+use crate::history::EditorHistoryResource;
+use crate::selection::SelectionGroupsComponent;
+
+use super::zone_file::{CustomPropsComponent, ZoneBounds, ZoneEntityId, ZoneEntityV2, ZoneFileV2};
+use super::{zone_file_path, SaveZoneToFileEvent, ZoneComponent, ZoneEntitySpawners};
+
+/*
+
+Distance based zone streaming, for the editor and for games at runtime.
+
+Instead of loading every zone up front, send ZoneStreamingEvent::StreamZones with the zone
+names.  Their files are read in the background for their bounds (written into the zone file
+on every save), and each zone is loaded once a `ZoneViewer` comes within `load_distance` of
+its bounds and unloaded again when every viewer is further than `unload_distance`.  The gap
+between the two keeps a zone from loading and unloading over and over at its edge.
+
+Loaded zones spawn `entities_per_frame` of their entities per frame so large zones do not
+cause a hitch.  Until every entity they sent a spawn event for exists (found by its
+ZoneEntityId) they have a `StreamingInZone` component and are not saved.
+
+In the editor (`track_edits`), zones with edits are kept loaded until they are saved, so no
+work is lost.  Games never edit their zones and skip the tracking.
+
+*/
+
+pub fn zone_streaming_plugin(app: &mut App) {
+    let update_rate = Duration::from_millis(250);
+
+    app.init_resource::<ZoneStreamingResource>()
+        .add_message::<ZoneStreamingEvent>()
+        .add_systems(
+            Update,
+            (
+                handle_zone_streaming_events,
+                update_streamed_zones.run_if(on_timer(update_rate)),
+                finish_zone_file_read_tasks,
+                spawn_streamed_zone_entities,
+                (track_streamed_zone_edits, clear_saved_zone_edits).run_if(zone_edits_tracked),
+            )
+                .chain(),
+        )
+        .add_systems(Update, draw_streamed_zone_bounds);
+}
+
+/// Zones are streamed in around entities with this component, usually the camera or the player.
+#[derive(Component, Default)]
+pub struct ZoneViewer;
+
+#[derive(Message)]
+pub enum ZoneStreamingEvent {
+    StreamZones(Vec<String>),
+
+    // zones that are loaded stay in the world as regular zones
+    StopStreaming,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ZoneStreamingConfig {
+    pub load_distance: f32,
+
+    // should be larger than load_distance
+    pub unload_distance: f32,
+
+    // across every zone that is streaming in
+    pub entities_per_frame: usize,
+}
+
+impl Default for ZoneStreamingConfig {
+    fn default() -> Self {
+        Self {
+            load_distance: 300.0,
+            unload_distance: 400.0,
+            entities_per_frame: 50,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StreamedZoneState {
+    Unloaded,
+    Loading,
+    Loaded,
+
+    // the zone file could not be read
+    Failed,
+}
+
+pub struct StreamedZone {
+    // None until the file has been read, and for zones without entities
+    pub bounds: Option<ZoneBounds>,
+    pub state: StreamedZoneState,
+    pub zone_entity: Option<Entity>,
+
+    // ignores the viewers, set from the editor
+    pub keep_loaded: bool,
+
+    bounds_task: Option<Task<Option<ZoneFileV2>>>,
+}
+
+impl StreamedZone {
+    pub fn distance_to(&self, viewer_locations: &[Vec2]) -> Option<f32> {
+        let bounds = self.bounds?;
+
+        viewer_locations
+            .iter()
+            .map(|viewer_location| bounds.distance_xz(*viewer_location))
+            .reduce(f32::min)
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct ZoneStreamingResource {
+    pub config: ZoneStreamingConfig,
+    pub zones: BTreeMap<String, StreamedZone>,
+
+    // draws the bounds of every streamed zone, colored by state
+    pub show_bounds: bool,
+
+    // keeps edited zones loaded until they are saved, set by the editor
+    pub track_edits: bool,
+}
+
+#[derive(Component)]
+pub struct StreamedZoneComponent {
+    pub zone_name: String,
+}
+
+/// On a streamed zone until all of its entities have been spawned.
+#[derive(Component)]
+pub struct StreamingInZone {
+    file_task: Option<Task<Option<ZoneFileV2>>>,
+    queue: VecDeque<ZoneEntityV2>,
+
+    // sent to the spawn handlers, but not spawned yet
+    pending_spawns: HashSet<ZoneEntityId>,
+}
+
+/// A streamed zone that was edited after it loaded, it is not unloaded before it is saved.
+#[derive(Component)]
+pub struct ZoneUnsavedEdits;
+
+fn read_zone_file_task(zone_name: &str) -> Task<Option<ZoneFileV2>> {
+    let path = zone_file_path(zone_name);
+
+    IoTaskPool::get().spawn(async move { ZoneFileV2::load_from_path(&path) })
+}
+
+fn handle_zone_streaming_events(
+    mut commands: Commands,
+    mut evt_reader: MessageReader<ZoneStreamingEvent>,
+
+    streaming_in_query: Query<(), With<StreamingInZone>>,
+
+    mut zone_streaming: ResMut<ZoneStreamingResource>,
+) {
+    for evt in evt_reader.read() {
+        match evt {
+            ZoneStreamingEvent::StreamZones(zone_names) => {
+                for zone_name in zone_names {
+                    if zone_streaming.zones.contains_key(zone_name) {
+                        continue;
+                    }
+
+                    zone_streaming.zones.insert(
+                        zone_name.clone(),
+                        StreamedZone {
+                            bounds: None,
+                            state: StreamedZoneState::Unloaded,
+                            zone_entity: None,
+                            keep_loaded: false,
+                            bounds_task: Some(read_zone_file_task(zone_name)),
+                        },
+                    );
+                }
+            }
+
+            ZoneStreamingEvent::StopStreaming => {
+                for streamed_zone in std::mem::take(&mut zone_streaming.zones).into_values() {
+                    let Some(zone_entity) = streamed_zone.zone_entity else {
+                        continue;
+                    };
+
+                    // a half spawned zone would be saved without its missing entities
+                    if streaming_in_query.contains(zone_entity) {
+                        commands.entity(zone_entity).despawn();
+                    } else {
+                        commands
+                            .entity(zone_entity)
+                            .remove::<(StreamedZoneComponent, ZoneUnsavedEdits)>();
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn update_streamed_zones(
+    mut commands: Commands,
+
+    zone_viewer_query: Query<&GlobalTransform, With<ZoneViewer>>,
+    zone_query: Query<Has<ZoneUnsavedEdits>, With<StreamedZoneComponent>>,
+    children_query: Query<&Children>,
+
+    mut zone_streaming: ResMut<ZoneStreamingResource>,
+    mut history: Option<ResMut<EditorHistoryResource>>,
+) {
+    let viewer_locations: Vec<Vec2> = zone_viewer_query
+        .iter()
+        .map(|xform| xform.translation().xz())
+        .collect();
+
+    //nothing streams in or out until there is a viewer
+    if viewer_locations.is_empty() {
+        return;
+    }
+
+    let zone_streaming = zone_streaming.as_mut();
+    let config = &zone_streaming.config;
+
+    for (zone_name, streamed_zone) in zone_streaming.zones.iter_mut() {
+        // deleted in the hierarchy, it streams back in from its file
+        if let Some(zone_entity) = streamed_zone.zone_entity {
+            if !zone_query.contains(zone_entity) {
+                streamed_zone.zone_entity = None;
+                streamed_zone.state = StreamedZoneState::Unloaded;
+            }
+        }
+
+        let distance_to_viewer = streamed_zone.distance_to(&viewer_locations).unwrap_or(f32::MAX);
+
+        let has_unsaved_edits = streamed_zone
+            .zone_entity
+            .is_some_and(|zone_entity| zone_query.get(zone_entity).unwrap_or(false));
+
+        let should_load = streamed_zone.keep_loaded || distance_to_viewer <= config.load_distance;
+
+        // between load_distance and unload_distance a zone keeps whatever state it has
+        let should_keep = should_load || has_unsaved_edits || distance_to_viewer <= config.unload_distance;
+
+        match (streamed_zone.state, streamed_zone.zone_entity) {
+            (StreamedZoneState::Unloaded, None) if should_load => {
+                info!("streaming in zone {}", zone_name);
+
+                let zone_entity = commands
+                    .spawn((Transform::default(), Visibility::default()))
+                    .insert(ZoneComponent {})
+                    .insert(Name::new(zone_name.clone()))
+                    .insert(StreamedZoneComponent {
+                        zone_name: zone_name.clone(),
+                    })
+                    .insert(StreamingInZone {
+                        file_task: Some(read_zone_file_task(zone_name)),
+                        queue: VecDeque::new(),
+                        pending_spawns: HashSet::new(),
+                    })
+                    .id();
+
+                streamed_zone.zone_entity = Some(zone_entity);
+                streamed_zone.state = StreamedZoneState::Loading;
+            }
+
+            (StreamedZoneState::Loading | StreamedZoneState::Loaded, Some(zone_entity)) if !should_keep => {
+                info!("streaming out zone {}", zone_name);
+
+                // leaving the world this way is not a delete the user can undo
+                if let Some(history) = history.as_mut() {
+                    history.forget_entities(
+                        std::iter::once(zone_entity).chain(children_query.iter_descendants(zone_entity)),
+                    );
+                }
+
+                commands.entity(zone_entity).despawn();
+
+                streamed_zone.zone_entity = None;
+                streamed_zone.state = StreamedZoneState::Unloaded;
+            }
+
+            _ => {}
+        }
+    }
+}
+
+fn finish_zone_file_read_tasks(
+    mut commands: Commands,
+
+    mut zone_query: Query<(Entity, &StreamedZoneComponent, &mut StreamingInZone)>,
+
+    mut zone_streaming: ResMut<ZoneStreamingResource>,
+) {
+    for (zone_name, streamed_zone) in zone_streaming.zones.iter_mut() {
+        let Some(task) = &mut streamed_zone.bounds_task else {
+            continue;
+        };
+
+        let Some(zone_file) = future::block_on(future::poll_once(task)) else {
+            continue;
+        };
+
+        streamed_zone.bounds_task = None;
+
+        match zone_file {
+            Some(zone_file) => streamed_zone.bounds = zone_file.bounds.or_else(|| zone_file.compute_bounds()),
+            None => {
+                warn!("could not read zone {}, it will not be streamed", zone_name);
+                streamed_zone.state = StreamedZoneState::Failed;
+            }
+        }
+    }
+
+    for (zone_entity, streamed_zone_comp, mut streaming_in) in zone_query.iter_mut() {
+        let Some(task) = &mut streaming_in.file_task else {
+            continue;
+        };
+
+        let Some(zone_file) = future::block_on(future::poll_once(task)) else {
+            continue;
+        };
+
+        streaming_in.file_task = None;
+
+        let streamed_zone = zone_streaming.zones.get_mut(&streamed_zone_comp.zone_name);
+
+        let Some(zone_file) = zone_file else {
+            warn!("could not read zone {}, it will not be streamed", streamed_zone_comp.zone_name);
+
+            commands.entity(zone_entity).despawn();
+
+            if let Some(streamed_zone) = streamed_zone {
+                streamed_zone.zone_entity = None;
+                streamed_zone.state = StreamedZoneState::Failed;
+            }
+
+            continue;
+        };
+
+        // the file may have been saved since its bounds were read
+        if let Some(streamed_zone) = streamed_zone {
+            streamed_zone.bounds = zone_file.bounds.or_else(|| zone_file.compute_bounds());
+        }
+
+        commands.entity(zone_entity).insert(SelectionGroupsComponent {
+            groups: zone_file.selection_groups,
+        });
+
+        streaming_in.queue = zone_file.entities.into();
+    }
+}
+
+fn spawn_streamed_zone_entities(
+    mut commands: Commands,
+
+    mut zone_query: Query<(Entity, &StreamedZoneComponent, &mut StreamingInZone)>,
+
+    // every spawn handler inserts the id it was given
+    spawned_query: Query<&ZoneEntityId, Added<ZoneEntityId>>,
+
+    mut zone_entity_spawners: ZoneEntitySpawners,
+
+    mut zone_streaming: ResMut<ZoneStreamingResource>,
+) {
+    let mut spawn_budget = zone_streaming.config.entities_per_frame.max(1);
+
+    for (zone_entity, streamed_zone_comp, mut streaming_in) in zone_query.iter_mut() {
+        if streaming_in.file_task.is_some() {
+            continue;
+        }
+
+        if !streaming_in.pending_spawns.is_empty() {
+            for zone_entity_id in spawned_query.iter() {
+                streaming_in.pending_spawns.remove(zone_entity_id);
+            }
+        }
+
+        while spawn_budget > 0 {
+            let Some(zone_entity_data) = streaming_in.queue.pop_front() else {
+                break;
+            };
+
+            zone_entity_spawners.spawn(&zone_entity_data, zone_entity);
+            streaming_in.pending_spawns.insert(zone_entity_data.get_id());
+            spawn_budget -= 1;
+        }
+
+        if !streaming_in.queue.is_empty() || !streaming_in.pending_spawns.is_empty() {
+            continue;
+        }
+
+        commands.entity(zone_entity).remove::<StreamingInZone>();
+
+        if let Some(streamed_zone) = zone_streaming.zones.get_mut(&streamed_zone_comp.zone_name) {
+            streamed_zone.state = StreamedZoneState::Loaded;
+        }
+    }
+}
+
+fn zone_edits_tracked(zone_streaming: Res<ZoneStreamingResource>) -> bool {
+    zone_streaming.track_edits
+}
+
+// moved, added, removed or re-propped entities anywhere below a loaded streamed zone, or changed selection groups
+fn track_streamed_zone_edits(
+    mut commands: Commands,
+
+    // only saved entities, animated model parts move all the time
+    changed_query: Query<
+        (Entity, Ref<Transform>),
+        (
+            With<ZoneEntityId>,
+            Or<(Changed<Transform>, Changed<CustomPropsComponent>, Changed<ChildOf>)>,
+        ),
+    >,
+    changed_zone_query: Query<(Entity, Ref<Children>), With<StreamedZoneComponent>>,
+    changed_groups_query: Query<(Entity, Ref<SelectionGroupsComponent>), With<StreamedZoneComponent>>,
+
+    parent_query: Query<&ChildOf>,
+    zone_query: Query<(), (With<StreamedZoneComponent>, Without<StreamingInZone>)>,
+) {
+    let mut edited_zones: Vec<Entity> = changed_zone_query
+        .iter()
+        .filter(|(_, children)| children.is_changed() && !children.is_added())
+        .map(|(zone_entity, _)| zone_entity)
+        .collect();
+
+    edited_zones.extend(
+        changed_groups_query
+            .iter()
+            .filter(|(_, selection_groups)| selection_groups.is_changed() && !selection_groups.is_added())
+            .map(|(zone_entity, _)| zone_entity),
+    );
+
+    for (entity, transform) in changed_query.iter() {
+        // freshly spawned, anything placed by the user also changes the zone children
+        if transform.is_added() {
+            continue;
+        }
+
+        if let Some(zone_entity) = parent_query
+            .iter_ancestors(entity)
+            .find(|ancestor| zone_query.contains(*ancestor))
+        {
+            edited_zones.push(zone_entity);
+        }
+    }
+
+    for zone_entity in edited_zones {
+        if zone_query.contains(zone_entity) {
+            commands.entity(zone_entity).insert(ZoneUnsavedEdits);
+        }
+    }
+}
+
+fn clear_saved_zone_edits(
+    mut commands: Commands,
+    mut evt_reader: MessageReader<SaveZoneToFileEvent>,
+
+    zone_query: Query<(), (With<ZoneUnsavedEdits>, Without<StreamingInZone>)>,
+) {
+    for evt in evt_reader.read() {
+        if zone_query.contains(evt.0) {
+            commands.entity(evt.0).remove::<ZoneUnsavedEdits>();
+        }
+    }
+}
+
+fn draw_streamed_zone_bounds(
+    mut gizmos: Gizmos,
+
+    zone_query: Query<Has<ZoneUnsavedEdits>>,
+
+    zone_streaming: Res<ZoneStreamingResource>,
+) {
+    if !zone_streaming.show_bounds {
+        return;
+    }
+
+    for streamed_zone in zone_streaming.zones.values() {
+        let Some(bounds) = streamed_zone.bounds else {
+            continue;
+        };
+
+        let has_unsaved_edits = streamed_zone
+            .zone_entity
+            .is_some_and(|zone_entity| zone_query.get(zone_entity).unwrap_or(false));
+
+        let color = match streamed_zone.state {
+            _ if has_unsaved_edits => Color::srgb(1.0, 0.5, 0.0),
+            StreamedZoneState::Loaded => Color::srgb(0.2, 1.0, 0.2),
+            StreamedZoneState::Loading => Color::srgb(1.0, 1.0, 0.2),
+            StreamedZoneState::Unloaded => Color::srgb(0.5, 0.5, 0.5),
+            StreamedZoneState::Failed => Color::srgb(1.0, 0.2, 0.2),
+        };
+
+        // flat zones still get a visible box
+        let size = bounds.size().max(Vec3::ONE);
+
+        gizmos.cube(
+            Transform::from_translation(bounds.center()).with_scale(size),
+            color,
+        );
+    }
+}
@@ -0,0 +1,605 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use bevy::platform::collections::hash_map::HashMap;
+use bevy::prelude::*;
+
+use serde::Deserialize;
+
+use bevy_clay_tiles::tile_types_config::{ClayTilesTypesConfigResource, TileTypesConfig};
+
+use crate::doodads::doodad_manifest::{DoodadDefinition, DoodadDefinitionsResource, DoodadManifest, DoodadName};
+use crate::prefabs::prefab_definitions::PrefabDefinitionsResource;
+use crate::prefabs::prefab_file::PrefabFile;
+
+use super::zone_file::{TransformSimpleRads, ZoneEntityId, ZoneEntityV2, ZoneFileV2};
+
+/*
+
+Lints zone and prefab files against the doodad manifests, prefab definitions, material
+manifest and clay tile types, plus some sanity checks on the transforms.
+
+Nothing in here needs a running app so the same checks back the Zone Validation editor
+window and the zone-validate binary.  Each part of the context is optional, a check whose
+data is not loaded is skipped.
+
+*/
+
+const MATERIAL_OVERRIDE_PROP: &str = "material_override";
+
+// two instances of the same thing closer than this are reported as duplicates
+pub const DUPLICATE_INSTANCE_DISTANCE: f32 = 0.01;
+
+// how far outside the terrain an entity may be before it is reported
+pub const TERRAIN_BOUNDS_MARGIN: f32 = 64.0;
+
+const MIN_SCALE: f32 = 0.000_001;
+
+#[derive(Default, Clone)]
+pub struct ZoneValidationContext {
+    pub doodad_definitions: Option<HashMap<DoodadName, DoodadDefinition>>,
+    pub prefab_names: Option<HashSet<String>>,
+    pub material_names: Option<HashSet<String>>,
+    pub tile_type_count: Option<usize>,
+
+    // terrain extent on the xz plane, in world units
+    pub terrain_bounds: Option<Rect>,
+}
+
+/// Set by whatever loads the terrain so validation in the editor can check the bounds.
+#[derive(Resource, Default)]
+pub struct ZoneValidationTerrainBounds(pub Option<Rect>);
+
+impl ZoneValidationContext {
+    /// Builds the context from what the editor has loaded.  Material names are not known to
+    /// this crate and have to be filled in by the caller.
+    pub fn from_world(world: &World) -> Self {
+        let doodad_definitions = world
+            .get_resource::<DoodadDefinitionsResource>()
+            .and_then(|r| r.loaded_doodad_definitions.clone());
+
+        let prefab_names = world
+            .get_resource::<PrefabDefinitionsResource>()
+            .and_then(|r| r.loaded_prefab_definitions.as_ref())
+            .map(|defs| defs.keys().cloned().collect());
+
+        let tile_type_count = world
+            .get_resource::<ClayTilesTypesConfigResource>()
+            .map(|r| r.tile_type_data.len());
+
+        let terrain_bounds = world
+            .get_resource::<ZoneValidationTerrainBounds>()
+            .and_then(|r| r.0);
+
+        Self {
+            doodad_definitions,
+            prefab_names,
+            tile_type_count,
+            terrain_bounds,
+            ..default()
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ZoneValidationSeverity {
+    Warning,
+    Error,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ZoneValidationIssueKind {
+    UnknownDoodad,
+    UnknownPrefab,
+    UnknownMaterial(String),
+    InvalidCustomProp(String),
+    UnknownTileType(usize),
+    NonFiniteTransform,
+    ZeroScale,
+    DuplicateInstance(ZoneEntityId),
+    OutsideTerrainBounds(Vec3),
+}
+
+impl ZoneValidationIssueKind {
+    pub fn severity(&self) -> ZoneValidationSeverity {
+        match self {
+            Self::UnknownDoodad
+            | Self::UnknownPrefab
+            | Self::UnknownTileType(_)
+            | Self::NonFiniteTransform
+            | Self::ZeroScale => ZoneValidationSeverity::Error,
+
+            Self::UnknownMaterial(_)
+            | Self::InvalidCustomProp(_)
+            | Self::DuplicateInstance(_)
+            | Self::OutsideTerrainBounds(_) => ZoneValidationSeverity::Warning,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ZoneValidationIssue {
+    pub entity_id: ZoneEntityId,
+    pub entity_name: String,
+    pub kind: ZoneValidationIssueKind,
+}
+
+impl ZoneValidationIssue {
+    pub fn severity(&self) -> ZoneValidationSeverity {
+        self.kind.severity()
+    }
+}
+
+impl std::fmt::Display for ZoneValidationIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} ({}): ", self.entity_name, self.entity_id)?;
+
+        match &self.kind {
+            ZoneValidationIssueKind::UnknownDoodad => write!(f, "not in any doodad manifest"),
+            ZoneValidationIssueKind::UnknownPrefab => write!(f, "no prefab with this name"),
+            ZoneValidationIssueKind::UnknownMaterial(material) => {
+                write!(f, "material override {:?} is not in the material manifest", material)
+            }
+            ZoneValidationIssueKind::InvalidCustomProp(key) => {
+                write!(f, "custom prop {:?} does not match the doodads prop schema", key)
+            }
+            ZoneValidationIssueKind::UnknownTileType(index) => {
+                write!(f, "tile type {} is not in the tile types config", index)
+            }
+            ZoneValidationIssueKind::NonFiniteTransform => write!(f, "transform has NaN or infinite values"),
+            ZoneValidationIssueKind::ZeroScale => write!(f, "transform has a zero scale"),
+            ZoneValidationIssueKind::DuplicateInstance(other_id) => {
+                write!(f, "overlaps an identical instance {}", other_id)
+            }
+            ZoneValidationIssueKind::OutsideTerrainBounds(translation) => {
+                write!(f, "at {} is far outside the terrain", translation)
+            }
+        }
+    }
+}
+
+pub fn validate_zone_file(zone_file: &ZoneFileV2, context: &ZoneValidationContext) -> Vec<ZoneValidationIssue> {
+    validate_zone_entities(
+        &zone_file.entities,
+        zone_file.translation_offset.unwrap_or_default(),
+        context,
+        true,
+    )
+}
+
+// prefab entities are relative to wherever the prefab is placed, so there are no terrain bounds to check
+pub fn validate_prefab_file(prefab_file: &PrefabFile, context: &ZoneValidationContext) -> Vec<ZoneValidationIssue> {
+    validate_zone_entities(
+        &prefab_file.entities,
+        prefab_file.translation_offset.unwrap_or_default(),
+        context,
+        false,
+    )
+}
+
+pub fn validate_zone_entities(
+    entities: &[ZoneEntityV2],
+    translation_offset: Vec3,
+    context: &ZoneValidationContext,
+    check_terrain_bounds: bool,
+) -> Vec<ZoneValidationIssue> {
+    let mut issues = Vec::new();
+
+    for entity in entities.iter() {
+        let mut push_issue = |kind| {
+            issues.push(ZoneValidationIssue {
+                entity_id: entity.get_id(),
+                entity_name: entity_display_name(entity),
+                kind,
+            })
+        };
+
+        match entity {
+            ZoneEntityV2::Doodad { name, custom_props, .. } => {
+                let definition = context.doodad_definitions.as_ref().map(|defs| defs.get(name));
+
+                if let Some(None) = definition {
+                    push_issue(ZoneValidationIssueKind::UnknownDoodad);
+                }
+
+                // the custom prop wins over the definition, same as when the doodad is spawned
+                let material_override = custom_props
+                    .as_ref()
+                    .and_then(|props| props.get(MATERIAL_OVERRIDE_PROP))
+                    .map(|prop| prop.to_string())
+                    .or_else(|| definition.flatten().and_then(|def| def.material_override.clone()));
+
+                if let (Some(material_override), Some(material_names)) =
+                    (material_override, context.material_names.as_ref())
+                {
+                    if !material_names.contains(&material_override) {
+                        push_issue(ZoneValidationIssueKind::UnknownMaterial(material_override));
+                    }
+                }
+
+                if let (Some(definition), Some(custom_props)) = (definition.flatten(), custom_props) {
+                    let mut invalid_keys: Vec<&String> = custom_props
+                        .iter()
+                        .filter(|(key, prop)| {
+                            definition
+                                .get_prop_schema(key)
+                                .is_some_and(|prop_schema| !prop_schema.kind.accepts(prop))
+                        })
+                        .map(|(key, _)| key)
+                        .collect();
+
+                    invalid_keys.sort();
+
+                    for key in invalid_keys {
+                        push_issue(ZoneValidationIssueKind::InvalidCustomProp(key.clone()));
+                    }
+                }
+            }
+
+            ZoneEntityV2::Prefab { name, .. } => {
+                if let Some(prefab_names) = context.prefab_names.as_ref() {
+                    if !prefab_names.contains(name) {
+                        push_issue(ZoneValidationIssueKind::UnknownPrefab);
+                    }
+                }
+            }
+
+            ZoneEntityV2::ClayTile { clay_tile_block, .. } => {
+                if let Some(tile_type_count) = context.tile_type_count {
+                    if clay_tile_block.tile_type_index >= tile_type_count {
+                        push_issue(ZoneValidationIssueKind::UnknownTileType(clay_tile_block.tile_type_index));
+                    }
+                }
+            }
+
+            ZoneEntityV2::Spline { .. } => {}
+        }
+
+        let transform = entity_transform(entity);
+
+        if !transform_is_finite(transform) {
+            push_issue(ZoneValidationIssueKind::NonFiniteTransform);
+            continue;
+        }
+
+        if transform.scale.abs().min_element() < MIN_SCALE {
+            push_issue(ZoneValidationIssueKind::ZeroScale);
+        }
+
+        if let (true, Some(terrain_bounds)) = (check_terrain_bounds, context.terrain_bounds) {
+            let translation = transform.translation + translation_offset;
+
+            if !terrain_bounds
+                .inflate(TERRAIN_BOUNDS_MARGIN)
+                .contains(translation.xz())
+            {
+                push_issue(ZoneValidationIssueKind::OutsideTerrainBounds(translation));
+            }
+        }
+    }
+
+    issues.extend(find_duplicate_instances(entities));
+
+    issues
+}
+
+fn find_duplicate_instances(entities: &[ZoneEntityV2]) -> Vec<ZoneValidationIssue> {
+    let mut issues = Vec::new();
+
+    // sorted by kind and x so only a short run of neighbours has to be compared
+    let mut instances: Vec<(String, &ZoneEntityV2)> = entities
+        .iter()
+        .filter(|entity| transform_is_finite(entity_transform(entity)))
+        .filter_map(|entity| instance_key(entity).map(|key| (key, entity)))
+        .collect();
+
+    instances.sort_by(|(a_key, a), (b_key, b)| {
+        a_key.cmp(b_key).then(
+            entity_transform(a)
+                .translation
+                .x
+                .total_cmp(&entity_transform(b).translation.x),
+        )
+    });
+
+    for (i, (key, entity)) in instances.iter().enumerate() {
+        let transform = entity_transform(entity);
+
+        for (other_key, other) in instances[i + 1..].iter() {
+            let other_transform = entity_transform(other);
+
+            if other_key != key
+                || other_transform.translation.x - transform.translation.x > DUPLICATE_INSTANCE_DISTANCE
+            {
+                break;
+            }
+
+            if other_transform.translation.distance(transform.translation) > DUPLICATE_INSTANCE_DISTANCE
+                || !other_transform.scale.abs_diff_eq(transform.scale, DUPLICATE_INSTANCE_DISTANCE)
+            {
+                continue;
+            }
+
+            issues.push(ZoneValidationIssue {
+                entity_id: other.get_id(),
+                entity_name: entity_display_name(other),
+                kind: ZoneValidationIssueKind::DuplicateInstance(entity.get_id()),
+            });
+        }
+    }
+
+    issues
+}
+
+// what makes two instances identical for duplicate detection, tiles and splines are never duplicates
+fn instance_key(entity: &ZoneEntityV2) -> Option<String> {
+    match entity {
+        ZoneEntityV2::Doodad { name, .. } => Some(format!("doodad:{}", name)),
+        ZoneEntityV2::Prefab { name, .. } => Some(format!("prefab:{}", name)),
+        ZoneEntityV2::ClayTile { .. } | ZoneEntityV2::Spline { .. } => None,
+    }
+}
+
+fn entity_display_name(entity: &ZoneEntityV2) -> String {
+    match entity {
+        ZoneEntityV2::Doodad { name, .. }
+        | ZoneEntityV2::Prefab { name, .. }
+        | ZoneEntityV2::Spline { name, .. } => name.clone(),
+        ZoneEntityV2::ClayTile { .. } => "clay tile".into(),
+    }
+}
+
+fn entity_transform(entity: &ZoneEntityV2) -> &TransformSimpleRads {
+    match entity {
+        ZoneEntityV2::Doodad { transform, .. }
+        | ZoneEntityV2::ClayTile { transform, .. }
+        | ZoneEntityV2::Prefab { transform, .. }
+        | ZoneEntityV2::Spline { transform, .. } => transform,
+    }
+}
+
+fn transform_is_finite(transform: &TransformSimpleRads) -> bool {
+    transform.translation.is_finite() && transform.rotation.is_finite() && transform.scale.is_finite()
+}
+
+// ---- loading the context from files, for use outside of the editor
+
+pub fn collect_files_with_suffix(folder: &Path, suffix: &str, file_paths: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(folder)? {
+        let path = entry?.path();
+
+        if path.is_dir() {
+            collect_files_with_suffix(&path, suffix, file_paths)?;
+        } else if path.to_string_lossy().ends_with(suffix) {
+            file_paths.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+// the name a file is referenced by, "medieval_house1.prefab.ron" -> "medieval_house1"
+fn file_stem_name(path: &Path) -> Option<String> {
+    let file_name = path.file_name()?.to_str()?;
+
+    file_name.split('.').next().map(|name| name.to_string())
+}
+
+pub fn load_doodad_definitions_from_folder(
+    folder: &Path,
+) -> Result<HashMap<DoodadName, DoodadDefinition>, Box<dyn std::error::Error>> {
+    let mut file_paths = Vec::new();
+    collect_files_with_suffix(folder, ".ron", &mut file_paths)?;
+
+    let mut doodad_definitions = HashMap::new();
+
+    for file_path in file_paths {
+        let file_content = std::fs::read_to_string(&file_path)?;
+
+        let manifest = ron::from_str::<DoodadManifest>(&file_content)
+            .map_err(|e| format!("could not parse doodad manifest {:?}: {}", file_path, e))?;
+
+        doodad_definitions.extend(manifest.spawnables);
+    }
+
+    Ok(doodad_definitions)
+}
+
+pub fn load_prefab_names_from_folder(folder: &Path) -> Result<HashSet<String>, Box<dyn std::error::Error>> {
+    let mut file_paths = Vec::new();
+    collect_files_with_suffix(folder, ".ron", &mut file_paths)?;
+
+    Ok(file_paths.iter().filter_map(|path| file_stem_name(path)).collect())
+}
+
+#[derive(Deserialize)]
+struct MaterialNamesManifest {
+    override_materials: Vec<String>,
+}
+
+pub fn load_material_names(manifest_path: &Path) -> Result<HashSet<String>, Box<dyn std::error::Error>> {
+    let file_content = std::fs::read_to_string(manifest_path)?;
+    let manifest = ron::from_str::<MaterialNamesManifest>(&file_content)?;
+
+    Ok(manifest.override_materials.into_iter().collect())
+}
+
+pub fn load_tile_type_count(tile_types_path: &Path) -> Result<usize, Box<dyn std::error::Error>> {
+    let file_content = std::fs::read_to_string(tile_types_path)?;
+    let tile_types_config = ron::from_str::<TileTypesConfig>(&file_content)?;
+
+    Ok(tile_types_config.tile_types.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::doodads::doodad_manifest::RenderableType;
+    use crate::zones::zone_file::{CustomProp, CustomPropsMap};
+
+    fn doodad(name: &str, translation: Vec3) -> ZoneEntityV2 {
+        ZoneEntityV2::Doodad {
+            id: ZoneEntityId::new_random(),
+            name: name.to_string(),
+            transform: TransformSimpleRads {
+                translation,
+                ..Default::default()
+            },
+            custom_props: None,
+        }
+    }
+
+    fn definition(material_override: Option<&str>) -> DoodadDefinition {
+        DoodadDefinition {
+            model: RenderableType::NoModel,
+            initial_custom_props: None,
+            tags: None,
+            snap_dimensions: None,
+            material_override: material_override.map(|m| m.to_string()),
+            material_replacement_set: None,
+            prop_schema: None,
+            sockets: None,
+        }
+    }
+
+    fn context() -> ZoneValidationContext {
+        let mut doodad_definitions = HashMap::new();
+        doodad_definitions.insert("tree".to_string(), definition(None));
+        doodad_definitions.insert("stone_wall".to_string(), definition(Some("granite")));
+
+        ZoneValidationContext {
+            doodad_definitions: Some(doodad_definitions),
+            material_names: Some(["granite".to_string(), "marble".to_string()].into_iter().collect()),
+            ..default()
+        }
+    }
+
+    fn kinds(issues: &[ZoneValidationIssue]) -> Vec<ZoneValidationIssueKind> {
+        issues.iter().map(|issue| issue.kind.clone()).collect()
+    }
+
+    #[test]
+    fn test_unknown_doodad() {
+        let entities = vec![doodad("tree", Vec3::ZERO), doodad("rock", Vec3::X * 10.0)];
+
+        let issues = validate_zone_entities(&entities, Vec3::ZERO, &context(), false);
+
+        assert_eq!(kinds(&issues), vec![ZoneValidationIssueKind::UnknownDoodad]);
+        assert_eq!(issues[0].entity_id, entities[1].get_id());
+        assert_eq!(issues[0].severity(), ZoneValidationSeverity::Error);
+
+        // without manifests nothing is known, so nothing is reported
+        let issues = validate_zone_entities(&entities, Vec3::ZERO, &ZoneValidationContext::default(), false);
+        assert!(issues.is_empty());
+    }
+
+    #[test]
+    fn test_dangling_material_override() {
+        let mut props = CustomPropsMap::default();
+        props.insert(MATERIAL_OVERRIDE_PROP.to_string(), CustomProp::String("lava".to_string()));
+
+        let mut overridden = doodad("tree", Vec3::ZERO);
+        if let ZoneEntityV2::Doodad { custom_props, .. } = &mut overridden {
+            *custom_props = Some(props);
+        }
+
+        // the definition's own override is a known material
+        let entities = vec![overridden, doodad("stone_wall", Vec3::X * 10.0)];
+
+        let issues = validate_zone_entities(&entities, Vec3::ZERO, &context(), false);
+
+        assert_eq!(
+            kinds(&issues),
+            vec![ZoneValidationIssueKind::UnknownMaterial("lava".to_string())]
+        );
+        assert_eq!(issues[0].severity(), ZoneValidationSeverity::Warning);
+    }
+
+    #[test]
+    fn test_non_finite_and_zero_scale() {
+        let mut nan_tree = doodad("tree", Vec3::new(f32::NAN, 0.0, 0.0));
+        let mut flat_tree = doodad("tree", Vec3::X * 10.0);
+
+        // a non-finite transform is not also reported for its scale
+        if let ZoneEntityV2::Doodad { transform, .. } = &mut nan_tree {
+            transform.scale = Vec3::ZERO;
+        }
+        if let ZoneEntityV2::Doodad { transform, .. } = &mut flat_tree {
+            transform.scale = Vec3::new(1.0, 0.0, 1.0);
+        }
+
+        let entities = vec![nan_tree, flat_tree];
+
+        let issues = validate_zone_entities(&entities, Vec3::ZERO, &context(), false);
+
+        assert_eq!(
+            kinds(&issues),
+            vec![
+                ZoneValidationIssueKind::NonFiniteTransform,
+                ZoneValidationIssueKind::ZeroScale,
+            ]
+        );
+        assert_eq!(issues[0].entity_id, entities[0].get_id());
+        assert_eq!(issues[1].entity_id, entities[1].get_id());
+    }
+
+    #[test]
+    fn test_duplicate_instances() {
+        let entities = vec![
+            doodad("tree", Vec3::new(5.0, 0.0, 5.0)),
+            doodad("stone_wall", Vec3::new(5.0, 0.0, 5.0)),
+            doodad("tree", Vec3::new(5.0, 0.0, 5.005)),
+            doodad("tree", Vec3::new(6.0, 0.0, 5.0)),
+            doodad("tree", Vec3::new(f32::NAN, 0.0, 0.0)),
+        ];
+
+        let issues = find_duplicate_instances(&entities);
+
+        // only the second tree overlaps the first, a different doodad at the same spot is fine
+        assert_eq!(
+            kinds(&issues),
+            vec![ZoneValidationIssueKind::DuplicateInstance(entities[0].get_id())]
+        );
+        assert_eq!(issues[0].entity_id, entities[2].get_id());
+
+        // a scaled copy is not a duplicate
+        let mut scaled = doodad("tree", Vec3::new(5.0, 0.0, 5.0));
+        if let ZoneEntityV2::Doodad { transform, .. } = &mut scaled {
+            transform.scale = Vec3::splat(2.0);
+        }
+        assert!(find_duplicate_instances(&[entities[0].clone(), scaled]).is_empty());
+    }
+
+    #[test]
+    fn test_outside_terrain_bounds() {
+        let context = ZoneValidationContext {
+            terrain_bounds: Some(Rect::new(0.0, 0.0, 100.0, 100.0)),
+            ..default()
+        };
+
+        let entities = vec![
+            doodad("tree", Vec3::new(50.0, 0.0, 50.0)),
+            // within the margin
+            doodad("tree", Vec3::new(100.0 + TERRAIN_BOUNDS_MARGIN - 1.0, 0.0, 50.0)),
+            doodad("tree", Vec3::new(50.0, 0.0, -200.0)),
+        ];
+
+        let issues = validate_zone_entities(&entities, Vec3::ZERO, &context, true);
+        assert_eq!(
+            kinds(&issues),
+            vec![ZoneValidationIssueKind::OutsideTerrainBounds(Vec3::new(50.0, 0.0, -200.0))]
+        );
+
+        // the zone offset moves every entity
+        let issues = validate_zone_entities(&entities[..1], Vec3::new(500.0, 0.0, 0.0), &context, true);
+        assert_eq!(
+            kinds(&issues),
+            vec![ZoneValidationIssueKind::OutsideTerrainBounds(Vec3::new(550.0, 0.0, 50.0))]
+        );
+
+        // prefab entities are never checked against the terrain
+        let issues = validate_zone_entities(&entities, Vec3::ZERO, &context, false);
+        assert!(issues.is_empty());
+    }
+}
@@ -32,7 +32,7 @@ use bevy_app::prelude::*;
 use bevy_asset::{AssetApp, Assets};
 use bevy_ecs::prelude::*;
 use bevy_input::prelude::*;
-use bevy_math::{DQuat, DVec3, Vec2};
+use bevy_math::{DQuat, DVec3, Vec2, Vec3};
 use transform_gizmo::math::{DMat4 as GizmoDMat4, DVec3 as GizmoDVec3, DQuat as GizmoDQuat};
 use bevy::render::prelude::*;
 use bevy_transform::prelude::*;
@@ -88,6 +88,8 @@ pub struct GizmoOptions {
     pub gizmo_orientation: GizmoOrientation,
     /// Orientation of the gizmo. This affects the behaviour of transformations.
     pub pivot_point: TransformPivotPoint,
+    /// Pivot used with [`TransformPivotPoint::Position`], in world space.
+    pub pivot_position: Vec3,
     /// Look and feel of the gizmo.
     pub visuals: GizmoVisuals,
     /// Whether snapping is enabled in the gizmo transformations.
@@ -123,6 +125,7 @@ impl Default for GizmoOptions {
             gizmo_modes: GizmoMode::all(),
             gizmo_orientation: GizmoOrientation::default(),
             pivot_point: TransformPivotPoint::default(),
+            pivot_position: Vec3::ZERO,
             visuals: Default::default(),
             snapping: false,
             accurate_mode: false,
@@ -464,6 +467,12 @@ fn update_gizmos(
         mode_override: gizmo_options.mode_override,
         orientation: gizmo_options.gizmo_orientation,
         pivot_point: gizmo_options.pivot_point,
+        pivot_position: GizmoDVec3::new(
+            gizmo_options.pivot_position.x.into(),
+            gizmo_options.pivot_position.y.into(),
+            gizmo_options.pivot_position.z.into(),
+        )
+        .into(),
         visuals: gizmo_options.visuals,
         snapping: gizmo_options.snapping,
         snap_angle,
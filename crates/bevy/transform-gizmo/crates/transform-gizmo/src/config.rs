@@ -36,6 +36,8 @@ pub struct GizmoConfig {
     pub orientation: GizmoOrientation,
     /// Pivot point for transformations
     pub pivot_point: TransformPivotPoint,
+    /// Pivot used with [`TransformPivotPoint::Position`], in world space.
+    pub pivot_position: mint::Vector3<f64>,
     /// Toggles snapping to predefined increments during transformations for precision.
     pub snapping: bool,
     /// Angle increment for snapping rotations, in radians.
@@ -60,6 +62,7 @@ impl Default for GizmoConfig {
             mode_override: None,
             orientation: GizmoOrientation::default(),
             pivot_point: TransformPivotPoint::default(),
+            pivot_position: DVec3::ZERO.into(),
             snapping: false,
             snap_angle: DEFAULT_SNAP_ANGLE,
             snap_distance: DEFAULT_SNAP_DISTANCE,
@@ -187,6 +190,10 @@ impl PreparedGizmoConfig {
             scale /= target_count as f64;
         }
 
+        if self.pivot_point == TransformPivotPoint::Position {
+            translation = DVec3::from(self.pivot_position);
+        }
+
         self.update_transform(Transform {
             scale: scale.into(),
             rotation: rotation.into(),
@@ -404,6 +411,8 @@ pub enum TransformPivotPoint {
     MedianPoint,
     /// Pivot around each target's own origin
     IndividualOrigins,
+    /// Pivot around [`GizmoConfig::pivot_position`]
+    Position,
 }
 
 /// Orientation of a gizmo.
@@ -255,7 +255,7 @@ impl Gizmo {
 
     fn update_rotation_quat(&self, transform: &Transform, delta: DQuat) -> Transform {
         let translation = match self.config.pivot_point {
-            TransformPivotPoint::MedianPoint => (self.config.translation
+            TransformPivotPoint::MedianPoint | TransformPivotPoint::Position => (self.config.translation
                 + delta * (DVec3::from(transform.translation) - self.config.translation))
                 .into(),
             TransformPivotPoint::IndividualOrigins => transform.translation,
@@ -308,8 +308,27 @@ impl Gizmo {
             GizmoOrientation::Local => DVec3::from(start_transform.scale) * DVec3::from(scale),
         };
 
+        // around an explicit pivot position the targets also spread out from (or gather to) it,
+        // the other pivot modes only scale each target in place
+        let translation = match self.config.pivot_point {
+            TransformPivotPoint::IndividualOrigins | TransformPivotPoint::MedianPoint => transform.translation,
+            TransformPivotPoint::Position => {
+                let pivot = DVec3::from(self.gizmo_start_transform.translation);
+                let orientation = match self.config.orientation() {
+                    GizmoOrientation::Global => DQuat::IDENTITY,
+                    GizmoOrientation::Local => DQuat::from(self.gizmo_start_transform.rotation),
+                };
+
+                let offset = orientation.inverse()
+                    * (DVec3::from(start_transform.translation) - pivot);
+
+                (pivot + orientation * (offset * DVec3::from(scale))).into()
+            }
+        };
+
         Transform {
             scale: new_scale.into(),
+            translation,
             ..*transform
         }
     }
@@ -14,6 +14,7 @@ use crate::utils::{walk_dir};
 use spirit_edit_core::doodads::doodad_manifest::DoodadManifest;
 use spirit_edit_core::doodads::doodad_manifest::DoodadDefinitionsResource;
 use spirit_edit_core::doodads::doodad_manifest::DoodadTagMapResource;
+use spirit_edit_core::placement::PlacementToolsState;
 use bevy_common_assets::ron::RonAssetPlugin;
 
 use spirit_edit_core::prefabs::prefab_definitions::PrefabDefinition;
@@ -701,6 +702,7 @@ fn apply_editor_config(
 
     mut editor_cx: ResMut<Editor>,
     editor_config : Res<EditorConfig >,
+    mut placement_tools_state: ResMut<PlacementToolsState>,
     
 
 
@@ -710,18 +712,25 @@ fn apply_editor_config(
  //   let editor_config_handle = &editor_config_handles.editor_config;
     //let Some(editor_config) = editor_config_assets.get(editor_config_handle) else {return} ;
 
-   let Some(   state ) = editor_cx.window_state_mut::<PlacementWindow>() else {return};
-        
+   let Some( placement_config ) = editor_config.get_default_placement_settings() else {return};
 
-        if let Some( placement_config ) = editor_config.get_default_placement_settings() {
+        if let Some( surface_align_mode ) = placement_config.surface_align_mode {
+            placement_tools_state.surface_align_mode = surface_align_mode;
+        }
+        if let Some( max_surface_tilt_degrees ) = placement_config.max_surface_tilt_degrees {
+            placement_tools_state.max_surface_tilt_degrees = max_surface_tilt_degrees;
+        }
+        if let Some( nudge_out_of_colliders ) = placement_config.nudge_out_of_colliders {
+            placement_tools_state.nudge_out_of_colliders = nudge_out_of_colliders;
+        }
 
+   let Some(   state ) = editor_cx.window_state_mut::<PlacementWindow>() else {return};
+        
 
           state.translation_grid_lock_step = placement_config.translation_grid_lock_step
                     .clone().unwrap_or(Vec3::splat(0.0));
 
 
-        }
-
 
 
 }
\ No newline at end of file
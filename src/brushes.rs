@@ -0,0 +1,138 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use bevy::prelude::*;
+
+use bevy_egui::EguiContexts;
+use bevy_egui::egui;
+
+use bevy_brush_shapes::{BrushAlpha, BrushShapeKind};
+
+use crate::editor_pls::bevy_pls_editor_is_active;
+use crate::ui::{EditorToolsState, SubTool, ToolMode};
+use crate::utils::walk_dir;
+
+/*
+
+Brush alphas and height stamps for the paint tools (terrain height, splat, holes, regions and foliage).
+
+Any png in assets/brushes or in the brushes folder of the external game assets is loaded as a
+grayscale brush alpha. The built in stamps (mountain, crater, plateau) are always available.
+
+The selected shape, its rotation and the scatter settings live in EditorToolsState and are
+sent along with every paint event, see tools.rs.
+
+*/
+
+const BRUSH_FOLDERS: [&str; 2] = [
+    "./assets/brushes",
+    "./artifacts/game_assets/brushes", // copied from the external game assets on startup
+];
+
+pub fn brushes_plugin(app: &mut App) {
+    app
+        .init_resource::<BrushShapesResource>()
+        .add_systems(Startup, load_brush_alphas)
+        .add_systems(Update, brush_shape_ui.run_if(not(bevy_pls_editor_is_active)));
+}
+
+#[derive(Resource, Default)]
+pub struct BrushShapesResource {
+    pub alphas: Vec<Arc<BrushAlpha>>,
+}
+
+impl BrushShapesResource {
+    pub fn shape_kinds(&self) -> Vec<BrushShapeKind> {
+        BrushShapeKind::BUILT_IN
+            .into_iter()
+            .chain(self.alphas.iter().cloned().map(BrushShapeKind::Alpha))
+            .collect()
+    }
+}
+
+fn load_brush_alphas(mut brush_shapes: ResMut<BrushShapesResource>) {
+    let mut alphas: Vec<Arc<BrushAlpha>> = Vec::new();
+
+    for folder in BRUSH_FOLDERS {
+        let mut file_names_array: Vec<String> = Vec::new();
+        walk_dir(folder, "png", &mut file_names_array);
+
+        for file_path in file_names_array {
+            let Some(alpha) = BrushAlpha::load_from_path(Path::new(&file_path)) else {
+                warn!("could not load brush alpha {}", file_path);
+                continue;
+            };
+
+            // local brushes win over game asset brushes with the same name
+            if alphas.iter().any(|existing| existing.name == alpha.name) {
+                continue;
+            }
+
+            alphas.push(Arc::new(alpha));
+        }
+    }
+
+    alphas.sort_by(|a, b| a.name.cmp(&b.name));
+
+    info!("loaded {} brush alphas", alphas.len());
+
+    brush_shapes.alphas = alphas;
+}
+
+// the tools that paint through EditTerrainEvent, EditRegionEvent or EditFoliageEvent
+fn is_paint_tool(tools_state: &EditorToolsState) -> bool {
+    match tools_state.tool_mode {
+        ToolMode::Terrain => matches!(
+            tools_state.sub_tool,
+            Some(SubTool::TerrainHeight) | Some(SubTool::TerrainSplat) | Some(SubTool::TerrainHoles)
+        ),
+        ToolMode::Foliage | ToolMode::Regions => true,
+        _ => false,
+    }
+}
+
+fn brush_shape_ui(
+    mut contexts: EguiContexts,
+
+    mut tools_state: ResMut<EditorToolsState>,
+    brush_shapes: Res<BrushShapesResource>,
+) {
+    if !is_paint_tool(&tools_state) {
+        return;
+    }
+
+    egui::Window::new("Brush Shape").show(contexts.ctx_mut(), |ui| {
+        egui::ComboBox::new("brush_shape", "Shape")
+            .selected_text(tools_state.brush_shape.kind.label())
+            .show_ui(ui, |ui| {
+                for kind in brush_shapes.shape_kinds() {
+                    let label = kind.label();
+                    if ui
+                        .selectable_label(tools_state.brush_shape.kind == kind, label)
+                        .clicked()
+                    {
+                        tools_state.brush_shape.kind = kind;
+                    }
+                }
+            });
+
+        if brush_shapes.alphas.is_empty() {
+            ui.label("Add grayscale png files to assets/brushes for custom brush alphas.");
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("Rotation");
+            ui.drag_angle(&mut tools_state.brush_shape.rotation);
+        });
+
+        ui.separator();
+        ui.label("Scatter");
+
+        let scatter = &mut tools_state.brush_scatter;
+
+        ui.add(egui::Slider::new(&mut scatter.count, 1..=16).text("Dabs"));
+        ui.add(egui::Slider::new(&mut scatter.spread, 0.0..=3.0).text("Spread (x radius)"));
+        ui.add(egui::Slider::new(&mut scatter.rotation_jitter, 0.0..=std::f32::consts::PI).text("Rotation jitter"));
+        ui.add(egui::Slider::new(&mut scatter.scale_jitter, 0.0..=0.9).text("Scale jitter"));
+    });
+}
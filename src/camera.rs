@@ -3,6 +3,7 @@
 use crate::post_processing::PostProcessSettings;
 use bevy_foliage_tool::foliage_viewer::FoliageViewer;
 use degen_toon_terrain::terrain::TerrainViewer;
+use spirit_edit_core::zones::zone_streaming::ZoneViewer;
 use bevy::core_pipeline::tonemapping::Tonemapping;
 
 use bevy::render::view::ColorGrading;
@@ -80,6 +81,7 @@ fn init_camera (
      //  .insert( ToonShaderMainCamera )
          .insert( color_grading ) 
         .insert(TerrainViewer::default())
+        .insert(ZoneViewer)
          .insert( FoliageViewer )
          .insert( DoodadSpawnOrigin )
         .insert( DepthPrepass )
@@ -43,12 +43,14 @@ use bevy::platform::collections::hash_map::HashMap;
 use spirit_edit_core::doodads::DoodadToolEvent;
 use spirit_edit_core::placement::PlacementResource;
 use spirit_edit_core::doodads::DoodadProto;
+use spirit_edit_core::history::EditorHistoryEvent;
 use spirit_edit_core::zones::zone_file::CustomPropsComponent;
 use spirit_edit_core::doodads::PlaceDoodadEvent;
 use bevy_editor_pls_core::EditorEvent;
 use spirit_edit_core::doodads::doodad::DoodadComponent;
 use spirit_edit_core::doodads::doodad_manifest::RenderableType;
 use spirit_edit_core::doodads::DoodadNeedsModelAttached;
+use spirit_edit_core::doodads::sockets::GltfSocketsComponent;
 use spirit_edit_core::doodads::doodad_manifest::DoodadDefinitionsResource; 
 use crate::AssetLoadState;
 
@@ -827,6 +829,7 @@ pub fn update_doodad_placement_preview_model (
     let Some((placement_preview_entity, doodad_placement_comp)) = doodad_placement_component_query.single().ok() else {return};
     
          commands.entity(placement_preview_entity).despawn_related::<Children> () ;  //was despawn_descendants 
+         commands.entity(placement_preview_entity).remove::<GltfSocketsComponent>();
 
               let Some(doodad_name) =  &doodad_placement_comp.preview_doodad_name else {return};
 
@@ -953,6 +956,7 @@ pub fn handle_place_doodad_events(
 
     mut editor_event_writer: MessageWriter<EditorEvent>,
     mut doodad_tool_event_writer: MessageWriter<DoodadToolEvent>,
+    mut history_event_writer: MessageWriter<EditorHistoryEvent>,
 
     placement_resource: Res<PlacementResource>,
 
@@ -966,6 +970,8 @@ pub fn handle_place_doodad_events(
 
    // let manifest = manifest_handle.as_ref().map( |handle| doodad_manifest_assets.get(handle) ).flatten();
 
+    let mut recorded_spawns = Vec::new();
+
     for evt in evt_reader.read() {
         let position = &evt.position;
         let doodad_name = &evt.doodad_name;
@@ -1024,6 +1030,10 @@ pub fn handle_place_doodad_events(
             .insert( DoodadProto )
             .id();
 
+        if let Some(zone_entity_id) = evt.zone_entity_id {
+            commands.entity(doodad_spawned).insert(zone_entity_id);
+        }
+
 
         if *auto_select {
 
@@ -1067,8 +1077,17 @@ pub fn handle_place_doodad_events(
          if let Some(parent) = parent {
             commands.entity( doodad_spawned ).set_parent( *parent );
          }
+
+         if evt.record_history {
+            recorded_spawns.push( doodad_spawned );
+         }
  
     }
+
+    // a scatter stroke places many doodads in one frame, they become one entry
+    if !recorded_spawns.is_empty() {
+        history_event_writer.write( EditorHistoryEvent::RecordSpawned( recorded_spawns ) );
+    }
 }
 
 
@@ -1224,6 +1243,7 @@ pub fn handle_place_clay_tile_block_events(
 
     mut editor_event_writer: MessageWriter<EditorEvent>,
     mut doodad_tool_event_writer: MessageWriter<DoodadToolEvent>,
+    mut history_event_writer: MessageWriter<EditorHistoryEvent>,
 
     placement_resource: Res<PlacementResource>,
 
@@ -1235,6 +1255,8 @@ pub fn handle_place_clay_tile_block_events(
 
    // let manifest = manifest_handle.as_ref().map( |handle| doodad_manifest_assets.get(handle) ).flatten();
 
+    let mut recorded_spawns = Vec::new();
+
     for evt in evt_reader.read() {
         let position = &evt.position;
        // let doodad_name = &evt.doodad_name;
@@ -1259,6 +1281,10 @@ pub fn handle_place_clay_tile_block_events(
             .insert( DoodadProto )
             .id();
 
+        if let Some(zone_entity_id) = evt.zone_entity_id {
+            commands.entity(doodad_spawned).insert(zone_entity_id);
+        }
+
 
         editor_event_writer.write( 
             EditorEvent::SetSelectedEntities(Some(vec![ doodad_spawned ]))
@@ -1313,6 +1339,10 @@ pub fn handle_place_clay_tile_block_events(
             commands.entity( doodad_spawned ).set_parent( * parent );
          }
 
+         if evt.record_history {
+            recorded_spawns.push( doodad_spawned );
+         }
+
          /*
          if let Some(zone_override) = &evt.zone {
             if let Some(mut ent) = commands.get_entity(zone_override.clone()) {
@@ -1324,6 +1354,10 @@ pub fn handle_place_clay_tile_block_events(
             }
         }*/
     }
+
+    if !recorded_spawns.is_empty() {
+        history_event_writer.write( EditorHistoryEvent::RecordSpawned( recorded_spawns ) );
+    }
 }
 
 
@@ -1380,7 +1414,7 @@ pub fn replace_proto_doodads_with_doodads(
             continue;
         };
 
-        let  custom_props_from_manifest = &doodad_definition.initial_custom_props ;
+        let  custom_props_from_manifest = &doodad_definition.get_initial_custom_props() ; //includes the prop schema defaults
 
  
 
@@ -1469,7 +1503,23 @@ pub fn update_place_doodads(
         false => None,
     };
 
-    let scale: Option<Vec3> = match random_scale_multiplier >= 0.001 {
+    // a socket snap decides the rotation and keeps the kit piece at its modeled size
+    let snapped = evt.rotation.is_some();
+
+    // otherwise the surface may tilt the doodad, keeping its random yaw
+    let surface_rotation = placement_tools_state
+        .surface_rotation(evt.surface_normal)
+        .map(|tilt| tilt * Quat::from_rotation_y(rotation_euler.map_or(0.0, |euler| euler.x)));
+
+    let rotation_euler = match evt.rotation.or(surface_rotation) {
+        Some(rotation) => {
+            let (y, x, z) = rotation.to_euler(EulerRot::YXZ);
+            Some(Vec3::new(y, x, z))
+        }
+        None => rotation_euler,
+    };
+
+    let scale: Option<Vec3> = match random_scale_multiplier >= 0.001 && !snapped {
         true => {
             let random_f32 = rng.gen_range(-1.0..1.0);
             let random_scaled_f32 = 1.0 + random_scale_multiplier * random_f32;
@@ -1507,6 +1557,8 @@ pub fn update_place_doodads(
                 custom_props,
                 force_parent: None,
                  auto_select: true,
+                 zone_entity_id: None,
+                 record_history: true,
                 //clay_tile_block_data : None ,
       
 
@@ -4,8 +4,15 @@ use bevy::picking::backend::ray::RayMap;
 use rand::Rng;
 use bevy::prelude::*;
 
+use avian3d::collision::collider::contact_query;
+use avian3d::prelude::{Collider, ShapeCastConfig, SpatialQuery, SpatialQueryFilter};
+use bevy::ecs::relationship::DescendantIter;
+use spirit_edit_core::history::EditorHistoryEvent;
+use spirit_edit_core::placement::surface::yaw_rotation;
+use spirit_edit_core::placement::{PlacementEvent, PlacementToolsState};
 
-use crate::doodads::doodad_placement_preview::DoodadPlacementComponent;
+
+use crate::doodads::doodad_placement_preview::{DoodadPlacementComponent, DoodadSocketSnapResource};
 use bevy_editor_pls_core::Editor;
 use bevy_egui::EguiContexts;
  
@@ -24,6 +31,7 @@ use bevy_egui::EguiContexts;
             .add_systems(Update, (
                
                 update_doodad_placement_raycast, 
+                handle_drop_to_ground_events,
                  
 
                 ) .chain()  );
@@ -35,7 +43,11 @@ use bevy_egui::EguiContexts;
 #[derive(Message,Clone)]
 pub struct RequestPlaceDoodad {
 
- pub 	position: Vec3 
+ pub 	position: Vec3 ,
+
+ pub rotation: Option<Quat>, // set when the placement preview snapped to a socket
+
+ pub surface_normal: Option<Vec3>, // of the surface under the cursor, for surface aligned placement
 
 }
 
@@ -62,7 +74,9 @@ pub fn update_doodad_placement_raycast(
     editor: Res<Editor>,
 
      doodad_placement_component_query: Query<&Transform, With<DoodadPlacementComponent>>,
-       parent_query: Query<&ChildOf >
+       parent_query: Query<&ChildOf >,
+
+    socket_snap_resource: Res<DoodadSocketSnapResource>,
 ) {
     //we can tell if we are clicking in viewport
     let egui_ctx = contexts.ctx_mut();
@@ -133,9 +147,287 @@ pub fn update_doodad_placement_raycast(
             });*/
 
 
-            event_writer.write(RequestPlaceDoodad { position: hit_coordinates  }) ;
+            let request = match &socket_snap_resource.snap {
+                Some(snap) => RequestPlaceDoodad {
+                    position: snap.transform.translation,
+                    rotation: Some(snap.transform.rotation),
+                    surface_normal: None,
+                },
+                None => RequestPlaceDoodad {
+                    position: hit_coordinates,
+                    rotation: None,
+                    surface_normal: Some(intersection_data.normal),
+                },
+            };
+
+            event_writer.write(request) ;
+        }
+    }
+}
+
+
+
+
+
+/*
+
+Drop to ground sweeps the colliders of each doodad straight down onto terrain or other colliders.
+With surface aligned placement the doodad is first tilted to the surface under its origin.
+
+The nudge pushes a doodad out of colliders it overlaps, along the contact normals.
+It runs after a drop when enabled, or on its own.
+
+The selection is never swept against itself, the physics world still has it at the old spots.
+
+*/
+
+// how far below a doodad a drop looks for ground
+const DROP_TO_GROUND_MAX_DISTANCE: f32 = 500.0;
+
+// one push can move a doodad into another overlap, so the nudge is repeated a few times
+const NUDGE_ITERATIONS: usize = 8;
+
+// overlaps shallower than this are left alone
+const NUDGE_MIN_PENETRATION: f32 = 0.001;
+
+struct DoodadColliderPose<'a> {
+    collider: &'a Collider,
+    local_xform: Transform, // relative to the doodad
+}
+
+fn doodad_collider_poses<'a>(
+    doodad_entity: Entity,
+    doodad_xform: &GlobalTransform,
+    children_query: &Query<&Children>,
+    collider_query: &'a Query<(&Collider, &GlobalTransform)>,
+) -> Vec<DoodadColliderPose<'a>> {
+    std::iter::once(doodad_entity)
+        .chain(DescendantIter::new(children_query, doodad_entity))
+        .filter_map(|entity| collider_query.get(entity).ok())
+        .map(|(collider, collider_xform)| DoodadColliderPose {
+            collider,
+            local_xform: collider_xform.reparented_to(doodad_xform),
+        })
+        .collect()
+}
+
+struct GroundHit {
+    distance: f32,
+    normal: Vec3,
+}
+
+fn ray_to_ground(spatial_query: &SpatialQuery, filter: &SpatialQueryFilter, origin: Vec3) -> Option<GroundHit> {
+    spatial_query
+        .cast_ray(origin, Dir3::NEG_Y, DROP_TO_GROUND_MAX_DISTANCE, true, filter)
+        .map(|hit| GroundHit {
+            distance: hit.distance,
+            normal: hit.normal,
+        })
+}
+
+// the shortest fall over all colliders of the doodad, a doodad without colliders drops its origin
+fn sweep_to_ground(
+    spatial_query: &SpatialQuery,
+    filter: &SpatialQueryFilter,
+    doodad_xform: &Transform,
+    collider_poses: &[DoodadColliderPose],
+) -> Option<GroundHit> {
+    if collider_poses.is_empty() {
+        return ray_to_ground(spatial_query, filter, doodad_xform.translation);
+    }
+
+    let config = ShapeCastConfig::from_max_distance(DROP_TO_GROUND_MAX_DISTANCE);
+
+    collider_poses
+        .iter()
+        .filter_map(|pose| {
+            let collider_xform = doodad_xform.mul_transform(pose.local_xform);
+
+            spatial_query.cast_shape(
+                pose.collider,
+                collider_xform.translation,
+                collider_xform.rotation,
+                Dir3::NEG_Y,
+                &config,
+                filter,
+            )
+        })
+        .min_by(|a, b| a.distance.total_cmp(&b.distance))
+        .map(|hit| GroundHit {
+            distance: hit.distance,
+            normal: hit.normal1,
+        })
+}
+
+// the offset that moves the doodad out of the colliders it overlaps
+fn nudge_out_of_colliders(
+    spatial_query: &SpatialQuery,
+    filter: &SpatialQueryFilter,
+    doodad_xform: &Transform,
+    collider_poses: &[DoodadColliderPose],
+    collider_query: &Query<(&Collider, &GlobalTransform)>,
+) -> Vec3 {
+    let mut offset = Vec3::ZERO;
+
+    for _ in 0..NUDGE_ITERATIONS {
+        // the deepest overlap is resolved first, adding up pushes would overshoot on shared surfaces
+        let mut deepest_push = Vec3::ZERO;
+
+        for pose in collider_poses.iter() {
+            let collider_xform = doodad_xform.mul_transform(pose.local_xform);
+            let position = collider_xform.translation + offset;
+
+            for other_entity in spatial_query.shape_intersections(pose.collider, position, collider_xform.rotation, filter) {
+                let Ok((other_collider, other_xform)) = collider_query.get(other_entity) else {
+                    continue;
+                };
+
+                let (_, other_rotation, other_position) = other_xform.to_scale_rotation_translation();
+
+                let Ok(Some(contact)) = contact_query::contact(
+                    pose.collider,
+                    position,
+                    collider_xform.rotation,
+                    other_collider,
+                    other_position,
+                    other_rotation,
+                    0.0,
+                ) else {
+                    continue;
+                };
+
+                if contact.penetration < NUDGE_MIN_PENETRATION {
+                    continue;
+                }
+
+                // the normal points from the doodad into the other collider
+                let push = -(collider_xform.rotation * contact.local_normal1) * contact.penetration;
+
+                if push.length_squared() > deepest_push.length_squared() {
+                    deepest_push = push;
+                }
+            }
+        }
+
+        if deepest_push == Vec3::ZERO {
+            break;
         }
+
+        offset += deepest_push;
     }
+
+    offset
 }
 
+// the history snapshots the BeginEdit targets in PostUpdate, so the entities are moved the frame after
+pub fn handle_drop_to_ground_events(
+    mut evt_reader: MessageReader<PlacementEvent>,
+    mut history_evt_writer: MessageWriter<EditorHistoryEvent>,
+
+    spatial_query: SpatialQuery,
+    placement_tools_state: Res<PlacementToolsState>,
+
+    children_query: Query<&Children>,
+    parent_query: Query<&ChildOf>,
+    collider_query: Query<(&Collider, &GlobalTransform)>,
+    global_xform_query: Query<&GlobalTransform>,
+    mut xform_query: Query<&mut Transform>,
+
+    // (entities, drop to ground) waiting for their BeginEdit snapshot
+    mut pending_moves: Local<Vec<(Vec<Entity>, bool)>>,
+) {
+    let ready_moves = std::mem::take(&mut *pending_moves);
+
+    for evt in evt_reader.read() {
+        match evt {
+            PlacementEvent::DropToGround(entities) => pending_moves.push((entities.clone(), true)),
+            PlacementEvent::NudgeOutOfColliders(entities) => pending_moves.push((entities.clone(), false)),
+            _ => {}
+        }
+    }
+
+    for (entities, drop_to_ground) in ready_moves.iter() {
+        let drop_to_ground = *drop_to_ground;
+
+        let nudge = !drop_to_ground || placement_tools_state.nudge_out_of_colliders;
+
+        let selection_colliders: Vec<Entity> = entities
+            .iter()
+            .flat_map(|entity| std::iter::once(*entity).chain(DescendantIter::new(&children_query, *entity)))
+            .filter(|entity| collider_query.contains(*entity))
+            .collect();
+
+        let filter = SpatialQueryFilter::from_excluded_entities(selection_colliders);
+
+        // children of another selected entity move with it
+        let is_selected_descendant = |entity: Entity| {
+            parent_query
+                .iter_ancestors(entity)
+                .any(|ancestor| entities.contains(&ancestor))
+        };
+
+        for entity in entities.iter().copied() {
+            if is_selected_descendant(entity) {
+                continue;
+            }
+
+            let Ok(global_xform) = global_xform_query.get(entity) else {
+                continue;
+            };
+
+            let collider_poses = doodad_collider_poses(entity, global_xform, &children_query, &collider_query);
 
+            let mut world_xform = global_xform.compute_transform();
+
+            if drop_to_ground {
+                let surface_rotation = ray_to_ground(&spatial_query, &filter, world_xform.translation)
+                    .and_then(|ground| placement_tools_state.surface_rotation(Some(ground.normal)));
+
+                if let Some(surface_rotation) = surface_rotation {
+                    world_xform.rotation = surface_rotation * yaw_rotation(world_xform.rotation);
+                }
+
+                match sweep_to_ground(&spatial_query, &filter, &world_xform, &collider_poses) {
+                    Some(ground) => world_xform.translation.y -= ground.distance,
+                    None => warn!("no ground below {:?} to drop onto", entity),
+                }
+            }
+
+            if nudge {
+                world_xform.translation += nudge_out_of_colliders(
+                    &spatial_query,
+                    &filter,
+                    &world_xform,
+                    &collider_poses,
+                    &collider_query,
+                );
+            }
+
+            let local_xform: Transform = match parent_query.get(entity).ok() {
+                Some(child_of) => {
+                    let parent_xform = global_xform_query.get(child_of.parent()).copied().unwrap_or_default();
+                    GlobalTransform::from(world_xform).reparented_to(&parent_xform)
+                }
+                None => world_xform,
+            };
+
+            if let Ok(mut xform) = xform_query.get_mut(entity) {
+                *xform = local_xform;
+            }
+        }
+    }
+
+    // EndEdit before the next BeginEdit, a BeginEdit drops the baselines taken so far
+    if !ready_moves.is_empty() {
+        history_evt_writer.write(EditorHistoryEvent::EndEdit);
+    }
+
+    if !pending_moves.is_empty() {
+        let targets = pending_moves
+            .iter()
+            .flat_map(|(entities, _)| entities.iter().copied())
+            .collect();
+
+        history_evt_writer.write(EditorHistoryEvent::BeginEdit(targets));
+    }
+}
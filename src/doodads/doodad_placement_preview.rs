@@ -2,7 +2,14 @@
 
 
 use bevy::ecs::relationship::DescendantIter;
+use bevy::color::palettes::css::{GREEN, ORANGE, YELLOW};
 use spirit_edit_core::doodads::DoodadToolState;
+use spirit_edit_core::doodads::doodad::DoodadComponent;
+use spirit_edit_core::doodads::doodad_manifest::DoodadDefinitionsResource;
+use spirit_edit_core::doodads::sockets::{
+    doodad_sockets, find_socket_snap, CollectGltfSockets, DoodadSocket, GltfSocketsComponent, SocketSnap,
+};
+use spirit_edit_core::placement::PlacementToolsState;
 use bevy_editor_pls_core::Editor;
 use bevy::gltf::Gltf;
 use bevy::log::tracing_subscriber::filter::FilterFn;
@@ -26,10 +33,13 @@ pub fn doodad_placement_plugin(app: &mut App){
           app
 
              .add_observer( apply_ghostly_material )
+
+            .init_resource::<DoodadSocketSnapResource>()
             
             .add_systems(Startup, spawn_doodad_placement_component)
             .add_systems(Update, update_doodad_placement_preview_position)
              .add_systems(Update, update_doodad_placement_preview_state)
+             .add_systems(Update, draw_doodad_socket_gizmos.after(update_doodad_placement_preview_position))
              // .add_systems(Update, update_doodad_placement_preview_model)
              
             // .add_systems(Update, doodad_placement_preview::update_doodad_placement_preview)
@@ -56,13 +66,25 @@ pub struct DoodadPlacementComponent {
 
 }
 
+// where the preview snapped to this frame, placing a doodad uses this transform
+#[derive(Resource, Default)]
+pub struct DoodadSocketSnapResource {
+    pub snap: Option<SocketSnap>,
+}
+
+// only doodads this close to the cursor are searched for sockets
+const SOCKET_SEARCH_RADIUS: f32 = 25.0;
+
+const SOCKET_GIZMO_RADIUS: f32 = 0.15;
+
 
 pub fn spawn_doodad_placement_component(
 	mut commands: Commands
 	){
 
 	commands.spawn((Transform::default(),Visibility::default()))
-	.insert(DoodadPlacementComponent::default());
+	.insert(DoodadPlacementComponent::default())
+	.insert(CollectGltfSockets);
 
 }
 
@@ -85,7 +107,13 @@ pub fn update_doodad_placement_preview_position (
       doodad_placement_component_query: Query<Entity, With<DoodadPlacementComponent>>,
     mut doodad_placement_transform_query: Query<&mut Transform, With<DoodadPlacementComponent>>,
 
-      parent_query: Query<&ChildOf >
+      parent_query: Query<&ChildOf >,
+
+    preview_sockets_query: Query<(&DoodadPlacementComponent, Option<&GltfSocketsComponent>)>,
+    doodad_sockets_query: Query<(Entity, &GlobalTransform, &DoodadComponent, Option<&GltfSocketsComponent>)>,
+    doodad_definitions_resource: Res<DoodadDefinitionsResource>,
+    placement_tools_state: Res<PlacementToolsState>,
+    mut socket_snap_resource: ResMut<DoodadSocketSnapResource>,
 
 ) {
     //we can tell if we are clicking in viewport
@@ -148,10 +176,33 @@ pub fn update_doodad_placement_preview_position (
            // let custom_props = None; 
 
          
-         	if let Some( mut xform ) = doodad_placement_transform_query.single_mut().ok(){
+         	let placement = Transform::from_translation(hit_coordinates);
+
+         	socket_snap_resource.snap = match placement_tools_state.socket_snapping {
+         		true => find_preview_socket_snap(
+         			&placement,
+         			&preview_sockets_query,
+         			&doodad_sockets_query,
+         			&doodad_definitions_resource,
+         			placement_tools_state.socket_snap_distance,
+         		),
+         		false => None,
+         	};
 
+         	if let Some( mut xform ) = doodad_placement_transform_query.single_mut().ok(){
 
-         		xform.translation =  hit_coordinates.clone() ;
+         		match &socket_snap_resource.snap {
+         			Some(snap) => {
+         				xform.translation = snap.transform.translation;
+         				xform.rotation = snap.transform.rotation;
+         			}
+         			None => {
+         				xform.translation =  hit_coordinates.clone() ;
+         				xform.rotation = placement_tools_state
+         					.surface_rotation(Some(intersection_data.normal))
+         					.unwrap_or(Quat::IDENTITY);
+         			}
+         		}
 
          	}
         }
@@ -162,6 +213,104 @@ pub fn update_doodad_placement_preview_position (
 
 
 
+fn find_preview_socket_snap(
+    placement: &Transform,
+    preview_sockets_query: &Query<(&DoodadPlacementComponent, Option<&GltfSocketsComponent>)>,
+    doodad_sockets_query: &Query<(Entity, &GlobalTransform, &DoodadComponent, Option<&GltfSocketsComponent>)>,
+    doodad_definitions_resource: &DoodadDefinitionsResource,
+    snap_distance: f32,
+) -> Option<SocketSnap> {
+    let (placement_comp, preview_gltf_sockets) = preview_sockets_query.single().ok()?;
+
+    let preview_definition = placement_comp
+        .preview_doodad_name
+        .as_ref()
+        .and_then(|doodad_name| doodad_definitions_resource.get_doodad_definition_by_name(doodad_name))?;
+
+    let preview_sockets = doodad_sockets(Some(preview_definition), preview_gltf_sockets);
+    if preview_sockets.is_empty() {
+        return None;
+    }
+
+    let target_sockets = nearby_doodad_sockets(placement.translation, doodad_sockets_query);
+
+    find_socket_snap(placement, &preview_sockets, &target_sockets, snap_distance)
+}
+
+fn nearby_doodad_sockets(
+    center: Vec3,
+    doodad_sockets_query: &Query<(Entity, &GlobalTransform, &DoodadComponent, Option<&GltfSocketsComponent>)>,
+) -> Vec<(Entity, GlobalTransform, DoodadSocket)> {
+    let mut target_sockets = Vec::new();
+
+    for (doodad_entity, doodad_xform, doodad_comp, gltf_sockets) in doodad_sockets_query.iter() {
+        if doodad_xform.translation().distance(center) > SOCKET_SEARCH_RADIUS {
+            continue;
+        }
+
+        for socket in doodad_sockets(Some(&doodad_comp.definition), gltf_sockets) {
+            target_sockets.push((doodad_entity, *doodad_xform, socket));
+        }
+    }
+
+    target_sockets
+}
+
+// sockets of the doodads around the preview, the ones the preview can attach to are highlighted
+fn draw_doodad_socket_gizmos(
+    mut gizmos: Gizmos,
+
+    doodad_tool_resource: Res<DoodadToolState>,
+    placement_tools_state: Res<PlacementToolsState>,
+    socket_snap_resource: Res<DoodadSocketSnapResource>,
+    doodad_definitions_resource: Res<DoodadDefinitionsResource>,
+
+    preview_query: Query<(&GlobalTransform, &DoodadPlacementComponent, Option<&GltfSocketsComponent>)>,
+    doodad_sockets_query: Query<(Entity, &GlobalTransform, &DoodadComponent, Option<&GltfSocketsComponent>)>,
+) {
+    if !placement_tools_state.show_sockets || doodad_tool_resource.selected.is_none() {
+        return;
+    }
+
+    let Some((preview_xform, placement_comp, preview_gltf_sockets)) = preview_query.single().ok() else {
+        return;
+    };
+
+    let preview_definition = placement_comp
+        .preview_doodad_name
+        .as_ref()
+        .and_then(|doodad_name| doodad_definitions_resource.get_doodad_definition_by_name(doodad_name));
+
+    let preview_sockets = doodad_sockets(preview_definition, preview_gltf_sockets);
+
+    for socket in preview_sockets.iter() {
+        draw_socket_gizmo(&mut gizmos, preview_xform, socket, YELLOW.into());
+    }
+
+    for (_doodad_entity, doodad_xform, socket) in nearby_doodad_sockets(preview_xform.translation(), &doodad_sockets_query) {
+        let compatible = preview_sockets.iter().any(|preview_socket| preview_socket.can_attach_to(&socket));
+
+        let color: Color = match compatible {
+            true => ORANGE.into(),
+            false => Color::srgba(0.6, 0.6, 0.6, 0.5),
+        };
+
+        draw_socket_gizmo(&mut gizmos, &doodad_xform, &socket, color);
+    }
+
+    if let Some(snap) = &socket_snap_resource.snap {
+        gizmos.sphere(Isometry3d::from_translation(snap.target_socket_position), SOCKET_GIZMO_RADIUS * 2.0, GREEN);
+    }
+}
+
+fn draw_socket_gizmo(gizmos: &mut Gizmos, doodad_xform: &GlobalTransform, socket: &DoodadSocket, color: Color) {
+    let socket_xform = doodad_xform.mul_transform(socket.local_transform());
+    let position = socket_xform.translation();
+
+    gizmos.sphere(Isometry3d::from_translation(position), SOCKET_GIZMO_RADIUS, color);
+    gizmos.arrow(position, position + socket_xform.forward() * 0.75, color);
+}
+
 pub fn update_doodad_placement_preview_state (
   
 
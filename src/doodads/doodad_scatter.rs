@@ -0,0 +1,328 @@
+use bevy::picking::backend::ray::RayMap;
+use bevy::prelude::*;
+
+use bevy_regions::regions::RegionsDataMapResource;
+use bevy_regions::regions_config::RegionsConfig;
+use degen_toon_terrain::chunk::{Chunk, TerrainChunkMesh};
+use degen_toon_terrain::erosion::chunk_dimensions_of;
+use degen_toon_terrain::splat::ChunkSplatDataRaw;
+use degen_toon_terrain::terrain_config::TerrainConfig;
+
+use spirit_edit_core::doodads::doodad::DoodadComponent;
+use spirit_edit_core::doodads::doodad_manifest::DoodadTagMapResource;
+use spirit_edit_core::doodads::{DoodadProto, PlaceDoodadEvent};
+use spirit_edit_core::history::EditorHistoryEvent;
+use spirit_edit_core::placement::scatter::{pick_weighted_doodad, poisson_disk_points, ScatterSurfaceSample};
+use spirit_edit_core::placement::{PlacementResource, PlacementToolsState};
+
+use crate::editor_pls::bevy_pls_editor_is_active;
+use crate::editor_state::EditorStateResource;
+use crate::ui::{EditorToolsState, SubTool, ToolMode};
+
+/*
+
+Doodad scatter and erase brushes.
+
+Candidates are spread over the brush with poisson-disk spacing, dropped onto the terrain
+and filtered by the scatter rules in PlacementToolsState. Survivors are placed into the
+current placement parent as ordinary doodads.
+
+Both brushes place or erase every frame while the button is held. The stroke is bracketed
+with BeginStroke / EndStroke, so the history folds it into one undo entry.
+
+*/
+
+// candidate rays start this far above the brush hit
+const SCATTER_RAY_HEIGHT: f32 = 500.0;
+
+pub fn doodad_scatter_plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        (update_doodad_scatter_brush, update_doodad_erase_brush)
+            .chain()
+            // the doodads placed last frame are recorded before a released stroke ends
+            .after(crate::doodads::doodad::handle_place_doodad_events)
+            .run_if(not(bevy_pls_editor_is_active)),
+    );
+}
+
+fn brush_hit_point(ray_map: &RayMap, raycast: &mut MeshRayCast, terrain_mesh_query: &Query<(), With<TerrainChunkMesh>>) -> Option<Vec3> {
+    let terrain_filter = |entity: Entity| terrain_mesh_query.contains(entity);
+
+    let raycast_settings = MeshRayCastSettings {
+        filter: &terrain_filter,
+        ..default()
+    };
+
+    ray_map
+        .iter()
+        .find_map(|(_, cursor_ray)| raycast.cast_ray(*cursor_ray, &raycast_settings).first().map(|(_, hit)| hit.point))
+}
+
+fn brush_is_painting(
+    editor_tools_state: &EditorToolsState,
+    sub_tool: SubTool,
+    mouse_input: &ButtonInput<MouseButton>,
+    editor_state_resource: &EditorStateResource,
+) -> bool {
+    editor_tools_state.tool_mode == ToolMode::Doodads
+        && editor_tools_state.sub_tool == Some(sub_tool)
+        && mouse_input.pressed(MouseButton::Left)
+        && !editor_state_resource.cursor_overlaps_gui
+}
+
+// everything the brush records between pressing and releasing the button becomes one undo entry
+fn track_brush_stroke(
+    painting: bool,
+    stroke_active: &mut bool,
+    history_event_writer: &mut MessageWriter<EditorHistoryEvent>,
+) {
+    if painting && !*stroke_active {
+        history_event_writer.write(EditorHistoryEvent::BeginStroke);
+        *stroke_active = true;
+    }
+
+    if !painting && *stroke_active {
+        history_event_writer.write(EditorHistoryEvent::EndStroke);
+        *stroke_active = false;
+    }
+}
+
+fn update_doodad_scatter_brush(
+    mut history_event_writer: MessageWriter<EditorHistoryEvent>,
+
+    mouse_input: Res<ButtonInput<MouseButton>>,
+
+    ray_map: Res<RayMap>,
+    mut raycast: MeshRayCast,
+    terrain_mesh_query: Query<(), With<TerrainChunkMesh>>,
+
+    editor_tools_state: Res<EditorToolsState>,
+    editor_state_resource: Res<EditorStateResource>,
+    placement_tools_state: Res<PlacementToolsState>,
+    placement_resource: Res<PlacementResource>,
+    doodad_tag_map: Res<DoodadTagMapResource>,
+
+    doodad_query: Query<(&Name, &GlobalTransform), Or<(With<DoodadComponent>, With<DoodadProto>)>>,
+
+    regions_data: Res<RegionsDataMapResource>,
+    regions_config_query: Query<&RegionsConfig>,
+    terrain_config_query: Query<&TerrainConfig>,
+    chunk_splat_query: Query<(&GlobalTransform, &ChunkSplatDataRaw), With<Chunk>>,
+
+    mut place_doodad_event_writer: MessageWriter<PlaceDoodadEvent>,
+
+    // doodads placed this stroke, they only show up in the doodad query a frame later
+    mut stroke_points: Local<Vec<Vec2>>,
+    mut stroke_active: Local<bool>,
+) {
+    let painting = brush_is_painting(&editor_tools_state, SubTool::DoodadScatter, &mouse_input, &editor_state_resource);
+    track_brush_stroke(painting, &mut stroke_active, &mut history_event_writer);
+
+    if !painting {
+        stroke_points.clear();
+        return;
+    }
+
+    let Some(placement_parent) = placement_resource.placement_parent else {
+        if mouse_input.just_pressed(MouseButton::Left) {
+            warn!("doodad scatter needs a placement parent zone");
+        }
+        return;
+    };
+
+    let rules = &placement_tools_state.scatter;
+
+    let doodads = rules.source.resolve(&doodad_tag_map);
+    if doodads.is_empty() {
+        return;
+    }
+
+    let Some(hit_point) = brush_hit_point(&ray_map, &mut raycast, &terrain_mesh_query) else {
+        return;
+    };
+
+    let center = hit_point.xz();
+    let radius = editor_tools_state.brush_radius as f32;
+
+    let mut existing_points: Vec<Vec2> = stroke_points.clone();
+    let mut source_doodads_in_brush = stroke_points.iter().filter(|point| point.distance(center) <= radius).count();
+
+    for (name, xform) in doodad_query.iter() {
+        let point = xform.translation().xz();
+
+        if point.distance(center) > radius + rules.spacing {
+            continue;
+        }
+
+        existing_points.push(point);
+
+        if point.distance(center) <= radius && doodads.iter().any(|doodad| doodad.doodad_name == name.as_str()) {
+            source_doodads_in_brush += 1;
+        }
+    }
+
+    let wanted = rules.target_count(radius).saturating_sub(source_doodads_in_brush);
+    if wanted == 0 {
+        return;
+    }
+
+    let mut rng = rand::thread_rng();
+
+    let candidates = poisson_disk_points(center, radius, rules.spacing, wanted, &existing_points, &mut rng);
+
+    let terrain_filter = |entity: Entity| terrain_mesh_query.contains(entity);
+    let raycast_settings = MeshRayCastSettings {
+        filter: &terrain_filter,
+        ..default()
+    };
+
+    let regions_config = regions_config_query.iter().next();
+    let chunk_dimensions = terrain_config_query.iter().next().map(chunk_dimensions_of);
+
+    for candidate in candidates {
+        let ray = Ray3d::new(Vec3::new(candidate.x, hit_point.y + SCATTER_RAY_HEIGHT, candidate.y), Dir3::NEG_Y);
+
+        let Some((_, hit)) = raycast.cast_ray(ray, &raycast_settings).first() else {
+            continue;
+        };
+
+        let sample = ScatterSurfaceSample {
+            point: hit.point,
+            normal: hit.normal,
+            region: regions_config.and_then(|config| sample_region(&regions_data, config, candidate)),
+            splat_texture: chunk_dimensions.and_then(|dimensions| sample_splat_texture(&chunk_splat_query, dimensions, candidate)),
+        };
+
+        if !rules.accepts(&sample) {
+            continue;
+        }
+
+        let Some(doodad_name) = pick_weighted_doodad(&doodads, &mut rng) else {
+            continue;
+        };
+
+        place_doodad_event_writer.write(PlaceDoodadEvent {
+            position: hit.point,
+            scale: rules.scale(&mut rng),
+            rotation_euler: rules.rotation_euler(hit.normal, &mut rng),
+            doodad_name: doodad_name.clone(),
+            custom_props: None,
+            force_parent: Some(placement_parent),
+            auto_select: false,
+            zone_entity_id: None,
+            record_history: true,
+        });
+
+        stroke_points.push(candidate);
+    }
+}
+
+fn update_doodad_erase_brush(
+    mut history_event_writer: MessageWriter<EditorHistoryEvent>,
+
+    mouse_input: Res<ButtonInput<MouseButton>>,
+
+    ray_map: Res<RayMap>,
+    mut raycast: MeshRayCast,
+    terrain_mesh_query: Query<(), With<TerrainChunkMesh>>,
+
+    editor_tools_state: Res<EditorToolsState>,
+    editor_state_resource: Res<EditorStateResource>,
+    placement_tools_state: Res<PlacementToolsState>,
+    doodad_tag_map: Res<DoodadTagMapResource>,
+
+    doodad_query: Query<(Entity, &Name, &GlobalTransform), Or<(With<DoodadComponent>, With<DoodadProto>)>>,
+
+    mut stroke_active: Local<bool>,
+) {
+    let painting = brush_is_painting(&editor_tools_state, SubTool::DoodadErase, &mouse_input, &editor_state_resource);
+    track_brush_stroke(painting, &mut stroke_active, &mut history_event_writer);
+
+    if !painting {
+        return;
+    }
+
+    let erase_names = placement_tools_state.scatter.erase_doodad_names(&doodad_tag_map);
+    if erase_names.is_empty() {
+        return;
+    }
+
+    let Some(hit_point) = brush_hit_point(&ray_map, &mut raycast, &terrain_mesh_query) else {
+        return;
+    };
+
+    let center = hit_point.xz();
+    let radius = editor_tools_state.brush_radius as f32;
+
+    let mut erased = Vec::new();
+
+    for (doodad_entity, name, xform) in doodad_query.iter() {
+        if xform.translation().xz().distance(center) > radius {
+            continue;
+        }
+
+        if !erase_names.iter().any(|erase_name| erase_name == name.as_str()) {
+            continue;
+        }
+
+        erased.push(doodad_entity);
+    }
+
+    // deleted through the history so the erased doodads can be brought back
+    if !erased.is_empty() {
+        history_event_writer.write(EditorHistoryEvent::DeleteEntities(erased));
+    }
+}
+
+// the region map covers the region boundary dimensions starting at the world origin
+fn sample_region(regions_data: &RegionsDataMapResource, regions_config: &RegionsConfig, point: Vec2) -> Option<u8> {
+    let region_map = regions_data.regions_data_map.as_ref()?;
+
+    let rows = region_map.len();
+    let columns = region_map.first()?.len();
+
+    let normalized = point / regions_config.boundary_dimensions;
+    if normalized.x < 0.0 || normalized.y < 0.0 {
+        return None;
+    }
+
+    let x = (normalized.x * columns as f32) as usize;
+    let y = (normalized.y * rows as f32) as usize;
+
+    region_map.get(y)?.get(x).copied()
+}
+
+// splat pixels hold the primary texture, the secondary texture and the secondary strength
+fn sample_splat_texture(
+    chunk_splat_query: &Query<(&GlobalTransform, &ChunkSplatDataRaw), With<Chunk>>,
+    chunk_dimensions: UVec2,
+    point: Vec2,
+) -> Option<u8> {
+    let chunk_size = chunk_dimensions.as_vec2();
+
+    let (chunk_xform, splat_data) = chunk_splat_query.iter().find(|(chunk_xform, _)| {
+        let local = point - chunk_xform.translation().xz();
+        local.x >= 0.0 && local.y >= 0.0 && local.x < chunk_size.x && local.y < chunk_size.y
+    })?;
+
+    let texture = &splat_data.splat_map_texture;
+    let width = texture.width();
+    let height = texture.height();
+
+    let local = (point - chunk_xform.translation().xz()) / chunk_size;
+    let x = ((local.x * width as f32) as u32).min(width.saturating_sub(1));
+    let y = ((local.y * height as f32) as u32).min(height.saturating_sub(1));
+
+    let pixel_offset = ((y * width + x) * 4) as usize;
+    let data = texture.data.as_ref()?;
+
+    let primary = *data.get(pixel_offset)?;
+    let secondary = *data.get(pixel_offset + 1)?;
+    let secondary_strength = *data.get(pixel_offset + 2)?;
+
+    match secondary_strength >= 128 {
+        true => Some(secondary),
+        false => Some(primary),
+    }
+}
@@ -6,6 +6,7 @@ pub (crate) mod doodad_placement_preview;
 pub(crate) mod prefabs;
 pub(crate) mod doodad_placement ;
 pub(crate) mod doodad_colliders; 
+pub(crate) mod doodad_scatter;
 
 
 
@@ -17,6 +18,7 @@ pub(crate) fn doodads_plugin(app: &mut App) {
         	  .add_plugins(doodad::doodad_plugin)
         .add_plugins(doodad_placement_preview::doodad_placement_plugin  )
             .add_plugins(doodad_placement::doodad_placement_plugin)
+            .add_plugins(doodad_scatter::doodad_scatter_plugin)
         .add_plugins(prefabs::prefabs_plugin )    
 
 
@@ -4,14 +4,16 @@ use spirit_edit_core::doodads::PlaceDoodadEvent;
 use spirit_edit_core::placement::PlacementToolsState;
 use spirit_edit_core::prefabs::prefab_definitions;
 use spirit_edit_core::prefabs::prefab_definitions::PrefabDefinitionsResource;
-use spirit_edit_core::zones::zone_file::ZoneEntityV2;
+use spirit_edit_core::zones::zone_file::{ZoneEntityId, ZoneEntityV2};
 use crate::doodads::doodad_placement::RequestPlaceDoodad;
 use spirit_edit_core::prefabs::PrefabToolState;
 use spirit_edit_core::prefabs::PrefabToolEvent;
 use spirit_edit_core::placement::PlacementResource;
 use bevy::prelude::*;
-use spirit_edit_core::prefabs::{PrefabComponent, SpawnPrefabEvent};
+use spirit_edit_core::prefabs::{PrefabComponent, RebuildPrefabChildren, SpawnPrefabEvent};
+use spirit_edit_core::prefabs::prefab_overrides::{instance_child_id, PrefabOverridesComponent, MAX_PREFAB_NESTING_DEPTH};
 use spirit_edit_core::doodads::DoodadToolState;
+use spirit_edit_core::history::EditorHistoryEvent;
 
 
 
@@ -59,11 +61,14 @@ fn spawn_children_for_new_prefabs (
 
     prefab_definitions: Res<PrefabDefinitionsResource>,
 
-    added_prefabs_query: Query<(Entity, &Name ), Added<PrefabComponent>>,
+    added_prefabs_query: Query<(Entity, &Name, Option<&ZoneEntityId>, Option<&PrefabOverridesComponent> ), Or<(Added<PrefabComponent>, With<RebuildPrefabChildren>)>>,
 
+    parent_query: Query<&ChildOf>,
+    prefab_name_query: Query<&Name, With<PrefabComponent>>,
 
 
      mut place_doodad_evt_writer: MessageWriter<PlaceDoodadEvent>,
+     mut spawn_prefab_evt_writer: MessageWriter<SpawnPrefabEvent>,
 
 
 ){
@@ -71,69 +76,102 @@ fn spawn_children_for_new_prefabs (
 
 
 
-    for (prefab_root_entity, prefab_name) in added_prefabs_query.iter() {
+    for (prefab_root_entity, prefab_name, instance_id, prefab_overrides) in added_prefabs_query.iter() {
 
 
        // let prefab_def_name = prefab_name.to_string(); 
 
 
-       commands.entity(prefab_root_entity).despawn_related::<Children>(); 
+       commands.entity(prefab_root_entity)
+            .remove::<RebuildPrefabChildren>()
+            .despawn_related::<Children>(); 
 
 
-        if let Some( prefab_def  ) = prefab_definitions.get_prefab_definition_by_name( &prefab_name.to_string()  ) {
+        let Some( prefab_def  ) = prefab_definitions.get_prefab_definition_by_name( &prefab_name.to_string()  ) else {
 
-            for prefab_entity_def in &prefab_def.entities {
-
-                match prefab_entity_def {
-
-                    ZoneEntityV2::Doodad { name, transform, custom_props } => {
+            warn!("no prefab def found:  {}",&prefab_name.to_string()  );
+            continue;
+        };
+
+
+        // the prefab names from this instance up through the prefabs it is nested in
+        let ancestor_prefab_names: Vec<String> = parent_query
+            .iter_ancestors( prefab_root_entity )
+            .filter_map(|ancestor| prefab_name_query.get(ancestor).ok())
+            .map(|name| name.to_string())
+            .chain( std::iter::once( prefab_name.to_string() ) )
+            .collect();
+
+
+        // the children get ids from the instance id, so the instance needs one before they spawn
+        let instance_id = match instance_id {
+            Some(instance_id) => *instance_id,
+            None => {
+                let instance_id = ZoneEntityId::new_random();
+                commands.entity(prefab_root_entity).insert(instance_id);
+                instance_id
+            }
+        };
 
 
+        let prefab_entities = match prefab_overrides {
+            Some(prefab_overrides) => prefab_overrides.0.apply_to_entities( &prefab_def.entities ),
+            None => prefab_def.entities.clone(),
+        };
 
 
+        for prefab_entity_def in prefab_entities {
 
-                        //    global_xform_query: Query<&GlobalTransform>, 
-                          // if there is a parent, the relative position will be offset by the parent global translation 
+            match prefab_entity_def {
 
+                ZoneEntityV2::Doodad { id, name, transform, custom_props } => {
 
-                          //  let parent_global_translation = parent.map( |p| global_xform_query.get(*p).ok()  ).flatten().map(|x| x.translation() ) ; 
-                         //   let position_relative_to_parent = position - parent_global_translation.unwrap_or_default() ; ;
 
+                    //the id maps back to the one in the prefab file so overrides can be matched to it
+                    place_doodad_evt_writer.write(
+                        PlaceDoodadEvent { 
+                            position: transform.translation, 
+                            scale: Some(transform.scale), 
+                            rotation_euler: Some(transform.rotation), 
+                            doodad_name: name , 
+                            custom_props, 
+                            force_parent: Some(  prefab_root_entity   ) ,
+                             auto_select: false ,
+                             zone_entity_id: Some( instance_child_id(instance_id, id) ),
+                             record_history: false,
+                      },
 
+                     );
 
 
-                        let position = &transform.translation;
-                        let scale = &transform.scale;
-                        let rotation_euler = &transform.rotation;
-                        
-                        place_doodad_evt_writer.write(
-                            PlaceDoodadEvent { 
-                                position: position.clone(), 
-                                scale: Some(scale.clone()), 
-                                rotation_euler: Some(rotation_euler.clone()), 
-                                doodad_name: name.clone() , 
-                                custom_props: custom_props.clone(), 
-                                force_parent: Some(  prefab_root_entity   ) ,
-                                 auto_select: false ,
-                          },
 
-                         );
+                }
 
+                ZoneEntityV2::Prefab { id, name, transform, overrides } => {
 
+                    if ancestor_prefab_names.contains( &name ) || ancestor_prefab_names.len() >= MAX_PREFAB_NESTING_DEPTH {
 
+                        warn!("not spawning nested prefab {} in {}: prefabs cannot contain themselves", name, prefab_name);
+                        continue;
                     }
 
-                    _ => {}
+                    spawn_prefab_evt_writer.write(
+                        SpawnPrefabEvent {
+                            position: transform.translation,
+                            rotation_euler: Some(transform.rotation),
+                            prefab_name: name,
+                            zone: Some( prefab_root_entity ),
+                            zone_entity_id: Some( instance_child_id(instance_id, id) ),
+                            overrides,
+                            record_history: false,
+                        }
+                    );
+
                 }
 
+                _ => {}
             }
 
-
-        }else {
-
-
-            warn!("no prefab def found:  {}",&prefab_name.to_string()  );
-
         }
 
 
@@ -155,12 +193,15 @@ fn handle_spawn_prefab_events(
 	mut commands: Commands,
 
     mut evt_reader: MessageReader<SpawnPrefabEvent>,
+    mut history_event_writer: MessageWriter<EditorHistoryEvent>,
 
       placement_resource: Res<PlacementResource>,
 
 
 ){
 
+    let mut recorded_spawns = Vec::new();
+
 	for evt in evt_reader.read(){
 
 
@@ -192,6 +233,13 @@ fn handle_spawn_prefab_events(
             .insert( PrefabComponent )
             .id();
 
+        // every instance has an id, its children get theirs from it
+        commands.entity(prefab_spawned).insert( evt.zone_entity_id.unwrap_or_default() );
+
+        if let Some(overrides) = &evt.overrides {
+            commands.entity(prefab_spawned).insert( PrefabOverridesComponent( overrides.clone() ) );
+        }
+
 
              let mut parent = None ;
 
@@ -205,11 +253,18 @@ fn handle_spawn_prefab_events(
             commands.entity( prefab_spawned ).set_parent( * parent );
          }
 
-
+         // the children are spawned from the prefab file, only the prefab itself is recorded
+         if evt.record_history {
+            recorded_spawns.push( prefab_spawned );
+         }
 
 
 	}
 
+    if !recorded_spawns.is_empty() {
+        history_event_writer.write( EditorHistoryEvent::RecordSpawned( recorded_spawns ) );
+    }
+
 
 }
 
@@ -287,6 +342,9 @@ pub fn handle_place_prefabs(
              //   scale,
              //   custom_props,
                 zone: None,
+                zone_entity_id: None,
+                overrides: None,
+                record_history: true,
                 //clay_tile_block_data : None ,
       
 
@@ -1,6 +1,7 @@
 
 use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
+use spirit_edit_core::placement::surface::SurfaceAlignMode;
 
  
 use bevy::platform::collections::hash_map::HashMap;
@@ -141,4 +142,8 @@ pub fn load_editor_config (   world: &mut World  ) {
 pub struct EditorConfigPlacementSettings{
 
 	pub translation_grid_lock_step: Option<Vec3>,
+
+	pub surface_align_mode: Option<SurfaceAlignMode>,
+	pub max_surface_tilt_degrees: Option<f32>,
+	pub nudge_out_of_colliders: Option<bool>,
 }
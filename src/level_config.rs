@@ -2,6 +2,7 @@
 
 use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
+use spirit_edit_core::zones::zone_streaming::ZoneStreamingConfig;
 
 
 
@@ -13,6 +14,9 @@ pub struct LevelConfig {
 
 	initial_zones_to_load: Option<Vec<String>>,
 
+	// when set, the initial zones are streamed in around the camera instead of all loading at startup
+	zone_streaming: Option<ZoneStreamingConfig>,
+
 	initial_foliage_scene_to_load: Option<String>, 
 
 
@@ -50,6 +54,11 @@ impl LevelConfig{
 		 )
 	}
 
+	pub fn get_zone_streaming_config(&self) -> Option<ZoneStreamingConfig> {
+
+		return self.zone_streaming.clone()
+	}
+
 	pub fn get_foliage_scene_name(&self) -> Option<String> {
 
 
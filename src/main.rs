@@ -18,6 +18,8 @@ mod camera;
 mod commands;
 mod editor_pls;
 mod tools;
+mod brushes;
+mod paint_history;
 mod ui;
 mod asset_loading;
 mod liquid;
@@ -98,6 +100,7 @@ use bevy::{
 
         use spirit_edit_core::SpiritEditCorePlugin;
 use spirit_edit_core::zones::ZoneEvent; 
+use spirit_edit_core::zones::zone_streaming::{ZoneStreamingEvent, ZoneStreamingResource};
 use asset_loading::AssetLoadState;
 use bevy::core_pipeline::tonemapping::Tonemapping;
 use bevy_editor_pls_default_windows::lighting::Sun;
@@ -337,12 +340,14 @@ fn main() {
       
         .add_plugins(doodads::doodads_plugin )
          .add_plugins(terrain::terrain_plugin) 
+        .add_plugins(paint_history::paint_history_plugin)
         
         .add_plugins(asset_loading_plugin)
 
         //.add_plugins(material_overrides::material_overrides_plugin)
         .add_plugins(liquid_plugin)
         .add_plugins(brush_tools_plugin)
+        .add_plugins(brushes::brushes_plugin)
         .add_plugins(editor_ui_plugin)
         .add_plugins(camera_plugin)
        // .add_systems(Startup, set_window_icon)
@@ -554,6 +559,10 @@ fn load_all_zones(
 
    mut zone_event_writer: MessageWriter<ZoneEvent>,
 
+   mut zone_streaming_event_writer: MessageWriter<ZoneStreamingEvent>,
+
+   mut zone_streaming: ResMut<ZoneStreamingResource>,
+
    editor_config : Res<EditorConfig >,
 
     level_assets : Res<LevelAssets >,
@@ -575,8 +584,18 @@ fn load_all_zones(
         .map(|h| level_config_assets.get(h)  )  .flatten() {
 
               
+            let zone_names = level_config.get_initial_zones_to_load().unwrap_or(Vec::new());
+
+            if let Some(zone_streaming_config) = level_config.get_zone_streaming_config() {
+
+              zone_streaming.config = zone_streaming_config;
+              zone_streaming_event_writer.write( ZoneStreamingEvent::StreamZones(zone_names) );
+
+              return;
+            }
+
             //initialize zones 
-            for zone_name in level_config.get_initial_zones_to_load().unwrap_or(Vec::new()) {
+            for zone_name in zone_names {
          
               zone_event_writer.write(   ZoneEvent::LoadZoneFile(zone_name)  );
          
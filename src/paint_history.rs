@@ -0,0 +1,965 @@
+use std::collections::VecDeque;
+
+use bevy::ecs::system::SystemParam;
+use bevy::platform::collections::hash_map::HashMap;
+use bevy::prelude::*;
+
+use crate::editor_pls::bevy_pls_editor_is_active;
+
+use degen_toon_terrain::chunk::{Chunk, ChunkData, ChunkHeightMapResource};
+use degen_toon_terrain::edit::{
+    BrushType as TerrainBrushType, EditTerrainEvent, EditingTool as TerrainEditingTool,
+    TerrainDataReplacedEvent,
+};
+use degen_toon_terrain::holes::ChunkHoleMaskResource;
+use degen_toon_terrain::splat::{ChunkSplatDataRaw, SplatMapDataUpdated};
+use degen_toon_terrain::terrain::TerrainImageDataLoadStatus;
+use degen_toon_terrain::terrain_config::TerrainConfig;
+
+use bevy_regions::edit::{BrushType as RegionsBrushType, EditRegionEvent};
+use bevy_regions::regions::{RegionDataEvent, RegionsDataMapResource};
+
+use bevy_foliage_tool::edit::{
+    BrushType as FoliageBrushType, EditFoliageEvent, EditingTool as FoliageEditingTool,
+};
+use bevy_foliage_tool::foliage_density::FoliageDensityMapsComponent;
+
+/*
+
+Stroke based undo / redo for everything painted with the brush tools.
+
+While a stroke is active, every tile a brush is about to touch is snapshotted once.
+When the mouse is released the snapshots are diffed against the painted result and
+only the changed tiles are kept, as a zero-run-length encoded XOR delta.
+
+An XOR delta is its own inverse, so the same delta is replayed for both undo and redo.
+That only holds while nothing else rewrites the maps, so the history is cleared whenever
+terrain data is replaced in bulk (TerrainDataReplacedEvent). A stroke whose chunks are
+streamed out is not replayed at all and stays on its stack until they are back.
+
+*/
+
+// edge length (in texels) of a history tile
+const TILE_SIZE: u32 = 32;
+
+const DEFAULT_MEMORY_BUDGET_BYTES: usize = 64 * 1024 * 1024;
+
+pub fn paint_history_plugin(app: &mut App) {
+    app
+        .init_resource::<PaintHistoryResource>()
+
+        .add_systems(
+            Update,
+            capture_paint_tiles
+                .before(degen_toon_terrain::edit::apply_tool_edits)
+                .before(degen_toon_terrain::erosion::apply_erosion_brush_edits)
+                .before(degen_toon_terrain::holes::apply_hole_brush_edits)
+                .before(bevy_regions::edit::apply_tool_edits)
+                .before(bevy_foliage_tool::edit::apply_tool_edits),
+        )
+
+        .add_systems(
+            Update,
+            (
+                finish_paint_stroke,
+                clear_replaced_paint_history,
+                // scene entity undo owns these shortcuts while the editor is open
+                update_paint_history_commands.run_if(not(bevy_pls_editor_is_active)),
+            )
+                .chain()
+                .after(degen_toon_terrain::edit::apply_tool_edits)
+                .after(degen_toon_terrain::erosion::apply_erosion_brush_edits)
+                .after(degen_toon_terrain::holes::apply_hole_brush_edits)
+                .after(bevy_regions::edit::apply_tool_edits)
+                .after(bevy_foliage_tool::edit::apply_tool_edits),
+        );
+}
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum PaintLayer {
+    TerrainHeight { chunk_id: u32 },
+    TerrainSplat { chunk_id: u32 },
+    TerrainHoles { chunk_id: u32 },
+    Regions,
+    FoliageDensity { foliage_index: usize },
+}
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+struct TileKey {
+    layer: PaintLayer,
+    tile: UVec2,
+}
+
+struct TileDelta {
+    key: TileKey,
+    // byte length of the tile when it was recorded, a replay needs the same tile
+    tile_len: usize,
+    encoded_xor: Vec<u8>,
+}
+
+pub struct PaintStroke {
+    deltas: Vec<TileDelta>,
+}
+
+impl PaintStroke {
+    pub fn byte_size(&self) -> usize {
+        self.deltas
+            .iter()
+            .map(|delta| delta.encoded_xor.len() + std::mem::size_of::<TileDelta>())
+            .sum()
+    }
+
+    pub fn tile_count(&self) -> usize {
+        self.deltas.len()
+    }
+}
+
+#[derive(Resource)]
+pub struct PaintHistoryResource {
+    undo_stack: VecDeque<PaintStroke>,
+    redo_stack: Vec<PaintStroke>,
+
+    // tile snapshots taken before the brush first touched them during the active stroke
+    pending_tiles: HashMap<TileKey, Vec<u8>>,
+
+    pub memory_budget_bytes: usize,
+    used_bytes: usize,
+}
+
+impl Default for PaintHistoryResource {
+    fn default() -> Self {
+        Self {
+            undo_stack: VecDeque::new(),
+            redo_stack: Vec::new(),
+            pending_tiles: HashMap::new(),
+            memory_budget_bytes: DEFAULT_MEMORY_BUDGET_BYTES,
+            used_bytes: 0,
+        }
+    }
+}
+
+impl PaintHistoryResource {
+    pub fn stroke_in_progress(&self) -> bool {
+        !self.pending_tiles.is_empty()
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    pub fn used_bytes(&self) -> usize {
+        self.used_bytes + self.redo_stack.iter().map(|s| s.byte_size()).sum::<usize>()
+    }
+
+    pub fn clear(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.pending_tiles.clear();
+        self.used_bytes = 0;
+    }
+
+    fn push_stroke(&mut self, stroke: PaintStroke) {
+        self.redo_stack.clear();
+
+        self.used_bytes += stroke.byte_size();
+        self.undo_stack.push_back(stroke);
+
+        // always keep the latest stroke, even if it alone exceeds the budget
+        while self.used_bytes > self.memory_budget_bytes && self.undo_stack.len() > 1 {
+            if let Some(dropped) = self.undo_stack.pop_front() {
+                self.used_bytes -= dropped.byte_size();
+            }
+        }
+    }
+
+    fn pop_undo(&mut self) -> Option<PaintStroke> {
+        let stroke = self.undo_stack.pop_back()?;
+        self.used_bytes -= stroke.byte_size();
+        Some(stroke)
+    }
+
+    fn push_undo(&mut self, stroke: PaintStroke) {
+        self.used_bytes += stroke.byte_size();
+        self.undo_stack.push_back(stroke);
+    }
+}
+
+// ---- tile access ----
+
+trait GridCell: Copy {
+    const BYTES: usize;
+
+    fn write_bytes(&self, out: &mut Vec<u8>);
+    fn xor_bytes(&mut self, bytes: &[u8]);
+}
+
+impl GridCell for u8 {
+    const BYTES: usize = 1;
+
+    fn write_bytes(&self, out: &mut Vec<u8>) {
+        out.push(*self);
+    }
+
+    fn xor_bytes(&mut self, bytes: &[u8]) {
+        *self ^= bytes[0];
+    }
+}
+
+impl GridCell for u16 {
+    const BYTES: usize = 2;
+
+    fn write_bytes(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.to_le_bytes());
+    }
+
+    fn xor_bytes(&mut self, bytes: &[u8]) {
+        *self ^= u16::from_le_bytes([bytes[0], bytes[1]]);
+    }
+}
+
+trait TileGrid {
+    fn grid_size(&self) -> UVec2;
+
+    fn read_tile(&self, tile: UVec2) -> Vec<u8>;
+
+    fn xor_tile(&mut self, tile: UVec2, xor: &[u8]);
+}
+
+fn tile_bounds(tile: UVec2, grid_size: UVec2) -> (UVec2, UVec2) {
+    let start = tile * TILE_SIZE;
+    let end = (start + UVec2::splat(TILE_SIZE)).min(grid_size);
+    (start, end)
+}
+
+impl<T: GridCell> TileGrid for Vec<Vec<T>> {
+    fn grid_size(&self) -> UVec2 {
+        let height = self.len() as u32;
+        let width = self.first().map(|row| row.len()).unwrap_or(0) as u32;
+        UVec2::new(width, height)
+    }
+
+    fn read_tile(&self, tile: UVec2) -> Vec<u8> {
+        let (start, end) = tile_bounds(tile, self.grid_size());
+
+        let mut out = Vec::new();
+        for y in start.y..end.y {
+            for x in start.x..end.x {
+                self[y as usize][x as usize].write_bytes(&mut out);
+            }
+        }
+        out
+    }
+
+    fn xor_tile(&mut self, tile: UVec2, xor: &[u8]) {
+        let (start, end) = tile_bounds(tile, self.grid_size());
+
+        let mut cells = xor.chunks_exact(T::BYTES);
+        for y in start.y..end.y {
+            for x in start.x..end.x {
+                if let Some(bytes) = cells.next() {
+                    self[y as usize][x as usize].xor_bytes(bytes);
+                }
+            }
+        }
+    }
+}
+
+impl TileGrid for Image {
+    fn grid_size(&self) -> UVec2 {
+        UVec2::new(self.width(), self.height())
+    }
+
+    fn read_tile(&self, tile: UVec2) -> Vec<u8> {
+        let size = self.grid_size();
+        let (start, end) = tile_bounds(tile, size);
+
+        let Some(data) = self.data.as_ref() else {
+            return Vec::new();
+        };
+        let pixel_bytes = data.len() / (size.x * size.y).max(1) as usize;
+
+        let mut out = Vec::new();
+        for y in start.y..end.y {
+            let row_start = (y * size.x + start.x) as usize * pixel_bytes;
+            let row_end = (y * size.x + end.x) as usize * pixel_bytes;
+            out.extend_from_slice(&data[row_start..row_end]);
+        }
+        out
+    }
+
+    fn xor_tile(&mut self, tile: UVec2, xor: &[u8]) {
+        let size = self.grid_size();
+        let (start, end) = tile_bounds(tile, size);
+
+        let Some(data) = self.data.as_mut() else {
+            return;
+        };
+        let pixel_bytes = data.len() / (size.x * size.y).max(1) as usize;
+
+        let mut offset = 0;
+        for y in start.y..end.y {
+            let row_start = (y * size.x + start.x) as usize * pixel_bytes;
+            let row_end = (y * size.x + end.x) as usize * pixel_bytes;
+
+            for (byte, delta) in data[row_start..row_end].iter_mut().zip(&xor[offset..]) {
+                *byte ^= delta;
+            }
+            offset += row_end - row_start;
+        }
+    }
+}
+
+// all tiles overlapping the given texel rectangle, clipped to the grid
+fn tiles_in_rect(min: Vec2, max: Vec2, grid_size: UVec2) -> Vec<UVec2> {
+    if grid_size.x == 0 || grid_size.y == 0 {
+        return Vec::new();
+    }
+
+    let max_texel = (grid_size - UVec2::ONE).as_vec2();
+    if max.x < 0.0 || max.y < 0.0 || min.x > max_texel.x || min.y > max_texel.y {
+        return Vec::new();
+    }
+
+    let first = min.clamp(Vec2::ZERO, max_texel).as_uvec2() / TILE_SIZE;
+    let last = max.clamp(Vec2::ZERO, max_texel).as_uvec2() / TILE_SIZE;
+
+    let mut tiles = Vec::new();
+    for y in first.y..=last.y {
+        for x in first.x..=last.x {
+            tiles.push(UVec2::new(x, y));
+        }
+    }
+    tiles
+}
+
+// ---- delta encoding ----
+
+fn write_varint(out: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn read_varint(bytes: &[u8], cursor: &mut usize) -> Option<usize> {
+    let mut value = 0usize;
+    let mut shift = 0;
+
+    loop {
+        let byte = *bytes.get(*cursor)?;
+        *cursor += 1;
+
+        value |= ((byte & 0x7f) as usize) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+        shift += 7;
+    }
+}
+
+/// XORs two tile snapshots and run-length encodes the zero runs.
+/// Returns None when the tile did not change.
+fn encode_tile_delta(before: &[u8], after: &[u8]) -> Option<Vec<u8>> {
+    if before == after {
+        return None;
+    }
+
+    let xor: Vec<u8> = before.iter().zip(after).map(|(a, b)| a ^ b).collect();
+
+    // pairs of [zero run][literal count][literals..]
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < xor.len() {
+        let zero_start = i;
+        while i < xor.len() && xor[i] == 0 {
+            i += 1;
+        }
+        let literal_start = i;
+        while i < xor.len() && xor[i] != 0 {
+            i += 1;
+        }
+
+        write_varint(&mut out, literal_start - zero_start);
+        write_varint(&mut out, i - literal_start);
+        out.extend_from_slice(&xor[literal_start..i]);
+    }
+
+    Some(out)
+}
+
+fn decode_tile_delta(encoded: &[u8], tile_len: usize) -> Vec<u8> {
+    let mut xor = Vec::with_capacity(tile_len);
+    let mut cursor = 0;
+
+    while cursor < encoded.len() {
+        let (Some(zeros), Some(literals)) = (
+            read_varint(encoded, &mut cursor),
+            read_varint(encoded, &mut cursor),
+        ) else {
+            break;
+        };
+
+        xor.resize(xor.len() + zeros, 0);
+
+        let literal_end = (cursor + literals).min(encoded.len());
+        xor.extend_from_slice(&encoded[cursor..literal_end]);
+        cursor = literal_end;
+    }
+
+    xor.resize(tile_len, 0);
+    xor
+}
+
+// ---- systems ----
+
+#[derive(SystemParam)]
+struct PaintMaps<'w, 's> {
+    chunk_height_maps: ResMut<'w, ChunkHeightMapResource>,
+    hole_masks: ResMut<'w, ChunkHoleMaskResource>,
+    regions_data: ResMut<'w, RegionsDataMapResource>,
+
+    chunk_query: Query<
+        'w,
+        's,
+        (
+            Entity,
+            &'static Chunk,
+            &'static mut ChunkData,
+            &'static GlobalTransform,
+            Option<&'static mut ChunkSplatDataRaw>,
+        ),
+    >,
+    foliage_query: Query<'w, 's, &'static mut FoliageDensityMapsComponent>,
+    terrain_query: Query<'w, 's, &'static TerrainConfig>,
+}
+
+impl PaintMaps<'_, '_> {
+    fn read_tile(&self, key: &TileKey) -> Option<Vec<u8>> {
+        match key.layer {
+            PaintLayer::TerrainHeight { chunk_id } => self
+                .chunk_height_maps
+                .chunk_height_maps
+                .get(&chunk_id)
+                .map(|height_map| height_map.read_tile(key.tile)),
+
+            PaintLayer::TerrainSplat { chunk_id } => self
+                .chunk_query
+                .iter()
+                .find(|(_, chunk, ..)| chunk.chunk_id == chunk_id)
+                .and_then(|(.., splat_data)| splat_data)
+                .map(|splat_data| splat_data.splat_map_texture.read_tile(key.tile)),
+
+            // streamed out chunks have no mask to replay into
+            PaintLayer::TerrainHoles { chunk_id }
+                if !self.chunk_height_maps.chunk_height_maps.contains_key(&chunk_id) =>
+            {
+                None
+            }
+
+            // chunks without a mask yet read as solid, the mask is created by the first cut
+            PaintLayer::TerrainHoles { chunk_id } => match self.hole_masks.hole_masks.get(&chunk_id) {
+                Some(hole_mask) => Some(hole_mask.read_tile(key.tile)),
+                None => {
+                    let (start, end) = tile_bounds(key.tile, self.chunk_dimensions().as_uvec2());
+                    let tile_size = end.saturating_sub(start);
+                    Some(vec![0; (tile_size.x * tile_size.y) as usize])
+                }
+            },
+
+            PaintLayer::Regions => self
+                .regions_data
+                .regions_data_map
+                .as_ref()
+                .map(|region_map| region_map.read_tile(key.tile)),
+
+            PaintLayer::FoliageDensity { foliage_index } => self
+                .foliage_query
+                .iter()
+                .find_map(|density_maps| density_maps.0.get(&foliage_index))
+                .map(|density_map| density_map.0.read_tile(key.tile)),
+        }
+    }
+
+    // applies an xor delta to a tile and flags whatever needs rebuilding
+    fn xor_tile(&mut self, commands: &mut Commands, key: &TileKey, xor: &[u8]) {
+        match key.layer {
+            PaintLayer::TerrainHeight { chunk_id } => {
+                if let Some(height_map) = self.chunk_height_maps.chunk_height_maps.get_mut(&chunk_id) {
+                    height_map.xor_tile(key.tile, xor);
+                }
+
+                for (_, chunk, mut chunk_data, _, _) in self.chunk_query.iter_mut() {
+                    if chunk.chunk_id == chunk_id {
+                        chunk_data.height_map_image_data_load_status =
+                            TerrainImageDataLoadStatus::NeedsReload;
+                    }
+                }
+            }
+
+            PaintLayer::TerrainSplat { chunk_id } => {
+                for (chunk_entity, chunk, _, _, splat_data) in self.chunk_query.iter_mut() {
+                    if chunk.chunk_id != chunk_id {
+                        continue;
+                    }
+
+                    if let Some(mut splat_data) = splat_data {
+                        splat_data.splat_map_texture.xor_tile(key.tile, xor);
+
+                        if let Ok(mut cmds) = commands.get_entity(chunk_entity) {
+                            cmds.try_insert(SplatMapDataUpdated);
+                        }
+                    }
+                }
+            }
+
+            PaintLayer::TerrainHoles { chunk_id } => {
+                let chunk_dimensions = self.chunk_dimensions().as_uvec2();
+                self.hole_masks
+                    .hole_mask_mut(chunk_id, chunk_dimensions)
+                    .xor_tile(key.tile, xor);
+
+                for (_, chunk, mut chunk_data, _, _) in self.chunk_query.iter_mut() {
+                    if chunk.chunk_id == chunk_id {
+                        chunk_data.height_map_image_data_load_status =
+                            TerrainImageDataLoadStatus::NeedsReload;
+                    }
+                }
+            }
+
+            PaintLayer::Regions => {
+                if let Some(region_map) = self.regions_data.regions_data_map.as_mut() {
+                    region_map.xor_tile(key.tile, xor);
+
+                    commands.write_message(RegionDataEvent::RegionMapNeedsReloadFromResourceData);
+                }
+            }
+
+            // foliage chunks rebuild on their own from change detection
+            PaintLayer::FoliageDensity { foliage_index } => {
+                for mut density_maps in self.foliage_query.iter_mut() {
+                    if let Some(density_map) = density_maps.0.get_mut(&foliage_index) {
+                        density_map.0.xor_tile(key.tile, xor);
+                    }
+                }
+            }
+        }
+    }
+
+    // every tile of the stroke is loaded and still has the size it was recorded with
+    fn can_replay(&self, stroke: &PaintStroke) -> bool {
+        stroke.deltas.iter().all(|delta| {
+            self.read_tile(&delta.key)
+                .is_some_and(|tile| tile.len() == delta.tile_len)
+        })
+    }
+
+    fn chunk_dimensions(&self) -> Vec2 {
+        self.terrain_query
+            .iter()
+            .next()
+            .map(|terrain_config| {
+                terrain_config.terrain_dimensions / terrain_config.chunk_rows as f32
+            })
+            .unwrap_or(Vec2::splat(256.0))
+    }
+}
+
+fn capture_tiles(
+    history: &mut PaintHistoryResource,
+    maps: &PaintMaps,
+    layer: PaintLayer,
+    tiles: Vec<UVec2>,
+) {
+    for tile in tiles {
+        let key = TileKey { layer, tile };
+
+        if history.pending_tiles.contains_key(&key) {
+            continue;
+        }
+
+        if let Some(snapshot) = maps.read_tile(&key) {
+            history.pending_tiles.insert(key, snapshot);
+        }
+    }
+}
+
+fn capture_paint_tiles(
+    mut terrain_edit_reader: MessageReader<EditTerrainEvent>,
+    mut region_edit_reader: MessageReader<EditRegionEvent>,
+    mut foliage_edit_reader: MessageReader<EditFoliageEvent>,
+
+    maps: PaintMaps,
+    mut history: ResMut<PaintHistoryResource>,
+) {
+    // one texel of margin so smoothing kernels at the brush edge are covered
+    const MARGIN: f32 = 1.0;
+
+    for ev in terrain_edit_reader.read() {
+        if ev.brush_type == TerrainBrushType::EyeDropper {
+            continue;
+        }
+
+        let chunk_dimensions = maps.chunk_dimensions();
+        let radius = ev.radius + MARGIN;
+
+        for (_, chunk, _, chunk_transform, splat_data) in maps.chunk_query.iter() {
+            let chunk_origin = chunk_transform.translation();
+            let tool_coords_local = ev.coordinates - Vec2::new(chunk_origin.x, chunk_origin.z);
+
+            match &ev.tool {
+                TerrainEditingTool::SetHeightMap { .. } => {
+                    let Some(height_map) = maps.chunk_height_maps.chunk_height_maps.get(&chunk.chunk_id) else {
+                        continue;
+                    };
+
+                    let tiles = tiles_in_rect(
+                        tool_coords_local - Vec2::splat(radius),
+                        tool_coords_local + Vec2::splat(radius),
+                        height_map.grid_size(),
+                    );
+
+                    capture_tiles(
+                        &mut history,
+                        &maps,
+                        PaintLayer::TerrainHeight { chunk_id: chunk.chunk_id },
+                        tiles,
+                    );
+                }
+
+                TerrainEditingTool::SetSplatMap { .. } | TerrainEditingTool::SetSplatMapUltra { .. } => {
+                    let Some(splat_data) = splat_data else {
+                        continue;
+                    };
+
+                    let splat_dimensions = splat_data.splat_map_texture.grid_size();
+                    let scale_factor = splat_dimensions.as_vec2() / chunk_dimensions;
+
+                    let tiles = tiles_in_rect(
+                        (tool_coords_local - Vec2::splat(radius)) * scale_factor,
+                        (tool_coords_local + Vec2::splat(radius)) * scale_factor,
+                        splat_dimensions,
+                    );
+
+                    capture_tiles(
+                        &mut history,
+                        &maps,
+                        PaintLayer::TerrainSplat { chunk_id: chunk.chunk_id },
+                        tiles,
+                    );
+                }
+
+                TerrainEditingTool::SetHoleMask => {
+                    let tiles = tiles_in_rect(
+                        tool_coords_local - Vec2::splat(radius),
+                        tool_coords_local + Vec2::splat(radius),
+                        chunk_dimensions.as_uvec2(),
+                    );
+
+                    capture_tiles(
+                        &mut history,
+                        &maps,
+                        PaintLayer::TerrainHoles { chunk_id: chunk.chunk_id },
+                        tiles,
+                    );
+                }
+            }
+        }
+    }
+
+    for ev in region_edit_reader.read() {
+        if ev.brush_type == RegionsBrushType::EyeDropper {
+            continue;
+        }
+
+        let Some(region_map) = maps.regions_data.regions_data_map.as_ref() else {
+            continue;
+        };
+
+        let radius = ev.radius + MARGIN;
+        let tiles = tiles_in_rect(
+            ev.coordinates - Vec2::splat(radius),
+            ev.coordinates + Vec2::splat(radius),
+            region_map.grid_size(),
+        );
+
+        capture_tiles(&mut history, &maps, PaintLayer::Regions, tiles);
+    }
+
+    for ev in foliage_edit_reader.read() {
+        if ev.brush_type == FoliageBrushType::EyeDropper {
+            continue;
+        }
+
+        let FoliageEditingTool::SetFoliageDensity { foliage_index, .. } = ev.tool;
+        let foliage_index = foliage_index as usize;
+
+        let Some(density_map) = maps
+            .foliage_query
+            .iter()
+            .find_map(|density_maps| density_maps.0.get(&foliage_index))
+        else {
+            continue;
+        };
+
+        let radius = ev.radius + MARGIN;
+        let tiles = tiles_in_rect(
+            ev.coordinates - Vec2::splat(radius),
+            ev.coordinates + Vec2::splat(radius),
+            density_map.0.grid_size(),
+        );
+
+        capture_tiles(
+            &mut history,
+            &maps,
+            PaintLayer::FoliageDensity { foliage_index },
+            tiles,
+        );
+    }
+}
+
+// a stroke ends when the paint button is released
+fn finish_paint_stroke(
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    maps: PaintMaps,
+    mut history: ResMut<PaintHistoryResource>,
+) {
+    if !history.stroke_in_progress() || mouse_input.pressed(MouseButton::Left) {
+        return;
+    }
+
+    let pending_tiles: Vec<(TileKey, Vec<u8>)> = history.pending_tiles.drain().collect();
+
+    let mut deltas = Vec::new();
+    for (key, before) in pending_tiles {
+        let Some(after) = maps.read_tile(&key) else {
+            continue;
+        };
+
+        if let Some(encoded_xor) = encode_tile_delta(&before, &after) {
+            deltas.push(TileDelta {
+                key,
+                tile_len: after.len(),
+                encoded_xor,
+            });
+        }
+    }
+
+    if deltas.is_empty() {
+        return;
+    }
+
+    let stroke = PaintStroke { deltas };
+    info!(
+        "paint stroke recorded: {} tiles, {} bytes",
+        stroke.tile_count(),
+        stroke.byte_size()
+    );
+
+    history.push_stroke(stroke);
+}
+
+// bulk writes leave the recorded deltas pointing at data that is gone
+fn clear_replaced_paint_history(
+    mut replaced_reader: MessageReader<TerrainDataReplacedEvent>,
+    mut history: ResMut<PaintHistoryResource>,
+) {
+    if replaced_reader.read().count() == 0 {
+        return;
+    }
+
+    if history.can_undo() || history.can_redo() || history.stroke_in_progress() {
+        info!("terrain data was replaced, clearing paint history");
+    }
+
+    history.clear();
+}
+
+fn replay_stroke(stroke: &PaintStroke, maps: &mut PaintMaps, commands: &mut Commands) {
+    for delta in stroke.deltas.iter() {
+        let xor = decode_tile_delta(&delta.encoded_xor, delta.tile_len);
+
+        maps.xor_tile(commands, &delta.key, &xor);
+    }
+}
+
+fn update_paint_history_commands(
+    key_input: Res<ButtonInput<KeyCode>>,
+
+    mut maps: PaintMaps,
+    mut history: ResMut<PaintHistoryResource>,
+
+    mut commands: Commands,
+) {
+    if !(key_input.pressed(KeyCode::ControlLeft) || key_input.pressed(KeyCode::ControlRight)) {
+        return;
+    }
+
+    // never replay into the middle of an active stroke
+    if history.stroke_in_progress() {
+        return;
+    }
+
+    let shift_pressed =
+        key_input.pressed(KeyCode::ShiftLeft) || key_input.pressed(KeyCode::ShiftRight);
+
+    let redo_requested = key_input.just_pressed(KeyCode::KeyY)
+        || (shift_pressed && key_input.just_pressed(KeyCode::KeyZ));
+    let undo_requested = !shift_pressed && key_input.just_pressed(KeyCode::KeyZ);
+
+    if undo_requested {
+        let Some(stroke) = history.undo_stack.back() else {
+            return;
+        };
+
+        if !maps.can_replay(stroke) {
+            warn!("cannot undo paint stroke: some of its chunks are not loaded");
+            return;
+        }
+
+        if let Some(stroke) = history.pop_undo() {
+            replay_stroke(&stroke, &mut maps, &mut commands);
+            history.redo_stack.push(stroke);
+        }
+    } else if redo_requested {
+        let Some(stroke) = history.redo_stack.last() else {
+            return;
+        };
+
+        if !maps.can_replay(stroke) {
+            warn!("cannot redo paint stroke: some of its chunks are not loaded");
+            return;
+        }
+
+        if let Some(stroke) = history.redo_stack.pop() {
+            replay_stroke(&stroke, &mut maps, &mut commands);
+            history.push_undo(stroke);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn height_grid(size: usize) -> Vec<Vec<u16>> {
+        (0..size)
+            .map(|y| (0..size).map(|x| (y * size + x) as u16 * 7).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_unchanged_tile_has_no_delta() {
+        let tile = vec![1, 2, 3, 4];
+        assert!(encode_tile_delta(&tile, &tile).is_none());
+    }
+
+    #[test]
+    fn test_delta_round_trip() {
+        let before: Vec<u8> = (0..200).map(|i| (i * 31 % 256) as u8).collect();
+
+        let mut after = before.clone();
+        after[0] ^= 0xff;
+        after[57] = 3;
+        after[58] = 4;
+        after[199] = 0;
+        for byte in after[120..160].iter_mut() {
+            *byte = byte.wrapping_add(1);
+        }
+
+        let encoded = encode_tile_delta(&before, &after).expect("tile changed");
+        let xor = decode_tile_delta(&encoded, before.len());
+
+        let restored: Vec<u8> = after.iter().zip(&xor).map(|(a, x)| a ^ x).collect();
+        assert_eq!(restored, before);
+
+        let replayed: Vec<u8> = before.iter().zip(&xor).map(|(b, x)| b ^ x).collect();
+        assert_eq!(replayed, after);
+    }
+
+    #[test]
+    fn test_delta_round_trip_with_long_zero_runs() {
+        // runs longer than 127 need more than one varint byte
+        let before = vec![0u8; 4096];
+        let mut after = before.clone();
+        after[3000] = 9;
+
+        let encoded = encode_tile_delta(&before, &after).expect("tile changed");
+        assert!(encoded.len() < 8);
+
+        let xor = decode_tile_delta(&encoded, before.len());
+        assert_eq!(xor.len(), before.len());
+        assert_eq!(xor[3000], 9);
+        assert_eq!(xor.iter().filter(|byte| **byte != 0).count(), 1);
+    }
+
+    #[test]
+    fn test_undo_redo_restores_height_tile() {
+        let mut grid = height_grid(40);
+        let tile = UVec2::new(1, 0);
+
+        let before = grid.read_tile(tile);
+        for row in grid.iter_mut().take(10) {
+            for height in row.iter_mut().skip(33) {
+                *height = height.wrapping_mul(3);
+            }
+        }
+        let after = grid.read_tile(tile);
+        let painted = grid.clone();
+
+        let encoded = encode_tile_delta(&before, &after).expect("tile changed");
+        let xor = decode_tile_delta(&encoded, after.len());
+
+        // undo
+        grid.xor_tile(tile, &xor);
+        assert_eq!(grid, height_grid(40));
+
+        // redo
+        grid.xor_tile(tile, &xor);
+        assert_eq!(grid, painted);
+    }
+
+    #[test]
+    fn test_undo_redo_restores_image_tile() {
+        use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+        use bevy::asset::RenderAssetUsages;
+
+        let mut image = Image::new_fill(
+            Extent3d {
+                width: 48,
+                height: 48,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            &[10, 20, 30, 255],
+            TextureFormat::Rgba8Unorm,
+            RenderAssetUsages::default(),
+        );
+        let original = image.data.clone();
+
+        let tile = UVec2::new(1, 1);
+        let before = image.read_tile(tile);
+
+        let data = image.data.as_mut().expect("image has data");
+        for y in 32..48 {
+            let row_start = (y * 48 + 40) * 4;
+            data[row_start] = 99;
+            data[row_start + 2] = 7;
+        }
+        let painted = image.data.clone();
+        let after = image.read_tile(tile);
+
+        let encoded = encode_tile_delta(&before, &after).expect("tile changed");
+        let xor = decode_tile_delta(&encoded, after.len());
+
+        image.xor_tile(tile, &xor);
+        assert_eq!(image.data, original);
+
+        image.xor_tile(tile, &xor);
+        assert_eq!(image.data, painted);
+    }
+}
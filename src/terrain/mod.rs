@@ -5,7 +5,10 @@ use bevy::prelude::*;
 pub(crate) mod terrain_manifest;
 pub (crate) mod terrain_loading;
 pub (crate) mod terrain_generation;
+pub (crate) mod terrain_generator_graph;
 pub (crate) mod terrain_colliders; 
+pub (crate) mod terrain_auto_splat;
+pub (crate) mod terrain_splines;
 
  
 
@@ -18,6 +21,8 @@ pub fn terrain_plugin(app: &mut App){
 	 	.add_plugins( terrain_manifest::terrain_manifest_plugin)
         .add_plugins( terrain_loading::terrain_loading_plugin)
         .add_plugins( terrain_colliders:: terrain_colliders_plugin  )
+        .add_plugins( terrain_auto_splat::terrain_auto_splat_plugin )
+        .add_plugins( terrain_splines::terrain_splines_plugin )
       
 
 	;
@@ -0,0 +1,100 @@
+
+use crate::liquid::LiquidPlaneComponent;
+use crate::terrain::terrain_manifest::{TerrainManifest, TerrainManifestResource};
+use degen_toon_terrain::auto_splat::{AutoSplatEvent, LiquidPlaneArea};
+use degen_toon_terrain::chunk::{ChunkCoordinates, ChunkCoords};
+use degen_toon_terrain::terrain_config::TerrainConfig;
+use bevy::prelude::*;
+
+/*
+
+Runs the rule based splat painting of degen_toon_terrain with the rules from the
+terrain manifest and the liquid planes that are currently in the scene.
+
+*/
+
+pub fn terrain_auto_splat_plugin(app: &mut App){
+
+	app
+		.init_resource::<AutoSplatToolState>()
+
+		.add_message::<RunAutoSplatEvent>()
+
+		.add_systems(Update, run_auto_splat)
+
+	;
+
+}
+
+/// Auto splats every chunk within `chunk_min..=chunk_max` (chunk coordinates).
+#[derive(Message, Clone)]
+pub struct RunAutoSplatEvent {
+    pub chunk_min: UVec2,
+    pub chunk_max: UVec2,
+}
+
+#[derive(Resource, Default)]
+pub struct AutoSplatToolState {
+    pub chunk_min: UVec2,
+    pub chunk_max: UVec2,
+}
+
+fn run_auto_splat(
+    mut evt_reader: MessageReader<RunAutoSplatEvent>,
+
+    terrain_query: Query<&TerrainConfig>,
+    liquid_plane_query: Query<&GlobalTransform, With<LiquidPlaneComponent>>,
+
+    terrain_manifest_res: Res<TerrainManifestResource>,
+    terrain_manifest_asset: Res<Assets<TerrainManifest>>,
+
+    mut auto_splat_evt_writer: MessageWriter<AutoSplatEvent>,
+) {
+    let Some(evt) = evt_reader.read().last() else {
+        return;
+    };
+
+    let Some(terrain_config) = terrain_query.iter().next() else {
+        warn!("cannot auto splat: no terrain loaded");
+        return;
+    };
+
+    let Some(rules) = terrain_manifest_res
+        .manifest
+        .as_ref()
+        .and_then(|handle| terrain_manifest_asset.get(handle))
+        .and_then(|manifest| manifest.auto_splat_rules.clone())
+    else {
+        warn!("cannot auto splat: the terrain manifest has no auto_splat_rules");
+        return;
+    };
+
+    let chunk_rows = terrain_config.chunk_rows;
+    let last_chunk = UVec2::splat(chunk_rows.saturating_sub(1));
+    let chunk_min = evt.chunk_min.min(evt.chunk_max).min(last_chunk);
+    let chunk_max = evt.chunk_min.max(evt.chunk_max).min(last_chunk);
+
+    let chunk_ids = (chunk_min.y..=chunk_max.y)
+        .flat_map(|y| (chunk_min.x..=chunk_max.x).map(move |x| ChunkCoords::new(x, y).get_chunk_index(chunk_rows)))
+        .collect();
+
+    // liquid planes are unit planes scaled by their transform
+    let liquid_planes = liquid_plane_query
+        .iter()
+        .map(|transform| {
+            let (scale, _, translation) = transform.to_scale_rotation_translation();
+
+            LiquidPlaneArea {
+                center: Vec2::new(translation.x, translation.z),
+                half_extents: Vec2::new(scale.x, scale.z).abs() * 0.5,
+                height: translation.y,
+            }
+        })
+        .collect();
+
+    auto_splat_evt_writer.write(AutoSplatEvent {
+        chunk_ids,
+        rules,
+        liquid_planes,
+    });
+}
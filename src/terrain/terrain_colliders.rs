@@ -3,6 +3,7 @@ use degen_toon_terrain::terrain::TerrainData;
 use crate::doodads::doodad_colliders::CollisionLayer;
 use degen_toon_terrain::chunk::Chunk;
 use degen_toon_terrain::chunk::ChunkData;
+use degen_toon_terrain::streaming::StreamedChunkCollider;
 use degen_toon_terrain::terrain_config::TerrainConfig;
 use avian3d::prelude::*;
  
@@ -10,6 +11,9 @@ use bevy::prelude::*;
 
 pub fn terrain_colliders_plugin(app: &mut App){
 
+	// colliders of streamed chunks are loaded by the terrain plugin (chunk_streaming.stream_colliders)
+	app.add_systems(Update, add_collision_layers_to_streamed_colliders);
+
 /*
 	app
 		 
@@ -30,6 +34,23 @@ pub fn terrain_colliders_plugin(app: &mut App){
 }
 
 
+fn add_collision_layers_to_streamed_colliders(
+    mut commands: Commands,
+
+    collider_query: Query<Entity, Added<StreamedChunkCollider>>,
+) {
+    for entity in collider_query.iter() {
+        commands.entity(entity).insert((
+            Restitution::new(0.0001).with_combine_rule(CoefficientCombine::Multiply),
+            CollisionLayers::new(
+                [CollisionLayer::Terrain, CollisionLayer::CameraObstacle],
+                [CollisionLayer::Character],
+            ),
+        ));
+    }
+}
+
+
 #[derive(Component, Default)]
 struct ChunkColliderMarker;
 
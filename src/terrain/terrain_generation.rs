@@ -1,201 +1,279 @@
 
 
+use std::path::PathBuf;
+
+use crate::terrain::terrain_generator_graph::{GeneratorContext, TerrainGeneratorGraph};
+use bevy::math::DVec2;
+use bevy::tasks::ComputeTaskPool;
+use bevy_regions::regions::RegionsDataMapResource;
 use degen_toon_terrain::chunk::TerrainImageDataNeedsReload;
-use degen_toon_terrain::terrain::TerrainImageDataLoadStatus;
-use degen_toon_terrain::heightmap::HeightMap;
-use degen_toon_terrain::splat::ChunkSplatDataRaw;
 use degen_toon_terrain::chunk::ChunkHeightMapResource;
-use degen_toon_terrain::terrain::TerrainData;
+use degen_toon_terrain::chunk::{ChunkCoordinates, ChunkCoords};
+use degen_toon_terrain::edit::TerrainDataReplacedEvent;
 use degen_toon_terrain::terrain_config::TerrainConfig;
 use degen_toon_terrain::chunk::Chunk;
-use degen_toon_terrain::chunk::ChunkData;
-use noise::NoiseFn;
-use noise::Perlin;
-use bevy::prelude::*; 
-
-pub fn terrain_generation_plugin(app: &mut App){
-
-
-	app
-		.add_event::<GenerateTerrainEvent>()
-
-		.add_observer(  generate_terrain )
-	 	
-	;
-
-}
-
-#[derive(Message)]
-pub struct GenerateTerrainEvent ; 
-
+use bevy::prelude::*;
 
+/*
 
+Terrain generation evaluates a generator graph (see terrain_generator_graph.rs) for a
+rectangle of chunks and replaces their heightmaps.
 
+Chunks are generated in parallel and sampled in world space so their edges stay seamless.
+The result is only in memory until the chunks are saved.
 
+*/
 
-fn generate_terrain(
+pub const DEFAULT_GENERATOR_GRAPH_PATH: &str = "assets/terrain_generators/default.terraingen.ron";
 
+const PREVIEW_RESOLUTION: u32 = 64;
 
-	_trigger: Trigger<GenerateTerrainEvent>,
+pub fn terrain_generation_plugin(app: &mut App){
 
-	terrain_query: Query<(&TerrainData, &TerrainConfig)>,
-	chunk_query: Query<(Entity, &Chunk, & ChunkData, &ChunkSplatDataRaw, &ChildOf, &Children)>,
 
-    mut chunk_height_maps: ResMut<ChunkHeightMapResource>,
+	app
+		.init_resource::<TerrainGeneratorState>()
 
-    mut commands : Commands , 
+		.add_message::<GenerateTerrainEvent>()
+		.add_message::<PreviewTerrainGenerationEvent>()
 
-) {
+		.add_systems(Update, (
+			generate_terrain,
+			preview_terrain_generation,
+		))
 
+	;
 
-    info!( "generating terrain ! ! " );
+}
 
-	for (chunk_entity, chunk, chunk_data, chunk_splat_data, parent_terrain_entity, chunk_children) in chunk_query.iter() {
+/// Regenerates every chunk within `chunk_min..=chunk_max` (chunk coordinates) from the graph.
+#[derive(Message, Clone)]
+pub struct GenerateTerrainEvent {
+    pub graph_path: PathBuf,
+    pub chunk_min: UVec2,
+    pub chunk_max: UVec2,
+    pub seed: u32,
+}
 
+/// Renders a low resolution preview of the same chunk range into `TerrainGeneratorState`.
+#[derive(Message, Clone)]
+pub struct PreviewTerrainGenerationEvent {
+    pub graph_path: PathBuf,
+    pub chunk_min: UVec2,
+    pub chunk_max: UVec2,
+    pub seed: u32,
+}
 
-		    let terrain_entity_id = parent_terrain_entity.get();
-		    if terrain_query.get(terrain_entity_id).is_ok() == false {
-	                continue;
-	            }
+#[derive(Resource)]
+pub struct TerrainGeneratorState {
+    pub graph_path: String,
+    pub chunk_min: UVec2,
+    pub chunk_max: UVec2,
+    pub seed: u32,
 
-	        let (terrain_data, terrain_config) = terrain_query.get(terrain_entity_id).unwrap();
-	       
+    // whole-terrain heightmap for import / export, png or raw by extension
+    pub heightmap_path: String,
 
-	         let chunk_id = chunk.chunk_id ;
+    pub preview: Option<TerrainGenerationPreview>,
+    pub last_error: Option<String>,
+}
 
-	      //   let file_name = format!("{}.png", chunk.chunk_id);
-           //   let asset_folder_path = PathBuf::from("assets");
+impl Default for TerrainGeneratorState {
+    fn default() -> Self {
+        Self {
+            graph_path: DEFAULT_GENERATOR_GRAPH_PATH.into(),
+            chunk_min: UVec2::ZERO,
+            chunk_max: UVec2::ZERO,
+            seed: 0,
+            heightmap_path: "heightmaps/terrain.png".into(),
+            preview: None,
+            last_error: None,
+        }
+    }
+}
 
+impl TerrainGeneratorState {
+    pub fn build_generate_event(&self) -> GenerateTerrainEvent {
+        GenerateTerrainEvent {
+            graph_path: PathBuf::from(&self.graph_path),
+            chunk_min: self.chunk_min,
+            chunk_max: self.chunk_max,
+            seed: self.seed,
+        }
+    }
 
+    pub fn build_preview_event(&self) -> PreviewTerrainGenerationEvent {
+        PreviewTerrainGenerationEvent {
+            graph_path: PathBuf::from(&self.graph_path),
+            chunk_min: self.chunk_min,
+            chunk_max: self.chunk_max,
+            seed: self.seed,
+        }
+    }
+}
 
-            let terrain_dimensions = &terrain_config.terrain_dimensions;
-            let chunk_rows = &terrain_config.chunk_rows ;
+pub struct TerrainGenerationPreview {
+    pub resolution: u32,
+    pub pixels: Vec<u8>, // row major grayscale
+}
 
+// clamps the requested range to the terrain and returns (min, max) inclusive
+fn clamp_chunk_range(chunk_min: UVec2, chunk_max: UVec2, chunk_rows: u32) -> (UVec2, UVec2) {
+    let last_chunk = UVec2::splat(chunk_rows.saturating_sub(1));
 
+    let min = chunk_min.min(chunk_max).min(last_chunk);
+    let max = chunk_min.max(chunk_max).min(last_chunk);
 
-               let   chunk_dimensions = [terrain_dimensions.x as u32 / chunk_rows, 
-               terrain_dimensions.y as u32  / chunk_rows];//compute me from terrain config
+    (min, max)
+}
 
+fn build_generator_context<'a>(
+    terrain_config: &TerrainConfig,
+    chunk_height_maps: &'a ChunkHeightMapResource,
+    regions_data: Option<&'a RegionsDataMapResource>,
+) -> GeneratorContext<'a> {
+    let terrain_dimensions = terrain_config.terrain_dimensions;
+    let chunk_rows = terrain_config.chunk_rows;
+
+    GeneratorContext {
+        terrain_size: DVec2::new(terrain_dimensions.x as f64, terrain_dimensions.y as f64),
+        chunk_rows,
+        chunk_dimensions: UVec2::new(
+            terrain_dimensions.x as u32 / chunk_rows,
+            terrain_dimensions.y as u32 / chunk_rows,
+        ),
+        existing_heights: Some(&chunk_height_maps.chunk_height_maps),
+        regions: regions_data.and_then(|r| r.regions_data_map.as_ref()),
+    }
+}
 
-				  // Generate noise map for the current chunk
-            let width = chunk_dimensions[0] as usize;
-            let height = chunk_dimensions[1] as usize;
 
-            let offset_x =  chunk_id / chunk_rows;
-            let offset_y = chunk_id % chunk_rows;
 
-            let horizontal_scale = 1.0 / terrain_dimensions.x ;
 
-            let seed = 0 ;
+fn generate_terrain(
 
-            let octaves = 1;
 
-            let persistence = 0.5 ;
+	mut evt_reader: MessageReader<GenerateTerrainEvent>,
 
-            let amplitude_scale = 1.0 ; 
+	terrain_query: Query<&TerrainConfig>,
+	chunk_query: Query<(Entity, &Chunk, &ChildOf)>,
 
+    mut chunk_height_maps: ResMut<ChunkHeightMapResource>,
+    regions_data: Option<Res<RegionsDataMapResource>>,
 
-            let height_noise_map = generate_terrain_noise_map(
-                width,
-                height,
-                offset_x as f64,
-                offset_y as f64 ,
-                horizontal_scale.into(),
-                amplitude_scale,
-                seed,
-                octaves, 
-                persistence
-            );
+    mut generator_state: ResMut<TerrainGeneratorState>,
 
-        	chunk_height_maps.chunk_height_maps.insert( chunk.chunk_id,  height_noise_map   );
+    mut commands : Commands ,
 
+) {
 
-            commands.trigger_targets(   TerrainImageDataNeedsReload  , chunk_entity  );
+    // the ui sends one request per click, only the latest of a frame is generated
+    let Some(evt) = evt_reader.read().last() else {
+        return;
+    };
+
+    let Some(terrain_config) = terrain_query.iter().next() else {
+        warn!("cannot generate terrain: no terrain loaded");
+        return;
+    };
+
+    let graph = match TerrainGeneratorGraph::load_from_file(&evt.graph_path)
+        .and_then(|graph| graph.compile(evt.seed))
+    {
+        Ok(graph) => graph,
+        Err(err) => {
+            error!("cannot generate terrain: {:?}", err);
+            generator_state.last_error = Some(format!("{:#}", err));
+            return;
+        }
+    };
 
-            
-              
+    generator_state.last_error = None;
 
+    let chunk_rows = terrain_config.chunk_rows;
+    let (chunk_min, chunk_max) = clamp_chunk_range(evt.chunk_min, evt.chunk_max, chunk_rows);
 
+    let chunk_ids: Vec<u32> = (chunk_min.y..=chunk_max.y)
+        .flat_map(|y| (chunk_min.x..=chunk_max.x).map(move |x| ChunkCoords::new(x, y).get_chunk_index(chunk_rows)))
+        .collect();
 
-	}
+    info!("generating terrain for {} chunks ", chunk_ids.len());
 
+    // every chunk samples the heights as they were before generation started
+    let generated_chunks = {
+        let context = build_generator_context(terrain_config, &chunk_height_maps, regions_data.as_deref());
 
-	//   pub type HeightMapU16 = Vec<Vec<u16>>;
+        let graph = &graph;
+        let context = &context;
 
- 	
+        ComputeTaskPool::get().scope(|scope| {
+            for chunk_id in chunk_ids.iter().copied() {
+                scope.spawn(async move { (chunk_id, graph.generate_chunk(chunk_id, context)) });
+            }
+        })
+    };
 
+    for (chunk_id, height_map) in generated_chunks {
+        chunk_height_maps.chunk_height_maps.insert(chunk_id, height_map);
+    }
 
+    for (chunk_entity, chunk, parent_terrain_entity) in chunk_query.iter() {
+        if terrain_query.get(parent_terrain_entity.get()).is_err() {
+            continue;
+        }
 
+        if chunk_ids.contains(&chunk.chunk_id) {
+            commands.trigger(   TerrainImageDataNeedsReload { entity: chunk_entity }  );
+        }
+    }
 
+    // paint history recorded against the old heights can not be replayed anymore
+    commands.write_message(TerrainDataReplacedEvent);
 }
 
-/*
+fn preview_terrain_generation(
+    mut evt_reader: MessageReader<PreviewTerrainGenerationEvent>,
 
-pub struct GenerateTerrain {
-    pub width: usize,
-    pub height: usize,
-    pub offset_x: f64,
-    pub offset_y: f64,
-    pub scale: f64,
-    pub seed: u32,
-}
-*/
+    terrain_query: Query<&TerrainConfig>,
 
-pub fn generate_terrain_noise_map(
-
-
-    width: usize,
-    height: usize,
-    offset_x: f64,
-    offset_y: f64,
-    horizontal_scale: f64,
-    amplitude_scale: f64,
-    seed: u32,
-    octaves: usize,
-    persistence: f64,
-) -> Vec<Vec<u16>> {
-    // Create a seeded noise generator
-    let perlin = Perlin::new(seed);
-
-    // Initialize the height map
-    let mut noise_map: Vec<Vec<u16>> = vec![vec![0; width]; height];
-
-    // Generate heights
-    for y in 0..height {
-        for x in 0..width {
-            // Map x and y to world coordinates with offset and scale
-            let world_x = (x as f64 ) + offset_x * width as f64;
-            let world_y = (y as f64 ) + offset_y * height as f64;
-
-            // Log the input coordinates to debug
-            info!("world_x: {}, world_y: {}", world_x, world_y);
-
-            // Compute fractal noise (FBM)
-            let mut noise_value = 0.0;
-            let mut amplitude = amplitude_scale;
-            let mut frequency = 1.0;
-            let mut max_amplitude = 0.0;
-
-            for _ in 0..octaves {
-                noise_value += perlin.get([world_x  * horizontal_scale, world_y * horizontal_scale ]) * amplitude;
-                max_amplitude += amplitude;
-                amplitude *= persistence;
-                frequency *= 2.0;
-            }
+    chunk_height_maps: Res<ChunkHeightMapResource>,
+    regions_data: Option<Res<RegionsDataMapResource>>,
 
-            // Normalize the noise value to range [-1.0, 1.0]
-            noise_value /= max_amplitude;
+    mut generator_state: ResMut<TerrainGeneratorState>,
+) {
+    let Some(evt) = evt_reader.read().last() else {
+        return;
+    };
+
+    let Some(terrain_config) = terrain_query.iter().next() else {
+        warn!("cannot preview terrain generation: no terrain loaded");
+        return;
+    };
+
+    let graph = match TerrainGeneratorGraph::load_from_file(&evt.graph_path)
+        .and_then(|graph| graph.compile(evt.seed))
+    {
+        Ok(graph) => graph,
+        Err(err) => {
+            generator_state.last_error = Some(format!("{:#}", err));
+            generator_state.preview = None;
+            return;
+        }
+    };
 
-            // Map noise value (-1.0 to 1.0) to u16 (0 to 65535)
-            let height = ((noise_value + 1.0) / 2.0 * u16::MAX as f64) as u16;
+    let context = build_generator_context(terrain_config, &chunk_height_maps, regions_data.as_deref());
 
-            // Log noise values for debugging
-            info!("noise_value: {:?}, height: {}", noise_value, height);
+    let (chunk_min, chunk_max) = clamp_chunk_range(evt.chunk_min, evt.chunk_max, terrain_config.chunk_rows);
 
-            // Store the height
-            noise_map[y][x] = height;
-        }
-    }
+    let chunk_size = context.chunk_dimensions.as_dvec2();
+    let world_min = chunk_min.as_dvec2() * chunk_size;
+    let world_max = (chunk_max + UVec2::ONE).as_dvec2() * chunk_size;
+
+    let pixels = graph.generate_preview(world_min, world_max, PREVIEW_RESOLUTION, &context);
 
-    noise_map
+    generator_state.last_error = None;
+    generator_state.preview = Some(TerrainGenerationPreview {
+        resolution: PREVIEW_RESOLUTION,
+        pixels,
+    });
 }
@@ -0,0 +1,946 @@
+use std::collections::HashMap;
+use std::f64::consts::TAU;
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Context};
+use bevy::math::{DVec2, UVec2};
+use bevy::platform::collections::HashMap as BevyHashMap;
+use noise::{NoiseFn, Perlin, Simplex, Worley};
+use serde::{Deserialize, Serialize};
+
+use degen_toon_terrain::chunk::{ChunkCoordinates, ChunkCoords};
+use degen_toon_terrain::heightmap::HeightMapU16;
+
+/*
+
+A composable heightmap generator described in RON.
+
+Every node outputs a value that is roughly in 0..1, the output node is clamped to 0..1
+and mapped onto the full u16 height range.
+
+Nodes are evaluated in world space (one texel per world unit), so chunks generated
+separately line up at their edges. With `tileable: true` the noise sources are sampled
+on a 4D torus so the whole terrain also wraps around seamlessly.
+
+(
+    tileable: false,
+    output: "final",
+    nodes: {
+        "base": Perlin(( frequency: 0.004, octaves: 5 )),
+        "ridges": Simplex(( frequency: 0.01, octaves: 4, fractal: Ridged, seed: 7 )),
+        "mixed": Lerp( a: "base", b: "ridges", mask: "base" ),
+        "final": Terraces( input: "mixed", steps: 8, smoothness: 0.6 ),
+    },
+)
+
+*/
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TerrainGeneratorGraph {
+    #[serde(default)]
+    pub tileable: bool,
+
+    pub output: String,
+    pub nodes: HashMap<String, GeneratorNode>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum FractalKind {
+    #[default]
+    Fbm,
+    Ridged,
+    Billow,
+}
+
+fn default_octaves() -> u32 {
+    1
+}
+
+fn default_persistence() -> f64 {
+    0.5
+}
+
+fn default_lacunarity() -> f64 {
+    2.0
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NoiseSettings {
+    pub frequency: f64,
+
+    #[serde(default = "default_octaves")]
+    pub octaves: u32,
+    #[serde(default = "default_persistence")]
+    pub persistence: f64,
+    #[serde(default = "default_lacunarity")]
+    pub lacunarity: f64,
+
+    #[serde(default)]
+    pub seed: u32,
+    #[serde(default)]
+    pub fractal: FractalKind,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum BlendOp {
+    Add,
+    Subtract,
+    Multiply,
+    Min,
+    Max,
+    Average,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum GeneratorNode {
+    Constant(f64),
+
+    Perlin(NoiseSettings),
+    Simplex(NoiseSettings),
+    Worley(NoiseSettings),
+
+    // offsets the sample position of `input` by two other nodes, in world units
+    DomainWarp {
+        input: String,
+        warp_x: String,
+        warp_y: String,
+        strength: f64,
+    },
+
+    Terraces {
+        input: String,
+        steps: u32,
+        #[serde(default)]
+        smoothness: f64,
+    },
+
+    // piecewise linear remap, points are (in, out) sorted by `in`
+    Curve {
+        input: String,
+        points: Vec<(f64, f64)>,
+    },
+
+    ScaleBias {
+        input: String,
+        scale: f64,
+        bias: f64,
+    },
+
+    Clamp {
+        input: String,
+        min: f64,
+        max: f64,
+    },
+
+    Blend {
+        a: String,
+        b: String,
+        op: BlendOp,
+    },
+
+    Lerp {
+        a: String,
+        b: String,
+        mask: String,
+    },
+
+    // the current terrain height, normalized
+    ExistingHeight,
+
+    // 1.0 where the current terrain height is within min..max, fading out over `falloff`
+    HeightMask {
+        min: f64,
+        max: f64,
+        #[serde(default)]
+        falloff: f64,
+    },
+
+    // 1.0 inside the painted region, 0.0 elsewhere
+    RegionMask {
+        region_index: u8,
+    },
+}
+
+impl TerrainGeneratorGraph {
+    pub fn load_from_file<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("could not read generator graph {:?}", path))?;
+
+        ron::from_str(&contents).with_context(|| format!("could not parse generator graph {:?}", path))
+    }
+
+    /// `seed` is added to the seed of every noise source so one graph can produce many terrains.
+    pub fn compile(&self, seed: u32) -> anyhow::Result<CompiledGeneratorGraph> {
+        let mut names: Vec<&String> = self.nodes.keys().collect();
+        names.sort();
+
+        let index_of: HashMap<&String, usize> =
+            names.iter().enumerate().map(|(i, name)| (*name, i)).collect();
+
+        let lookup = |owner: &str, name: &String| {
+            index_of
+                .get(name)
+                .copied()
+                .ok_or_else(|| anyhow!("node {:?} references unknown node {:?}", owner, name))
+        };
+
+        let mut compiled_nodes = Vec::with_capacity(names.len());
+        for name in names.iter() {
+            let node = &self.nodes[*name];
+
+            let compiled = match node {
+                GeneratorNode::Constant(value) => CompiledNode::Constant(*value),
+
+                GeneratorNode::Perlin(settings) => CompiledNode::Source(NoiseSource::perlin(settings, seed)),
+                GeneratorNode::Simplex(settings) => CompiledNode::Source(NoiseSource::simplex(settings, seed)),
+                GeneratorNode::Worley(settings) => CompiledNode::Source(NoiseSource::worley(settings, seed)),
+
+                GeneratorNode::DomainWarp { input, warp_x, warp_y, strength } => CompiledNode::DomainWarp {
+                    input: lookup(name, input)?,
+                    warp_x: lookup(name, warp_x)?,
+                    warp_y: lookup(name, warp_y)?,
+                    strength: *strength,
+                },
+
+                GeneratorNode::Terraces { input, steps, smoothness } => CompiledNode::Terraces {
+                    input: lookup(name, input)?,
+                    steps: (*steps).max(1),
+                    smoothness: smoothness.clamp(0.0, 1.0),
+                },
+
+                GeneratorNode::Curve { input, points } => {
+                    if points.is_empty() {
+                        bail!("curve node {:?} has no points", name);
+                    }
+
+                    let mut points = points.clone();
+                    points.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+                    CompiledNode::Curve {
+                        input: lookup(name, input)?,
+                        points,
+                    }
+                }
+
+                GeneratorNode::ScaleBias { input, scale, bias } => CompiledNode::ScaleBias {
+                    input: lookup(name, input)?,
+                    scale: *scale,
+                    bias: *bias,
+                },
+
+                GeneratorNode::Clamp { input, min, max } => CompiledNode::Clamp {
+                    input: lookup(name, input)?,
+                    min: *min,
+                    max: *max,
+                },
+
+                GeneratorNode::Blend { a, b, op } => CompiledNode::Blend {
+                    a: lookup(name, a)?,
+                    b: lookup(name, b)?,
+                    op: op.clone(),
+                },
+
+                GeneratorNode::Lerp { a, b, mask } => CompiledNode::Lerp {
+                    a: lookup(name, a)?,
+                    b: lookup(name, b)?,
+                    mask: lookup(name, mask)?,
+                },
+
+                GeneratorNode::ExistingHeight => CompiledNode::ExistingHeight,
+
+                GeneratorNode::HeightMask { min, max, falloff } => CompiledNode::HeightMask {
+                    min: *min,
+                    max: *max,
+                    falloff: falloff.max(0.0),
+                },
+
+                GeneratorNode::RegionMask { region_index } => CompiledNode::RegionMask {
+                    region_index: *region_index,
+                },
+            };
+
+            compiled_nodes.push(compiled);
+        }
+
+        let output = lookup("output", &self.output)?;
+
+        let mut compiled = CompiledGeneratorGraph {
+            nodes: compiled_nodes,
+            output,
+            tileable: self.tileable,
+            evaluation_orders: HashMap::new(),
+        };
+
+        if let Some(cycle_node) = compiled.find_cycle() {
+            bail!("generator graph has a cycle through node {:?}", names[cycle_node]);
+        }
+
+        compiled.build_evaluation_orders();
+
+        Ok(compiled)
+    }
+}
+
+struct NoiseSource {
+    kind: NoiseKind,
+    frequency: f64,
+    persistence: f64,
+    lacunarity: f64,
+    fractal: FractalKind,
+}
+
+enum NoiseKind {
+    Perlin(Vec<Perlin>),
+    Simplex(Vec<Simplex>),
+    Worley(Vec<Worley>),
+}
+
+impl NoiseSource {
+    fn octave_seeds(settings: &NoiseSettings, seed: u32) -> impl Iterator<Item = u32> {
+        let base_seed = settings.seed.wrapping_add(seed);
+
+        (0..settings.octaves.max(1)).map(move |octave| base_seed.wrapping_add(octave))
+    }
+
+    fn new(settings: &NoiseSettings, kind: NoiseKind) -> Self {
+        Self {
+            kind,
+            frequency: settings.frequency,
+            persistence: settings.persistence,
+            lacunarity: settings.lacunarity,
+            fractal: settings.fractal.clone(),
+        }
+    }
+
+    fn perlin(settings: &NoiseSettings, seed: u32) -> Self {
+        let octaves = Self::octave_seeds(settings, seed).map(Perlin::new).collect();
+        Self::new(settings, NoiseKind::Perlin(octaves))
+    }
+
+    fn simplex(settings: &NoiseSettings, seed: u32) -> Self {
+        let octaves = Self::octave_seeds(settings, seed).map(Simplex::new).collect();
+        Self::new(settings, NoiseKind::Simplex(octaves))
+    }
+
+    fn worley(settings: &NoiseSettings, seed: u32) -> Self {
+        let octaves = Self::octave_seeds(settings, seed)
+            .map(|seed| Worley::new(seed).set_return_type(noise::core::worley::ReturnType::Distance))
+            .collect();
+        Self::new(settings, NoiseKind::Worley(octaves))
+    }
+
+    fn octave_count(&self) -> usize {
+        match &self.kind {
+            NoiseKind::Perlin(octaves) => octaves.len(),
+            NoiseKind::Simplex(octaves) => octaves.len(),
+            NoiseKind::Worley(octaves) => octaves.len(),
+        }
+    }
+
+    // raw noise in -1..1
+    fn raw(&self, octave: usize, position: NoisePosition) -> f64 {
+        match (&self.kind, position) {
+            (NoiseKind::Perlin(octaves), NoisePosition::Plane(p)) => octaves[octave].get(p),
+            (NoiseKind::Perlin(octaves), NoisePosition::Torus(p)) => octaves[octave].get(p),
+            (NoiseKind::Simplex(octaves), NoisePosition::Plane(p)) => octaves[octave].get(p),
+            (NoiseKind::Simplex(octaves), NoisePosition::Torus(p)) => octaves[octave].get(p),
+            (NoiseKind::Worley(octaves), NoisePosition::Plane(p)) => octaves[octave].get(p),
+            (NoiseKind::Worley(octaves), NoisePosition::Torus(p)) => octaves[octave].get(p),
+        }
+    }
+
+    fn sample(&self, world_position: DVec2, period: Option<DVec2>) -> f64 {
+        let mut total = 0.0;
+        let mut amplitude = 1.0;
+        let mut max_amplitude = 0.0;
+        let mut frequency = self.frequency;
+
+        for octave in 0..self.octave_count() {
+            let position = NoisePosition::new(world_position, frequency, period);
+            let raw = self.raw(octave, position).clamp(-1.0, 1.0);
+
+            let shaped = match self.fractal {
+                FractalKind::Fbm => (raw + 1.0) * 0.5,
+                FractalKind::Ridged => {
+                    let ridge = 1.0 - raw.abs();
+                    ridge * ridge
+                }
+                FractalKind::Billow => raw.abs(),
+            };
+
+            total += shaped * amplitude;
+            max_amplitude += amplitude;
+
+            amplitude *= self.persistence;
+            frequency *= self.lacunarity;
+        }
+
+        if max_amplitude > 0.0 { total / max_amplitude } else { 0.0 }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum NoisePosition {
+    Plane([f64; 2]),
+    Torus([f64; 4]),
+}
+
+impl NoisePosition {
+    fn new(world_position: DVec2, frequency: f64, period: Option<DVec2>) -> Self {
+        let Some(period) = period else {
+            let p = world_position * frequency;
+            return Self::Plane([p.x, p.y]);
+        };
+
+        // map each axis onto a circle whose circumference keeps the feature size of the plane
+        let angle = world_position / period * TAU;
+        let radius = period * frequency / TAU;
+
+        Self::Torus([
+            angle.x.cos() * radius.x,
+            angle.x.sin() * radius.x,
+            angle.y.cos() * radius.y,
+            angle.y.sin() * radius.y,
+        ])
+    }
+}
+
+enum CompiledNode {
+    Constant(f64),
+    Source(NoiseSource),
+    DomainWarp { input: usize, warp_x: usize, warp_y: usize, strength: f64 },
+    Terraces { input: usize, steps: u32, smoothness: f64 },
+    Curve { input: usize, points: Vec<(f64, f64)> },
+    ScaleBias { input: usize, scale: f64, bias: f64 },
+    Clamp { input: usize, min: f64, max: f64 },
+    Blend { a: usize, b: usize, op: BlendOp },
+    Lerp { a: usize, b: usize, mask: usize },
+    ExistingHeight,
+    HeightMask { min: f64, max: f64, falloff: f64 },
+    RegionMask { region_index: u8 },
+}
+
+impl CompiledNode {
+    // inputs sampled at the same position as the node itself.
+    // a domain warp samples its `input` somewhere else, so that one is evaluated separately
+    fn same_position_inputs(&self) -> Vec<usize> {
+        match self {
+            Self::DomainWarp { warp_x, warp_y, .. } => vec![*warp_x, *warp_y],
+            _ => self.inputs(),
+        }
+    }
+
+    fn inputs(&self) -> Vec<usize> {
+        match self {
+            Self::DomainWarp { input, warp_x, warp_y, .. } => vec![*input, *warp_x, *warp_y],
+            Self::Terraces { input, .. }
+            | Self::Curve { input, .. }
+            | Self::ScaleBias { input, .. }
+            | Self::Clamp { input, .. } => vec![*input],
+            Self::Blend { a, b, .. } => vec![*a, *b],
+            Self::Lerp { a, b, mask } => vec![*a, *b, *mask],
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Read-only terrain data the graph can sample (existing heights, regions).
+pub struct GeneratorContext<'a> {
+    pub terrain_size: DVec2,
+    pub chunk_rows: u32,
+    pub chunk_dimensions: UVec2,
+
+    pub existing_heights: Option<&'a BevyHashMap<u32, HeightMapU16>>,
+
+    // assumed to be one texel per world unit, like the heightmap
+    pub regions: Option<&'a Vec<Vec<u8>>>,
+}
+
+impl GeneratorContext<'_> {
+    fn existing_height(&self, world_position: DVec2) -> f64 {
+        let Some(existing_heights) = self.existing_heights else {
+            return 0.0;
+        };
+
+        let texel = world_position.max(DVec2::ZERO).as_uvec2();
+        let chunk_coords = texel / self.chunk_dimensions.max(UVec2::ONE);
+        let chunk_id = ChunkCoords::new(chunk_coords.x, chunk_coords.y).get_chunk_index(self.chunk_rows);
+
+        let local = texel - chunk_coords * self.chunk_dimensions;
+
+        existing_heights
+            .get(&chunk_id)
+            .and_then(|height_map| height_map.get(local.y as usize)?.get(local.x as usize))
+            .map(|height| *height as f64 / u16::MAX as f64)
+            .unwrap_or(0.0)
+    }
+
+    fn region_at(&self, world_position: DVec2) -> Option<u8> {
+        let texel = world_position.max(DVec2::ZERO).as_uvec2();
+
+        self.regions?
+            .get(texel.y as usize)?
+            .get(texel.x as usize)
+            .copied()
+    }
+}
+
+pub struct CompiledGeneratorGraph {
+    nodes: Vec<CompiledNode>,
+    output: usize,
+    tileable: bool,
+
+    // for the output and every domain warp input: the nodes it depends on, inputs first
+    evaluation_orders: HashMap<usize, Vec<usize>>,
+}
+
+impl CompiledGeneratorGraph {
+    fn find_cycle(&self) -> Option<usize> {
+        // 0 = unvisited, 1 = on the stack, 2 = done
+        let mut marks = vec![0u8; self.nodes.len()];
+
+        fn visit(graph: &CompiledGeneratorGraph, node: usize, marks: &mut [u8]) -> Option<usize> {
+            match marks[node] {
+                1 => return Some(node),
+                2 => return None,
+                _ => {}
+            }
+
+            marks[node] = 1;
+            for input in graph.nodes[node].inputs() {
+                if let Some(cycle) = visit(graph, input, marks) {
+                    return Some(cycle);
+                }
+            }
+            marks[node] = 2;
+
+            None
+        }
+
+        (0..self.nodes.len()).find_map(|node| visit(self, node, &mut marks))
+    }
+
+    fn build_evaluation_orders(&mut self) {
+        let mut roots = vec![self.output];
+
+        while let Some(root) = roots.pop() {
+            if self.evaluation_orders.contains_key(&root) {
+                continue;
+            }
+
+            let order = self.evaluation_order(root);
+
+            for node in order.iter() {
+                if let CompiledNode::DomainWarp { input, .. } = &self.nodes[*node] {
+                    roots.push(*input);
+                }
+            }
+
+            self.evaluation_orders.insert(root, order);
+        }
+    }
+
+    // post order walk, so every node comes after the inputs it reads. needs an acyclic graph
+    fn evaluation_order(&self, root: usize) -> Vec<usize> {
+        let mut visited = vec![false; self.nodes.len()];
+        let mut order = Vec::new();
+
+        fn visit(graph: &CompiledGeneratorGraph, node: usize, visited: &mut [bool], order: &mut Vec<usize>) {
+            if visited[node] {
+                return;
+            }
+            visited[node] = true;
+
+            for input in graph.nodes[node].same_position_inputs() {
+                visit(graph, input, visited, order);
+            }
+            order.push(node);
+        }
+
+        visit(self, root, &mut visited, &mut order);
+        order
+    }
+
+    pub fn sample(&self, world_position: DVec2, context: &GeneratorContext) -> f64 {
+        self.evaluate(self.output, world_position, context).clamp(0.0, 1.0)
+    }
+
+    // every node that `root` depends on is evaluated once per position, shared inputs are reused
+    fn evaluate(&self, root: usize, p: DVec2, context: &GeneratorContext) -> f64 {
+        let Some(order) = self.evaluation_orders.get(&root) else {
+            return 0.0;
+        };
+
+        let mut values = vec![0.0; self.nodes.len()];
+        for node in order.iter() {
+            values[*node] = self.evaluate_node(*node, p, &values, context);
+        }
+
+        values[root]
+    }
+
+    fn evaluate_node(&self, node: usize, p: DVec2, values: &[f64], context: &GeneratorContext) -> f64 {
+        let period = self.tileable.then_some(context.terrain_size);
+
+        match &self.nodes[node] {
+            CompiledNode::Constant(value) => *value,
+
+            CompiledNode::Source(source) => source.sample(p, period),
+
+            CompiledNode::DomainWarp { input, warp_x, warp_y, strength } => {
+                // warp sources are 0..1, recentre them so the warp is symmetric
+                let offset = DVec2::new(values[*warp_x] - 0.5, values[*warp_y] - 0.5) * 2.0 * *strength;
+
+                self.evaluate(*input, p + offset, context)
+            }
+
+            CompiledNode::Terraces { input, steps, smoothness } => {
+                let value = values[*input] * *steps as f64;
+                let step = value.floor();
+                let fraction = value - step;
+
+                // smoothness 0 gives hard steps, 1 gives the original slope back
+                let edge = 1.0 - smoothness;
+                let blended = if fraction < edge { 0.0 } else { (fraction - edge) / smoothness.max(1e-6) };
+
+                (step + blended.clamp(0.0, 1.0)) / *steps as f64
+            }
+
+            CompiledNode::Curve { input, points } => evaluate_curve(points, values[*input]),
+
+            CompiledNode::ScaleBias { input, scale, bias } => values[*input] * scale + bias,
+
+            CompiledNode::Clamp { input, min, max } => values[*input].clamp(*min, *max),
+
+            CompiledNode::Blend { a, b, op } => {
+                let (a, b) = (values[*a], values[*b]);
+
+                match op {
+                    BlendOp::Add => a + b,
+                    BlendOp::Subtract => a - b,
+                    BlendOp::Multiply => a * b,
+                    BlendOp::Min => a.min(b),
+                    BlendOp::Max => a.max(b),
+                    BlendOp::Average => (a + b) * 0.5,
+                }
+            }
+
+            CompiledNode::Lerp { a, b, mask } => {
+                let t = values[*mask].clamp(0.0, 1.0);
+                let (a, b) = (values[*a], values[*b]);
+
+                a + (b - a) * t
+            }
+
+            CompiledNode::ExistingHeight => context.existing_height(p),
+
+            CompiledNode::HeightMask { min, max, falloff } => {
+                let height = context.existing_height(p);
+
+                let distance = if height < *min {
+                    min - height
+                } else if height > *max {
+                    height - max
+                } else {
+                    0.0
+                };
+
+                if distance <= 0.0 {
+                    1.0
+                } else if *falloff <= 0.0 {
+                    0.0
+                } else {
+                    (1.0 - distance / falloff).max(0.0)
+                }
+            }
+
+            CompiledNode::RegionMask { region_index } => {
+                if context.region_at(p) == Some(*region_index) { 1.0 } else { 0.0 }
+            }
+        }
+    }
+
+    /// Generates one chunk heightmap. Positions are in world units (one texel per unit).
+    pub fn generate_chunk(&self, chunk_id: u32, context: &GeneratorContext) -> HeightMapU16 {
+        let chunk_coords = ChunkCoords::from_chunk_id(chunk_id, context.chunk_rows);
+        let dimensions = context.chunk_dimensions;
+
+        let origin = DVec2::new(
+            (chunk_coords.x() * dimensions.x) as f64,
+            (chunk_coords.y() * dimensions.y) as f64,
+        );
+
+        (0..dimensions.y)
+            .map(|y| {
+                (0..dimensions.x)
+                    .map(|x| {
+                        let value = self.sample(origin + DVec2::new(x as f64, y as f64), context);
+                        (value * u16::MAX as f64).round() as u16
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Low resolution grayscale preview of a world-space rectangle, row major.
+    pub fn generate_preview(
+        &self,
+        min: DVec2,
+        max: DVec2,
+        resolution: u32,
+        context: &GeneratorContext,
+    ) -> Vec<u8> {
+        let resolution = resolution.max(1);
+        let step = (max - min) / resolution as f64;
+
+        let mut pixels = Vec::with_capacity((resolution * resolution) as usize);
+        for y in 0..resolution {
+            for x in 0..resolution {
+                let p = min + step * DVec2::new(x as f64 + 0.5, y as f64 + 0.5);
+                pixels.push((self.sample(p, context) * 255.0).round() as u8);
+            }
+        }
+        pixels
+    }
+}
+
+fn evaluate_curve(points: &[(f64, f64)], value: f64) -> f64 {
+    let first = points[0];
+    let last = points[points.len() - 1];
+
+    if value <= first.0 {
+        return first.1;
+    }
+    if value >= last.0 {
+        return last.1;
+    }
+
+    for window in points.windows(2) {
+        let (a, b) = (window[0], window[1]);
+
+        if value <= b.0 {
+            let span = (b.0 - a.0).max(1e-9);
+            return a.1 + (b.1 - a.1) * (value - a.0) / span;
+        }
+    }
+
+    last.1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_context() -> GeneratorContext<'static> {
+        GeneratorContext {
+            terrain_size: DVec2::new(512.0, 512.0),
+            chunk_rows: 2,
+            chunk_dimensions: UVec2::new(256, 256),
+            existing_heights: None,
+            regions: None,
+        }
+    }
+
+    fn parse(source: &str) -> TerrainGeneratorGraph {
+        ron::from_str(source).expect("graph should parse")
+    }
+
+    #[test]
+    fn test_parse_graph() {
+        let graph = parse(
+            r#"(
+                output: "final",
+                nodes: {
+                    "base": Perlin(( frequency: 0.004, octaves: 5 )),
+                    "ridges": Simplex(( frequency: 0.01, fractal: Ridged, seed: 7 )),
+                    "mixed": Lerp( a: "base", b: "ridges", mask: "base" ),
+                    "final": Terraces( input: "mixed", steps: 8, smoothness: 0.6 ),
+                },
+            )"#,
+        );
+
+        assert!(!graph.tileable);
+        assert_eq!(graph.nodes.len(), 4);
+
+        let GeneratorNode::Simplex(settings) = &graph.nodes["ridges"] else {
+            panic!("ridges should be a simplex node");
+        };
+        assert_eq!(settings.fractal, FractalKind::Ridged);
+        assert_eq!(settings.octaves, 1);
+        assert_eq!(settings.seed, 7);
+
+        assert!(graph.compile(0).is_ok());
+    }
+
+    #[test]
+    fn test_unknown_node_is_rejected() {
+        let graph = parse(
+            r#"(
+                output: "final",
+                nodes: {
+                    "final": ScaleBias( input: "missing", scale: 1.0, bias: 0.0 ),
+                },
+            )"#,
+        );
+
+        assert!(graph.compile(0).is_err());
+    }
+
+    #[test]
+    fn test_cycle_is_rejected() {
+        let graph = parse(
+            r#"(
+                output: "a",
+                nodes: {
+                    "a": ScaleBias( input: "b", scale: 1.0, bias: 0.0 ),
+                    "b": Blend( a: "c", b: "a", op: Add ),
+                    "c": Constant(0.5),
+                },
+            )"#,
+        );
+
+        let error = graph.compile(0).err().expect("cycle should be rejected");
+        assert!(error.to_string().contains("cycle"));
+    }
+
+    #[test]
+    fn test_cycle_through_domain_warp_input_is_rejected() {
+        let graph = parse(
+            r#"(
+                output: "warped",
+                nodes: {
+                    "warped": DomainWarp( input: "warped", warp_x: "c", warp_y: "c", strength: 4.0 ),
+                    "c": Constant(0.5),
+                },
+            )"#,
+        );
+
+        assert!(graph.compile(0).is_err());
+    }
+
+    #[test]
+    fn test_shared_inputs_are_evaluated_in_order() {
+        let graph = parse(
+            r#"(
+                output: "sum",
+                nodes: {
+                    "half": Constant(0.25),
+                    "scaled": ScaleBias( input: "half", scale: 2.0, bias: 0.0 ),
+                    "sum": Blend( a: "scaled", b: "half", op: Add ),
+                },
+            )"#,
+        );
+        let compiled = graph.compile(0).unwrap();
+
+        let order = &compiled.evaluation_orders[&compiled.output];
+        assert_eq!(order.len(), 3);
+        assert_eq!(*order.last().unwrap(), compiled.output);
+
+        let value = compiled.sample(DVec2::new(10.0, 20.0), &test_context());
+        assert!((value - 0.75).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_domain_warp_samples_input_at_offset() {
+        let graph = parse(
+            r#"(
+                output: "warped",
+                nodes: {
+                    "noise": Perlin(( frequency: 0.01 )),
+                    "right": Constant(1.0),
+                    "centre": Constant(0.5),
+                    "warped": DomainWarp( input: "noise", warp_x: "right", warp_y: "centre", strength: 8.0 ),
+                },
+            )"#,
+        );
+        let compiled = graph.compile(3).unwrap();
+        let context = test_context();
+
+        let p = DVec2::new(37.0, 91.0);
+        let noise = compiled.nodes.iter().position(|node| matches!(node, CompiledNode::Source(_))).unwrap();
+
+        let warped = compiled.sample(p, &context);
+        let expected = compiled.evaluate(noise, p + DVec2::new(8.0, 0.0), &context).clamp(0.0, 1.0);
+        assert!((warped - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_tileable_graph_wraps_around() {
+        let graph = parse(
+            r#"(
+                tileable: true,
+                output: "base",
+                nodes: {
+                    "base": Perlin(( frequency: 0.02, octaves: 3 )),
+                },
+            )"#,
+        );
+        let compiled = graph.compile(11).unwrap();
+        let context = test_context();
+
+        for p in [DVec2::new(0.0, 0.0), DVec2::new(13.0, 200.0), DVec2::new(480.5, 7.25)] {
+            let value = compiled.sample(p, &context);
+
+            let wrapped_x = compiled.sample(p + DVec2::new(context.terrain_size.x, 0.0), &context);
+            let wrapped_y = compiled.sample(p + DVec2::new(0.0, context.terrain_size.y), &context);
+
+            assert!((value - wrapped_x).abs() < 1e-6);
+            assert!((value - wrapped_y).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_torus_keeps_feature_size() {
+        let period = DVec2::new(512.0, 512.0);
+        let frequency = 0.02;
+
+        let NoisePosition::Torus(a) = NoisePosition::new(DVec2::ZERO, frequency, Some(period)) else {
+            panic!("tileable positions should be on the torus");
+        };
+        let NoisePosition::Torus(b) = NoisePosition::new(DVec2::new(1.0, 0.0), frequency, Some(period)) else {
+            panic!("tileable positions should be on the torus");
+        };
+
+        // one world unit along x moves about `frequency` in noise space, like on the plane
+        let distance = ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2)).sqrt();
+        assert!((distance - frequency).abs() < 1e-6);
+        assert_eq!((a[2], a[3]), (b[2], b[3]));
+    }
+
+    #[test]
+    fn test_generate_chunk_lines_up_with_world_positions() {
+        let graph = parse(
+            r#"(
+                output: "base",
+                nodes: {
+                    "base": Simplex(( frequency: 0.03, octaves: 2 )),
+                },
+            )"#,
+        );
+        let compiled = graph.compile(0).unwrap();
+        let context = GeneratorContext {
+            chunk_dimensions: UVec2::new(8, 8),
+            ..test_context()
+        };
+
+        // chunk 1 is the second chunk of the first row
+        let chunk = compiled.generate_chunk(1, &context);
+        assert_eq!(chunk.len(), 8);
+        assert_eq!(chunk[0].len(), 8);
+
+        let expected = compiled.sample(DVec2::new(8.0 + 3.0, 5.0), &context);
+        assert_eq!(chunk[5][3], (expected * u16::MAX as f64).round() as u16);
+    }
+
+    #[test]
+    fn test_evaluate_curve() {
+        let points = [(0.0, 0.0), (0.5, 1.0), (1.0, 0.0)];
+
+        assert_eq!(evaluate_curve(&points, -1.0), 0.0);
+        assert!((evaluate_curve(&points, 0.25) - 0.5).abs() < 1e-9);
+        assert!((evaluate_curve(&points, 0.75) - 0.5).abs() < 1e-9);
+        assert_eq!(evaluate_curve(&points, 2.0), 0.0);
+    }
+}
@@ -4,6 +4,7 @@
 use degen_toon_terrain::{chunk::ChunkHeightMapResource, terrain::TerrainData, terrain_config::TerrainConfig};
 use bevy::prelude::*;
 use degen_toon_terrain::terrain_loading_state::TerrainLoadingState;
+use spirit_edit_core::zones::zone_validation::ZoneValidationTerrainBounds;
 
 
 pub fn terrain_loading_plugin(app: &mut App){
@@ -15,6 +16,7 @@ pub fn terrain_loading_plugin(app: &mut App){
 	 	update_terrain_load_state.run_if( not(in_state(TerrainLoadingState::Complete)) )
 
 	 	)
+	 .add_systems(Update, update_zone_validation_terrain_bounds)
 	;
 
 }
@@ -49,4 +51,23 @@ fn update_terrain_load_state(
 	}
 	
 
-}
\ No newline at end of file
+}
+
+
+// lets the zone validator flag doodads that were placed off the terrain
+fn update_zone_validation_terrain_bounds(
+	terrain_query: Query< (&TerrainConfig, &GlobalTransform), Or<(Changed<TerrainConfig>, Changed<GlobalTransform>)> > ,
+
+	mut terrain_bounds: ResMut<ZoneValidationTerrainBounds>,
+){
+
+	let Some( (terrain_config, terrain_transform) ) = terrain_query.iter().next() else {return};
+
+	let terrain_origin = terrain_transform.translation().xz();
+
+	terrain_bounds.0 = Some( Rect::from_corners(
+		terrain_origin,
+		terrain_origin + terrain_config.terrain_dimensions
+	) );
+
+}
@@ -8,6 +8,7 @@ use serde::{Serialize,Deserialize} ;
 
  
 use bevy_common_assets::ron::RonAssetPlugin;
+use degen_toon_terrain::auto_splat::AutoSplatRuleSet;
  
 
 pub fn terrain_manifest_plugin(app: &mut App) {
@@ -50,6 +51,10 @@ pub struct TerrainManifestResource {
 #[derive(Asset, TypePath, Clone, Debug, Serialize, Deserialize)]
 pub struct TerrainManifest {
     pub terrain_definitions: Vec<TerrainDefinition>,
+
+    // texture indices refer to terrain_definitions
+    #[serde(default)]
+    pub auto_splat_rules: Option<AutoSplatRuleSet>,
 }
 
 impl TerrainManifest {
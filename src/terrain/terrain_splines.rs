@@ -0,0 +1,1033 @@
+
+use crate::editor_pls::bevy_pls_editor_is_active;
+use crate::editor_state::EditorStateResource;
+use crate::liquid::LiquidPlaneComponent;
+use crate::ui::{EditorToolsState, SubTool};
+use bevy::picking::backend::ray::RayMap;
+use bevy::platform::collections::hash_map::HashMap;
+use bevy::platform::collections::HashSet;
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use degen_toon_terrain::chunk::{Chunk, ChunkCoordinates, ChunkCoords, ChunkData, ChunkHeightMapResource};
+use degen_toon_terrain::edit::TerrainDataReplacedEvent;
+use degen_toon_terrain::erosion::{chunk_dimensions_of, HeightField};
+use degen_toon_terrain::heightmap::HeightMapU16;
+use degen_toon_terrain::splat::{ChunkSplatDataRaw, SplatMapDataUpdated};
+use degen_toon_terrain::terrain::TerrainImageDataLoadStatus;
+use degen_toon_terrain::terrain_config::TerrainConfig;
+use spirit_edit_core::placement::PlacementResource;
+use spirit_edit_core::splines::{
+    sync_spline_from_control_point_handles, SplineControlPoint, SplineControlPointHandle, SplineEvent, SplineLiquid,
+    SplineSample, SplineTerrainMode, TerrainSpline,
+};
+
+/*
+
+Applies the splines of spirit_edit_core (roads, rivers) to the terrain.
+
+Applying a spline flattens or carves the heightmap under it, paints its splat layer and spawns
+a strip of liquid planes for rivers. The original heights and splat pixels under the spline are
+remembered, so an edited spline is re-applied on top of the untouched terrain instead of digging
+deeper every time. That memory only lives for this session, the result is baked once chunks are saved.
+
+Only texels that still hold what the spline wrote are restored. Anything edited over the spline
+afterwards (painting, erosion, another spline) is kept.
+
+*/
+
+// distance between curve samples when rasterizing, in world units
+const SAMPLE_SPACING: f32 = 0.5;
+
+// approximate length of one liquid plane of a river
+const LIQUID_SEGMENT_LENGTH: f32 = 4.0;
+
+pub fn terrain_splines_plugin(app: &mut App){
+
+	app
+		.init_resource::<SplineToolState>()
+
+		.add_message::<ApplySplineEvent>()
+		.add_message::<RevertSplineEvent>()
+
+		.add_systems(Update, (
+			snap_spline_control_points_to_terrain.before( sync_spline_from_control_point_handles ),
+			(reapply_edited_splines, apply_spline, revert_spline).chain(),
+			despawn_orphaned_liquid_segments,
+		))
+
+		.add_systems(Update, (
+			place_spline_control_points,
+			spline_tool_ui,
+		).run_if(not(bevy_pls_editor_is_active)))
+
+	;
+
+}
+
+/// Restores the terrain under the spline and applies it again.
+#[derive(Message, Clone)]
+pub struct ApplySplineEvent {
+    pub spline_entity: Entity,
+}
+
+/// Restores the terrain under the spline without applying it.
+#[derive(Message, Clone)]
+pub struct RevertSplineEvent {
+    pub spline_entity: Entity,
+}
+
+#[derive(Resource)]
+pub struct SplineToolState {
+    // clicking the terrain adds control points to this spline, or creates a new one when empty
+    pub active_spline: Option<Entity>,
+    // settings for the next new spline
+    pub template: TerrainSpline,
+
+    pub snap_to_terrain: bool,
+    pub auto_reapply: bool,
+}
+
+impl Default for SplineToolState {
+    fn default() -> Self {
+        Self {
+            active_spline: None,
+            template: TerrainSpline::default(),
+            snap_to_terrain: true,
+            auto_reapply: true,
+        }
+    }
+}
+
+/// What applying the spline changed, so it can be undone before applying it again.
+#[derive(Component, Default)]
+pub struct AppliedSplineFootprint {
+    // terrain texel -> height before the spline and the height it wrote
+    original_heights: HashMap<UVec2, FootprintValue<u16>>,
+    // (chunk id, splat pixel) -> RGB before the spline and the RGB it wrote
+    original_splat: HashMap<(u32, UVec2), FootprintValue<[u8; 3]>>,
+
+    liquid_segments: Vec<Entity>,
+}
+
+#[derive(Clone, Copy)]
+struct FootprintValue<T> {
+    original: T,
+    written: T,
+}
+
+#[derive(Component)]
+pub struct SplineLiquidSegment {
+    pub spline: Entity,
+}
+
+// applied again once the mouse is released, so dragging a control point does not rebuild chunks every frame
+#[derive(Component)]
+struct SplineNeedsReapply;
+
+struct TerrainLayout {
+    chunk_rows: u32,
+    chunk_dimensions: UVec2,
+    terrain_size: UVec2,
+    height_scale: f32,
+
+    // world position of terrain texel (0,0)
+    origin: Vec3,
+}
+
+impl TerrainLayout {
+    fn new(terrain_config: &TerrainConfig, chunk_transforms: &Query<(&Chunk, &GlobalTransform)>) -> Option<Self> {
+        let chunk_dimensions = chunk_dimensions_of(terrain_config);
+
+        let origin = chunk_transforms.iter().next().map(|(chunk, transform)| {
+            let chunk_coords = ChunkCoords::from_chunk_id(chunk.chunk_id, terrain_config.chunk_rows);
+            let chunk_offset = UVec2::new(chunk_coords.x(), chunk_coords.y()) * chunk_dimensions;
+
+            transform.translation() - Vec3::new(chunk_offset.x as f32, 0.0, chunk_offset.y as f32)
+        })?;
+
+        Some(Self {
+            chunk_rows: terrain_config.chunk_rows,
+            chunk_dimensions,
+            terrain_size: chunk_dimensions * terrain_config.chunk_rows,
+            height_scale: terrain_config.height_scale,
+            origin,
+        })
+    }
+
+    fn terrain_position(&self, world_position: Vec3) -> Vec2 {
+        Vec2::new(world_position.x - self.origin.x, world_position.z - self.origin.z)
+    }
+
+    fn texel_to_chunk(&self, texel: UVec2) -> (u32, UVec2) {
+        let chunk_coords = texel / self.chunk_dimensions;
+        let chunk_id = ChunkCoords::new(chunk_coords.x, chunk_coords.y).get_chunk_index(self.chunk_rows);
+
+        (chunk_id, texel - chunk_coords * self.chunk_dimensions)
+    }
+
+    fn texel_mut<'a>(&self, chunk_height_maps: &'a mut HashMap<u32, HeightMapU16>, texel: UVec2) -> Option<&'a mut u16> {
+        let (chunk_id, local) = self.texel_to_chunk(texel);
+
+        chunk_height_maps
+            .get_mut(&chunk_id)?
+            .get_mut(local.y as usize)?
+            .get_mut(local.x as usize)
+    }
+
+    // world height of the terrain, ignoring what splines did to it when a footprint is given
+    fn sample_height(
+        &self,
+        chunk_height_maps: &HashMap<u32, HeightMapU16>,
+        terrain_position: Vec2,
+        footprint: Option<&AppliedSplineFootprint>,
+    ) -> Option<f32> {
+        let max_texel = self.terrain_size.saturating_sub(UVec2::ONE).as_vec2();
+        let position = terrain_position.clamp(Vec2::ZERO, max_texel);
+
+        let texel_height = |texel: UVec2| -> Option<f32> {
+            let (chunk_id, local) = self.texel_to_chunk(texel);
+            let current = *chunk_height_maps.get(&chunk_id)?.get(local.y as usize)?.get(local.x as usize)?;
+
+            let height = match footprint.and_then(|f| f.original_heights.get(&texel)) {
+                Some(value) if value.written == current => value.original,
+                _ => current,
+            };
+
+            Some(height as f32 / u16::MAX as f32 * self.height_scale)
+        };
+
+        let cell = position.floor();
+        let next = (cell + Vec2::ONE).min(max_texel);
+        let offset = position - cell;
+
+        let h00 = texel_height(cell.as_uvec2())?;
+        let h10 = texel_height(UVec2::new(next.x as u32, cell.y as u32))?;
+        let h01 = texel_height(UVec2::new(cell.x as u32, next.y as u32))?;
+        let h11 = texel_height(next.as_uvec2())?;
+
+        let height = h00.lerp(h10, offset.x).lerp(h01.lerp(h11, offset.x), offset.y);
+
+        Some(self.origin.y + height)
+    }
+}
+
+/// How strongly the spline covers each terrain texel of a rectangle, and the curve height there.
+struct SplineCoverage {
+    origin: UVec2,
+    size: UVec2,
+
+    weights: Vec<f32>,
+    heights: Vec<f32>,
+}
+
+impl SplineCoverage {
+    fn rasterize(samples: &[SplineSample], falloff: f32, layout: &TerrainLayout) -> Option<Self> {
+        let falloff = falloff.max(0.0);
+        let max_width = samples.iter().map(|s| s.width).fold(0.0, f32::max);
+        let reach = Vec2::splat(max_width * 0.5 + falloff + 1.0);
+
+        let points: Vec<Vec2> = samples.iter().map(|s| layout.terrain_position(s.position)).collect();
+
+        let min = points.iter().fold(Vec2::MAX, |acc, p| acc.min(*p)) - reach;
+        let max = points.iter().fold(Vec2::MIN, |acc, p| acc.max(*p)) + reach;
+
+        let terrain_size = layout.terrain_size.as_vec2();
+        let min = min.floor().clamp(Vec2::ZERO, terrain_size);
+        let max = max.ceil().clamp(Vec2::ZERO, terrain_size);
+
+        let origin = min.as_uvec2();
+        let size = (max - min).as_uvec2();
+        if size.x == 0 || size.y == 0 {
+            return None;
+        }
+
+        let len = (size.x * size.y) as usize;
+        let mut coverage = Self {
+            origin,
+            size,
+            weights: vec![0.0; len],
+            heights: vec![0.0; len],
+        };
+
+        for index in 0..points.len().saturating_sub(1) {
+            let (a, b) = (points[index], points[index + 1]);
+            let (start, end) = (&samples[index], &samples[index + 1]);
+
+            let segment_reach = Vec2::splat(start.width.max(end.width) * 0.5 + falloff + 1.0);
+            let segment_min = ((a.min(b) - segment_reach).floor() - origin.as_vec2()).max(Vec2::ZERO).as_uvec2();
+            let segment_max = ((a.max(b) + segment_reach).ceil() - origin.as_vec2())
+                .min(size.as_vec2())
+                .max(Vec2::ZERO)
+                .as_uvec2();
+
+            let ab = b - a;
+            let length_squared = ab.length_squared();
+
+            for y in segment_min.y..segment_max.y {
+                for x in segment_min.x..segment_max.x {
+                    let position = (origin + UVec2::new(x, y)).as_vec2();
+
+                    let t = if length_squared > 0.0 {
+                        ((position - a).dot(ab) / length_squared).clamp(0.0, 1.0)
+                    } else {
+                        0.0
+                    };
+
+                    let distance = position.distance(a + ab * t);
+                    let half_width = start.width.lerp(end.width, t) * 0.5;
+
+                    let weight = if distance <= half_width {
+                        1.0
+                    } else if falloff > 0.0 && distance < half_width + falloff {
+                        // smooth embankment back into the untouched terrain
+                        let s = 1.0 - (distance - half_width) / falloff;
+                        s * s * (3.0 - 2.0 * s)
+                    } else {
+                        0.0
+                    };
+
+                    let cell = (y * size.x + x) as usize;
+                    if weight > coverage.weights[cell] {
+                        coverage.weights[cell] = weight;
+                        coverage.heights[cell] = start.position.y.lerp(end.position.y, t);
+                    }
+                }
+            }
+        }
+
+        Some(coverage)
+    }
+
+    fn get(&self, terrain_position: Vec2) -> Option<(f32, f32)> {
+        let local = terrain_position.floor() - self.origin.as_vec2();
+        if local.x < 0.0 || local.y < 0.0 || local.x >= self.size.x as f32 || local.y >= self.size.y as f32 {
+            return None;
+        }
+
+        let cell = (local.y as u32 * self.size.x + local.x as u32) as usize;
+        Some((self.weights[cell], self.heights[cell]))
+    }
+}
+
+// puts the terrain back the way it was before the footprint was applied
+fn restore_footprint(
+    commands: &mut Commands,
+    footprint: &mut AppliedSplineFootprint,
+    layout: &TerrainLayout,
+    chunk_height_maps: &mut HashMap<u32, HeightMapU16>,
+    chunk_splat_query: &mut Query<(Entity, &Chunk, &mut ChunkSplatDataRaw)>,
+    changed_height_chunks: &mut HashSet<u32>,
+) {
+    for (texel, value) in footprint.original_heights.drain() {
+        if let Some(current) = layout.texel_mut(chunk_height_maps, texel) {
+            // edited since the spline was applied, keep the edit
+            if *current != value.written {
+                continue;
+            }
+
+            if *current != value.original {
+                *current = value.original;
+                changed_height_chunks.insert(layout.texel_to_chunk(texel).0);
+            }
+        }
+    }
+
+    if !footprint.original_splat.is_empty() {
+        for (chunk_entity, chunk, mut splat_data) in chunk_splat_query.iter_mut() {
+            let mut changed = false;
+
+            for ((chunk_id, pixel), value) in footprint.original_splat.iter() {
+                if *chunk_id != chunk.chunk_id {
+                    continue;
+                }
+
+                let current =
+                    [0, 1, 2].map(|layer| splat_data.get_pixel_index_map_data(pixel.x, pixel.y, layer).unwrap_or(0));
+                if current != value.written {
+                    continue;
+                }
+
+                for (layer, original) in value.original.iter().enumerate() {
+                    splat_data.set_pixel_index_map_data(pixel.x, pixel.y, layer as u8, *original);
+                }
+                changed = true;
+            }
+
+            if changed {
+                if let Ok(mut cmds) = commands.get_entity(chunk_entity) {
+                    cmds.try_insert(SplatMapDataUpdated);
+                }
+            }
+        }
+
+        footprint.original_splat.clear();
+    }
+
+    for segment in footprint.liquid_segments.drain(..) {
+        if let Ok(mut cmds) = commands.get_entity(segment) {
+            cmds.despawn();
+        }
+    }
+}
+
+fn mark_chunks_for_reload(changed_chunks: &HashSet<u32>, chunk_data_query: &mut Query<(&Chunk, &mut ChunkData)>) {
+    for (chunk, mut chunk_data) in chunk_data_query.iter_mut() {
+        if changed_chunks.contains(&chunk.chunk_id) {
+            chunk_data.height_map_image_data_load_status = TerrainImageDataLoadStatus::NeedsReload;
+        }
+    }
+}
+
+fn apply_spline(
+    mut evt_reader: MessageReader<ApplySplineEvent>,
+
+    mut commands: Commands,
+
+    mut spline_query: Query<(&TerrainSpline, &GlobalTransform, Option<&mut AppliedSplineFootprint>)>,
+
+    terrain_query: Query<&TerrainConfig>,
+    chunk_transform_query: Query<(&Chunk, &GlobalTransform)>,
+    mut chunk_data_query: Query<(&Chunk, &mut ChunkData)>,
+    mut chunk_splat_query: Query<(Entity, &Chunk, &mut ChunkSplatDataRaw)>,
+
+    mut chunk_height_maps: ResMut<ChunkHeightMapResource>,
+) {
+    // the footprint is taken out of the spline and inserted back with commands, so each spline once per frame
+    let spline_entities: HashSet<Entity> = evt_reader.read().map(|evt| evt.spline_entity).collect();
+
+    if spline_entities.is_empty() {
+        return;
+    }
+
+    let Some(terrain_config) = terrain_query.iter().next() else {
+        warn!("cannot apply spline: no terrain loaded");
+        return;
+    };
+
+    let Some(layout) = TerrainLayout::new(terrain_config, &chunk_transform_query) else {
+        return;
+    };
+
+    for spline_entity in spline_entities {
+        let Ok((spline, spline_transform, existing_footprint)) = spline_query.get_mut(spline_entity) else {
+            continue;
+        };
+
+        let chunk_height_maps = &mut chunk_height_maps.chunk_height_maps;
+        let mut changed_height_chunks = HashSet::new();
+
+        let mut footprint = existing_footprint.map(|mut f| std::mem::take(&mut *f)).unwrap_or_default();
+
+        restore_footprint(
+            &mut commands,
+            &mut footprint,
+            &layout,
+            chunk_height_maps,
+            &mut chunk_splat_query,
+            &mut changed_height_chunks,
+        );
+
+        let samples = spline.sample_world(spline_transform, SAMPLE_SPACING);
+
+        let coverage = match samples.len() {
+            0 | 1 => None,
+            _ => SplineCoverage::rasterize(&samples, spline.falloff, &layout),
+        };
+
+        if let Some(coverage) = &coverage {
+            let depth = match spline.terrain_mode {
+                SplineTerrainMode::None => None,
+                SplineTerrainMode::Flatten => Some(0.0),
+                SplineTerrainMode::Carve { depth } => Some(depth),
+            };
+
+            if let Some(depth) = depth {
+                let mut field =
+                    HeightField::from_chunk_height_maps(chunk_height_maps, coverage.origin, coverage.size, terrain_config);
+
+                for (index, weight) in coverage.weights.iter().enumerate() {
+                    if *weight <= 0.0 || field.mask[index] <= 0.0 {
+                        field.mask[index] = 0.0;
+                        continue;
+                    }
+
+                    let texel = coverage.origin + UVec2::new(index as u32 % coverage.size.x, index as u32 / coverage.size.x);
+                    if let Some(original) = layout.texel_mut(chunk_height_maps, texel) {
+                        footprint.original_heights.insert(
+                            texel,
+                            FootprintValue {
+                                original: *original,
+                                written: *original,
+                            },
+                        );
+                    }
+
+                    let target = coverage.heights[index] - depth - layout.origin.y;
+                    field.heights[index] = field.heights[index].lerp(target, *weight);
+                }
+
+                changed_height_chunks.extend(field.write_to_chunk_height_maps(chunk_height_maps, terrain_config));
+
+                for (texel, value) in footprint.original_heights.iter_mut() {
+                    if let Some(written) = layout.texel_mut(chunk_height_maps, *texel) {
+                        value.written = *written;
+                    }
+                }
+            }
+
+            if let Some(texture_index) = spline.splat_texture_index {
+                paint_spline_splat(
+                    &mut commands,
+                    coverage,
+                    texture_index,
+                    &layout,
+                    &mut chunk_splat_query,
+                    &mut footprint,
+                );
+            }
+        }
+
+        if let Some(liquid) = &spline.liquid {
+            footprint.liquid_segments = spawn_liquid_segments(&mut commands, spline_entity, &samples, liquid);
+        }
+
+        mark_chunks_for_reload(&changed_height_chunks, &mut chunk_data_query);
+
+        commands.entity(spline_entity).insert(footprint);
+    }
+
+    commands.write_message(TerrainDataReplacedEvent);
+}
+
+fn revert_spline(
+    mut evt_reader: MessageReader<RevertSplineEvent>,
+
+    mut commands: Commands,
+
+    mut spline_query: Query<&mut AppliedSplineFootprint>,
+
+    terrain_query: Query<&TerrainConfig>,
+    chunk_transform_query: Query<(&Chunk, &GlobalTransform)>,
+    mut chunk_data_query: Query<(&Chunk, &mut ChunkData)>,
+    mut chunk_splat_query: Query<(Entity, &Chunk, &mut ChunkSplatDataRaw)>,
+
+    mut chunk_height_maps: ResMut<ChunkHeightMapResource>,
+) {
+    let spline_entities: HashSet<Entity> = evt_reader.read().map(|evt| evt.spline_entity).collect();
+
+    if spline_entities.is_empty() {
+        return;
+    }
+
+    let Some(layout) = terrain_query
+        .iter()
+        .next()
+        .and_then(|terrain_config| TerrainLayout::new(terrain_config, &chunk_transform_query))
+    else {
+        return;
+    };
+
+    let mut changed_height_chunks = HashSet::new();
+
+    for spline_entity in spline_entities {
+        let Ok(mut footprint) = spline_query.get_mut(spline_entity) else {
+            continue;
+        };
+
+        restore_footprint(
+            &mut commands,
+            &mut footprint,
+            &layout,
+            &mut chunk_height_maps.chunk_height_maps,
+            &mut chunk_splat_query,
+            &mut changed_height_chunks,
+        );
+
+        commands.entity(spline_entity).remove::<(AppliedSplineFootprint, SplineNeedsReapply)>();
+    }
+
+    mark_chunks_for_reload(&changed_height_chunks, &mut chunk_data_query);
+
+    commands.write_message(TerrainDataReplacedEvent);
+}
+
+// blends the layer in as the secondary splat texture, so the embankment fades into what was painted before
+fn paint_spline_splat(
+    commands: &mut Commands,
+    coverage: &SplineCoverage,
+    texture_index: u8,
+    layout: &TerrainLayout,
+    chunk_splat_query: &mut Query<(Entity, &Chunk, &mut ChunkSplatDataRaw)>,
+    footprint: &mut AppliedSplineFootprint,
+) {
+    let coverage_min = coverage.origin.as_vec2();
+    let coverage_max = (coverage.origin + coverage.size).as_vec2();
+
+    for (chunk_entity, chunk, mut splat_data) in chunk_splat_query.iter_mut() {
+        let chunk_coords = ChunkCoords::from_chunk_id(chunk.chunk_id, layout.chunk_rows);
+        let chunk_origin = (UVec2::new(chunk_coords.x(), chunk_coords.y()) * layout.chunk_dimensions).as_vec2();
+        let chunk_max = chunk_origin + layout.chunk_dimensions.as_vec2();
+
+        if chunk_max.x <= coverage_min.x
+            || chunk_max.y <= coverage_min.y
+            || chunk_origin.x >= coverage_max.x
+            || chunk_origin.y >= coverage_max.y
+        {
+            continue;
+        }
+
+        let splat_dimensions = UVec2::new(splat_data.splat_map_texture.width(), splat_data.splat_map_texture.height());
+        let texels_per_pixel = layout.chunk_dimensions.as_vec2() / splat_dimensions.max(UVec2::ONE).as_vec2();
+
+        let pixel_min = ((coverage_min - chunk_origin) / texels_per_pixel).floor().max(Vec2::ZERO).as_uvec2();
+        let pixel_max = ((coverage_max - chunk_origin) / texels_per_pixel)
+            .ceil()
+            .max(Vec2::ZERO)
+            .as_uvec2()
+            .min(splat_dimensions);
+
+        let mut changed = false;
+
+        for y in pixel_min.y..pixel_max.y {
+            for x in pixel_min.x..pixel_max.x {
+                let terrain_position = chunk_origin + (Vec2::new(x as f32, y as f32) + 0.5) * texels_per_pixel;
+
+                let Some((weight, _)) = coverage.get(terrain_position) else {
+                    continue;
+                };
+                if weight <= 0.0 {
+                    continue;
+                }
+
+                let pixel = UVec2::new(x, y);
+                let [r, g, b] = [0, 1, 2].map(|layer| splat_data.get_pixel_index_map_data(x, y, layer).unwrap_or(0));
+
+                let previous_strength = if g == texture_index { b } else { 0 };
+                let strength = ((weight * 255.0).round() as u8).max(previous_strength);
+
+                footprint
+                    .original_splat
+                    .entry((chunk.chunk_id, pixel))
+                    .or_insert(FootprintValue {
+                        original: [r, g, b],
+                        written: [r, g, b],
+                    })
+                    .written = [r, texture_index, strength];
+
+                splat_data.set_pixel_index_map_data(x, y, 1, texture_index);
+                splat_data.set_pixel_index_map_data(x, y, 2, strength);
+                changed = true;
+            }
+        }
+
+        if changed {
+            if let Ok(mut cmds) = commands.get_entity(chunk_entity) {
+                cmds.try_insert(SplatMapDataUpdated);
+            }
+        }
+    }
+}
+
+// liquid planes are unit planes, each segment is one scaled and turned along the curve
+fn spawn_liquid_segments(
+    commands: &mut Commands,
+    spline_entity: Entity,
+    samples: &[SplineSample],
+    liquid: &SplineLiquid,
+) -> Vec<Entity> {
+    let step = ((LIQUID_SEGMENT_LENGTH / SAMPLE_SPACING).round() as usize).max(1);
+
+    let mut indices: Vec<usize> = (0..samples.len()).step_by(step).collect();
+    if indices.last() != Some(&(samples.len().saturating_sub(1))) && samples.len() > 1 {
+        indices.push(samples.len() - 1);
+    }
+
+    indices
+        .windows(2)
+        .filter_map(|pair| {
+            let (start, end) = (&samples[pair[0]], &samples[pair[1]]);
+
+            let direction = Vec2::new(end.position.x - start.position.x, end.position.z - start.position.z);
+            let length = direction.length();
+            if length <= f32::EPSILON {
+                return None;
+            }
+
+            let middle = (start.position + end.position) * 0.5 + Vec3::Y * liquid.height_offset;
+            let width = (start.width + end.width) * 0.5;
+
+            let transform = Transform::from_translation(middle)
+                .with_rotation(Quat::from_rotation_y(direction.x.atan2(direction.y)))
+                // a little overlap hides the seams where the curve bends
+                .with_scale(Vec3::new(width, 1.0, length * 1.1));
+
+            Some(
+                commands
+                    .spawn((
+                        Name::new("SplineLiquidSegment"),
+                        transform,
+                        Visibility::default(),
+                        LiquidPlaneComponent {
+                            liquid_type: liquid.liquid_type.clone(),
+                        },
+                        SplineLiquidSegment { spline: spline_entity },
+                    ))
+                    .id(),
+            )
+        })
+        .collect()
+}
+
+fn snap_spline_control_points_to_terrain(
+    tool_state: Res<SplineToolState>,
+
+    mut handle_query: Query<(&mut Transform, &ChildOf), (With<SplineControlPointHandle>, Changed<Transform>)>,
+    spline_query: Query<(&GlobalTransform, Option<&AppliedSplineFootprint>), With<TerrainSpline>>,
+
+    terrain_query: Query<&TerrainConfig>,
+    chunk_transform_query: Query<(&Chunk, &GlobalTransform)>,
+    chunk_height_maps: Res<ChunkHeightMapResource>,
+) {
+    if !tool_state.snap_to_terrain || handle_query.is_empty() {
+        return;
+    }
+
+    let Some(layout) = terrain_query
+        .iter()
+        .next()
+        .and_then(|terrain_config| TerrainLayout::new(terrain_config, &chunk_transform_query))
+    else {
+        return;
+    };
+
+    for (mut transform, child_of) in handle_query.iter_mut() {
+        let Ok((spline_transform, footprint)) = spline_query.get(child_of.parent()) else {
+            continue;
+        };
+
+        let world_position = spline_transform.transform_point(transform.translation);
+
+        // snap to the terrain as it was before this spline changed it
+        let Some(height) = layout.sample_height(
+            &chunk_height_maps.chunk_height_maps,
+            layout.terrain_position(world_position),
+            footprint,
+        ) else {
+            continue;
+        };
+
+        let snapped = spline_transform
+            .affine()
+            .inverse()
+            .transform_point3(Vec3::new(world_position.x, height, world_position.z));
+
+        if transform.translation.distance_squared(snapped) > 0.0001 {
+            transform.translation = snapped;
+        }
+    }
+}
+
+fn reapply_edited_splines(
+    mut commands: Commands,
+
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    tool_state: Res<SplineToolState>,
+
+    edited_query: Query<
+        Entity,
+        (
+            With<AppliedSplineFootprint>,
+            Or<(Changed<TerrainSpline>, Changed<GlobalTransform>)>,
+        ),
+    >,
+    pending_query: Query<Entity, With<SplineNeedsReapply>>,
+) {
+    if !tool_state.auto_reapply {
+        return;
+    }
+
+    for spline_entity in edited_query.iter() {
+        commands.entity(spline_entity).insert(SplineNeedsReapply);
+    }
+
+    if mouse_input.pressed(MouseButton::Left) {
+        return;
+    }
+
+    for spline_entity in pending_query.iter() {
+        commands.entity(spline_entity).remove::<SplineNeedsReapply>();
+        commands.write_message(ApplySplineEvent { spline_entity });
+    }
+}
+
+fn despawn_orphaned_liquid_segments(
+    mut commands: Commands,
+
+    segment_query: Query<(Entity, &SplineLiquidSegment)>,
+    spline_query: Query<(), With<TerrainSpline>>,
+) {
+    for (segment_entity, segment) in segment_query.iter() {
+        if !spline_query.contains(segment.spline) {
+            commands.entity(segment_entity).despawn();
+        }
+    }
+}
+
+fn place_spline_control_points(
+    mut commands: Commands,
+
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    ray_map: Res<RayMap>,
+    mut raycast: MeshRayCast,
+
+    editor_tools_state: Res<EditorToolsState>,
+    editor_state_resource: Res<EditorStateResource>,
+    placement_resource: Res<PlacementResource>,
+
+    mut tool_state: ResMut<SplineToolState>,
+
+    global_xform_query: Query<&GlobalTransform>,
+    spline_query: Query<(), With<TerrainSpline>>,
+    ignored_query: Query<(), Or<(With<SplineControlPointHandle>, With<SplineLiquidSegment>)>>,
+    parent_query: Query<&ChildOf>,
+
+    mut spline_evt_writer: MessageWriter<SplineEvent>,
+) {
+    if editor_tools_state.sub_tool != Some(SubTool::TerrainSpline) {
+        return;
+    }
+
+    if editor_state_resource.cursor_overlaps_gui {
+        return;
+    }
+
+    let active_spline = tool_state.active_spline.filter(|entity| spline_query.contains(*entity));
+
+    if mouse_input.just_pressed(MouseButton::Right) {
+        if let Some(spline_entity) = active_spline {
+            spline_evt_writer.write(SplineEvent::RemoveLastControlPoint { spline_entity });
+        }
+        return;
+    }
+
+    if !mouse_input.just_pressed(MouseButton::Left) {
+        return;
+    }
+
+    // the control point spheres and river planes are not ground to click on
+    let is_ignored = |entity: Entity| {
+        ignored_query.contains(entity)
+            || parent_query.get(entity).is_ok_and(|child_of| ignored_query.contains(child_of.parent()))
+    };
+
+    for (_, cursor_ray) in ray_map.iter() {
+        let Some((_, hit)) = raycast
+            .cast_ray(*cursor_ray, &default())
+            .iter()
+            .find(|(entity, _)| !is_ignored(*entity))
+        else {
+            continue;
+        };
+
+        let hit_point = hit.point;
+
+        match active_spline {
+            Some(spline_entity) => {
+                spline_evt_writer.write(SplineEvent::AddControlPoint {
+                    spline_entity,
+                    position: hit_point,
+                });
+            }
+            None => {
+                let parent = placement_resource.placement_parent;
+
+                let parent_global_translation = parent
+                    .and_then(|p| global_xform_query.get(p).ok())
+                    .map(|xform| xform.translation())
+                    .unwrap_or_default();
+
+                let spline_entity = commands
+                    .spawn((
+                        Name::new("Spline"),
+                        Transform::from_translation(hit_point - parent_global_translation),
+                        Visibility::default(),
+                        TerrainSpline {
+                            control_points: vec![SplineControlPoint::new(Vec3::ZERO)],
+                            ..tool_state.template.clone()
+                        },
+                    ))
+                    .id();
+
+                if let Some(parent) = parent {
+                    commands.entity(parent).add_child(spline_entity);
+                }
+
+                tool_state.active_spline = Some(spline_entity);
+            }
+        }
+
+        return;
+    }
+}
+
+fn road_preset() -> TerrainSpline {
+    TerrainSpline::default()
+}
+
+fn river_preset() -> TerrainSpline {
+    TerrainSpline {
+        width: 8.0,
+        falloff: 6.0,
+        terrain_mode: SplineTerrainMode::Carve { depth: 2.0 },
+        liquid: Some(SplineLiquid::default()),
+        ..default()
+    }
+}
+
+fn spline_settings_ui(ui: &mut egui::Ui, spline: &mut TerrainSpline) {
+    ui.add(egui::Slider::new(&mut spline.width, 0.5..=64.0).text("Width"));
+    ui.add(egui::Slider::new(&mut spline.falloff, 0.0..=64.0).text("Falloff"));
+    ui.checkbox(&mut spline.closed, "Closed loop");
+
+    let mode_name = match spline.terrain_mode {
+        SplineTerrainMode::None => "None",
+        SplineTerrainMode::Flatten => "Flatten",
+        SplineTerrainMode::Carve { .. } => "Carve",
+    };
+
+    ui.horizontal(|ui| {
+        ui.label("Terrain");
+        egui::ComboBox::new("spline_terrain_mode", "")
+            .selected_text(mode_name)
+            .show_ui(ui, |ui| {
+                if ui.selectable_label(mode_name == "None", "None").clicked() {
+                    spline.terrain_mode = SplineTerrainMode::None;
+                }
+                if ui.selectable_label(mode_name == "Flatten", "Flatten").clicked() {
+                    spline.terrain_mode = SplineTerrainMode::Flatten;
+                }
+                if ui.selectable_label(mode_name == "Carve", "Carve").clicked() && mode_name != "Carve" {
+                    spline.terrain_mode = SplineTerrainMode::Carve { depth: 2.0 };
+                }
+            });
+    });
+
+    if let SplineTerrainMode::Carve { depth } = &mut spline.terrain_mode {
+        ui.add(egui::Slider::new(depth, 0.0..=32.0).text("Carve depth"));
+    }
+
+    let mut paint_splat = spline.splat_texture_index.is_some();
+    ui.checkbox(&mut paint_splat, "Paint splat layer");
+    match (paint_splat, &mut spline.splat_texture_index) {
+        (true, Some(texture_index)) => {
+            ui.add(egui::Slider::new(texture_index, 0..=255).text("Texture index"));
+        }
+        (true, None) => spline.splat_texture_index = Some(0),
+        (false, _) => spline.splat_texture_index = None,
+    }
+
+    let mut spawn_liquid = spline.liquid.is_some();
+    ui.checkbox(&mut spawn_liquid, "Spawn liquid");
+    match (spawn_liquid, &mut spline.liquid) {
+        (true, Some(liquid)) => {
+            ui.horizontal(|ui| {
+                ui.label("Liquid type");
+                ui.text_edit_singleline(&mut liquid.liquid_type);
+            });
+            ui.add(egui::Slider::new(&mut liquid.height_offset, -8.0..=8.0).text("Surface offset"));
+        }
+        (true, None) => spline.liquid = Some(SplineLiquid::default()),
+        (false, _) => spline.liquid = None,
+    }
+}
+
+fn spline_tool_ui(
+    mut contexts: EguiContexts,
+
+    editor_tools_state: Res<EditorToolsState>,
+    mut tool_state: ResMut<SplineToolState>,
+
+    mut spline_query: Query<(Entity, &Name, &mut TerrainSpline)>,
+    footprint_query: Query<(), With<AppliedSplineFootprint>>,
+
+    mut commands: Commands,
+) {
+    if editor_tools_state.sub_tool != Some(SubTool::TerrainSpline) {
+        return;
+    }
+
+    egui::Window::new("Spline Tool").show(contexts.ctx_mut(), |ui| {
+        ui.label("Left click the terrain to add control points, right click removes the last one.");
+        ui.label("Drag the control points with the gizmo to edit the curve.");
+
+        ui.separator();
+
+        let active_spline = tool_state.active_spline.filter(|entity| spline_query.contains(*entity));
+
+        let active_name = active_spline
+            .and_then(|entity| spline_query.get(entity).ok())
+            .map(|(entity, name, _)| format!("{} {}", name, entity))
+            .unwrap_or_else(|| "New spline".to_string());
+
+        ui.horizontal(|ui| {
+            ui.label("Spline");
+            egui::ComboBox::new("active_spline", "")
+                .selected_text(active_name)
+                .show_ui(ui, |ui| {
+                    if ui.selectable_label(active_spline.is_none(), "New spline").clicked() {
+                        tool_state.active_spline = None;
+                    }
+
+                    for (entity, name, _) in spline_query.iter() {
+                        if ui
+                            .selectable_label(active_spline == Some(entity), format!("{} {}", name, entity))
+                            .clicked()
+                        {
+                            tool_state.active_spline = Some(entity);
+                        }
+                    }
+                });
+        });
+
+        ui.checkbox(&mut tool_state.snap_to_terrain, "Snap control points to terrain");
+        ui.checkbox(&mut tool_state.auto_reapply, "Re-apply when edited");
+
+        ui.separator();
+
+        match active_spline.and_then(|entity| spline_query.get_mut(entity).ok()) {
+            Some((spline_entity, _, mut spline)) => {
+                // edit a copy so the spline only counts as changed when a value really changed
+                let mut edited = spline.clone();
+                spline_settings_ui(ui, &mut edited);
+
+                if edited != *spline {
+                    *spline = edited;
+                }
+
+                ui.label(format!("{} control points", spline.control_points.len()));
+
+                ui.horizontal(|ui| {
+                    if ui.button(" Apply ").clicked() {
+                        commands.write_message(ApplySplineEvent { spline_entity });
+                    }
+
+                    if footprint_query.contains(spline_entity) && ui.button(" Revert ").clicked() {
+                        commands.write_message(RevertSplineEvent { spline_entity });
+                    }
+                });
+            }
+            None => {
+                ui.horizontal(|ui| {
+                    if ui.button("Road preset").clicked() {
+                        tool_state.template = road_preset();
+                    }
+                    if ui.button("River preset").clicked() {
+                        tool_state.template = river_preset();
+                    }
+                });
+
+                spline_settings_ui(ui, &mut tool_state.template);
+            }
+        }
+    });
+}
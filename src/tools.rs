@@ -13,6 +13,7 @@ use bevy::prelude::*;
 
 use crate::editor_pls::bevy_pls_editor_is_active;
 use crate::ui::{BrushType, EditorToolsState, ToolMode};
+use bevy_brush_shapes::{BrushScatter, BrushShape};
 use degen_toon_terrain::edit::{ BrushType as TerrainBrushType,  EditingTool as TerrainEditingTool , TerrainBrushEvent};
 
 use bevy_regions::edit::{
@@ -81,6 +82,8 @@ struct EditingToolData {
     brush_type: BrushType,
     brush_radius: f32,
     brush_hardness: f32,
+    brush_shape: BrushShape,
+    brush_scatter: BrushScatter,
 }
 
 impl   EditingToolData {
@@ -92,6 +95,8 @@ impl   EditingToolData {
             brush_radius: state.brush_radius as f32,
             brush_type: state.brush_type,
             brush_hardness: (state.brush_hardness as f32) / 100.0,
+            brush_shape: state.brush_shape,
+            brush_scatter: state.brush_scatter,
         } )
     }
 }
@@ -104,6 +109,8 @@ enum EditingTool {
     TilesEditingTool(TilesEditingTool),
   //  PlaceDoodads, 
      FoliageEditingTool(FoliageEditingTool),
+    DoodadBrush, // scatter and erase, see doodads/doodad_scatter.rs
+
 
 }
 
@@ -158,6 +165,9 @@ impl   EditingTool {
                                 g: state.color.g as u8,
                                 b: state.color.b as u8,
                             }) ),
+                           Some(SubTool::TerrainHoles) => Some( EditingTool::TerrainEditingTool( 
+                                TerrainEditingTool::SetHoleMask
+                            ) ),
 
                          /*  Some(SubTool::TerrainSplatUltra) => Some( EditingTool::TerrainEditingTool( 
                                 TerrainEditingTool::SetSplatMapUltra {
@@ -222,7 +232,10 @@ impl   EditingTool {
                         region_index: state.color.r as u8,
                     }) ),  
 
-                     // ToolMode::Doodads =>  None ,  
+                     ToolMode::Doodads => match state.sub_tool {
+                        Some(SubTool::DoodadScatter) | Some(SubTool::DoodadErase) => Some(EditingTool::DoodadBrush),
+                        _ => None,
+                     },
 
 
                     ToolMode::Foliage =>  Some( EditingTool::FoliageEditingTool( 
@@ -366,6 +379,8 @@ fn update_brush_paint(
     let radius = tool_data.brush_radius;
     let brush_hardness = tool_data.brush_hardness;
     let brush_type = tool_data.brush_type;
+    let brush_shape = tool_data.brush_shape;
+    let brush_scatter = tool_data.brush_scatter;
 
     // let tool = EditingTool::SetSplatMap(5,1,0,25.0,false);
 
@@ -399,6 +414,20 @@ fn update_brush_paint(
                 return;
             }
 
+            // stamps are applied once per click instead of every frame
+            if brush_type == BrushType::Stamp && !mouse_input.just_pressed(MouseButton::Left) {
+                return;
+            }
+
+            // one dab without scatter, otherwise jittered copies of the brush around the cursor.
+            // the eye dropper only samples, so it always reads once right under the cursor
+            let dabs = if brush_type == BrushType::EyeDropper {
+                BrushScatter::default().dabs(&brush_shape, hit_coordinates, radius, &mut rand::thread_rng())
+            } else {
+                brush_scatter.dabs(&brush_shape, hit_coordinates, radius, &mut rand::thread_rng())
+            };
+
+            for dab in dabs {
 
             match tool_data.editing_tool {
                 EditingTool::TerrainEditingTool(ref terrain_edit_tool) =>  {
@@ -411,6 +440,8 @@ fn update_brush_paint(
                      BrushType::ClearAll => TerrainBrushType::ClearAll,
 
                        BrushType::RaiseLower => TerrainBrushType::RaiseLower,
+                       BrushType::Erode => TerrainBrushType::Erode,
+                       BrushType::Stamp => TerrainBrushType::Stamp,
 
                          
                 };
@@ -420,8 +451,9 @@ fn update_brush_paint(
                             tool: terrain_edit_tool.clone(),
                             brush_type: terrain_brush_type,
                             brush_hardness,
-                            coordinates: hit_coordinates,
-                            radius,
+                            coordinates: dab.coordinates,
+                            radius: dab.radius,
+                            brush_shape: dab.shape.clone(),
                         });
                   
 
@@ -441,8 +473,9 @@ fn update_brush_paint(
                                 tool: foliage_edit_tool.clone(),
                                 brush_type:foliage_brush_type,
                                 brush_hardness,
-                                coordinates: hit_coordinates,
-                                radius,
+                                coordinates: dab.coordinates,
+                                radius: dab.radius,
+                                brush_shape: dab.shape.clone(),
                             });
                      }
                       
@@ -462,8 +495,9 @@ fn update_brush_paint(
                             tool: region_edit_tool.clone(),
                             brush_type:regions_brush_type,
                             brush_hardness,
-                            coordinates: hit_coordinates,
-                            radius,
+                            coordinates: dab.coordinates,
+                            radius: dab.radius,
+                            brush_shape: dab.shape.clone(),
                         });
                     }
                     
@@ -478,9 +512,17 @@ fn update_brush_paint(
                                            
                 },
 
+                 EditingTool::DoodadBrush => {
+
+                    // only drives the brush preview, the doodad scatter plugin does the painting
+
+                 },
+
                
             }
 
+            } // dabs
+
          
         }
     }
@@ -1,5 +1,5 @@
  
-use crate::terrain::terrain_generation::GenerateTerrainEvent;
+use crate::terrain::terrain_generation::TerrainGeneratorState;
 use bevy_clay_tiles::tile_types_config::ClayTilesTypesConfigResource;
 use spirit_edit_core::prefabs::PrefabEvent;
 use crate::terrain::terrain_manifest::{TerrainManifestResource,TerrainManifest};
@@ -9,11 +9,18 @@ use bevy_egui::EguiContexts;
 use bevy_egui::{egui };
 
 use degen_toon_terrain::edit::{BrushType as TerrainBrushType, TerrainCommandEvent};
+use degen_toon_terrain::erosion::ErosionSettings;
+use degen_toon_terrain::auto_splat::SplatLockMaskResource;
+use crate::terrain::terrain_auto_splat::{AutoSplatToolState, RunAutoSplatEvent};
 use bevy_regions::edit::{BrushType as RegionsBrushType, RegionCommandEvent};
 use spirit_edit_core::zones::ZoneEvent;
 use bevy_foliage_tool::edit::{BrushType as FoliageBrushType, FoliageCommandEvent};
+use bevy_brush_shapes::{BrushScatter, BrushShape};
+use spirit_edit_core::placement::PlacementToolsState;
+use spirit_edit_core::placement::scatter::{ScatterDoodadSource, ScatterRules, WeightedDoodad};
 
 use std::fmt::{self, Display, Formatter};
+use std::path::PathBuf;
 
 use crate::editor_pls::bevy_pls_editor_is_active;
 
@@ -46,6 +53,8 @@ pub struct EditorToolsState {
     pub brush_hardness: u32,
     pub color: LinearPixelColor, //brush mode, for simple brush data  
 
+    pub brush_shape: BrushShape, // edited in the Brush Shape window, see brushes.rs
+    pub brush_scatter: BrushScatter,
  
 
  //   pub layered_splatmap_data: LayeredSplatMapData   //for splat map ultra 
@@ -62,6 +71,8 @@ pub enum BrushType {
     EyeDropper,
 
     RaiseLower,
+    Erode,
+    Stamp,
     
 }
 
@@ -78,6 +89,8 @@ impl BrushType{
              BrushType::EyeDropper  => "Eyedropper".into(),
 
               BrushType::RaiseLower  => "Raise/Lower".into(),
+              BrushType::Erode  => "Erode".into(),
+              BrushType::Stamp  => "Stamp".into(),
              
 
             
@@ -109,6 +122,7 @@ pub enum ToolMode {
     Foliage, 
     Regions,
     Tiles,
+    Doodads,
      
 }
 
@@ -121,6 +135,8 @@ pub enum SubTool {
 
     TerrainHeight,
     TerrainSplat, 
+    TerrainSpline,
+    TerrainHoles,
    
 
     TerrainGeneration,
@@ -132,6 +148,9 @@ pub enum SubTool {
     ModifyTileDragVertices,
  //   ModifyTileType, 
 
+    DoodadScatter,
+    DoodadErase,
+
 }
 
 
@@ -143,6 +162,8 @@ impl SubTool{
              Self::TerrainGeneration  => "Terrain Generation".into(),
              Self::TerrainHeight  => "Terrain Height".into(),   
             Self::TerrainSplat  => "Terrain Splat".into(),
+            Self::TerrainSpline  => "Terrain Spline".into(),
+            Self::TerrainHoles  => "Terrain Holes".into(),
           
 
             Self::BuildTileRectangle  => "Build: Rectangle".into(),
@@ -152,6 +173,9 @@ impl SubTool{
             Self::ModifyTileDragSides => "Modify: Drag Sides".into(),
             Self::ModifyTileDragVertices => "Modify: Drag Vertices".into(),
 
+            Self::DoodadScatter => "Scatter".into(),
+            Self::DoodadErase => "Erase".into(),
+
            /* Self::ModifyTileHeight  => "Modify: Height".into(),
             Self::ModifyTileBevel  => "Modify: Bevel".into(),
             Self::ModifyTileType  => "Modify: Tile Type".into(),*/
@@ -165,18 +189,21 @@ impl SubTool{
 
 
 
-const TOOL_MODES: [ToolMode; 5] = [
+const TOOL_MODES: [ToolMode; 6] = [
 ToolMode::Terrain,
  ToolMode::TerrainGen,
 ToolMode::Foliage, 
 ToolMode::Regions,
 ToolMode::Tiles,
+ToolMode::Doodads,
  
 ];
 
-const TERRAIN_SUBTOOLS : [SubTool; 2] = [
+const TERRAIN_SUBTOOLS : [SubTool; 4] = [
     SubTool::TerrainHeight,
     SubTool::TerrainSplat, 
+    SubTool::TerrainSpline,
+    SubTool::TerrainHoles,
    
 
 ];
@@ -197,17 +224,24 @@ const TILE_SUBTOOLS : [SubTool; 5] = [
     SubTool::ModifyTileDragVertices
 ];
 
+const DOODAD_SUBTOOLS : [SubTool; 2] = [
+    SubTool::DoodadScatter,
+    SubTool::DoodadErase,
+];
 
 
 
 
-const BRUSH_TYPES_HEIGHT: [ BrushType; 5] = [
+
+const BRUSH_TYPES_HEIGHT: [ BrushType; 7] = [
 BrushType::SetExact , 
 BrushType::Smooth , 
 BrushType::Noise , 
 BrushType::EyeDropper,
 
 BrushType::RaiseLower, 
+BrushType::Erode, 
+BrushType::Stamp, 
 
 ];
 
@@ -217,6 +251,11 @@ BrushType::SetExact ,
  BrushType::ClearAll , 
  BrushType::EyeDropper
 ];
+// SetExact cuts a hole, ClearAll fills it again
+const BRUSH_TYPES_HOLES: [BrushType; 2] = [
+BrushType::SetExact ,   
+BrushType::ClearAll
+];
 const BRUSH_TYPES_REGION: [BrushType; 2] = [
 BrushType::SetExact ,   
 BrushType::EyeDropper
@@ -237,7 +276,7 @@ impl Display for ToolMode {
             ToolMode::Tiles => "Tiles",
             ToolMode::Foliage => "Foliage",
             ToolMode::Regions => "Regions",
-          //    ToolMode::Doodads => "Doodads"
+            ToolMode::Doodads => "Doodads",
         };
 
         write!(f, "{}", label)
@@ -260,8 +299,13 @@ fn editor_tools_ui(
 
     mut commands: Commands , 
 
+    mut terrain_generator_state: ResMut<TerrainGeneratorState>,
+    mut erosion_settings: ResMut<ErosionSettings>,
+    mut auto_splat_state: ResMut<AutoSplatToolState>,
+    mut splat_lock_masks: ResMut<SplatLockMaskResource>,
 
     clay_tiles_config_resource: Res<ClayTilesTypesConfigResource>, 
+    mut placement_tools_state: ResMut<PlacementToolsState>,
 ) {
     egui::Window::new("Editor Tools").show(contexts.ctx_mut(), |ui| {
 
@@ -362,29 +406,82 @@ fn editor_tools_ui(
  
 
                                         ui.spacing();
-                                        
-
 
-                                  
+                                    ui.horizontal(|ui| {
+                                        ui.label("Graph ");
+                                        ui.text_edit_singleline(&mut terrain_generator_state.graph_path);
+                                    });
 
-
- 
                                     ui.add(
-                                        egui::Slider::new(&mut tools_state.color.r, 0..=65535)
+                                        egui::Slider::new(&mut terrain_generator_state.seed, 0..=65535)
                                             .text("Seed  ")
                                            
                                             ,
                                     );
-                                      if ui.button(" Generate ").clicked() { 
 
+                                    ui.horizontal(|ui| {
+                                        ui.label("Chunks from ");
+                                        ui.add(egui::DragValue::new(&mut terrain_generator_state.chunk_min.x));
+                                        ui.add(egui::DragValue::new(&mut terrain_generator_state.chunk_min.y));
+                                        ui.label(" to ");
+                                        ui.add(egui::DragValue::new(&mut terrain_generator_state.chunk_max.x));
+                                        ui.add(egui::DragValue::new(&mut terrain_generator_state.chunk_max.y));
+                                    });
+
+                                    ui.horizontal(|ui| {
+                                        if ui.button(" Preview ").clicked() {
+                                            commands.write_message( terrain_generator_state.build_preview_event() );
+                                        }
 
-                                        commands.trigger( GenerateTerrainEvent );
-
+                                        if ui.button(" Generate ").clicked() { 
+                                            commands.write_message( terrain_generator_state.build_generate_event() );
+                                        }
+                                    });
 
+                                    if let Some(error) = &terrain_generator_state.last_error {
+                                        ui.colored_label(egui::Color32::LIGHT_RED, error);
                                     }
 
+                                    ui.separator();
 
-                                          
+                                    ui.horizontal(|ui| {
+                                        ui.label("Heightmap ");
+                                        ui.text_edit_singleline(&mut terrain_generator_state.heightmap_path);
+                                    });
+
+                                    ui.horizontal(|ui| {
+                                        let heightmap_path = PathBuf::from(&terrain_generator_state.heightmap_path);
+
+                                        if ui.button(" Import Heightmap ").clicked() {
+                                            command_event_writer.write(TerrainCommandEvent::ImportHeightmap(heightmap_path.clone()));
+                                        }
+
+                                        if ui.button(" Export Heightmap ").clicked() {
+                                            command_event_writer.write(TerrainCommandEvent::ExportHeightmap(heightmap_path));
+                                        }
+                                    });
+
+                                    if let Some(preview) = &terrain_generator_state.preview {
+                                        let preview_size = 192.0;
+                                        let (rect, _) = ui.allocate_exact_size(
+                                            egui::vec2(preview_size, preview_size),
+                                            egui::Sense::hover(),
+                                        );
+
+                                        let cell_size = preview_size / preview.resolution as f32;
+                                        let painter = ui.painter_at(rect);
+
+                                        for (index, value) in preview.pixels.iter().enumerate() {
+                                            let x = (index as u32 % preview.resolution) as f32;
+                                            let y = (index as u32 / preview.resolution) as f32;
+
+                                            let cell = egui::Rect::from_min_size(
+                                                rect.min + egui::vec2(x * cell_size, y * cell_size),
+                                                egui::vec2(cell_size, cell_size),
+                                            );
+                                            painter.rect_filled(cell, 0.0, egui::Color32::from_gray(*value));
+                                        }
+                                    }
 
                                 }
 
@@ -589,6 +686,33 @@ fn editor_tools_ui(
                                 }
 
                             }
+
+                            ui.separator();
+                            ui.heading("Auto Splat");
+
+                            ui.horizontal(|ui| {
+                                ui.label("Chunks from ");
+                                ui.add(egui::DragValue::new(&mut auto_splat_state.chunk_min.x));
+                                ui.add(egui::DragValue::new(&mut auto_splat_state.chunk_min.y));
+                                ui.label(" to ");
+                                ui.add(egui::DragValue::new(&mut auto_splat_state.chunk_max.x));
+                                ui.add(egui::DragValue::new(&mut auto_splat_state.chunk_max.y));
+                            });
+
+                            ui.checkbox(&mut splat_lock_masks.lock_painted_texels, "Lock hand painted texels");
+
+                            ui.horizontal(|ui| {
+                                if ui.button(" Auto Splat ").clicked() {
+                                    commands.write_message(RunAutoSplatEvent {
+                                        chunk_min: auto_splat_state.chunk_min,
+                                        chunk_max: auto_splat_state.chunk_max,
+                                    });
+                                }
+
+                                if ui.button(" Clear Locks ").clicked() {
+                                    splat_lock_masks.clear();
+                                }
+                            });
                             
 
  
@@ -774,8 +898,72 @@ fn editor_tools_ui(
                         );
 
 
+                        if tools_state.brush_type == BrushType::Stamp {
+                            ui.label("Stamps once per click, the height above is the stamp amplitude. Pick the stamp in the Brush Shape window.");
+                        }
+
+                        if tools_state.brush_type == BrushType::Erode {
+                            ui.separator();
+
+                            ui.add(
+                                egui::Slider::new(&mut erosion_settings.hydraulic.brush_droplets_per_texel, 0.0..=0.2)
+                                    .text("Droplets per texel"),
+                            );
+                            ui.add(
+                                egui::Slider::new(&mut erosion_settings.hydraulic.whole_terrain_droplets, 0..=1_000_000)
+                                    .text("Whole terrain droplets"),
+                            );
+                            ui.add(
+                                egui::Slider::new(&mut erosion_settings.thermal.talus_angle, 5.0..=85.0)
+                                    .text("Talus angle"),
+                            );
+                            ui.add(
+                                egui::Slider::new(&mut erosion_settings.thermal.whole_terrain_iterations, 0..=200)
+                                    .text("Thermal iterations"),
+                            );
+                            ui.checkbox(&mut erosion_settings.output_maps, "Output flow / deposition maps");
+
+                            if ui.button("Erode Whole Terrain").clicked() {
+                                command_event_writer.write(TerrainCommandEvent::ErodeTerrain);
+                            }
+                        }
+
+
+
+
 
-                              
+                    }
+
+                    SubTool::TerrainSpline => {
+
+                        ui.label("Roads and rivers are edited in the Spline Tool window.");
+
+                    }
+
+                    SubTool::TerrainHoles => {
+
+
+                        egui::ComboBox::new("brush_type", "")
+                            .selected_text(tools_state.brush_type.to_string())
+                            .show_ui(ui, |ui| {
+                                for brush_type in BRUSH_TYPES_HOLES.into_iter() {
+                                    if ui
+                                        .selectable_label(
+                                            tools_state.brush_type == brush_type,
+                                            brush_type.to_string(),
+                                        )
+                                        .clicked()
+                                    {
+                                        tools_state.brush_type = brush_type;
+                                    }
+                                }
+                            });
+
+                            ui.spacing();
+                            ui.add(egui::Slider::new(&mut tools_state.brush_radius, 0..=100).text("Brush Radius"));
+                            ui.spacing();
+
+                        ui.label("SetExact cuts a hole for caves and basements, ClearAll fills it again. Holes are saved with the splat data.");
 
                     }
 
@@ -783,7 +971,7 @@ fn editor_tools_ui(
                     _ => {}
                 }
               }
-               
+
             }
             ToolMode::Tiles => {
 
@@ -878,11 +1066,39 @@ fn editor_tools_ui(
                 
             },
 
-             /*ToolMode::Doodads => {
- 
-                  ui.heading("Placing Doodads (no tool)");
+            ToolMode::Doodads => {
+
+                  ui.heading("Sub Tool");
+
+                let subtool_name = match &tools_state.sub_tool {
+
+                    Some(st) => st.to_string(),
+                    None => "None".to_string()
+                };
 
-              },*/
+                  egui::ComboBox::new("Doodads Tool", "")
+                    .selected_text(subtool_name)
+                    .show_ui(ui, |ui| {
+                        for sub_tool in DOODAD_SUBTOOLS.into_iter() {
+                            if ui
+                                .selectable_label(
+                                     Some(sub_tool.clone()) ==  tools_state.sub_tool  ,
+                                    sub_tool.to_string(),
+                                )
+                                .clicked()
+                            {
+                                tools_state.sub_tool = Some(sub_tool);
+                            }
+                        }
+                    });
+
+                ui.spacing();
+                ui.add(egui::Slider::new(&mut tools_state.brush_radius, 0..=100).text("Brush Radius"));
+                ui.separator();
+
+                doodad_scatter_rules_ui(ui, &mut placement_tools_state.scatter, tools_state.sub_tool.clone());
+
+              },
             ToolMode::Foliage => {
 
 
@@ -971,6 +1187,142 @@ fn editor_tools_ui(
     });
 }
 
+fn doodad_scatter_rules_ui(ui: &mut egui::Ui, rules: &mut ScatterRules, sub_tool: Option<SubTool>) {
+
+    if sub_tool == Some(SubTool::DoodadErase) {
+        ui.horizontal(|ui| {
+            ui.label("Erase Tag:");
+            ui.text_edit_singleline(&mut rules.erase_tag);
+        });
+        ui.label("Leave empty to erase the scatter doodads.");
+        ui.separator();
+    }
+
+    ui.heading("Doodads");
+
+    let mut use_tag = matches!(rules.source, ScatterDoodadSource::Tag(_));
+
+    ui.horizontal(|ui| {
+        if ui.radio_value(&mut use_tag, false, "Weighted").clicked() && !matches!(rules.source, ScatterDoodadSource::Weighted(_)) {
+            rules.source = ScatterDoodadSource::Weighted(Vec::new());
+        }
+        if ui.radio_value(&mut use_tag, true, "Tag").clicked() && !matches!(rules.source, ScatterDoodadSource::Tag(_)) {
+            rules.source = ScatterDoodadSource::Tag(String::new());
+        }
+    });
+
+    match &mut rules.source {
+        ScatterDoodadSource::Tag(tag) => {
+            ui.horizontal(|ui| {
+                ui.label("Tag:");
+                ui.text_edit_singleline(tag);
+            });
+        }
+        ScatterDoodadSource::Weighted(doodads) => {
+            let mut removed = None;
+
+            for (index, doodad) in doodads.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.add(egui::TextEdit::singleline(&mut doodad.doodad_name).desired_width(140.0));
+                    ui.add(egui::DragValue::new(&mut doodad.weight).speed(0.05).range(0.0..=100.0).prefix("weight "));
+                    if ui.button("x").clicked() {
+                        removed = Some(index);
+                    }
+                });
+            }
+
+            if let Some(index) = removed {
+                doodads.remove(index);
+            }
+
+            if ui.button("Add Doodad").clicked() {
+                doodads.push(WeightedDoodad {
+                    doodad_name: String::new(),
+                    weight: 1.0,
+                });
+            }
+        }
+    }
+
+    if sub_tool == Some(SubTool::DoodadErase) {
+        return;
+    }
+
+    ui.separator();
+    ui.heading("Rules");
+
+    ui.add(egui::Slider::new(&mut rules.spacing, 0.0..=50.0).text("Spacing (m)"));
+    ui.add(egui::Slider::new(&mut rules.density, 0.0..=50.0).text("Density (per 100 m2)"));
+
+    ui.add(egui::Slider::new(&mut rules.min_slope_degrees, 0.0..=90.0).text("Min Slope"));
+    ui.add(egui::Slider::new(&mut rules.max_slope_degrees, 0.0..=90.0).text("Max Slope"));
+
+    optional_height_ui(ui, "Min Height", &mut rules.min_height);
+    optional_height_ui(ui, "Max Height", &mut rules.max_height);
+
+    index_list_ui(ui, "Regions", "scatter_allowed_regions", &mut rules.allowed_regions);
+    index_list_ui(ui, "Splat Textures", "scatter_allowed_splat_textures", &mut rules.allowed_splat_textures);
+
+    ui.separator();
+    ui.heading("Alignment");
+
+    ui.add(egui::Slider::new(&mut rules.normal_alignment, 0.0..=1.0).text("Align To Normal"));
+    ui.checkbox(&mut rules.random_yaw, "Random Yaw");
+
+    ui.horizontal(|ui| {
+        ui.label("Scale:");
+        ui.add(egui::DragValue::new(&mut rules.min_scale).speed(0.01).range(0.01..=100.0));
+        ui.label("to");
+        ui.add(egui::DragValue::new(&mut rules.max_scale).speed(0.01).range(0.01..=100.0));
+    });
+}
+
+fn optional_height_ui(ui: &mut egui::Ui, label: &str, height: &mut Option<f32>) {
+    ui.horizontal(|ui| {
+        let mut enabled = height.is_some();
+
+        if ui.checkbox(&mut enabled, label).changed() {
+            *height = enabled.then_some(0.0);
+        }
+
+        if let Some(height) = height {
+            ui.add(egui::DragValue::new(height).speed(0.5));
+        }
+    });
+}
+
+// an empty list means any index is allowed
+fn index_list_ui(ui: &mut egui::Ui, label: &str, id: &str, indices: &mut Vec<u8>) {
+    let pending_id = egui::Id::new(id);
+    let mut pending: u8 = ui.data_mut(|data| *data.get_temp_mut_or_default::<u8>(pending_id));
+
+    ui.horizontal_wrapped(|ui| {
+        ui.label(format!("{}:", label));
+
+        if indices.is_empty() {
+            ui.label("any");
+        }
+
+        let mut removed = None;
+        for (position, index) in indices.iter().enumerate() {
+            if ui.small_button(format!("{} x", index)).clicked() {
+                removed = Some(position);
+            }
+        }
+        if let Some(position) = removed {
+            indices.remove(position);
+        }
+
+        ui.add(egui::DragValue::new(&mut pending).range(0..=255));
+
+        if ui.small_button("Add").clicked() && !indices.contains(&pending) {
+            indices.push(pending);
+        }
+    });
+
+    ui.data_mut(|data| data.insert_temp(pending_id, pending));
+}
+
 /*
 fn force_update_tool_mode(
 
@@ -984,4 +1336,4 @@ fn force_update_tool_mode(
         editor_tools_state.tool_mode = ToolMode::Doodads; 
     }
 
-}*/
\ No newline at end of file
+}*/